@@ -0,0 +1,433 @@
+//! Benchmarks against the standard library
+//! Measures insert, lookup, remove and iteration of the crate HashMap,
+//! singly linked list, Stack and Queue next to `std::collections::HashMap`,
+//! `LinkedList`, `Vec` and `VecDeque`, for a few collection sizes. The
+//! `workload` group replays the same generated mix of inserts, lookups and
+//! removes on every map, with uniform and with zipfian keys. The `heaps` group
+//! times the min heaps against each other: the array-based binary heap is
+//! fastest at plain push and pop but has to re-push every item to meld, while
+//! the pointer-based heaps meld cheaply.
+//! Run with `cargo bench`, or `cargo bench -- hash_map` for one group.
+
+use std::collections::{ BTreeMap, HashMap as StdHashMap, LinkedList, VecDeque };
+use std::hint::black_box;
+
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BatchSize,
+    BenchmarkGroup,
+    BenchmarkId,
+    Criterion,
+};
+use data_structures_in_rust::prelude::*;
+use data_structures_in_rust::workload::{ self, KeyDistribution, Op, Workload };
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn filled_hash_map(size: usize) -> HashMap<usize, usize> {
+    let mut map = HashMap::new();
+    for key in 0..size {
+        map.insert(key, key);
+    }
+    map
+}
+
+fn hash_map(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_map");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("crate/insert", size), &size, |b, &size| {
+            b.iter(|| filled_hash_map(size));
+        });
+        group.bench_with_input(BenchmarkId::new("std/insert", size), &size, |b, &size| {
+            b.iter(|| (0..size).map(|key| (key, key)).collect::<StdHashMap<_, _>>());
+        });
+
+        let map = filled_hash_map(size);
+        let std_map: StdHashMap<usize, usize> = (0..size).map(|key| (key, key)).collect();
+        group.bench_with_input(BenchmarkId::new("crate/lookup", size), &size, |b, &size| {
+            b.iter(|| (0..size).filter_map(|key| map.get(black_box(key))).sum::<usize>());
+        });
+        group.bench_with_input(BenchmarkId::new("std/lookup", size), &size, |b, &size| {
+            b.iter(|| (0..size).filter_map(|key| std_map.get(&black_box(key))).sum::<usize>());
+        });
+
+        group.bench_with_input(BenchmarkId::new("crate/remove", size), &size, |b, &size| {
+            b.iter_batched_ref(
+                || map.clone(),
+                |map| (0..size).filter_map(|key| map.remove(key)).count(),
+                BatchSize::LargeInput
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("std/remove", size), &size, |b, &size| {
+            b.iter_batched_ref(
+                || std_map.clone(),
+                |map| (0..size).filter_map(|key| map.remove(&key)).count(),
+                BatchSize::LargeInput
+            );
+        });
+
+        group.bench_with_input(BenchmarkId::new("crate/iterate", size), &size, |b, _| {
+            b.iter(|| map.iter().map(|(_, value)| value).sum::<usize>());
+        });
+        group.bench_with_input(BenchmarkId::new("std/iterate", size), &size, |b, _| {
+            b.iter(|| std_map.values().sum::<usize>());
+        });
+    }
+    group.finish();
+}
+
+fn filled_list(size: usize) -> SinglyLinkedList<usize> {
+    let mut list = SinglyLinkedList::new();
+    for item in (0..size).rev() {
+        list.prepend(item);
+    }
+    list
+}
+
+fn linked_list(c: &mut Criterion) {
+    let mut group = c.benchmark_group("linked_list");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("crate/insert", size), &size, |b, &size| {
+            b.iter(|| filled_list(size));
+        });
+        group.bench_with_input(BenchmarkId::new("std/insert", size), &size, |b, &size| {
+            b.iter(|| (0..size).rev().fold(LinkedList::new(), |mut list, item| {
+                list.push_front(item);
+                list
+            }));
+        });
+
+        let list = filled_list(size);
+        let std_list: LinkedList<usize> = (0..size).collect();
+        // Looking up the last item walks the whole list
+        group.bench_with_input(BenchmarkId::new("crate/lookup", size), &size, |b, &size| {
+            b.iter(|| list.contains(&black_box(size - 1)));
+        });
+        group.bench_with_input(BenchmarkId::new("std/lookup", size), &size, |b, &size| {
+            b.iter(|| std_list.contains(&black_box(size - 1)));
+        });
+
+        group.bench_with_input(BenchmarkId::new("crate/remove", size), &size, |b, _| {
+            b.iter_batched_ref(
+                || list.clone(),
+                |list| {
+                    while !list.is_empty() {
+                        list.delete_first();
+                    }
+                },
+                BatchSize::LargeInput
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("std/remove", size), &size, |b, _| {
+            b.iter_batched_ref(
+                || std_list.clone(),
+                |list| while list.pop_front().is_some() {},
+                BatchSize::LargeInput
+            );
+        });
+
+        group.bench_with_input(BenchmarkId::new("crate/iterate", size), &size, |b, _| {
+            b.iter(|| list.iter().sum::<usize>());
+        });
+        group.bench_with_input(BenchmarkId::new("std/iterate", size), &size, |b, _| {
+            b.iter(|| std_list.iter().sum::<usize>());
+        });
+    }
+    group.finish();
+}
+
+fn stack(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stack");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("crate/push_pop", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut stack = Stack::new();
+                for item in 0..size {
+                    stack.push(black_box(item));
+                }
+                while stack.pop().is_some() {}
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("std/push_pop", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut stack = Vec::new();
+                for item in 0..size {
+                    stack.push(black_box(item));
+                }
+                while stack.pop().is_some() {}
+            });
+        });
+
+        let mut filled = Stack::new();
+        (0..size).for_each(|item| filled.push(item));
+        let vec: Vec<usize> = (0..size).collect();
+        group.bench_with_input(BenchmarkId::new("crate/iterate", size), &size, |b, _| {
+            b.iter(|| filled.iter().sum::<usize>());
+        });
+        group.bench_with_input(BenchmarkId::new("std/iterate", size), &size, |b, _| {
+            b.iter(|| vec.iter().sum::<usize>());
+        });
+    }
+    group.finish();
+}
+
+fn queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("queue");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("crate/add_remove", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut queue = Queue::new();
+                for item in 0..size {
+                    queue.add(black_box(item));
+                }
+                while queue.remove().is_some() {}
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("std/add_remove", size), &size, |b, &size| {
+            b.iter(|| {
+                let mut queue = VecDeque::new();
+                for item in 0..size {
+                    queue.push_back(black_box(item));
+                }
+                while queue.pop_front().is_some() {}
+            });
+        });
+
+        let mut filled = Queue::new();
+        (0..size).for_each(|item| filled.add(item));
+        let deque: VecDeque<usize> = (0..size).collect();
+        group.bench_with_input(BenchmarkId::new("crate/iterate", size), &size, |b, _| {
+            b.iter(|| filled.iter().sum::<usize>());
+        });
+        group.bench_with_input(BenchmarkId::new("std/iterate", size), &size, |b, _| {
+            b.iter(|| deque.iter().sum::<usize>());
+        });
+    }
+    group.finish();
+}
+
+/// Replays `ops` on a new map and counts the ops that found their key
+fn run_workload<M: Map<u64, u64> + Default>(ops: &[Op]) -> usize {
+    let mut map = M::default();
+    workload::replay(&mut map, black_box(ops)).filter(Option::is_some).count()
+}
+
+fn workloads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("workload");
+    let keys = [
+        ("uniform", KeyDistribution::Uniform { keys: 10_000 }),
+        ("zipfian", KeyDistribution::Zipfian { keys: 10_000, exponent: 1.0 }),
+    ];
+    for (name, keys) in keys {
+        let ops = Workload::new(42, 100_000).with_keys(keys).ops();
+        group.bench_with_input(BenchmarkId::new("crate_hash_map", name), &ops, |b, ops| {
+            b.iter(|| run_workload::<HashMap<u64, u64>>(ops));
+        });
+        group.bench_with_input(BenchmarkId::new("avl_tree", name), &ops, |b, ops| {
+            b.iter(|| run_workload::<AvlTreeMap<u64, u64>>(ops));
+        });
+        group.bench_with_input(BenchmarkId::new("skip_list", name), &ops, |b, ops| {
+            b.iter(|| run_workload::<ConcurrentSkipListMap<u64, u64>>(ops));
+        });
+        group.bench_with_input(BenchmarkId::new("std_hash_map", name), &ops, |b, ops| {
+            b.iter(|| run_workload::<StdHashMap<u64, u64>>(ops));
+        });
+        group.bench_with_input(BenchmarkId::new("std_btree_map", name), &ops, |b, ops| {
+            b.iter(|| run_workload::<BTreeMap<u64, u64>>(ops));
+        });
+    }
+    group.finish();
+}
+
+/// Common interface over the heaps for the `heaps` group
+trait BenchHeap: Sized {
+    const NAME: &'static str;
+    fn empty() -> Self;
+    fn push(&mut self, item: u64);
+    fn pop(&mut self) -> Option<u64>;
+    fn meld(&mut self, other: Self);
+}
+
+impl BenchHeap for BinaryHeap<u64> {
+    const NAME: &'static str = "binary";
+
+    fn empty() -> Self {
+        BinaryHeap::new_min()
+    }
+
+    fn push(&mut self, item: u64) {
+        BinaryHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        BinaryHeap::pop(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        for item in other.into_vec() {
+            BinaryHeap::push(self, item);
+        }
+    }
+}
+
+impl BenchHeap for BinomialHeap<u64> {
+    const NAME: &'static str = "binomial";
+
+    fn empty() -> Self {
+        BinomialHeap::new()
+    }
+
+    fn push(&mut self, item: u64) {
+        BinomialHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        BinomialHeap::pop(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        self.union(other);
+    }
+}
+
+impl BenchHeap for PairingHeap<u64> {
+    const NAME: &'static str = "pairing";
+
+    fn empty() -> Self {
+        PairingHeap::new()
+    }
+
+    fn push(&mut self, item: u64) {
+        PairingHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        PairingHeap::pop(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        PairingHeap::meld(self, other);
+    }
+}
+
+impl BenchHeap for LeftistHeap<u64> {
+    const NAME: &'static str = "leftist";
+
+    fn empty() -> Self {
+        LeftistHeap::new()
+    }
+
+    fn push(&mut self, item: u64) {
+        LeftistHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        LeftistHeap::pop(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        LeftistHeap::meld(self, other);
+    }
+}
+
+impl BenchHeap for SkewHeap<u64> {
+    const NAME: &'static str = "skew";
+
+    fn empty() -> Self {
+        SkewHeap::new()
+    }
+
+    fn push(&mut self, item: u64) {
+        SkewHeap::push(self, item);
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        SkewHeap::pop(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        SkewHeap::meld(self, other);
+    }
+}
+
+fn random_items(count: usize, seed: u64) -> Vec<u64> {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            state >> 16
+        })
+        .collect()
+}
+
+fn filled_heap<H: BenchHeap>(items: &[u64]) -> H {
+    let mut heap = H::empty();
+    for &item in items {
+        heap.push(item);
+    }
+    heap
+}
+
+/// Push then pop everything, pushes and pops in turns on a half full heap,
+/// and melding 64 heaps into one, where only the melds are timed
+fn bench_heap<H: BenchHeap>(group: &mut BenchmarkGroup<'_, WallTime>, items: &[u64]) {
+    let size = items.len();
+    let id = |workload: &str| BenchmarkId::new(format!("{}/{}", H::NAME, workload), size);
+    group.bench_with_input(id("push_pop"), items, |b, items| {
+        b.iter(|| {
+            let mut heap = filled_heap::<H>(black_box(items));
+            while let Some(item) = heap.pop() {
+                black_box(item);
+            }
+        });
+    });
+
+    let (first, second) = items.split_at(size / 2);
+    group.bench_with_input(id("mixed"), second, |b, second| {
+        b.iter_batched(
+            || filled_heap::<H>(first),
+            |mut heap| {
+                for &item in second {
+                    heap.push(item);
+                    black_box(heap.pop());
+                }
+                heap
+            },
+            BatchSize::SmallInput
+        );
+    });
+
+    let chunk = size.div_ceil(64);
+    group.bench_with_input(id("meld"), items, |b, items| {
+        b.iter_batched(
+            || items.chunks(chunk).map(filled_heap::<H>).collect::<Vec<H>>(),
+            |heaps| {
+                let mut total = H::empty();
+                for heap in heaps {
+                    total.meld(heap);
+                }
+                black_box(total.pop());
+                total
+            },
+            BatchSize::SmallInput
+        );
+    });
+}
+
+fn heaps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heaps");
+    for size in SIZES {
+        let items = random_items(size, size as u64);
+        bench_heap::<BinaryHeap<u64>>(&mut group, &items);
+        bench_heap::<BinomialHeap<u64>>(&mut group, &items);
+        bench_heap::<PairingHeap<u64>>(&mut group, &items);
+        bench_heap::<LeftistHeap<u64>>(&mut group, &items);
+        bench_heap::<SkewHeap<u64>>(&mut group, &items);
+    }
+    group.finish();
+}
+
+criterion_group!(benches, hash_map, linked_list, stack, queue, workloads, heaps);
+criterion_main!(benches);
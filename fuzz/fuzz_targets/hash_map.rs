@@ -0,0 +1,42 @@
+#![no_main]
+
+use std::collections::HashMap as StdHashMap;
+
+use arbitrary::Arbitrary;
+use data_structures_in_rust::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(u16, u8),
+    Remove(u16),
+    Get(u16),
+    Iterate,
+    Clear,
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut map = HashMap::new();
+    let mut model = StdHashMap::new();
+    for op in ops {
+        match op {
+            Op::Insert(key, value) => assert_eq!(map.insert(key, value), model.insert(key, value)),
+            Op::Remove(key) => assert_eq!(map.remove(key), model.remove(&key)),
+            Op::Get(key) => assert_eq!(map.get(key), model.get(&key).copied()),
+            Op::Iterate => {
+                let mut pairs: Vec<(u16, u8)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+                pairs.sort();
+                let mut expected: Vec<(u16, u8)> = model.iter().map(|(&k, &v)| (k, v)).collect();
+                expected.sort();
+                assert_eq!(pairs, expected);
+            }
+            Op::Clear => {
+                map.clear();
+                model.clear();
+            }
+        }
+        // The stored size has to agree with what iteration finds
+        assert_eq!(map.len(), model.len());
+        assert_eq!(map.iter().count(), map.len());
+    }
+});
@@ -0,0 +1,52 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use data_structures_in_rust::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Append(u8),
+    Prepend(u8),
+    InsertAfter(u8, u8),
+    InsertBefore(u8, u8),
+    DeleteFirst,
+    DeleteLast,
+    DeleteWithData(u8),
+    Contains(u8),
+    Clear,
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut list = SinglyLinkedList::new();
+    for op in ops {
+        // Only panics and broken invariants count as failures here, the
+        // exact results are checked against a model in tests/model.rs
+        match op {
+            Op::Append(data) => list.append(data),
+            Op::Prepend(data) => list.prepend(data),
+            Op::InsertAfter(data, given) => {
+                let _ = list.try_insert_after_given(data, given);
+            }
+            Op::InsertBefore(data, given) => {
+                let _ = list.try_insert_before_given(data, given);
+            }
+            Op::DeleteFirst => {
+                let _ = list.try_delete_first();
+            }
+            Op::DeleteLast => {
+                let _ = list.try_delete_last();
+            }
+            Op::DeleteWithData(data) => {
+                let _ = list.try_delete_node_with_data(data);
+            }
+            Op::Contains(data) => {
+                let mut items = list.iter();
+                assert_eq!(Collection::contains(&list, &data), items.any(|&item| item == data));
+            }
+            Op::Clear => list.clear(),
+        }
+        assert_eq!(list.iter().count(), list.len());
+        assert_eq!(list.is_empty(), list.len() == 0);
+    }
+});
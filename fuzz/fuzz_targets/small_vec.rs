@@ -0,0 +1,62 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use data_structures_in_rust::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Push(u8),
+    Pop,
+    Insert(u8, u8),
+    Remove(u8),
+    SwapRemove(u8),
+    Clone,
+    Clear,
+    IntoVec,
+}
+
+// Strings own heap memory, so a double drop or a read of an uninitialized
+// slot in the unsafe inline storage shows up under the sanitizer
+fuzz_target!(|ops: Vec<Op>| {
+    let mut items: SmallVec<String, 4> = SmallVec::new();
+    let mut model: Vec<String> = Vec::new();
+    for op in ops {
+        match op {
+            Op::Push(item) => {
+                items.push(item.to_string());
+                model.push(item.to_string());
+            }
+            Op::Pop => assert_eq!(items.pop(), model.pop()),
+            Op::Insert(index, item) => {
+                let index = index as usize % (model.len() + 1);
+                items.insert(index, item.to_string());
+                model.insert(index, item.to_string());
+            }
+            Op::Remove(index) if !model.is_empty() => {
+                let index = index as usize % model.len();
+                assert_eq!(items.remove(index), model.remove(index));
+            }
+            Op::SwapRemove(index) if !model.is_empty() => {
+                let index = index as usize % model.len();
+                assert_eq!(items.swap_remove(index), model.swap_remove(index));
+            }
+            Op::Remove(_) | Op::SwapRemove(_) => {}
+            Op::Clone => items = items.clone(),
+            Op::Clear => {
+                items.clear();
+                model.clear();
+            }
+            Op::IntoVec => {
+                let vec = std::mem::take(&mut items).into_vec();
+                assert_eq!(vec, model);
+                items = vec.into_iter().collect();
+            }
+        }
+        assert_eq!(items.as_slice(), model.as_slice());
+        assert!(items.len() <= items.capacity());
+        if !items.spilled() {
+            assert_eq!(items.capacity(), 4);
+        }
+    }
+});
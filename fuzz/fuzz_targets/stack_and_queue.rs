@@ -0,0 +1,55 @@
+#![no_main]
+
+use std::collections::VecDeque;
+
+use arbitrary::Arbitrary;
+use data_structures_in_rust::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Push(i32),
+    Pop,
+    Peek,
+    Iterate,
+    Clear,
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut stack = Stack::new();
+    let mut stack_model = Vec::new();
+    let mut queue = Queue::new();
+    let mut queue_model = VecDeque::new();
+    for op in ops {
+        match op {
+            Op::Push(item) => {
+                stack.push(item);
+                stack_model.push(item);
+                queue.add(item);
+                queue_model.push_back(item);
+            }
+            Op::Pop => {
+                assert_eq!(stack.pop(), stack_model.pop());
+                assert_eq!(queue.remove(), queue_model.pop_front());
+            }
+            Op::Peek => {
+                assert_eq!(stack.peek(), stack_model.last());
+                assert_eq!(queue.peek(), queue_model.front().copied());
+            }
+            Op::Iterate => {
+                assert!(stack.iter().eq(stack_model.iter()));
+                assert!(queue.iter().eq(queue_model.iter()));
+            }
+            Op::Clear => {
+                Collection::clear(&mut stack);
+                stack_model.clear();
+                Collection::clear(&mut queue);
+                queue_model.clear();
+            }
+        }
+        assert_eq!(Collection::len(&stack), stack_model.len());
+        assert_eq!(stack.is_empty(), stack_model.is_empty());
+        assert_eq!(Collection::len(&queue), queue_model.len());
+        assert_eq!(queue.is_empty(), queue_model.is_empty());
+    }
+});
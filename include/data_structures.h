@@ -0,0 +1,128 @@
+#ifndef DATA_STRUCTURES_H
+#define DATA_STRUCTURES_H
+
+/* Generated by cbindgen from src/ffi.rs, don't edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct DsrHashMap DsrHashMap;
+
+typedef struct DsrQueue DsrQueue;
+
+typedef struct DsrStack DsrStack;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+DsrHashMap *dsr_hash_map_new(void);
+
+/**
+ * Returns true and writes the old value to `old` if the key was present
+ *
+ * # Safety
+ * `map` must be null or a live handle, `old` must be null or writable
+ */
+bool dsr_hash_map_insert(DsrHashMap *map, int64_t key, int64_t value, int64_t *old);
+
+/**
+ * # Safety
+ * `map` must be null or a live handle, `value` must be null or writable
+ */
+bool dsr_hash_map_get(const DsrHashMap *map, int64_t key, int64_t *value);
+
+/**
+ * # Safety
+ * `map` must be null or a live handle, `value` must be null or writable
+ */
+bool dsr_hash_map_remove(DsrHashMap *map, int64_t key, int64_t *value);
+
+/**
+ * # Safety
+ * `map` must be null or a live handle
+ */
+size_t dsr_hash_map_len(const DsrHashMap *map);
+
+/**
+ * # Safety
+ * `map` must be null or a live handle, it is dangling afterwards
+ */
+void dsr_hash_map_free(DsrHashMap *map);
+
+DsrStack *dsr_stack_new(void);
+
+/**
+ * Returns false if `stack` is null
+ *
+ * # Safety
+ * `stack` must be null or a live handle
+ */
+bool dsr_stack_push(DsrStack *stack, int64_t item);
+
+/**
+ * # Safety
+ * `stack` must be null or a live handle, `item` must be null or writable
+ */
+bool dsr_stack_pop(DsrStack *stack, int64_t *item);
+
+/**
+ * # Safety
+ * `stack` must be null or a live handle, `item` must be null or writable
+ */
+bool dsr_stack_peek(const DsrStack *stack, int64_t *item);
+
+/**
+ * # Safety
+ * `stack` must be null or a live handle
+ */
+size_t dsr_stack_len(const DsrStack *stack);
+
+/**
+ * # Safety
+ * `stack` must be null or a live handle, it is dangling afterwards
+ */
+void dsr_stack_free(DsrStack *stack);
+
+DsrQueue *dsr_queue_new(void);
+
+/**
+ * Returns false if `queue` is null
+ *
+ * # Safety
+ * `queue` must be null or a live handle
+ */
+bool dsr_queue_add(DsrQueue *queue, int64_t item);
+
+/**
+ * # Safety
+ * `queue` must be null or a live handle, `item` must be null or writable
+ */
+bool dsr_queue_remove(DsrQueue *queue, int64_t *item);
+
+/**
+ * # Safety
+ * `queue` must be null or a live handle, `item` must be null or writable
+ */
+bool dsr_queue_peek(const DsrQueue *queue, int64_t *item);
+
+/**
+ * # Safety
+ * `queue` must be null or a live handle
+ */
+size_t dsr_queue_len(const DsrQueue *queue);
+
+/**
+ * # Safety
+ * `queue` must be null or a live handle, it is dangling afterwards
+ */
+void dsr_queue_free(DsrQueue *queue);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DATA_STRUCTURES_H */
@@ -0,0 +1,875 @@
+//! Adaptive Radix Tree (ART) implementation
+//! Ordered map from byte-string keys to values. Every inner node branches on
+//! one byte of the key, like a trie, but picks the smallest of four layouts
+//! for its number of children and switches as children come and go. Node4
+//! and Node16 keep sorted key bytes next to their children, Node48 maps all
+//! 256 bytes to one of 48 child slots and Node256 is indexed by the byte.
+//! Inner nodes store the bytes that all their keys share as a compressed
+//! prefix, and a subtree with a single key is just a leaf holding the whole
+//! key, so the height is bounded by the key length rather than the number
+//! of keys. A key that ends at an inner node is stored in that node.
+//! Children are visited in byte order, so iteration is in key order.
+
+use std::mem;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+struct Leaf<V> {
+    key: Vec<u8>,
+    value: V,
+}
+
+#[derive(Debug, Clone)]
+enum ArtNode<V> {
+    Leaf(Box<Leaf<V>>),
+    Inner(Box<InnerNode<V>>),
+}
+
+#[derive(Debug, Clone)]
+struct InnerNode<V> {
+    /// Bytes shared by every key below, after the byte that led here
+    prefix: Vec<u8>,
+    /// Key that ends right after the prefix
+    terminal: Option<Box<Leaf<V>>>,
+    children: Children<V>,
+}
+
+/// Slot of a Node48 byte that has no child
+const EMPTY: u8 = u8::MAX;
+
+#[derive(Debug, Clone)]
+enum Children<V> {
+    /// The first `len` keys are sorted and the first `len` children are set
+    Node4 {
+        keys: [u8; 4],
+        children: [Option<ArtNode<V>>; 4],
+        len: usize,
+    },
+    Node16 {
+        keys: [u8; 16],
+        children: Box<[Option<ArtNode<V>>; 16]>,
+        len: usize,
+    },
+    /// Slot of every byte's child, or `EMPTY`
+    Node48 {
+        slots: Box<[u8; 256]>,
+        children: Box<[Option<ArtNode<V>>; 48]>,
+        len: usize,
+    },
+    Node256 {
+        children: Box<[Option<ArtNode<V>>; 256]>,
+        len: usize,
+    },
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Position of the byte in the sorted keys of a Node4 or Node16
+fn sorted_position(keys: &[u8], byte: u8) -> Result<usize, usize> {
+    keys.binary_search(&byte)
+}
+
+impl<V> Children<V> {
+    /// Smallest layout that fits the children, given in byte order
+    fn from_sorted(pairs: Vec<(u8, ArtNode<V>)>) -> Self {
+        let len = pairs.len();
+        match len {
+            0..=4 => {
+                let mut node = Children::Node4 {
+                    keys: [0; 4],
+                    children: [const { None }; 4],
+                    len: 0,
+                };
+                pairs.into_iter().for_each(|(byte, child)| node.add(byte, child));
+                node
+            }
+            5..=16 => {
+                let mut keys = [0; 16];
+                let mut children = Box::new([const { None }; 16]);
+                for (index, (byte, child)) in pairs.into_iter().enumerate() {
+                    keys[index] = byte;
+                    children[index] = Some(child);
+                }
+                Children::Node16 { keys, children, len }
+            }
+            17..=48 => {
+                let mut slots = Box::new([EMPTY; 256]);
+                let mut children = Box::new([const { None }; 48]);
+                for (index, (byte, child)) in pairs.into_iter().enumerate() {
+                    slots[byte as usize] = index as u8;
+                    children[index] = Some(child);
+                }
+                Children::Node48 { slots, children, len }
+            }
+            _ => {
+                let mut children = Box::new([const { None }; 256]);
+                for (byte, child) in pairs {
+                    children[byte as usize] = Some(child);
+                }
+                Children::Node256 { children, len }
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Children::Node4 { len, .. }
+            | Children::Node16 { len, .. }
+            | Children::Node48 { len, .. }
+            | Children::Node256 { len, .. } => *len,
+        }
+    }
+
+    /// Largest number of children the layout holds
+    fn capacity(&self) -> usize {
+        match self {
+            Children::Node4 { .. } => 4,
+            Children::Node16 { .. } => 16,
+            Children::Node48 { .. } => 48,
+            Children::Node256 { .. } => 256,
+        }
+    }
+
+    fn get(&self, byte: u8) -> Option<&ArtNode<V>> {
+        match self {
+            Children::Node4 { keys, children, len } => {
+                children[sorted_position(&keys[..*len], byte).ok()?].as_ref()
+            }
+            Children::Node16 { keys, children, len } => {
+                children[sorted_position(&keys[..*len], byte).ok()?].as_ref()
+            }
+            Children::Node48 { slots, children, .. } => match slots[byte as usize] {
+                EMPTY => None,
+                slot => children[slot as usize].as_ref(),
+            },
+            Children::Node256 { children, .. } => children[byte as usize].as_ref(),
+        }
+    }
+
+    /// Slot of the byte's child, only if the child exists
+    fn slot_mut(&mut self, byte: u8) -> Option<&mut Option<ArtNode<V>>> {
+        match self {
+            Children::Node4 { keys, children, len } => {
+                Some(&mut children[sorted_position(&keys[..*len], byte).ok()?])
+            }
+            Children::Node16 { keys, children, len } => {
+                Some(&mut children[sorted_position(&keys[..*len], byte).ok()?])
+            }
+            Children::Node48 { slots, children, .. } => match slots[byte as usize] {
+                EMPTY => None,
+                slot => Some(&mut children[slot as usize]),
+            },
+            Children::Node256 { children, .. } => {
+                let slot = &mut children[byte as usize];
+                slot.is_some().then_some(slot)
+            }
+        }
+    }
+
+    /// Adds a child for a byte that has none, growing to the next layout when full
+    fn add(&mut self, byte: u8, child: ArtNode<V>) {
+        if self.len() == self.capacity() {
+            let mut pairs = mem::replace(self, Children::from_sorted(vec![])).into_sorted();
+            let position = pairs.partition_point(|(key, _)| *key < byte);
+            pairs.insert(position, (byte, child));
+            *self = Children::from_sorted(pairs);
+            return;
+        }
+        match self {
+            Children::Node4 { keys, children, len } => {
+                Self::insert_sorted(keys, children, len, byte, child)
+            }
+            Children::Node16 { keys, children, len } => {
+                Self::insert_sorted(keys, &mut children[..], len, byte, child)
+            }
+            Children::Node48 { slots, children, len } => {
+                let slot = children.iter().position(|child| child.is_none()).unwrap();
+                children[slot] = Some(child);
+                slots[byte as usize] = slot as u8;
+                *len += 1;
+            }
+            Children::Node256 { children, len } => {
+                children[byte as usize] = Some(child);
+                *len += 1;
+            }
+        }
+    }
+
+    fn insert_sorted(
+        keys: &mut [u8],
+        children: &mut [Option<ArtNode<V>>],
+        len: &mut usize,
+        byte: u8,
+        child: ArtNode<V>
+    ) {
+        let position = sorted_position(&keys[..*len], byte).unwrap_err();
+        keys[position..=*len].rotate_right(1);
+        children[position..=*len].rotate_right(1);
+        keys[position] = byte;
+        children[position] = Some(child);
+        *len += 1;
+    }
+
+    /// Drops the slot of a byte whose child was taken out of it, shrinking to
+    /// a smaller layout once it is well below the capacity of the smaller one
+    fn remove_slot(&mut self, byte: u8) {
+        match self {
+            Children::Node4 { keys, children, len } => {
+                Self::remove_sorted(keys, children, len, byte)
+            }
+            Children::Node16 { keys, children, len } => {
+                Self::remove_sorted(keys, &mut children[..], len, byte)
+            }
+            Children::Node48 { slots, len, .. } => {
+                slots[byte as usize] = EMPTY;
+                *len -= 1;
+            }
+            Children::Node256 { len, .. } => {
+                *len -= 1;
+            }
+        }
+        let shrink_at = match self {
+            Children::Node4 { .. } => 0,
+            Children::Node16 { .. } => 3,
+            Children::Node48 { .. } => 12,
+            Children::Node256 { .. } => 37,
+        };
+        if self.len() <= shrink_at && self.len() > 0 {
+            let pairs = mem::replace(self, Children::from_sorted(vec![])).into_sorted();
+            *self = Children::from_sorted(pairs);
+        }
+    }
+
+    fn remove_sorted(
+        keys: &mut [u8],
+        children: &mut [Option<ArtNode<V>>],
+        len: &mut usize,
+        byte: u8
+    ) {
+        let position = sorted_position(&keys[..*len], byte).unwrap();
+        keys[position..*len].rotate_left(1);
+        children[position..*len].rotate_left(1);
+        *len -= 1;
+    }
+
+    /// Children with their bytes in byte order
+    fn into_sorted(self) -> Vec<(u8, ArtNode<V>)> {
+        match self {
+            Children::Node4 { keys, children, len } => {
+                keys.into_iter().zip(children).take(len).map(|(k, c)| (k, c.unwrap())).collect()
+            }
+            Children::Node16 { keys, children, len } => {
+                keys.into_iter().zip(*children).take(len).map(|(k, c)| (k, c.unwrap())).collect()
+            }
+            Children::Node48 { slots, mut children, .. } => {
+                (0..=255u8)
+                    .filter(|&byte| slots[byte as usize] != EMPTY)
+                    .map(|byte| (byte, children[slots[byte as usize] as usize].take().unwrap()))
+                    .collect()
+            }
+            Children::Node256 { children, .. } => {
+                (0..=255u8)
+                    .zip(*children)
+                    .filter_map(|(byte, child)| child.map(|child| (byte, child)))
+                    .collect()
+            }
+        }
+    }
+
+    /// Checks the layout agrees with `len` and returns the children with
+    /// their bytes in byte order
+    #[cfg(any(test, feature = "debug-invariants"))]
+    fn checked_pairs(&self) -> Vec<(u8, &ArtNode<V>)> {
+        let pairs: Vec<(u8, &ArtNode<V>)> = match self {
+            Children::Node4 { keys, children, len } => {
+                Self::checked_sorted(keys, &children[..], *len)
+            }
+            Children::Node16 { keys, children, len } => {
+                Self::checked_sorted(keys, &children[..], *len)
+            }
+            Children::Node48 { slots, children, .. } => {
+                let used: Vec<(u8, u8)> = (0..=255u8)
+                    .filter(|&byte| slots[byte as usize] != EMPTY)
+                    .map(|byte| (byte, slots[byte as usize]))
+                    .collect();
+                let mut seen: Vec<u8> = used.iter().map(|&(_, slot)| slot).collect();
+                seen.sort();
+                seen.dedup();
+                assert_eq!(seen.len(), used.len(), "Two bytes share a Node48 slot");
+                let filled = children.iter().filter(|child| child.is_some()).count();
+                assert_eq!(filled, used.len(), "Node48 has a child no byte points at");
+                used.into_iter()
+                    .map(|(byte, slot)| {
+                        let child = children[slot as usize].as_ref();
+                        (byte, child.expect("Node48 byte points at an empty slot"))
+                    })
+                    .collect()
+            }
+            Children::Node256 { children, .. } => {
+                (0..=255u8)
+                    .zip(children.iter())
+                    .filter_map(|(byte, child)| child.as_ref().map(|child| (byte, child)))
+                    .collect()
+            }
+        };
+        assert_eq!(pairs.len(), self.len(), "len doesn't match the children");
+        assert!(self.len() <= self.capacity(), "More children than the layout holds");
+        pairs
+    }
+
+    /// Checks the first `len` keys are ascending and only their children are set
+    #[cfg(any(test, feature = "debug-invariants"))]
+    fn checked_sorted<'a>(
+        keys: &[u8],
+        children: &'a [Option<ArtNode<V>>],
+        len: usize
+    ) -> Vec<(u8, &'a ArtNode<V>)> {
+        assert!(keys[..len].windows(2).all(|pair| pair[0] < pair[1]), "Keys are out of order");
+        assert!(children[len..].iter().all(Option::is_none), "Child set after len");
+        keys[..len]
+            .iter()
+            .zip(children)
+            .map(|(&byte, child)| (byte, child.as_ref().expect("Child missing before len")))
+            .collect()
+    }
+
+    /// Pushes the children in descending byte order, so they pop in ascending order
+    fn push_reversed<'a>(&'a self, stack: &mut Vec<&'a ArtNode<V>>) {
+        match self {
+            Children::Node4 { children, len, .. } => {
+                stack.extend(children[..*len].iter().rev().flatten());
+            }
+            Children::Node16 { children, len, .. } => {
+                stack.extend(children[..*len].iter().rev().flatten());
+            }
+            Children::Node48 { slots, children, .. } => {
+                let used = slots.iter().rev().filter(|&&slot| slot != EMPTY);
+                stack.extend(used.filter_map(|&slot| children[slot as usize].as_ref()));
+            }
+            Children::Node256 { children, .. } => {
+                stack.extend(children.iter().rev().flatten());
+            }
+        }
+    }
+}
+
+impl<V> InnerNode<V> {
+    fn new(prefix: Vec<u8>) -> Self {
+        InnerNode { prefix, terminal: None, children: Children::from_sorted(vec![]) }
+    }
+
+    /// Stores the leaf in this node if its key ends at `depth`, as a child otherwise
+    fn place(&mut self, leaf: Box<Leaf<V>>, depth: usize) {
+        if leaf.key.len() == depth {
+            self.terminal = Some(leaf);
+        } else {
+            self.children.add(leaf.key[depth], ArtNode::Leaf(leaf));
+        }
+    }
+}
+
+/// Checks every key below the node starts with the bytes that lead to it,
+/// and returns how many keys there are. `path` is the key up to the node.
+#[cfg(any(test, feature = "debug-invariants"))]
+fn assert_subtree<V>(node: &ArtNode<V>, path: &mut Vec<u8>) -> usize {
+    let inner = match node {
+        ArtNode::Leaf(leaf) => {
+            assert!(leaf.key.starts_with(path), "Leaf is below another key's bytes");
+            return 1;
+        }
+        ArtNode::Inner(inner) => inner,
+    };
+    let depth = path.len();
+    path.extend_from_slice(&inner.prefix);
+    let mut keys = 0;
+    if let Some(terminal) = &inner.terminal {
+        assert_eq!(terminal.key, *path, "Terminal key doesn't end at its node");
+        keys += 1;
+    }
+    for (byte, child) in inner.children.checked_pairs() {
+        path.push(byte);
+        keys += assert_subtree(child, path);
+        path.pop();
+    }
+    assert!(keys >= 2, "Inner node has fewer than two keys below it");
+    path.truncate(depth);
+    keys
+}
+
+#[derive(Debug, Clone)]
+pub struct AdaptiveRadixTree<V> {
+    root: Option<ArtNode<V>>,
+    len: usize,
+}
+
+impl<V> AdaptiveRadixTree<V> {
+    pub fn new() -> Self {
+        AdaptiveRadixTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = AdaptiveRadixTree::new();
+        check_invariants!(self);
+    }
+
+    /// Inserts the pair. If the key existed, returns the old value.
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        let leaf = Box::new(Leaf { key: key.to_vec(), value });
+        let old = Self::insert_at(&mut self.root, leaf, 0);
+        if old.is_none() {
+            self.len += 1;
+        }
+        check_invariants!(self);
+        old
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        let mut node = self.root.as_ref()?;
+        let mut depth = 0;
+        loop {
+            match node {
+                ArtNode::Leaf(leaf) => {
+                    return (leaf.key == key).then_some(&leaf.value);
+                }
+                ArtNode::Inner(inner) => {
+                    if !key[depth..].starts_with(&inner.prefix) {
+                        return None;
+                    }
+                    depth += inner.prefix.len();
+                    if depth == key.len() {
+                        return inner.terminal.as_ref().map(|leaf| &leaf.value);
+                    }
+                    node = inner.children.get(key[depth])?;
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes the key and returns its value if it existed
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let removed = Self::remove_at(&mut self.root, key, 0);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        check_invariants!(self);
+        removed
+    }
+
+    /// Pairs in ascending key order
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter { stack: self.root.iter().collect() }
+    }
+
+    /// Pairs whose key starts with the prefix, in ascending key order
+    pub fn iter_prefix(&self, prefix: &[u8]) -> Iter<'_, V> {
+        let mut node = self.root.as_ref();
+        let mut depth = 0;
+        while let Some(ArtNode::Inner(inner)) = node {
+            let rest = &prefix[depth..];
+            let common = common_prefix_len(&inner.prefix, rest);
+            if common == rest.len() {
+                break;
+            }
+            if common < inner.prefix.len() {
+                node = None;
+                break;
+            }
+            depth += common;
+            node = inner.children.get(prefix[depth]);
+            depth += 1;
+        }
+        if let Some(ArtNode::Leaf(leaf)) = node {
+            if !leaf.key.starts_with(prefix) {
+                node = None;
+            }
+        }
+        Iter { stack: node.into_iter().collect() }
+    }
+
+    fn insert_at(slot: &mut Option<ArtNode<V>>, leaf: Box<Leaf<V>>, depth: usize) -> Option<V> {
+        match slot {
+            None => {
+                *slot = Some(ArtNode::Leaf(leaf));
+                None
+            }
+            Some(ArtNode::Leaf(existing)) => {
+                if existing.key == leaf.key {
+                    return Some(mem::replace(&mut existing.value, leaf.value));
+                }
+                // Both keys go below a new node that holds their shared bytes
+                let common = common_prefix_len(&existing.key[depth..], &leaf.key[depth..]);
+                let split = depth + common;
+                let mut inner = InnerNode::new(leaf.key[depth..split].to_vec());
+                let Some(ArtNode::Leaf(existing)) = slot.take() else { unreachable!() };
+                inner.place(existing, split);
+                inner.place(leaf, split);
+                *slot = Some(ArtNode::Inner(Box::new(inner)));
+                None
+            }
+            Some(ArtNode::Inner(inner)) => {
+                let common = common_prefix_len(&inner.prefix, &leaf.key[depth..]);
+                if common < inner.prefix.len() {
+                    // The key leaves the prefix, so the node is split where they differ
+                    let Some(ArtNode::Inner(mut old)) = slot.take() else { unreachable!() };
+                    let mut parent = InnerNode::new(old.prefix[..common].to_vec());
+                    let byte = old.prefix[common];
+                    old.prefix.drain(..=common);
+                    parent.children.add(byte, ArtNode::Inner(old));
+                    parent.place(leaf, depth + common);
+                    *slot = Some(ArtNode::Inner(Box::new(parent)));
+                    return None;
+                }
+                let depth = depth + common;
+                if depth == leaf.key.len() {
+                    return match &mut inner.terminal {
+                        Some(existing) => Some(mem::replace(&mut existing.value, leaf.value)),
+                        None => {
+                            inner.terminal = Some(leaf);
+                            None
+                        }
+                    };
+                }
+                let byte = leaf.key[depth];
+                match inner.children.slot_mut(byte) {
+                    Some(child) => Self::insert_at(child, leaf, depth + 1),
+                    None => {
+                        inner.children.add(byte, ArtNode::Leaf(leaf));
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    fn remove_at(slot: &mut Option<ArtNode<V>>, key: &[u8], depth: usize) -> Option<V> {
+        let removed = match slot.as_mut()? {
+            ArtNode::Leaf(leaf) => {
+                if leaf.key != key {
+                    return None;
+                }
+                let Some(ArtNode::Leaf(leaf)) = slot.take() else { unreachable!() };
+                return Some(leaf.value);
+            }
+            ArtNode::Inner(inner) => {
+                if !key[depth..].starts_with(&inner.prefix) {
+                    return None;
+                }
+                let depth = depth + inner.prefix.len();
+                if depth == key.len() {
+                    inner.terminal.take()?.value
+                } else {
+                    let byte = key[depth];
+                    let child = inner.children.slot_mut(byte)?;
+                    let removed = Self::remove_at(child, key, depth + 1)?;
+                    if child.is_none() {
+                        inner.children.remove_slot(byte);
+                    }
+                    removed
+                }
+            }
+        };
+        Self::collapse(slot);
+        Some(removed)
+    }
+
+    /// Replaces an inner node that is left with a single key or child by
+    /// that key or child, so no inner node has fewer than two keys below it
+    fn collapse(slot: &mut Option<ArtNode<V>>) {
+        let Some(ArtNode::Inner(inner)) = slot else {
+            return;
+        };
+        match (inner.terminal.is_some(), inner.children.len()) {
+            (true, 0) => {
+                *slot = inner.terminal.take().map(ArtNode::Leaf);
+            }
+            (false, 1) => {
+                let children = mem::replace(&mut inner.children, Children::from_sorted(vec![]));
+                let (byte, child) = children.into_sorted().pop().unwrap();
+                *slot = Some(match child {
+                    ArtNode::Leaf(leaf) => ArtNode::Leaf(leaf),
+                    ArtNode::Inner(mut child) => {
+                        let mut prefix = mem::take(&mut inner.prefix);
+                        prefix.push(byte);
+                        prefix.append(&mut child.prefix);
+                        child.prefix = prefix;
+                        ArtNode::Inner(child)
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<V> Default for AdaptiveRadixTree<V> {
+    fn default() -> Self {
+        AdaptiveRadixTree::new()
+    }
+}
+
+/// The items of a trie are its keys
+impl<V> Collection for AdaptiveRadixTree<V> {
+    type Item = Vec<u8>;
+
+    fn len(&self) -> usize {
+        AdaptiveRadixTree::len(self)
+    }
+
+    fn clear(&mut self) {
+        AdaptiveRadixTree::clear(self);
+    }
+
+    fn contains(&self, key: &Vec<u8>) -> bool {
+        self.contains_key(key)
+    }
+}
+
+/// In-order iterator, keys that end at an inner node come before its children
+pub struct Iter<'a, V> {
+    stack: Vec<&'a ArtNode<V>>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a [u8], &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.pop()? {
+                ArtNode::Leaf(leaf) => {
+                    return Some((&leaf.key, &leaf.value));
+                }
+                ArtNode::Inner(inner) => {
+                    inner.children.push_reversed(&mut self.stack);
+                    if let Some(leaf) = &inner.terminal {
+                        return Some((&leaf.key, &leaf.value));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<V> Invariants for AdaptiveRadixTree<V> {
+    fn assert_invariants(&self) {
+        let keys = self.root.as_ref().map_or(0, |root| assert_subtree(root, &mut vec![]));
+        assert_eq!(keys, self.len, "len doesn't match the keys");
+    }
+}
+
+impl<V: HeapSize> HeapSize for Leaf<V> {
+    fn heap_size(&self) -> usize {
+        self.key.heap_size() + self.value.heap_size()
+    }
+}
+
+impl<V: HeapSize> HeapSize for ArtNode<V> {
+    fn heap_size(&self) -> usize {
+        match self {
+            ArtNode::Leaf(leaf) => leaf.heap_size(),
+            ArtNode::Inner(inner) => inner.heap_size(),
+        }
+    }
+}
+
+impl<V: HeapSize> HeapSize for InnerNode<V> {
+    fn heap_size(&self) -> usize {
+        self.prefix.heap_size() + self.terminal.heap_size() + self.children.heap_size()
+    }
+}
+
+/// The larger layouts keep their arrays in boxes, which the node grows into
+impl<V: HeapSize> HeapSize for Children<V> {
+    fn heap_size(&self) -> usize {
+        match self {
+            Children::Node4 { children, .. } => children.heap_size(),
+            Children::Node16 { children, .. } => children.heap_size(),
+            Children::Node48 { slots, children, .. } => slots.heap_size() + children.heap_size(),
+            Children::Node256 { children, .. } => children.heap_size(),
+        }
+    }
+}
+
+impl<V: HeapSize> HeapSize for AdaptiveRadixTree<V> {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn root_capacity<V>(tree: &AdaptiveRadixTree<V>) -> Option<usize> {
+        match &tree.root {
+            Some(ArtNode::Inner(inner)) => Some(inner.children.capacity()),
+            _ => None,
+        }
+    }
+
+    fn keys<V>(tree: &AdaptiveRadixTree<V>) -> Vec<String> {
+        tree.iter().map(|(key, _)| String::from_utf8(key.to_vec()).unwrap()).collect()
+    }
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let tree = AdaptiveRadixTree::<i32>::new();
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.get(b"a"), None);
+        assert_eq!(tree.iter().next(), None);
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut tree = AdaptiveRadixTree::new();
+
+        assert_eq!(tree.insert(b"apple", 1), None);
+        assert_eq!(tree.insert(b"apply", 2), None);
+        assert_eq!(tree.insert(b"apple", 3), Some(1));
+
+        assert_eq!(tree.get(b"apple"), Some(&3));
+        assert_eq!(tree.get(b"apply"), Some(&2));
+        assert_eq!(tree.get(b"appl"), None);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn test_prefix_is_compressed_and_split() {
+        let mut tree = AdaptiveRadixTree::new();
+        tree.insert(b"romane", 1);
+        tree.insert(b"romanus", 2);
+
+        let Some(ArtNode::Inner(inner)) = &tree.root else { panic!("Root is an inner node") };
+        assert_eq!(inner.prefix, b"roman");
+
+        tree.insert(b"rubens", 3);
+        let Some(ArtNode::Inner(inner)) = &tree.root else { panic!("Root is an inner node") };
+        assert_eq!(inner.prefix, b"r");
+        assert_eq!(keys(&tree), vec!["romane", "romanus", "rubens"]);
+    }
+
+    #[test]
+    fn test_key_that_is_a_prefix_of_another() {
+        let mut tree = AdaptiveRadixTree::new();
+
+        tree.insert(b"car", 1);
+        tree.insert(b"ca", 2);
+        tree.insert(b"cart", 3);
+        tree.insert(b"", 4);
+
+        assert_eq!(tree.get(b"ca"), Some(&2));
+        assert_eq!(tree.get(b""), Some(&4));
+        assert_eq!(keys(&tree), vec!["", "ca", "car", "cart"]);
+        assert_eq!(tree.remove(b"car"), Some(1));
+        assert_eq!(keys(&tree), vec!["", "ca", "cart"]);
+    }
+
+    #[test]
+    fn test_node_grows_and_shrinks_with_children() {
+        let mut tree = AdaptiveRadixTree::new();
+        let mut capacities = vec![];
+
+        for byte in 0..=255u8 {
+            tree.insert(&[byte, 0], byte);
+            capacities.push(root_capacity(&tree));
+        }
+        assert_eq!(capacities[3], Some(4));
+        assert_eq!(capacities[4], Some(16));
+        assert_eq!(capacities[16], Some(48));
+        assert_eq!(capacities[48], Some(256));
+
+        for byte in 0..=252u8 {
+            tree.remove(&[byte, 0]);
+        }
+        assert_eq!(root_capacity(&tree), Some(4));
+        let values: Vec<u8> = tree.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec![253, 254, 255]);
+    }
+
+    #[test]
+    fn test_remove_merges_single_child_into_parent() {
+        let mut tree = AdaptiveRadixTree::new();
+        tree.insert(b"test", 1);
+        tree.insert(b"team", 2);
+        tree.insert(b"toast", 3);
+
+        tree.remove(b"toast");
+        let Some(ArtNode::Inner(inner)) = &tree.root else { panic!("Root is an inner node") };
+        assert_eq!(inner.prefix, b"te");
+
+        tree.remove(b"team");
+        assert!(matches!(tree.root, Some(ArtNode::Leaf(_))));
+        assert_eq!(tree.remove(b"team"), None);
+        assert_eq!(tree.remove(b"test"), Some(1));
+        assert!(tree.root.is_none());
+    }
+
+    #[test]
+    fn test_iter_prefix() {
+        let mut tree = AdaptiveRadixTree::new();
+        for (value, key) in ["bat", "bath", "batman", "cat", "bad"].iter().enumerate() {
+            tree.insert(key.as_bytes(), value);
+        }
+
+        let with_prefix = |prefix: &str| -> Vec<usize> {
+            tree.iter_prefix(prefix.as_bytes()).map(|(_, value)| *value).collect()
+        };
+
+        assert_eq!(with_prefix("bat"), vec![0, 1, 2]);
+        assert_eq!(with_prefix("ba"), vec![4, 0, 1, 2]);
+        assert_eq!(with_prefix("batm"), vec![2]);
+        assert_eq!(with_prefix("dog"), Vec::<usize>::new());
+        assert_eq!(with_prefix("").len(), 5);
+    }
+
+    #[test]
+    fn test_random_operations_match_btree_map() {
+        let mut tree = AdaptiveRadixTree::new();
+        let mut expected = BTreeMap::new();
+        let mut state: u64 = 31;
+
+        for step in 0..5000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            // Few distinct bytes and short keys give deep shared prefixes
+            // and some keys with any first byte make the root grow
+            let key: Vec<u8> = if (state >> 40).is_multiple_of(7) {
+                vec![(state >> 32) as u8; 3]
+            } else {
+                let len = (state >> 58) as usize % 5;
+                (0..len).map(|i| b"abcz"[(state >> (i * 3 + 20)) as usize % 4]).collect()
+            };
+            if (state >> 62).is_multiple_of(2) {
+                assert_eq!(tree.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(tree.insert(&key, step), expected.insert(key.clone(), step));
+            }
+            assert_eq!(tree.get(&key), expected.get(&key));
+        }
+
+        assert_eq!(tree.len(), expected.len());
+        assert!(tree.iter().eq(expected.iter().map(|(key, value)| (key.as_slice(), value))));
+    }
+}
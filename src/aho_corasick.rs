@@ -0,0 +1,314 @@
+//! Aho-Corasick multi-pattern automaton
+//! All patterns are stored in a trie of states. Every state gets a failure
+//! link to the state of its longest proper suffix that is also in the trie,
+//! so the text is scanned only once no matter how many patterns there are.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::queue::Queue;
+
+const ROOT: usize = 0;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct State {
+    transitions: Vec<(u8, usize)>,
+    fail: usize,
+    /// Ids of all patterns that end in this state, including the ones
+    /// reachable through failure links.
+    outputs: Vec<usize>,
+}
+
+impl State {
+    fn next(&self, byte: u8) -> Option<usize> {
+        self.transitions.iter().find(|(b, _)| *b == byte).map(|&(_, state)| state)
+    }
+}
+
+/// Single occurrence of a pattern in the text.
+/// `start` and `end` are byte offsets, `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub pattern_id: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    states: Vec<State>,
+    pattern_lengths: Vec<usize>,
+}
+
+impl AhoCorasick {
+    /// Builds the automaton. Pattern id is the index in `patterns`.
+    /// Empty patterns are kept for numbering but never reported.
+    pub fn new<P: AsRef<[u8]>>(patterns: &[P]) -> Self {
+        let mut automaton = AhoCorasick {
+            states: vec![State::default()],
+            pattern_lengths: Vec::with_capacity(patterns.len()),
+        };
+        for (id, pattern) in patterns.iter().enumerate() {
+            automaton.add_pattern(id, pattern.as_ref());
+        }
+        automaton.build_failure_links();
+        check_invariants!(&automaton);
+        automaton
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_lengths.len()
+    }
+
+    fn add_pattern(&mut self, id: usize, pattern: &[u8]) {
+        self.pattern_lengths.push(pattern.len());
+        if pattern.is_empty() {
+            return;
+        }
+
+        let mut current = ROOT;
+        for &byte in pattern {
+            current = match self.states[current].next(byte) {
+                Some(next) => next,
+                None => {
+                    self.states.push(State::default());
+                    let new_state = self.states.len() - 1;
+                    self.states[current].transitions.push((byte, new_state));
+                    new_state
+                }
+            };
+        }
+        self.states[current].outputs.push(id);
+    }
+
+    /// Breadth first walk, so failure links of shallower states
+    /// are always ready when a deeper state needs them.
+    fn build_failure_links(&mut self) {
+        let mut queue = Queue::new();
+        for &(_, child) in &self.states[ROOT].transitions {
+            queue.add(child);
+        }
+
+        while let Some(state) = queue.remove() {
+            let transitions = self.states[state].transitions.clone();
+            for (byte, child) in transitions {
+                let fail = self.step(self.states[state].fail, byte);
+                self.states[child].fail = fail;
+                let inherited = self.states[fail].outputs.clone();
+                self.states[child].outputs.extend(inherited);
+                queue.add(child);
+            }
+        }
+    }
+
+    /// Follows failure links until a transition for `byte` exists.
+    fn step(&self, mut state: usize, byte: u8) -> usize {
+        loop {
+            if let Some(next) = self.states[state].next(byte) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.states[state].fail;
+        }
+    }
+
+    /// Lazily yields matches in the order their end offset is reached.
+    /// Matches ending at the same offset are yielded longest first.
+    pub fn find_iter<'a, T: AsRef<[u8]> + ?Sized>(&'a self, text: &'a T) -> FindIter<'a> {
+        FindIter {
+            automaton: self,
+            text: text.as_ref(),
+            position: 0,
+            state: ROOT,
+            output_index: 0,
+        }
+    }
+
+    pub fn find_all<T: AsRef<[u8]> + ?Sized>(&self, text: &T) -> Vec<Match> {
+        self.find_iter(text).collect()
+    }
+
+    pub fn is_match<T: AsRef<[u8]> + ?Sized>(&self, text: &T) -> bool {
+        self.find_iter(text).next().is_some()
+    }
+}
+
+/// Checks the states form a trie with one edge per byte, every failure link
+/// goes to a shallower state, and every output is a pattern that fits the
+/// depth of its state
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for AhoCorasick {
+    fn assert_invariants(&self) {
+        let mut depths = vec![None; self.states.len()];
+        depths[ROOT] = Some(0);
+        let mut queue = Queue::new();
+        queue.add(ROOT);
+        while let Some(state) = queue.remove() {
+            let transitions = &self.states[state].transitions;
+            for (index, &(byte, child)) in transitions.iter().enumerate() {
+                let repeated = transitions[..index].iter().any(|(other, _)| *other == byte);
+                assert!(!repeated, "Two transitions on the same byte");
+                assert!(depths[child].is_none(), "State is reached twice");
+                depths[child] = Some(depths[state].unwrap() + 1);
+                queue.add(child);
+            }
+        }
+        for (state, depth) in self.states.iter().zip(&depths) {
+            let depth = depth.expect("State isn't reachable from the root");
+            let fail_depth = depths[state.fail].unwrap();
+            assert!(depth == 0 || fail_depth < depth, "Failure link doesn't go up");
+            for &id in &state.outputs {
+                assert!(self.pattern_lengths[id] <= depth, "Output is longer than its state");
+            }
+        }
+    }
+}
+
+pub struct FindIter<'a> {
+    automaton: &'a AhoCorasick,
+    text: &'a [u8],
+    position: usize,
+    state: usize,
+    output_index: usize,
+}
+
+impl<'a> Iterator for FindIter<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            let outputs = &self.automaton.states[self.state].outputs;
+            if let Some(&pattern_id) = outputs.get(self.output_index) {
+                self.output_index += 1;
+                return Some(Match {
+                    pattern_id,
+                    start: self.position - self.automaton.pattern_lengths[pattern_id],
+                    end: self.position,
+                });
+            }
+
+            let byte = *self.text.get(self.position)?;
+            self.state = self.automaton.step(self.state, byte);
+            self.position += 1;
+            self.output_index = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(patterns: &[&str], text: &str) -> Vec<Match> {
+        let mut matches = vec![];
+        for end in 1..=text.len() {
+            let mut at_end: Vec<Match> = patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !p.is_empty() && p.len() <= end)
+                .filter(|(_, p)| &text.as_bytes()[end - p.len()..end] == p.as_bytes())
+                .map(|(id, p)| Match { pattern_id: id, start: end - p.len(), end })
+                .collect();
+            at_end.sort_by_key(|m| m.start);
+            matches.extend(at_end);
+        }
+        matches
+    }
+
+    #[test]
+    fn test_no_patterns_never_match() {
+        let automaton = AhoCorasick::new::<&str>(&[]);
+
+        assert_eq!(automaton.pattern_count(), 0);
+        assert!(!automaton.is_match("anything"));
+    }
+
+    #[test]
+    fn test_single_pattern_multiple_occurrences() {
+        let automaton = AhoCorasick::new(&["ab"]);
+
+        let matches = automaton.find_all("xabyab");
+
+        assert_eq!(
+            matches,
+            vec![
+                Match { pattern_id: 0, start: 1, end: 3 },
+                Match { pattern_id: 0, start: 4, end: 6 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classic_he_she_his_hers() {
+        let automaton = AhoCorasick::new(&["he", "she", "his", "hers"]);
+
+        let matches = automaton.find_all("ushers");
+
+        assert_eq!(
+            matches,
+            vec![
+                Match { pattern_id: 1, start: 1, end: 4 },
+                Match { pattern_id: 0, start: 2, end: 4 },
+                Match { pattern_id: 3, start: 2, end: 6 }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_patterns() {
+        let automaton = AhoCorasick::new(&["a", "aa", "aaa"]);
+
+        let matches = automaton.find_all("aaa");
+
+        assert_eq!(matches.len(), 6);
+        assert_eq!(matches[5], Match { pattern_id: 0, start: 2, end: 3 });
+    }
+
+    #[test]
+    fn test_failure_links_point_to_longest_suffix() {
+        let automaton = AhoCorasick::new(&["abcd", "bcx"]);
+        let abc = automaton.step(automaton.step(automaton.step(ROOT, b'a'), b'b'), b'c');
+        let bc = automaton.step(automaton.step(ROOT, b'b'), b'c');
+
+        assert_eq!(automaton.states[abc].fail, bc);
+        assert_eq!(automaton.find_all("abcx"), vec![Match { pattern_id: 1, start: 1, end: 4 }]);
+    }
+
+    #[test]
+    fn test_empty_pattern_is_ignored() {
+        let automaton = AhoCorasick::new(&["", "b"]);
+
+        assert_eq!(automaton.pattern_count(), 2);
+        assert_eq!(automaton.find_all("abc"), vec![Match { pattern_id: 1, start: 1, end: 2 }]);
+    }
+
+    #[test]
+    fn test_find_iter_is_lazy() {
+        let automaton = AhoCorasick::new(&["needle"]);
+        let text = "needle".repeat(1000);
+
+        let first_two: Vec<Match> = automaton.find_iter(&text).take(2).collect();
+
+        assert_eq!(first_two[1], Match { pattern_id: 0, start: 6, end: 12 });
+    }
+
+    #[test]
+    fn test_matches_agree_with_brute_force() {
+        let patterns = ["abra", "cad", "a", "bra", "racadabra", "dab"];
+        let text = "abracadabra abracadabra";
+        let automaton = AhoCorasick::new(&patterns);
+
+        assert_eq!(automaton.find_all(text), brute_force(&patterns, text));
+    }
+
+    #[test]
+    fn test_byte_offsets_with_multibyte_text() {
+        let automaton = AhoCorasick::new(&["ł"]);
+
+        let matches = automaton.find_all("żół");
+
+        assert_eq!(matches, vec![Match { pattern_id: 0, start: 4, end: 6 }]);
+    }
+}
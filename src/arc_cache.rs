@@ -0,0 +1,333 @@
+//! ARC (Adaptive Replacement Cache) implementation
+//! Resident entries are split into T1, seen once recently, and T2, seen at
+//! least twice. Evicted keys are remembered without their values in the
+//! ghost lists B1 and B2. A miss that hits B1 means T1 was too small and a
+//! miss that hits B2 means T2 was too small, so the target size of T1 moves
+//! toward whichever side would have kept the entry. This balances recency
+//! and frequency without any tuning. All lists are linked hash sets kept in
+//! LRU order, the oldest key at the front.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::linked_hash_set::LinkedHashSet;
+
+/// Hit and miss counts of `get` calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Share of lookups that were hits, 0 when there were none
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArcCache<K, V> {
+    capacity: usize,
+    /// Target size of `recent`, adapted on ghost hits
+    target: usize,
+    values: HashMap<K, V>,
+    /// T1, resident entries used once
+    recent: LinkedHashSet<K>,
+    /// T2, resident entries used more than once
+    frequent: LinkedHashSet<K>,
+    /// B1, keys evicted from T1
+    recent_ghosts: LinkedHashSet<K>,
+    /// B2, keys evicted from T2
+    frequent_ghosts: LinkedHashSet<K>,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq + Clone, V> ArcCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        ArcCache {
+            capacity,
+            target: 0,
+            values: HashMap::with_capacity(capacity),
+            recent: LinkedHashSet::new(),
+            frequent: LinkedHashSet::new(),
+            recent_ghosts: LinkedHashSet::new(),
+            frequent_ghosts: LinkedHashSet::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// How many entries the cache currently wants to keep for recency
+    pub fn target_recent_size(&self) -> usize {
+        self.target
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Entries used once, from the oldest
+    pub fn recent_entries(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.recent.iter().map(|key| (key, &self.values[key]))
+    }
+
+    /// Entries used more than once, from the oldest
+    pub fn frequent_entries(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.frequent.iter().map(|key| (key, &self.values[key]))
+    }
+
+    /// Returns the value and moves the entry to the frequent list.
+    /// Counts a hit or a miss.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        if self.recent.remove(key) || self.frequent.remove(key) {
+            self.frequent.insert(key.clone());
+            self.stats.hits += 1;
+            check_invariants!(self);
+            return self.values.get(key);
+        }
+        self.stats.misses += 1;
+        None
+    }
+
+    /// Inserts or updates the entry and returns the old value
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if self.capacity == 0 {
+            return None;
+        }
+        if self.recent.remove(&key) || self.frequent.remove(&key) {
+            self.frequent.insert(key.clone());
+            let old = self.values.insert(key, value);
+            check_invariants!(self);
+            return old;
+        }
+
+        if self.recent_ghosts.contains(&key) {
+            let step = (self.frequent_ghosts.len() / self.recent_ghosts.len()).max(1);
+            self.target = (self.target + step).min(self.capacity);
+            self.replace(false);
+            self.recent_ghosts.remove(&key);
+            self.frequent.insert(key.clone());
+        } else if self.frequent_ghosts.contains(&key) {
+            let step = (self.recent_ghosts.len() / self.frequent_ghosts.len()).max(1);
+            self.target = self.target.saturating_sub(step);
+            self.replace(true);
+            self.frequent_ghosts.remove(&key);
+            self.frequent.insert(key.clone());
+        } else {
+            let recent_side = self.recent.len() + self.recent_ghosts.len();
+            let total = recent_side + self.frequent.len() + self.frequent_ghosts.len();
+            if recent_side == self.capacity {
+                if self.recent.len() < self.capacity {
+                    self.recent_ghosts.pop_front();
+                    self.replace(false);
+                } else {
+                    let evicted = self.recent.pop_front().unwrap();
+                    self.values.remove(&evicted);
+                }
+            } else if total >= self.capacity {
+                if total == 2 * self.capacity {
+                    self.frequent_ghosts.pop_front();
+                }
+                self.replace(false);
+            }
+            self.recent.insert(key.clone());
+        }
+        self.values.insert(key, value);
+        check_invariants!(self);
+        None
+    }
+
+    /// Evicts one resident entry into its ghost list once the cache is full.
+    /// T1 gives up its oldest entry when it is larger than the target.
+    fn replace(&mut self, hit_frequent_ghost: bool) {
+        if self.values.len() < self.capacity {
+            return;
+        }
+        let recent_len = self.recent.len();
+        let from_recent = recent_len > 0 &&
+            (recent_len > self.target ||
+                (hit_frequent_ghost && recent_len == self.target) ||
+                self.frequent.is_empty());
+        let (list, ghosts) = if from_recent {
+            (&mut self.recent, &mut self.recent_ghosts)
+        } else {
+            (&mut self.frequent, &mut self.frequent_ghosts)
+        };
+        let evicted = list.pop_front().unwrap();
+        self.values.remove(&evicted);
+        ghosts.insert(evicted);
+    }
+}
+
+/// Checks T1 and T2 hold exactly the cached keys, the ghosts hold none of
+/// them, and the lists stay within the sizes ARC bounds them by
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Hash + Eq + Clone, V> Invariants for ArcCache<K, V> {
+    fn assert_invariants(&self) {
+        let resident = self.recent.len() + self.frequent.len();
+        assert_eq!(resident, self.values.len(), "T1 and T2 don't match the values");
+        assert!(self.values.len() <= self.capacity, "More entries than capacity");
+        for key in self.recent.iter() {
+            assert!(self.values.contains_key(key), "Key in T1 has no value");
+            assert!(!self.frequent.contains(key), "Key is in T1 and T2");
+        }
+        for key in self.frequent.iter() {
+            assert!(self.values.contains_key(key), "Key in T2 has no value");
+        }
+        for key in self.recent_ghosts.iter().chain(self.frequent_ghosts.iter()) {
+            assert!(!self.values.contains_key(key), "Ghost key is still cached");
+        }
+        let recent_side = self.recent.len() + self.recent_ghosts.len();
+        let total = recent_side + self.frequent.len() + self.frequent_ghosts.len();
+        assert!(recent_side <= self.capacity, "T1 and B1 hold more than capacity");
+        assert!(total <= 2 * self.capacity, "Lists hold more than twice the capacity");
+        assert!(self.target <= self.capacity, "Target is larger than capacity");
+    }
+}
+
+/// The cached values and the keys of all four lists, ghosts included
+impl<K: HeapSize, V: HeapSize> HeapSize for ArcCache<K, V> {
+    fn heap_size(&self) -> usize {
+        let lists = [&self.recent, &self.frequent, &self.recent_ghosts, &self.frequent_ghosts];
+        self.values.heap_size() + lists.iter().map(|list| list.heap_size()).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads the key and stores it on a miss, the usual way a cache is used
+    fn access(cache: &mut ArcCache<u64, u64>, key: u64) {
+        if cache.get(&key).is_none() {
+            cache.put(key, key * 10);
+        }
+    }
+
+    #[test]
+    fn when_cache_is_created_it_is_empty() {
+        let cache = ArcCache::<u32, u32>::new(4);
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats(), CacheStats::default());
+        assert_eq!(cache.stats().hit_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_put_get_and_stats() {
+        let mut cache = ArcCache::new(2);
+
+        assert_eq!(cache.put("a", 1), None);
+        assert_eq!(cache.put("a", 2), Some(1));
+        assert_eq!(cache.get(&"a"), Some(&2));
+        assert_eq!(cache.get(&"b"), None);
+
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+        assert_eq!(cache.stats().hit_ratio(), 0.5);
+        cache.reset_stats();
+        assert_eq!(cache.stats().hits, 0);
+    }
+
+    #[test]
+    fn test_never_holds_more_than_capacity() {
+        let mut cache = ArcCache::new(8);
+        let mut state = 3u64;
+
+        for _ in 0..5000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            access(&mut cache, (state >> 33) % 30);
+
+            assert!(cache.len() <= 8);
+            assert_eq!(cache.len(), cache.recent.len() + cache.frequent.len());
+            assert!(cache.recent_ghosts.len() + cache.frequent_ghosts.len() <= 8);
+            assert!(cache.target_recent_size() <= 8);
+        }
+    }
+
+    #[test]
+    fn test_values_match_last_put() {
+        let mut cache = ArcCache::new(4);
+        let mut state = 17u64;
+        let mut latest = HashMap::new();
+
+        for time in 0..3000u64 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (state >> 33) % 10;
+            if (state >> 20).is_multiple_of(2) {
+                cache.put(key, time);
+                latest.insert(key, time);
+            } else if let Some(&value) = cache.get(&key) {
+                assert_eq!(value, latest[&key]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frequent_entries_survive_a_scan() {
+        let mut cache = ArcCache::new(4);
+        for _ in 0..3 {
+            access(&mut cache, 1);
+            access(&mut cache, 2);
+        }
+
+        for key in 100..120 {
+            access(&mut cache, key);
+        }
+
+        assert!(cache.contains_key(&1));
+        assert!(cache.contains_key(&2));
+    }
+
+    #[test]
+    fn test_ghost_hits_adapt_target() {
+        let mut cache = ArcCache::new(4);
+        for key in [0, 0, 1, 1, 2, 3, 4] {
+            access(&mut cache, key);
+        }
+        assert!(cache.recent_ghosts.contains(&2));
+        assert_eq!(cache.target_recent_size(), 0);
+
+        access(&mut cache, 2);
+
+        assert_eq!(cache.target_recent_size(), 1, "Hit in B1 grows the recency target");
+        assert!(cache.contains_key(&2));
+    }
+
+    #[test]
+    fn test_zero_capacity_stores_nothing() {
+        let mut cache = ArcCache::new(0);
+
+        cache.put(1, 1);
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&1), None);
+    }
+}
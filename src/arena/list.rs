@@ -0,0 +1,137 @@
+//! Singly linked list with its nodes in an arena
+//! Works like a stack: items are added and removed at the front. Nodes are
+//! never freed one by one, a popped node stays in the arena until the arena
+//! is dropped, so `pop_front` hands out a reference to the item.
+
+use super::Arena;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+pub struct ArenaNode<'a, T> {
+    item: T,
+    next: Option<&'a mut ArenaNode<'a, T>>,
+}
+
+pub struct ArenaList<'a, T> {
+    arena: &'a Arena<ArenaNode<'a, T>>,
+    head: Option<&'a mut ArenaNode<'a, T>>,
+    len: usize,
+}
+
+impl<'a, T> ArenaList<'a, T> {
+    /// Creates a list that allocates its nodes in the arena
+    pub fn new_in(arena: &'a Arena<ArenaNode<'a, T>>) -> Self {
+        ArenaList { arena, head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn push_front(&mut self, item: T) {
+        let node = self.arena.alloc(ArenaNode { item, next: self.head.take() });
+        self.head = Some(node);
+        self.len += 1;
+        check_invariants!(self);
+    }
+
+    pub fn pop_front(&mut self) -> Option<&'a mut T> {
+        let node = self.head.take()?;
+        self.head = node.next.take();
+        self.len -= 1;
+        check_invariants!(self);
+        Some(&mut node.item)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.item)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.item)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + use<'_, 'a, T> {
+        let mut current = self.head.as_deref();
+        std::iter::from_fn(move || {
+            let node = current?;
+            current = node.next.as_deref();
+            Some(&node.item)
+        })
+    }
+}
+
+impl<T: HeapSize> HeapSize for ArenaNode<'_, T> {
+    fn heap_size(&self) -> usize {
+        self.item.heap_size()
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for ArenaList<'_, T> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.iter().count(), self.len, "len doesn't match the nodes");
+    }
+}
+
+/// The whole arena the list allocates from, which other lists may share
+impl<T: HeapSize> HeapSize for ArenaList<'_, T> {
+    fn heap_size(&self) -> usize {
+        self.arena.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_list_is_created_it_is_empty() {
+        let arena = Arena::new();
+        let list = ArenaList::<i32>::new_in(&arena);
+
+        assert!(list.is_empty());
+        assert_eq!(list.front(), None);
+    }
+
+    #[test]
+    fn test_push_and_pop_front() {
+        let arena = Arena::new();
+        let mut list = ArenaList::new_in(&arena);
+
+        for item in 1..=3 {
+            list.push_front(item);
+        }
+        *list.front_mut().unwrap() *= 10;
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![30, 2, 1]);
+        assert_eq!(list.pop_front(), Some(&mut 30));
+        assert_eq!(list.len(), 2);
+        assert_eq!(arena.len(), 3, "Popped nodes stay in the arena");
+    }
+
+    #[test]
+    fn test_lists_can_share_an_arena() {
+        let arena = Arena::with_capacity(4);
+        let mut even = ArenaList::new_in(&arena);
+        let mut odd = ArenaList::new_in(&arena);
+
+        for item in 0..10 {
+            if item % 2 == 0 {
+                even.push_front(item);
+            } else {
+                odd.push_front(item);
+            }
+        }
+
+        assert_eq!(even.iter().copied().collect::<Vec<_>>(), vec![8, 6, 4, 2, 0]);
+        assert_eq!(odd.iter().copied().collect::<Vec<_>>(), vec![9, 7, 5, 3, 1]);
+        assert_eq!(arena.len(), 10);
+    }
+}
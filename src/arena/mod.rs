@@ -0,0 +1,127 @@
+//! Typed arena allocator
+//! Values are moved into chunks of memory that are never reallocated, so a
+//! reference to an allocated value stays valid for as long as the arena
+//! lives. Allocating is a push onto the last chunk, O(1), and every value is
+//! dropped at once together with the arena. When a chunk is full a new one
+//! twice its size is started, so there are only O(log n) allocations.
+//! The list and tree in this module keep their nodes in an arena instead of
+//! allocating a Box per node.
+
+mod list;
+mod tree;
+
+use std::cell::RefCell;
+
+use crate::heap_size::HeapSize;
+
+pub use list::ArenaList;
+pub use tree::ArenaTreeMap;
+
+const DEFAULT_CHUNK_CAPACITY: usize = 16;
+
+pub struct Arena<T> {
+    chunks: RefCell<Vec<Vec<T>>>,
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Arena::with_capacity(DEFAULT_CHUNK_CAPACITY)
+    }
+
+    /// Creates an arena whose first chunk fits `capacity` values
+    pub fn with_capacity(capacity: usize) -> Self {
+        Arena { chunks: RefCell::new(vec![Vec::with_capacity(capacity.max(1))]) }
+    }
+
+    /// Moves the value into the arena and returns a reference to it
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc(&self, value: T) -> &mut T {
+        let mut chunks = self.chunks.borrow_mut();
+        let last = chunks.last().unwrap();
+        if last.len() == last.capacity() {
+            let capacity = last.capacity() * 2;
+            chunks.push(Vec::with_capacity(capacity));
+        }
+        let chunk = chunks.last_mut().unwrap();
+        let len = chunk.len();
+        // SAFETY: The chunk has room for one more, so writing past its length
+        // stays in the buffer, which is never reallocated: a full chunk is
+        // never written to again and growing `chunks` only moves the Vec
+        // headers. The slot is reached through `as_mut_ptr`, which doesn't
+        // form a slice over the items, so references handed out earlier stay
+        // valid. Every value is handed out exactly once, so the reference is
+        // unique, and the items are dropped together with the arena.
+        unsafe {
+            let slot = chunk.as_mut_ptr().add(len);
+            slot.write(value);
+            chunk.set_len(len + 1);
+            &mut *slot
+        }
+    }
+
+    /// Number of values allocated so far
+    pub fn len(&self) -> usize {
+        self.chunks.borrow().iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Arena<T> {
+    fn heap_size(&self) -> usize {
+        self.chunks.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn when_arena_is_created_it_is_empty() {
+        let arena = Arena::<i32>::new();
+
+        assert!(arena.is_empty());
+        assert_eq!(arena.chunk_count(), 1);
+    }
+
+    #[test]
+    fn test_references_stay_valid_while_chunks_grow() {
+        let arena = Arena::with_capacity(2);
+
+        let mut values: Vec<&mut usize> = (0..100).map(|i| arena.alloc(i)).collect();
+        *values[0] += 1000;
+
+        assert_eq!(arena.len(), 100);
+        assert_eq!(arena.chunk_count(), 6, "Chunks of 2, 4, ..., 64");
+        assert_eq!(*values[0], 1000);
+        assert!(values.iter().skip(1).enumerate().all(|(i, value)| **value == i + 1));
+    }
+
+    #[test]
+    fn test_values_are_dropped_with_the_arena() {
+        let counter = Rc::new(());
+        let arena = Arena::new();
+
+        for _ in 0..50 {
+            arena.alloc(counter.clone());
+        }
+        assert_eq!(Rc::strong_count(&counter), 51);
+        drop(arena);
+
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+}
@@ -0,0 +1,227 @@
+//! AVL tree sorted map with its nodes in an arena
+//! The same balancing as `AvlTreeMap`, but nodes are allocated in an arena
+//! and linked with references instead of boxes, so building a large tree
+//! makes only a handful of allocations. There is no removal: memory is only
+//! given back when the arena is dropped.
+
+use super::Arena;
+use std::cmp::Ordering;
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+type Tree<'a, K, V> = Option<&'a mut ArenaTreeNode<'a, K, V>>;
+
+pub struct ArenaTreeNode<'a, K, V> {
+    key: K,
+    value: V,
+    height: usize,
+    left: Tree<'a, K, V>,
+    right: Tree<'a, K, V>,
+}
+
+pub struct ArenaTreeMap<'a, K, V> {
+    arena: &'a Arena<ArenaTreeNode<'a, K, V>>,
+    root: Tree<'a, K, V>,
+    size: usize,
+}
+
+impl<'a, K: Ord, V> ArenaTreeMap<'a, K, V> {
+    /// Creates a map that allocates its nodes in the arena
+    pub fn new_in(arena: &'a Arena<ArenaTreeNode<'a, K, V>>) -> Self {
+        ArenaTreeMap { arena, root: None, size: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Height of the tree, 0 when it is empty
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    /// Inserts the pair. Returns the old value if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old_value = insert(self.arena, &mut self.root, key, value);
+        if old_value.is_none() {
+            self.size += 1;
+        }
+        check_invariants!(self);
+        old_value
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            current = match key.cmp(&node.key) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Pairs in ascending key order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + use<'_, 'a, K, V> {
+        let mut stack: Vec<&ArenaTreeNode<'a, K, V>> = vec![];
+        let mut current = self.root.as_deref();
+        std::iter::from_fn(move || {
+            while let Some(node) = current {
+                stack.push(node);
+                current = node.left.as_deref();
+            }
+            let node = stack.pop()?;
+            current = node.right.as_deref();
+            Some((&node.key, &node.value))
+        })
+    }
+}
+
+/// Checks the keys ascend, every height is right and the subtrees differ in
+/// height by at most one, and the size matches the nodes
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Ord, V> Invariants for ArenaTreeMap<'_, K, V> {
+    fn assert_invariants(&self) {
+        assert_eq!(assert_balanced(&self.root), self.size, "size doesn't match the nodes");
+        let keys: Vec<&K> = self.iter().map(|(key, _)| key).collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]), "Keys are out of order");
+    }
+}
+
+/// Number of nodes in the tree
+#[cfg(any(test, feature = "debug-invariants"))]
+fn assert_balanced<K, V>(tree: &Tree<'_, K, V>) -> usize {
+    let Some(node) = tree else {
+        return 0;
+    };
+    let (left, right) = (height(&node.left), height(&node.right));
+    assert_eq!(node.height, 1 + left.max(right), "Height is stale");
+    assert!(left.abs_diff(right) <= 1, "Node is out of balance");
+    1 + assert_balanced(&node.left) + assert_balanced(&node.right)
+}
+
+fn height<K, V>(tree: &Tree<'_, K, V>) -> usize {
+    tree.as_ref().map_or(0, |node| node.height)
+}
+
+fn update_height<K, V>(node: &mut ArenaTreeNode<'_, K, V>) {
+    node.height = 1 + height(&node.left).max(height(&node.right));
+}
+
+fn rotate_right<K, V>(tree: &mut Tree<'_, K, V>) {
+    let node = tree.take().unwrap();
+    let left = node.left.take().unwrap();
+    node.left = left.right.take();
+    update_height(node);
+    left.right = Some(node);
+    update_height(left);
+    *tree = Some(left);
+}
+
+fn rotate_left<K, V>(tree: &mut Tree<'_, K, V>) {
+    let node = tree.take().unwrap();
+    let right = node.right.take().unwrap();
+    node.right = right.left.take();
+    update_height(node);
+    right.left = Some(node);
+    update_height(right);
+    *tree = Some(right);
+}
+
+/// Restores the height difference of at most one at the root of `tree`
+fn rebalance<K, V>(tree: &mut Tree<'_, K, V>) {
+    let Some(node) = tree.as_mut() else {
+        return;
+    };
+    update_height(node);
+    let (left, right) = (height(&node.left), height(&node.right));
+    if left > right + 1 {
+        let child = node.left.as_ref().unwrap();
+        if height(&child.left) < height(&child.right) {
+            rotate_left(&mut node.left);
+        }
+        rotate_right(tree);
+    } else if right > left + 1 {
+        let child = node.right.as_ref().unwrap();
+        if height(&child.right) < height(&child.left) {
+            rotate_right(&mut node.right);
+        }
+        rotate_left(tree);
+    }
+}
+
+fn insert<'a, K: Ord, V>(
+    arena: &'a Arena<ArenaTreeNode<'a, K, V>>,
+    tree: &mut Tree<'a, K, V>,
+    key: K,
+    value: V
+) -> Option<V> {
+    let Some(node) = tree else {
+        *tree = Some(arena.alloc(ArenaTreeNode { key, value, height: 1, left: None, right: None }));
+        return None;
+    };
+    let old_value = match key.cmp(&node.key) {
+        Ordering::Less => insert(arena, &mut node.left, key, value),
+        Ordering::Greater => insert(arena, &mut node.right, key, value),
+        Ordering::Equal => {
+            return Some(std::mem::replace(&mut node.value, value));
+        }
+    };
+    rebalance(tree);
+    old_value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let arena = Arena::new();
+        let tree = ArenaTreeMap::<i32, i32>::new_in(&arena);
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.iter().next(), None);
+    }
+
+    #[test]
+    fn test_insert_get_and_update() {
+        let arena = Arena::new();
+        let mut tree = ArenaTreeMap::new_in(&arena);
+
+        assert_eq!(tree.insert("b", 2), None);
+        assert_eq!(tree.insert("a", 1), None);
+        assert_eq!(tree.insert("b", 20), Some(2));
+
+        assert_eq!(tree.get(&"b"), Some(&20));
+        assert!(!tree.contains_key(&"c"));
+        assert_eq!(tree.len(), 2);
+        assert_eq!(arena.len(), 2);
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_balanced() {
+        let arena = Arena::new();
+        let mut tree = ArenaTreeMap::new_in(&arena);
+
+        for key in 0..1000 {
+            tree.insert(key, key * 2);
+        }
+
+        assert!(tree.height() <= 14, "Height {} is too big for an AVL tree", tree.height());
+        assert!(tree.iter().map(|(key, _)| *key).eq(0..1000));
+        assert!(arena.chunk_count() <= 7);
+    }
+}
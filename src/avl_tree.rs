@@ -0,0 +1,889 @@
+//! AVL Tree implementation of a sorted map
+//! Binary search tree where the heights of the two subtrees of every node
+//! differ by at most one. Each node stores its height, and after every
+//! insert or remove the nodes on the path back to the root are fixed with
+//! one or two rotations. That keeps the height below 1.44 log2(n), so
+//! lookups, inserts and removes are O(log n). Nodes are kept by a
+//! `NodeAlloc`, `Global` unless `new_in` is given another one, and link to
+//! their children by handle.
+
+use std::cmp::Ordering;
+use std::fmt::{ self, Debug };
+use std::marker::PhantomData;
+use std::ops::{ Bound, RangeBounds, RangeFull };
+
+use crate::collection::Collection;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::map::Map;
+use crate::node_alloc::{ Global, NodeAlloc, NodeHandle };
+use crate::queue::Queue;
+use crate::render::{ self, Render };
+use crate::stack::Stack;
+use crate::trace::{ self, Event, Rotation };
+use crate::viz::{ DotWriter, ToDot };
+use crate::heap_size::HeapSize;
+
+/// Tree node, only public so an allocator for it can be named
+pub struct AvlNode<K, V, H> {
+    key: K,
+    value: V,
+    height: usize,
+    left: Option<H>,
+    right: Option<H>,
+}
+
+/// Node of a tree keeping its nodes in `A`
+type Node<K, V, A> = AvlNode<K, V, <A as NodeHandle>::Handle>;
+
+/// Root of a subtree, None for an empty one
+type Tree<A> = Option<<A as NodeHandle>::Handle>;
+
+pub struct AvlTreeMap<K, V, A = Global>
+where
+    A: NodeAlloc<Node<K, V, A>>,
+{
+    nodes: A,
+    root: Tree<A>,
+    size: usize,
+    /// The map owns the nodes and their pairs, for the drop check
+    marker: PhantomData<Box<Node<K, V, A>>>,
+}
+
+impl<K: Ord, V> AvlTreeMap<K, V> {
+    pub fn new() -> Self {
+        AvlTreeMap::new_in(Global)
+    }
+}
+
+impl<K: Ord, V, A: NodeAlloc<Node<K, V, A>>> AvlTreeMap<K, V, A> {
+    /// Empty map that keeps its nodes in `nodes`
+    pub fn new_in(nodes: A) -> Self {
+        AvlTreeMap { nodes, root: None, size: 0, marker: PhantomData }
+    }
+
+    /// The allocator holding the nodes
+    pub fn nodes(&self) -> &A {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.free_nodes();
+        check_invariants!(self);
+    }
+
+    /// Height of the tree, 0 when it is empty
+    pub fn height(&self) -> usize {
+        self.height_of(self.root)
+    }
+
+    /// Inserts the pair. Returns the old value if the key was present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (root, old_value) = self.insert_into(self.root, key, value);
+        self.root = Some(root);
+        if old_value.is_none() {
+            self.size += 1;
+        }
+        check_invariants!(self);
+        old_value
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut current = self.root;
+        while let Some(node) = current {
+            let node = self.node(node);
+            current = match key.cmp(&node.key) {
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+                Ordering::Equal => {
+                    return Some(&node.value);
+                }
+            };
+        }
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (root, removed) = self.remove_from(self.root, key);
+        self.root = root;
+        // The stored key is only dropped once the size is right, so a
+        // panicking drop can't leave the two out of step
+        let (_key, value) = removed?;
+        self.size -= 1;
+        check_invariants!(self);
+        Some(value)
+    }
+
+    /// Pair with the smallest key
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.node(self.root?);
+        while let Some(left) = node.left {
+            node = self.node(left);
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Pair with the largest key
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.node(self.root?);
+        while let Some(right) = node.right {
+            node = self.node(right);
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Pair with the smallest key strictly greater than `key`
+    pub fn next_above(&self, key: &K) -> Option<(&K, &V)> {
+        let mut best = None;
+        let mut current = self.root;
+        while let Some(node) = current {
+            let node = self.node(node);
+            if node.key > *key {
+                best = Some((&node.key, &node.value));
+                current = node.left;
+            } else {
+                current = node.right;
+            }
+        }
+        best
+    }
+
+    /// Pair with the largest key strictly smaller than `key`
+    pub fn next_below(&self, key: &K) -> Option<(&K, &V)> {
+        let mut best = None;
+        let mut current = self.root;
+        while let Some(node) = current {
+            let node = self.node(node);
+            if node.key < *key {
+                best = Some((&node.key, &node.value));
+                current = node.right;
+            } else {
+                current = node.left;
+            }
+        }
+        best
+    }
+
+    /// Pairs in ascending key order
+    pub fn iter(&self) -> Range<'_, K, V, RangeFull, A> {
+        self.range(..)
+    }
+
+    /// Pairs with keys in the range, in ascending order. Finding the first
+    /// pair is O(log n), every following one is O(1) amortized.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, R, A> {
+        let mut stack = vec![];
+        let mut current = self.root;
+        while let Some(node) = current {
+            let node = self.node(node);
+            let above_start = match range.start_bound() {
+                Bound::Included(start) => node.key >= *start,
+                Bound::Excluded(start) => node.key > *start,
+                Bound::Unbounded => true,
+            };
+            if above_start {
+                stack.push(node);
+                current = node.left;
+            } else {
+                current = node.right;
+            }
+        }
+        Range { nodes: &self.nodes, stack, range }
+    }
+
+    /// Inserts into the subtree under `tree` and returns its new root
+    fn insert_into(&mut self, tree: Tree<A>, key: K, value: V) -> (A::Handle, Option<V>) {
+        let Some(node) = tree else {
+            let leaf = AvlNode { key, value, height: 1, left: None, right: None };
+            return (self.nodes.alloc(leaf), None);
+        };
+        let old_value = match key.cmp(&self.node(node).key) {
+            Ordering::Less => {
+                let (left, old_value) = self.insert_into(self.node(node).left, key, value);
+                self.node_mut(node).left = Some(left);
+                old_value
+            }
+            Ordering::Greater => {
+                let (right, old_value) = self.insert_into(self.node(node).right, key, value);
+                self.node_mut(node).right = Some(right);
+                old_value
+            }
+            Ordering::Equal => {
+                return (node, Some(std::mem::replace(&mut self.node_mut(node).value, value)));
+            }
+        };
+        (self.rebalance(node), old_value)
+    }
+
+    /// Removes from the subtree under `tree` and returns its new root with
+    /// the removed pair, the caller drops the key
+    fn remove_from(&mut self, tree: Tree<A>, key: &K) -> (Tree<A>, Option<(K, V)>) {
+        let Some(node) = tree else {
+            return (None, None);
+        };
+        let removed = match key.cmp(&self.node(node).key) {
+            Ordering::Less => {
+                let (left, removed) = self.remove_from(self.node(node).left, key);
+                self.node_mut(node).left = left;
+                removed
+            }
+            Ordering::Greater => {
+                let (right, removed) = self.remove_from(self.node(node).right, key);
+                self.node_mut(node).right = right;
+                removed
+            }
+            Ordering::Equal => match (self.node(node).left, self.node(node).right) {
+                (Some(_), Some(right)) => {
+                    // Two children: the smallest node on the right takes this place
+                    let (right, successor) = self.remove_min(right);
+                    let node = self.node_mut(node);
+                    node.right = right;
+                    let key = std::mem::replace(&mut node.key, successor.key);
+                    Some((key, std::mem::replace(&mut node.value, successor.value)))
+                }
+                (left, right) => {
+                    let node = self.free(node);
+                    return (left.or(right), Some((node.key, node.value)));
+                }
+            },
+        };
+        (Some(self.rebalance(node)), removed)
+    }
+
+    /// Unlinks the smallest node under `tree`, returns the new root and the node
+    fn remove_min(&mut self, tree: A::Handle) -> (Tree<A>, Node<K, V, A>) {
+        if let Some(left) = self.node(tree).left {
+            let (left, min) = self.remove_min(left);
+            self.node_mut(tree).left = left;
+            return (Some(self.rebalance(tree)), min);
+        }
+        let min = self.free(tree);
+        (min.right, min)
+    }
+}
+
+impl<K, V, A: NodeAlloc<Node<K, V, A>>> AvlTreeMap<K, V, A> {
+    /// Pairs in ascending key order, left subtree, node, right subtree
+    pub fn in_order(&self) -> InOrder<'_, K, V, A> {
+        let mut traversal = InOrder { nodes: &self.nodes, stack: Stack::new() };
+        traversal.push_left_spine(self.root);
+        traversal
+    }
+
+    /// Pairs with every node before its subtrees, left subtree first
+    pub fn pre_order(&self) -> PreOrder<'_, K, V, A> {
+        let mut stack = Stack::new();
+        if let Some(root) = self.root {
+            stack.push(self.node(root));
+        }
+        PreOrder { nodes: &self.nodes, stack }
+    }
+
+    /// Pairs with every node after its subtrees, left subtree first
+    pub fn post_order(&self) -> PostOrder<'_, K, V, A> {
+        let mut stack = Stack::new();
+        if let Some(root) = self.root {
+            stack.push((self.node(root), false));
+        }
+        PostOrder { nodes: &self.nodes, stack }
+    }
+
+    /// Pairs level by level from the root, left to right within a level
+    pub fn level_order(&self) -> LevelOrder<'_, K, V, A> {
+        let mut queue = Queue::new();
+        if let Some(root) = self.root {
+            queue.add(self.node(root));
+        }
+        LevelOrder { nodes: &self.nodes, queue }
+    }
+
+    /// Outline of the tree with every node labelled by `label`
+    pub(crate) fn render_with(&self, label: impl Fn(&K, &V) -> String) -> String {
+        render::render_tree(
+            self.root.map(|root| self.node(root)),
+            &|node: &Node<K, V, A>| label(&node.key, &node.value),
+            &|node: &Node<K, V, A>| self.children(node)
+        )
+    }
+
+    /// `handle` has to be linked into the tree
+    fn node(&self, handle: A::Handle) -> &Node<K, V, A> {
+        linked(&self.nodes, handle)
+    }
+
+    /// `handle` has to be linked into the tree
+    fn node_mut(&mut self, handle: A::Handle) -> &mut Node<K, V, A> {
+        // SAFETY: As in `linked`
+        unsafe { self.nodes.get_mut(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// `handle` has to be linked into the tree, and is unlinked by the caller
+    fn free(&mut self, handle: A::Handle) -> Node<K, V, A> {
+        // SAFETY: As in `linked`
+        unsafe { self.nodes.free(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// Frees the nodes in a loop through the allocator, which may not own them
+    fn free_nodes(&mut self) {
+        let mut pending: Vec<A::Handle> = self.root.take().into_iter().collect();
+        while let Some(handle) = pending.pop() {
+            let node = self.free(handle);
+            pending.extend(node.left.into_iter().chain(node.right));
+        }
+        self.size = 0;
+    }
+
+    fn children(&self, node: &Node<K, V, A>) -> [Option<&Node<K, V, A>>; 2] {
+        [node.left, node.right].map(|child| child.map(|child| self.node(child)))
+    }
+
+    fn height_of(&self, tree: Tree<A>) -> usize {
+        tree.map_or(0, |node| self.node(node).height)
+    }
+
+    fn update_height(&mut self, node: A::Handle) {
+        let AvlNode { left, right, .. } = *self.node(node);
+        self.node_mut(node).height = 1 + self.height_of(left).max(self.height_of(right));
+    }
+
+    /// Rotates the subtree under `node` and returns its new root
+    fn rotate_right(&mut self, node: A::Handle) -> A::Handle {
+        let left = self.node(node).left.unwrap();
+        self.node_mut(node).left = self.node(left).right;
+        self.update_height(node);
+        self.node_mut(left).right = Some(node);
+        self.update_height(left);
+        let height = self.node(left).height;
+        trace::emit(|| Event::Rotated { rotation: Rotation::Right, height });
+        left
+    }
+
+    fn rotate_left(&mut self, node: A::Handle) -> A::Handle {
+        let right = self.node(node).right.unwrap();
+        self.node_mut(node).right = self.node(right).left;
+        self.update_height(node);
+        self.node_mut(right).left = Some(node);
+        self.update_height(right);
+        let height = self.node(right).height;
+        trace::emit(|| Event::Rotated { rotation: Rotation::Left, height });
+        right
+    }
+
+    /// Restores the height difference of at most one at `node` and returns
+    /// the new root of its subtree
+    fn rebalance(&mut self, node: A::Handle) -> A::Handle {
+        self.update_height(node);
+        let AvlNode { left, right, .. } = *self.node(node);
+        if self.height_of(left) > self.height_of(right) + 1 {
+            let child = self.node(left.unwrap());
+            // Left-right case needs the child rotated first
+            if self.height_of(child.left) < self.height_of(child.right) {
+                let left = self.rotate_left(left.unwrap());
+                self.node_mut(node).left = Some(left);
+            }
+            self.rotate_right(node)
+        } else if self.height_of(right) > self.height_of(left) + 1 {
+            let child = self.node(right.unwrap());
+            if self.height_of(child.right) < self.height_of(child.left) {
+                let right = self.rotate_right(right.unwrap());
+                self.node_mut(node).right = Some(right);
+            }
+            self.rotate_left(node)
+        } else {
+            node
+        }
+    }
+}
+
+/// `handle` has to be linked into the tree whose nodes are `nodes`
+fn linked<K, V, A: NodeAlloc<Node<K, V, A>>>(nodes: &A, handle: A::Handle) -> &Node<K, V, A> {
+    // SAFETY: The tree only links nodes it allocated and hasn't freed
+    unsafe { nodes.get(handle) }.expect("Linked nodes are never freed")
+}
+
+impl<K, V, A> Map<K, V> for AvlTreeMap<K, V, A>
+where
+    K: Ord + Clone,
+    V: Clone,
+    A: NodeAlloc<Node<K, V, A>>,
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        AvlTreeMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        AvlTreeMap::get(self, key).cloned()
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        AvlTreeMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        AvlTreeMap::iter(self).map(|(key, value)| (key.clone(), value.clone()))
+    }
+}
+
+impl<K: Ord, V, A: NodeAlloc<Node<K, V, A>> + Default> Default for AvlTreeMap<K, V, A> {
+    fn default() -> Self {
+        AvlTreeMap::new_in(A::default())
+    }
+}
+
+impl<K, V, A: NodeAlloc<Node<K, V, A>>> Drop for AvlTreeMap<K, V, A> {
+    fn drop(&mut self) {
+        self.free_nodes();
+    }
+}
+
+/// The items of a map are its keys
+impl<K: Ord, V, A: NodeAlloc<Node<K, V, A>>> Collection for AvlTreeMap<K, V, A> {
+    type Item = K;
+
+    fn len(&self) -> usize {
+        AvlTreeMap::len(self)
+    }
+
+    fn clear(&mut self) {
+        AvlTreeMap::clear(self);
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.contains_key(key)
+    }
+}
+
+/// Copies node by node into a new allocator, keeping the shape of the tree
+impl<K, V, A> Clone for AvlTreeMap<K, V, A>
+where
+    K: Clone,
+    V: Clone,
+    A: NodeAlloc<Node<K, V, A>> + Default,
+{
+    fn clone(&self) -> Self {
+        let mut nodes = A::default();
+        let root = self.root.map(|root| copy_subtree(&self.nodes, root, &mut nodes));
+        AvlTreeMap { nodes, root, size: self.size, marker: PhantomData }
+    }
+}
+
+/// Copies the subtree under `tree` from `from` to `to`, returns the copy's root
+fn copy_subtree<K, V, A>(from: &A, tree: A::Handle, to: &mut A) -> A::Handle
+where
+    K: Clone,
+    V: Clone,
+    A: NodeAlloc<Node<K, V, A>>,
+{
+    let node = linked(from, tree);
+    let left = node.left.map(|left| copy_subtree(from, left, to));
+    let right = node.right.map(|right| copy_subtree(from, right, to));
+    let (key, value) = (node.key.clone(), node.value.clone());
+    to.alloc(AvlNode { key, value, height: node.height, left, right })
+}
+
+/// Equal if the pairs are, whatever the shape of the two trees
+impl<K: PartialEq, V: PartialEq, A: NodeAlloc<Node<K, V, A>>> PartialEq for AvlTreeMap<K, V, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.in_order().eq(other.in_order())
+    }
+}
+
+/// Formats the pairs like a map, in key order
+impl<K: Debug, V: Debug, A: NodeAlloc<Node<K, V, A>>> Debug for AvlTreeMap<K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.in_order()).finish()
+    }
+}
+
+/// Iterator over the pairs of a key range
+pub struct Range<'a, K, V, R, A: NodeAlloc<Node<K, V, A>> = Global> {
+    nodes: &'a A,
+    /// Nodes still to visit, the next one on top
+    stack: Vec<&'a Node<K, V, A>>,
+    range: R,
+}
+
+impl<'a, K, V, R, A> Iterator for Range<'a, K, V, R, A>
+where
+    K: Ord,
+    R: RangeBounds<K>,
+    A: NodeAlloc<Node<K, V, A>>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let below_end = match self.range.end_bound() {
+            Bound::Included(end) => node.key <= *end,
+            Bound::Excluded(end) => node.key < *end,
+            Bound::Unbounded => true,
+        };
+        if !below_end {
+            self.stack.clear();
+            return None;
+        }
+        let mut current = node.right;
+        while let Some(child) = current {
+            let child = linked(self.nodes, child);
+            self.stack.push(child);
+            current = child.left;
+        }
+        Some((&node.key, &node.value))
+    }
+}
+
+/// In-order traversal, keeps the nodes whose right subtree is still to come
+pub struct InOrder<'a, K, V, A: NodeAlloc<Node<K, V, A>> = Global> {
+    nodes: &'a A,
+    stack: Stack<&'a Node<K, V, A>>,
+}
+
+impl<'a, K, V, A: NodeAlloc<Node<K, V, A>>> InOrder<'a, K, V, A> {
+    fn push_left_spine(&mut self, mut tree: Tree<A>) {
+        while let Some(node) = tree {
+            let node = linked(self.nodes, node);
+            self.stack.push(node);
+            tree = node.left;
+        }
+    }
+}
+
+impl<'a, K, V, A: NodeAlloc<Node<K, V, A>>> Iterator for InOrder<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right);
+        Some((&node.key, &node.value))
+    }
+}
+
+pub struct PreOrder<'a, K, V, A: NodeAlloc<Node<K, V, A>> = Global> {
+    nodes: &'a A,
+    stack: Stack<&'a Node<K, V, A>>,
+}
+
+impl<'a, K, V, A: NodeAlloc<Node<K, V, A>>> Iterator for PreOrder<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        // Right goes in first so the left subtree comes out first
+        for child in [node.right, node.left].into_iter().flatten() {
+            self.stack.push(linked(self.nodes, child));
+        }
+        Some((&node.key, &node.value))
+    }
+}
+
+pub struct PostOrder<'a, K, V, A: NodeAlloc<Node<K, V, A>> = Global> {
+    nodes: &'a A,
+    /// Nodes with whether their children were already pushed above them
+    stack: Stack<(&'a Node<K, V, A>, bool)>,
+}
+
+impl<'a, K, V, A: NodeAlloc<Node<K, V, A>>> Iterator for PostOrder<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, children_pushed) = self.stack.pop()?;
+            if children_pushed {
+                return Some((&node.key, &node.value));
+            }
+            self.stack.push((node, true));
+            for child in [node.right, node.left].into_iter().flatten() {
+                self.stack.push((linked(self.nodes, child), false));
+            }
+        }
+    }
+}
+
+pub struct LevelOrder<'a, K, V, A: NodeAlloc<Node<K, V, A>> = Global> {
+    nodes: &'a A,
+    queue: Queue<&'a Node<K, V, A>>,
+}
+
+impl<'a, K, V, A: NodeAlloc<Node<K, V, A>>> Iterator for LevelOrder<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.remove()?;
+        for child in [node.left, node.right].into_iter().flatten() {
+            self.queue.add(linked(self.nodes, child));
+        }
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K: Debug, V: Debug, A: NodeAlloc<Node<K, V, A>>> ToDot for AvlTreeMap<K, V, A> {
+    fn to_dot(&self) -> String {
+        let mut dot = DotWriter::new(true, "tree", &["node [shape=ellipse]"]);
+        let mut next_id = 0;
+        let mut stack = vec![];
+        if let Some(root) = self.root {
+            stack.push((self.node(root), next_id));
+            next_id += 1;
+        }
+        while let Some((node, id)) = stack.pop() {
+            dot.node(id, &format!("{:?}: {:?}", node.key, node.value));
+            if node.left.is_none() && node.right.is_none() {
+                continue;
+            }
+            for child in self.children(node) {
+                let child_id = next_id;
+                next_id += 1;
+                match child {
+                    Some(child) => {
+                        dot.edge(id, child_id, None);
+                        stack.push((child, child_id));
+                    }
+                    None => {
+                        dot.point(child_id);
+                        dot.invisible_edge(id, child_id);
+                    }
+                }
+            }
+        }
+        dot.finish()
+    }
+}
+
+impl<K: Debug, V: Debug, A: NodeAlloc<Node<K, V, A>>> Render for AvlTreeMap<K, V, A> {
+    fn render(&self) -> String {
+        self.render_with(|key, value| format!("{:?}: {:?}", key, value))
+    }
+}
+
+/// Checks the subtree and returns its height and number of nodes
+#[cfg(any(test, feature = "debug-invariants"))]
+fn check_subtree<K, V, A: NodeAlloc<Node<K, V, A>>>(nodes: &A, tree: Tree<A>) -> (usize, usize) {
+    let Some(node) = tree else {
+        return (0, 0);
+    };
+    let node = linked(nodes, node);
+    let (left_height, left_nodes) = check_subtree(nodes, node.left);
+    let (right_height, right_nodes) = check_subtree(nodes, node.right);
+    assert_eq!(node.height, 1 + left_height.max(right_height), "stored height is stale");
+    assert!(left_height.abs_diff(right_height) <= 1, "subtree heights differ by more than one");
+    (node.height, 1 + left_nodes + right_nodes)
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Ord, V, A: NodeAlloc<Node<K, V, A>>> Invariants for AvlTreeMap<K, V, A> {
+    fn assert_invariants(&self) {
+        let (_, nodes) = check_subtree(&self.nodes, self.root);
+        assert_eq!(self.size, nodes, "size doesn't match the nodes");
+        // Ascending in order means every left subtree is smaller and every
+        // right subtree larger than the node between them
+        let keys: Vec<&K> = self.in_order().map(|(key, _)| key).collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]), "keys are out of order");
+    }
+}
+
+/// Every node is an allocation of its own
+impl<K: HeapSize, V: HeapSize> HeapSize for AvlTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        let node_size = std::mem::size_of::<Node<K, V, Global>>();
+        self.in_order().map(|(key, value)| node_size + key.heap_size() + value.heap_size()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// Checks ordering, stored heights and balance, returns the height
+    fn assert_valid<K: Ord, V>(
+        tree: &AvlTreeMap<K, V>,
+        subtree: Tree<Global>,
+        min: Option<&K>,
+        max: Option<&K>
+    ) -> usize {
+        let Some(node) = subtree else {
+            return 0;
+        };
+        let node = tree.node(node);
+        assert!(min.is_none_or(|min| node.key > *min));
+        assert!(max.is_none_or(|max| node.key < *max));
+        let left = assert_valid(tree, node.left, min, Some(&node.key));
+        let right = assert_valid(tree, node.right, Some(&node.key), max);
+        assert!(left.abs_diff(right) <= 1);
+        assert_eq!(node.height, 1 + left.max(right));
+        node.height
+    }
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let tree = AvlTreeMap::<i32, i32>::new();
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.first(), None);
+        assert_eq!(tree.iter().next(), None);
+    }
+
+    #[test]
+    fn test_insert_get_and_update() {
+        let mut tree = AvlTreeMap::new();
+
+        assert_eq!(tree.insert("b", 2), None);
+        assert_eq!(tree.insert("a", 1), None);
+        assert_eq!(tree.insert("b", 20), Some(2));
+
+        assert_eq!(tree.get(&"b"), Some(&20));
+        assert_eq!(tree.get(&"c"), None);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_balanced() {
+        let mut tree = AvlTreeMap::new();
+
+        for key in 0..1023 {
+            tree.insert(key, ());
+        }
+
+        assert_eq!(tree.height(), 10);
+        assert_valid(&tree, tree.root, None, None);
+    }
+
+    #[test]
+    fn test_remove_node_with_two_children() {
+        let mut tree = AvlTreeMap::new();
+        for key in [4, 2, 6, 1, 3, 5, 7] {
+            tree.insert(key, key * 10);
+        }
+
+        assert_eq!(tree.remove(&4), Some(40));
+        assert_eq!(tree.remove(&4), None);
+
+        assert_eq!(tree.node(tree.root.unwrap()).key, 5);
+        assert_eq!(tree.iter().map(|(&k, _)| k).collect::<Vec<_>>(), vec![1, 2, 3, 5, 6, 7]);
+        assert_valid(&tree, tree.root, None, None);
+    }
+
+    #[test]
+    fn test_traversal_orders() {
+        let mut tree = AvlTreeMap::new();
+        // Sorted inserts rotate into the perfect tree 4 / 2 6 / 1 3 5 7
+        for key in 1..=7 {
+            tree.insert(key, key * 10);
+        }
+        let keys = |pairs: &mut dyn Iterator<Item = (&i32, &i32)>| {
+            pairs.map(|(&k, _)| k).collect::<Vec<i32>>()
+        };
+
+        assert_eq!(keys(&mut tree.in_order()), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(keys(&mut tree.pre_order()), vec![4, 2, 1, 3, 6, 5, 7]);
+        assert_eq!(keys(&mut tree.post_order()), vec![1, 3, 2, 5, 7, 6, 4]);
+        assert_eq!(keys(&mut tree.level_order()), vec![4, 2, 6, 1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn test_traversals_of_empty_tree() {
+        let tree = AvlTreeMap::<i32, i32>::new();
+
+        assert_eq!(tree.in_order().next(), None);
+        assert_eq!(tree.pre_order().next(), None);
+        assert_eq!(tree.post_order().next(), None);
+        assert_eq!(tree.level_order().next(), None);
+    }
+
+    #[test]
+    fn test_traversals_compose_with_iterator_adapters() {
+        let mut tree = AvlTreeMap::new();
+        for key in 0..100 {
+            tree.insert(key, key % 7);
+        }
+
+        let top_of_tree: Vec<i32> = tree.level_order().take(3).map(|(&k, _)| k).collect();
+        let sum_of_values: i32 = tree.post_order().map(|(_, &v)| v).sum();
+
+        assert!(tree.in_order().eq(tree.iter()));
+        assert_eq!(tree.pre_order().count(), 100);
+        assert_eq!(top_of_tree[0], tree.node(tree.root.unwrap()).key);
+        assert_eq!(sum_of_values, (0..100).map(|key| key % 7).sum::<i32>());
+    }
+
+    #[test]
+    fn test_to_dot_keeps_children_on_their_sides() {
+        let mut tree = AvlTreeMap::new();
+        for key in [2, 1, 3, 4] {
+            tree.insert(key, ());
+        }
+
+        let dot = tree.to_dot();
+
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert_eq!(dot.matches("label=").count(), 4);
+        assert!(dot.contains("n0 [label=\"2: ()\"];"));
+        assert!(dot.contains("n0 -> n1;\n    n0 -> n2;"));
+        // 3 has only a right child, an invisible point stands in for the left one
+        assert!(dot.contains("n2 [label=\"3: ()\"];\n    n3 [shape=point, style=invis];"));
+        assert!(dot.contains("n2 -> n3 [style=invis];\n    n2 -> n4;"));
+    }
+
+    #[test]
+    fn test_first_last_and_neighbors() {
+        let tree: AvlTreeMap<i32, ()> = {
+            let mut tree = AvlTreeMap::new();
+            for key in [10, 20, 30, 40] {
+                tree.insert(key, ());
+            }
+            tree
+        };
+
+        assert_eq!(tree.first().map(|(&k, _)| k), Some(10));
+        assert_eq!(tree.last().map(|(&k, _)| k), Some(40));
+        assert_eq!(tree.next_above(&20).map(|(&k, _)| k), Some(30));
+        assert_eq!(tree.next_above(&25).map(|(&k, _)| k), Some(30));
+        assert_eq!(tree.next_above(&40), None);
+        assert_eq!(tree.next_below(&20).map(|(&k, _)| k), Some(10));
+        assert_eq!(tree.next_below(&10), None);
+    }
+
+    #[test]
+    fn test_random_operations_match_btree_map() {
+        let mut tree = AvlTreeMap::new();
+        let mut expected = BTreeMap::new();
+        let mut state = 8u64;
+
+        for step in 0..3000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (state >> 33) % 200;
+            if (state >> 20).is_multiple_of(3) {
+                assert_eq!(tree.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(tree.insert(key, step), expected.insert(key, step));
+            }
+            assert_eq!(tree.len(), expected.len());
+        }
+
+        assert_valid(&tree, tree.root, None, None);
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.range(50..120).eq(expected.range(50..120)));
+        assert!(tree.range(..=30).eq(expected.range(..=30)));
+        assert!(tree
+            .range((Bound::Excluded(70), Bound::Unbounded))
+            .eq(expected.range((Bound::Excluded(70), Bound::Unbounded))));
+    }
+}
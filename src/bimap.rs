@@ -0,0 +1,267 @@
+//! Bidirectional Map implementation
+//! One-to-one map between left and right values, looked up from either side.
+//! Built on two of the crate's HashMaps, one indexing left to right and one
+//! right to left, that always hold the same pairs. Every left value and every
+//! right value belongs to at most one pair, so inserting a pair removes the
+//! pairs that used either of its values.
+
+use std::{ hash::Hash, fmt::Debug };
+
+use crate::collection::Collection;
+use crate::hash_map::HashMap;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+/// Pairs that an insert removed to keep the map one-to-one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Overwritten<L, R> {
+    /// Neither value was used
+    Neither,
+    /// The pair that used the left value
+    Left(L, R),
+    /// The pair that used the right value
+    Right(L, R),
+    /// Exactly the same pair was already present
+    Pair(L, R),
+    /// The pairs that used the left and the right value
+    Both((L, R), (L, R)),
+}
+
+#[derive(Debug, Clone)]
+pub struct BiMap<L, R> {
+    left_to_right: HashMap<L, R>,
+    right_to_left: HashMap<R, L>,
+}
+
+impl<L, R> BiMap<L, R>
+where
+    L: Hash + Clone + PartialEq + Debug,
+    R: Hash + Clone + PartialEq + Debug,
+{
+    pub fn new() -> Self {
+        BiMap { left_to_right: HashMap::new(), right_to_left: HashMap::new() }
+    }
+
+    /// Number of pairs
+    pub fn len(&self) -> usize {
+        self.left_to_right.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left_to_right.is_empty()
+    }
+
+    /// Inserts the pair, removing any pair that used either value
+    pub fn insert(&mut self, left: L, right: R) -> Overwritten<L, R> {
+        let by_left = self.remove_by_left(&left);
+        let by_right = self.remove_by_right(&right);
+        let overwritten = match (by_left, by_right) {
+            (None, None) => Overwritten::Neither,
+            (Some((l, r)), None) if r == right => Overwritten::Pair(l, r),
+            (Some((l, r)), None) => Overwritten::Left(l, r),
+            (None, Some((l, r))) => Overwritten::Right(l, r),
+            (Some(by_left), Some(by_right)) => Overwritten::Both(by_left, by_right),
+        };
+        self.left_to_right.insert(left.clone(), right.clone());
+        self.right_to_left.insert(right, left);
+        check_invariants!(self);
+        overwritten
+    }
+
+    /// Inserts the pair only if neither value is used, otherwise hands it back
+    pub fn insert_no_overwrite(&mut self, left: L, right: R) -> Result<(), (L, R)> {
+        if self.contains_left(&left) || self.contains_right(&right) {
+            return Err((left, right));
+        }
+        self.insert(left, right);
+        Ok(())
+    }
+
+    pub fn get_by_left(&self, left: &L) -> Option<R> {
+        self.left_to_right.get(left.clone())
+    }
+
+    pub fn get_by_right(&self, right: &R) -> Option<L> {
+        self.right_to_left.get(right.clone())
+    }
+
+    pub fn contains_left(&self, left: &L) -> bool {
+        self.get_by_left(left).is_some()
+    }
+
+    pub fn contains_right(&self, right: &R) -> bool {
+        self.get_by_right(right).is_some()
+    }
+
+    /// Removes the pair that uses the left value
+    pub fn remove_by_left(&mut self, left: &L) -> Option<(L, R)> {
+        let right = self.left_to_right.remove(left.clone())?;
+        let left = self.right_to_left.remove(right.clone()).unwrap();
+        check_invariants!(self);
+        Some((left, right))
+    }
+
+    /// Removes the pair that uses the right value
+    pub fn remove_by_right(&mut self, right: &R) -> Option<(L, R)> {
+        let left = self.right_to_left.remove(right.clone())?;
+        let right = self.left_to_right.remove(left.clone()).unwrap();
+        check_invariants!(self);
+        Some((left, right))
+    }
+
+    /// Pairs in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&L, &R)> {
+        self.left_to_right.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.left_to_right.clear();
+        self.right_to_left.clear();
+        check_invariants!(self);
+    }
+}
+
+/// Checks the two maps hold the same pairs
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<L, R> Invariants for BiMap<L, R>
+where
+    L: Hash + Clone + PartialEq + Debug,
+    R: Hash + Clone + PartialEq + Debug,
+{
+    fn assert_invariants(&self) {
+        let (lefts, rights) = (self.left_to_right.len(), self.right_to_left.len());
+        assert_eq!(lefts, rights, "Maps hold different numbers of pairs");
+        for (left, right) in self.left_to_right.iter() {
+            let back = self.right_to_left.get(right.clone());
+            assert_eq!(back.as_ref(), Some(left), "{:?} doesn't map back to {:?}", right, left);
+        }
+    }
+}
+
+impl<L, R> Default for BiMap<L, R>
+where
+    L: Hash + Clone + PartialEq + Debug,
+    R: Hash + Clone + PartialEq + Debug,
+{
+    fn default() -> Self {
+        BiMap::new()
+    }
+}
+
+/// The items are the left values, `contains_right` looks for the others
+impl<L, R> Collection for BiMap<L, R>
+where
+    L: Hash + Clone + PartialEq + Debug,
+    R: Hash + Clone + PartialEq + Debug,
+{
+    type Item = L;
+
+    fn len(&self) -> usize {
+        BiMap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BiMap::clear(self);
+    }
+
+    fn contains(&self, left: &L) -> bool {
+        self.contains_left(left)
+    }
+}
+
+impl<L: HeapSize, R: HeapSize> HeapSize for BiMap<L, R> {
+    fn heap_size(&self) -> usize {
+        self.left_to_right.heap_size() + self.right_to_left.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_consistent(map: &BiMap<u32, char>) {
+        for (left, right) in map.iter() {
+            assert_eq!(map.get_by_right(right), Some(*left), "Both indexes hold the pair");
+        }
+        assert_eq!(map.left_to_right.len(), map.right_to_left.len());
+    }
+
+    #[test]
+    fn when_map_is_created_it_is_empty() {
+        let map = BiMap::<u32, char>::new();
+
+        assert!(map.is_empty());
+        assert_eq!(map.get_by_left(&1), None);
+        assert_eq!(map.get_by_right(&'a'), None);
+    }
+
+    #[test]
+    fn test_lookup_from_both_sides() {
+        let mut map = BiMap::new();
+
+        assert_eq!(map.insert(1, 'a'), Overwritten::Neither);
+        assert_eq!(map.insert(2, 'b'), Overwritten::Neither);
+
+        assert_eq!(map.get_by_left(&1), Some('a'));
+        assert_eq!(map.get_by_right(&'b'), Some(2));
+        assert!(map.contains_left(&2));
+        assert!(!map.contains_right(&'c'));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_overwrites_on_either_side() {
+        let mut map = BiMap::new();
+        map.insert(1, 'a');
+        map.insert(2, 'b');
+
+        assert_eq!(map.insert(1, 'c'), Overwritten::Left(1, 'a'));
+        assert_eq!(map.get_by_right(&'a'), None);
+        assert_eq!(map.insert(3, 'b'), Overwritten::Right(2, 'b'));
+        assert_eq!(map.get_by_left(&2), None);
+        assert_eq!(map.insert(3, 'b'), Overwritten::Pair(3, 'b'));
+
+        assert_eq!(map.len(), 2);
+        assert_consistent(&map);
+    }
+
+    #[test]
+    fn test_insert_can_overwrite_two_pairs() {
+        let mut map = BiMap::new();
+        map.insert(1, 'a');
+        map.insert(2, 'b');
+
+        let overwritten = map.insert(1, 'b');
+
+        assert_eq!(overwritten, Overwritten::Both((1, 'a'), (2, 'b')));
+        assert_eq!(map.len(), 1);
+        assert_consistent(&map);
+    }
+
+    #[test]
+    fn test_insert_no_overwrite() {
+        let mut map = BiMap::new();
+        map.insert(1, 'a');
+
+        assert_eq!(map.insert_no_overwrite(1, 'b'), Err((1, 'b')));
+        assert_eq!(map.insert_no_overwrite(2, 'a'), Err((2, 'a')));
+        assert_eq!(map.insert_no_overwrite(2, 'b'), Ok(()));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_from_both_sides() {
+        let mut map = BiMap::new();
+        map.insert(1, 'a');
+        map.insert(2, 'b');
+
+        assert_eq!(map.remove_by_left(&1), Some((1, 'a')));
+        assert_eq!(map.remove_by_right(&'b'), Some((2, 'b')));
+        assert_eq!(map.remove_by_right(&'b'), None);
+
+        assert!(map.is_empty());
+        assert_consistent(&map);
+    }
+}
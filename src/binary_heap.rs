@@ -0,0 +1,306 @@
+//! Binary Heap implementation
+//! Complete binary tree stored in a vector: children of the item at index `i`
+//! are at `2i + 1` and `2i + 2`. The item at the root is always the smallest
+//! (Min heap) or the largest (Max heap), depending on the chosen ordering.
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapOrder {
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryHeap<T: Ord> {
+    data: Vec<T>,
+    order: HeapOrder,
+}
+
+impl<T: Ord> BinaryHeap<T> {
+    pub fn new(order: HeapOrder) -> Self {
+        BinaryHeap { data: vec![], order }
+    }
+
+    pub fn new_min() -> Self {
+        Self::new(HeapOrder::Min)
+    }
+
+    pub fn new_max() -> Self {
+        Self::new(HeapOrder::Max)
+    }
+
+    /// Builds a heap from a vector in O(n) by sifting down
+    /// every parent, starting from the last one.
+    pub fn from_vec(data: Vec<T>, order: HeapOrder) -> Self {
+        let mut heap = BinaryHeap { data, order };
+        for index in (0..heap.data.len() / 2).rev() {
+            heap.sift_down(index, heap.data.len());
+        }
+        check_invariants!(&heap);
+        heap
+    }
+
+    pub fn order(&self) -> HeapOrder {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        check_invariants!(self);
+    }
+
+    /// Returns the root of the heap without removing it
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// Adds an item at the end and sifts it up. O(log n)
+    pub fn push(&mut self, item: T) {
+        self.data.push(item);
+        self.sift_up(self.data.len() - 1);
+        check_invariants!(self);
+    }
+
+    /// Removes the root, moves the last item in its place and sifts it down. O(log n)
+    pub fn pop(&mut self) -> Option<T> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let last = self.data.len() - 1;
+        self.data.swap(0, last);
+        let result = self.data.pop();
+        self.sift_down(0, self.data.len());
+        check_invariants!(self);
+        result
+    }
+
+    /// Consumes the heap and returns its items in ascending order
+    /// regardless of the heap ordering.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        // Heap sort: repeatedly move the root behind the shrinking heap
+        for end in (1..self.data.len()).rev() {
+            self.data.swap(0, end);
+            self.sift_down(0, end);
+        }
+        if self.order == HeapOrder::Min {
+            self.data.reverse();
+        }
+        self.data
+    }
+
+    /// Consumes the heap and returns the underlying vector in heap order
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Returns true if `a` has to be closer to the root than `b`
+    fn is_before(&self, a: &T, b: &T) -> bool {
+        match self.order {
+            HeapOrder::Min => a < b,
+            HeapOrder::Max => a > b,
+        }
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !self.is_before(&self.data[index], &self.data[parent]) {
+                break;
+            }
+            self.data.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    /// Sifts the item down, only looking at the first `end` items
+    fn sift_down(&mut self, mut index: usize, end: usize) {
+        loop {
+            let left = 2 * index + 1;
+            let right = left + 1;
+            let mut first = index;
+            if left < end && self.is_before(&self.data[left], &self.data[first]) {
+                first = left;
+            }
+            if right < end && self.is_before(&self.data[right], &self.data[first]) {
+                first = right;
+            }
+            if first == index {
+                break;
+            }
+            self.data.swap(index, first);
+            index = first;
+        }
+    }
+}
+
+/// `contains` goes through every item, a heap is only ordered for its top
+impl<T: Ord> Collection for BinaryHeap<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.data.contains(item)
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for BinaryHeap<T> {
+    fn assert_invariants(&self) {
+        for index in 1..self.data.len() {
+            let parent = (index - 1) / 2;
+            assert!(
+                !self.is_before(&self.data[index], &self.data[parent]),
+                "item at {} belongs above its parent at {}",
+                index,
+                parent
+            );
+        }
+    }
+}
+
+impl<T: HeapSize + Ord> HeapSize for BinaryHeap<T> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_heap_property<T: Ord>(heap: &BinaryHeap<T>) {
+        for index in 1..heap.data.len() {
+            let parent = (index - 1) / 2;
+            assert!(
+                !heap.is_before(&heap.data[index], &heap.data[parent]),
+                "Child at index {} breaks the heap property",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn when_heap_is_created_it_is_empty() {
+        let heap = BinaryHeap::<i32>::new_max();
+
+        assert!(heap.is_empty());
+        assert_eq!(heap.len(), 0);
+        assert_eq!(heap.peek(), None);
+    }
+
+    #[test]
+    fn test_pop_when_empty_heap() {
+        let mut heap = BinaryHeap::<i32>::new_min();
+
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    fn test_push_keeps_largest_on_top_of_max_heap() {
+        let mut heap = BinaryHeap::new_max();
+
+        for item in [5, 1, 8, 3, 9, 2] {
+            heap.push(item);
+            assert_heap_property(&heap);
+        }
+
+        assert_eq!(heap.peek(), Some(&9));
+        assert_eq!(heap.len(), 6);
+    }
+
+    #[test]
+    fn test_push_keeps_smallest_on_top_of_min_heap() {
+        let mut heap = BinaryHeap::new_min();
+
+        for item in [5, 1, 8, 3, 9, 2] {
+            heap.push(item);
+            assert_heap_property(&heap);
+        }
+
+        assert_eq!(heap.peek(), Some(&1));
+    }
+
+    #[test]
+    fn test_pop_returns_items_in_heap_order() {
+        let items = vec![7, 3, 9, 1, 4, 4, 8];
+        let mut max_heap = BinaryHeap::new_max();
+        let mut min_heap = BinaryHeap::new_min();
+        for &item in &items {
+            max_heap.push(item);
+            min_heap.push(item);
+        }
+
+        let mut descending = vec![];
+        while let Some(item) = max_heap.pop() {
+            assert_heap_property(&max_heap);
+            descending.push(item);
+        }
+        let mut ascending = vec![];
+        while let Some(item) = min_heap.pop() {
+            ascending.push(item);
+        }
+
+        assert_eq!(descending, vec![9, 8, 7, 4, 4, 3, 1]);
+        assert_eq!(ascending, vec![1, 3, 4, 4, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_from_vec_builds_valid_heap() {
+        let items: Vec<i32> = (0..100).map(|i| (i * 37) % 100).collect();
+
+        let max_heap = BinaryHeap::from_vec(items.clone(), HeapOrder::Max);
+        let min_heap = BinaryHeap::from_vec(items, HeapOrder::Min);
+
+        assert_heap_property(&max_heap);
+        assert_heap_property(&min_heap);
+        assert_eq!(max_heap.peek(), Some(&99));
+        assert_eq!(min_heap.peek(), Some(&0));
+        assert_eq!(max_heap.len(), 100);
+    }
+
+    #[test]
+    fn test_into_sorted_vec_is_ascending_for_both_orders() {
+        let items = vec!["pear", "apple", "fig", "kiwi", "banana"];
+        let expected = vec!["apple", "banana", "fig", "kiwi", "pear"];
+
+        let from_max = BinaryHeap::from_vec(items.clone(), HeapOrder::Max).into_sorted_vec();
+        let from_min = BinaryHeap::from_vec(items, HeapOrder::Min).into_sorted_vec();
+
+        assert_eq!(from_max, expected);
+        assert_eq!(from_min, expected);
+    }
+
+    #[test]
+    fn test_clear_heap() {
+        let mut heap = BinaryHeap::from_vec(vec![3, 2, 1], HeapOrder::Max);
+
+        heap.clear();
+
+        assert!(heap.is_empty());
+        assert_eq!(heap.order(), HeapOrder::Max);
+    }
+}
@@ -0,0 +1,318 @@
+//! Binomial Heap implementation (min heap)
+//! Forest of binomial trees with at most one tree of every rank. A tree of
+//! rank k has exactly 2^k nodes, so the ranks present in the forest follow
+//! the binary representation of the heap size. Union works like binary
+//! addition: two trees of the same rank are linked into a tree of rank + 1.
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+struct BinomialTree<T> {
+    item: T,
+    rank: usize,
+    /// Children ordered by ascending rank: 0, 1, ..., rank - 1
+    children: Vec<BinomialTree<T>>,
+}
+
+impl<T: Ord> BinomialTree<T> {
+    fn new(item: T) -> Self {
+        BinomialTree { item, rank: 0, children: vec![] }
+    }
+
+    /// Links two trees of the same rank, the larger root becomes a child.
+    fn link(self, other: BinomialTree<T>) -> BinomialTree<T> {
+        let (mut parent, child) = if other.item < self.item { (other, self) } else { (self, other) };
+        parent.children.push(child);
+        parent.rank += 1;
+        parent
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinomialHeap<T: Ord> {
+    /// Trees ordered by ascending rank, each rank at most once
+    trees: Vec<BinomialTree<T>>,
+    size: usize,
+}
+
+impl<T: Ord> BinomialHeap<T> {
+    pub fn new() -> Self {
+        BinomialHeap { trees: vec![], size: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = BinomialHeap::new();
+        check_invariants!(self);
+    }
+
+    /// Ranks of the trees in the forest, in ascending order.
+    /// Exposed to show how the forest mirrors the binary form of `len()`.
+    pub fn ranks(&self) -> Vec<usize> {
+        self.trees.iter().map(|tree| tree.rank).collect()
+    }
+
+    /// Merges all trees of `other` into this heap in O(log n)
+    pub fn union(&mut self, other: BinomialHeap<T>) {
+        let trees = std::mem::take(&mut self.trees);
+        self.trees = Self::merge_forests(trees, other.trees);
+        self.size += other.size;
+        check_invariants!(self);
+    }
+
+    pub fn push(&mut self, item: T) {
+        let single = BinomialHeap { trees: vec![BinomialTree::new(item)], size: 1 };
+        self.union(single);
+    }
+
+    /// Returns the smallest item, which is one of the tree roots
+    pub fn peek(&self) -> Option<&T> {
+        self.trees.iter().map(|tree| &tree.item).min()
+    }
+
+    /// Items in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut stack: Vec<&BinomialTree<T>> = self.trees.iter().collect();
+        std::iter::from_fn(move || {
+            let tree = stack.pop()?;
+            stack.extend(&tree.children);
+            Some(&tree.item)
+        })
+    }
+
+    /// Removes the smallest root. Its children already form a valid
+    /// forest, which is merged back into the heap.
+    pub fn pop(&mut self) -> Option<T> {
+        let min_index = self.trees
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.item.cmp(&b.item))
+            .map(|(index, _)| index)?;
+        let tree = self.trees.remove(min_index);
+        let trees = std::mem::take(&mut self.trees);
+        self.trees = Self::merge_forests(trees, tree.children);
+        self.size -= 1;
+        check_invariants!(self);
+        Some(tree.item)
+    }
+
+    /// Adds trees to slots indexed by rank, carrying into the next slot
+    /// whenever a slot is already taken.
+    fn merge_forests(
+        first: Vec<BinomialTree<T>>,
+        second: Vec<BinomialTree<T>>
+    ) -> Vec<BinomialTree<T>> {
+        let mut slots: Vec<Option<BinomialTree<T>>> = vec![];
+        for tree in first.into_iter().chain(second) {
+            let mut carry = tree;
+            loop {
+                let rank = carry.rank;
+                if slots.len() <= rank {
+                    slots.resize_with(rank + 1, || None);
+                }
+                match slots[rank].take() {
+                    Some(existing) => {
+                        carry = existing.link(carry);
+                    }
+                    None => {
+                        slots[rank] = Some(carry);
+                        break;
+                    }
+                }
+            }
+        }
+        slots.into_iter().flatten().collect()
+    }
+}
+
+impl<T: Ord> Default for BinomialHeap<T> {
+    fn default() -> Self {
+        BinomialHeap::new()
+    }
+}
+
+/// `contains` goes through every item, a heap is only ordered for its top
+impl<T: Ord> Collection for BinomialHeap<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        BinomialHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BinomialHeap::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.iter().any(|other| other == item)
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+fn assert_tree_is_binomial<T: Ord>(tree: &BinomialTree<T>) -> usize {
+    assert_eq!(tree.children.len(), tree.rank, "Tree of rank k has k children");
+    let mut node_count = 1;
+    for (expected_rank, child) in tree.children.iter().enumerate() {
+        assert_eq!(child.rank, expected_rank, "Children have ranks 0..k");
+        assert!(tree.item <= child.item, "Parent is not larger than child");
+        node_count += assert_tree_is_binomial(child);
+    }
+    assert_eq!(node_count, 1 << tree.rank, "Tree of rank k has 2^k nodes");
+    node_count
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for BinomialHeap<T> {
+    fn assert_invariants(&self) {
+        let ranks = self.ranks();
+        let expected_ranks: Vec<usize> = (0..usize::BITS as usize)
+            .filter(|bit| self.len() & (1 << bit) != 0)
+            .collect();
+        assert_eq!(ranks, expected_ranks, "Ranks follow the binary form of len");
+        let total: usize = self.trees.iter().map(assert_tree_is_binomial).sum();
+        assert_eq!(total, self.len());
+    }
+}
+
+impl<T: HeapSize> HeapSize for BinomialTree<T> {
+    fn heap_size(&self) -> usize {
+        self.item.heap_size() + self.children.heap_size()
+    }
+}
+
+impl<T: Ord + HeapSize> HeapSize for BinomialHeap<T> {
+    fn heap_size(&self) -> usize {
+        self.trees.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic generator, so the property tests are reproducible
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+    }
+
+    #[test]
+    fn when_heap_is_created_it_is_empty() {
+        let mut heap = BinomialHeap::<i32>::new();
+
+        assert!(heap.is_empty());
+        assert!(heap.ranks().is_empty());
+        assert_eq!(heap.peek(), None);
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    fn test_push_builds_forest_matching_binary_size() {
+        let mut heap = BinomialHeap::new();
+
+        for item in 0..13 {
+            heap.push(item);
+        }
+
+        assert_eq!(heap.ranks(), vec![0, 2, 3], "13 = 0b1101");
+        heap.assert_invariants();
+    }
+
+    #[test]
+    fn test_peek_and_pop_return_smallest() {
+        let mut heap = BinomialHeap::new();
+        for item in [9, 4, 7, 1, 8, 2] {
+            heap.push(item);
+        }
+
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.len(), 4);
+        heap.assert_invariants();
+    }
+
+    #[test]
+    fn test_union_of_two_heaps() {
+        let mut heap_a = BinomialHeap::new();
+        let mut heap_b = BinomialHeap::new();
+        for item in 0..6 {
+            heap_a.push(item * 2);
+        }
+        for item in 0..6 {
+            heap_b.push(item * 2 + 1);
+        }
+
+        heap_a.union(heap_b);
+
+        assert_eq!(heap_a.len(), 12);
+        assert_eq!(heap_a.ranks(), vec![2, 3]);
+        heap_a.assert_invariants();
+        let mut items = vec![];
+        while let Some(item) = heap_a.pop() {
+            items.push(item);
+        }
+        assert_eq!(items, (0..12).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_union_with_empty_heap() {
+        let mut heap = BinomialHeap::new();
+        heap.push("a");
+
+        heap.union(BinomialHeap::new());
+
+        assert_eq!(heap.len(), 1);
+        heap.assert_invariants();
+    }
+
+    #[test]
+    fn property_random_operations_keep_structure_valid() {
+        for seed in 0..20 {
+            let mut random = Lcg(seed);
+            let mut heap = BinomialHeap::new();
+            let mut model: Vec<u64> = vec![];
+
+            for _ in 0..300 {
+                match random.next() % 4 {
+                    0 => {
+                        model.sort();
+                        let expected = if model.is_empty() { None } else { Some(model.remove(0)) };
+                        assert_eq!(heap.pop(), expected);
+                    }
+                    1 => {
+                        let mut other = BinomialHeap::new();
+                        for _ in 0..random.next() % 10 {
+                            let item = random.next() % 100;
+                            other.push(item);
+                            model.push(item);
+                        }
+                        heap.union(other);
+                    }
+                    _ => {
+                        let item = random.next() % 100;
+                        heap.push(item);
+                        model.push(item);
+                    }
+                }
+                heap.assert_invariants();
+                assert_eq!(heap.peek(), model.iter().min());
+            }
+        }
+    }
+}
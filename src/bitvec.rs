@@ -0,0 +1,313 @@
+//! BitVec implementation
+//! Growable vector of bits packed into 64-bit words. Bit i lives in word
+//! i / 64 at position i % 64. Bits past the length in the last word are
+//! always kept zero, so counting and bitwise operations can work on whole
+//! words.
+
+use std::ops::{ BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign };
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+pub(crate) const WORD_BITS: usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        BitVec { words: vec![], len: 0 }
+    }
+
+    /// `len` bits, all set to `value`
+    pub fn repeat(value: bool, len: usize) -> Self {
+        let mut bits = BitVec {
+            words: vec![if value { u64::MAX } else { 0 }; len.div_ceil(WORD_BITS)],
+            len,
+        };
+        bits.clear_unused_bits();
+        check_invariants!(&bits);
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The packed words, bits past the length are zero
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, value);
+        check_invariants!(self);
+    }
+
+    pub fn pop(&mut self) -> Option<bool> {
+        let value = self.get(self.len.checked_sub(1)?)?;
+        self.set(self.len - 1, false);
+        self.len -= 1;
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.pop();
+        }
+        check_invariants!(self);
+        Some(value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+        Some(self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len, "Index {} out of bounds for length {}", index, self.len);
+        let mask = 1 << (index % WORD_BITS);
+        if value {
+            self.words[index / WORD_BITS] |= mask;
+        } else {
+            self.words[index / WORD_BITS] &= !mask;
+        }
+        check_invariants!(self);
+    }
+
+    /// Sets every bit to zero, keeping the length
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+        check_invariants!(self);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Number of set bits in `0..end`, by popcount of whole words
+    pub fn rank(&self, end: usize) -> usize {
+        assert!(end <= self.len, "End {} out of bounds for length {}", end, self.len);
+        let full_words = end / WORD_BITS;
+        let mut count: usize = self.words[..full_words]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+        let remainder = end % WORD_BITS;
+        if remainder > 0 {
+            count += (self.words[full_words] & ((1 << remainder) - 1)).count_ones() as usize;
+        }
+        count
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|index| self.get(index).unwrap())
+    }
+
+    /// Indices of the set bits in ascending order. Skips whole zero words
+    /// and finds each bit with `trailing_zeros`.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, &word)| {
+                let mut rest = word;
+                std::iter::from_fn(move || {
+                    if rest == 0 {
+                        return None;
+                    }
+                    let bit = rest.trailing_zeros() as usize;
+                    rest &= rest - 1;
+                    Some(word_index * WORD_BITS + bit)
+                })
+            })
+    }
+
+    fn clear_unused_bits(&mut self) {
+        let remainder = self.len % WORD_BITS;
+        if remainder > 0 {
+            *self.words.last_mut().unwrap() &= (1 << remainder) - 1;
+        }
+    }
+
+    fn combine_with(&mut self, other: &BitVec, operation: fn(u64, u64) -> u64) {
+        assert_eq!(
+            self.len,
+            other.len,
+            "Bitwise operations need bit vectors of the same length"
+        );
+        for (word, &other_word) in self.words.iter_mut().zip(&other.words) {
+            *word = operation(*word, other_word);
+        }
+        check_invariants!(self);
+    }
+}
+
+/// Checks there are just enough words for the bits and the unused bits of
+/// the last word are zero. Only looks at the last word, so it is O(1).
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for BitVec {
+    fn assert_invariants(&self) {
+        assert_eq!(self.words.len(), self.len.div_ceil(WORD_BITS), "Word count is off");
+        let remainder = self.len % WORD_BITS;
+        if remainder > 0 {
+            let unused = self.words.last().unwrap() >> remainder;
+            assert_eq!(unused, 0, "Bits past the length are set");
+        }
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = BitVec::new();
+        for value in iter {
+            bits.push(value);
+        }
+        bits
+    }
+}
+
+macro_rules! impl_bit_operation {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl $assign_trait<&BitVec> for BitVec {
+            fn $assign_method(&mut self, other: &BitVec) {
+                self.combine_with(other, |a, b| a $op b);
+            }
+        }
+
+        impl $trait for &BitVec {
+            type Output = BitVec;
+
+            fn $method(self, other: &BitVec) -> BitVec {
+                let mut result = self.clone();
+                result.$assign_method(other);
+                result
+            }
+        }
+    };
+}
+
+impl_bit_operation!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+impl_bit_operation!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+impl_bit_operation!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl HeapSize for BitVec {
+    fn heap_size(&self) -> usize {
+        self.words.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_str(bits: &str) -> BitVec {
+        bits.chars().map(|c| c == '1').collect()
+    }
+
+    #[test]
+    fn when_bitvec_is_created_it_is_empty() {
+        let mut bits = BitVec::new();
+
+        assert!(bits.is_empty());
+        assert_eq!(bits.get(0), None);
+        assert_eq!(bits.pop(), None);
+    }
+
+    #[test]
+    fn test_push_get_and_pop_across_words() {
+        let mut bits = BitVec::new();
+
+        for i in 0..130 {
+            bits.push(i % 3 == 0);
+        }
+
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.as_words().len(), 3);
+        assert_eq!(bits.get(129), Some(true));
+        assert_eq!(bits.get(64), Some(false));
+        assert_eq!(bits.pop(), Some(true));
+        assert_eq!(bits.pop(), Some(false));
+        assert_eq!(bits.as_words().len(), 2);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut bits = BitVec::repeat(false, 70);
+
+        bits.set(69, true);
+        bits.set(3, true);
+        bits.set(3, false);
+
+        assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![69]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 5 out of bounds for length 5")]
+    fn test_set_out_of_bounds_panics() {
+        BitVec::repeat(true, 5).set(5, false);
+    }
+
+    #[test]
+    fn test_repeat_keeps_unused_bits_zero() {
+        let bits = BitVec::repeat(true, 67);
+
+        assert_eq!(bits.count_ones(), 67);
+        assert_eq!(bits.count_zeros(), 0);
+        assert_eq!(bits.as_words()[1], 0b111);
+    }
+
+    #[test]
+    fn test_rank_matches_naive_count() {
+        let bits: BitVec = (0..200).map(|i| (i * 7) % 5 < 2).collect();
+
+        for end in 0..=200 {
+            assert_eq!(bits.rank(end), bits.iter().take(end).filter(|&bit| bit).count());
+        }
+    }
+
+    #[test]
+    fn test_bitwise_operations() {
+        let a = from_str("1100");
+        let b = from_str("1010");
+
+        assert_eq!(&a & &b, from_str("1000"));
+        assert_eq!(&a | &b, from_str("1110"));
+        assert_eq!(&a ^ &b, from_str("0110"));
+
+        let mut c = a.clone();
+        c ^= &a;
+        assert_eq!(c.count_ones(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bitwise operations need bit vectors of the same length")]
+    fn test_bitwise_operation_on_different_lengths_panics() {
+        let _ = &from_str("10") | &from_str("101");
+    }
+
+    #[test]
+    fn test_iter_ones() {
+        let mut bits = BitVec::repeat(false, 300);
+        for index in [0, 63, 64, 128, 299] {
+            bits.set(index, true);
+        }
+
+        assert_eq!(bits.iter_ones().collect::<Vec<_>>(), vec![0, 63, 64, 128, 299]);
+    }
+}
@@ -0,0 +1,296 @@
+//! BK-tree implementation
+//! Tree for finding items that are close to a query under a metric, such as
+//! words within a few typos of a misspelled one. Every child is stored under
+//! its distance to the parent. By the triangle inequality, items within `d`
+//! of the query can only be below children whose distance to the parent is
+//! within `d` of the parent's distance to the query, so whole subtrees are
+//! skipped without being measured.
+//! Nodes live in a vector and link to their children by index.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+/// Distance between two items. Must be a metric: zero only for equal items,
+/// symmetric, and obeying the triangle inequality.
+pub trait Metric<T: ?Sized> {
+    fn distance(&self, a: &T, b: &T) -> usize;
+}
+
+/// Edit distance: the number of single character insertions, deletions and
+/// substitutions that turn one string into the other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Levenshtein;
+
+impl Metric<str> for Levenshtein {
+    fn distance(&self, a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        // Row of distances from a prefix of `a` to every prefix of `b`
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+        for (i, a_char) in a.chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, b_char) in b.iter().enumerate() {
+                let substitution = diagonal + usize::from(a_char != *b_char);
+                diagonal = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+            }
+        }
+        row[b.len()]
+    }
+}
+
+impl Metric<String> for Levenshtein {
+    fn distance(&self, a: &String, b: &String) -> usize {
+        Metric::<str>::distance(self, a, b)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BkNode<T> {
+    item: T,
+    /// Distance to this node and the child node index
+    children: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BkTree<T, M> {
+    nodes: Vec<BkNode<T>>,
+    metric: M,
+}
+
+impl<T, M: Metric<T>> BkTree<T, M> {
+    pub fn new(metric: M) -> Self {
+        BkTree { nodes: vec![], metric }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+
+    /// Adds the item below the child at its distance from each node on the way.
+    /// Returns false if an equal item is already in the tree.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.nodes.is_empty() {
+            self.nodes.push(BkNode { item, children: vec![] });
+            return true;
+        }
+        let mut current = 0;
+        loop {
+            let distance = self.metric.distance(&item, &self.nodes[current].item);
+            if distance == 0 {
+                return false;
+            }
+            let child = self.nodes[current].children.iter().find(|(d, _)| *d == distance);
+            match child {
+                Some(&(_, child)) => {
+                    current = child;
+                }
+                None => {
+                    let index = self.nodes.len();
+                    self.nodes.push(BkNode { item, children: vec![] });
+                    self.nodes[current].children.push((distance, index));
+                    check_invariants!(self);
+                    return true;
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        !self.find_within(item, 0).is_empty()
+    }
+
+    /// Items at most `max_distance` from the query with their distances,
+    /// closest first
+    pub fn find_within(&self, query: &T, max_distance: usize) -> Vec<(usize, &T)> {
+        let mut found = vec![];
+        let mut stack: Vec<usize> = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let distance = self.metric.distance(query, &node.item);
+            if distance <= max_distance {
+                found.push((distance, &node.item));
+            }
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(child_distance, _)| (low..=high).contains(child_distance))
+                    .map(|(_, child)| *child)
+            );
+        }
+        found.sort_by_key(|(distance, _)| *distance);
+        found
+    }
+
+    /// Closest item to the query, the search radius shrinks as better items are found
+    pub fn find_closest(&self, query: &T) -> Option<(usize, &T)> {
+        let mut best: Option<(usize, &T)> = None;
+        let mut stack: Vec<usize> = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let distance = self.metric.distance(query, &node.item);
+            if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                best = Some((distance, &node.item));
+            }
+            let radius = best.map_or(usize::MAX, |(best_distance, _)| best_distance);
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(child_distance, _)| child_distance.abs_diff(distance) < radius)
+                    .map(|(_, child)| *child)
+            );
+        }
+        best
+    }
+
+    /// Items in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.nodes.iter().map(|node| &node.item)
+    }
+}
+
+impl<T, M: Metric<T>> Extend<T> for BkTree<T, M> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+/// Checks every node but the root is the child of exactly one node, under
+/// its distance to that node, and no node has two children at one distance
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, M: Metric<T>> Invariants for BkTree<T, M> {
+    fn assert_invariants(&self) {
+        let mut parents = vec![0; self.nodes.len()];
+        for node in &self.nodes {
+            for (i, &(distance, child)) in node.children.iter().enumerate() {
+                assert!(child > 0 && child < self.nodes.len(), "Child index is out of bounds");
+                parents[child] += 1;
+                let measured = self.metric.distance(&self.nodes[child].item, &node.item);
+                assert_eq!(distance, measured, "Child is stored under the wrong distance");
+                let repeated = node.children[..i].iter().any(|(other, _)| *other == distance);
+                assert!(!repeated, "Two children at the same distance");
+            }
+        }
+        assert!(parents.iter().skip(1).all(|&count| count == 1), "Node isn't linked once");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Distance between numbers on the number line
+    struct Absolute;
+
+    impl Metric<i64> for Absolute {
+        fn distance(&self, a: &i64, b: &i64) -> usize {
+            a.abs_diff(*b) as usize
+        }
+    }
+
+    fn dictionary() -> BkTree<String, Levenshtein> {
+        let mut tree = BkTree::new(Levenshtein);
+        let words = ["book", "books", "cake", "boo", "boon", "cook", "cape", "cart"];
+        tree.extend(words.iter().map(|word| word.to_string()));
+        tree
+    }
+
+    fn words<'a>(found: &[(usize, &'a String)]) -> Vec<&'a str> {
+        let mut words: Vec<&str> = found.iter().map(|(_, word)| word.as_str()).collect();
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let tree = BkTree::<String, Levenshtein>::new(Levenshtein);
+
+        assert!(tree.is_empty());
+        assert!(tree.find_within(&"a".to_string(), 5).is_empty());
+        assert_eq!(tree.find_closest(&"a".to_string()), None);
+    }
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(Levenshtein.distance("kitten", "sitting"), 3);
+        assert_eq!(Levenshtein.distance("", "abc"), 3);
+        assert_eq!(Levenshtein.distance("flaw", "lawn"), 2);
+        assert_eq!(Levenshtein.distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_insert_skips_duplicates() {
+        let mut tree = dictionary();
+
+        assert!(!tree.insert("book".to_string()));
+        assert!(tree.insert("bool".to_string()));
+
+        assert_eq!(tree.len(), 9);
+        assert!(tree.contains(&"bool".to_string()));
+        assert!(!tree.contains(&"boot".to_string()));
+    }
+
+    #[test]
+    fn test_find_within() {
+        let tree = dictionary();
+
+        let found = tree.find_within(&"bo".to_string(), 1);
+        assert_eq!(words(&found), vec!["boo"]);
+
+        let found = tree.find_within(&"bool".to_string(), 1);
+        assert_eq!(words(&found), vec!["boo", "book", "boon"]);
+        assert!(found.iter().all(|(distance, _)| *distance == 1));
+
+        let found = tree.find_within(&"cap".to_string(), 2);
+        assert_eq!(found[0], (1, &"cape".to_string()), "Closest match comes first");
+        assert_eq!(words(&found), vec!["cake", "cape", "cart"]);
+    }
+
+    #[test]
+    fn test_find_closest() {
+        let tree = dictionary();
+
+        assert_eq!(tree.find_closest(&"caek".to_string()).map(|(d, _)| d), Some(2));
+        assert_eq!(tree.find_closest(&"coke".to_string()), Some((1, &"cake".to_string())));
+    }
+
+    #[test]
+    fn test_custom_metric_matches_linear_scan() {
+        let mut tree = BkTree::new(Absolute);
+        let mut items = vec![];
+        let mut state: u64 = 37;
+        for _ in 0..500 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let item = ((state >> 33) % 10_000) as i64;
+            if tree.insert(item) {
+                items.push(item);
+            }
+        }
+
+        for query in [0, 1234, 5000, 9999] {
+            let found = tree.find_within(&query, 150);
+            let mut found: Vec<i64> = found.iter().map(|(_, item)| **item).collect();
+            let mut expected: Vec<i64> =
+                items.iter().copied().filter(|item| item.abs_diff(query) <= 150).collect();
+            found.sort();
+            expected.sort();
+            assert_eq!(found, expected, "Query {}", query);
+
+            let closest = items.iter().map(|item| item.abs_diff(query) as usize).min();
+            assert_eq!(tree.find_closest(&query).map(|(distance, _)| distance), closest);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Bloom Filter implementation
+//! Probabilistic set that stores only a bit array. Every item sets `k` bits
+//! picked by double hashing (`h1 + i * h2`). A lookup that finds any of its
+//! bits unset means the item was never inserted; finding all of them set
+//! means it probably was. There are no false negatives, and the rate of
+//! false positives is chosen up front together with the expected item count.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+use std::marker::PhantomData;
+
+use crate::bitvec::BitVec;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter<T: ?Sized> {
+    bits: BitVec,
+    hash_count: usize,
+    marker: PhantomData<T>,
+}
+
+impl<T: Hash + ?Sized> BloomFilter<T> {
+    /// Creates a filter sized so that after `expected_items` inserts the
+    /// false-positive rate is about `false_positive_rate`.
+    /// Uses `m = -n ln p / (ln 2)^2` bits and `k = m / n * ln 2` hashes.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        assert!(expected_items > 0, "Expected items has to be at least 1");
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "False-positive rate {} has to be between 0 and 1",
+            false_positive_rate
+        );
+        let items = expected_items as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-items * false_positive_rate.ln() / (ln2 * ln2)).ceil() as usize;
+        let hash_count = ((bit_count as f64 / items) * ln2).round().max(1.0) as usize;
+        Self::with_parameters(bit_count, hash_count)
+    }
+
+    pub fn with_parameters(bit_count: usize, hash_count: usize) -> Self {
+        assert!(bit_count > 0 && hash_count > 0, "Bit and hash counts have to be at least 1");
+        BloomFilter {
+            bits: BitVec::repeat(false, bit_count),
+            hash_count,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn bit_count(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn hash_count(&self) -> usize {
+        self.hash_count
+    }
+
+    /// Number of bits that are set
+    pub fn count_ones(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.count_ones() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.clear();
+        check_invariants!(self);
+    }
+
+    pub fn insert(&mut self, item: &T) {
+        for bit in self.bit_indices(item) {
+            self.bits.set(bit, true);
+        }
+        check_invariants!(self);
+    }
+
+    /// False means the item is definitely not in the filter,
+    /// true means it is in the filter or this is a false positive.
+    pub fn maybe_contains(&self, item: &T) -> bool {
+        self.bit_indices(item).all(|bit| self.bits.get(bit) == Some(true))
+    }
+
+    /// Filter containing the items of both filters
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Filter that may contain the items present in both filters. It can
+    /// report more false positives than a filter built from the common items.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a & b)
+    }
+
+    fn combine(&self, other: &Self, operation: fn(&BitVec, &BitVec) -> BitVec) -> Self {
+        assert!(
+            self.bit_count() == other.bit_count() && self.hash_count == other.hash_count,
+            "Bloom filters need the same bit and hash counts to be combined"
+        );
+        let combined = BloomFilter {
+            bits: operation(&self.bits, &other.bits),
+            hash_count: self.hash_count,
+            marker: PhantomData,
+        };
+        check_invariants!(&combined);
+        combined
+    }
+
+    fn bit_indices(&self, item: &T) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let first = hasher.finish();
+        // Hashing one more word gives a second, independent looking hash.
+        // It is made odd so it never gets stuck on a single bit.
+        hasher.write_u64(0x9e3779b97f4a7c15);
+        let second = hasher.finish() | 1;
+        let bit_count = self.bits.len() as u64;
+        (0..self.hash_count as u64).map(move |i| {
+            (first.wrapping_add(i.wrapping_mul(second)) % bit_count) as usize
+        })
+    }
+}
+
+/// Checks there is at least one bit and one hash, and the bits themselves
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: ?Sized> Invariants for BloomFilter<T> {
+    fn assert_invariants(&self) {
+        assert!(!self.bits.is_empty() && self.hash_count > 0, "Filter has no bits or hashes");
+        self.bits.assert_invariants();
+    }
+}
+
+impl<T: ?Sized> HeapSize for BloomFilter<T> {
+    fn heap_size(&self) -> usize {
+        self.bits.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimal_parameters() {
+        let filter = BloomFilter::<u32>::new(1000, 0.01);
+
+        assert_eq!(filter.bit_count(), 9586);
+        assert_eq!(filter.hash_count(), 7);
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn test_inserted_items_are_always_found() {
+        let mut filter = BloomFilter::new(500, 0.05);
+
+        for i in 0..500u32 {
+            filter.insert(&i);
+        }
+
+        assert!((0..500u32).all(|i| filter.maybe_contains(&i)));
+    }
+
+    #[test]
+    fn test_unsized_items() {
+        let mut filter = BloomFilter::<str>::new(10, 0.01);
+
+        filter.insert("apple");
+
+        assert!(filter.maybe_contains("apple"));
+        assert!(!filter.maybe_contains("pear"));
+    }
+
+    #[test]
+    fn test_observed_false_positive_rate_is_close_to_target() {
+        for target in [0.1, 0.01] {
+            let mut filter = BloomFilter::new(2000, target);
+            for i in 0..2000u64 {
+                filter.insert(&i);
+            }
+
+            let trials = 50_000;
+            let false_positives = (1_000_000..1_000_000 + trials)
+                .filter(|i: &u64| filter.maybe_contains(i))
+                .count();
+            let observed = false_positives as f64 / trials as f64;
+
+            assert!(observed < target * 1.5, "Observed {} for target {}", observed, target);
+        }
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut filter = BloomFilter::new(10, 0.1);
+        filter.insert(&"a");
+
+        filter.clear();
+
+        assert!(filter.is_empty());
+        assert!(!filter.maybe_contains(&"a"));
+    }
+
+    #[test]
+    fn test_union_contains_items_of_both() {
+        let mut evens = BloomFilter::new(100, 0.01);
+        let mut odds = BloomFilter::new(100, 0.01);
+        for i in 0..100u32 {
+            if i % 2 == 0 { evens.insert(&i) } else { odds.insert(&i) }
+        }
+
+        let union = evens.union(&odds);
+
+        assert!((0..100u32).all(|i| union.maybe_contains(&i)));
+        assert!(union.count_ones() >= evens.count_ones().max(odds.count_ones()));
+    }
+
+    #[test]
+    fn test_intersection_keeps_common_items() {
+        let mut first = BloomFilter::new(100, 0.01);
+        let mut second = BloomFilter::new(100, 0.01);
+        for i in 0..60u32 {
+            first.insert(&i);
+        }
+        for i in 40..100u32 {
+            second.insert(&i);
+        }
+
+        let intersection = first.intersection(&second);
+
+        assert!((40..60u32).all(|i| intersection.maybe_contains(&i)));
+        assert!(intersection.count_ones() <= first.count_ones().min(second.count_ones()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Bloom filters need the same bit and hash counts to be combined")]
+    fn test_combining_different_sizes_panics() {
+        let first = BloomFilter::<u8>::new(100, 0.01);
+        let second = BloomFilter::<u8>::new(200, 0.01);
+
+        first.union(&second);
+    }
+}
@@ -0,0 +1,172 @@
+//! Collection trait
+//! The questions every container can answer, so generic code and tests can
+//! work with any of them. `contains` looks for an item, which for maps is a
+//! key. Structures that already have inherent methods with these names keep
+//! them, the trait methods agree with them.
+
+pub trait Collection {
+    /// What `contains` looks for
+    type Item;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every item
+    fn clear(&mut self);
+
+    fn contains(&self, item: &Self::Item) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptive_radix_tree::AdaptiveRadixTree;
+    use crate::avl_tree::AvlTreeMap;
+    use crate::bimap::BiMap;
+    use crate::binary_heap::{ BinaryHeap, HeapOrder };
+    use crate::binomial_heap::BinomialHeap;
+    use crate::hash_map::HashMap;
+    use crate::indexed_priority_queue::IndexedPriorityQueue;
+    use crate::interval_heap::IntervalHeap;
+    use crate::leftist_heap::LeftistHeap;
+    use crate::linked_hash_set::LinkedHashSet;
+    use crate::multiset::MultiSet;
+    use crate::ordered_set::OrderedSet;
+    use crate::pairing_heap::PairingHeap;
+    use crate::patricia_trie::{ BitPrefix, PatriciaTrie };
+    use crate::queue::Queue;
+    use crate::radix_trie::RadixTrie;
+    use crate::singly_linked_list::SinglyLinkedList;
+    use crate::skew_heap::SkewHeap;
+    use crate::sorted_vec::SortedVec;
+    use crate::stack::Stack;
+    use crate::veb_tree::VebTree;
+
+    /// Fills a collection through `insert` and checks it through the trait only
+    fn check_collection<C: Collection<Item = i32>>(mut collection: C, insert: fn(&mut C, i32)) {
+        assert!(collection.is_empty());
+        assert!(!collection.contains(&3));
+
+        for item in 0..10 {
+            insert(&mut collection, item);
+        }
+
+        assert_eq!(collection.len(), 10);
+        assert!(!collection.is_empty());
+        assert!(collection.contains(&0));
+        assert!(collection.contains(&9));
+        assert!(!collection.contains(&10));
+
+        collection.clear();
+
+        assert_eq!(collection.len(), 0);
+        assert!(collection.is_empty());
+        assert!(!collection.contains(&0));
+    }
+
+    #[test]
+    fn test_singly_linked_list() {
+        check_collection(SinglyLinkedList::new(), |list, item| list.append(item));
+    }
+
+    #[test]
+    fn test_stack() {
+        check_collection(Stack::new(), |stack, item| stack.push(item));
+    }
+
+    #[test]
+    fn test_queue() {
+        check_collection(Queue::new(), |queue, item| queue.add(item));
+    }
+
+    #[test]
+    fn test_hash_map_contains_keys() {
+        check_collection(HashMap::new(), |map, key| {
+            map.insert(key, key.to_string());
+        });
+    }
+
+    #[test]
+    fn test_sorted_collections() {
+        check_collection(AvlTreeMap::new(), |tree, key| {
+            tree.insert(key, ());
+        });
+        check_collection(SortedVec::new(), |sorted, item| {
+            sorted.insert(item);
+        });
+        check_collection(OrderedSet::new(), |set, item| {
+            set.insert(item);
+        });
+    }
+
+    #[test]
+    fn test_sets_and_bimap() {
+        check_collection(MultiSet::new(), |set, item| {
+            set.insert(item);
+        });
+        check_collection(LinkedHashSet::new(), |set, item| {
+            set.insert(item);
+        });
+        check_collection(BiMap::new(), |map, left| {
+            map.insert(left, left.to_string());
+        });
+    }
+
+    #[test]
+    fn test_heaps() {
+        check_collection(BinaryHeap::new(HeapOrder::Min), |heap, item| heap.push(item));
+        check_collection(BinomialHeap::new(), |heap, item| heap.push(item));
+        check_collection(LeftistHeap::new(), |heap, item| heap.push(item));
+        check_collection(SkewHeap::new(), |heap, item| heap.push(item));
+        check_collection(PairingHeap::new(), |heap, item| {
+            heap.push(item);
+        });
+        check_collection(IntervalHeap::new(), |heap, item| heap.push(item));
+        check_collection(IndexedPriorityQueue::new(), |queue, key| {
+            queue.push(key, -key);
+        });
+    }
+
+    #[test]
+    fn test_tries_contain_their_keys() {
+        let mut radix = RadixTrie::new();
+        radix.insert("romane", 1);
+        let mut patricia = PatriciaTrie::new();
+        patricia.insert(BitPrefix::new(0b101 << 125, 3), 1);
+        let mut art = AdaptiveRadixTree::new();
+        art.insert(b"key", 1);
+        let mut veb = VebTree::new(8);
+        veb.insert(200);
+
+        assert!(Collection::contains(&radix, &"romane".to_string()));
+        assert!(!Collection::contains(&radix, &"roman".to_string()));
+        assert!(Collection::contains(&patricia, &BitPrefix::new(0b101 << 125, 3)));
+        assert!(Collection::contains(&art, &b"key".to_vec()));
+        assert!(Collection::contains(&veb, &200));
+        Collection::clear(&mut radix);
+        Collection::clear(&mut patricia);
+        Collection::clear(&mut art);
+        Collection::clear(&mut veb);
+        assert_eq!([radix.len(), patricia.len(), art.len(), veb.len()], [0; 4]);
+        assert_eq!(veb.universe_bits(), 8);
+        assert!(!veb.contains(200));
+    }
+
+    #[test]
+    fn test_works_through_trait_objects() {
+        let mut stack = Stack::new();
+        stack.push('a');
+        let mut queue = Queue::new();
+        queue.add('a');
+        queue.add('b');
+
+        let collections: Vec<&dyn Collection<Item = char>> = vec![&stack, &queue];
+
+        let lengths: Vec<usize> = collections.iter().map(|c| c.len()).collect();
+        assert_eq!(lengths, vec![1, 2]);
+        assert!(collections.iter().all(|c| c.contains(&'a')));
+    }
+}
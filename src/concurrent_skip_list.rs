@@ -0,0 +1,407 @@
+//! Concurrent Skip List Map implementation
+//! Ordered map that many threads can use through a shared reference.
+//! Every node has a tower of forward links, each behind its own mutex, so
+//! threads only ever lock the one or two links they are changing.
+//! An entry exists once it is linked on the bottom level. Removal first marks
+//! the node, then unlinks it level by level from the top; links are only
+//! changed after checking under the lock that the predecessor is unmarked
+//! and still points where the search saw it, otherwise the search is redone.
+//! Locks are always taken in ascending key order, so threads can't deadlock.
+
+use std::mem;
+use std::sync::atomic::{ AtomicBool, AtomicU64, AtomicUsize, Ordering };
+use std::sync::{ Arc, Mutex, MutexGuard };
+use std::thread;
+
+use crate::map::Map;
+
+const MAX_LEVEL: usize = 24;
+
+type Link<K, V> = Option<Arc<SkipNode<K, V>>>;
+
+struct SkipNode<K, V> {
+    /// None only for the head node, which sorts before every key
+    key: Option<K>,
+    value: Mutex<Option<V>>,
+    next: Vec<Mutex<Link<K, V>>>,
+    marked: AtomicBool,
+    /// Held while a node is being linked, so it can't be removed half linked
+    linking: Mutex<()>,
+}
+
+impl<K, V> SkipNode<K, V> {
+    fn new(key: Option<K>, value: Option<V>, height: usize) -> Self {
+        SkipNode {
+            key,
+            value: Mutex::new(value),
+            next: (0..height).map(|_| Mutex::new(None)).collect(),
+            marked: AtomicBool::new(false),
+            linking: Mutex::new(()),
+        }
+    }
+
+    fn key(&self) -> &K {
+        self.key.as_ref().expect("The head node has no key")
+    }
+
+    fn next(&self, level: usize) -> Link<K, V> {
+        lock(&self.next[level]).clone()
+    }
+
+    fn is_marked(&self) -> bool {
+        self.marked.load(Ordering::SeqCst)
+    }
+}
+
+/// Nodes found by a search on every level: the last one with a smaller key
+/// and the one after it
+struct Position<K, V> {
+    predecessors: Vec<Arc<SkipNode<K, V>>>,
+    successors: Vec<Link<K, V>>,
+}
+
+pub struct ConcurrentSkipListMap<K, V> {
+    head: Arc<SkipNode<K, V>>,
+    len: AtomicUsize,
+    seed: AtomicU64,
+}
+
+impl<K: Ord + Clone, V: Clone> ConcurrentSkipListMap<K, V> {
+    pub fn new() -> Self {
+        ConcurrentSkipListMap {
+            head: Arc::new(SkipNode::new(None, None, MAX_LEVEL)),
+            len: AtomicUsize::new(0),
+            seed: AtomicU64::new(0x2545_f491_4f6c_dd1d),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let node = self.find_node(key)?;
+        if node.is_marked() {
+            return None;
+        }
+        let value = lock(&node.value).clone();
+        value
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts or updates the entry and returns the old value
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let node = Arc::new(SkipNode::new(Some(key), Some(value), self.random_height()));
+        let _linking = lock(&node.linking);
+        let key = node.key();
+
+        loop {
+            let position = self.find(key);
+            if let Some(existing) = position.successors[0].as_ref().filter(|n| n.key() == key) {
+                if !existing.is_marked() {
+                    if let Some(old) = lock(&existing.value).as_mut() {
+                        let value = lock(&node.value).take().unwrap();
+                        return Some(mem::replace(old, value));
+                    }
+                }
+                // Being removed, wait until it is unlinked
+                thread::yield_now();
+                continue;
+            }
+            if self.link(&node, 0, &position) {
+                break;
+            }
+        }
+        self.len.fetch_add(1, Ordering::SeqCst);
+
+        for level in 1..node.next.len() {
+            while !self.link(&node, level, &self.find(key)) {}
+        }
+        None
+    }
+
+    /// Removes the entry and returns its value
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let position = self.find(key);
+        let node = position.successors[0].as_ref().filter(|n| n.key() == key)?.clone();
+        let _linking = lock(&node.linking);
+        if node.marked.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        let value = lock(&node.value).take();
+
+        for level in (0..node.next.len()).rev() {
+            let mut position = self.find(key);
+            while !self.unlink(&node, level, &position) {
+                position = self.find(key);
+            }
+        }
+        self.len.fetch_sub(1, Ordering::SeqCst);
+        value
+    }
+
+    /// Entries in ascending key order. Changes made by other threads during
+    /// the walk may or may not be included.
+    pub fn snapshot(&self) -> Vec<(K, V)> {
+        let mut entries = vec![];
+        let mut current = self.head.next(0);
+        while let Some(node) = current {
+            if !node.is_marked() {
+                if let Some(value) = lock(&node.value).clone() {
+                    entries.push((node.key().clone(), value));
+                }
+            }
+            current = node.next(0);
+        }
+        entries
+    }
+
+    fn find(&self, key: &K) -> Position<K, V> {
+        let mut predecessors = vec![self.head.clone(); MAX_LEVEL];
+        let mut successors = vec![None; MAX_LEVEL];
+        let mut predecessor = self.head.clone();
+        for level in (0..MAX_LEVEL).rev() {
+            let mut successor = predecessor.next(level);
+            while let Some(node) = successor.clone().filter(|node| node.key() < key) {
+                successor = node.next(level);
+                predecessor = node;
+            }
+            predecessors[level] = predecessor.clone();
+            successors[level] = successor;
+        }
+        Position { predecessors, successors }
+    }
+
+    /// First node with a key that is not smaller than `key`, if it matches
+    fn find_node(&self, key: &K) -> Option<Arc<SkipNode<K, V>>> {
+        let mut predecessor = self.head.clone();
+        for level in (0..MAX_LEVEL).rev() {
+            while let Some(node) = predecessor.next(level) {
+                match node.key().cmp(key) {
+                    std::cmp::Ordering::Less => predecessor = node,
+                    std::cmp::Ordering::Equal => return Some(node),
+                    std::cmp::Ordering::Greater => break,
+                }
+            }
+        }
+        None
+    }
+
+    /// Links the node on one level if the search result is still valid
+    fn link(&self, node: &Arc<SkipNode<K, V>>, level: usize, position: &Position<K, V>) -> bool {
+        let predecessor = &position.predecessors[level];
+        let mut next = lock(&predecessor.next[level]);
+        if predecessor.is_marked() || !same_node(&next, &position.successors[level]) {
+            return false;
+        }
+        *lock(&node.next[level]) = next.clone();
+        *next = Some(node.clone());
+        true
+    }
+
+    /// Unlinks the node on one level if the search result is still valid
+    fn unlink(&self, node: &Arc<SkipNode<K, V>>, level: usize, position: &Position<K, V>) -> bool {
+        let predecessor = &position.predecessors[level];
+        let mut next = lock(&predecessor.next[level]);
+        if predecessor.is_marked() || !same_node(&next, &Some(node.clone())) {
+            return false;
+        }
+        *next = node.next(level);
+        true
+    }
+
+    /// Height with probability 1/2 of every extra level
+    fn random_height(&self) -> usize {
+        let mut state = self.seed.fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed);
+        state = (state ^ (state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        state ^= state >> 31;
+        (state.trailing_ones() as usize + 1).min(MAX_LEVEL)
+    }
+}
+
+/// A panic in another thread can't leave a link half changed, so a
+/// poisoned lock is still safe to use
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn same_node<K, V>(a: &Link<K, V>, b: &Link<K, V>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for ConcurrentSkipListMap<K, V> {
+    fn default() -> Self {
+        ConcurrentSkipListMap::new()
+    }
+}
+
+/// Frees nodes in a loop along the bottom level, recursive drops could
+/// overflow the stack on long lists
+/// Every call goes through the locking inherent methods, so the trait works
+/// on a map shared with other threads as well
+impl<K: Ord + Clone, V: Clone> Map<K, V> for ConcurrentSkipListMap<K, V> {
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        ConcurrentSkipListMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        ConcurrentSkipListMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        ConcurrentSkipListMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        ConcurrentSkipListMap::len(self)
+    }
+
+    /// Walks a snapshot, see `snapshot`
+    fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.snapshot().into_iter()
+    }
+}
+
+impl<K, V> Drop for ConcurrentSkipListMap<K, V> {
+    fn drop(&mut self) {
+        let mut current = lock(&self.head.next[0]).take();
+        for level in 1..MAX_LEVEL {
+            lock(&self.head.next[level]).take();
+        }
+        while let Some(node) = current {
+            current = lock(&node.next[0]).take();
+            for level in 1..node.next.len() {
+                lock(&node.next[level]).take();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_map_is_created_it_is_empty() {
+        let map = ConcurrentSkipListMap::<i32, i32>::new();
+
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.remove(&1), None);
+    }
+
+    #[test]
+    fn test_insert_get_and_update() {
+        let map = ConcurrentSkipListMap::new();
+
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 20), Some(2));
+
+        assert_eq!(map.get(&"b"), Some(20));
+        assert!(map.contains_key(&"a"));
+        assert!(!map.contains_key(&"c"));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_is_ordered() {
+        let map = ConcurrentSkipListMap::new();
+
+        for key in [5, 3, 9, 1, 7] {
+            map.insert(key, key * 10);
+        }
+        map.remove(&3);
+
+        assert_eq!(map.snapshot(), vec![(1, 10), (5, 50), (7, 70), (9, 90)]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let map: ConcurrentSkipListMap<u32, u32> = ConcurrentSkipListMap::new();
+        for key in 0..1000 {
+            map.insert(key, key);
+        }
+
+        for key in (0..1000).step_by(2) {
+            assert_eq!(map.remove(&key), Some(key));
+        }
+
+        assert_eq!(map.remove(&0), None);
+        assert_eq!(map.len(), 500);
+        assert!(map.snapshot().iter().all(|&(key, _)| key % 2 == 1));
+    }
+
+    #[test]
+    fn test_concurrent_inserts() {
+        let map = ConcurrentSkipListMap::new();
+
+        thread::scope(|scope| {
+            for thread in 0..4 {
+                let map = &map;
+                scope.spawn(move || {
+                    for i in 0..2000 {
+                        map.insert(i * 4 + thread, thread);
+                    }
+                });
+            }
+        });
+
+        let keys: Vec<u32> = map.snapshot().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, (0..8000).collect::<Vec<_>>());
+        assert_eq!(map.len(), 8000);
+    }
+
+    #[test]
+    fn test_concurrent_inserts_and_removes_of_the_same_keys() {
+        let map = ConcurrentSkipListMap::new();
+
+        thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let map = &map;
+                scope.spawn(move || {
+                    let mut state = thread;
+                    for _ in 0..5000 {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        let key = (state >> 33) % 64;
+                        if state.is_multiple_of(3) {
+                            map.remove(&key);
+                        } else {
+                            map.insert(key, thread);
+                        }
+                    }
+                });
+            }
+        });
+
+        let snapshot = map.snapshot();
+        assert_eq!(snapshot.len(), map.len());
+        assert!(snapshot.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(snapshot.iter().all(|(key, _)| map.contains_key(key)));
+    }
+
+    #[test]
+    fn test_long_map_drops_without_overflow() {
+        let map = ConcurrentSkipListMap::new();
+
+        for key in 0..50_000 {
+            map.insert(key, ());
+        }
+
+        drop(map);
+    }
+}
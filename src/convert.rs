@@ -0,0 +1,216 @@
+//! Conversions between the crate's structures and std collections
+//! `From` and `Into` between the singly linked list, Stack, Queue, the crate
+//! HashMap and `Vec`, `VecDeque` and `std::collections::HashMap`. Sequences
+//! keep the order the items went in: the first node of a list, the bottom of
+//! a stack and the front of a queue all become the first item of a `Vec`,
+//! and the other way round. So a `Vec` turned into a stack pops its last
+//! item first, and a stack turned into a queue gives back its oldest item
+//! first. Hash maps keep every pair, in no particular order.
+
+use std::collections::{ HashMap as StdHashMap, VecDeque };
+use std::fmt::{ Debug, Display };
+use std::hash::Hash;
+
+use crate::hash_map::HashMap;
+use crate::queue::Queue;
+use crate::singly_linked_list::SinglyLinkedList;
+use crate::stack::Stack;
+
+/// Pops from the top and reverses, so the bottom comes first
+fn stack_into_vec<T>(mut stack: Stack<T>) -> Vec<T> {
+    let mut items: Vec<T> = std::iter::from_fn(|| stack.pop()).collect();
+    items.reverse();
+    items
+}
+
+impl<T: PartialEq + Debug + Clone + Display> From<Vec<T>> for SinglyLinkedList<T> {
+    fn from(items: Vec<T>) -> Self {
+        items.into_iter().collect()
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Display> From<SinglyLinkedList<T>> for Vec<T> {
+    fn from(list: SinglyLinkedList<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Display> From<VecDeque<T>> for SinglyLinkedList<T> {
+    fn from(items: VecDeque<T>) -> Self {
+        items.into_iter().collect()
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Display> From<SinglyLinkedList<T>> for VecDeque<T> {
+    fn from(list: SinglyLinkedList<T>) -> Self {
+        list.into_iter().collect()
+    }
+}
+
+impl<T> From<Vec<T>> for Stack<T> {
+    fn from(items: Vec<T>) -> Self {
+        let mut stack = Stack::new();
+        items.into_iter().for_each(|item| stack.push(item));
+        stack
+    }
+}
+
+impl<T> From<Stack<T>> for Vec<T> {
+    fn from(stack: Stack<T>) -> Self {
+        stack_into_vec(stack)
+    }
+}
+
+impl<T: Copy> From<Vec<T>> for Queue<T> {
+    fn from(items: Vec<T>) -> Self {
+        let mut queue = Queue::new();
+        items.into_iter().for_each(|item| queue.add(item));
+        queue
+    }
+}
+
+impl<T: Copy> From<Queue<T>> for Vec<T> {
+    fn from(queue: Queue<T>) -> Self {
+        queue.iter().copied().collect()
+    }
+}
+
+impl<T: Copy> From<VecDeque<T>> for Queue<T> {
+    fn from(items: VecDeque<T>) -> Self {
+        Vec::from(items).into()
+    }
+}
+
+impl<T: Copy> From<Queue<T>> for VecDeque<T> {
+    fn from(queue: Queue<T>) -> Self {
+        queue.iter().copied().collect()
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Display> From<Stack<T>> for SinglyLinkedList<T> {
+    fn from(stack: Stack<T>) -> Self {
+        stack_into_vec(stack).into()
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Display> From<SinglyLinkedList<T>> for Stack<T> {
+    fn from(list: SinglyLinkedList<T>) -> Self {
+        let mut stack = Stack::new();
+        list.into_iter().for_each(|item| stack.push(item));
+        stack
+    }
+}
+
+impl<T: Copy + PartialEq + Debug + Display> From<Queue<T>> for SinglyLinkedList<T> {
+    fn from(queue: Queue<T>) -> Self {
+        Vec::from(queue).into()
+    }
+}
+
+impl<T: Copy + PartialEq + Debug + Display> From<SinglyLinkedList<T>> for Queue<T> {
+    fn from(list: SinglyLinkedList<T>) -> Self {
+        let mut queue = Queue::new();
+        list.into_iter().for_each(|item| queue.add(item));
+        queue
+    }
+}
+
+impl<T: Copy> From<Stack<T>> for Queue<T> {
+    fn from(stack: Stack<T>) -> Self {
+        stack_into_vec(stack).into()
+    }
+}
+
+impl<T: Copy> From<Queue<T>> for Stack<T> {
+    fn from(queue: Queue<T>) -> Self {
+        Vec::from(queue).into()
+    }
+}
+
+impl<K, V> From<StdHashMap<K, V>> for HashMap<K, V>
+where
+    K: Hash + Eq + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn from(pairs: StdHashMap<K, V>) -> Self {
+        let mut map = HashMap::new();
+        for (key, value) in pairs {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for StdHashMap<K, V>
+where
+    K: Hash + Eq + Clone + Debug,
+    V: Clone + Debug,
+{
+    fn from(map: HashMap<K, V>) -> Self {
+        map.iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_empty_conversions_stay_empty() {
+        let list = SinglyLinkedList::<i32>::from(Vec::new());
+        let stack: Stack<i32> = Vec::new().into();
+
+        assert!(list.is_empty());
+        assert!(Vec::from(stack).is_empty());
+        assert!(VecDeque::from(Queue::<i32>::new()).is_empty());
+    }
+
+    #[test]
+    fn test_list_round_trips_in_order() {
+        let list = SinglyLinkedList::from(vec![1, 2, 3]);
+
+        assert_eq!(list.iter().next(), Some(&1));
+        assert_eq!(list.len(), 3);
+        assert_eq!(Vec::from(list.clone()), vec![1, 2, 3]);
+        assert_eq!(VecDeque::from(list), VecDeque::from([1, 2, 3]));
+    }
+
+    #[test]
+    fn test_stack_keeps_the_bottom_first() {
+        let mut stack = Stack::from(vec![1, 2, 3]);
+
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(Vec::from(stack), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_queue_keeps_the_front_first() {
+        let mut queue = Queue::from(VecDeque::from([1, 2, 3]));
+
+        assert_eq!(queue.remove(), Some(1));
+        assert_eq!(Vec::from(queue), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_between_crate_structures() {
+        let stack = Stack::from(vec![1, 2, 3]);
+
+        let mut queue = Queue::from(stack);
+        assert_eq!(queue.remove(), Some(1));
+        let list = SinglyLinkedList::from(queue);
+        assert_eq!(Vec::from(list.clone()), vec![2, 3]);
+        let mut stack = Stack::from(list);
+        assert_eq!(stack.pop(), Some(3));
+    }
+
+    #[test]
+    fn test_hash_maps_keep_every_pair() {
+        let std_map: StdHashMap<&str, i32> = [("a", 1), ("b", 2)].into_iter().collect();
+
+        let map = HashMap::from(std_map.clone());
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("b"), Some(2));
+        assert_eq!(StdHashMap::from(map), std_map);
+    }
+}
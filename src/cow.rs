@@ -0,0 +1,217 @@
+//! Copy-on-write wrappers
+//! `CowList` and `CowMap` hold the singly linked list and the hash map behind
+//! an `Arc`. Cloning a wrapper, or taking a `snapshot`, only bumps the count,
+//! so keeping old versions around for undo or for a diff costs nothing until
+//! they are changed. The first change made through a shared wrapper clones
+//! the whole structure once, after that the wrapper owns its copy and changes
+//! it in place. Reads go through `Deref` and never clone. `Arc` rather than
+//! `Rc`, so a snapshot can be handed to another thread.
+
+use std::fmt::{ Debug, Display };
+use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::collection::Collection;
+use crate::hash_map::HashMap;
+use crate::singly_linked_list::SinglyLinkedList;
+
+#[derive(Clone, Debug)]
+pub struct CowList<T: Clone> {
+    list: Arc<SinglyLinkedList<T>>,
+}
+
+impl<T: PartialEq + Debug + Clone + Display> CowList<T> {
+    pub fn new() -> Self {
+        CowList { list: Arc::new(SinglyLinkedList::new()) }
+    }
+
+    /// Another handle on the same list, the two part on the next change
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// True while another wrapper still sees the same list
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.list) > 1
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.list, &other.list)
+    }
+
+    /// The list to change in place, cloned first if it is shared
+    pub fn make_mut(&mut self) -> &mut SinglyLinkedList<T> {
+        Arc::make_mut(&mut self.list)
+    }
+
+    pub fn append(&mut self, data: T) {
+        self.make_mut().append(data);
+    }
+
+    pub fn prepend(&mut self, data: T) {
+        self.make_mut().prepend(data);
+    }
+
+    /// Doesn't clone a shared list that is empty
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.list.is_empty() {
+            return None;
+        }
+        self.make_mut().try_delete_first().ok()
+    }
+
+    /// The list, cloned only if a snapshot still holds it
+    pub fn into_inner(self) -> SinglyLinkedList<T> {
+        Arc::unwrap_or_clone(self.list)
+    }
+}
+
+impl<T: PartialEq + Debug + Clone + Display> Default for CowList<T> {
+    fn default() -> Self {
+        CowList::new()
+    }
+}
+
+impl<T: Clone> Deref for CowList<T> {
+    type Target = SinglyLinkedList<T>;
+
+    fn deref(&self) -> &SinglyLinkedList<T> {
+        &self.list
+    }
+}
+
+impl<T: Clone> From<SinglyLinkedList<T>> for CowList<T> {
+    fn from(list: SinglyLinkedList<T>) -> Self {
+        CowList { list: Arc::new(list) }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CowMap<K, V> {
+    map: Arc<HashMap<K, V>>,
+}
+
+impl<K: Hash + Clone + PartialEq + Debug, V: Clone + Debug> CowMap<K, V> {
+    pub fn new() -> Self {
+        CowMap { map: Arc::new(HashMap::new()) }
+    }
+
+    /// Another handle on the same map, the two part on the next change
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// True while another wrapper still sees the same map
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.map) > 1
+    }
+
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.map, &other.map)
+    }
+
+    /// The map to change in place, cloned first if it is shared
+    pub fn make_mut(&mut self) -> &mut HashMap<K, V> {
+        Arc::make_mut(&mut self.map)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.make_mut().insert(key, value)
+    }
+
+    /// Doesn't clone a shared map that has no such key
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        if !self.map.contains(&key) {
+            return None;
+        }
+        self.make_mut().remove(key)
+    }
+
+    /// The map, cloned only if a snapshot still holds it
+    pub fn into_inner(self) -> HashMap<K, V> {
+        Arc::unwrap_or_clone(self.map)
+    }
+}
+
+impl<K: Hash + Clone + PartialEq + Debug, V: Clone + Debug> Default for CowMap<K, V> {
+    fn default() -> Self {
+        CowMap::new()
+    }
+}
+
+impl<K, V> Deref for CowMap<K, V> {
+    type Target = HashMap<K, V>;
+
+    fn deref(&self) -> &HashMap<K, V> {
+        &self.map
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for CowMap<K, V> {
+    fn from(map: HashMap<K, V>) -> Self {
+        CowMap { map: Arc::new(map) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_shares_until_the_first_change() {
+        let mut list = CowList::new();
+        list.append(1);
+        list.append(2);
+
+        let snapshot = list.snapshot();
+        assert!(list.ptr_eq(&snapshot));
+        list.append(3);
+
+        assert!(!list.ptr_eq(&snapshot));
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(list.len(), 3);
+        assert!(!list.is_shared());
+    }
+
+    #[test]
+    fn when_not_shared_changes_happen_in_place() {
+        let mut list = CowList::new();
+        list.append(1);
+        let before: *const SinglyLinkedList<i32> = &*list;
+
+        list.prepend(0);
+
+        assert!(std::ptr::eq(before, &*list));
+        assert_eq!(list.pop_front(), Some(0));
+    }
+
+    #[test]
+    fn test_snapshots_of_a_map_keep_old_versions() {
+        let mut map = CowMap::new();
+        map.insert("a", 1);
+        let first = map.snapshot();
+
+        map.insert("a", 2);
+        map.insert("b", 3);
+        let second = map.snapshot();
+        map.remove("b");
+
+        assert_eq!(first.get("a"), Some(1));
+        assert_eq!(second.get("b"), Some(3));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.into_inner().get("a"), Some(2));
+    }
+
+    #[test]
+    fn when_nothing_changes_a_shared_map_is_not_cloned() {
+        let mut map = CowMap::from(HashMap::new());
+        map.insert(1, 'x');
+        let snapshot = map.snapshot();
+
+        assert_eq!(map.remove(2), None);
+
+        assert!(map.ptr_eq(&snapshot));
+        assert!(map.is_shared());
+    }
+}
@@ -0,0 +1,74 @@
+//! Cursor traits for list-like structures
+//! A cursor points at one item of a list and walks it front to back, so an
+//! algorithm that edits a list in one pass can be written once against the
+//! traits. After the last item the cursor is at the end, where `current` is
+//! `None`, `move_next` does nothing and `insert_after` appends. Removing the
+//! current item moves the cursor to the one after it.
+
+pub trait Cursor {
+    type Item;
+
+    /// None at the end
+    fn current(&self) -> Option<&Self::Item>;
+
+    fn move_next(&mut self);
+}
+
+pub trait CursorMut: Cursor {
+    fn current_mut(&mut self) -> Option<&mut Self::Item>;
+
+    /// Links `item` after the current one, the cursor doesn't move
+    fn insert_after(&mut self, item: Self::Item);
+
+    fn remove_current(&mut self) -> Option<Self::Item>;
+}
+
+/// Keeps the items `keep` returns true for, in one pass from the cursor on
+pub fn retain<C: CursorMut>(mut cursor: C, mut keep: impl FnMut(&C::Item) -> bool) {
+    while let Some(item) = cursor.current() {
+        if keep(item) {
+            cursor.move_next();
+        } else {
+            cursor.remove_current();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::singly_linked_list::SinglyLinkedList;
+
+    /// Puts a copy of every item right after it
+    fn duplicate_each<C: CursorMut<Item = i32>>(mut cursor: C) {
+        while let Some(&item) = cursor.current() {
+            cursor.insert_after(item);
+            cursor.move_next();
+            cursor.move_next();
+        }
+    }
+
+    #[test]
+    fn test_generic_algorithms_on_a_list() {
+        let mut list = SinglyLinkedList::from(vec![1, 2, 3, 4]);
+
+        retain(list.cursor_mut(), |item| item % 2 == 0);
+        duplicate_each(list.cursor_mut());
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(Vec::from(list), vec![2, 2, 4, 4]);
+    }
+
+    #[test]
+    fn when_at_the_end_insert_after_appends() {
+        let mut list = SinglyLinkedList::from(vec![1]);
+        let mut cursor = list.cursor_mut();
+
+        cursor.move_next();
+        cursor.insert_after(2);
+        cursor.insert_after(3);
+
+        assert_eq!(cursor.current(), None);
+        assert_eq!(Vec::from(list), vec![1, 2, 3]);
+    }
+}
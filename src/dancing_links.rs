@@ -0,0 +1,345 @@
+//! Dancing Links (DLX) exact cover solver
+//! Exact cover: pick rows of a 0/1 matrix so that every column has a 1 in
+//! exactly one picked row. Knuth's Algorithm X tries the rows of the column
+//! with the fewest candidates, covers every column of the picked row and
+//! recurses. The matrix is a toroidal grid of doubly linked nodes, one per
+//! 1, so covering a column unlinks its nodes in O(1) each, and uncovering
+//! them again is the same steps in reverse: an unlinked node still points
+//! at its neighbors, which "dance" back into place.
+//! Nodes live in vectors and link to each other by index.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+/// Node 0 is the root, nodes 1..=columns are the column headers
+const ROOT: usize = 0;
+
+#[derive(Debug, Clone)]
+pub struct DancingLinks {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Header node of the node's column
+    header: Vec<usize>,
+    /// Row the node belongs to, unused for headers
+    row: Vec<usize>,
+    /// Number of nodes in every column, by header node
+    size: Vec<usize>,
+    row_count: usize,
+}
+
+impl DancingLinks {
+    /// Creates a matrix with the given number of columns and no rows
+    pub fn new(columns: usize) -> Self {
+        let nodes = columns + 1;
+        let mut links = DancingLinks {
+            left: (0..nodes).map(|node| (node + nodes - 1) % nodes).collect(),
+            right: (0..nodes).map(|node| (node + 1) % nodes).collect(),
+            up: (0..nodes).collect(),
+            down: (0..nodes).collect(),
+            header: (0..nodes).collect(),
+            row: vec![usize::MAX; nodes],
+            size: vec![0; nodes],
+            row_count: 0,
+        };
+        links.header[ROOT] = ROOT;
+        links
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.size.len() - 1
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Adds a row with 1s in the given columns and returns its index
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        let row = self.row_count;
+        self.row_count += 1;
+        let mut columns = columns.to_vec();
+        columns.sort_unstable();
+        columns.dedup();
+
+        let mut first: Option<usize> = None;
+        for column in columns {
+            assert!(
+                column < self.column_count(),
+                "Column {} out of bounds for {} columns",
+                column,
+                self.column_count()
+            );
+            let header = column + 1;
+            let node = self.left.len();
+            // Append at the bottom of the column
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.down[self.up[header]] = node;
+            self.up[header] = node;
+            self.header.push(header);
+            self.row.push(row);
+            self.size[header] += 1;
+            // Append at the end of the row
+            match first {
+                Some(first) => {
+                    self.left.push(self.left[first]);
+                    self.right.push(first);
+                    self.right[self.left[first]] = node;
+                    self.left[first] = node;
+                }
+                None => {
+                    self.left.push(node);
+                    self.right.push(node);
+                    first = Some(node);
+                }
+            }
+        }
+        check_invariants!(self);
+        row
+    }
+
+    /// Rows of one exact cover, in the order they were picked
+    pub fn solve(&mut self) -> Option<Vec<usize>> {
+        self.solve_all(1).pop()
+    }
+
+    /// Up to `limit` exact covers
+    pub fn solve_all(&mut self, limit: usize) -> Vec<Vec<usize>> {
+        let mut solutions = vec![];
+        if limit > 0 {
+            self.search(&mut vec![], &mut solutions, limit);
+        }
+        check_invariants!(self);
+        solutions
+    }
+
+    pub fn count_solutions(&mut self) -> usize {
+        self.solve_all(usize::MAX).len()
+    }
+
+    fn search(&mut self, picked: &mut Vec<usize>, solutions: &mut Vec<Vec<usize>>, limit: usize) {
+        if self.right[ROOT] == ROOT {
+            solutions.push(picked.clone());
+            return;
+        }
+        let column = self.smallest_column();
+        if self.size[column] == 0 {
+            return;
+        }
+
+        self.cover(column);
+        let mut row_node = self.down[column];
+        while row_node != column && solutions.len() < limit {
+            picked.push(self.row[row_node]);
+            let mut node = self.right[row_node];
+            while node != row_node {
+                self.cover(self.header[node]);
+                node = self.right[node];
+            }
+
+            self.search(picked, solutions, limit);
+
+            let mut node = self.left[row_node];
+            while node != row_node {
+                self.uncover(self.header[node]);
+                node = self.left[node];
+            }
+            picked.pop();
+            row_node = self.down[row_node];
+        }
+        self.uncover(column);
+    }
+
+    /// Column with the fewest nodes, which keeps the search tree narrow
+    fn smallest_column(&self) -> usize {
+        let mut best = self.right[ROOT];
+        let mut column = self.right[best];
+        while column != ROOT {
+            if self.size[column] < self.size[best] {
+                best = column;
+            }
+            column = self.right[column];
+        }
+        best
+    }
+
+    /// Unlinks the column header and every row that has a 1 in the column
+    fn cover(&mut self, column: usize) {
+        self.right[self.left[column]] = self.right[column];
+        self.left[self.right[column]] = self.left[column];
+        let mut row_node = self.down[column];
+        while row_node != column {
+            let mut node = self.right[row_node];
+            while node != row_node {
+                self.down[self.up[node]] = self.down[node];
+                self.up[self.down[node]] = self.up[node];
+                self.size[self.header[node]] -= 1;
+                node = self.right[node];
+            }
+            row_node = self.down[row_node];
+        }
+    }
+
+    /// Exactly reverses `cover`
+    fn uncover(&mut self, column: usize) {
+        let mut row_node = self.up[column];
+        while row_node != column {
+            let mut node = self.left[row_node];
+            while node != row_node {
+                self.size[self.header[node]] += 1;
+                self.down[self.up[node]] = node;
+                self.up[self.down[node]] = node;
+                node = self.left[node];
+            }
+            row_node = self.up[row_node];
+        }
+        self.right[self.left[column]] = column;
+        self.left[self.right[column]] = column;
+    }
+}
+
+/// Checks the links of every node are mirrored by its neighbors and every
+/// column, with nothing covered, holds as many nodes as its size says
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for DancingLinks {
+    fn assert_invariants(&self) {
+        let nodes = self.left.len();
+        for links in [&self.right, &self.up, &self.down, &self.header, &self.row] {
+            assert_eq!(links.len(), nodes, "Node vectors have different lengths");
+        }
+        for node in 0..nodes {
+            assert_eq!(self.right[self.left[node]], node, "Left and right links disagree");
+            assert_eq!(self.down[self.up[node]], node, "Up and down links disagree");
+        }
+        let mut header = self.right[ROOT];
+        let mut columns = 0;
+        while header != ROOT {
+            let mut count = 0;
+            let mut node = self.down[header];
+            while node != header {
+                assert_eq!(self.header[node], header, "Node is in the wrong column");
+                count += 1;
+                node = self.down[node];
+            }
+            assert_eq!(count, self.size[header], "Column size doesn't match its nodes");
+            columns += 1;
+            header = self.right[header];
+        }
+        assert_eq!(columns, self.column_count(), "A column is still covered");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sudoku as exact cover: a row is a digit in a cell, and the 324 columns
+    /// say every cell, and every digit in every row, column and box, is
+    /// used exactly once. Givens only get the row of their digit.
+    fn solve_sudoku(grid: &str) -> Option<String> {
+        let cells: Vec<u32> = grid.chars().filter_map(|c| c.to_digit(10)).collect();
+        assert_eq!(cells.len(), 81);
+        let mut links = DancingLinks::new(324);
+        let mut choices = vec![];
+        for (cell, given) in cells.iter().enumerate() {
+            let (row, column) = (cell / 9, cell % 9);
+            let square = row / 3 * 3 + column / 3;
+            for digit in 1..=9usize {
+                if *given != 0 && *given as usize != digit {
+                    continue;
+                }
+                let d = digit - 1;
+                links.add_row(&[
+                    cell,
+                    81 + row * 9 + d,
+                    162 + column * 9 + d,
+                    243 + square * 9 + d,
+                ]);
+                choices.push((cell, digit));
+            }
+        }
+
+        let mut solved = vec![0; 81];
+        for row in links.solve()? {
+            let (cell, digit) = choices[row];
+            solved[cell] = digit;
+        }
+        Some(solved.iter().map(|digit| digit.to_string()).collect())
+    }
+
+    #[test]
+    fn when_matrix_has_no_columns_the_empty_cover_solves_it() {
+        let mut links = DancingLinks::new(0);
+
+        assert_eq!(links.solve(), Some(vec![]));
+        assert_eq!(links.row_count(), 0);
+    }
+
+    #[test]
+    fn test_knuths_example() {
+        // The matrix from Knuth's paper, with the unique cover rows 0, 3 and 4
+        let mut links = DancingLinks::new(7);
+        links.add_row(&[2, 4, 5]);
+        links.add_row(&[0, 3, 6]);
+        links.add_row(&[1, 2, 5]);
+        links.add_row(&[0, 3]);
+        links.add_row(&[1, 6]);
+        links.add_row(&[3, 4, 6]);
+
+        let mut solution = links.solve().unwrap();
+        solution.sort();
+
+        assert_eq!(solution, vec![0, 3, 4]);
+        assert_eq!(links.count_solutions(), 1);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let mut links = DancingLinks::new(3);
+        links.add_row(&[0, 1]);
+        links.add_row(&[1, 2]);
+
+        assert_eq!(links.solve(), None);
+    }
+
+    #[test]
+    fn test_counts_all_solutions_and_restores_the_matrix() {
+        // Every row covers one column, so each column has two choices
+        let mut links = DancingLinks::new(3);
+        for column in [0, 0, 1, 1, 2, 2] {
+            links.add_row(&[column]);
+        }
+
+        assert_eq!(links.count_solutions(), 8);
+        assert_eq!(links.solve_all(3).len(), 3);
+        assert_eq!(links.count_solutions(), 8, "Search leaves the links as they were");
+    }
+
+    #[test]
+    fn test_sudoku() {
+        let puzzle = concat!(
+            "530070000", "600195000", "098000060",
+            "800060003", "400803001", "700020006",
+            "060000280", "000419005", "000080079",
+        );
+
+        let solution = solve_sudoku(puzzle).unwrap();
+
+        let expected = concat!(
+            "534678912", "672195348", "198342567",
+            "859761423", "426853791", "713924856",
+            "961537284", "287419635", "345286179",
+        );
+        assert_eq!(solution, expected);
+    }
+
+    #[test]
+    fn test_sudoku_with_conflicting_givens_has_no_solution() {
+        let puzzle = format!("55{}", "0".repeat(79));
+
+        assert_eq!(solve_sudoku(&puzzle), None);
+    }
+}
@@ -0,0 +1,58 @@
+//! Error type shared by the collections
+//! Fallible methods return `Result<T>`, which is `Result<T, Error>`. Their
+//! panicking counterparts stay available and panic with the messages they
+//! always had.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The operation needs at least one item
+    EmptyCollection,
+    /// No item matches the one that was asked for
+    NotFound,
+    /// The collection has no room for another item
+    CapacityExceeded,
+    IndexOutOfBounds { index: usize, len: usize },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyCollection => write!(f, "the collection is empty"),
+            Error::NotFound => write!(f, "the item was not found"),
+            Error::CapacityExceeded => write!(f, "the collection is full"),
+            Error::IndexOutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = Error::IndexOutOfBounds { index: 4, len: 2 };
+
+        assert_eq!(error.to_string(), "index 4 is out of bounds for length 2");
+        assert_eq!(Error::EmptyCollection.to_string(), "the collection is empty");
+    }
+
+    #[test]
+    fn test_works_as_boxed_std_error() {
+        fn first_of_empty() -> std::result::Result<(), Box<dyn std::error::Error>> {
+            Err(Error::NotFound)?
+        }
+
+        let error = first_of_empty().unwrap_err();
+
+        assert_eq!(error.downcast_ref::<Error>(), Some(&Error::NotFound));
+    }
+}
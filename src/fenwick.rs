@@ -0,0 +1,245 @@
+//! Fenwick Tree (Binary Indexed Tree) implementation
+//! Array where the cell at (1-based) index i stores the sum of the last
+//! `i & -i` items up to i. Walking down by clearing the lowest set bit gives
+//! a prefix sum in O(log n), walking up by adding it updates every cell that
+//! covers an item, also in O(log n).
+
+use std::ops::{ Add, Range, Sub };
+
+use crate::heap_size::HeapSize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FenwickTree<T> {
+    /// 1-based, `tree[0]` is unused
+    tree: Vec<T>,
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> FenwickTree<T> {
+    /// Creates a tree of `size` items, all equal to zero (`T::default()`)
+    pub fn new(size: usize) -> Self {
+        FenwickTree { tree: vec![T::default(); size + 1] }
+    }
+
+    /// Builds the tree in O(n) by pushing every cell into its parent once
+    pub fn from_slice(items: &[T]) -> Self {
+        let mut tree = vec![T::default()];
+        tree.extend_from_slice(items);
+        for index in 1..tree.len() {
+            let parent = index + lowest_bit(index);
+            if parent < tree.len() {
+                tree[parent] = tree[parent] + tree[index];
+            }
+        }
+        FenwickTree { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds `delta` to the item at `index`
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len(), "Index {} out of bounds for length {}", index, self.len());
+        let mut position = index + 1;
+        while position < self.tree.len() {
+            self.tree[position] = self.tree[position] + delta;
+            position += lowest_bit(position);
+        }
+    }
+
+    /// Sum of the first `end` items, that is of `0..end`
+    pub fn prefix_sum(&self, end: usize) -> T {
+        assert!(end <= self.len(), "End {} out of bounds for length {}", end, self.len());
+        let mut sum = T::default();
+        let mut position = end;
+        while position > 0 {
+            sum = sum + self.tree[position];
+            position -= lowest_bit(position);
+        }
+        sum
+    }
+
+    pub fn range_sum(&self, range: Range<usize>) -> T {
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        self.range_sum(index..index + 1)
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        let current = self.get(index);
+        self.add(index, value - current);
+    }
+}
+
+/// Fenwick tree over the differences between neighboring items.
+/// Adding to a range only touches its two ends, and the value of an item
+/// is the prefix sum of the differences up to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeUpdateFenwickTree<T> {
+    differences: FenwickTree<T>,
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> RangeUpdateFenwickTree<T> {
+    pub fn new(size: usize) -> Self {
+        RangeUpdateFenwickTree { differences: FenwickTree::new(size) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.differences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Adds `delta` to every item in the range in O(log n)
+    pub fn range_add(&mut self, range: Range<usize>, delta: T) {
+        if range.start >= range.end {
+            return;
+        }
+        self.differences.add(range.start, delta);
+        if range.end < self.len() {
+            self.differences.add(range.end, T::default() - delta);
+        }
+    }
+
+    /// Value of a single item in O(log n)
+    pub fn point_query(&self, index: usize) -> T {
+        self.differences.prefix_sum(index + 1)
+    }
+}
+
+/// Value of the lowest set bit, `i & -i` in two's complement
+fn lowest_bit(index: usize) -> usize {
+    index & index.wrapping_neg()
+}
+
+impl<T: HeapSize> HeapSize for FenwickTree<T> {
+    fn heap_size(&self) -> usize {
+        self.tree.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for RangeUpdateFenwickTree<T> {
+    fn heap_size(&self) -> usize {
+        self.differences.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_tree_is_created_all_sums_are_zero() {
+        let tree = FenwickTree::<i64>::new(8);
+
+        assert_eq!(tree.len(), 8);
+        assert_eq!(tree.prefix_sum(8), 0);
+        assert_eq!(tree.range_sum(2..5), 0);
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = FenwickTree::<i32>::new(0);
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.prefix_sum(0), 0);
+    }
+
+    #[test]
+    fn test_add_and_prefix_sum() {
+        let mut tree = FenwickTree::new(5);
+
+        tree.add(0, 3);
+        tree.add(2, 4);
+        tree.add(4, 10);
+
+        assert_eq!(tree.prefix_sum(1), 3);
+        assert_eq!(tree.prefix_sum(3), 7);
+        assert_eq!(tree.prefix_sum(5), 17);
+    }
+
+    #[test]
+    fn test_from_slice_matches_naive_sums() {
+        let items: Vec<i64> = (0..37).map(|i| (i * 13) % 11 - 5).collect();
+
+        let tree = FenwickTree::from_slice(&items);
+
+        for end in 0..=items.len() {
+            assert_eq!(tree.prefix_sum(end), items[..end].iter().sum::<i64>());
+        }
+    }
+
+    #[test]
+    fn test_range_sum_and_get() {
+        let tree = FenwickTree::from_slice(&[5, 1, 4, 2, 8, 6]);
+
+        assert_eq!(tree.range_sum(1..4), 7);
+        assert_eq!(tree.range_sum(3..3), 0);
+        assert_eq!(tree.get(4), 8);
+    }
+
+    #[test]
+    fn test_set_replaces_item() {
+        let mut tree = FenwickTree::from_slice(&[1.5, 2.5, 3.0]);
+
+        tree.set(1, 10.0);
+
+        assert_eq!(tree.get(1), 10.0);
+        assert_eq!(tree.prefix_sum(3), 14.5);
+    }
+
+    #[test]
+    fn test_random_updates_match_naive_array() {
+        let mut items = vec![0i64; 50];
+        let mut tree = FenwickTree::new(50);
+        let mut state = 12345u64;
+
+        for _ in 0..500 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let index = (state >> 33) as usize % 50;
+            let delta = (state >> 50) as i64 - 8000;
+            items[index] += delta;
+            tree.add(index, delta);
+
+            let start = (state >> 20) as usize % 50;
+            assert_eq!(tree.range_sum(start..50), items[start..].iter().sum::<i64>());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 3 out of bounds for length 3")]
+    fn test_add_out_of_bounds_panics() {
+        let mut tree = FenwickTree::<i32>::new(3);
+        tree.add(3, 1);
+    }
+
+    #[test]
+    fn test_range_add_and_point_query() {
+        let mut tree = RangeUpdateFenwickTree::new(6);
+
+        tree.range_add(1..4, 5);
+        tree.range_add(3..6, 2);
+        tree.range_add(0..1, -1);
+
+        let values: Vec<i32> = (0..6).map(|i| tree.point_query(i)).collect();
+        assert_eq!(values, vec![-1, 5, 5, 7, 2, 2]);
+    }
+
+    #[test]
+    fn test_range_add_with_empty_range_does_nothing() {
+        let mut tree = RangeUpdateFenwickTree::new(3);
+
+        tree.range_add(2..2, 9);
+
+        assert_eq!(tree.point_query(2), 0);
+        assert_eq!(tree.len(), 3);
+    }
+}
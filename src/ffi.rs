@@ -0,0 +1,264 @@
+//! C bindings, behind the `ffi` feature
+//! The hash map, stack and queue of `i64` are handed to C as opaque pointers.
+//! `dsr_*_new` allocates one and `dsr_*_free` gives it back; every other
+//! function takes the pointer first. Values come back through an out
+//! pointer, with the return value telling whether there was one, since C has
+//! no `Option`. A null handle behaves like an empty structure and a null out
+//! pointer just skips the write. `include/data_structures.h` declares all of
+//! it; regenerate it with `cbindgen --config cbindgen.toml --output
+//! include/data_structures.h` after changing this file.
+
+use crate::hash_map::HashMap;
+use crate::queue::Queue;
+use crate::stack::Stack;
+
+pub struct DsrHashMap {
+    map: HashMap<i64, i64>,
+}
+
+pub struct DsrStack {
+    stack: Stack<i64>,
+}
+
+pub struct DsrQueue {
+    queue: Queue<i64>,
+}
+
+/// Writes `value` to `out` if there is both a value and somewhere to put it
+unsafe fn write_out(value: Option<i64>, out: *mut i64) -> bool {
+    match value {
+        Some(value) => {
+            if !out.is_null() {
+                *out = value;
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Moves `value` to the heap and hands out the pointer
+fn into_handle<T>(value: T) -> *mut T {
+    Box::into_raw(Box::new(value))
+}
+
+/// # Safety
+/// `handle` must be null or come from the matching `_new` and not be freed yet
+unsafe fn free_handle<T>(handle: *mut T) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dsr_hash_map_new() -> *mut DsrHashMap {
+    into_handle(DsrHashMap { map: HashMap::new() })
+}
+
+/// Returns true and writes the old value to `old` if the key was present
+///
+/// # Safety
+/// `map` must be null or a live handle, `old` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_hash_map_insert(
+    map: *mut DsrHashMap,
+    key: i64,
+    value: i64,
+    old: *mut i64
+) -> bool {
+    let Some(map) = map.as_mut() else {
+        return false;
+    };
+    write_out(map.map.insert(key, value), old)
+}
+
+/// # Safety
+/// `map` must be null or a live handle, `value` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_hash_map_get(
+    map: *const DsrHashMap,
+    key: i64,
+    value: *mut i64
+) -> bool {
+    write_out(map.as_ref().and_then(|map| map.map.get(key)), value)
+}
+
+/// # Safety
+/// `map` must be null or a live handle, `value` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_hash_map_remove(
+    map: *mut DsrHashMap,
+    key: i64,
+    value: *mut i64
+) -> bool {
+    write_out(map.as_mut().and_then(|map| map.map.remove(key)), value)
+}
+
+/// # Safety
+/// `map` must be null or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn dsr_hash_map_len(map: *const DsrHashMap) -> usize {
+    map.as_ref().map_or(0, |map| map.map.len())
+}
+
+/// # Safety
+/// `map` must be null or a live handle, it is dangling afterwards
+#[no_mangle]
+pub unsafe extern "C" fn dsr_hash_map_free(map: *mut DsrHashMap) {
+    free_handle(map);
+}
+
+#[no_mangle]
+pub extern "C" fn dsr_stack_new() -> *mut DsrStack {
+    into_handle(DsrStack { stack: Stack::new() })
+}
+
+/// Returns false if `stack` is null
+///
+/// # Safety
+/// `stack` must be null or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn dsr_stack_push(stack: *mut DsrStack, item: i64) -> bool {
+    let Some(stack) = stack.as_mut() else {
+        return false;
+    };
+    stack.stack.push(item);
+    true
+}
+
+/// # Safety
+/// `stack` must be null or a live handle, `item` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_stack_pop(stack: *mut DsrStack, item: *mut i64) -> bool {
+    write_out(stack.as_mut().and_then(|stack| stack.stack.pop()), item)
+}
+
+/// # Safety
+/// `stack` must be null or a live handle, `item` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_stack_peek(stack: *const DsrStack, item: *mut i64) -> bool {
+    write_out(stack.as_ref().and_then(|stack| stack.stack.peek().copied()), item)
+}
+
+/// # Safety
+/// `stack` must be null or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn dsr_stack_len(stack: *const DsrStack) -> usize {
+    stack.as_ref().map_or(0, |stack| stack.stack.iter().len())
+}
+
+/// # Safety
+/// `stack` must be null or a live handle, it is dangling afterwards
+#[no_mangle]
+pub unsafe extern "C" fn dsr_stack_free(stack: *mut DsrStack) {
+    free_handle(stack);
+}
+
+#[no_mangle]
+pub extern "C" fn dsr_queue_new() -> *mut DsrQueue {
+    into_handle(DsrQueue { queue: Queue::new() })
+}
+
+/// Returns false if `queue` is null
+///
+/// # Safety
+/// `queue` must be null or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn dsr_queue_add(queue: *mut DsrQueue, item: i64) -> bool {
+    let Some(queue) = queue.as_mut() else {
+        return false;
+    };
+    queue.queue.add(item);
+    true
+}
+
+/// # Safety
+/// `queue` must be null or a live handle, `item` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_queue_remove(queue: *mut DsrQueue, item: *mut i64) -> bool {
+    write_out(queue.as_mut().and_then(|queue| queue.queue.remove()), item)
+}
+
+/// # Safety
+/// `queue` must be null or a live handle, `item` must be null or writable
+#[no_mangle]
+pub unsafe extern "C" fn dsr_queue_peek(queue: *const DsrQueue, item: *mut i64) -> bool {
+    write_out(queue.as_ref().and_then(|queue| queue.queue.peek()), item)
+}
+
+/// # Safety
+/// `queue` must be null or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn dsr_queue_len(queue: *const DsrQueue) -> usize {
+    queue.as_ref().map_or(0, |queue| queue.queue.iter().count())
+}
+
+/// # Safety
+/// `queue` must be null or a live handle, it is dangling afterwards
+#[no_mangle]
+pub unsafe extern "C" fn dsr_queue_free(queue: *mut DsrQueue) {
+    free_handle(queue);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn test_hash_map_through_the_c_api() {
+        let map = dsr_hash_map_new();
+        let mut value = 0;
+
+        unsafe {
+            assert!(!dsr_hash_map_insert(map, 1, 10, &mut value));
+            assert!(dsr_hash_map_insert(map, 1, 11, &mut value));
+            assert_eq!(value, 10);
+            assert!(dsr_hash_map_get(map, 1, &mut value));
+            assert_eq!(value, 11);
+            assert!(!dsr_hash_map_get(map, 2, &mut value));
+            assert!(dsr_hash_map_remove(map, 1, ptr::null_mut()));
+            assert_eq!(dsr_hash_map_len(map), 0);
+            dsr_hash_map_free(map);
+        }
+    }
+
+    #[test]
+    fn test_stack_and_queue_through_the_c_api() {
+        let stack = dsr_stack_new();
+        let queue = dsr_queue_new();
+        let mut item = 0;
+
+        unsafe {
+            for value in [1, 2] {
+                assert!(dsr_stack_push(stack, value));
+                assert!(dsr_queue_add(queue, value));
+            }
+            assert!(dsr_stack_pop(stack, &mut item));
+            assert_eq!(item, 2);
+            assert!(dsr_queue_remove(queue, &mut item));
+            assert_eq!(item, 1);
+            assert!(dsr_stack_peek(stack, &mut item));
+            assert!(dsr_queue_peek(queue, &mut item));
+            assert_eq!((dsr_stack_len(stack), dsr_queue_len(queue)), (1, 1));
+            dsr_stack_free(stack);
+            dsr_queue_free(queue);
+        }
+    }
+
+    #[test]
+    fn when_handle_is_null_nothing_happens() {
+        let mut item = 7;
+
+        unsafe {
+            assert!(!dsr_stack_push(ptr::null_mut(), 1));
+            assert!(!dsr_queue_remove(ptr::null_mut(), &mut item));
+            assert!(!dsr_hash_map_get(ptr::null(), 1, &mut item));
+            assert_eq!(dsr_hash_map_len(ptr::null()), 0);
+            dsr_queue_free(ptr::null_mut());
+        }
+
+        assert_eq!(item, 7);
+    }
+}
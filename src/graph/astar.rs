@@ -0,0 +1,208 @@
+//! A* search
+//! Finds a cheapest path between two nodes of a weighted graph. Like
+//! Dijkstra's algorithm it expands nodes from a min-heap, but orders them by
+//! the cost so far plus a heuristic estimate of the cost still to go, so
+//! nodes in the direction of the goal are expanded first. The path is
+//! optimal as long as the heuristic never overestimates the remaining cost,
+//! and a zero heuristic turns the search into Dijkstra's algorithm.
+//! Weights must not be negative.
+
+use std::cmp::Ordering;
+
+use crate::binary_heap::{ BinaryHeap, HeapOrder };
+
+use super::{ GraphOps, NodeIndex, Weight };
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<W> {
+    pub cost: W,
+    /// Nodes from the start to the goal, both included
+    pub nodes: Vec<NodeIndex>,
+}
+
+/// Heap entry, ordered by its estimated total cost
+#[derive(Debug, Clone, Copy)]
+struct Candidate<W> {
+    estimate: W,
+    cost: W,
+    node: NodeIndex,
+}
+
+impl<W: Weight> PartialEq for Candidate<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W: Weight> Eq for Candidate<W> {}
+
+impl<W: Weight> PartialOrd for Candidate<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: Weight> Ord for Candidate<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.estimate
+            .partial_cmp(&other.estimate)
+            .expect("Weights must be comparable")
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+/// Cheapest path from `start` to `goal`, or None if the goal can't be reached.
+/// `heuristic` estimates the cost from a node to the goal.
+pub fn astar<G, H>(
+    graph: &G,
+    start: NodeIndex,
+    goal: NodeIndex,
+    mut heuristic: H
+) -> Option<Path<G::EdgeData>>
+where
+    G: GraphOps,
+    G::EdgeData: Weight,
+    H: FnMut(NodeIndex) -> G::EdgeData,
+{
+    for node in [start, goal] {
+        if !graph.contains_node(node) {
+            panic!("Node {} is not in the graph!", node.index());
+        }
+    }
+    let mut best_cost: Vec<Option<G::EdgeData>> = vec![None; graph.node_bound()];
+    let mut came_from: Vec<Option<NodeIndex>> = vec![None; graph.node_bound()];
+    let mut open = BinaryHeap::new(HeapOrder::Min);
+    let zero = G::EdgeData::zero();
+    best_cost[start.index()] = Some(zero);
+    open.push(Candidate { estimate: heuristic(start), cost: zero, node: start });
+
+    while let Some(Candidate { cost, node, .. }) = open.pop() {
+        if node == goal {
+            return Some(Path { cost, nodes: trace_back(&came_from, goal) });
+        }
+        // A cheaper way to this node was found after this entry was pushed
+        if best_cost[node.index()].is_some_and(|best| best < cost) {
+            continue;
+        }
+        for (neighbor, &weight) in graph.edges(node) {
+            let new_cost = cost + weight;
+            if best_cost[neighbor.index()].is_none_or(|best| new_cost < best) {
+                best_cost[neighbor.index()] = Some(new_cost);
+                came_from[neighbor.index()] = Some(node);
+                open.push(Candidate {
+                    estimate: new_cost + heuristic(neighbor),
+                    cost: new_cost,
+                    node: neighbor,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn trace_back(came_from: &[Option<NodeIndex>], goal: NodeIndex) -> Vec<NodeIndex> {
+    let mut nodes: Vec<NodeIndex> =
+        std::iter::successors(Some(goal), |node| came_from[node.index()]).collect();
+    nodes.reverse();
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ Graph, MatrixGraph, WeightedGraph };
+
+    /// Two routes from S to G: S-A-G costs 2 + 5, S-B-C-G costs 1 + 1 + 1
+    fn detour() -> (WeightedGraph<char, u32>, Vec<NodeIndex>) {
+        let mut graph = Graph::new_directed();
+        let nodes: Vec<NodeIndex> = "SABCG".chars().map(|n| graph.add_node(n)).collect();
+        for (from, to, weight) in [(0, 1, 2), (1, 4, 5), (0, 2, 1), (2, 3, 1), (3, 4, 1)] {
+            graph.add_edge(nodes[from], nodes[to], weight);
+        }
+        (graph, nodes)
+    }
+
+    #[test]
+    fn test_finds_cheapest_path_not_fewest_edges() {
+        let (graph, nodes) = detour();
+
+        let path = astar(&graph, nodes[0], nodes[4], |_| 0).unwrap();
+
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.nodes, vec![nodes[0], nodes[2], nodes[3], nodes[4]]);
+    }
+
+    #[test]
+    fn test_path_to_start_is_just_start() {
+        let (graph, nodes) = detour();
+
+        let path = astar(&graph, nodes[2], nodes[2], |_| 0).unwrap();
+
+        assert_eq!(path, Path { cost: 0, nodes: vec![nodes[2]] });
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let (graph, nodes) = detour();
+
+        assert_eq!(astar(&graph, nodes[4], nodes[0], |_| 0), None);
+    }
+
+    #[test]
+    fn test_node_is_expanded_again_when_a_cheaper_way_is_found() {
+        // S reaches C directly for 3 or through A for 2, the heuristic is
+        // admissible but makes A look worse, so C is first expanded at cost 3
+        let mut graph = Graph::new_directed();
+        let nodes: Vec<NodeIndex> = "SACG".chars().map(|n| graph.add_node(n)).collect();
+        for (from, to, weight) in [(0, 1, 1), (1, 2, 1), (0, 2, 3), (2, 3, 5)] {
+            graph.add_edge(nodes[from], nodes[to], weight);
+        }
+        let estimates = [0, 4, 0, 0];
+
+        let path = astar(&graph, nodes[0], nodes[3], |node| estimates[node.index()]).unwrap();
+
+        assert_eq!(path.cost, 7);
+        assert_eq!(path.nodes, nodes);
+    }
+
+    #[test]
+    fn test_good_heuristic_expands_fewer_nodes() {
+        // A line 0 - 1 - ... - 9 with a branch of ten nodes hanging off node 0
+        let mut graph = MatrixGraph::<usize, u32>::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..20).map(|n| graph.add_node(n)).collect();
+        for i in 0..9 {
+            graph.add_edge(nodes[i], nodes[i + 1], 1);
+        }
+        graph.add_edge(nodes[0], nodes[10], 1);
+        for i in 10..19 {
+            graph.add_edge(nodes[i], nodes[i + 1], 1);
+        }
+        let distance_to_goal = |node: NodeIndex| match node.index() {
+            i @ 0..10 => 9 - i as u32,
+            i => i as u32,
+        };
+
+        let mut blind_calls = 0;
+        let blind = astar(&graph, nodes[0], nodes[9], |_| {
+            blind_calls += 1;
+            0
+        });
+        let mut informed_calls = 0;
+        let informed = astar(&graph, nodes[0], nodes[9], |node| {
+            informed_calls += 1;
+            distance_to_goal(node)
+        });
+
+        assert_eq!(blind.unwrap().cost, 9);
+        assert_eq!(informed.unwrap().cost, 9);
+        assert_eq!(informed_calls, 11);
+        assert!(blind_calls > informed_calls);
+    }
+
+    #[test]
+    #[should_panic(expected = "Node 7 is not in the graph!")]
+    fn test_missing_goal_panics() {
+        let (graph, nodes) = detour();
+        astar(&graph, nodes[0], NodeIndex::new(7), |_| 0);
+    }
+}
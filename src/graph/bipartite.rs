@@ -0,0 +1,203 @@
+//! Bipartite check
+//! A graph is bipartite if its nodes can be split into two sides with every
+//! edge going between the sides, which is the same as having no cycle of odd
+//! length. Breadth-first search puts the start of each component on the left
+//! and every newly found node on the other side from the node it was found
+//! from. An edge between two nodes on the same side closes an odd cycle: the
+//! two nodes are on the same BFS level, so their paths up the BFS tree to the
+//! first shared node have the same length, and together with the edge they
+//! make a cycle of odd length.
+
+use crate::queue::Queue;
+
+use super::{ GraphOps, NodeIndex };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/// Side of every node, with every edge going between the sides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwoColoring {
+    sides: Vec<Option<Side>>,
+}
+
+impl TwoColoring {
+    pub fn side(&self, node: NodeIndex) -> Side {
+        match self.sides.get(node.index()).copied().flatten() {
+            Some(side) => side,
+            None => panic!("Node {} is not in the graph!", node.index()),
+        }
+    }
+
+    /// Nodes on one side in index order
+    pub fn nodes(&self, side: Side) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.sides
+            .iter()
+            .enumerate()
+            .filter(move |(_, node_side)| **node_side == Some(side))
+            .map(|(index, _)| NodeIndex::new(index))
+    }
+}
+
+/// Cycle of odd length, each node with an edge to the next and the last one
+/// with an edge back to the first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OddCycle {
+    pub nodes: Vec<NodeIndex>,
+}
+
+/// Splits the nodes of an undirected graph into two sides, or returns an odd
+/// cycle that proves the graph isn't bipartite
+pub fn two_color<G: GraphOps>(graph: &G) -> Result<TwoColoring, OddCycle> {
+    assert!(!graph.is_directed(), "Two-coloring needs an undirected graph");
+    let mut sides: Vec<Option<Side>> = vec![None; graph.node_bound()];
+    let mut parent: Vec<Option<NodeIndex>> = vec![None; graph.node_bound()];
+    for root in graph.node_indices() {
+        if sides[root.index()].is_some() {
+            continue;
+        }
+        sides[root.index()] = Some(Side::Left);
+        let mut queue = Queue::new();
+        queue.add(root);
+        while let Some(node) = queue.remove() {
+            let side = sides[node.index()].unwrap();
+            for neighbor in graph.neighbors(node) {
+                match sides[neighbor.index()] {
+                    None => {
+                        sides[neighbor.index()] = Some(side.other());
+                        parent[neighbor.index()] = Some(node);
+                        queue.add(neighbor);
+                    }
+                    Some(neighbor_side) if neighbor_side == side => {
+                        return Err(odd_cycle(&parent, node, neighbor));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    Ok(TwoColoring { sides })
+}
+
+/// Joins the BFS tree paths of the ends of an edge on one level into a cycle
+fn odd_cycle(parent: &[Option<NodeIndex>], a: NodeIndex, b: NodeIndex) -> OddCycle {
+    let (mut a_path, mut b_path) = (vec![a], vec![]);
+    let (mut a_end, mut b_end) = (a, b);
+    while a_end != b_end {
+        b_path.push(b_end);
+        a_end = parent[a_end.index()].unwrap();
+        b_end = parent[b_end.index()].unwrap();
+        a_path.push(a_end);
+    }
+    // From the shared node down to `a`, across the edge, and up from `b`
+    a_path.reverse();
+    a_path.extend(b_path);
+    OddCycle { nodes: a_path }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ Graph, MatrixGraph };
+
+    fn undirected(nodes: usize, edges: &[(usize, usize)]) -> Graph<(), ()> {
+        let mut graph = Graph::new_undirected();
+        for _ in 0..nodes {
+            graph.add_node(());
+        }
+        for &(from, to) in edges {
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), ());
+        }
+        graph
+    }
+
+    fn assert_odd_cycle(graph: &Graph<(), ()>, cycle: &OddCycle) {
+        let nodes = &cycle.nodes;
+        assert!(nodes.len() % 2 == 1, "{:?} has even length", nodes);
+        for (i, &node) in nodes.iter().enumerate() {
+            assert!(graph.contains_edge(node, nodes[(i + 1) % nodes.len()]));
+        }
+    }
+
+    #[test]
+    fn when_graph_is_empty_it_is_bipartite() {
+        let graph = undirected(0, &[]);
+
+        assert!(two_color(&graph).is_ok());
+    }
+
+    #[test]
+    fn test_even_cycle_alternates_sides() {
+        let graph = undirected(6, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 0)]);
+
+        let coloring = two_color(&graph).unwrap();
+
+        let left: Vec<usize> = coloring.nodes(Side::Left).map(|n| n.index()).collect();
+        let right: Vec<usize> = coloring.nodes(Side::Right).map(|n| n.index()).collect();
+        assert_eq!(left, vec![0, 2, 4]);
+        assert_eq!(right, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_every_edge_crosses_sides() {
+        // Workers 0 to 3 and the jobs 4 to 7 they can do, in two components
+        let edges = [(0, 4), (0, 5), (1, 5), (2, 6), (3, 6), (3, 7)];
+        let graph = undirected(8, &edges);
+
+        let coloring = two_color(&graph).unwrap();
+
+        for (from, to, _) in graph.all_edges() {
+            assert_ne!(coloring.side(from), coloring.side(to));
+        }
+        assert_eq!(coloring.side(NodeIndex::new(2)), Side::Left);
+    }
+
+    #[test]
+    fn test_triangle_is_odd_cycle() {
+        let graph = undirected(4, &[(0, 1), (1, 2), (2, 0), (2, 3)]);
+
+        let cycle = two_color(&graph).unwrap_err();
+
+        assert_odd_cycle(&graph, &cycle);
+        assert_eq!(cycle.nodes.len(), 3);
+    }
+
+    #[test]
+    fn test_long_odd_cycle_with_tail() {
+        let edges = [(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 2), (0, 7)];
+        let graph = undirected(8, &edges);
+
+        let cycle = two_color(&graph).unwrap_err();
+
+        assert_odd_cycle(&graph, &cycle);
+        assert_eq!(cycle.nodes.len(), 5);
+    }
+
+    #[test]
+    fn test_self_loop_is_odd_cycle() {
+        let mut graph = MatrixGraph::<(), ()>::new_undirected();
+        let a = graph.add_node(());
+        graph.add_edge(a, a, ());
+
+        assert_eq!(two_color(&graph), Err(OddCycle { nodes: vec![a] }));
+    }
+
+    #[test]
+    #[should_panic(expected = "Two-coloring needs an undirected graph")]
+    fn test_directed_graph_panics() {
+        let graph = Graph::<(), ()>::new_directed();
+        let _ = two_color(&graph);
+    }
+}
@@ -0,0 +1,159 @@
+//! Cycle detection
+//! Depth-first search that remembers which nodes are on the current path.
+//! An edge to a node on the path closes a cycle, and the cycle is the part of
+//! the path from that node to the current one. In an undirected graph the
+//! edge back to the node we came from doesn't count, it is the same edge that
+//! was just followed. Every other edge to an already visited node in an
+//! undirected graph also leads to a node on the path, so one rule serves
+//! both kinds of graph.
+
+use super::{ GraphOps, NodeIndex };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Unvisited,
+    OnPath,
+    Done,
+}
+
+/// Some cycle of the graph as the nodes along it, each one with an edge to
+/// the next and the last one with an edge back to the first. None if the
+/// graph has no cycle. A self loop is a cycle of one node.
+pub fn find_cycle<G: GraphOps>(graph: &G) -> Option<Vec<NodeIndex>> {
+    let mut state = vec![State::Unvisited; graph.node_bound()];
+    for root in graph.node_indices() {
+        if state[root.index()] != State::Unvisited {
+            continue;
+        }
+        state[root.index()] = State::OnPath;
+        // The current path, each node with the neighbors still to look at
+        let mut path = vec![(root, graph.neighbors(root))];
+        while let Some((node, neighbors)) = path.last_mut() {
+            let node = *node;
+            let Some(neighbor) = neighbors.next() else {
+                state[node.index()] = State::Done;
+                path.pop();
+                continue;
+            };
+            match state[neighbor.index()] {
+                State::Unvisited => {
+                    state[neighbor.index()] = State::OnPath;
+                    path.push((neighbor, graph.neighbors(neighbor)));
+                }
+                State::OnPath => {
+                    let parent = path.len().checked_sub(2).map(|i| path[i].0);
+                    if !graph.is_directed() && parent == Some(neighbor) {
+                        continue;
+                    }
+                    let start = path.iter().position(|(n, _)| *n == neighbor).unwrap();
+                    return Some(path[start..].iter().map(|(n, _)| *n).collect());
+                }
+                State::Done => {}
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ Graph, GraphKind, MatrixGraph };
+
+    fn graph_with_edges(kind: GraphKind, nodes: usize, edges: &[(usize, usize)]) -> Graph<(), ()> {
+        let mut graph = Graph::new(kind);
+        for _ in 0..nodes {
+            graph.add_node(());
+        }
+        for &(from, to) in edges {
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), ());
+        }
+        graph
+    }
+
+    fn assert_is_cycle<G: GraphOps>(graph: &G, cycle: &[NodeIndex]) {
+        assert!(!cycle.is_empty());
+        for (i, &node) in cycle.iter().enumerate() {
+            let next = cycle[(i + 1) % cycle.len()];
+            assert!(graph.contains_edge(node, next), "No edge {:?} -> {:?}", node, next);
+        }
+        let mut distinct = cycle.to_vec();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), cycle.len(), "Nodes repeat in {:?}", cycle);
+    }
+
+    fn indices(cycle: Vec<NodeIndex>) -> Vec<usize> {
+        cycle.into_iter().map(|node| node.index()).collect()
+    }
+
+    #[test]
+    fn when_graph_is_empty_it_has_no_cycle() {
+        let graph = Graph::<(), ()>::new_directed();
+
+        assert_eq!(find_cycle(&graph), None);
+    }
+
+    #[test]
+    fn test_directed_acyclic_graph_with_shared_descendant() {
+        // Diamond: two paths reach node 3, which is no cycle when directed
+        let graph = graph_with_edges(GraphKind::Directed, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+
+        assert_eq!(find_cycle(&graph), None);
+    }
+
+    #[test]
+    fn test_directed_cycle() {
+        let edges = [(0, 1), (1, 2), (2, 3), (3, 1), (2, 4)];
+        let graph = graph_with_edges(GraphKind::Directed, 5, &edges);
+
+        let cycle = find_cycle(&graph).unwrap();
+
+        assert_is_cycle(&graph, &cycle);
+        assert_eq!(indices(cycle), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_cycle_in_later_component() {
+        let edges = [(0, 1), (2, 3), (3, 4), (4, 2)];
+        let graph = graph_with_edges(GraphKind::Directed, 5, &edges);
+
+        assert_eq!(indices(find_cycle(&graph).unwrap()), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_undirected_tree_has_no_cycle() {
+        let edges = [(0, 1), (0, 2), (1, 3), (1, 4), (5, 6)];
+        let graph = graph_with_edges(GraphKind::Undirected, 7, &edges);
+
+        assert_eq!(find_cycle(&graph), None);
+    }
+
+    #[test]
+    fn test_undirected_cycle() {
+        let graph = graph_with_edges(GraphKind::Undirected, 4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+
+        let cycle = find_cycle(&graph).unwrap();
+
+        assert_is_cycle(&graph, &cycle);
+        assert_eq!(cycle.len(), 4);
+    }
+
+    #[test]
+    fn test_self_loop() {
+        let mut graph = MatrixGraph::<(), ()>::new_undirected();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.add_edge(a, b, ());
+        graph.add_edge(b, b, ());
+
+        assert_eq!(find_cycle(&graph), Some(vec![b]));
+    }
+
+    #[test]
+    fn test_two_directed_edges_between_nodes_are_a_cycle() {
+        let graph = graph_with_edges(GraphKind::Directed, 2, &[(0, 1), (1, 0)]);
+
+        assert_eq!(indices(find_cycle(&graph).unwrap()), vec![0, 1]);
+    }
+}
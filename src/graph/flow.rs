@@ -0,0 +1,359 @@
+//! Flow networks and maximum flow
+//! A directed graph whose edges carry capacities. The maximum flow from a
+//! source to a sink is found with Dinic's algorithm on the residual graph,
+//! where every edge has a reverse edge that can take back flow already sent.
+//! Each phase labels nodes by their BFS distance from the source and then
+//! pushes a blocking flow along paths that only go one level further at each
+//! step, until the sink can't be reached anymore: O(V^2 E), and much faster
+//! on unit capacities such as bipartite matching. The nodes still reachable
+//! from the source at the end form the source side of a minimum cut.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::queue::Queue;
+
+use super::{ Graph, NodeIndex };
+
+/// Directed graph with a capacity on every edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowNetwork<N> {
+    graph: Graph<N, u64>,
+}
+
+/// Result of a maximum flow computation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaxFlow {
+    pub value: u64,
+    /// Edges that carry flow, as (from, to, flow)
+    flows: Vec<(NodeIndex, NodeIndex, u64)>,
+    /// Nodes on the source side of the minimum cut
+    source_side: Vec<bool>,
+    /// Edges from the source side to the sink side, as (from, to, capacity)
+    cut: Vec<(NodeIndex, NodeIndex, u64)>,
+}
+
+impl MaxFlow {
+    /// Flow sent along the edge from `from` to `to`
+    pub fn flow(&self, from: NodeIndex, to: NodeIndex) -> u64 {
+        self.flows
+            .iter()
+            .find(|&&(edge_from, edge_to, _)| (edge_from, edge_to) == (from, to))
+            .map_or(0, |&(_, _, flow)| flow)
+    }
+
+    /// Edges that carry flow, as (from, to, flow)
+    pub fn flows(&self) -> &[(NodeIndex, NodeIndex, u64)] {
+        &self.flows
+    }
+
+    /// Edges of a minimum cut as (from, to, capacity), their capacities add
+    /// up to the maximum flow
+    pub fn min_cut(&self) -> &[(NodeIndex, NodeIndex, u64)] {
+        &self.cut
+    }
+
+    /// Whether the node is on the source side of the minimum cut
+    pub fn on_source_side(&self, node: NodeIndex) -> bool {
+        self.source_side.get(node.index()).copied().unwrap_or(false)
+    }
+}
+
+/// Edges stored in pairs, edge `e` and its reverse `e ^ 1`
+struct Residual {
+    targets: Vec<usize>,
+    /// Capacity left on every edge
+    capacities: Vec<u64>,
+    /// Edges leaving every node
+    adjacency: Vec<Vec<usize>>,
+    levels: Vec<Option<usize>>,
+    /// Next edge to try in every node during a blocking flow
+    next_edge: Vec<usize>,
+}
+
+impl Residual {
+    fn reachable_levels(&mut self, source: usize) {
+        self.levels.fill(None);
+        self.levels[source] = Some(0);
+        let mut queue = Queue::new();
+        queue.add(source);
+        while let Some(node) = queue.remove() {
+            let level = self.levels[node].unwrap();
+            for &edge in &self.adjacency[node] {
+                let target = self.targets[edge];
+                if self.capacities[edge] > 0 && self.levels[target].is_none() {
+                    self.levels[target] = Some(level + 1);
+                    queue.add(target);
+                }
+            }
+        }
+    }
+
+    /// Pushes at most `limit` from `node` to the sink along level-increasing edges
+    fn push(&mut self, node: usize, sink: usize, limit: u64) -> u64 {
+        if node == sink {
+            return limit;
+        }
+        while self.next_edge[node] < self.adjacency[node].len() {
+            let edge = self.adjacency[node][self.next_edge[node]];
+            let target = self.targets[edge];
+            let next_level = self.levels[node].map(|level| level + 1);
+            if self.capacities[edge] > 0 && self.levels[target] == next_level {
+                let pushed = self.push(target, sink, limit.min(self.capacities[edge]));
+                if pushed > 0 {
+                    self.capacities[edge] -= pushed;
+                    self.capacities[edge ^ 1] += pushed;
+                    return pushed;
+                }
+            }
+            // The edge is saturated or leads nowhere in this phase
+            self.next_edge[node] += 1;
+        }
+        0
+    }
+}
+
+impl<N> FlowNetwork<N> {
+    pub fn new() -> Self {
+        FlowNetwork { graph: Graph::new_directed() }
+    }
+
+    /// Uses the weights of a directed graph as capacities
+    pub fn from_graph(graph: Graph<N, u64>) -> Self {
+        assert!(graph.is_directed(), "A flow network needs a directed graph");
+        FlowNetwork { graph }
+    }
+
+    pub fn graph(&self) -> &Graph<N, u64> {
+        &self.graph
+    }
+
+    pub fn add_node(&mut self, data: N) -> NodeIndex {
+        let node = self.graph.add_node(data);
+        check_invariants!(self);
+        node
+    }
+
+    /// Adds an edge, or replaces the capacity of an existing one
+    pub fn add_edge(&mut self, from: NodeIndex, to: NodeIndex, capacity: u64) -> Option<u64> {
+        let old = self.graph.add_edge(from, to, capacity);
+        check_invariants!(self);
+        old
+    }
+
+    pub fn capacity(&self, from: NodeIndex, to: NodeIndex) -> Option<u64> {
+        self.graph.edge(from, to).copied()
+    }
+
+    /// Largest flow from `source` to `sink` with a minimum cut
+    pub fn max_flow(&self, source: NodeIndex, sink: NodeIndex) -> MaxFlow {
+        for node in [source, sink] {
+            if !self.graph.contains_node(node) {
+                panic!("Node {} is not in the graph!", node.index());
+            }
+        }
+        assert_ne!(source, sink, "Source and sink must be different nodes");
+        let size = self.graph.node_bound();
+        let mut residual = Residual {
+            targets: vec![],
+            capacities: vec![],
+            adjacency: vec![vec![]; size],
+            levels: vec![None; size],
+            next_edge: vec![0; size],
+        };
+        let mut original = vec![];
+        for (from, to, &capacity) in self.graph.all_edges() {
+            let edge = residual.targets.len();
+            residual.targets.extend([to.index(), from.index()]);
+            residual.capacities.extend([capacity, 0]);
+            residual.adjacency[from.index()].push(edge);
+            residual.adjacency[to.index()].push(edge + 1);
+            original.push((from, to, capacity, edge));
+        }
+
+        let (source_index, sink_index) = (source.index(), sink.index());
+        let mut value = 0;
+        loop {
+            residual.reachable_levels(source_index);
+            if residual.levels[sink_index].is_none() {
+                break;
+            }
+            residual.next_edge.fill(0);
+            loop {
+                let pushed = residual.push(source_index, sink_index, u64::MAX);
+                if pushed == 0 {
+                    break;
+                }
+                value += pushed;
+            }
+        }
+
+        // The last BFS marked the nodes still reachable in the residual graph
+        let source_side: Vec<bool> = residual.levels.iter().map(Option::is_some).collect();
+        let flows = original
+            .iter()
+            .map(|&(from, to, capacity, edge)| (from, to, capacity - residual.capacities[edge]))
+            .filter(|&(_, _, flow)| flow > 0)
+            .collect();
+        let cut = original
+            .iter()
+            .filter(|(from, to, _, _)| source_side[from.index()] && !source_side[to.index()])
+            .map(|&(from, to, capacity, _)| (from, to, capacity))
+            .collect();
+        MaxFlow { value, flows, source_side, cut }
+    }
+}
+
+impl<N> Default for FlowNetwork<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<N> Invariants for FlowNetwork<N> {
+    fn assert_invariants(&self) {
+        assert!(self.graph.is_directed(), "Flow network isn't directed");
+        self.graph.assert_invariants();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The network from CLRS figure 26.1
+    fn clrs() -> (FlowNetwork<&'static str>, Vec<NodeIndex>) {
+        let mut network = FlowNetwork::new();
+        let names = ["s", "v1", "v2", "v3", "v4", "t"];
+        let nodes: Vec<NodeIndex> = names.into_iter().map(|n| network.add_node(n)).collect();
+        let edges = [
+            (0, 1, 16),
+            (0, 2, 13),
+            (2, 1, 4),
+            (1, 3, 12),
+            (3, 2, 9),
+            (2, 4, 14),
+            (4, 3, 7),
+            (3, 5, 20),
+            (4, 5, 4),
+        ];
+        for (from, to, capacity) in edges {
+            network.add_edge(nodes[from], nodes[to], capacity);
+        }
+        (network, nodes)
+    }
+
+    fn assert_valid_flow(network: &FlowNetwork<&str>, flow: &MaxFlow, source: NodeIndex) {
+        let graph = network.graph();
+        let mut balance = vec![0i64; graph.node_bound()];
+        for &(from, to, amount) in flow.flows() {
+            assert!(amount <= network.capacity(from, to).unwrap());
+            balance[from.index()] -= amount as i64;
+            balance[to.index()] += amount as i64;
+        }
+        assert_eq!(balance[source.index()], -(flow.value as i64));
+        assert_eq!(balance.iter().filter(|&&b| b != 0).count(), 2, "Flow is conserved");
+    }
+
+    #[test]
+    fn when_sink_is_unreachable_flow_is_zero() {
+        let mut network = FlowNetwork::<()>::new();
+        let a = network.add_node(());
+        let b = network.add_node(());
+        network.add_edge(b, a, 5);
+
+        let flow = network.max_flow(a, b);
+
+        assert_eq!(flow.value, 0);
+        assert!(flow.min_cut().is_empty());
+        assert!(flow.on_source_side(a));
+        assert!(!flow.on_source_side(b));
+    }
+
+    #[test]
+    fn test_textbook_network() {
+        let (network, nodes) = clrs();
+
+        let flow = network.max_flow(nodes[0], nodes[5]);
+
+        assert_eq!(flow.value, 23);
+        assert_valid_flow(&network, &flow, nodes[0]);
+    }
+
+    #[test]
+    fn test_min_cut_matches_max_flow() {
+        let (network, nodes) = clrs();
+
+        let flow = network.max_flow(nodes[0], nodes[5]);
+
+        let cut_capacity: u64 = flow.min_cut().iter().map(|&(_, _, capacity)| capacity).sum();
+        assert_eq!(cut_capacity, flow.value);
+        let mut cut: Vec<(NodeIndex, NodeIndex)> =
+            flow.min_cut().iter().map(|&(from, to, _)| (from, to)).collect();
+        cut.sort();
+        assert_eq!(cut, vec![(nodes[1], nodes[3]), (nodes[4], nodes[3]), (nodes[4], nodes[5])]);
+        for &(from, to, _) in flow.min_cut() {
+            assert_eq!(flow.flow(from, to), network.capacity(from, to).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_flow_is_sent_back_along_reverse_edges() {
+        // Sending the first path s-a-b-t greedily blocks both other paths,
+        // the full flow needs the a-b flow taken back
+        let mut network = FlowNetwork::new();
+        let nodes: Vec<NodeIndex> = ["s", "a", "b", "t"].map(|n| network.add_node(n)).to_vec();
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (0, 2), (1, 3)] {
+            network.add_edge(nodes[from], nodes[to], 1);
+        }
+
+        let flow = network.max_flow(nodes[0], nodes[3]);
+
+        assert_eq!(flow.value, 2);
+        assert_valid_flow(&network, &flow, nodes[0]);
+    }
+
+    #[test]
+    fn test_bipartite_matching() {
+        // Workers can do some of the jobs, each does at most one job
+        let can_do = [("ann", vec![0, 1]), ("bob", vec![0]), ("cid", vec![1, 2]), ("dee", vec![1])];
+        let mut network = FlowNetwork::new();
+        let source = network.add_node("source");
+        let sink = network.add_node("sink");
+        let jobs: Vec<NodeIndex> = ["j0", "j1", "j2"].map(|job| network.add_node(job)).to_vec();
+        let mut workers = vec![];
+        for (name, possible_jobs) in &can_do {
+            let worker = network.add_node(name);
+            network.add_edge(source, worker, 1);
+            for &job in possible_jobs {
+                network.add_edge(worker, jobs[job], 1);
+            }
+            workers.push(worker);
+        }
+        for &job in &jobs {
+            network.add_edge(job, sink, 1);
+        }
+
+        let flow = network.max_flow(source, sink);
+
+        assert_eq!(flow.value, 3);
+        let matched: Vec<(NodeIndex, NodeIndex)> = flow
+            .flows()
+            .iter()
+            .filter(|(from, to, _)| workers.contains(from) && jobs.contains(to))
+            .map(|&(worker, job, _)| (worker, job))
+            .collect();
+        assert_eq!(matched.len(), 3);
+        for &job in &jobs {
+            assert_eq!(matched.iter().filter(|(_, matched_job)| *matched_job == job).count(), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Source and sink must be different nodes")]
+    fn test_same_source_and_sink_panics() {
+        let (network, nodes) = clrs();
+        network.max_flow(nodes[1], nodes[1]);
+    }
+}
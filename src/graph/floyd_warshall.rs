@@ -0,0 +1,197 @@
+//! Floyd-Warshall all-pairs shortest paths
+//! Starts from the edge weights and lets every node in turn act as a stop on
+//! the way: the distance from `i` to `j` becomes the distance through `k`
+//! whenever that is shorter. After all nodes had their turn the distances are
+//! the shortest ones, in O(V^3) time. Each pair also remembers the first node
+//! after `i` on its shortest path, so paths are rebuilt by following those
+//! nodes. Negative weights are fine, a negative cycle shows up as a node with
+//! a negative distance to itself.
+
+use crate::matrix::Matrix;
+
+use super::{ MatrixGraph, NodeIndex, Weight };
+
+/// Distances between every pair of nodes, with paths
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllPairsShortestPaths<W> {
+    /// Cell (from, to) is None if `to` can't be reached from `from`
+    distances: Matrix<Option<W>>,
+    /// First node after `from` on the shortest path to `to`
+    next: Matrix<Option<NodeIndex>>,
+}
+
+impl<W: Weight> AllPairsShortestPaths<W> {
+    pub fn distance(&self, from: NodeIndex, to: NodeIndex) -> Option<W> {
+        self.distances.get(from.index(), to.index()).copied().flatten()
+    }
+
+    /// Matrix of all distances, indexed by node index
+    pub fn distances(&self) -> &Matrix<Option<W>> {
+        &self.distances
+    }
+
+    /// Nodes on a shortest path from `from` to `to`, both included.
+    /// None if there is no path, or no shortest one because of a negative cycle.
+    pub fn path(&self, from: NodeIndex, to: NodeIndex) -> Option<Vec<NodeIndex>> {
+        self.distance(from, to)?;
+        let mut path = vec![from];
+        let mut current = from;
+        while current != to {
+            current = self.next[(current.index(), to.index())]?;
+            path.push(current);
+            // A simple path can't be longer, so this one goes around a negative cycle
+            if path.len() > self.distances.rows() {
+                return None;
+            }
+        }
+        Some(path)
+    }
+
+    pub fn has_negative_cycle(&self) -> bool {
+        (0..self.distances.rows())
+            .any(|node| self.distances[(node, node)].is_some_and(|d| d < W::zero()))
+    }
+}
+
+/// Shortest paths between all pairs of nodes of an adjacency-matrix graph
+pub fn floyd_warshall<N, W: Weight>(graph: &MatrixGraph<N, W>) -> AllPairsShortestPaths<W> {
+    let size = graph.node_bound();
+    let mut distances: Matrix<Option<W>> = Matrix::new(size, size, None);
+    let mut next: Matrix<Option<NodeIndex>> = Matrix::new(size, size, None);
+    for node in graph.node_indices() {
+        distances[(node.index(), node.index())] = Some(W::zero());
+        next[(node.index(), node.index())] = Some(node);
+    }
+    for from in graph.node_indices() {
+        for (to, &weight) in graph.edges(from) {
+            let cell = (from.index(), to.index());
+            if distances[cell].is_none_or(|distance| weight < distance) {
+                distances[cell] = Some(weight);
+                next[cell] = Some(to);
+            }
+        }
+    }
+
+    for k in 0..size {
+        for i in 0..size {
+            let Some(to_k) = distances[(i, k)] else {
+                continue;
+            };
+            for j in 0..size {
+                let Some(from_k) = distances[(k, j)] else {
+                    continue;
+                };
+                let through_k = to_k + from_k;
+                if distances[(i, j)].is_none_or(|distance| through_k < distance) {
+                    distances[(i, j)] = Some(through_k);
+                    next[(i, j)] = next[(i, k)];
+                }
+            }
+        }
+    }
+    AllPairsShortestPaths { distances, next }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::astar;
+
+    fn directed(nodes: usize, edges: &[(usize, usize, i32)]) -> MatrixGraph<(), i32> {
+        let mut graph = MatrixGraph::new_directed();
+        for _ in 0..nodes {
+            graph.add_node(());
+        }
+        for &(from, to, weight) in edges {
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), weight);
+        }
+        graph
+    }
+
+    fn node(index: usize) -> NodeIndex {
+        NodeIndex::new(index)
+    }
+
+    #[test]
+    fn when_graph_is_empty_there_are_no_distances() {
+        let graph = MatrixGraph::<(), u32>::new_directed();
+
+        let paths = floyd_warshall(&graph);
+
+        assert!(paths.distances().is_empty());
+        assert!(!paths.has_negative_cycle());
+    }
+
+    #[test]
+    fn test_distances_and_paths() {
+        let graph = directed(4, &[(0, 1, 5), (0, 3, 10), (1, 2, 3), (2, 3, 1)]);
+
+        let paths = floyd_warshall(&graph);
+
+        assert_eq!(paths.distance(node(0), node(3)), Some(9));
+        assert_eq!(paths.path(node(0), node(3)), Some(vec![node(0), node(1), node(2), node(3)]));
+        assert_eq!(paths.distance(node(3), node(0)), None);
+        assert_eq!(paths.path(node(3), node(0)), None);
+        assert_eq!(paths.path(node(2), node(2)), Some(vec![node(2)]));
+    }
+
+    #[test]
+    fn test_negative_weights() {
+        let graph = directed(3, &[(0, 1, 4), (0, 2, 5), (2, 1, -3)]);
+
+        let paths = floyd_warshall(&graph);
+
+        assert_eq!(paths.distance(node(0), node(1)), Some(2));
+        assert_eq!(paths.path(node(0), node(1)), Some(vec![node(0), node(2), node(1)]));
+        assert!(!paths.has_negative_cycle());
+    }
+
+    #[test]
+    fn test_negative_cycle() {
+        let graph = directed(4, &[(0, 1, 1), (1, 2, -2), (2, 1, 1), (2, 3, 1)]);
+
+        let paths = floyd_warshall(&graph);
+
+        assert!(paths.has_negative_cycle());
+        assert_eq!(paths.path(node(0), node(3)), None);
+    }
+
+    #[test]
+    fn test_removed_node_has_no_distances() {
+        let mut graph = directed(3, &[(0, 1, 1), (1, 2, 1)]);
+        graph.remove_node(node(1));
+
+        let paths = floyd_warshall(&graph);
+
+        assert_eq!(paths.distance(node(0), node(2)), None);
+        assert_eq!(paths.distance(node(1), node(1)), None);
+    }
+
+    #[test]
+    fn test_random_graph_matches_astar() {
+        let mut state: u64 = 5;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            state >> 33
+        };
+        let mut graph = MatrixGraph::<(), u32>::new_directed();
+        let nodes: Vec<NodeIndex> = (0..25).map(|_| graph.add_node(())).collect();
+        for _ in 0..120 {
+            let (from, to) = (next() as usize % 25, next() as usize % 25);
+            graph.add_edge(nodes[from], nodes[to], (next() % 50) as u32);
+        }
+
+        let paths = floyd_warshall(&graph);
+
+        for &from in &nodes {
+            for &to in &nodes {
+                let expected = astar(&graph, from, to, |_| 0).map(|path| path.cost);
+                assert_eq!(paths.distance(from, to), expected);
+                if let Some(path) = paths.path(from, to) {
+                    let cost = path.windows(2).map(|step| graph.edge(step[0], step[1]).unwrap());
+                    assert_eq!(Some(cost.sum()), expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Grid worlds
+//! A rectangular map of open cells and walls turned into a weighted graph:
+//! every open cell is a node with edges to the open cells next to it, so
+//! path finding on the map is A* on the graph. Moves go to the four
+//! orthogonal neighbors at cost 1, or also to the four diagonal ones at cost
+//! √2. Manhattan distance is the exact cost on an empty orthogonal grid, but
+//! overestimates once diagonal moves are allowed; Euclidean distance never
+//! overestimates, so it works for both.
+
+use super::{ astar, Graph, NodeIndex, WeightedGraph };
+
+/// Cell as (row, column)
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moves {
+    Orthogonal,
+    Diagonal,
+}
+
+pub fn manhattan(a: Position, b: Position) -> f64 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as f64
+}
+
+pub fn euclidean(a: Position, b: Position) -> f64 {
+    let rows = a.0.abs_diff(b.0) as f64;
+    let columns = a.1.abs_diff(b.1) as f64;
+    rows.hypot(columns)
+}
+
+#[derive(Debug, Clone)]
+pub struct GridWorld {
+    rows: usize,
+    columns: usize,
+    /// Node of every cell row by row, None for walls
+    cells: Vec<Option<NodeIndex>>,
+    graph: WeightedGraph<Position, f64>,
+}
+
+impl GridWorld {
+    /// Builds the grid from one line per row, `#` is a wall and any other
+    /// character an open cell. Shorter lines are padded with walls.
+    pub fn parse(map: &str, moves: Moves) -> Self {
+        let lines: Vec<&str> = map.lines().collect();
+        let rows = lines.len();
+        let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let mut graph = Graph::new_undirected();
+        let mut cells = vec![None; rows * columns];
+        for (row, line) in lines.iter().enumerate() {
+            for (column, cell) in line.chars().enumerate() {
+                if cell != '#' {
+                    cells[row * columns + column] = Some(graph.add_node((row, column)));
+                }
+            }
+        }
+
+        let mut steps = vec![((0, 1), 1.0), ((1, 0), 1.0)];
+        if moves == Moves::Diagonal {
+            steps.extend([((1, 1), std::f64::consts::SQRT_2), ((1, -1), std::f64::consts::SQRT_2)]);
+        }
+        let mut world = GridWorld { rows, columns, cells, graph };
+        for row in 0..rows {
+            for column in 0..columns {
+                let Some(from) = world.node((row, column)) else {
+                    continue;
+                };
+                for &((row_step, column_step), cost) in &steps {
+                    let next = (row + row_step, column.checked_add_signed(column_step));
+                    if let (next_row, Some(next_column)) = next {
+                        if let Some(to) = world.node((next_row, next_column)) {
+                            world.graph.add_edge(from, to, cost);
+                        }
+                    }
+                }
+            }
+        }
+        world
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn graph(&self) -> &WeightedGraph<Position, f64> {
+        &self.graph
+    }
+
+    /// Node of an open cell, None for walls and cells outside the grid
+    pub fn node(&self, (row, column): Position) -> Option<NodeIndex> {
+        if row >= self.rows || column >= self.columns {
+            return None;
+        }
+        self.cells[row * self.columns + column]
+    }
+
+    pub fn position(&self, node: NodeIndex) -> Position {
+        match self.graph.node(node) {
+            Some(&position) => position,
+            None => panic!("Node {} is not in the graph!", node.index()),
+        }
+    }
+
+    pub fn is_open(&self, position: Position) -> bool {
+        self.node(position).is_some()
+    }
+
+    /// Cheapest path between two open cells with its cost, using A* with the
+    /// given heuristic. None if either cell is a wall or the goal can't be reached.
+    pub fn shortest_path(
+        &self,
+        from: Position,
+        to: Position,
+        heuristic: fn(Position, Position) -> f64
+    ) -> Option<(f64, Vec<Position>)> {
+        let (start, goal) = (self.node(from)?, self.node(to)?);
+        let path = astar(&self.graph, start, goal, |node| heuristic(self.position(node), to))?;
+        let cells = path.nodes.into_iter().map(|node| self.position(node)).collect();
+        Some((path.cost, cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "\
+S...#...
+.##.#.#.
+.#..#.#.
+.#.##.#.
+......#G";
+
+    #[test]
+    fn test_heuristics() {
+        assert_eq!(manhattan((0, 0), (3, 4)), 7.0);
+        assert_eq!(euclidean((0, 0), (3, 4)), 5.0);
+        assert_eq!(euclidean((5, 1), (2, 5)), 5.0);
+    }
+
+    #[test]
+    fn test_parse() {
+        let world = GridWorld::parse("..#\n.", Moves::Orthogonal);
+
+        assert_eq!((world.rows(), world.columns()), (2, 3));
+        assert!(world.is_open((0, 1)));
+        assert!(!world.is_open((0, 2)));
+        assert!(!world.is_open((1, 1)), "Short lines are padded with walls");
+        assert!(!world.is_open((5, 0)));
+        assert_eq!(world.graph().node_count(), 3);
+        assert_eq!(world.graph().edge_count(), 2);
+    }
+
+    #[test]
+    fn test_path_through_maze() {
+        let world = GridWorld::parse(MAZE, Moves::Orthogonal);
+
+        let (cost, cells) = world.shortest_path((0, 0), (4, 7), manhattan).unwrap();
+
+        assert_eq!(cost, 19.0);
+        assert_eq!(cells.len(), 20);
+        assert_eq!((cells[0], cells[19]), ((0, 0), (4, 7)));
+        assert!(cells.windows(2).all(|step| manhattan(step[0], step[1]) == 1.0));
+        assert!(cells.iter().all(|&cell| world.is_open(cell)));
+    }
+
+    #[test]
+    fn test_both_heuristics_find_the_same_cost() {
+        let world = GridWorld::parse(MAZE, Moves::Orthogonal);
+
+        let with_manhattan = world.shortest_path((4, 0), (0, 7), manhattan).unwrap();
+        let with_euclidean = world.shortest_path((4, 0), (0, 7), euclidean).unwrap();
+
+        assert_eq!(with_manhattan.0, with_euclidean.0);
+    }
+
+    #[test]
+    fn test_diagonal_moves() {
+        let world = GridWorld::parse(".....\n.....\n.....", Moves::Diagonal);
+
+        let (cost, cells) = world.shortest_path((0, 0), (2, 4), euclidean).unwrap();
+
+        assert!((cost - (2.0 + 2.0 * std::f64::consts::SQRT_2)).abs() < 1e-9);
+        assert_eq!(cells.len(), 5);
+    }
+
+    #[test]
+    fn test_walled_off_goal() {
+        let world = GridWorld::parse("..#.\n..#.", Moves::Diagonal);
+
+        assert_eq!(world.shortest_path((0, 0), (1, 3), euclidean), None);
+        assert_eq!(world.shortest_path((0, 0), (0, 2), euclidean), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Lowest common ancestors with binary lifting
+//! Built over a tree rooted at a node of a graph. Every node stores its
+//! ancestors 1, 2, 4, ... levels up, each row computed from the previous one
+//! in O(n log n). A node can then jump up any number of levels in O(log n)
+//! by following the jumps for the set bits of the number. To find the lowest
+//! common ancestor of two nodes, the deeper one first jumps to the depth of
+//! the other, then both take the largest jumps that keep them apart, which
+//! leaves them just below the ancestor they share.
+
+use super::{ Bfs, GraphOps, NodeIndex };
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lca {
+    root: NodeIndex,
+    /// Depth of every node, None for nodes outside the tree
+    depths: Vec<Option<usize>>,
+    /// Row `k` holds the ancestor `2^k` levels up of every node
+    jumps: Vec<Vec<Option<NodeIndex>>>,
+}
+
+impl Lca {
+    /// Uses the nodes reachable from `root` as the tree. If the graph isn't
+    /// a tree, its breadth-first search tree from `root` is used instead.
+    pub fn new<G: GraphOps>(graph: &G, root: NodeIndex) -> Self {
+        let mut depths = vec![None; graph.node_bound()];
+        let mut parents = vec![None; graph.node_bound()];
+        for visit in Bfs::new(graph, root) {
+            depths[visit.node.index()] = Some(visit.depth);
+            parents[visit.node.index()] = visit.predecessor;
+        }
+        let height = depths.iter().flatten().copied().max().unwrap_or(0);
+        let mut jumps = vec![parents];
+        // Enough rows for a jump from the deepest node to the root
+        while 1 << jumps.len() <= height {
+            let previous = jumps.last().unwrap();
+            let row = previous
+                .iter()
+                .map(|ancestor| ancestor.and_then(|node: NodeIndex| previous[node.index()]))
+                .collect();
+            jumps.push(row);
+        }
+        let lca = Lca { root, depths, jumps };
+        check_invariants!(&lca);
+        lca
+    }
+
+    pub fn root(&self) -> NodeIndex {
+        self.root
+    }
+
+    pub fn contains(&self, node: NodeIndex) -> bool {
+        self.depths.get(node.index()).is_some_and(|depth| depth.is_some())
+    }
+
+    /// Number of edges between the node and the root
+    pub fn depth(&self, node: NodeIndex) -> usize {
+        match self.depths.get(node.index()).copied().flatten() {
+            Some(depth) => depth,
+            None => panic!("Node {} is not in the tree!", node.index()),
+        }
+    }
+
+    pub fn parent(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.ancestor(node, 1)
+    }
+
+    /// Ancestor `levels` levels above the node, None above the root
+    pub fn ancestor(&self, mut node: NodeIndex, levels: usize) -> Option<NodeIndex> {
+        if levels > self.depth(node) {
+            return None;
+        }
+        for (bit, row) in self.jumps.iter().enumerate() {
+            if levels & (1 << bit) != 0 {
+                node = row[node.index()].unwrap();
+            }
+        }
+        Some(node)
+    }
+
+    pub fn is_ancestor(&self, ancestor: NodeIndex, node: NodeIndex) -> bool {
+        let (ancestor_depth, depth) = (self.depth(ancestor), self.depth(node));
+        depth >= ancestor_depth && self.ancestor(node, depth - ancestor_depth) == Some(ancestor)
+    }
+
+    /// Deepest node that is an ancestor of both, a node counts as its own ancestor
+    pub fn lca(&self, a: NodeIndex, b: NodeIndex) -> NodeIndex {
+        let (depth_a, depth_b) = (self.depth(a), self.depth(b));
+        let (mut a, mut b) = if depth_a >= depth_b {
+            (self.ancestor(a, depth_a - depth_b).unwrap(), b)
+        } else {
+            (a, self.ancestor(b, depth_b - depth_a).unwrap())
+        };
+        if a == b {
+            return a;
+        }
+        for row in self.jumps.iter().rev() {
+            if row[a.index()] != row[b.index()] {
+                a = row[a.index()].unwrap();
+                b = row[b.index()].unwrap();
+            }
+        }
+        self.jumps[0][a.index()].unwrap()
+    }
+
+    /// Number of edges on the tree path between the nodes
+    pub fn distance(&self, a: NodeIndex, b: NodeIndex) -> usize {
+        self.depth(a) + self.depth(b) - 2 * self.depth(self.lca(a, b))
+    }
+
+    /// Nodes on the tree path from `a` to `b`, both included
+    pub fn path(&self, a: NodeIndex, b: NodeIndex) -> Vec<NodeIndex> {
+        let common = self.lca(a, b);
+        let up = self.depth(a) - self.depth(common);
+        let down = self.depth(b) - self.depth(common);
+        let mut path: Vec<NodeIndex> =
+            (0..=up).map(|levels| self.ancestor(a, levels).unwrap()).collect();
+        path.extend((0..down).rev().map(|levels| self.ancestor(b, levels).unwrap()));
+        path
+    }
+}
+
+/// Checks the root is at depth 0 and every jump of `2^k` levels lands on a
+/// node exactly that much shallower, or on None when it would pass the root
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for Lca {
+    fn assert_invariants(&self) {
+        assert_eq!(self.depths[self.root.index()], Some(0), "Root isn't at depth 0");
+        for (level, row) in self.jumps.iter().enumerate() {
+            assert_eq!(row.len(), self.depths.len(), "Jump row has the wrong length");
+            for (depth, jump) in self.depths.iter().zip(row) {
+                let expected = depth.and_then(|depth| depth.checked_sub(1 << level));
+                let landed = jump.and_then(|node| self.depths[node.index()]);
+                assert_eq!(landed, expected, "Jump lands at the wrong depth");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    ///         0
+    ///       / | \
+    ///      1  2  3
+    ///     / \     \
+    ///    4   5     6
+    ///   /         / \
+    ///  7         8   9
+    fn tree() -> (Graph<(), ()>, Vec<NodeIndex>) {
+        let mut graph = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..10).map(|_| graph.add_node(())).collect();
+        let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (1, 5), (3, 6), (4, 7), (6, 8), (6, 9)];
+        for (parent, child) in edges {
+            graph.add_edge(nodes[parent], nodes[child], ());
+        }
+        (graph, nodes)
+    }
+
+    #[test]
+    fn test_depth_and_ancestors() {
+        let (graph, nodes) = tree();
+
+        let lca = Lca::new(&graph, nodes[0]);
+
+        assert_eq!(lca.depth(nodes[7]), 3);
+        assert_eq!(lca.parent(nodes[7]), Some(nodes[4]));
+        assert_eq!(lca.parent(nodes[0]), None);
+        assert_eq!(lca.ancestor(nodes[7], 2), Some(nodes[1]));
+        assert_eq!(lca.ancestor(nodes[7], 3), Some(nodes[0]));
+        assert_eq!(lca.ancestor(nodes[7], 4), None);
+        assert!(lca.is_ancestor(nodes[1], nodes[7]));
+        assert!(!lca.is_ancestor(nodes[7], nodes[1]));
+    }
+
+    #[test]
+    fn test_lca() {
+        let (graph, nodes) = tree();
+
+        let lca = Lca::new(&graph, nodes[0]);
+
+        assert_eq!(lca.lca(nodes[7], nodes[5]), nodes[1]);
+        assert_eq!(lca.lca(nodes[7], nodes[9]), nodes[0]);
+        assert_eq!(lca.lca(nodes[8], nodes[9]), nodes[6]);
+        assert_eq!(lca.lca(nodes[4], nodes[7]), nodes[4]);
+        assert_eq!(lca.lca(nodes[2], nodes[2]), nodes[2]);
+    }
+
+    #[test]
+    fn test_distance_and_path() {
+        let (graph, nodes) = tree();
+
+        let lca = Lca::new(&graph, nodes[0]);
+
+        assert_eq!(lca.distance(nodes[7], nodes[9]), 6);
+        assert_eq!(lca.distance(nodes[3], nodes[3]), 0);
+        let path: Vec<usize> = lca.path(nodes[5], nodes[8]).iter().map(|n| n.index()).collect();
+        assert_eq!(path, vec![5, 1, 0, 3, 6, 8]);
+    }
+
+    #[test]
+    fn test_other_root_changes_answers() {
+        let (graph, nodes) = tree();
+
+        let lca = Lca::new(&graph, nodes[6]);
+
+        assert_eq!(lca.lca(nodes[7], nodes[2]), nodes[0]);
+        assert_eq!(lca.lca(nodes[0], nodes[9]), nodes[6]);
+        assert_eq!(lca.depth(nodes[7]), 5);
+    }
+
+    #[test]
+    fn test_long_path_matches_naive_ancestors() {
+        // A directed path of 1000 nodes with a short branch every 10 nodes
+        let mut graph = Graph::<(), ()>::new_directed();
+        let spine: Vec<NodeIndex> = (0..1000).map(|_| graph.add_node(())).collect();
+        for pair in spine.windows(2) {
+            graph.add_edge(pair[0], pair[1], ());
+        }
+        let branches: Vec<NodeIndex> = spine
+            .iter()
+            .step_by(10)
+            .map(|&node| {
+                let branch = graph.add_node(());
+                graph.add_edge(node, branch, ());
+                branch
+            })
+            .collect();
+
+        let lca = Lca::new(&graph, spine[0]);
+
+        for (i, &a) in branches.iter().enumerate() {
+            let b = spine[(i * 37) % 1000];
+            let expected = spine[(i * 10).min((i * 37) % 1000)];
+            assert_eq!(lca.lca(a, b), expected);
+            assert_eq!(lca.distance(a, b), (i * 10).abs_diff((i * 37) % 1000) + 1);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Node 1 is not in the tree!")]
+    fn test_node_outside_tree_panics() {
+        let mut graph = Graph::<(), ()>::new_directed();
+        let root = graph.add_node(());
+        let other = graph.add_node(());
+
+        Lca::new(&graph, root).lca(root, other);
+    }
+}
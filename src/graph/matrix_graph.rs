@@ -0,0 +1,400 @@
+//! Graph implementation using an adjacency matrix
+//! Edge data lives in a square matrix where cell (from, to) is `Some(data)`
+//! when the edge exists. Checking or changing an edge is O(1), at the cost of
+//! O(V^2) memory and O(V) neighbor scans, which pays off for dense graphs.
+//! With `E = ()` every cell is a single byte, so it acts as a bit matrix.
+
+use super::{ GraphKind, GraphOps, NodeIndex };
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixGraph<N, E> {
+    kind: GraphKind,
+    nodes: Vec<Option<N>>,
+    /// Row-major `dimension * dimension` matrix of edges
+    matrix: Vec<Option<E>>,
+    dimension: usize,
+    node_count: usize,
+    edge_count: usize,
+}
+
+impl<N, E: Clone> MatrixGraph<N, E> {
+    pub fn new(kind: GraphKind) -> Self {
+        MatrixGraph {
+            kind,
+            nodes: vec![],
+            matrix: vec![],
+            dimension: 0,
+            node_count: 0,
+            edge_count: 0,
+        }
+    }
+
+    pub fn new_directed() -> Self {
+        Self::new(GraphKind::Directed)
+    }
+
+    pub fn new_undirected() -> Self {
+        Self::new(GraphKind::Undirected)
+    }
+
+    pub fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.kind == GraphKind::Directed
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.node_count == 0
+    }
+
+    pub fn node_bound(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Adds a node. When the matrix is full its dimension is doubled,
+    /// so adding n nodes costs O(n^2) in total.
+    pub fn add_node(&mut self, data: N) -> NodeIndex {
+        if self.nodes.len() == self.dimension {
+            self.grow((self.dimension * 2).max(4));
+        }
+        self.nodes.push(Some(data));
+        self.node_count += 1;
+        check_invariants!(self);
+        NodeIndex::new(self.nodes.len() - 1)
+    }
+
+    /// Removes the node and clears its row and column
+    pub fn remove_node(&mut self, node: NodeIndex) -> Option<N> {
+        let data = self.nodes.get_mut(node.index())?.take()?;
+        self.node_count -= 1;
+        for other in 0..self.nodes.len() {
+            let other = NodeIndex::new(other);
+            if self.cell(node, other).is_some() {
+                self.take_edge(node, other);
+            }
+            if self.cell(other, node).is_some() {
+                self.take_edge(other, node);
+            }
+        }
+        check_invariants!(self);
+        Some(data)
+    }
+
+    pub fn contains_node(&self, node: NodeIndex) -> bool {
+        self.node(node).is_some()
+    }
+
+    pub fn node(&self, node: NodeIndex) -> Option<&N> {
+        self.nodes.get(node.index()).and_then(|data| data.as_ref())
+    }
+
+    pub fn node_mut(&mut self, node: NodeIndex) -> Option<&mut N> {
+        self.nodes.get_mut(node.index()).and_then(|data| data.as_mut())
+    }
+
+    /// Adds an edge in O(1). Returns the old data if the edge existed.
+    pub fn add_edge(&mut self, from: NodeIndex, to: NodeIndex, data: E) -> Option<E> {
+        for node in [from, to] {
+            if !self.contains_node(node) {
+                panic!("Node {} is not in the graph!", node.index());
+            }
+        }
+        let position = self.position(from, to);
+        let old_data = self.matrix[position].replace(data.clone());
+        if !self.is_directed() {
+            let mirror = self.position(to, from);
+            self.matrix[mirror] = Some(data);
+        }
+        if old_data.is_none() {
+            self.edge_count += 1;
+        }
+        check_invariants!(self);
+        old_data
+    }
+
+    pub fn remove_edge(&mut self, from: NodeIndex, to: NodeIndex) -> Option<E> {
+        let removed = self.take_edge(from, to)?;
+        check_invariants!(self);
+        Some(removed)
+    }
+
+    /// `remove_edge` without the invariant check, which fails halfway
+    /// through `remove_node`
+    fn take_edge(&mut self, from: NodeIndex, to: NodeIndex) -> Option<E> {
+        if from.index() >= self.dimension || to.index() >= self.dimension {
+            return None;
+        }
+        let position = self.position(from, to);
+        let removed = self.matrix[position].take()?;
+        if !self.is_directed() {
+            let mirror = self.position(to, from);
+            self.matrix[mirror] = None;
+        }
+        self.edge_count -= 1;
+        Some(removed)
+    }
+
+    /// O(1) lookup of the edge data
+    pub fn edge(&self, from: NodeIndex, to: NodeIndex) -> Option<&E> {
+        self.cell(from, to)
+    }
+
+    pub fn contains_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.cell(from, to).is_some()
+    }
+
+    pub fn node_indices(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, data)| data.is_some())
+            .map(|(index, _)| NodeIndex::new(index))
+    }
+
+    /// Neighbors in ascending index order, found by scanning the node's row
+    pub fn neighbors(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.edges(node).map(|(target, _)| target)
+    }
+
+    pub fn edges(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &E)> + '_ {
+        let row: &[Option<E>] = if self.contains_node(node) {
+            let start = node.index() * self.dimension;
+            &self.matrix[start..start + self.nodes.len()]
+        } else {
+            &[]
+        };
+        row.iter()
+            .enumerate()
+            .filter_map(|(target, data)| data.as_ref().map(|data| (NodeIndex::new(target), data)))
+    }
+
+    fn position(&self, from: NodeIndex, to: NodeIndex) -> usize {
+        from.index() * self.dimension + to.index()
+    }
+
+    fn cell(&self, from: NodeIndex, to: NodeIndex) -> Option<&E> {
+        if from.index() >= self.dimension || to.index() >= self.dimension {
+            return None;
+        }
+        self.matrix[self.position(from, to)].as_ref()
+    }
+
+    /// Copies the matrix into a larger one
+    fn grow(&mut self, new_dimension: usize) {
+        let mut matrix: Vec<Option<E>> = vec![None; new_dimension * new_dimension];
+        for from in 0..self.dimension {
+            for to in 0..self.dimension {
+                matrix[from * new_dimension + to] = self.matrix[from * self.dimension + to].take();
+            }
+        }
+        self.matrix = matrix;
+        self.dimension = new_dimension;
+    }
+}
+
+/// Checks the counts, that only cells between nodes in the graph hold edges,
+/// and that an undirected graph's matrix is symmetric
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<N, E: Clone> Invariants for MatrixGraph<N, E> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.matrix.len(), self.dimension * self.dimension, "Matrix isn't square");
+        assert!(self.nodes.len() <= self.dimension, "More nodes than the matrix has rows");
+        assert_eq!(self.node_indices().count(), self.node_count, "node_count is off");
+        let mut cells = 0;
+        let mut loops = 0;
+        for from in (0..self.dimension).map(NodeIndex::new) {
+            for to in (0..self.dimension).map(NodeIndex::new) {
+                if self.cell(from, to).is_none() {
+                    continue;
+                }
+                let between_nodes = self.contains_node(from) && self.contains_node(to);
+                assert!(between_nodes, "Edge leads to a removed or missing node");
+                if !self.is_directed() {
+                    assert!(self.contains_edge(to, from), "Undirected edge has one side");
+                }
+                cells += 1;
+                loops += usize::from(from == to);
+            }
+        }
+        let edges = if self.is_directed() { cells } else { (cells + loops) / 2 };
+        assert_eq!(edges, self.edge_count, "edge_count is off");
+    }
+}
+
+impl<N, E: Clone> GraphOps for MatrixGraph<N, E> {
+    type EdgeData = E;
+
+    fn is_directed(&self) -> bool {
+        self.is_directed()
+    }
+
+    fn node_count(&self) -> usize {
+        self.node_count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edge_count()
+    }
+
+    fn node_bound(&self) -> usize {
+        self.node_bound()
+    }
+
+    fn contains_node(&self, node: NodeIndex) -> bool {
+        self.contains_node(node)
+    }
+
+    fn node_indices(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.node_indices()
+    }
+
+    fn edge(&self, from: NodeIndex, to: NodeIndex) -> Option<&E> {
+        self.edge(from, to)
+    }
+
+    fn edges(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &E)> + '_ {
+        self.edges(node)
+    }
+}
+
+/// The matrix always has a cell for every pair of node slots
+impl<N: HeapSize, E: HeapSize> HeapSize for MatrixGraph<N, E> {
+    fn heap_size(&self) -> usize {
+        self.nodes.heap_size() + self.matrix.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+
+    /// Written once against the trait, used with both graph types
+    fn describe<G: GraphOps<EdgeData = u32>>(graph: &G) -> Vec<(usize, Vec<(usize, u32)>)> {
+        graph
+            .node_indices()
+            .map(|node| {
+                let mut edges: Vec<(usize, u32)> = graph
+                    .edges(node)
+                    .map(|(target, &weight)| (target.index(), weight))
+                    .collect();
+                edges.sort();
+                (node.index(), edges)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn when_graph_is_created_it_is_empty() {
+        let graph = MatrixGraph::<&str, ()>::new_directed();
+
+        assert!(graph.is_empty());
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(graph.dimension, 0);
+    }
+
+    #[test]
+    fn test_add_nodes_grows_matrix() {
+        let mut graph = MatrixGraph::<usize, ()>::new_undirected();
+
+        let nodes: Vec<NodeIndex> = (0..9).map(|n| graph.add_node(n)).collect();
+
+        assert_eq!(graph.node_count(), 9);
+        assert_eq!(graph.dimension, 16);
+        assert_eq!(graph.node(nodes[8]), Some(&8));
+    }
+
+    #[test]
+    fn test_edges_survive_matrix_growth() {
+        let mut graph = MatrixGraph::new_directed();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        graph.add_edge(a, b, 5);
+
+        for _ in 0..10 {
+            graph.add_node("X");
+        }
+
+        assert_eq!(graph.edge(a, b), Some(&5));
+        assert_eq!(graph.edge(b, a), None);
+    }
+
+    #[test]
+    fn test_undirected_edge_is_symmetric() {
+        let mut graph = MatrixGraph::new_undirected();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+
+        assert_eq!(graph.add_edge(a, b, 3), None);
+        assert_eq!(graph.add_edge(b, a, 4), Some(3));
+
+        assert_eq!(graph.edge(a, b), Some(&4));
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.remove_edge(a, b), Some(4));
+        assert!(!graph.contains_edge(b, a));
+        assert_eq!(graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn test_remove_node_clears_row_and_column() {
+        let mut graph = MatrixGraph::new_directed();
+        let nodes: Vec<NodeIndex> = (0..3).map(|n| graph.add_node(n)).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[1], nodes[2], ());
+        graph.add_edge(nodes[2], nodes[0], ());
+
+        assert_eq!(graph.remove_node(nodes[1]), Some(1));
+
+        assert_eq!(graph.edge_count(), 1);
+        assert!(graph.contains_edge(nodes[2], nodes[0]));
+        assert_eq!(graph.neighbors(nodes[0]).count(), 0);
+        assert_eq!(graph.node_indices().collect::<Vec<_>>(), vec![nodes[0], nodes[2]]);
+    }
+
+    #[test]
+    fn test_neighbors_in_index_order() {
+        let mut graph = MatrixGraph::new_directed();
+        let nodes: Vec<NodeIndex> = (0..4).map(|n| graph.add_node(n)).collect();
+        graph.add_edge(nodes[0], nodes[3], ());
+        graph.add_edge(nodes[0], nodes[1], ());
+
+        assert_eq!(graph.neighbors(nodes[0]).collect::<Vec<_>>(), vec![nodes[1], nodes[3]]);
+    }
+
+    #[test]
+    fn test_both_graph_types_agree_through_graph_ops() {
+        for kind in [GraphKind::Directed, GraphKind::Undirected] {
+            let mut list_graph = Graph::new(kind);
+            let mut matrix_graph = MatrixGraph::new(kind);
+            for n in 0..6 {
+                list_graph.add_node(n);
+                matrix_graph.add_node(n);
+            }
+            for (from, to, weight) in [(0, 1, 7), (1, 2, 3), (2, 0, 1), (3, 4, 9), (5, 5, 2)] {
+                list_graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), weight);
+                matrix_graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), weight);
+            }
+
+            assert_eq!(describe(&list_graph), describe(&matrix_graph));
+            assert_eq!(GraphOps::edge_count(&list_graph), GraphOps::edge_count(&matrix_graph));
+            assert_eq!(
+                GraphOps::out_degree(&list_graph, NodeIndex::new(1)),
+                GraphOps::out_degree(&matrix_graph, NodeIndex::new(1))
+            );
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! Graph implementation using adjacency lists
+//! Every node keeps a list of its outgoing edges. In an undirected graph an
+//! edge is stored in the lists of both of its ends. Removed nodes leave an
+//! empty slot behind, so indices of the remaining nodes never change.
+
+mod astar;
+mod bipartite;
+mod cycle;
+mod floyd_warshall;
+mod flow;
+mod grid;
+mod lca;
+mod matrix_graph;
+mod prim;
+mod topological;
+mod traversal;
+mod weighted;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+pub use astar::{ astar, Path };
+pub use bipartite::{ two_color, OddCycle, Side, TwoColoring };
+pub use cycle::find_cycle;
+pub use floyd_warshall::{ floyd_warshall, AllPairsShortestPaths };
+pub use flow::{ FlowNetwork, MaxFlow };
+pub use grid::{ euclidean, manhattan, GridWorld, Moves, Position };
+pub use lca::Lca;
+pub use matrix_graph::MatrixGraph;
+pub use prim::prim_mst;
+pub use topological::{ topological_order, topological_sort, CycleDetected, TopologicalOrder };
+pub use traversal::{ Bfs, Dfs, Visit };
+pub use weighted::{ Weight, WeightedGraph };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeIndex(usize);
+
+impl NodeIndex {
+    pub fn new(index: usize) -> Self {
+        NodeIndex(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphKind {
+    Directed,
+    Undirected,
+}
+
+/// Read-only view shared by the adjacency-list `Graph` and the `MatrixGraph`,
+/// so algorithms can be written once and run on either representation.
+pub trait GraphOps {
+    type EdgeData;
+
+    fn is_directed(&self) -> bool;
+    fn node_count(&self) -> usize;
+    fn edge_count(&self) -> usize;
+    /// Upper bound of node indices, including removed slots
+    fn node_bound(&self) -> usize;
+    fn contains_node(&self, node: NodeIndex) -> bool;
+    fn node_indices(&self) -> impl Iterator<Item = NodeIndex> + '_;
+    fn edge(&self, from: NodeIndex, to: NodeIndex) -> Option<&Self::EdgeData>;
+    /// Outgoing edges of a node as (target, edge data) pairs
+    fn edges(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &Self::EdgeData)> + '_;
+
+    fn contains_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.edge(from, to).is_some()
+    }
+
+    fn neighbors(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.edges(node).map(|(target, _)| target)
+    }
+
+    fn out_degree(&self, node: NodeIndex) -> usize {
+        self.edges(node).count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AdjacentEdge<E> {
+    target: NodeIndex,
+    data: E,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct NodeEntry<N, E> {
+    data: N,
+    edges: Vec<AdjacentEdge<E>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph<N, E> {
+    kind: GraphKind,
+    nodes: Vec<Option<NodeEntry<N, E>>>,
+    node_count: usize,
+    edge_count: usize,
+}
+
+impl<N, E: Clone> Graph<N, E> {
+    pub fn new(kind: GraphKind) -> Self {
+        Graph { kind, nodes: vec![], node_count: 0, edge_count: 0 }
+    }
+
+    pub fn new_directed() -> Self {
+        Self::new(GraphKind::Directed)
+    }
+
+    pub fn new_undirected() -> Self {
+        Self::new(GraphKind::Undirected)
+    }
+
+    pub fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.kind == GraphKind::Directed
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.node_count == 0
+    }
+
+    /// Upper bound of node indices, including removed slots.
+    /// Useful for sizing vectors indexed by `NodeIndex::index()`.
+    pub fn node_bound(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn add_node(&mut self, data: N) -> NodeIndex {
+        self.nodes.push(Some(NodeEntry { data, edges: vec![] }));
+        self.node_count += 1;
+        check_invariants!(self);
+        NodeIndex(self.nodes.len() - 1)
+    }
+
+    /// Removes the node together with all edges that touch it.
+    /// Other nodes keep their indices.
+    pub fn remove_node(&mut self, node: NodeIndex) -> Option<N> {
+        let entry = self.nodes.get_mut(node.0)?.take()?;
+        self.node_count -= 1;
+        self.edge_count -= entry.edges.len();
+
+        // Undirected edges were already counted in the node's own list,
+        // only incoming directed edges have to be counted here.
+        for other in self.nodes.iter_mut().flatten() {
+            let before = other.edges.len();
+            other.edges.retain(|edge| edge.target != node);
+            if self.kind == GraphKind::Directed {
+                self.edge_count -= before - other.edges.len();
+            }
+        }
+        check_invariants!(self);
+        Some(entry.data)
+    }
+
+    pub fn contains_node(&self, node: NodeIndex) -> bool {
+        self.entry(node).is_some()
+    }
+
+    pub fn node(&self, node: NodeIndex) -> Option<&N> {
+        self.entry(node).map(|entry| &entry.data)
+    }
+
+    pub fn node_mut(&mut self, node: NodeIndex) -> Option<&mut N> {
+        self.nodes.get_mut(node.0)?.as_mut().map(|entry| &mut entry.data)
+    }
+
+    /// Adds an edge from `from` to `to`. If the edge already exists
+    /// its data is replaced and the old data is returned.
+    pub fn add_edge(&mut self, from: NodeIndex, to: NodeIndex, data: E) -> Option<E> {
+        self.assert_node_exists(from);
+        self.assert_node_exists(to);
+
+        let old_data = self.insert_adjacent(from, to, data.clone());
+        if !self.is_directed() && from != to {
+            self.insert_adjacent(to, from, data);
+        }
+        if old_data.is_none() {
+            self.edge_count += 1;
+        }
+        check_invariants!(self);
+        old_data
+    }
+
+    pub fn remove_edge(&mut self, from: NodeIndex, to: NodeIndex) -> Option<E> {
+        let removed = self.remove_adjacent(from, to)?;
+        if !self.is_directed() && from != to {
+            self.remove_adjacent(to, from);
+        }
+        self.edge_count -= 1;
+        check_invariants!(self);
+        Some(removed)
+    }
+
+    pub fn contains_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.edge(from, to).is_some()
+    }
+
+    pub fn edge(&self, from: NodeIndex, to: NodeIndex) -> Option<&E> {
+        self.entry(from)?
+            .edges.iter()
+            .find(|edge| edge.target == to)
+            .map(|edge| &edge.data)
+    }
+
+    /// Indices of all nodes that are still in the graph
+    pub fn node_indices(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_some())
+            .map(|(index, _)| NodeIndex(index))
+    }
+
+    /// Nodes reachable by a single outgoing edge, in insertion order
+    pub fn neighbors(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.edges(node).map(|(target, _)| target)
+    }
+
+    /// Outgoing edges of a node as (target, edge data) pairs
+    pub fn edges(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &E)> + '_ {
+        self.entry(node)
+            .into_iter()
+            .flat_map(|entry| entry.edges.iter().map(|edge| (edge.target, &edge.data)))
+    }
+
+    /// Every edge once as (from, to, data). An undirected edge is reported
+    /// only from its lower node index.
+    pub fn all_edges(&self) -> impl Iterator<Item = (NodeIndex, NodeIndex, &E)> + '_ {
+        self.node_indices().flat_map(move |from| {
+            self.edges(from)
+                .filter(move |(to, _)| self.is_directed() || from <= *to)
+                .map(move |(to, data)| (from, to, data))
+        })
+    }
+
+    pub fn out_degree(&self, node: NodeIndex) -> usize {
+        self.entry(node).map_or(0, |entry| entry.edges.len())
+    }
+
+    fn entry(&self, node: NodeIndex) -> Option<&NodeEntry<N, E>> {
+        self.nodes.get(node.0).and_then(|entry| entry.as_ref())
+    }
+
+    fn entry_mut(&mut self, node: NodeIndex) -> &mut NodeEntry<N, E> {
+        self.nodes[node.0].as_mut().expect("Node exists")
+    }
+
+    fn assert_node_exists(&self, node: NodeIndex) {
+        if !self.contains_node(node) {
+            panic!("Node {} is not in the graph!", node.0);
+        }
+    }
+
+    fn insert_adjacent(&mut self, from: NodeIndex, to: NodeIndex, data: E) -> Option<E> {
+        let edges = &mut self.entry_mut(from).edges;
+        match edges.iter_mut().find(|edge| edge.target == to) {
+            Some(edge) => Some(std::mem::replace(&mut edge.data, data)),
+            None => {
+                edges.push(AdjacentEdge { target: to, data });
+                None
+            }
+        }
+    }
+
+    fn remove_adjacent(&mut self, from: NodeIndex, to: NodeIndex) -> Option<E> {
+        let edges = &mut self.nodes.get_mut(from.0)?.as_mut()?.edges;
+        let position = edges.iter().position(|edge| edge.target == to)?;
+        Some(edges.remove(position).data)
+    }
+}
+
+/// Checks the counts, that edges only lead to nodes in the graph, at most
+/// one per pair, and that an undirected edge is in the lists of both ends
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<N, E: Clone> Invariants for Graph<N, E> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.node_indices().count(), self.node_count, "node_count is off");
+        let mut adjacent = 0;
+        let mut loops = 0;
+        for from in self.node_indices() {
+            let targets: Vec<NodeIndex> = self.neighbors(from).collect();
+            for (position, &to) in targets.iter().enumerate() {
+                assert!(self.contains_node(to), "Edge leads to a removed node");
+                assert!(!targets[..position].contains(&to), "Edge is stored twice");
+                if !self.is_directed() {
+                    assert!(self.contains_edge(to, from), "Undirected edge has one side");
+                }
+            }
+            adjacent += targets.len();
+            loops += targets.iter().filter(|&&to| to == from).count();
+        }
+        let edges = if self.is_directed() { adjacent } else { (adjacent + loops) / 2 };
+        assert_eq!(edges, self.edge_count, "edge_count is off");
+    }
+}
+
+impl<N, E: Clone> GraphOps for Graph<N, E> {
+    type EdgeData = E;
+
+    fn is_directed(&self) -> bool {
+        self.is_directed()
+    }
+
+    fn node_count(&self) -> usize {
+        self.node_count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edge_count()
+    }
+
+    fn node_bound(&self) -> usize {
+        self.node_bound()
+    }
+
+    fn contains_node(&self, node: NodeIndex) -> bool {
+        self.contains_node(node)
+    }
+
+    fn node_indices(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.node_indices()
+    }
+
+    fn edge(&self, from: NodeIndex, to: NodeIndex) -> Option<&E> {
+        self.edge(from, to)
+    }
+
+    fn edges(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &E)> + '_ {
+        self.edges(node)
+    }
+}
+
+impl HeapSize for NodeIndex {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<E: HeapSize> HeapSize for AdjacentEdge<E> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
+}
+
+impl<N: HeapSize, E: HeapSize> HeapSize for NodeEntry<N, E> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size() + self.edges.heap_size()
+    }
+}
+
+/// Removed nodes keep their slots until the graph is dropped
+impl<N: HeapSize, E: HeapSize> HeapSize for Graph<N, E> {
+    fn heap_size(&self) -> usize {
+        self.nodes.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(kind: GraphKind) -> (Graph<&'static str, u32>, Vec<NodeIndex>) {
+        let mut graph = Graph::new(kind);
+        let nodes: Vec<NodeIndex> = ["A", "B", "C"].into_iter().map(|n| graph.add_node(n)).collect();
+        graph.add_edge(nodes[0], nodes[1], 1);
+        graph.add_edge(nodes[1], nodes[2], 2);
+        graph.add_edge(nodes[2], nodes[0], 3);
+        (graph, nodes)
+    }
+
+    #[test]
+    fn when_graph_is_created_it_is_empty() {
+        let graph = Graph::<&str, ()>::new_directed();
+
+        assert!(graph.is_empty());
+        assert!(graph.is_directed());
+        assert_eq!(graph.node_count(), 0);
+        assert_eq!(graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn test_add_nodes_returns_sequential_indices() {
+        let mut graph = Graph::<&str, ()>::new_undirected();
+
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+
+        assert_eq!(a.index(), 0);
+        assert_eq!(b.index(), 1);
+        assert_eq!(graph.node(b), Some(&"B"));
+        assert_eq!(graph.node_count(), 2);
+    }
+
+    #[test]
+    fn test_directed_edge_only_goes_one_way() {
+        let (graph, nodes) = triangle(GraphKind::Directed);
+
+        assert!(graph.contains_edge(nodes[0], nodes[1]));
+        assert!(!graph.contains_edge(nodes[1], nodes[0]));
+        assert_eq!(graph.edge(nodes[2], nodes[0]), Some(&3));
+        assert_eq!(graph.edge_count(), 3);
+    }
+
+    #[test]
+    fn test_undirected_edge_goes_both_ways() {
+        let (graph, nodes) = triangle(GraphKind::Undirected);
+
+        assert_eq!(graph.edge(nodes[1], nodes[0]), Some(&1));
+        assert_eq!(graph.edge(nodes[0], nodes[2]), Some(&3));
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.out_degree(nodes[0]), 2);
+    }
+
+    #[test]
+    fn test_add_existing_edge_replaces_data() {
+        let (mut graph, nodes) = triangle(GraphKind::Undirected);
+
+        let result = graph.add_edge(nodes[1], nodes[0], 10);
+
+        assert_eq!(result, Some(1));
+        assert_eq!(graph.edge(nodes[0], nodes[1]), Some(&10));
+        assert_eq!(graph.edge_count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Node 7 is not in the graph!")]
+    fn test_add_edge_to_missing_node_panics() {
+        let (mut graph, nodes) = triangle(GraphKind::Directed);
+        graph.add_edge(nodes[0], NodeIndex::new(7), 0);
+    }
+
+    #[test]
+    fn test_neighbors_in_insertion_order() {
+        let mut graph = Graph::<u8, ()>::new_directed();
+        let nodes: Vec<NodeIndex> = (0..4).map(|n| graph.add_node(n)).collect();
+        graph.add_edge(nodes[0], nodes[3], ());
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[0], nodes[2], ());
+
+        let neighbors: Vec<NodeIndex> = graph.neighbors(nodes[0]).collect();
+
+        assert_eq!(neighbors, vec![nodes[3], nodes[1], nodes[2]]);
+        assert_eq!(graph.neighbors(nodes[3]).count(), 0);
+    }
+
+    #[test]
+    fn test_remove_edge() {
+        let (mut graph, nodes) = triangle(GraphKind::Undirected);
+
+        assert_eq!(graph.remove_edge(nodes[2], nodes[1]), Some(2));
+        assert_eq!(graph.remove_edge(nodes[2], nodes[1]), None);
+
+        assert!(!graph.contains_edge(nodes[1], nodes[2]));
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_remove_node_keeps_other_indices_stable() {
+        let (mut graph, nodes) = triangle(GraphKind::Directed);
+        let d = graph.add_node("D");
+        graph.add_edge(d, nodes[1], 4);
+
+        let removed = graph.remove_node(nodes[1]);
+
+        assert_eq!(removed, Some("B"));
+        assert!(!graph.contains_node(nodes[1]));
+        assert_eq!(graph.node(d), Some(&"D"));
+        assert_eq!(graph.node(nodes[2]), Some(&"C"));
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 1, "Only C -> A is left");
+        assert_eq!(graph.node_indices().collect::<Vec<_>>(), vec![nodes[0], nodes[2], d]);
+        assert_eq!(graph.remove_node(nodes[1]), None);
+    }
+
+    #[test]
+    fn test_remove_node_from_undirected_graph() {
+        let (mut graph, nodes) = triangle(GraphKind::Undirected);
+
+        graph.remove_node(nodes[0]);
+
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.neighbors(nodes[1]).collect::<Vec<_>>(), vec![nodes[2]]);
+    }
+
+    #[test]
+    fn test_self_loop_in_undirected_graph() {
+        let mut graph = Graph::<&str, ()>::new_undirected();
+        let a = graph.add_node("A");
+        graph.add_edge(a, a, ());
+
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.out_degree(a), 1);
+
+        graph.remove_node(a);
+        assert_eq!(graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn test_all_edges_reports_undirected_edges_once() {
+        let (graph, nodes) = triangle(GraphKind::Undirected);
+
+        let mut edges: Vec<(NodeIndex, NodeIndex, u32)> = graph
+            .all_edges()
+            .map(|(from, to, data)| (from, to, *data))
+            .collect();
+        edges.sort();
+
+        assert_eq!(
+            edges,
+            vec![(nodes[0], nodes[1], 1), (nodes[0], nodes[2], 3), (nodes[1], nodes[2], 2)]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Prim's minimum spanning tree
+//! Grows the tree from a start node, always adding the lightest edge from a
+//! tree node to a node outside the tree. Every outside node is kept in an
+//! indexed priority queue under the weight of its lightest edge to the tree,
+//! and that priority is lowered in place when a lighter edge shows up, so the
+//! queue never holds more than one entry per node: O(E log V).
+
+use crate::indexed_priority_queue::IndexedPriorityQueue;
+
+use super::weighted::Priority;
+use super::{ GraphOps, NodeIndex, Weight };
+
+/// Edges of a minimum spanning tree of the component of `start`, as
+/// (tree node, new node, weight) in the order they were added
+pub fn prim_mst<G>(graph: &G, start: NodeIndex) -> Vec<(NodeIndex, NodeIndex, G::EdgeData)>
+where
+    G: GraphOps,
+    G::EdgeData: Weight,
+{
+    assert!(!graph.is_directed(), "Spanning trees need an undirected graph");
+    if !graph.contains_node(start) {
+        panic!("Node {} is not in the graph!", start.index());
+    }
+    let mut in_tree = vec![false; graph.node_bound()];
+    // Lightest known edge from the tree to every queued node
+    let mut lightest: Vec<Option<(NodeIndex, G::EdgeData)>> = vec![None; graph.node_bound()];
+    let mut queue = IndexedPriorityQueue::new();
+    queue.push(start, Priority(G::EdgeData::zero()));
+    let mut tree = vec![];
+
+    while let Some((node, _)) = queue.pop() {
+        in_tree[node.index()] = true;
+        if let Some((from, weight)) = lightest[node.index()] {
+            tree.push((from, node, weight));
+        }
+        for (neighbor, &weight) in graph.edges(node) {
+            if in_tree[neighbor.index()] {
+                continue;
+            }
+            if lightest[neighbor.index()].is_none_or(|(_, best)| weight < best) {
+                lightest[neighbor.index()] = Some((node, weight));
+                queue.push(neighbor, Priority(weight));
+            }
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ Graph, GraphKind, MatrixGraph };
+
+    /// Kruskal's algorithm with a union-find, as a reference
+    fn kruskal(graph: &Graph<usize, u32>) -> u32 {
+        fn find(parent: &mut Vec<usize>, node: usize) -> usize {
+            if parent[node] != node {
+                parent[node] = find(parent, parent[node]);
+            }
+            parent[node]
+        }
+        let mut edges: Vec<(u32, usize, usize)> = graph
+            .all_edges()
+            .map(|(from, to, &weight)| (weight, from.index(), to.index()))
+            .collect();
+        edges.sort();
+        let mut parent: Vec<usize> = (0..graph.node_bound()).collect();
+        let mut total = 0;
+        for (weight, from, to) in edges {
+            let (from_root, to_root) = (find(&mut parent, from), find(&mut parent, to));
+            if from_root != to_root {
+                parent[from_root] = to_root;
+                total += weight;
+            }
+        }
+        total
+    }
+
+    fn total<W: Weight>(tree: &[(NodeIndex, NodeIndex, W)]) -> W {
+        tree.iter().fold(W::zero(), |total, &(_, _, weight)| total + weight)
+    }
+
+    #[test]
+    fn when_start_is_isolated_tree_is_empty() {
+        let mut graph = Graph::<(), u32>::new_undirected();
+        let a = graph.add_node(());
+        graph.add_node(());
+
+        assert!(prim_mst(&graph, a).is_empty());
+    }
+
+    #[test]
+    fn test_textbook_example() {
+        // Square A-B-C-D with diagonal A-C
+        let mut graph = Graph::new_undirected();
+        let nodes: Vec<NodeIndex> = "ABCD".chars().map(|n| graph.add_node(n)).collect();
+        for (from, to, weight) in [(0, 1, 1), (1, 2, 4), (2, 3, 2), (3, 0, 5), (0, 2, 3)] {
+            graph.add_edge(nodes[from], nodes[to], weight);
+        }
+
+        let tree = prim_mst(&graph, nodes[0]);
+
+        assert_eq!(
+            tree,
+            vec![(nodes[0], nodes[1], 1), (nodes[0], nodes[2], 3), (nodes[2], nodes[3], 2)]
+        );
+    }
+
+    #[test]
+    fn test_only_spans_component_of_start() {
+        let mut graph = MatrixGraph::<(), f64>::new_undirected();
+        let nodes: Vec<NodeIndex> = (0..5).map(|_| graph.add_node(())).collect();
+        graph.add_edge(nodes[0], nodes[1], 0.5);
+        graph.add_edge(nodes[1], nodes[2], 1.5);
+        graph.add_edge(nodes[0], nodes[2], 2.5);
+        graph.add_edge(nodes[3], nodes[4], 1.0);
+
+        let tree = prim_mst(&graph, nodes[2]);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(total(&tree), 2.0);
+    }
+
+    #[test]
+    fn test_random_dense_graphs_match_kruskal() {
+        let mut state: u64 = 11;
+        for _ in 0..20 {
+            let mut graph = Graph::new(GraphKind::Undirected);
+            let nodes: Vec<NodeIndex> = (0..30).map(|n| graph.add_node(n)).collect();
+            for from in 0..nodes.len() {
+                for to in from + 1..nodes.len() {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    if (state >> 62) != 0 {
+                        graph.add_edge(nodes[from], nodes[to], ((state >> 33) % 100) as u32);
+                    }
+                }
+            }
+
+            let tree = prim_mst(&graph, nodes[0]);
+
+            assert_eq!(tree.len(), nodes.len() - 1);
+            assert_eq!(total(&tree), kruskal(&graph));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Spanning trees need an undirected graph")]
+    fn test_directed_graph_panics() {
+        let mut graph = Graph::<(), u32>::new_directed();
+        let a = graph.add_node(());
+        prim_mst(&graph, a);
+    }
+}
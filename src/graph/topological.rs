@@ -0,0 +1,187 @@
+//! Topological sort
+//! Orders the nodes of a directed graph so every edge goes from an earlier
+//! node to a later one, using Kahn's algorithm: count the incoming edges of
+//! every node, start with the nodes that have none, and each time a node is
+//! taken remove its outgoing edges, which frees the targets whose count drops
+//! to zero. Nodes on a cycle never reach zero, so a graph with a cycle runs
+//! out of free nodes before every node is ordered.
+
+use crate::queue::Queue;
+
+use super::{ GraphOps, NodeIndex };
+
+/// The graph has a cycle, so only part of it could be ordered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleDetected {
+    /// Nodes left unordered: the nodes on cycles and the nodes reachable from them
+    pub unordered: Vec<NodeIndex>,
+}
+
+/// Nodes in topological order, ties broken by node index.
+/// Fails if the graph has a cycle.
+pub fn topological_sort<G: GraphOps>(graph: &G) -> Result<Vec<NodeIndex>, CycleDetected> {
+    let mut order = topological_order(graph);
+    let sorted: Vec<NodeIndex> = order.by_ref().collect();
+    if order.remaining() > 0 {
+        let unordered = graph
+            .node_indices()
+            .filter(|node| order.in_degree[node.index()] > 0)
+            .collect();
+        return Err(CycleDetected { unordered });
+    }
+    Ok(sorted)
+}
+
+/// Lazy topological order, see `TopologicalOrder`
+pub fn topological_order<G: GraphOps>(graph: &G) -> TopologicalOrder<'_, G> {
+    TopologicalOrder::new(graph)
+}
+
+/// Yields a node as soon as all of its predecessors were yielded. On a graph
+/// with a cycle it stops early, leaving `remaining` nodes that were never yielded.
+pub struct TopologicalOrder<'a, G> {
+    graph: &'a G,
+    /// Incoming edges from nodes that weren't yielded yet
+    in_degree: Vec<usize>,
+    /// Nodes with no such edges left
+    free: Queue<NodeIndex>,
+    remaining: usize,
+}
+
+impl<'a, G: GraphOps> TopologicalOrder<'a, G> {
+    pub fn new(graph: &'a G) -> Self {
+        assert!(graph.is_directed(), "Topological order needs a directed graph");
+        let mut in_degree = vec![0; graph.node_bound()];
+        for node in graph.node_indices() {
+            for neighbor in graph.neighbors(node) {
+                in_degree[neighbor.index()] += 1;
+            }
+        }
+        let mut free = Queue::new();
+        for node in graph.node_indices().filter(|node| in_degree[node.index()] == 0) {
+            free.add(node);
+        }
+        TopologicalOrder { graph, in_degree, free, remaining: graph.node_count() }
+    }
+
+    /// Nodes not yielded yet
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<G: GraphOps> Iterator for TopologicalOrder<'_, G> {
+    type Item = NodeIndex;
+
+    fn next(&mut self) -> Option<NodeIndex> {
+        let node = self.free.remove()?;
+        for neighbor in self.graph.neighbors(node) {
+            self.in_degree[neighbor.index()] -= 1;
+            if self.in_degree[neighbor.index()] == 0 {
+                self.free.add(neighbor);
+            }
+        }
+        self.remaining -= 1;
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ Graph, MatrixGraph };
+
+    /// Getting dressed: an edge means "put on before"
+    fn clothes() -> (Graph<&'static str, ()>, Vec<NodeIndex>) {
+        let mut graph = Graph::new_directed();
+        let items = ["socks", "underwear", "pants", "shoes", "shirt", "belt", "tie", "jacket"];
+        let nodes: Vec<NodeIndex> = items.into_iter().map(|item| graph.add_node(item)).collect();
+        let rules = [(0, 3), (1, 2), (1, 3), (2, 3), (2, 5), (4, 5), (4, 6), (5, 7), (6, 7)];
+        for (before, after) in rules {
+            graph.add_edge(nodes[before], nodes[after], ());
+        }
+        (graph, nodes)
+    }
+
+    fn assert_respects_edges(graph: &Graph<&str, ()>, order: &[NodeIndex]) {
+        let position = |node: NodeIndex| order.iter().position(|&n| n == node).unwrap();
+        for (from, to, _) in graph.all_edges() {
+            assert!(position(from) < position(to), "{:?} comes before {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn when_graph_is_empty_order_is_empty() {
+        let graph = Graph::<(), ()>::new_directed();
+
+        assert_eq!(topological_sort(&graph), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_sort_respects_every_edge() {
+        let (graph, nodes) = clothes();
+
+        let order = topological_sort(&graph).unwrap();
+
+        assert_eq!(order.len(), nodes.len());
+        assert_respects_edges(&graph, &order);
+        let names: Vec<&str> = order.iter().map(|&node| *graph.node(node).unwrap()).collect();
+        assert_eq!(
+            names,
+            vec!["socks", "underwear", "shirt", "pants", "tie", "shoes", "belt", "jacket"]
+        );
+    }
+
+    #[test]
+    fn test_cycle_is_detected() {
+        let (mut graph, nodes) = clothes();
+        graph.add_edge(nodes[7], nodes[4], ());
+
+        let result = topological_sort(&graph);
+
+        let unordered = vec![nodes[4], nodes[5], nodes[6], nodes[7]];
+        assert_eq!(result, Err(CycleDetected { unordered }));
+    }
+
+    #[test]
+    fn test_self_loop_is_a_cycle() {
+        let mut graph = MatrixGraph::<(), ()>::new_directed();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.add_edge(b, b, ());
+
+        assert_eq!(topological_sort(&graph), Err(CycleDetected { unordered: vec![b] }));
+        assert_eq!(topological_order(&graph).collect::<Vec<_>>(), vec![a]);
+    }
+
+    #[test]
+    fn test_order_is_lazy() {
+        let (graph, nodes) = clothes();
+        let mut order = topological_order(&graph);
+
+        let first_two: Vec<NodeIndex> = order.by_ref().take(2).collect();
+
+        assert_eq!(first_two, vec![nodes[0], nodes[1]]);
+        assert_eq!(order.remaining(), 6);
+        assert_eq!(order.count(), 6);
+    }
+
+    #[test]
+    fn test_order_stops_at_cycle() {
+        let (mut graph, nodes) = clothes();
+        graph.add_edge(nodes[3], nodes[0], ());
+        let mut order = topological_order(&graph);
+
+        let yielded: Vec<NodeIndex> = order.by_ref().collect();
+
+        assert_eq!(yielded, vec![nodes[1], nodes[4], nodes[2], nodes[6], nodes[5], nodes[7]]);
+        assert_eq!(order.remaining(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Topological order needs a directed graph")]
+    fn test_undirected_graph_panics() {
+        let graph = Graph::<(), ()>::new_undirected();
+        let _ = topological_sort(&graph);
+    }
+}
@@ -0,0 +1,251 @@
+//! Breadth-first and depth-first traversals
+//! Both are lazy iterators that visit every node reachable from the start
+//! node once, following outgoing edges in insertion order. BFS keeps the
+//! nodes to visit in a queue and visits them by distance from the start,
+//! DFS keeps them on a stack and goes as deep as it can before backtracking,
+//! in the same order as the recursive algorithm. Each visit also tells which
+//! node it was reached from and how many edges away from the start it is.
+//! They work on any `GraphOps`, so on both graph representations.
+
+use crate::queue::Queue;
+
+use super::{ Graph, GraphOps, NodeIndex };
+
+/// A node reached by a traversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visit {
+    pub node: NodeIndex,
+    /// Node whose edge led here, None for the start node
+    pub predecessor: Option<NodeIndex>,
+    /// Number of edges on the traversal's path from the start node
+    pub depth: usize,
+}
+
+fn assert_start_exists<G: GraphOps>(graph: &G, start: NodeIndex) {
+    if !graph.contains_node(start) {
+        panic!("Node {} is not in the graph!", start.index());
+    }
+}
+
+/// Breadth-first traversal, nodes come in order of their distance from the start
+pub struct Bfs<'a, G> {
+    graph: &'a G,
+    queue: Queue<Visit>,
+    /// Nodes that were put in the queue, so none is queued twice
+    discovered: Vec<bool>,
+}
+
+impl<'a, G: GraphOps> Bfs<'a, G> {
+    pub fn new(graph: &'a G, start: NodeIndex) -> Self {
+        assert_start_exists(graph, start);
+        let mut discovered = vec![false; graph.node_bound()];
+        discovered[start.index()] = true;
+        let mut queue = Queue::new();
+        queue.add(Visit { node: start, predecessor: None, depth: 0 });
+        Bfs { graph, queue, discovered }
+    }
+}
+
+impl<G: GraphOps> Iterator for Bfs<'_, G> {
+    type Item = Visit;
+
+    fn next(&mut self) -> Option<Visit> {
+        let visit = self.queue.remove()?;
+        for neighbor in self.graph.neighbors(visit.node) {
+            if !self.discovered[neighbor.index()] {
+                self.discovered[neighbor.index()] = true;
+                self.queue.add(Visit {
+                    node: neighbor,
+                    predecessor: Some(visit.node),
+                    depth: visit.depth + 1,
+                });
+            }
+        }
+        Some(visit)
+    }
+}
+
+/// Depth-first traversal, nodes come in preorder
+pub struct Dfs<'a, G> {
+    graph: &'a G,
+    /// Nodes waiting to be visited, a node can be on it more than once
+    stack: Vec<Visit>,
+    visited: Vec<bool>,
+}
+
+impl<'a, G: GraphOps> Dfs<'a, G> {
+    pub fn new(graph: &'a G, start: NodeIndex) -> Self {
+        assert_start_exists(graph, start);
+        let stack = vec![Visit { node: start, predecessor: None, depth: 0 }];
+        Dfs { graph, stack, visited: vec![false; graph.node_bound()] }
+    }
+}
+
+impl<G: GraphOps> Iterator for Dfs<'_, G> {
+    type Item = Visit;
+
+    fn next(&mut self) -> Option<Visit> {
+        // A node is only visited when it is popped, from the last node that
+        // pushed it, which is the node the recursive algorithm would come from
+        let visit = loop {
+            let visit = self.stack.pop()?;
+            if !self.visited[visit.node.index()] {
+                break visit;
+            }
+        };
+        self.visited[visit.node.index()] = true;
+        let neighbors: Vec<NodeIndex> = self.graph
+            .neighbors(visit.node)
+            .filter(|neighbor| !self.visited[neighbor.index()])
+            .collect();
+        // Pushed in reverse so the first neighbor is visited first
+        self.stack.extend(
+            neighbors.into_iter().rev().map(|neighbor| Visit {
+                node: neighbor,
+                predecessor: Some(visit.node),
+                depth: visit.depth + 1,
+            })
+        );
+        Some(visit)
+    }
+}
+
+impl<N, E: Clone> Graph<N, E> {
+    /// Nodes reachable from `start` in breadth-first order
+    pub fn bfs(&self, start: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.bfs_visits(start).map(|visit| visit.node)
+    }
+
+    /// Breadth-first traversal that also reports predecessors and depths
+    pub fn bfs_visits(&self, start: NodeIndex) -> Bfs<'_, Self> {
+        Bfs::new(self, start)
+    }
+
+    /// Nodes reachable from `start` in depth-first preorder
+    pub fn dfs(&self, start: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.dfs_visits(start).map(|visit| visit.node)
+    }
+
+    /// Depth-first traversal that also reports predecessors and depths
+    pub fn dfs_visits(&self, start: NodeIndex) -> Dfs<'_, Self> {
+        Dfs::new(self, start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{ GraphKind, MatrixGraph };
+
+    ///   0
+    ///  / \
+    /// 1   2
+    /// |   | \
+    /// 3   4  5
+    ///  \ /
+    ///   6
+    fn tree_with_join(kind: GraphKind) -> (Graph<usize, ()>, Vec<NodeIndex>) {
+        let mut graph = Graph::new(kind);
+        let nodes: Vec<NodeIndex> = (0..7).map(|n| graph.add_node(n)).collect();
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 4), (2, 5), (3, 6), (4, 6)] {
+            graph.add_edge(nodes[from], nodes[to], ());
+        }
+        (graph, nodes)
+    }
+
+    fn indices(nodes: impl Iterator<Item = NodeIndex>) -> Vec<usize> {
+        nodes.map(|node| node.index()).collect()
+    }
+
+    #[test]
+    fn when_start_has_no_edges_only_start_is_visited() {
+        let mut graph = Graph::<(), ()>::new_directed();
+        let a = graph.add_node(());
+        graph.add_node(());
+
+        assert_eq!(graph.bfs(a).collect::<Vec<_>>(), vec![a]);
+        assert_eq!(graph.dfs(a).collect::<Vec<_>>(), vec![a]);
+    }
+
+    #[test]
+    fn test_bfs_visits_by_distance() {
+        let (graph, nodes) = tree_with_join(GraphKind::Directed);
+
+        assert_eq!(indices(graph.bfs(nodes[0])), vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(indices(graph.bfs(nodes[2])), vec![2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_dfs_goes_deep_first() {
+        let (graph, nodes) = tree_with_join(GraphKind::Directed);
+
+        assert_eq!(indices(graph.dfs(nodes[0])), vec![0, 1, 3, 6, 2, 4, 5]);
+    }
+
+    #[test]
+    fn test_dfs_in_undirected_graph_matches_recursion() {
+        let (graph, nodes) = tree_with_join(GraphKind::Undirected);
+
+        fn recursive(graph: &Graph<usize, ()>, node: NodeIndex, order: &mut Vec<usize>) {
+            order.push(node.index());
+            for neighbor in graph.neighbors(node) {
+                if !order.contains(&neighbor.index()) {
+                    recursive(graph, neighbor, order);
+                }
+            }
+        }
+        let mut expected = vec![];
+        recursive(&graph, nodes[5], &mut expected);
+
+        assert_eq!(indices(graph.dfs(nodes[5])), expected);
+        assert_eq!(expected, vec![5, 2, 0, 1, 3, 6, 4]);
+    }
+
+    #[test]
+    fn test_visits_report_predecessor_and_depth() {
+        let (graph, nodes) = tree_with_join(GraphKind::Directed);
+
+        let bfs: Vec<Visit> = graph.bfs_visits(nodes[0]).collect();
+        let dfs: Vec<Visit> = graph.dfs_visits(nodes[0]).collect();
+
+        assert_eq!(bfs[6], Visit { node: nodes[6], predecessor: Some(nodes[3]), depth: 3 });
+        assert_eq!(bfs[0].predecessor, None);
+        let dfs_of_4 = dfs.iter().find(|visit| visit.node == nodes[4]).unwrap();
+        assert_eq!(dfs_of_4.predecessor, Some(nodes[2]));
+        assert_eq!(dfs_of_4.depth, 2);
+    }
+
+    #[test]
+    fn test_traversals_compose_with_iterator_adapters() {
+        let (graph, nodes) = tree_with_join(GraphKind::Undirected);
+
+        let within_one: Vec<usize> = graph
+            .bfs_visits(nodes[0])
+            .take_while(|visit| visit.depth <= 1)
+            .map(|visit| visit.node.index())
+            .collect();
+        let first_leaf = graph.dfs(nodes[0]).find(|&node| graph.out_degree(node) == 1);
+
+        assert_eq!(within_one, vec![0, 1, 2]);
+        assert_eq!(first_leaf, Some(nodes[5]));
+    }
+
+    #[test]
+    fn test_traversal_of_matrix_graph() {
+        let mut graph = MatrixGraph::<char, ()>::new_directed();
+        let nodes: Vec<NodeIndex> = "abcd".chars().map(|n| graph.add_node(n)).collect();
+        graph.add_edge(nodes[0], nodes[2], ());
+        graph.add_edge(nodes[2], nodes[1], ());
+        graph.add_edge(nodes[3], nodes[0], ());
+
+        assert_eq!(indices(Bfs::new(&graph, nodes[0]).map(|visit| visit.node)), vec![0, 2, 1]);
+        assert_eq!(Dfs::new(&graph, nodes[3]).count(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Node 3 is not in the graph!")]
+    fn test_traversal_from_missing_node_panics() {
+        let graph = Graph::<(), ()>::new_directed();
+        let _ = graph.bfs(NodeIndex::new(3));
+    }
+}
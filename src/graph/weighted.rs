@@ -0,0 +1,162 @@
+//! Weighted graphs
+//! A weighted graph is a `Graph` whose edge data is a `Weight`. Shortest path
+//! and minimum spanning tree algorithms only need to add weights together
+//! and compare them, which is all the `Weight` trait asks for.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::Add;
+
+use super::{ Graph, NodeIndex };
+
+pub trait Weight: Copy + PartialOrd + Add<Output = Self> + Debug {
+    fn zero() -> Self;
+}
+
+macro_rules! impl_weight {
+    ($($t:ty => $zero:expr),*) => {
+        $(
+            impl Weight for $t {
+                fn zero() -> Self {
+                    $zero
+                }
+            }
+        )*
+    };
+}
+
+impl_weight!(
+    i8 => 0, i16 => 0, i32 => 0, i64 => 0, i128 => 0, isize => 0,
+    u8 => 0, u16 => 0, u32 => 0, u64 => 0, u128 => 0, usize => 0,
+    f32 => 0.0, f64 => 0.0
+);
+
+/// Weight usable as an `Ord` priority. Weights are only `PartialOrd` so that
+/// floats can be weights, comparing a NaN panics.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Priority<W>(pub W);
+
+impl<W: Weight> PartialEq for Priority<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W: Weight> Eq for Priority<W> {}
+
+impl<W: Weight> PartialOrd for Priority<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: Weight> Ord for Priority<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).expect("Weights must be comparable")
+    }
+}
+
+/// Graph whose edges carry weights
+pub type WeightedGraph<N, W> = Graph<N, W>;
+
+impl<N, W: Weight> Graph<N, W> {
+    /// Weight of the edge from `from` to `to`, if there is one
+    pub fn weight(&self, from: NodeIndex, to: NodeIndex) -> Option<W> {
+        self.edge(from, to).copied()
+    }
+
+    /// Changes the weight of an existing edge and returns the old weight.
+    /// Unlike `add_edge`, a missing edge is not created.
+    pub fn set_weight(&mut self, from: NodeIndex, to: NodeIndex, weight: W) -> Option<W> {
+        if !self.contains_edge(from, to) {
+            return None;
+        }
+        self.add_edge(from, to, weight)
+    }
+
+    /// Outgoing edges of a node as (neighbor, weight) pairs
+    pub fn weighted_neighbors(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, W)> + '_ {
+        self.edges(node).map(|(target, &weight)| (target, weight))
+    }
+
+    /// Sum of the weights of all edges, undirected edges counted once
+    pub fn total_weight(&self) -> W {
+        self.all_edges().fold(W::zero(), |total, (_, _, &weight)| total + weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::GraphKind;
+
+    fn weighted_square(kind: GraphKind) -> (WeightedGraph<char, u32>, Vec<NodeIndex>) {
+        let mut graph = Graph::new(kind);
+        let nodes: Vec<NodeIndex> = "ABCD".chars().map(|n| graph.add_node(n)).collect();
+        graph.add_edge(nodes[0], nodes[1], 4);
+        graph.add_edge(nodes[1], nodes[2], 1);
+        graph.add_edge(nodes[2], nodes[3], 7);
+        graph.add_edge(nodes[3], nodes[0], 2);
+        (graph, nodes)
+    }
+
+    #[test]
+    fn test_weight_lookup() {
+        let (graph, nodes) = weighted_square(GraphKind::Directed);
+
+        assert_eq!(graph.weight(nodes[0], nodes[1]), Some(4));
+        assert_eq!(graph.weight(nodes[1], nodes[0]), None);
+        assert_eq!(graph.weight(nodes[0], nodes[2]), None);
+    }
+
+    #[test]
+    fn test_set_weight_updates_both_directions_of_undirected_edge() {
+        let (mut graph, nodes) = weighted_square(GraphKind::Undirected);
+
+        let result = graph.set_weight(nodes[1], nodes[0], 9);
+
+        assert_eq!(result, Some(4));
+        assert_eq!(graph.weight(nodes[0], nodes[1]), Some(9));
+        assert_eq!(graph.weight(nodes[1], nodes[0]), Some(9));
+        assert_eq!(graph.edge_count(), 4);
+    }
+
+    #[test]
+    fn test_set_weight_does_not_create_missing_edge() {
+        let (mut graph, nodes) = weighted_square(GraphKind::Directed);
+
+        let result = graph.set_weight(nodes[0], nodes[2], 3);
+
+        assert_eq!(result, None);
+        assert!(!graph.contains_edge(nodes[0], nodes[2]));
+    }
+
+    #[test]
+    fn test_weighted_neighbors() {
+        let (graph, nodes) = weighted_square(GraphKind::Undirected);
+
+        let neighbors: Vec<(NodeIndex, u32)> = graph.weighted_neighbors(nodes[0]).collect();
+
+        assert_eq!(neighbors, vec![(nodes[1], 4), (nodes[3], 2)]);
+    }
+
+    #[test]
+    fn test_total_weight() {
+        let (directed, _) = weighted_square(GraphKind::Directed);
+        let (undirected, _) = weighted_square(GraphKind::Undirected);
+
+        assert_eq!(directed.total_weight(), 14);
+        assert_eq!(undirected.total_weight(), 14);
+    }
+
+    #[test]
+    fn test_float_weights() {
+        let mut graph = WeightedGraph::<&str, f64>::new_directed();
+        let a = graph.add_node("A");
+        let b = graph.add_node("B");
+        graph.add_edge(a, b, 0.5);
+        graph.add_edge(b, a, 1.25);
+
+        assert_eq!(graph.total_weight(), 1.75);
+    }
+}
@@ -0,0 +1,95 @@
+//! Growth policies
+//! How much room a collection makes once it is full. The stack and the queue
+//! ask their policy for a new capacity when an item doesn't fit, and the hash
+//! map asks it for a new bucket count when it holds more pairs than buckets.
+//! Each of them takes the policy as a type parameter, `Doubling` by default,
+//! and a value of it per instance through `with_growth`. A policy that
+//! refuses to grow makes the stack and queue report `CapacityExceeded`, while
+//! the map just keeps filling the buckets it has.
+
+pub trait GrowthPolicy {
+    /// Capacity to grow to from `capacity`, None if it can't grow any more.
+    /// Anything returned is larger than `capacity`.
+    fn grow(&self, capacity: usize) -> Option<usize>;
+}
+
+/// Twice the capacity, amortized constant time per item
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+    fn grow(&self, capacity: usize) -> Option<usize> {
+        capacity.checked_mul(2).map(|grown| grown.max(1))
+    }
+}
+
+/// The same number of slots every time, never more spare room than that
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedIncrement(pub usize);
+
+impl GrowthPolicy for FixedIncrement {
+    fn grow(&self, capacity: usize) -> Option<usize> {
+        capacity.checked_add(self.0).filter(|&grown| grown > capacity)
+    }
+}
+
+/// Grows like `policy` up to `max` and not past it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounded<P = Doubling> {
+    policy: P,
+    max: usize,
+}
+
+impl<P: GrowthPolicy> Bounded<P> {
+    pub fn new(policy: P, max: usize) -> Self {
+        Bounded { policy, max }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl<P: GrowthPolicy> GrowthPolicy for Bounded<P> {
+    fn grow(&self, capacity: usize) -> Option<usize> {
+        if capacity >= self.max {
+            return None;
+        }
+        Some(self.policy.grow(capacity).map_or(self.max, |grown| grown.min(self.max)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Capacities the policy goes through from `capacity` until it stops
+    /// or `steps` run out
+    fn steps(policy: &impl GrowthPolicy, capacity: usize, steps: usize) -> Vec<usize> {
+        std::iter::successors(Some(capacity), |&capacity| policy.grow(capacity))
+            .skip(1)
+            .take(steps)
+            .collect()
+    }
+
+    #[test]
+    fn test_doubling_and_fixed_increment() {
+        assert_eq!(steps(&Doubling, 0, 4), vec![1, 2, 4, 8]);
+        assert_eq!(steps(&FixedIncrement(3), 1, 3), vec![4, 7, 10]);
+    }
+
+    #[test]
+    fn test_bounded_stops_at_the_max() {
+        let bounded = Bounded::new(Doubling, 10);
+
+        assert_eq!(steps(&bounded, 3, 5), vec![6, 10]);
+        assert_eq!(steps(&Bounded::new(FixedIncrement(4), 6), 0, 5), vec![4, 6]);
+    }
+
+    #[test]
+    fn when_capacity_would_overflow_growth_stops() {
+        assert_eq!(Doubling.grow(usize::MAX / 2 + 1), None);
+        assert_eq!(FixedIncrement(1).grow(usize::MAX), None);
+        assert_eq!(FixedIncrement(0).grow(5), None);
+    }
+}
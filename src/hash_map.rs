@@ -1,93 +1,313 @@
-// Implement Hash Map from scratch using built in Linked List
-// to avoid collisions.
-
-use std::{ hash::Hash, fmt::Debug };
-use std::collections::LinkedList;
-
+// Implement Hash Map from scratch using small inline vectors as buckets
+// to avoid collisions. Buckets rarely hold more than a couple of pairs,
+// so most of them never allocate. Every map hashes a random seed ahead of
+// its keys, so a set of keys that all land in one bucket can't be worked
+// out in advance; `with_seed` fixes the seed when the layout must repeat.
+// The table of buckets is allocated by the first insert. Once the map holds
+// more pairs than buckets, the growth policy picks a larger bucket count and
+// every pair moves to its bucket in the new table.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{ BuildHasher, Hash, Hasher };
+use std::fmt::Debug;
+
+use crate::collection::Collection;
+use crate::growth::{ Doubling, GrowthPolicy };
 use crate::hasher_trait::KeyToIndexHasherTrait;
 use crate::hasher_trait::DEFAULT_MAX_SIZE;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::map::Map;
+use crate::small_vec::SmallVec;
+use crate::trace::{ self, Event };
+use crate::heap_size::HeapSize;
+
+/// Pairs a bucket holds before it moves to the heap
+const BUCKET_INLINE_SIZE: usize = 2;
+
+type Bucket<K, V> = SmallVec<(K, V), BUCKET_INLINE_SIZE>;
 
-#[allow(dead_code)]
 #[derive(Clone, Debug)]
-pub struct HashMap<K, V> {
+pub struct HashMap<K, V, G: GrowthPolicy = Doubling> {
     current_size: usize,
-    array: [Option<LinkedList<(K, V)>>; DEFAULT_MAX_SIZE],
+    array: Vec<Bucket<K, V>>,
+    seed: u64,
+    growth: G,
+}
+
+impl<K: Hash + Clone, V, G: GrowthPolicy> KeyToIndexHasherTrait<K> for HashMap<K, V, G> {
+    fn seed(&self) -> Option<u64> {
+        Some(self.seed)
+    }
+
+    /// Before the first insert there is no table yet, keys hash as they
+    /// will once the first one is allocated
+    fn bucket_count(&self) -> usize {
+        self.array.len().max(DEFAULT_MAX_SIZE)
+    }
+}
+
+/// The hashing of a map with another bucket count, to find where pairs go
+/// before they move
+struct Resized {
+    seed: u64,
+    buckets: usize,
+}
+
+impl<K: Hash> KeyToIndexHasherTrait<K> for Resized {
+    fn seed(&self) -> Option<u64> {
+        Some(self.seed)
+    }
+
+    fn bucket_count(&self) -> usize {
+        self.buckets
+    }
 }
 
-impl<K: Hash + Clone, V> KeyToIndexHasherTrait<K> for HashMap<K, V> {}
+/// Different for every map and every run, so keys picked to land in one
+/// bucket of one map are spread out in any other
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
 
-#[allow(dead_code)]
 impl<K: Hash + Clone + PartialEq + Debug, V: Clone + Debug> HashMap<K, V> {
-    // Allows to work around lack of 'Copy' trait
-    const INIT: Option<LinkedList<(K, V)>> = None;
+    /// Empty map with a random seed
+    pub fn new() -> Self {
+        Self::with_seed(random_seed())
+    }
+
+    /// Empty map that puts each key in the same bucket on every run, for
+    /// tests and anything else that needs a repeatable layout
+    pub fn with_seed(seed: u64) -> Self {
+        HashMap { current_size: 0, array: Vec::new(), seed, growth: Doubling }
+    }
+}
+
+impl<K, V, G> HashMap<K, V, G>
+where
+    K: Hash + Clone + PartialEq + Debug,
+    V: Clone + Debug,
+    G: GrowthPolicy,
+{
+    /// Empty map with a random seed that grows its table as `growth` says
+    pub fn with_growth(growth: G) -> Self {
+        HashMap { current_size: 0, array: Vec::new(), seed: random_seed(), growth }
+    }
 
     pub fn is_empty(&self) -> bool {
         self.current_size == 0
     }
 
-    pub fn new() -> Self {
-        HashMap {
-            current_size: 0,
-            array: [Self::INIT; DEFAULT_MAX_SIZE],
-        }
+    /// Number of key-value pairs in the hashmap.
+    pub fn len(&self) -> usize {
+        self.current_size
     }
 
     /// Inserts key and value pair in the hashmap. If key didn't exist, returns None
     /// If key is present, returns the old value and updates stored value to the new value.
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        let index = self.get_index(key.clone());
-        let list = self.array[index].get_or_insert_with(LinkedList::new);
-        if let Some(node) = list.iter_mut().find(|(k, _v)| *k == key) {
+        if self.array.is_empty() {
+            self.array = empty_buckets(DEFAULT_MAX_SIZE);
+        }
+        let index = self.bucket_index(&key);
+        let bucket = &mut self.array[index];
+        if let Some(node) = bucket.iter_mut().find(|(k, _v)| *k == key) {
             return Some(std::mem::replace(&mut node.1, value));
         }
-        list.push_back((key, value));
+        bucket.push((key, value));
         self.current_size += 1;
+        if self.current_size > self.array.len() {
+            self.grow();
+        }
+        check_invariants!(self);
         None
     }
 
+    /// Moves every pair to a table with as many buckets as the growth
+    /// policy asks for, nothing happens if it refuses
+    fn grow(&mut self) {
+        let Some(buckets) = self.growth.grow(self.array.len()) else {
+            return;
+        };
+        // Hashing clones the keys, which can panic, so every new index is
+        // worked out before the first pair moves. Pairs leave each bucket
+        // from the back, so the indexes are taken in that order too.
+        let resized = Resized { seed: self.seed, buckets };
+        let indexes: Vec<usize> = self.array
+            .iter()
+            .flat_map(|bucket| bucket.iter().rev())
+            .map(|(key, _)| resized.get_index(key.clone()))
+            .collect();
+        let mut array = empty_buckets(buckets);
+        let pairs = std::mem::take(&mut self.array)
+            .into_iter()
+            .flat_map(|mut bucket| std::iter::from_fn(move || bucket.pop()));
+        for (pair, index) in pairs.zip(indexes) {
+            array[index].push(pair);
+        }
+        self.array = array;
+        trace::emit(|| Event::Resized { len: self.current_size, buckets });
+    }
+
     /// Gets value for a given key. If key exists, value is returned.
     /// If key doesn't exist, returns None
     pub fn get(&self, key: K) -> Option<V> {
-        let index = self.get_index(key.clone());
-        self.array[index]
-            .as_ref()
-            .and_then(|list| list.iter().find(|(k, _v)| *k == key))
+        let index = self.bucket_index(&key);
+        self.array
+            .get(index)?
+            .iter()
+            .find(|(k, _v)| *k == key)
             .map(|node| node.1.clone())
     }
 
     /// Removes the key-value pair from the map for a given key.
     /// Returns the value is the key existed, None otherwise.
     pub fn remove(&mut self, key: K) -> Option<V> {
+        let index = self.bucket_index(&key);
+
+        let bucket = self.array.get_mut(index)?;
+        let node_index = bucket.iter().position(|(k, _v)| *k == key)?;
+        let (_key, value) = bucket.remove(node_index);
+        self.current_size -= 1;
+        check_invariants!(self);
+        Some(value)
+    }
+
+    /// Bucket of the key, traced with how full the bucket already is
+    fn bucket_index(&self, key: &K) -> usize {
         let index = self.get_index(key.clone());
+        trace::emit(|| Event::Hashed {
+            key: format!("{:?}", key),
+            bucket: index,
+            occupied: self.array.get(index).map_or(0, |bucket| bucket.len()),
+        });
+        index
+    }
 
-        if let Some(list) = &mut self.array[index] {
-            if let Some(node_index) = list.iter().position(|(k, _v)| *k == key) {
-                let mut iter = list.iter_mut();
-                let return_value = iter.nth(node_index).map(|node| node.1.clone());
-                iter.next();
-
-                if node_index != 0 {
-                    let mut split_list = list.split_off(node_index);
-                    split_list.pop_front();
-                    list.append(&mut split_list);
-                } else {
-                    self.array[index] = None;
-                }
-                self.current_size -= 1;
-                return return_value;
-            }
-        }
-        None
+    /// Iterates over key-value pairs, bucket by bucket in index order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.array
+            .iter()
+            .flat_map(|bucket| bucket.iter())
+            .map(|(key, value)| (key, value))
+    }
+
+    /// The buckets in index order, for code that works on them one by one
+    #[cfg(feature = "rayon")]
+    pub(crate) fn buckets(&self) -> &[Bucket<K, V>] {
+        &self.array
     }
 
-    /// Clears data in the hashmap.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn buckets_mut(&mut self) -> &mut [Bucket<K, V>] {
+        &mut self.array
+    }
+
+    /// Clears data in the hashmap and frees its table.
     pub fn clear(&mut self) {
-        self.array = [Self::INIT; DEFAULT_MAX_SIZE];
+        // Size first: the old pairs are dropped by the assignment, and if one
+        // of them panics the map must already be empty
         self.current_size = 0;
+        self.array = Vec::new();
+        check_invariants!(self);
+    }
+}
+
+fn empty_buckets<K, V>(count: usize) -> Vec<Bucket<K, V>> {
+    (0..count).map(|_| SmallVec::new()).collect()
+}
+
+impl<K: Hash + Clone + PartialEq + Debug, V: Clone + Debug> Default for HashMap<K, V> {
+    fn default() -> Self {
+        HashMap::new()
+    }
+}
+
+/// The inherent `get` and `remove` take the key by value, these clone it
+impl<K, V, G> Map<K, V> for HashMap<K, V, G>
+where
+    K: Hash + Clone + PartialEq + Debug,
+    V: Clone + Debug,
+    G: GrowthPolicy,
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        HashMap::get(self, key.clone())
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        HashMap::remove(self, key.clone())
+    }
+
+    fn len(&self) -> usize {
+        self.current_size
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        HashMap::iter(self).map(|(key, value)| (key.clone(), value.clone()))
     }
 }
-pub fn run() {
-    println!("Hash Table data structure added as module");
+
+/// The items of a map are its keys
+impl<K, V, G> Collection for HashMap<K, V, G>
+where
+    K: Hash + Clone + PartialEq + Debug,
+    V: Clone + Debug,
+    G: GrowthPolicy,
+{
+    type Item = K;
+
+    fn len(&self) -> usize {
+        self.current_size
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.get(key.clone()).is_some()
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K, V, G> Invariants for HashMap<K, V, G>
+where
+    K: Hash + Clone + PartialEq + Debug,
+    V: Clone + Debug,
+    G: GrowthPolicy,
+{
+    fn assert_invariants(&self) {
+        let pairs: usize = self.array.iter().map(|bucket| bucket.len()).sum();
+        assert_eq!(self.current_size, pairs, "size doesn't match the pairs in the buckets");
+        assert!(
+            self.array.is_empty() || self.array.len() >= DEFAULT_MAX_SIZE,
+            "table has {} buckets, fewer than the first table",
+            self.array.len()
+        );
+        for (index, bucket) in self.array.iter().enumerate() {
+            for (position, (key, _)) in bucket.iter().enumerate() {
+                let home = self.get_index(key.clone());
+                assert_eq!(home, index, "key {:?} is in the wrong bucket", key);
+                assert!(
+                    bucket[..position].iter().all(|(other, _)| other != key),
+                    "key {:?} is in its bucket twice",
+                    key
+                );
+            }
+        }
+    }
+}
+
+/// The table of buckets and the buckets that spilled out of it
+impl<K: HeapSize, V: HeapSize, G: GrowthPolicy> HeapSize for HashMap<K, V, G> {
+    fn heap_size(&self) -> usize {
+        self.array.heap_size()
+    }
 }
 
 #[cfg(test)]
@@ -95,19 +315,27 @@ mod tests {
     use std::{ vec, fmt::Display };
 
     use super::*;
+    use crate::growth::{ Bounded, FixedIncrement };
 
-    #[allow(dead_code)]
     struct HashMapTestBuilder<K, V> {
-        expected: [Option<LinkedList<(K, V)>>; DEFAULT_MAX_SIZE],
+        expected: [Bucket<K, V>; DEFAULT_MAX_SIZE],
     }
 
-    impl<K: Hash + Clone, V> KeyToIndexHasherTrait<K> for HashMapTestBuilder<K, V> {}
+    /// Seed of the maps the builder lays out buckets for, picked so that
+    /// "K" and "Q" share a bucket for the collision tests
+    const TEST_SEED: u64 = 310;
+
+    impl<K: Hash + Clone, V> KeyToIndexHasherTrait<K> for HashMapTestBuilder<K, V> {
+        fn seed(&self) -> Option<u64> {
+            Some(TEST_SEED)
+        }
+    }
 
     impl<
         K: Clone + Hash + Display + Debug + PartialEq,
         V: Clone + Display + Debug + PartialEq
     > HashMapTestBuilder<K, V> {
-        const INIT: Option<LinkedList<(K, V)>> = None;
+        const INIT: Bucket<K, V> = SmallVec::new();
 
         fn new() -> Self {
             HashMapTestBuilder { expected: [Self::INIT; DEFAULT_MAX_SIZE] }
@@ -116,17 +344,16 @@ mod tests {
         fn build_expected_array(
             &mut self,
             expected_values: &Vec<(K, V)>
-        ) -> [Option<LinkedList<(K, V)>>; DEFAULT_MAX_SIZE] {
+        ) -> [Bucket<K, V>; DEFAULT_MAX_SIZE] {
             for (key, value) in expected_values {
                 let index = self.get_index(key.clone());
-                let list = self.expected[index].get_or_insert_with(LinkedList::new);
-                list.push_back((key.clone(), value.clone()));
+                self.expected[index].push((key.clone(), value.clone()));
             }
             self.expected.clone()
         }
 
         fn new_map_with_values(values: &Vec<(K, V)>) -> HashMap<K, V> {
-            let mut map: HashMap<K, V> = HashMap::new();
+            let mut map: HashMap<K, V> = fixed_seed();
             for (key, value) in values {
                 map.insert(key.clone(), value.clone());
             }
@@ -134,6 +361,15 @@ mod tests {
         }
     }
 
+    /// Map with the indexes the builder and the collision tests expect
+    fn fixed_seed<K, V>() -> HashMap<K, V>
+    where
+        K: Hash + Clone + PartialEq + Debug,
+        V: Clone + Debug,
+    {
+        HashMap::with_seed(TEST_SEED)
+    }
+
     #[test]
     fn when_new_hashmap_created_it_is_empty() {
         let map: HashMap<String, String> = HashMap::new();
@@ -148,7 +384,7 @@ mod tests {
         let mut test_builder = HashMapTestBuilder::new();
         let expected_array = test_builder.build_expected_array(&values);
 
-        let mut map: HashMap<&str, &str> = HashMap::new();
+        let mut map: HashMap<&str, &str> = fixed_seed();
         let result = map.insert(values[0].0, values[0].1);
 
         assert!(result.is_none(), "Result is none, because Key didn't exist");
@@ -161,7 +397,7 @@ mod tests {
         let values = vec![("A", "Value A"), ("B", "Value B"), ("C", "Value C"), ("D", "Value D")];
         let mut test_builder = HashMapTestBuilder::new();
         let expected_array = test_builder.build_expected_array(&values);
-        let mut map: HashMap<&str, &str> = HashMap::new();
+        let mut map: HashMap<&str, &str> = fixed_seed();
 
         for &(key, value) in &values {
             assert_eq!(map.insert(key, value), None);
@@ -178,7 +414,7 @@ mod tests {
         let new_value = "New Value A";
         let mut test_builder = HashMapTestBuilder::new();
         let expected_array = test_builder.build_expected_array(&vec![(key, new_value)]);
-        let mut map = HashMap::new();
+        let mut map = fixed_seed();
 
         let result_1 = map.insert(key, old_value);
         let result_2 = map.insert(key, new_value);
@@ -200,14 +436,14 @@ mod tests {
         let map: HashMap<&str, &str> = HashMapTestBuilder::new_map_with_values(&values);
 
         assert_eq!(
-            map.get_index(&values[1].0),
-            map.get_index(&values[2].0),
+            map.get_index(values[1].0),
+            map.get_index(values[2].0),
             "Keys K and Q map to the same index."
         );
 
         let mut test_builder = HashMapTestBuilder::new();
         let expected = test_builder.build_expected_array(&values);
-        assert_eq!(expected, map.array);
+        assert_eq!(map.array, expected);
         assert_eq!(map.current_size, 4);
     }
 
@@ -387,6 +623,18 @@ mod tests {
         assert_eq!(map.current_size, 2);
     }
 
+    #[test]
+    fn test_remove_first_key_of_bucket_keeps_the_others() {
+        let values = vec![("K", "Value K"), ("Q", "Value Q")];
+        let mut map = HashMapTestBuilder::<&str, &str>::new_map_with_values(&values);
+
+        let result = map.remove("K");
+
+        assert_eq!(result, Some("Value K"));
+        assert_eq!(map.get("Q"), Some("Value Q"));
+        assert_eq!(map.len(), 1);
+    }
+
     #[test]
     fn test_remove_when_all_values_removed() {
         let values = vec![
@@ -433,8 +681,77 @@ mod tests {
 
         assert!(map.is_empty());
         assert_eq!(map.current_size, 0);
-        for value in &map.array {
-            assert!(value.is_none());
+        for bucket in &map.array {
+            assert!(bucket.is_empty());
         }
     }
+
+    #[test]
+    fn test_iter_visits_every_pair() {
+        let values = vec![("A", 1), ("B", 2), ("C", 3)];
+        let map = HashMapTestBuilder::new_map_with_values(&values);
+
+        let mut pairs: Vec<(&str, i32)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        pairs.sort();
+
+        assert_eq!(pairs, values);
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_keys_colliding_under_one_seed_are_spread_out_by_another() {
+        let fixed: HashMap<u32, ()> = fixed_seed();
+        let colliding: Vec<u32> = (0..).filter(|&key| fixed.get_index(key) == 0).take(20).collect();
+        let mut map = HashMap::new();
+
+        for &key in &colliding {
+            map.insert(key, ());
+        }
+
+        let used = map.array.iter().filter(|bucket| !bucket.is_empty()).count();
+        assert!(used > 1, "all keys still share one bucket");
+        assert_eq!(map.len(), 20);
+    }
+
+    #[test]
+    fn test_same_seed_gives_the_same_layout() {
+        let values = vec![("A", 1), ("K", 2), ("Q", 3)];
+        let mut first = HashMap::with_seed(7);
+        let mut second = HashMap::with_seed(7);
+
+        for &(key, value) in &values {
+            first.insert(key, value);
+            second.insert(key, value);
+        }
+
+        assert_eq!(first.array, second.array);
+        assert_eq!(first.seed(), Some(7));
+    }
+
+    #[test]
+    fn test_table_grows_once_there_are_more_pairs_than_buckets() {
+        let mut map = HashMap::new();
+        assert_eq!(map.array.len(), 0);
+
+        for key in 0..=DEFAULT_MAX_SIZE {
+            map.insert(key, key * 2);
+        }
+
+        assert_eq!(map.array.len(), DEFAULT_MAX_SIZE * 2);
+        assert_eq!(map.iter().count(), DEFAULT_MAX_SIZE + 1);
+        assert!((0..=DEFAULT_MAX_SIZE).all(|key| map.get(key) == Some(key * 2)));
+    }
+
+    #[test]
+    fn when_growth_is_refused_buckets_fill_up() {
+        let mut map = HashMap::with_growth(Bounded::new(FixedIncrement(100), 300));
+
+        for key in 0..1000 {
+            map.insert(key, ());
+        }
+
+        assert_eq!(map.array.len(), 300);
+        assert_eq!(map.len(), 1000);
+        assert!((0..1000).all(|key| map.contains(&key)));
+    }
 }
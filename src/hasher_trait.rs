@@ -4,11 +4,26 @@ use std::collections::hash_map::DefaultHasher;
 pub const DEFAULT_MAX_SIZE: usize = 256;
 
 pub trait KeyToIndexHasherTrait<K: Hash> {
+    /// Hashed ahead of every key, so which keys share an index depends on
+    /// it. None keeps the fixed indexes, which anyone can find collisions for.
+    fn seed(&self) -> Option<u64> {
+        None
+    }
+
+    /// Number of buckets the index has to fall in
+    fn bucket_count(&self) -> usize {
+        DEFAULT_MAX_SIZE
+    }
+
+    /// Bucket of the key, below `bucket_count`
     fn get_index(&self, key: K) -> usize {
         let mut hasher = DefaultHasher::new();
+        if let Some(seed) = self.seed() {
+            hasher.write_u64(seed);
+        }
         key.hash(&mut hasher);
         let key_hash = hasher.finish();
-        (key_hash % (DEFAULT_MAX_SIZE as u64)) as usize
+        (key_hash % (self.bucket_count() as u64)) as usize
     }
 }
 
@@ -29,7 +44,7 @@ mod tests {
     fn get_index_string() {
         let test_struct = TestKeyToIndexStruct::new();
 
-        let index = test_struct.get_index(&"A");
+        let index = test_struct.get_index("A");
 
         assert_eq!(index, 163);
     }
@@ -60,4 +75,34 @@ mod tests {
 
         assert_ne!(index_1, index_2, "Keys that are different, map to differnt indexes.");
     }
+
+    #[test]
+    fn test_seed_changes_the_indexes() {
+        struct Seeded(u64);
+        impl<K: Hash> KeyToIndexHasherTrait<K> for Seeded {
+            fn seed(&self) -> Option<u64> {
+                Some(self.0)
+            }
+        }
+
+        let indexes = |seed| (0..64).map(|key| Seeded(seed).get_index(key)).collect::<Vec<_>>();
+
+        assert_eq!(indexes(1), indexes(1));
+        assert_ne!(indexes(1), indexes(2));
+    }
+
+    #[test]
+    fn test_indexes_stay_below_the_bucket_count() {
+        struct Small;
+        impl<K: Hash> KeyToIndexHasherTrait<K> for Small {
+            fn bucket_count(&self) -> usize {
+                8
+            }
+        }
+
+        let indexes: Vec<usize> = (0..100).map(|key| Small.get_index(key)).collect();
+
+        assert!(indexes.iter().all(|&index| index < 8));
+        assert!((0..8).all(|bucket| indexes.contains(&bucket)));
+    }
 }
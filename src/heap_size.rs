@@ -0,0 +1,195 @@
+//! Heap memory footprint
+//! `heap_size` is the number of bytes a value owns on the heap: nodes,
+//! buckets, and capacity that is allocated but not used yet. The value itself
+//! isn't included, `total_size` adds it, which matters for structures that
+//! keep a lot inline like the hash map and its fixed array of buckets.
+//! Allocator bookkeeping isn't counted. Borrowed data counts for nothing, and
+//! shared data behind an `Rc` counts in full for every value that shares it.
+
+use std::cell::RefCell;
+use std::mem::size_of;
+use std::rc::Rc;
+
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+
+    /// Bytes of the value itself plus what it owns on the heap
+    fn total_size(&self) -> usize
+    where
+        Self: Sized,
+    {
+        size_of::<Self>() + self.heap_size()
+    }
+}
+
+macro_rules! impl_no_heap {
+    ($($t:ty),*) => {
+        $(
+            impl HeapSize for $t {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_no_heap!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+impl_no_heap!(bool, char, ());
+
+impl<T: ?Sized> HeapSize for &T {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+/// Only what the items own, the items themselves are inline
+impl<T: HeapSize, const N: usize> HeapSize for [T; N] {
+    fn heap_size(&self) -> usize {
+        self.iter().map(HeapSize::heap_size).sum()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        size_of::<T>() + (**self).heap_size()
+    }
+}
+
+/// The value and the two reference counts stored with it
+impl<T: HeapSize> HeapSize for Rc<T> {
+    fn heap_size(&self) -> usize {
+        2 * size_of::<usize>() + size_of::<T>() + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for RefCell<T> {
+    fn heap_size(&self) -> usize {
+        self.borrow().heap_size()
+    }
+}
+
+/// An estimate, the std table keeps one control byte next to every slot
+impl<K: HeapSize, V: HeapSize, S> HeapSize for std::collections::HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        let slots = self.capacity() * (size_of::<(K, V)>() + 1);
+        slots + self.iter().map(|(key, value)| key.heap_size() + value.heap_size()).sum::<usize>()
+    }
+}
+
+impl<A: HeapSize, B: HeapSize> HeapSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<A: HeapSize, B: HeapSize, C: HeapSize> HeapSize for (A, B, C) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size() + self.2.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adaptive_radix_tree::AdaptiveRadixTree;
+    use crate::arena::{ Arena, ArenaList };
+    use crate::binomial_heap::BinomialHeap;
+    use crate::graph::Graph;
+    use crate::hash_map::HashMap;
+    use crate::leftist_heap::LeftistHeap;
+    use crate::persistent::HamtMap;
+    use crate::radix_trie::RadixTrie;
+    use crate::singly_linked_list::SinglyLinkedList;
+    use crate::skew_heap::SkewHeap;
+    use crate::veb_tree::VebTree;
+
+    #[test]
+    fn when_nothing_is_allocated_heap_size_is_zero() {
+        assert_eq!(Vec::<u64>::new().heap_size(), 0);
+        assert_eq!(String::new().heap_size(), 0);
+        assert_eq!(SinglyLinkedList::<u64>::new().heap_size(), 0);
+        assert_eq!(HashMap::<u64, u64>::new().heap_size(), 0);
+        assert_eq!(LeftistHeap::<u64>::new().heap_size(), 0);
+        assert_eq!(BinomialHeap::<u64>::new().heap_size(), 0);
+        assert_eq!(RadixTrie::<u64>::new().heap_size(), 0);
+        assert_eq!(AdaptiveRadixTree::<u64>::new().heap_size(), 0);
+        assert_eq!(VebTree::new(16).heap_size(), 0);
+        assert_eq!(HamtMap::<u64, u64>::new().heap_size(), 0);
+        assert_eq!(Graph::<u64, u64>::new_directed().heap_size(), 0);
+    }
+
+    #[test]
+    fn test_heaps_count_one_node_per_item() {
+        let leftist: LeftistHeap<u64> = (0..1000).collect();
+        let skew: SkewHeap<u64> = (0..1000).collect();
+
+        // The item, the rank and two child pointers
+        assert_eq!(leftist.heap_size(), 1000 * 32);
+        // The item and two child pointers
+        assert_eq!(skew.heap_size(), 1000 * 24);
+    }
+
+    #[test]
+    fn test_graph_counts_nodes_and_edges() {
+        let mut graph = Graph::new_directed();
+        let nodes: Vec<_> = (0..10u64).map(|node| graph.add_node(node)).collect();
+        let empty = graph.heap_size();
+        for pair in nodes.windows(2) {
+            graph.add_edge(pair[0], pair[1], String::from("edge"));
+        }
+
+        assert!(empty > 0);
+        assert!(graph.heap_size() >= empty + 9 * (size_of::<(usize, String)>() + 4));
+    }
+
+    #[test]
+    fn test_nested_allocations_are_counted() {
+        let mut strings = Vec::with_capacity(4);
+        strings.push(String::with_capacity(10));
+        strings.push(String::from("abc"));
+
+        let size = strings.heap_size();
+
+        assert_eq!(size, 4 * size_of::<String>() + 10 + 3);
+        assert_eq!(Some(Box::new(7u32)).heap_size(), 4);
+    }
+
+    #[test]
+    fn test_box_list_arena_list_and_vec() {
+        let mut boxed = SinglyLinkedList::new();
+        let arena = Arena::with_capacity(1000);
+        let mut in_arena = ArenaList::new_in(&arena);
+        let mut vec = Vec::with_capacity(1000);
+        for item in 0..1000u64 {
+            boxed.prepend(item);
+            in_arena.push_front(item);
+            vec.push(item);
+        }
+
+        let (boxed, in_arena, vec) = (boxed.heap_size(), in_arena.heap_size(), vec.heap_size());
+
+        // Every box node holds the item and a pointer to the next node
+        assert_eq!(boxed, 1000 * 16);
+        assert_eq!(in_arena, 1000 * 16 + size_of::<Vec<()>>());
+        assert_eq!(vec, 1000 * 8);
+    }
+}
@@ -0,0 +1,322 @@
+//! Indexed Priority Queue implementation (min priority first)
+//! Binary heap of (key, priority) pairs plus a map from every key to its
+//! position in the heap. Knowing the position makes it possible to change
+//! the priority of, or remove, any key in O(log n), which is what Dijkstra
+//! and Prim need when a shorter edge to a node is found.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+pub struct IndexedPriorityQueue<K, P: Ord> {
+    heap: Vec<(K, P)>,
+    positions: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> IndexedPriorityQueue<K, P> {
+    pub fn new() -> Self {
+        IndexedPriorityQueue { heap: vec![], positions: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = IndexedPriorityQueue::new();
+        check_invariants!(self);
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.positions.get(key).map(|&position| &self.heap[position].1)
+    }
+
+    /// Adds the key with a priority. If the key is already queued, its
+    /// priority is changed and the old priority is returned.
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if self.contains_key(&key) {
+            return self.change_priority(&key, priority);
+        }
+        self.heap.push((key.clone(), priority));
+        let position = self.heap.len() - 1;
+        self.positions.insert(key, position);
+        self.sift_up(position);
+        check_invariants!(self);
+        None
+    }
+
+    /// Returns the key with the smallest priority without removing it
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.heap.first().map(|(key, priority)| (key, priority))
+    }
+
+    /// Keys and their priorities in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &P)> + '_ {
+        self.heap.iter().map(|(key, priority)| (key, priority))
+    }
+
+    /// Removes the key with the smallest priority
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let entry = self.remove_at(0);
+        check_invariants!(self);
+        Some(entry)
+    }
+
+    /// Sets a new priority for a queued key in O(log n).
+    /// Returns the old priority, or None if the key is not queued.
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        let position = *self.positions.get(key)?;
+        let old_priority = std::mem::replace(&mut self.heap[position].1, priority);
+        if self.heap[position].1 < old_priority {
+            self.sift_up(position);
+        } else {
+            self.sift_down(position);
+        }
+        check_invariants!(self);
+        Some(old_priority)
+    }
+
+    /// Removes any queued key in O(log n) and returns its priority
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let position = *self.positions.get(key)?;
+        let (_, priority) = self.remove_at(position);
+        check_invariants!(self);
+        Some(priority)
+    }
+
+    /// Swaps the entry with the last one, removes it and restores the heap
+    /// around the entry that took its place.
+    fn remove_at(&mut self, position: usize) -> (K, P) {
+        let last = self.heap.len() - 1;
+        self.swap(position, last);
+        let entry = self.heap.pop().expect("Heap is not empty");
+        self.positions.remove(&entry.0);
+        if position < self.heap.len() {
+            self.sift_up(position);
+            self.sift_down(position);
+        }
+        entry
+    }
+
+    /// Swaps two heap entries and keeps the position map in sync
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions.insert(self.heap[a].0.clone(), a);
+        self.positions.insert(self.heap[b].0.clone(), b);
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.heap[position].1 >= self.heap[parent].1 {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let left = 2 * position + 1;
+            let right = left + 1;
+            let mut smallest = position;
+            if left < self.heap.len() && self.heap[left].1 < self.heap[smallest].1 {
+                smallest = left;
+            }
+            if right < self.heap.len() && self.heap[right].1 < self.heap[smallest].1 {
+                smallest = right;
+            }
+            if smallest == position {
+                break;
+            }
+            self.swap(position, smallest);
+            position = smallest;
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> Default for IndexedPriorityQueue<K, P> {
+    fn default() -> Self {
+        IndexedPriorityQueue::new()
+    }
+}
+
+/// The items of a queue are its keys
+impl<K: Hash + Eq + Clone, P: Ord> Collection for IndexedPriorityQueue<K, P> {
+    type Item = K;
+
+    fn len(&self) -> usize {
+        IndexedPriorityQueue::len(self)
+    }
+
+    fn clear(&mut self) {
+        IndexedPriorityQueue::clear(self);
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        self.contains_key(key)
+    }
+}
+
+/// Checks heap order and that the map holds the position of every key
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Hash + Eq + Clone, P: Ord> Invariants for IndexedPriorityQueue<K, P> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.positions.len(), self.heap.len(), "Map and heap hold different keys");
+        for (position, (key, priority)) in self.heap.iter().enumerate() {
+            assert_eq!(self.positions.get(key), Some(&position), "Key maps to another position");
+            if position > 0 {
+                let parent = &self.heap[(position - 1) / 2].1;
+                assert!(parent <= priority, "Parent is larger than its child");
+            }
+        }
+    }
+}
+
+impl<K: HeapSize, P: Ord + HeapSize> HeapSize for IndexedPriorityQueue<K, P> {
+    fn heap_size(&self) -> usize {
+        self.heap.heap_size() + self.positions.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_queue_is_created_it_is_empty() {
+        let mut queue = IndexedPriorityQueue::<&str, i32>::new();
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_push_and_pop_in_priority_order() {
+        let mut queue = IndexedPriorityQueue::new();
+        queue.push("c", 30);
+        queue.push("a", 10);
+        queue.push("d", 40);
+        queue.push("b", 20);
+        queue.assert_invariants();
+
+        assert_eq!(queue.peek(), Some((&"a", &10)));
+        assert_eq!(queue.pop(), Some(("a", 10)));
+        assert_eq!(queue.pop(), Some(("b", 20)));
+        assert_eq!(queue.pop(), Some(("c", 30)));
+        assert_eq!(queue.pop(), Some(("d", 40)));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_push_existing_key_changes_priority() {
+        let mut queue = IndexedPriorityQueue::new();
+        queue.push("a", 5);
+
+        let result = queue.push("a", 1);
+
+        assert_eq!(result, Some(5));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.priority(&"a"), Some(&1));
+    }
+
+    #[test]
+    fn test_change_priority_decrease_moves_key_to_front() {
+        let mut queue = IndexedPriorityQueue::new();
+        for (key, priority) in [("a", 10), ("b", 20), ("c", 30), ("d", 40)] {
+            queue.push(key, priority);
+        }
+
+        let result = queue.change_priority(&"d", 5);
+
+        assert_eq!(result, Some(40));
+        assert_eq!(queue.peek(), Some((&"d", &5)));
+        queue.assert_invariants();
+    }
+
+    #[test]
+    fn test_change_priority_increase_moves_key_back() {
+        let mut queue = IndexedPriorityQueue::new();
+        for (key, priority) in [("a", 10), ("b", 20), ("c", 30)] {
+            queue.push(key, priority);
+        }
+
+        queue.change_priority(&"a", 100);
+
+        assert_eq!(queue.pop(), Some(("b", 20)));
+        assert_eq!(queue.pop(), Some(("c", 30)));
+        assert_eq!(queue.pop(), Some(("a", 100)));
+    }
+
+    #[test]
+    fn test_change_priority_of_missing_key() {
+        let mut queue = IndexedPriorityQueue::<&str, i32>::new();
+
+        assert_eq!(queue.change_priority(&"z", 1), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_remove_arbitrary_key() {
+        let mut queue = IndexedPriorityQueue::new();
+        for key in 0..20 {
+            queue.push(key, (key * 7) % 20);
+        }
+
+        assert_eq!(queue.remove(&3), Some(1));
+        assert_eq!(queue.remove(&3), None);
+        queue.assert_invariants();
+        assert!(!queue.contains_key(&3));
+        assert_eq!(queue.len(), 19);
+
+        let mut priorities = vec![];
+        while let Some((_, priority)) = queue.pop() {
+            priorities.push(priority);
+        }
+        let expected: Vec<i32> = (0..20).filter(|&p| p != 1).collect();
+        assert_eq!(priorities, expected);
+    }
+
+    #[test]
+    fn test_mixed_operations_keep_positions_in_sync() {
+        let mut queue = IndexedPriorityQueue::new();
+        let mut state = 1u64;
+
+        for step in 0..400u64 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let key = (state >> 40) % 50;
+            let priority = (state >> 20) % 1000;
+            match step % 4 {
+                0 => {
+                    queue.remove(&key);
+                }
+                1 => {
+                    queue.change_priority(&key, priority);
+                }
+                _ => {
+                    queue.push(key, priority);
+                }
+            }
+            queue.assert_invariants();
+        }
+    }
+}
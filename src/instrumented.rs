@@ -0,0 +1,317 @@
+//! Operation counters
+//! Makes the work a collection does visible, so a claim like "lookups are
+//! O(log n)" can be checked by counting instead of timing. Items wrapped in
+//! `Counted` count every ordering comparison, equality probe, hash and clone
+//! done on them, and `CountingAllocator`, once installed as the global
+//! allocator, counts heap allocations such as new list nodes. The counters
+//! are per thread, so tests running side by side don't mix their numbers.
+//! `Instrumented` wraps any collection and reports what each operation on
+//! it cost, and what all of them cost together.
+
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt::{ self, Debug, Display };
+use std::hash::{ Hash, Hasher };
+use std::ops::{ Add, Sub };
+
+thread_local! {
+    static COMPARISONS: Cell<u64> = const { Cell::new(0) };
+    static PROBES: Cell<u64> = const { Cell::new(0) };
+    static HASHES: Cell<u64> = const { Cell::new(0) };
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static MOVES: Cell<u64> = const { Cell::new(0) };
+}
+
+fn bump(counter: &'static std::thread::LocalKey<Cell<u64>>) {
+    // The allocator can run while the thread is being torn down
+    let _ = counter.try_with(|count| count.set(count.get() + 1));
+}
+
+/// Counts of the work done, on the current thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Ordering comparisons, `cmp` and `partial_cmp`
+    pub comparisons: u64,
+    /// Equality checks, what a hash bucket or a linear search does per item
+    pub probes: u64,
+    pub hashes: u64,
+    /// Heap allocations, only counted with `CountingAllocator` installed
+    pub allocations: u64,
+    /// Clones of items, the moves a collection can't do without copying
+    pub moves: u64,
+}
+
+impl Report {
+    /// Counts since the thread started
+    pub fn now() -> Self {
+        Report {
+            comparisons: COMPARISONS.with(Cell::get),
+            probes: PROBES.with(Cell::get),
+            hashes: HASHES.with(Cell::get),
+            allocations: ALLOCATIONS.with(Cell::get),
+            moves: MOVES.with(Cell::get),
+        }
+    }
+}
+
+impl Add for Report {
+    type Output = Report;
+
+    fn add(self, other: Report) -> Report {
+        Report {
+            comparisons: self.comparisons + other.comparisons,
+            probes: self.probes + other.probes,
+            hashes: self.hashes + other.hashes,
+            allocations: self.allocations + other.allocations,
+            moves: self.moves + other.moves,
+        }
+    }
+}
+
+impl Sub for Report {
+    type Output = Report;
+
+    fn sub(self, other: Report) -> Report {
+        Report {
+            comparisons: self.comparisons - other.comparisons,
+            probes: self.probes - other.probes,
+            hashes: self.hashes - other.hashes,
+            allocations: self.allocations - other.allocations,
+            moves: self.moves - other.moves,
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} comparisons, {} probes, {} hashes, {} allocations, {} moves",
+            self.comparisons,
+            self.probes,
+            self.hashes,
+            self.allocations,
+            self.moves
+        )
+    }
+}
+
+/// Item that counts what is done with it
+#[derive(Default)]
+pub struct Counted<T>(pub T);
+
+impl<T: PartialEq> PartialEq for Counted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        bump(&PROBES);
+        self.0 == other.0
+    }
+}
+
+impl<T: Eq> Eq for Counted<T> {}
+
+impl<T: PartialOrd> PartialOrd for Counted<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        bump(&COMPARISONS);
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl<T: Ord> Ord for Counted<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        bump(&COMPARISONS);
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T: Hash> Hash for Counted<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        bump(&HASHES);
+        self.0.hash(state);
+    }
+}
+
+impl<T: Clone> Clone for Counted<T> {
+    fn clone(&self) -> Self {
+        bump(&MOVES);
+        Counted(self.0.clone())
+    }
+}
+
+impl<T: Debug> Debug for Counted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Display> Display for Counted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The system allocator, counting allocations. Install it with
+/// `#[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;`
+pub struct CountingAllocator;
+
+// SAFETY: Every call is passed on to the system allocator unchanged
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        bump(&ALLOCATIONS);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        bump(&ALLOCATIONS);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Collection that reports the cost of every operation run through it
+pub struct Instrumented<C> {
+    collection: C,
+    total: Report,
+}
+
+impl<C> Instrumented<C> {
+    pub fn new(collection: C) -> Self {
+        Instrumented { collection, total: Report::default() }
+    }
+
+    /// Runs the operation and returns its result with what it cost
+    pub fn measure<R>(&mut self, operation: impl FnOnce(&mut C) -> R) -> (R, Report) {
+        let before = Report::now();
+        let result = operation(&mut self.collection);
+        let cost = Report::now() - before;
+        self.total = self.total + cost;
+        (result, cost)
+    }
+
+    /// Cost of all measured operations since creation or the last reset
+    pub fn total(&self) -> Report {
+        self.total
+    }
+
+    pub fn reset(&mut self) {
+        self.total = Report::default();
+    }
+
+    pub fn get(&self) -> &C {
+        &self.collection
+    }
+
+    pub fn into_inner(self) -> C {
+        self.collection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl_tree::AvlTreeMap;
+    use crate::hash_map::HashMap;
+    use crate::singly_linked_list::SinglyLinkedList;
+    use crate::sorted_vec::SortedVec;
+    use crate::stack::Stack;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn when_nothing_is_measured_total_is_zero() {
+        let mut instrumented = Instrumented::new(SortedVec::<Counted<i32>>::new());
+
+        let (_, cost) = instrumented.measure(|_| ());
+
+        assert_eq!(cost, Report::default());
+        assert_eq!(instrumented.total(), Report::default());
+    }
+
+    #[test]
+    fn test_tree_lookup_is_logarithmic() {
+        let mut tree = AvlTreeMap::new();
+        for key in 0..1024 {
+            tree.insert(Counted(key), ());
+        }
+        let mut instrumented = Instrumented::new(tree);
+
+        for key in [0, 511, 1023] {
+            let (found, cost) = instrumented.measure(|tree| tree.contains_key(&Counted(key)));
+
+            assert!(found);
+            // A tree of height at most 1.44 log2(n) and one comparison per level
+            assert!(cost.comparisons <= 15, "{}", cost);
+        }
+    }
+
+    #[test]
+    fn test_list_search_is_linear_and_sorted_vec_is_not() {
+        let mut list = SinglyLinkedList::new();
+        for item in (0..1000).rev() {
+            list.prepend(Counted(item));
+        }
+        let sorted: SortedVec<Counted<i32>> = (0..1000).map(Counted).collect();
+        let mut list = Instrumented::new(list);
+        let mut sorted = Instrumented::new(sorted);
+
+        let (_, list_cost) = list.measure(|list| list.contains(&Counted(999)));
+        let (_, sorted_cost) = sorted.measure(|sorted| sorted.contains(&Counted(999)));
+
+        assert_eq!(list_cost.probes, 1000);
+        assert!(sorted_cost.comparisons <= 11, "{}", sorted_cost);
+    }
+
+    #[test]
+    fn test_hash_map_hashes_once_per_lookup() {
+        let mut map = HashMap::new();
+        for key in 0..100 {
+            map.insert(Counted(key), key);
+        }
+        let mut instrumented = Instrumented::new(map);
+
+        let (value, cost) = instrumented.measure(|map| map.get(Counted(42)));
+
+        assert_eq!(value, Some(42));
+        assert_eq!(cost.hashes, 1);
+        // 100 keys over 256 buckets leave very few keys to probe
+        assert!(cost.probes <= 3, "{}", cost);
+    }
+
+    #[test]
+    fn test_allocations_per_node_and_amortized() {
+        let mut list = Instrumented::new(SinglyLinkedList::new());
+        let mut stack = Instrumented::new(Stack::new());
+
+        for item in 0..100 {
+            list.measure(|list| list.prepend(item));
+            stack.measure(|stack| stack.push(item));
+        }
+
+        assert_eq!(list.total().allocations, 100);
+        // The vector under the stack grows by doubling, and regrowing in
+        // place doesn't count as a new allocation
+        assert!(stack.total().allocations <= 2, "{}", stack.total());
+    }
+
+    #[test]
+    fn test_total_adds_up_and_resets() {
+        let mut instrumented = Instrumented::new(SortedVec::new());
+
+        let (_, first) = instrumented.measure(|sorted| sorted.insert(Counted(2)));
+        let (_, second) = instrumented.measure(|sorted| sorted.insert(Counted(1)));
+
+        assert_eq!(instrumented.total(), first + second);
+        assert!(second.comparisons >= 1);
+        instrumented.reset();
+        assert_eq!(instrumented.total(), Report::default());
+        assert_eq!(instrumented.into_inner().len(), 2);
+    }
+}
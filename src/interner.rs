@@ -0,0 +1,171 @@
+//! String Interner implementation
+//! Maps every distinct string to a small integer symbol and back. The text
+//! of all strings is appended to one buffer that never shrinks, and a symbol
+//! is the index of the string's span in that buffer, so resolving is O(1)
+//! and a symbol stays valid for the lifetime of the interner.
+//! The crate's HashMap maps a string hash to the symbols with that hash,
+//! so the text is stored only once.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+
+use crate::hash_map::HashMap;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StringInterner {
+    buffer: String,
+    /// Start and end of every interned string in the buffer, by symbol
+    spans: Vec<(usize, usize)>,
+    symbols: HashMap<u64, Vec<Symbol>>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        StringInterner { buffer: String::new(), spans: vec![], symbols: HashMap::new() }
+    }
+
+    /// Number of distinct strings
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns the symbol of the string, storing it first if it is new
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        let hash = hash_of(text);
+        let mut candidates = self.symbols.get(hash).unwrap_or_default();
+        if let Some(symbol) = self.find(&candidates, text) {
+            return symbol;
+        }
+        let symbol = Symbol(u32::try_from(self.spans.len()).expect("Too many interned strings"));
+        let start = self.buffer.len();
+        self.buffer.push_str(text);
+        self.spans.push((start, self.buffer.len()));
+        candidates.push(symbol);
+        self.symbols.insert(hash, candidates);
+        check_invariants!(self);
+        symbol
+    }
+
+    /// Symbol of an already interned string
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        let candidates = self.symbols.get(hash_of(text))?;
+        self.find(&candidates, text)
+    }
+
+    /// Text of the symbol, None for a symbol of another interner
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        let &(start, end) = self.spans.get(symbol.0 as usize)?;
+        Some(&self.buffer[start..end])
+    }
+
+    /// Symbols and their strings in interning order
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+        self.spans
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| (Symbol(index as u32), &self.buffer[start..end]))
+    }
+
+    fn find(&self, candidates: &[Symbol], text: &str) -> Option<Symbol> {
+        candidates.iter().copied().find(|&symbol| self.resolve(symbol) == Some(text))
+    }
+}
+
+impl Default for StringInterner {
+    fn default() -> Self {
+        StringInterner::new()
+    }
+}
+
+/// Checks the spans tile the buffer in order and every string is found
+/// again under its own symbol
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for StringInterner {
+    fn assert_invariants(&self) {
+        let mut end = 0;
+        for (index, &(start, span_end)) in self.spans.iter().enumerate() {
+            assert_eq!(start, end, "Spans don't follow each other");
+            assert!(self.buffer.is_char_boundary(span_end), "Span cuts a char");
+            end = span_end;
+            let text = &self.buffer[start..span_end];
+            assert_eq!(self.get(text), Some(Symbol(index as u32)), "String isn't found again");
+        }
+        assert_eq!(end, self.buffer.len(), "Buffer has text past the last span");
+    }
+}
+
+fn hash_of(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_interner_is_created_it_is_empty() {
+        let interner = StringInterner::new();
+
+        assert!(interner.is_empty());
+        assert_eq!(interner.get("a"), None);
+        assert_eq!(interner.resolve(Symbol(0)), None);
+    }
+
+    #[test]
+    fn test_same_string_gets_same_symbol() {
+        let mut interner = StringInterner::new();
+
+        let first = interner.intern("apple");
+        let other = interner.intern("pear");
+        let again = interner.intern(&String::from("apple"));
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get("pear"), Some(other));
+    }
+
+    #[test]
+    fn test_resolve_round_trips() {
+        let mut interner = StringInterner::new();
+        let words = ["", "a", "ab", "abc", "ünïcödé"];
+
+        let symbols: Vec<Symbol> = words.iter().map(|word| interner.intern(word)).collect();
+
+        for (symbol, word) in symbols.iter().zip(words) {
+            assert_eq!(interner.resolve(*symbol), Some(word));
+        }
+        assert_eq!(interner.buffer.len(), words.iter().map(|word| word.len()).sum::<usize>());
+    }
+
+    #[test]
+    fn test_symbols_are_dense_and_stable() {
+        let mut interner = StringInterner::new();
+
+        let symbols: Vec<u32> = (0..1000)
+            .map(|i| interner.intern(&format!("word{}", i % 500)).as_u32())
+            .collect();
+
+        assert_eq!(interner.len(), 500);
+        assert!(symbols.iter().enumerate().all(|(i, symbol)| *symbol as usize == i % 500));
+        assert_eq!(interner.iter().nth(42), Some((Symbol(42), "word42")));
+    }
+}
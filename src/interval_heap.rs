@@ -0,0 +1,319 @@
+//! Interval Heap implementation
+//! Double-ended priority queue: both the smallest and the largest item can
+//! be read in O(1) and removed in O(log n). Every node of a complete binary
+//! tree holds a pair `lo <= hi` stored side by side in one vector (node k at
+//! indices 2k and 2k + 1) and the interval of a child lies within the
+//! interval of its parent. So the left ends form a min heap and the right
+//! ends form a max heap.
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalHeap<T: Ord> {
+    data: Vec<T>,
+}
+
+/// Interval heap is the usual backing for a double-ended priority queue
+pub type DoubleEndedPriorityQueue<T> = IntervalHeap<T>;
+
+impl<T: Ord> IntervalHeap<T> {
+    pub fn new() -> Self {
+        IntervalHeap { data: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        check_invariants!(self);
+    }
+
+    pub fn peek_min(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// The largest item is the right end of the root, or the only
+    /// item when the heap has a single element.
+    pub fn peek_max(&self) -> Option<&T> {
+        self.data.get(1).or(self.data.first())
+    }
+
+    /// Items in no particular order
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.data.push(item);
+        let index = self.data.len() - 1;
+
+        if index % 2 == 1 {
+            // Node now holds two items, keep lo <= hi
+            if self.data[index] < self.data[index - 1] {
+                self.data.swap(index, index - 1);
+                self.sift_up_min(index - 1);
+            } else {
+                self.sift_up_max(index);
+            }
+        } else if index > 0 {
+            let parent = (index / 2 - 1) / 2;
+            if self.data[index] < self.data[2 * parent] {
+                self.sift_up_min(index);
+            } else if self.data[index] > self.data[2 * parent + 1] {
+                self.sift_up_max(index);
+            }
+        }
+        check_invariants!(self);
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        if self.data.len() <= 1 {
+            return self.data.pop();
+        }
+        let last = self.data.len() - 1;
+        self.data.swap(0, last);
+        let result = self.data.pop();
+        self.sift_down_min(0);
+        check_invariants!(self);
+        result
+    }
+
+    pub fn pop_max(&mut self) -> Option<T> {
+        if self.data.len() <= 2 {
+            return self.data.pop();
+        }
+        let last = self.data.len() - 1;
+        self.data.swap(1, last);
+        let result = self.data.pop();
+        self.sift_down_max(1);
+        check_invariants!(self);
+        result
+    }
+
+    /// Moves the item at a left end up while it is smaller than the left end of the parent
+    fn sift_up_min(&mut self, mut index: usize) {
+        while index / 2 > 0 {
+            let parent_min = 2 * ((index / 2 - 1) / 2);
+            if self.data[index] >= self.data[parent_min] {
+                break;
+            }
+            self.data.swap(index, parent_min);
+            index = parent_min;
+        }
+    }
+
+    /// Moves the item at a right end up while it is larger than the right end of the parent
+    fn sift_up_max(&mut self, mut index: usize) {
+        while index / 2 > 0 {
+            let parent_max = 2 * ((index / 2 - 1) / 2) + 1;
+            if self.data[index] <= self.data[parent_max] {
+                break;
+            }
+            self.data.swap(index, parent_max);
+            index = parent_max;
+        }
+    }
+
+    fn sift_down_min(&mut self, mut index: usize) {
+        let len = self.data.len();
+        loop {
+            if index + 1 < len && self.data[index] > self.data[index + 1] {
+                self.data.swap(index, index + 1);
+            }
+            let node = index / 2;
+            let smallest_child = [2 * (2 * node + 1), 2 * (2 * node + 2)]
+                .into_iter()
+                .filter(|&child_min| child_min < len)
+                .min_by(|&a, &b| self.data[a].cmp(&self.data[b]));
+            let child_min = match smallest_child {
+                Some(child_min) if self.data[child_min] < self.data[index] => child_min,
+                _ => {
+                    break;
+                }
+            };
+            self.data.swap(index, child_min);
+            index = child_min;
+        }
+    }
+
+    fn sift_down_max(&mut self, mut index: usize) {
+        let len = self.data.len();
+        loop {
+            if self.data[index] < self.data[index - 1] {
+                self.data.swap(index, index - 1);
+            }
+            let node = index / 2;
+            // A child node with a single item stores it at its left end
+            let largest_child = [2 * node + 1, 2 * node + 2]
+                .into_iter()
+                .filter(|&child| 2 * child < len)
+                .map(|child| if 2 * child + 1 < len { 2 * child + 1 } else { 2 * child })
+                .max_by(|&a, &b| self.data[a].cmp(&self.data[b]));
+            let child_max = match largest_child {
+                Some(child_max) if self.data[child_max] > self.data[index] => child_max,
+                _ => {
+                    break;
+                }
+            };
+            self.data.swap(index, child_max);
+            if child_max % 2 == 0 {
+                break;
+            }
+            index = child_max;
+        }
+    }
+}
+
+impl<T: Ord> Default for IntervalHeap<T> {
+    fn default() -> Self {
+        IntervalHeap::new()
+    }
+}
+
+/// `contains` goes through every item, a heap is only ordered at its ends
+impl<T: Ord> Collection for IntervalHeap<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        IntervalHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        IntervalHeap::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.iter().any(|other| other == item)
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for IntervalHeap<T> {
+    fn assert_invariants(&self) {
+        let data = &self.data;
+        for index in 0..data.len() {
+            let node = index / 2;
+            if index % 2 == 1 {
+                assert!(data[index - 1] <= data[index], "Node {} has lo > hi", node);
+            }
+            if node > 0 {
+                let parent = (node - 1) / 2;
+                assert!(data[2 * parent] <= data[index], "Node {} below parent lo", node);
+                assert!(data[index] <= data[2 * parent + 1], "Node {} above parent hi", node);
+            }
+        }
+    }
+}
+
+impl<T: Ord + HeapSize> HeapSize for IntervalHeap<T> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_heap_is_created_it_is_empty() {
+        let mut heap = IntervalHeap::<i32>::new();
+
+        assert!(heap.is_empty());
+        assert_eq!(heap.peek_min(), None);
+        assert_eq!(heap.peek_max(), None);
+        assert_eq!(heap.pop_min(), None);
+        assert_eq!(heap.pop_max(), None);
+    }
+
+    #[test]
+    fn test_single_item_is_both_min_and_max() {
+        let mut heap = IntervalHeap::new();
+
+        heap.push(5);
+
+        assert_eq!(heap.peek_min(), Some(&5));
+        assert_eq!(heap.peek_max(), Some(&5));
+        assert_eq!(heap.pop_max(), Some(5));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_push_keeps_min_and_max_at_root() {
+        let mut heap = IntervalHeap::new();
+
+        for item in [40, 10, 70, 25, 90, 5, 60, 33] {
+            heap.push(item);
+            heap.assert_invariants();
+        }
+
+        assert_eq!(heap.peek_min(), Some(&5));
+        assert_eq!(heap.peek_max(), Some(&90));
+        assert_eq!(heap.len(), 8);
+    }
+
+    #[test]
+    fn test_pop_min_returns_ascending_items() {
+        let mut heap = IntervalHeap::new();
+        for item in (0..40).map(|i| (i * 13) % 40) {
+            heap.push(item);
+        }
+
+        let mut items = vec![];
+        while let Some(item) = heap.pop_min() {
+            heap.assert_invariants();
+            items.push(item);
+        }
+
+        assert_eq!(items, (0..40).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_pop_max_returns_descending_items() {
+        let mut heap = IntervalHeap::new();
+        for item in (0..41).map(|i| (i * 17) % 41) {
+            heap.push(item);
+        }
+
+        let mut items = vec![];
+        while let Some(item) = heap.pop_max() {
+            heap.assert_invariants();
+            items.push(item);
+        }
+
+        assert_eq!(items, (0..41).rev().collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_alternating_pops_match_sorted_model() {
+        let mut heap = DoubleEndedPriorityQueue::new();
+        let mut model: Vec<u32> = vec![];
+        let mut state = 7u32;
+
+        for step in 0..500 {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            let item = (state >> 16) % 1000;
+            heap.push(item);
+            model.push(item);
+            model.sort();
+            match step % 3 {
+                0 => assert_eq!(heap.pop_min(), Some(model.remove(0))),
+                1 => assert_eq!(heap.pop_max(), model.pop()),
+                _ => {}
+            }
+            heap.assert_invariants();
+            assert_eq!(heap.peek_min(), model.first());
+            assert_eq!(heap.peek_max(), model.last());
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Interval Set implementation
+//! Set of values stored as disjoint half-open ranges `start..end` in an AVL
+//! tree keyed by the range start. Inserting a range merges it with every
+//! range it overlaps or touches, so the stored ranges are always maximal and
+//! separated by gaps. Removing a range trims or splits the ranges it cuts.
+//! Both are O(k log n) for k affected ranges, and lookups are O(log n).
+
+use std::ops::Range;
+
+use crate::avl_tree::AvlTreeMap;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalSet<T> {
+    /// Start of every range mapped to its end
+    ranges: AvlTreeMap<T, T>,
+}
+
+impl<T: Ord + Copy> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet { ranges: AvlTreeMap::new() }
+    }
+
+    /// Number of maximal ranges
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Adds the range, merging it with overlapping and adjacent ranges
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = (range.start, range.end);
+        if let Some((floor_start, floor_end)) = self.floor(&start) {
+            if floor_end >= start {
+                start = floor_start;
+                end = end.max(floor_end);
+            }
+        }
+        let covered: Vec<(T, T)> = self.ranges.range(start..=end).map(|(s, e)| (*s, *e)).collect();
+        for (covered_start, covered_end) in covered {
+            self.ranges.remove(&covered_start);
+            end = end.max(covered_end);
+        }
+        self.ranges.insert(start, end);
+        check_invariants!(self);
+    }
+
+    /// Removes the range, splitting a stored range that contains it
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        let (start, end) = (range.start, range.end);
+        if let Some((before_start, before_end)) = self.ranges.next_below(&start) {
+            let (before_start, before_end) = (*before_start, *before_end);
+            if before_end > start {
+                self.ranges.insert(before_start, start);
+                if before_end > end {
+                    self.ranges.insert(end, before_end);
+                }
+            }
+        }
+        let cut: Vec<(T, T)> = self.ranges.range(start..end).map(|(s, e)| (*s, *e)).collect();
+        for (cut_start, cut_end) in cut {
+            self.ranges.remove(&cut_start);
+            if cut_end > end {
+                self.ranges.insert(end, cut_end);
+            }
+        }
+        check_invariants!(self);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.floor(value).is_some_and(|(_, end)| end > *value)
+    }
+
+    /// True if every value of the range is in the set
+    pub fn contains_range(&self, range: &Range<T>) -> bool {
+        range.is_empty()
+            || self.floor(&range.start).is_some_and(|(_, end)| end >= range.end)
+    }
+
+    /// Maximal ranges in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        self.ranges.iter().map(|(start, end)| *start..*end)
+    }
+
+    /// Range with the largest start that is not after `value`
+    fn floor(&self, value: &T) -> Option<(T, T)> {
+        match self.ranges.get(value) {
+            Some(end) => Some((*value, *end)),
+            None => self.ranges.next_below(value).map(|(start, end)| (*start, *end)),
+        }
+    }
+}
+
+/// Checks no range is empty and a gap separates every two ranges
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord + Copy> Invariants for IntervalSet<T> {
+    fn assert_invariants(&self) {
+        let ranges: Vec<Range<T>> = self.iter().collect();
+        assert!(ranges.iter().all(|range| range.start < range.end), "Stored range is empty");
+        let separated = ranges.windows(2).all(|pair| pair[0].end < pair[1].start);
+        assert!(separated, "Ranges overlap or touch");
+    }
+}
+
+impl<T: Ord + Copy> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Ord + Copy> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T: HeapSize> HeapSize for IntervalSet<T> {
+    fn heap_size(&self) -> usize {
+        self.ranges.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(set: &IntervalSet<i32>) -> Vec<Range<i32>> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn when_set_is_created_it_is_empty() {
+        let set = IntervalSet::<i32>::new();
+
+        assert!(set.is_empty());
+        assert!(!set.contains(&0));
+        assert_eq!(set.iter().next(), None);
+    }
+
+    #[test]
+    fn test_insert_merges_overlapping_and_adjacent_ranges() {
+        let mut set = IntervalSet::new();
+
+        set.insert(1..3);
+        set.insert(10..12);
+        set.insert(5..7);
+        assert_eq!(ranges(&set), vec![1..3, 5..7, 10..12]);
+
+        set.insert(3..5);
+        assert_eq!(ranges(&set), vec![1..7, 10..12]);
+
+        set.insert(0..11);
+        assert_eq!(ranges(&set), vec![0..12]);
+    }
+
+    #[test]
+    fn test_insert_inside_existing_range_changes_nothing() {
+        let mut set: IntervalSet<i32> = std::iter::once(0..10).collect();
+
+        set.insert(2..4);
+        set.insert(5..5);
+
+        assert_eq!(ranges(&set), vec![0..10]);
+    }
+
+    #[test]
+    fn test_remove_splits_and_trims() {
+        let mut set: IntervalSet<i32> = vec![0..10, 20..30].into_iter().collect();
+
+        set.remove(4..6);
+        assert_eq!(ranges(&set), vec![0..4, 6..10, 20..30]);
+
+        set.remove(8..25);
+        assert_eq!(ranges(&set), vec![0..4, 6..8, 25..30]);
+
+        set.remove(-5..100);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_contains() {
+        let set: IntervalSet<i32> = vec![0..5, 10..15].into_iter().collect();
+
+        assert!(set.contains(&0));
+        assert!(set.contains(&4));
+        assert!(!set.contains(&5));
+        assert!(!set.contains(&-1));
+        assert!(set.contains_range(&(11..15)));
+        assert!(!set.contains_range(&(3..11)));
+    }
+
+    #[test]
+    fn test_random_operations_match_bitmap() {
+        let mut set = IntervalSet::new();
+        let mut expected = [false; 64];
+        let mut state: u64 = 11;
+
+        for _ in 0..2000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let start = (state >> 33) as usize % 64;
+            let end = (start + (state >> 50) as usize % 10).min(64);
+            let insert = state.is_multiple_of(2);
+            if insert {
+                set.insert(start..end);
+            } else {
+                set.remove(start..end);
+            }
+            expected[start..end].iter_mut().for_each(|bit| *bit = insert);
+        }
+
+        for (value, present) in expected.iter().enumerate() {
+            assert_eq!(set.contains(&value), *present, "Value {}", value);
+        }
+        let ranges: Vec<Range<usize>> = set.iter().collect();
+        assert!(ranges.windows(2).all(|pair| pair[0].end < pair[1].start), "Ranges are maximal");
+    }
+}
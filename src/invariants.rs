@@ -0,0 +1,27 @@
+//! Invariant checks
+//! `assert_invariants` walks a whole structure and panics at the first thing
+//! that doesn't add up: a stored length that disagrees with the items, a heap
+//! out of order, a tree out of balance. The mutating methods call it through
+//! `check_invariants!` before they return, so the unit tests check every
+//! structure after every change they make, at O(n) per change. The
+//! `debug-invariants` feature turns the checks on outside the unit tests too,
+//! for the integration tests or a debug build of a program. Otherwise the
+//! macro expands to nothing and `Invariants` doesn't exist.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+pub trait Invariants {
+    /// Panics describing the first broken invariant
+    fn assert_invariants(&self);
+}
+
+/// Checks the structure behind the reference in unit tests and when
+/// `debug-invariants` is on
+macro_rules! check_invariants {
+    ($structure:expr) => {
+        #[cfg(any(test, feature = "debug-invariants"))]
+        $crate::invariants::Invariants::assert_invariants($structure);
+    };
+}
+
+pub(crate) use check_invariants;
+
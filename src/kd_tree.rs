@@ -0,0 +1,268 @@
+//! K-d Tree implementation for points in K dimensions
+//! Binary tree that splits space by one coordinate per level, cycling
+//! through the axes. It is built in bulk: the median point along the axis
+//! becomes the node, smaller points go left and larger go right. The tree is
+//! implicit, the node of range `lo..hi` of the point vector sits at its middle.
+//! Searches skip every subtree whose half-space can't contain an answer.
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+pub type Point<const K: usize> = [f64; K];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KdTree<const K: usize> {
+    points: Vec<Point<K>>,
+}
+
+impl<const K: usize> KdTree<K> {
+    /// Builds a balanced tree in O(n log n) using median selection
+    pub fn new(mut points: Vec<Point<K>>) -> Self {
+        let length = points.len();
+        Self::build(&mut points, 0, length, 0);
+        let tree = KdTree { points };
+        check_invariants!(&tree);
+        tree
+    }
+
+    fn build(points: &mut [Point<K>], lo: usize, hi: usize, depth: usize) {
+        if hi - lo <= 1 {
+            return;
+        }
+        let axis = depth % K;
+        let middle = (lo + hi) / 2;
+        points[lo..hi].select_nth_unstable_by(middle - lo, |a, b| a[axis].total_cmp(&b[axis]));
+        Self::build(points, lo, middle, depth + 1);
+        Self::build(points, middle + 1, hi, depth + 1);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Points in the order the tree keeps them
+    pub fn iter(&self) -> std::slice::Iter<'_, Point<K>> {
+        self.points.iter()
+    }
+
+    /// Closest point to `query` together with its Euclidean distance
+    pub fn nearest(&self, query: &Point<K>) -> Option<(&Point<K>, f64)> {
+        let mut best: Option<(usize, f64)> = None;
+        self.nearest_in(query, 0, self.points.len(), 0, &mut best);
+        best.map(|(index, squared)| (&self.points[index], squared.sqrt()))
+    }
+
+    fn nearest_in(
+        &self,
+        query: &Point<K>,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        best: &mut Option<(usize, f64)>
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let middle = (lo + hi) / 2;
+        let point = &self.points[middle];
+        let distance = squared_distance(point, query);
+        if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+            *best = Some((middle, distance));
+        }
+
+        let axis = depth % K;
+        let difference = query[axis] - point[axis];
+        let (near, far) = if difference < 0.0 {
+            ((lo, middle), (middle + 1, hi))
+        } else {
+            ((middle + 1, hi), (lo, middle))
+        };
+        self.nearest_in(query, near.0, near.1, depth + 1, best);
+        // The far side can only help if the splitting plane is closer than the best point
+        if best.is_none_or(|(_, best_distance)| difference * difference < best_distance) {
+            self.nearest_in(query, far.0, far.1, depth + 1, best);
+        }
+    }
+
+    /// All points inside the axis-aligned box `min..=max`
+    pub fn range(&self, min: &Point<K>, max: &Point<K>) -> Vec<&Point<K>> {
+        let mut found = vec![];
+        self.range_in(min, max, 0, self.points.len(), 0, &mut found);
+        found
+    }
+
+    fn range_in<'a>(
+        &'a self,
+        min: &Point<K>,
+        max: &Point<K>,
+        lo: usize,
+        hi: usize,
+        depth: usize,
+        found: &mut Vec<&'a Point<K>>
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let middle = (lo + hi) / 2;
+        let point = &self.points[middle];
+        if (0..K).all(|axis| min[axis] <= point[axis] && point[axis] <= max[axis]) {
+            found.push(point);
+        }
+
+        let axis = depth % K;
+        if min[axis] <= point[axis] {
+            self.range_in(min, max, lo, middle, depth + 1, found);
+        }
+        if point[axis] <= max[axis] {
+            self.range_in(min, max, middle + 1, hi, depth + 1, found);
+        }
+    }
+}
+
+/// Checks the points of `lo..hi` lie on the right side of every node, one
+/// level at a time
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<const K: usize> Invariants for KdTree<K> {
+    fn assert_invariants(&self) {
+        let mut pending = vec![(0, self.points.len(), 0)];
+        while let Some((lo, hi, depth)) = pending.pop() {
+            if hi - lo <= 1 {
+                continue;
+            }
+            let axis = depth % K;
+            let middle = (lo + hi) / 2;
+            let split = self.points[middle][axis];
+            let left = &self.points[lo..middle];
+            let right = &self.points[middle + 1..hi];
+            assert!(left.iter().all(|point| point[axis] <= split), "Left point past the split");
+            assert!(right.iter().all(|point| point[axis] >= split), "Right point before the split");
+            pending.extend([(lo, middle, depth + 1), (middle + 1, hi, depth + 1)]);
+        }
+    }
+}
+
+fn squared_distance<const K: usize>(a: &Point<K>, b: &Point<K>) -> f64 {
+    (0..K).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+impl<const K: usize> HeapSize for KdTree<K> {
+    fn heap_size(&self) -> usize {
+        self.points.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_points<const K: usize>(count: usize, seed: u64) -> Vec<Point<K>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                let mut point = [0.0; K];
+                for coordinate in point.iter_mut() {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    *coordinate = ((state >> 33) % 1000) as f64 / 10.0;
+                }
+                point
+            })
+            .collect()
+    }
+
+    fn brute_force_nearest<const K: usize>(points: &[Point<K>], query: &Point<K>) -> f64 {
+        points
+            .iter()
+            .map(|point| squared_distance(point, query).sqrt())
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn sample_points() -> Vec<Point<2>> {
+        vec![[7.0, 2.0], [5.0, 4.0], [9.0, 6.0], [2.0, 3.0], [4.0, 7.0], [8.0, 1.0]]
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = KdTree::<2>::new(vec![]);
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.nearest(&[1.0, 1.0]), None);
+        assert!(tree.range(&[0.0, 0.0], &[9.0, 9.0]).is_empty());
+    }
+
+    #[test]
+    fn test_root_is_median_of_first_axis() {
+        let tree = KdTree::new(sample_points());
+
+        assert_eq!(tree.points[3], [7.0, 2.0]);
+        assert!(tree.points[..3].iter().all(|point| point[0] <= 7.0));
+        assert!(tree.points[4..].iter().all(|point| point[0] >= 7.0));
+    }
+
+    #[test]
+    fn test_nearest_in_small_tree() {
+        let tree = KdTree::new(sample_points());
+
+        let (point, distance) = tree.nearest(&[9.0, 2.0]).unwrap();
+
+        assert_eq!(point, &[8.0, 1.0]);
+        assert!((distance - 2f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_nearest_of_existing_point_is_itself() {
+        let points = random_points::<3>(200, 1);
+        let tree = KdTree::new(points.clone());
+
+        for point in &points {
+            assert_eq!(tree.nearest(point).unwrap().1, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_nearest_matches_brute_force() {
+        let points = random_points::<2>(500, 7);
+        let tree = KdTree::new(points.clone());
+
+        for query in random_points::<2>(200, 99) {
+            let (_, distance) = tree.nearest(&query).unwrap();
+            assert_eq!(distance, brute_force_nearest(&points, &query));
+        }
+    }
+
+    #[test]
+    fn test_range_matches_brute_force() {
+        let points = random_points::<3>(400, 3);
+        let tree = KdTree::new(points.clone());
+        let corners = random_points::<3>(40, 11);
+
+        for pair in corners.chunks(2) {
+            let min: Point<3> = std::array::from_fn(|axis| pair[0][axis].min(pair[1][axis]));
+            let max: Point<3> = std::array::from_fn(|axis| pair[0][axis].max(pair[1][axis]));
+
+            let mut found: Vec<Point<3>> = tree.range(&min, &max).into_iter().copied().collect();
+            let mut expected: Vec<Point<3>> = points
+                .iter()
+                .filter(|p| (0..3).all(|axis| min[axis] <= p[axis] && p[axis] <= max[axis]))
+                .copied()
+                .collect();
+            found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_range_box_is_inclusive() {
+        let tree = KdTree::new(vec![[1.0], [2.0], [3.0], [4.0]]);
+
+        let found = tree.range(&[2.0], &[3.0]);
+
+        assert_eq!(found.len(), 2);
+    }
+}
@@ -0,0 +1,183 @@
+//! Key adapters
+//! A `KeyAdapter` decides which keys count as the same entry, by hashing and
+//! comparing them its own way. Wrapping a key in `Adapted` makes the hash map
+//! (or anything else that goes by `Hash` and `Eq`) use the adapter instead of
+//! the key's own impls, so "Key" and "key" can be one entry without every
+//! caller lowercasing first. The wrapper keeps the key as it was given, the
+//! map just stores whichever spelling was inserted first.
+//! `CaseInsensitive` and `Normalized` cover the usual cases; an adapter of
+//! your own only has to keep `hash` and `eq` consistent: keys that are equal
+//! must hash the same.
+
+use std::fmt::{ self, Debug, Display };
+use std::hash::{ Hash, Hasher };
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+pub trait KeyAdapter<K: ?Sized> {
+    fn hash<H: Hasher>(key: &K, state: &mut H);
+
+    fn eq(a: &K, b: &K) -> bool;
+}
+
+/// Compares strings by their lowercase forms. That is not full case folding:
+/// "Straße" and "STRASSE" stay different keys
+pub struct IgnoreCase;
+
+impl<S: AsRef<str> + ?Sized> KeyAdapter<S> for IgnoreCase {
+    fn hash<H: Hasher>(key: &S, state: &mut H) {
+        hash_chars(lowercase(key.as_ref()), state);
+    }
+
+    fn eq(a: &S, b: &S) -> bool {
+        lowercase(a.as_ref()).eq(lowercase(b.as_ref()))
+    }
+}
+
+/// Ignores case, whitespace at either end and how long a run of whitespace
+/// inside is, so " New  York" and "new york" are the same
+pub struct IgnoreCaseAndSpacing;
+
+impl<S: AsRef<str> + ?Sized> KeyAdapter<S> for IgnoreCaseAndSpacing {
+    fn hash<H: Hasher>(key: &S, state: &mut H) {
+        hash_chars(normalized(key.as_ref()), state);
+    }
+
+    fn eq(a: &S, b: &S) -> bool {
+        normalized(a.as_ref()).eq(normalized(b.as_ref()))
+    }
+}
+
+fn lowercase(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars().flat_map(char::to_lowercase)
+}
+
+/// Lowercase words joined by single spaces
+fn normalized(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.split_whitespace()
+        .enumerate()
+        .flat_map(|(index, word)| (index > 0).then_some(' ').into_iter().chain(lowercase(word)))
+}
+
+/// Hashes the chars and then a terminator, like `str` does, so keys that
+/// are a prefix of one another don't collide on purpose
+fn hash_chars<H: Hasher>(chars: impl Iterator<Item = char>, state: &mut H) {
+    chars.for_each(|ch| ch.hash(state));
+    state.write_u8(0xff);
+}
+
+/// A key that hashes and compares through the adapter `A`
+pub struct Adapted<K, A> {
+    key: K,
+    adapter: PhantomData<fn() -> A>,
+}
+
+pub type CaseInsensitive<K> = Adapted<K, IgnoreCase>;
+
+pub type Normalized<K> = Adapted<K, IgnoreCaseAndSpacing>;
+
+impl<K, A> Adapted<K, A> {
+    pub fn new(key: K) -> Self {
+        Adapted { key, adapter: PhantomData }
+    }
+
+    /// The key as it was given
+    pub fn into_inner(self) -> K {
+        self.key
+    }
+}
+
+impl<K, A> From<K> for Adapted<K, A> {
+    fn from(key: K) -> Self {
+        Adapted::new(key)
+    }
+}
+
+impl<K, A> Deref for Adapted<K, A> {
+    type Target = K;
+
+    fn deref(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Clone, A> Clone for Adapted<K, A> {
+    fn clone(&self) -> Self {
+        Adapted::new(self.key.clone())
+    }
+}
+
+impl<K: Debug, A> Debug for Adapted<K, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)
+    }
+}
+
+impl<K: Display, A> Display for Adapted<K, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.key.fmt(f)
+    }
+}
+
+impl<K, A: KeyAdapter<K>> Hash for Adapted<K, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        A::hash(&self.key, state);
+    }
+}
+
+impl<K, A: KeyAdapter<K>> PartialEq for Adapted<K, A> {
+    fn eq(&self, other: &Self) -> bool {
+        A::eq(&self.key, &other.key)
+    }
+}
+
+impl<K, A: KeyAdapter<K>> Eq for Adapted<K, A> {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+    use crate::hash_map::HashMap;
+
+    fn hash_of(key: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_case_insensitive_keys_share_an_entry() {
+        let mut map = HashMap::new();
+
+        map.insert(CaseInsensitive::new("Key".to_string()), 1);
+        let old = map.insert(CaseInsensitive::new("KEY".to_string()), 2);
+
+        assert_eq!(old, Some(1));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("key".to_string().into()), Some(2));
+        let (stored, _) = map.iter().next().unwrap();
+        assert_eq!(stored.as_str(), "Key", "The first spelling is kept");
+    }
+
+    #[test]
+    fn test_normalized_keys_ignore_spacing_and_case() {
+        let mut map = HashMap::new();
+        map.insert(Normalized::new(" New  York "), "NY");
+
+        assert_eq!(map.get(Normalized::new("new york")), Some("NY"));
+        assert_eq!(map.get(Normalized::new("New\tYork")), Some("NY"));
+        assert_eq!(map.get(Normalized::new("NewYork")), None);
+    }
+
+    #[test]
+    fn test_equal_keys_hash_the_same() {
+        let pairs = [("Straße", "STRASSE"), ("ǅemal", "ǆEMAL"), ("Σ", "σ")];
+
+        for (a, b) in pairs {
+            let (a, b) = (CaseInsensitive::<&str>::new(a), CaseInsensitive::new(b));
+            assert_eq!(a == b, hash_of(&a) == hash_of(&b), "{} and {}", a, b);
+        }
+        assert_ne!(hash_of(&Normalized::new("a b")), hash_of(&Normalized::new("ab")));
+    }
+}
@@ -0,0 +1,310 @@
+//! Leftist Heap implementation (min heap)
+//! Binary tree where every node is smaller than its children, and the rank
+//! (length of the rightmost path down to a missing child) of a left child
+//! is never smaller than the rank of its right sibling. The right spine is
+//! therefore at most log2(n + 1) nodes long. Meld walks only the right
+//! spines of both heaps and swaps children where the ranks break the rule,
+//! so it is O(log n), and push and pop are both just a meld.
+//! Unlike the array-based `BinaryHeap`, two heaps meld without copying.
+
+use std::mem;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+struct LeftistNode<T> {
+    item: T,
+    rank: usize,
+    left: Option<Box<LeftistNode<T>>>,
+    right: Option<Box<LeftistNode<T>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LeftistHeap<T: Ord> {
+    root: Option<Box<LeftistNode<T>>>,
+    size: usize,
+}
+
+fn rank<T>(node: &Option<Box<LeftistNode<T>>>) -> usize {
+    node.as_ref().map_or(0, |node| node.rank)
+}
+
+/// Merges the right spine of the heap that has the larger root into the
+/// one with the smaller root, swapping children to keep the left side higher
+fn meld_nodes<T: Ord>(
+    a: Option<Box<LeftistNode<T>>>,
+    b: Option<Box<LeftistNode<T>>>
+) -> Option<Box<LeftistNode<T>>> {
+    match (a, b) {
+        (None, node) | (node, None) => node,
+        (Some(a), Some(b)) => {
+            let (mut parent, child) = if b.item < a.item { (b, a) } else { (a, b) };
+            parent.right = meld_nodes(parent.right.take(), Some(child));
+            if rank(&parent.left) < rank(&parent.right) {
+                mem::swap(&mut parent.left, &mut parent.right);
+            }
+            parent.rank = rank(&parent.right) + 1;
+            Some(parent)
+        }
+    }
+}
+
+impl<T: Ord> LeftistHeap<T> {
+    pub fn new() -> Self {
+        LeftistHeap { root: None, size: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = LeftistHeap::new();
+        check_invariants!(self);
+    }
+
+    /// Returns the smallest item without removing it
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.item)
+    }
+
+    /// Items in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut stack: Vec<&LeftistNode<T>> = self.root.as_deref().into_iter().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+            Some(&node.item)
+        })
+    }
+
+    /// Melds a single node heap into this one. O(log n)
+    pub fn push(&mut self, item: T) {
+        self.link(item);
+        check_invariants!(self);
+    }
+
+    /// `push` without the invariant check
+    fn link(&mut self, item: T) {
+        let node = Box::new(LeftistNode { item, rank: 1, left: None, right: None });
+        self.root = meld_nodes(self.root.take(), Some(node));
+        self.size += 1;
+    }
+
+    /// Removes the root and melds its two subtrees. O(log n)
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let LeftistNode { item, left, right, .. } = *root;
+        self.root = meld_nodes(left, right);
+        self.size -= 1;
+        check_invariants!(self);
+        Some(item)
+    }
+
+    /// Moves all items of `other` into this heap. O(log n)
+    pub fn meld(&mut self, mut other: LeftistHeap<T>) {
+        self.root = meld_nodes(self.root.take(), other.root.take());
+        self.size += mem::take(&mut other.size);
+        check_invariants!(self);
+    }
+}
+
+impl<T: Ord> Default for LeftistHeap<T> {
+    fn default() -> Self {
+        LeftistHeap::new()
+    }
+}
+
+/// `contains` goes through every item, a heap is only ordered for its top
+impl<T: Ord> Collection for LeftistHeap<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        LeftistHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        LeftistHeap::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.iter().any(|other| other == item)
+    }
+}
+
+/// Builds the heap in O(n) by melding single node heaps in pairs,
+/// round after round, until one is left
+impl<T: Ord> FromIterator<T> for LeftistHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heaps: Vec<LeftistHeap<T>> = iter
+            .into_iter()
+            .map(|item| {
+                let mut heap = LeftistHeap::new();
+                heap.push(item);
+                heap
+            })
+            .collect();
+        while heaps.len() > 1 {
+            let mut melded = Vec::with_capacity(heaps.len().div_ceil(2));
+            let mut pairs = heaps.into_iter();
+            while let Some(mut heap) = pairs.next() {
+                if let Some(other) = pairs.next() {
+                    heap.meld(other);
+                }
+                melded.push(heap);
+            }
+            heaps = melded;
+        }
+        heaps.pop().unwrap_or_default()
+    }
+}
+
+/// Drops the nodes one by one, the left spine can be O(n) deep
+impl<T: Ord> Drop for LeftistHeap<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<LeftistNode<T>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+/// Checks heap order and the leftist rank rule node by node, in a loop
+/// since the left spine can be O(n) deep
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for LeftistHeap<T> {
+    fn assert_invariants(&self) {
+        let mut nodes = 0;
+        let mut stack: Vec<&LeftistNode<T>> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            nodes += 1;
+            for child in [&node.left, &node.right].into_iter().flatten() {
+                assert!(node.item <= child.item, "Parent is not greater than its children");
+                stack.push(child);
+            }
+            assert!(rank(&node.left) >= rank(&node.right), "Left rank is at least right rank");
+            assert_eq!(node.rank, rank(&node.right) + 1, "Rank is one more than right rank");
+        }
+        assert_eq!(nodes, self.size, "size doesn't match the nodes");
+        let spine = rank(&self.root);
+        assert!(1 << spine <= self.size + 1, "Right spine is at most log2(n + 1) long");
+    }
+}
+
+/// Every node is a box of its own
+impl<T: Ord + HeapSize> HeapSize for LeftistHeap<T> {
+    fn heap_size(&self) -> usize {
+        self.iter().map(|item| mem::size_of::<LeftistNode<T>>() + item.heap_size()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain<T: Ord>(heap: &mut LeftistHeap<T>) -> Vec<T> {
+        let mut items = vec![];
+        while let Some(item) = heap.pop() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn when_heap_is_created_it_is_empty() {
+        let mut heap = LeftistHeap::<i32>::new();
+
+        assert!(heap.is_empty());
+        assert_eq!(heap.peek(), None);
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    fn test_push_keeps_smallest_on_top() {
+        let mut heap = LeftistHeap::new();
+
+        for item in [5, 3, 8, 1, 9] {
+            heap.push(item);
+            heap.assert_invariants();
+        }
+
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 5);
+    }
+
+    #[test]
+    fn test_pop_returns_items_in_ascending_order() {
+        let mut heap = LeftistHeap::new();
+        for item in (0..100).map(|i| (i * 37) % 100) {
+            heap.push(item);
+        }
+
+        assert_eq!(drain(&mut heap), (0..100).collect::<Vec<i32>>());
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_meld_two_heaps() {
+        let mut heap_a: LeftistHeap<i32> = [4, 8, 2, 6].into_iter().collect();
+        let heap_b: LeftistHeap<i32> = [7, 1, 5].into_iter().collect();
+
+        heap_a.meld(heap_b);
+
+        heap_a.assert_invariants();
+        assert_eq!(heap_a.len(), 7);
+        assert_eq!(drain(&mut heap_a), vec![1, 2, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_from_iter_builds_valid_heap() {
+        let mut heap: LeftistHeap<u32> = (0..1000).rev().collect();
+
+        heap.assert_invariants();
+        assert_eq!(heap.peek(), Some(&0));
+        assert_eq!(drain(&mut heap), (0..1000).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_random_operations_keep_invariants() {
+        let mut heap = LeftistHeap::new();
+        let mut model: Vec<u64> = vec![];
+        let mut state: u64 = 17;
+
+        for _ in 0..1000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let value = (state >> 33) % 500;
+            if (state >> 60).is_multiple_of(3) {
+                model.sort_unstable_by(|a, b| b.cmp(a));
+                assert_eq!(heap.pop(), model.pop());
+            } else {
+                heap.push(value);
+                model.push(value);
+            }
+            heap.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn test_dropping_long_left_spine_does_not_overflow() {
+        // Descending pushes make every new item the root with the old heap on
+        // its left. Linked directly, `push` would check the invariants after
+        // every item.
+        let mut heap = LeftistHeap::new();
+        for item in (0..100_000).rev() {
+            heap.link(item);
+        }
+
+        assert_eq!(heap.len(), 100_000);
+        drop(heap);
+    }
+}
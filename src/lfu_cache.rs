@@ -0,0 +1,339 @@
+//! LFU (least frequently used) Cache implementation
+//! Every entry counts how often it was used. Entries with the same count sit
+//! in one doubly linked list ordered by recency, and the smallest count that
+//! still has entries is tracked. When the cache is full the oldest entry of
+//! that list is evicted, so both `get` and `put` are O(1).
+//! List nodes live in a vector and link to each other by index.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+struct Slot<K, V> {
+    key: K,
+    value: V,
+    frequency: usize,
+    /// Neighbor that was used more recently
+    previous: Option<usize>,
+    /// Neighbor that was used less recently
+    next: Option<usize>,
+}
+
+impl<K, V> Slot<K, V> {
+    fn new(key: K, value: V) -> Self {
+        Slot { key, value, frequency: 1, previous: None, next: None }
+    }
+}
+
+/// Ends of the list of entries with one frequency
+#[derive(Debug, Clone, Copy)]
+struct FrequencyList {
+    newest: usize,
+    oldest: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct LfuCache<K, V> {
+    capacity: usize,
+    slots: Vec<Slot<K, V>>,
+    positions: HashMap<K, usize>,
+    lists: HashMap<usize, FrequencyList>,
+    min_frequency: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        LfuCache {
+            capacity,
+            slots: Vec::with_capacity(capacity),
+            positions: HashMap::with_capacity(capacity),
+            lists: HashMap::new(),
+            min_frequency: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// How often the entry was used, without counting this call
+    pub fn frequency(&self, key: &K) -> Option<usize> {
+        self.positions.get(key).map(|&slot| self.slots[slot].frequency)
+    }
+
+    /// Entries in the order they would be evicted, the next to go first
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        let mut frequencies: Vec<usize> = self.lists.keys().copied().collect();
+        frequencies.sort_unstable();
+        frequencies
+            .into_iter()
+            .flat_map(move |frequency| {
+                let oldest = self.lists[&frequency].oldest;
+                std::iter::successors(Some(oldest), |&slot| self.slots[slot].previous)
+            })
+            .map(|slot| (&self.slots[slot].key, &self.slots[slot].value))
+    }
+
+    /// Returns the value and counts it as a use
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let slot = *self.positions.get(key)?;
+        self.touch(slot);
+        check_invariants!(self);
+        Some(&self.slots[slot].value)
+    }
+
+    /// Inserts or updates the entry and counts it as a use. When a new key
+    /// doesn't fit, the least frequently used entry is evicted first, the
+    /// least recently used one among equals. Returns the old value.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&slot) = self.positions.get(&key) {
+            self.touch(slot);
+            let old = std::mem::replace(&mut self.slots[slot].value, value);
+            check_invariants!(self);
+            return Some(old);
+        }
+        if self.capacity == 0 {
+            return None;
+        }
+
+        let slot = if self.slots.len() == self.capacity {
+            let evicted = self.lists[&self.min_frequency].oldest;
+            self.detach(evicted);
+            self.positions.remove(&self.slots[evicted].key);
+            self.slots[evicted] = Slot::new(key.clone(), value);
+            evicted
+        } else {
+            self.slots.push(Slot::new(key.clone(), value));
+            self.slots.len() - 1
+        };
+        self.positions.insert(key, slot);
+        self.attach(slot);
+        self.min_frequency = 1;
+        check_invariants!(self);
+        None
+    }
+
+    /// Moves the entry from its frequency list to the front of the next one
+    fn touch(&mut self, slot: usize) {
+        let frequency = self.slots[slot].frequency;
+        self.detach(slot);
+        if self.min_frequency == frequency && !self.lists.contains_key(&frequency) {
+            self.min_frequency += 1;
+        }
+        self.slots[slot].frequency += 1;
+        self.attach(slot);
+    }
+
+    /// Unlinks the entry from its list, dropping the list if it became empty
+    fn detach(&mut self, slot: usize) {
+        let Slot { frequency, previous, next, .. } = self.slots[slot];
+        match previous {
+            Some(previous) => self.slots[previous].next = next,
+            None => match next {
+                Some(next) => self.lists.get_mut(&frequency).unwrap().newest = next,
+                None => {
+                    self.lists.remove(&frequency);
+                }
+            },
+        }
+        match next {
+            Some(next) => self.slots[next].previous = previous,
+            None => {
+                if let (Some(previous), Some(list)) = (previous, self.lists.get_mut(&frequency)) {
+                    list.oldest = previous;
+                }
+            }
+        }
+    }
+
+    /// Links the entry in as the newest of its frequency list
+    fn attach(&mut self, slot: usize) {
+        let frequency = self.slots[slot].frequency;
+        self.slots[slot].previous = None;
+        match self.lists.get_mut(&frequency) {
+            Some(list) => {
+                self.slots[slot].next = Some(list.newest);
+                self.slots[list.newest].previous = Some(slot);
+                list.newest = slot;
+            }
+            None => {
+                self.slots[slot].next = None;
+                self.lists.insert(frequency, FrequencyList { newest: slot, oldest: slot });
+            }
+        }
+    }
+}
+
+/// Walks every frequency list, checking its links, its ends and the
+/// frequencies in it, and that the lists and the map cover every slot
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Hash + Eq + Clone, V> Invariants for LfuCache<K, V> {
+    fn assert_invariants(&self) {
+        assert!(self.slots.len() <= self.capacity, "More entries than capacity");
+        assert_eq!(self.positions.len(), self.slots.len(), "map and slots hold different entries");
+        for (key, &slot) in &self.positions {
+            assert!(self.slots[slot].key == *key, "Key is mapped to another entry's slot");
+        }
+        let mut linked = 0;
+        for (&frequency, list) in &self.lists {
+            let mut previous = None;
+            let mut current = Some(list.newest);
+            while let Some(slot) = current {
+                assert_eq!(self.slots[slot].frequency, frequency, "Entry is in the wrong list");
+                assert_eq!(self.slots[slot].previous, previous, "previous doesn't point back");
+                linked += 1;
+                previous = Some(slot);
+                current = self.slots[slot].next;
+            }
+            assert_eq!(previous, Some(list.oldest), "oldest isn't the end of its list");
+        }
+        assert_eq!(linked, self.slots.len(), "lists and slots hold different entries");
+        if let Some(&smallest) = self.lists.keys().min() {
+            assert_eq!(self.min_frequency, smallest, "min_frequency isn't the smallest list");
+        }
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for Slot<K, V> {
+    fn heap_size(&self) -> usize {
+        self.key.heap_size() + self.value.heap_size()
+    }
+}
+
+impl HeapSize for FrequencyList {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for LfuCache<K, V> {
+    fn heap_size(&self) -> usize {
+        self.slots.heap_size() + self.positions.heap_size() + self.lists.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_cache_is_created_it_is_empty() {
+        let mut cache = LfuCache::<&str, i32>::new(2);
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.capacity(), 2);
+        assert_eq!(cache.get(&"a"), None);
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let mut cache = LfuCache::new(2);
+
+        assert_eq!(cache.put("a", 1), None);
+        assert_eq!(cache.put("a", 2), Some(1));
+
+        assert_eq!(cache.get(&"a"), Some(&2));
+        assert_eq!(cache.frequency(&"a"), Some(3));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_evicts_least_frequently_used() {
+        let mut cache = LfuCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.get(&"a");
+
+        cache.put("c", 3);
+
+        assert!(cache.contains_key(&"a"));
+        assert!(!cache.contains_key(&"b"));
+        assert!(cache.contains_key(&"c"));
+    }
+
+    #[test]
+    fn test_ties_evict_least_recently_used() {
+        let mut cache = LfuCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        cache.get(&"a");
+        cache.get(&"b");
+        cache.get(&"c");
+        cache.get(&"a");
+
+        cache.put("d", 4);
+        cache.put("e", 5);
+
+        assert!(!cache.contains_key(&"b"), "b and c both had 2 uses, b was older");
+        assert!(!cache.contains_key(&"d"), "d had a single use");
+        assert!(cache.contains_key(&"a"));
+        assert!(cache.contains_key(&"c"));
+        assert!(cache.contains_key(&"e"));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_zero_capacity_stores_nothing() {
+        let mut cache = LfuCache::new(0);
+
+        cache.put(1, 1);
+
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_matches_naive_model() {
+        // Model keeps (key, value, frequency, last use) and evicts the minimum
+        let mut model: Vec<(u64, u64, usize, usize)> = vec![];
+        let mut cache = LfuCache::new(5);
+        let mut state = 99u64;
+
+        for time in 0..2000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = (state >> 33) % 12;
+            if (state >> 20).is_multiple_of(3) {
+                let expected = model.iter_mut().find(|entry| entry.0 == key).map(|entry| {
+                    entry.2 += 1;
+                    entry.3 = time;
+                    entry.1
+                });
+                assert_eq!(cache.get(&key).copied(), expected);
+            } else {
+                if let Some(entry) = model.iter_mut().find(|entry| entry.0 == key) {
+                    entry.1 = time as u64;
+                    entry.2 += 1;
+                    entry.3 = time;
+                } else {
+                    if model.len() == 5 {
+                        let victim = (0..5).min_by_key(|&i| (model[i].2, model[i].3)).unwrap();
+                        model.remove(victim);
+                    }
+                    model.push((key, time as u64, 1, time));
+                }
+                cache.put(key, time as u64);
+            }
+
+            for entry in &model {
+                assert_eq!(cache.frequency(&entry.0), Some(entry.2));
+            }
+        }
+    }
+}
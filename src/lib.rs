@@ -0,0 +1,87 @@
+//! Data structures implemented from scratch
+//! Every module holds one data structure (or a family of them, like `graph`
+//! and `persistent`) with its tests. The `prelude` re-exports the main type of
+//! every module, so `use data_structures_in_rust::prelude::*;` brings them all
+//! into scope.
+
+pub mod singly_linked_list;
+pub mod hash_map;
+pub mod hasher_trait;
+pub mod stack;
+pub mod queue;
+pub mod radix_trie;
+pub mod aho_corasick;
+pub mod binary_heap;
+pub mod pairing_heap;
+pub mod binomial_heap;
+pub mod interval_heap;
+pub mod indexed_priority_queue;
+pub mod graph;
+pub mod fenwick;
+pub mod sparse_table;
+pub mod kd_tree;
+pub mod quadtree;
+pub mod r_tree;
+pub mod bloom;
+pub mod lfu_cache;
+pub mod arc_cache;
+pub mod bitvec;
+pub mod rank_select;
+pub mod piece_table;
+pub mod multiset;
+pub mod avl_tree;
+pub mod ordered_set;
+pub mod sorted_vec;
+pub mod linked_hash_set;
+pub mod persistent;
+pub mod concurrent_skip_list;
+pub mod ring_channel;
+pub mod arena;
+pub mod slab;
+pub mod object_pool;
+pub mod interner;
+pub mod merkle;
+pub mod patricia_trie;
+pub mod dancing_links;
+pub mod matrix;
+pub mod sparse_matrix;
+pub mod interval_set;
+pub mod range_map;
+pub mod small_vec;
+pub mod bimap;
+pub mod leftist_heap;
+pub mod skew_heap;
+pub mod veb_tree;
+pub mod adaptive_radix_tree;
+pub mod bk_tree;
+pub mod link_cut_tree;
+pub mod viz;
+pub mod sliding_window;
+pub mod collection;
+pub mod error;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "rayon")]
+mod rayon_impls;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod instrumented;
+pub mod heap_size;
+pub mod trace;
+pub mod render;
+pub mod sync;
+pub mod node_alloc;
+pub mod convert;
+pub mod cow;
+pub mod sorting;
+pub mod cursor;
+pub mod undo;
+pub mod persist;
+pub mod growth;
+pub mod invariants;
+pub mod map;
+pub mod key_adapter;
+pub mod workload;
+pub mod prelude;
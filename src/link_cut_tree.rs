@@ -0,0 +1,461 @@
+//! Link-Cut Tree implementation
+//! Forest of rooted trees that can be changed by adding (`link`) and
+//! removing (`cut`) edges, with queries about the path between two nodes,
+//! all in O(log n) amortized. Every tree is split into preferred paths, and
+//! each path is kept in a splay tree ordered by depth. `access(x)` makes the
+//! path from the root to `x` preferred, so it ends up in one splay tree with
+//! `x` at its top. Re-rooting reverses that path, which is a lazy flag on
+//! the splay tree, so any node can become the root of its tree.
+//! Every splay node keeps the aggregate of its subtree in both directions,
+//! so the operation only has to be associative, not commutative.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+/// Node of the splay trees. The parent of a splay root is the path parent:
+/// the node above the top of its path, which doesn't have it as a child.
+#[derive(Debug, Clone)]
+struct SplayNode<T> {
+    value: T,
+    /// Values of the subtree combined from the shallowest to the deepest node
+    aggregate: T,
+    /// Values of the subtree combined from the deepest to the shallowest node
+    reverse_aggregate: T,
+    parent: Option<usize>,
+    /// Shallower and deeper nodes of the same path
+    children: [Option<usize>; 2],
+    /// The subtrees of the children still have to be reversed
+    reversed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkCutTree<T> {
+    nodes: Vec<SplayNode<T>>,
+    operation: fn(&T, &T) -> T,
+}
+
+impl<T: Clone> LinkCutTree<T> {
+    /// Creates a forest of single node trees with the given values, for an
+    /// associative operation used to combine values along paths
+    pub fn new(values: Vec<T>, operation: fn(&T, &T) -> T) -> Self {
+        let mut tree = LinkCutTree { nodes: vec![], operation };
+        for value in values {
+            tree.add_node(value);
+        }
+        tree
+    }
+
+    /// Adds a node that is the only one in its tree and returns its index
+    pub fn add_node(&mut self, value: T) -> usize {
+        self.nodes.push(SplayNode {
+            aggregate: value.clone(),
+            reverse_aggregate: value.clone(),
+            value,
+            parent: None,
+            children: [None, None],
+            reversed: false,
+        });
+        check_invariants!(self);
+        self.nodes.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn value(&self, node: usize) -> &T {
+        self.assert_node(node);
+        &self.nodes[node].value
+    }
+
+    pub fn set_value(&mut self, node: usize, value: T) {
+        self.assert_node(node);
+        self.access(node);
+        self.nodes[node].value = value;
+        self.update(node);
+        check_invariants!(self);
+    }
+
+    /// Root of the tree that contains the node
+    pub fn find_root(&mut self, node: usize) -> usize {
+        self.assert_node(node);
+        self.access(node);
+        let mut root = node;
+        loop {
+            self.push_down(root);
+            match self.nodes[root].children[0] {
+                Some(shallower) => root = shallower,
+                None => break,
+            }
+        }
+        // Splaying keeps repeated calls on a long path fast
+        self.splay(root);
+        root
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find_root(a) == self.find_root(b)
+    }
+
+    /// Makes the node the root of its tree
+    pub fn make_root(&mut self, node: usize) {
+        self.assert_node(node);
+        self.access(node);
+        self.toggle_reversed(node);
+    }
+
+    /// Adds an edge that makes `child` a child of `parent`. Returns false if
+    /// they are already in the same tree. Re-roots the tree of `child` at `child`.
+    pub fn link(&mut self, child: usize, parent: usize) -> bool {
+        self.make_root(child);
+        if self.find_root(parent) == child {
+            return false;
+        }
+        self.nodes[child].parent = Some(parent);
+        check_invariants!(self);
+        true
+    }
+
+    /// Removes the edge between the nodes, leaving `a` as the root of its
+    /// part. Returns false if there is no such edge.
+    pub fn cut(&mut self, a: usize, b: usize) -> bool {
+        self.assert_node(b);
+        self.make_root(a);
+        self.access(b);
+        // The path a..b is in b's splay tree, and the edge exists if a is
+        // right above b in it with nothing in between
+        if self.nodes[b].children[0] != Some(a) {
+            return false;
+        }
+        self.push_down(a);
+        if self.nodes[a].children[1].is_some() {
+            return false;
+        }
+        self.nodes[b].children[0] = None;
+        self.nodes[a].parent = None;
+        self.update(b);
+        check_invariants!(self);
+        true
+    }
+
+    /// Values on the path from `a` to `b` combined in that order, None if
+    /// they are in different trees. Re-roots the tree at `a`.
+    pub fn path_aggregate(&mut self, a: usize, b: usize) -> Option<T> {
+        if !self.connected(a, b) {
+            return None;
+        }
+        self.make_root(a);
+        self.access(b);
+        Some(self.nodes[b].aggregate.clone())
+    }
+
+    /// Makes the path from the root to the node preferred and splays the
+    /// node to the top of its splay tree, which then holds exactly that path
+    fn access(&mut self, node: usize) {
+        let mut below: Option<usize> = None;
+        let mut current = Some(node);
+        while let Some(top) = current {
+            self.splay(top);
+            self.nodes[top].children[1] = below;
+            self.update(top);
+            below = Some(top);
+            current = self.nodes[top].parent;
+        }
+        self.splay(node);
+    }
+
+    fn splay(&mut self, node: usize) {
+        // Pending reversals on the way down have to be applied first
+        let mut path = vec![node];
+        while !self.is_splay_root(*path.last().unwrap()) {
+            path.push(self.nodes[*path.last().unwrap()].parent.unwrap());
+        }
+        for &ancestor in path.iter().rev() {
+            self.push_down(ancestor);
+        }
+
+        while !self.is_splay_root(node) {
+            let parent = self.nodes[node].parent.unwrap();
+            if !self.is_splay_root(parent) {
+                let grandparent = self.nodes[parent].parent.unwrap();
+                let zig_zig = (self.nodes[grandparent].children[0] == Some(parent))
+                    == (self.nodes[parent].children[0] == Some(node));
+                self.rotate(if zig_zig { parent } else { node });
+            }
+            self.rotate(node);
+        }
+    }
+
+    /// Moves the node above its parent, keeping the depth order
+    fn rotate(&mut self, node: usize) {
+        let parent = self.nodes[node].parent.unwrap();
+        let grandparent = self.nodes[parent].parent;
+        let side = usize::from(self.nodes[parent].children[1] == Some(node));
+
+        let moved = self.nodes[node].children[1 - side];
+        self.nodes[parent].children[side] = moved;
+        if let Some(moved) = moved {
+            self.nodes[moved].parent = Some(parent);
+        }
+        self.nodes[node].children[1 - side] = Some(parent);
+
+        if !self.is_splay_root(parent) {
+            let grandparent = grandparent.unwrap();
+            let parent_side = usize::from(self.nodes[grandparent].children[1] == Some(parent));
+            self.nodes[grandparent].children[parent_side] = Some(node);
+        }
+        // A splay root passes its path parent on to the new root
+        self.nodes[node].parent = grandparent;
+        self.nodes[parent].parent = Some(node);
+        self.update(parent);
+        self.update(node);
+    }
+
+    fn is_splay_root(&self, node: usize) -> bool {
+        match self.nodes[node].parent {
+            Some(parent) => !self.nodes[parent].children.contains(&Some(node)),
+            None => true,
+        }
+    }
+
+    /// Reverses the order of the node's subtree. Its own aggregates are
+    /// swapped right away, its children are swapped by `push_down`.
+    fn toggle_reversed(&mut self, node: usize) {
+        let node = &mut self.nodes[node];
+        node.reversed = !node.reversed;
+        std::mem::swap(&mut node.aggregate, &mut node.reverse_aggregate);
+    }
+
+    fn push_down(&mut self, node: usize) {
+        if !self.nodes[node].reversed {
+            return;
+        }
+        self.nodes[node].reversed = false;
+        self.nodes[node].children.swap(0, 1);
+        for child in self.nodes[node].children.into_iter().flatten() {
+            self.toggle_reversed(child);
+        }
+    }
+
+    /// Recomputes the aggregates of the node from its children
+    fn update(&mut self, node: usize) {
+        let [shallower, deeper] = self.nodes[node].children;
+        let combine = self.operation;
+        let mut aggregate = self.nodes[node].value.clone();
+        let mut reverse_aggregate = aggregate.clone();
+        if let Some(shallower) = shallower {
+            aggregate = combine(&self.nodes[shallower].aggregate, &aggregate);
+            reverse_aggregate =
+                combine(&reverse_aggregate, &self.nodes[shallower].reverse_aggregate);
+        }
+        if let Some(deeper) = deeper {
+            aggregate = combine(&aggregate, &self.nodes[deeper].aggregate);
+            reverse_aggregate = combine(&self.nodes[deeper].reverse_aggregate, &reverse_aggregate);
+        }
+        self.nodes[node].aggregate = aggregate;
+        self.nodes[node].reverse_aggregate = reverse_aggregate;
+    }
+
+    fn assert_node(&self, node: usize) {
+        assert!(
+            node < self.nodes.len(),
+            "Node {} out of bounds for {} nodes",
+            node,
+            self.nodes.len()
+        );
+    }
+}
+
+/// Checks every splay child points back at its parent, and no node is the
+/// child of two nodes or of itself
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for LinkCutTree<T> {
+    fn assert_invariants(&self) {
+        let mut is_child = vec![false; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            for child in node.children.into_iter().flatten() {
+                assert_ne!(child, index, "Node is its own child");
+                assert_eq!(self.nodes[child].parent, Some(index), "Child doesn't point back");
+                assert!(!is_child[child], "Node is the child of two nodes");
+                is_child[child] = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum(a: &i64, b: &i64) -> i64 {
+        a + b
+    }
+
+    fn concat(a: &String, b: &String) -> String {
+        format!("{}{}", a, b)
+    }
+
+    /// Forest stored as parent pointers, changed the same way as the link-cut tree
+    struct NaiveForest {
+        parent: Vec<Option<usize>>,
+    }
+
+    impl NaiveForest {
+        fn root(&self, mut node: usize) -> usize {
+            while let Some(parent) = self.parent[node] {
+                node = parent;
+            }
+            node
+        }
+
+        /// Nodes from the node up to its root
+        fn path_to_root(&self, mut node: usize) -> Vec<usize> {
+            let mut path = vec![node];
+            while let Some(parent) = self.parent[node] {
+                path.push(parent);
+                node = parent;
+            }
+            path
+        }
+
+        fn make_root(&mut self, node: usize) {
+            let path = self.path_to_root(node);
+            for pair in path.windows(2) {
+                self.parent[pair[1]] = Some(pair[0]);
+            }
+            self.parent[node] = None;
+        }
+
+        fn path(&self, a: usize, b: usize) -> Option<Vec<usize>> {
+            let up_from_a = self.path_to_root(a);
+            let up_from_b = self.path_to_root(b);
+            let meet = *up_from_a.iter().find(|node| up_from_b.contains(node))?;
+            let mut path: Vec<usize> = up_from_a.into_iter().take_while(|&n| n != meet).collect();
+            path.push(meet);
+            let down: Vec<usize> = up_from_b.into_iter().take_while(|&n| n != meet).collect();
+            path.extend(down.into_iter().rev());
+            Some(path)
+        }
+    }
+
+    #[test]
+    fn when_forest_is_created_every_node_is_its_own_root() {
+        let mut tree = LinkCutTree::new(vec![1, 2, 3], sum);
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.find_root(1), 1);
+        assert!(!tree.connected(0, 2));
+        assert_eq!(tree.path_aggregate(0, 2), None);
+        assert_eq!(tree.path_aggregate(1, 1), Some(2));
+    }
+
+    #[test]
+    fn test_link_and_path_sum() {
+        // 0 - 1 - 2
+        //     |
+        //     3 - 4
+        let mut tree = LinkCutTree::new(vec![1, 10, 100, 1000, 10000], sum);
+        assert!(tree.link(1, 0));
+        assert!(tree.link(2, 1));
+        assert!(tree.link(3, 1));
+        assert!(tree.link(4, 3));
+
+        assert_eq!(tree.find_root(4), 0);
+        assert_eq!(tree.path_aggregate(2, 4), Some(11110));
+        assert_eq!(tree.path_aggregate(0, 2), Some(111));
+        assert!(!tree.link(0, 4), "Linking nodes of the same tree would make a cycle");
+    }
+
+    #[test]
+    fn test_cut_splits_the_tree() {
+        let mut tree = LinkCutTree::new(vec![1, 2, 3, 4], sum);
+        tree.link(1, 0);
+        tree.link(2, 1);
+        tree.link(3, 2);
+
+        assert!(!tree.cut(0, 2), "There is no edge between 0 and 2");
+        assert!(tree.cut(1, 2));
+
+        assert!(tree.connected(0, 1));
+        assert!(tree.connected(2, 3));
+        assert!(!tree.connected(1, 2));
+        assert_eq!(tree.path_aggregate(3, 2), Some(7));
+    }
+
+    #[test]
+    fn test_set_value_updates_path_aggregates() {
+        let mut tree = LinkCutTree::new(vec![1, 1, 1], sum);
+        tree.link(1, 0);
+        tree.link(2, 1);
+
+        tree.set_value(1, 5);
+
+        assert_eq!(*tree.value(1), 5);
+        assert_eq!(tree.path_aggregate(0, 2), Some(7));
+    }
+
+    #[test]
+    fn test_path_aggregate_keeps_order() {
+        let letters = "abcde".chars().map(|c| c.to_string()).collect();
+        let mut tree = LinkCutTree::new(letters, concat);
+        for node in 1..5 {
+            tree.link(node, node - 1);
+        }
+
+        assert_eq!(tree.path_aggregate(0, 4), Some("abcde".to_string()));
+        assert_eq!(tree.path_aggregate(4, 1), Some("edcb".to_string()));
+        assert_eq!(tree.path_aggregate(2, 0), Some("cba".to_string()));
+    }
+
+    #[test]
+    fn test_random_operations_match_naive_forest() {
+        let count = 30;
+        let letters = (0..count).map(|i| ((b'a' + i as u8 % 26) as char).to_string()).collect();
+        let mut tree = LinkCutTree::new(letters, concat);
+        let mut model = NaiveForest { parent: vec![None; count] };
+        let mut state: u64 = 41;
+
+        for _ in 0..3000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let a = (state >> 33) as usize % count;
+            let b = (state >> 45) as usize % count;
+            match (state >> 60) % 4 {
+                0 => {
+                    let linked = model.root(a) != model.root(b);
+                    assert_eq!(tree.link(a, b), linked);
+                    // Link re-roots at a even when it fails
+                    model.make_root(a);
+                    if linked {
+                        model.parent[a] = Some(b);
+                    }
+                }
+                1 => {
+                    let edge = model.parent[a] == Some(b) || model.parent[b] == Some(a);
+                    assert_eq!(tree.cut(a, b), edge);
+                    // Cut re-roots at a even when there is no edge
+                    model.make_root(a);
+                    if edge {
+                        model.parent[b] = None;
+                    }
+                }
+                2 => {
+                    let expected = model.path(a, b).map(|path| {
+                        path.iter().map(|&node| tree.value(node).clone()).collect::<String>()
+                    });
+                    assert_eq!(tree.path_aggregate(a, b), expected);
+                    if expected.is_some() {
+                        model.make_root(a);
+                    }
+                }
+                _ => {
+                    assert_eq!(tree.find_root(a), model.root(a));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Linked Hash Set implementation
+//! Hash set whose elements are also linked in a doubly linked list in the
+//! order they were inserted. The hash map points every element at its list
+//! node, so `insert`, `contains` and `remove` stay O(1) while iteration
+//! follows insertion order. List nodes are kept by a `NodeAlloc`, a `Slab`
+//! unless `new_in` is given another one, and link to each other by handle.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::node_alloc::{ NodeAlloc, NodeHandle };
+use crate::slab::{ Key, Slab };
+
+/// List node, only public so an allocator for it can be named
+#[derive(Debug, Clone)]
+pub struct OrderNode<T, H> {
+    item: T,
+    previous: Option<H>,
+    next: Option<H>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkedHashSet<T, A = Slab<OrderNode<T, Key>>>
+where
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>>,
+{
+    nodes: A,
+    positions: HashMap<T, A::Handle>,
+    front: Option<A::Handle>,
+    back: Option<A::Handle>,
+}
+
+impl<T: Hash + Eq + Clone> LinkedHashSet<T> {
+    pub fn new() -> Self {
+        LinkedHashSet::new_in(Slab::new())
+    }
+}
+
+impl<T, A> LinkedHashSet<T, A>
+where
+    T: Hash + Eq + Clone,
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>>,
+{
+    /// Empty set that keeps its list nodes in `nodes`
+    pub fn new_in(nodes: A) -> Self {
+        LinkedHashSet { nodes, positions: HashMap::new(), front: None, back: None }
+    }
+
+    /// The allocator holding the list nodes
+    pub fn nodes(&self) -> &A {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.positions.contains_key(item)
+    }
+
+    /// Adds the element at the back. Returns false and keeps its old
+    /// position if it was already present.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.contains(&item) {
+            return false;
+        }
+        let node = OrderNode { item: item.clone(), previous: self.back, next: None };
+        let handle = self.nodes.alloc(node);
+        match self.back {
+            Some(back) => self.node_mut(back).next = Some(handle),
+            None => self.front = Some(handle),
+        }
+        self.back = Some(handle);
+        self.positions.insert(item, handle);
+        check_invariants!(self);
+        true
+    }
+
+    /// Removes the element in O(1). Returns false if it wasn't present.
+    pub fn remove(&mut self, item: &T) -> bool {
+        self.take(item).is_some()
+    }
+
+    /// Moves the element to the back, as if it was just inserted.
+    /// Returns false if it wasn't present.
+    pub fn move_to_back(&mut self, item: &T) -> bool {
+        match self.take(item) {
+            Some(item) => self.insert(item),
+            None => false,
+        }
+    }
+
+    /// Oldest element
+    pub fn front(&self) -> Option<&T> {
+        self.front.map(|handle| &self.node(handle).item)
+    }
+
+    /// Newest element
+    pub fn back(&self) -> Option<&T> {
+        self.back.map(|handle| &self.node(handle).item)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let item = self.node(self.front?).item.clone();
+        self.take(&item)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let item = self.node(self.back?).item.clone();
+        self.take(&item)
+    }
+
+    /// Frees every node, the allocator is kept
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    /// Elements in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut current = self.front;
+        std::iter::from_fn(move || {
+            let node = self.node(current?);
+            current = node.next;
+            Some(&node.item)
+        })
+    }
+
+    fn take(&mut self, item: &T) -> Option<T> {
+        let handle = self.positions.remove(item)?;
+        // SAFETY: Every position is a handle this set allocated and not freed yet
+        let node = unsafe { self.nodes.free(handle) }.expect("Every position has a node");
+        match node.previous {
+            Some(previous) => self.node_mut(previous).next = node.next,
+            None => self.front = node.next,
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).previous = node.previous,
+            None => self.back = node.previous,
+        }
+        check_invariants!(self);
+        Some(node.item)
+    }
+
+    /// `handle` has to be linked into this set
+    fn node(&self, handle: A::Handle) -> &OrderNode<T, A::Handle> {
+        // SAFETY: Linked nodes were allocated by this set and are not freed yet
+        unsafe { self.nodes.get(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// `handle` has to be linked into this set
+    fn node_mut(&mut self, handle: A::Handle) -> &mut OrderNode<T, A::Handle> {
+        // SAFETY: As in `node`
+        unsafe { self.nodes.get_mut(handle) }.expect("Linked nodes are never freed")
+    }
+}
+
+impl<T, A> Default for LinkedHashSet<T, A>
+where
+    T: Hash + Eq + Clone,
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>> + Default,
+{
+    fn default() -> Self {
+        LinkedHashSet::new_in(A::default())
+    }
+}
+
+impl<T, A> FromIterator<T> for LinkedHashSet<T, A>
+where
+    T: Hash + Eq + Clone,
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = LinkedHashSet::default();
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<T, A> Collection for LinkedHashSet<T, A>
+where
+    T: Hash + Eq + Clone,
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>>,
+{
+    type Item = T;
+
+    fn len(&self) -> usize {
+        LinkedHashSet::len(self)
+    }
+
+    fn clear(&mut self) {
+        LinkedHashSet::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        LinkedHashSet::contains(self, item)
+    }
+}
+
+/// Frees the nodes through the allocator, which may not own them
+impl<T, A> Drop for LinkedHashSet<T, A>
+where
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>>,
+{
+    fn drop(&mut self) {
+        for (_, handle) in self.positions.drain() {
+            // SAFETY: Every position is a handle this set allocated and not freed yet
+            unsafe { self.nodes.free(handle) };
+        }
+    }
+}
+
+/// Walks the list from the front, checking the links both ways and that the
+/// map points every element at its own node
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, A> Invariants for LinkedHashSet<T, A>
+where
+    T: Hash + Eq + Clone,
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>>,
+{
+    fn assert_invariants(&self) {
+        let mut linked = 0;
+        let mut previous = None;
+        let mut current = self.front;
+        while let Some(handle) = current {
+            let node = self.node(handle);
+            assert_eq!(node.previous, previous, "previous doesn't point back");
+            let position = self.positions.get(&node.item);
+            assert_eq!(position, Some(&handle), "Element isn't mapped to its node");
+            linked += 1;
+            previous = Some(handle);
+            current = node.next;
+        }
+        assert_eq!(self.back, previous, "back isn't the last node");
+        assert_eq!(linked, self.positions.len(), "map and list hold different elements");
+    }
+}
+
+impl<T: HeapSize, H> HeapSize for OrderNode<T, H> {
+    fn heap_size(&self) -> usize {
+        self.item.heap_size()
+    }
+}
+
+impl<T: HeapSize, A> HeapSize for LinkedHashSet<T, A>
+where
+    A: NodeAlloc<OrderNode<T, <A as NodeHandle>::Handle>> + HeapSize,
+    A::Handle: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.nodes.heap_size() + self.positions.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(set: &LinkedHashSet<&'static str>) -> Vec<&'static str> {
+        set.iter().copied().collect()
+    }
+
+    #[test]
+    fn when_set_is_created_it_is_empty() {
+        let set = LinkedHashSet::<i32>::new();
+
+        assert!(set.is_empty());
+        assert_eq!(set.front(), None);
+        assert_eq!(set.iter().next(), None);
+    }
+
+    #[test]
+    fn test_iterates_in_insertion_order() {
+        let set: LinkedHashSet<&str> = ["pear", "apple", "fig", "apple"].into_iter().collect();
+
+        assert_eq!(items(&set), vec!["pear", "apple", "fig"]);
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_remove_keeps_order_of_the_rest() {
+        let mut set: LinkedHashSet<&str> = ["a", "b", "c", "d"].into_iter().collect();
+
+        assert!(set.remove(&"b"));
+        assert!(set.remove(&"d"));
+        assert!(!set.remove(&"d"));
+
+        assert_eq!(items(&set), vec!["a", "c"]);
+        assert_eq!(set.back(), Some(&"c"));
+    }
+
+    #[test]
+    fn test_reinserting_removed_item_goes_to_the_back() {
+        let mut set: LinkedHashSet<&str> = ["a", "b", "c"].into_iter().collect();
+
+        set.remove(&"a");
+        set.insert("a");
+        set.move_to_back(&"b");
+
+        assert_eq!(items(&set), vec!["c", "a", "b"]);
+        assert_eq!(set.nodes.capacity(), 3, "Freed nodes are reused");
+    }
+
+    #[test]
+    fn test_pop_front_and_back() {
+        let mut set: LinkedHashSet<i32> = (1..=3).collect();
+
+        assert_eq!(set.pop_front(), Some(1));
+        assert_eq!(set.pop_back(), Some(3));
+        assert_eq!(set.pop_back(), Some(2));
+        assert_eq!(set.pop_front(), None);
+        assert!(set.is_empty());
+    }
+}
@@ -1,10 +1,428 @@
-mod singly_linked_list;
-mod hash_map;
-mod hasher_trait;
-mod stack;
-mod queue;
+//! Playground for the crate's structures
+//! Reads commands like `list append A`, `map insert k v` or `stack pop` from
+//! standard input, runs them on a linked list, hash map, stack and queue, and
+//! prints the structure after every command. `help` lists the commands.
+//! With `trace on` every command also narrates its steps, like the bucket a
+//! key hashes to or the rotations that keep the AVL tree balanced. Changes
+//! to the list, map and stack can be taken back with `undo` and `redo`.
+//! `save` and `load` keep the list, map, stack and queue in a file.
+
+use std::fs::File;
+use std::io::{ self, BufRead, BufReader, BufWriter, Write };
+
+use data_structures_in_rust::prelude::*;
+use data_structures_in_rust::persist::Persist;
+use data_structures_in_rust::trace;
+use data_structures_in_rust::undo::{ ListEdit, MapEdit, StackEdit };
+
+const HELP: &str = "\
+list append|prepend|remove <item>    list first|last       delete the first or last item
+list after|before <item> <given>     insert next to the first <given>
+map insert <key> <value>             map get|remove <key>
+stack push <number>                  stack pop|peek
+queue add <number>                   queue remove|peek
+tree insert|remove <number>
+show [list|map|stack|queue|tree]     clear <structure>
+undo|redo list|map|stack             take back the last change or make it again
+save|load <file>                     every structure but the tree
+trace on|off                         narrate the steps of every command
+help                                 quit";
+
+const STRUCTURES: [&str; 5] = ["list", "map", "stack", "queue", "tree"];
+
+#[derive(Default)]
+struct Playground {
+    list: History<SinglyLinkedList<String>, ListEdit<String>>,
+    map: History<HashMap<String, String>, MapEdit<String, String>>,
+    stack: History<Stack<i64>, StackEdit<i64>>,
+    queue: Queue<i64>,
+    tree: OrderedSet<i64>,
+    trace: bool,
+}
+
+impl Playground {
+    /// Runs one command and returns what to print, the structure it touched
+    /// is shown after the result of the command and the traced steps before it
+    fn execute(&mut self, line: &str) -> Result<String, String> {
+        if !self.trace {
+            return self.run(line);
+        }
+        let (output, events) = trace::record(|| self.run(line));
+        let steps: Vec<String> = events.iter().map(|event| format!("  . {}", event)).collect();
+        match output {
+            Ok(output) if !steps.is_empty() => Ok(format!("{}\n{}", steps.join("\n"), output)),
+            output => output,
+        }
+    }
+
+    fn run(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let output = match words.as_slice() {
+            [] => String::new(),
+            ["help"] => HELP.to_string(),
+            ["trace", "on"] => {
+                self.trace = true;
+                "Tracing every step".to_string()
+            }
+            ["trace", "off"] => {
+                self.trace = false;
+                "Tracing stopped".to_string()
+            }
+            ["show"] => STRUCTURES
+                .iter()
+                .map(|name| self.show(name))
+                .collect::<Result<Vec<_>, _>>()?
+                .join("\n"),
+            ["show", name] => self.show(name)?,
+            ["clear", name] => {
+                match *name {
+                    "list" => self.list.reset(SinglyLinkedList::new()),
+                    "map" => self.map.reset(HashMap::new()),
+                    "stack" => self.stack.reset(Stack::new()),
+                    "queue" => Collection::clear(&mut self.queue),
+                    "tree" => self.tree = OrderedSet::new(),
+                    _ => return Err(format!("Unknown structure '{}'", name)),
+                }
+                self.show(name)?
+            }
+            ["save", path] => {
+                self.save(path).map_err(|err| format!("Failed to save: {}", err))?;
+                format!("Saved to {}", path)
+            }
+            ["load", path] => {
+                self.load(path).map_err(|err| format!("Failed to load: {}", err))?;
+                ["list", "map", "stack", "queue"]
+                    .iter()
+                    .map(|name| self.show(name))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join("\n")
+            }
+            [action @ ("undo" | "redo"), name] => {
+                let redo = *action == "redo";
+                let done = match *name {
+                    "list" => step(&mut self.list, redo),
+                    "map" => step(&mut self.map, redo),
+                    "stack" => step(&mut self.stack, redo),
+                    _ => return Err(format!("No undo for '{}', try list, map or stack", name)),
+                };
+                if !done {
+                    return Err(format!("Nothing to {}", action));
+                }
+                self.show(name)?
+            }
+            ["list", command @ ..] => {
+                let result = self.list_command(command)?;
+                Self::with_result(result, self.show("list")?)
+            }
+            ["map", command @ ..] => {
+                let result = self.map_command(command)?;
+                Self::with_result(result, self.show("map")?)
+            }
+            ["stack", command @ ..] => {
+                let result = self.stack_command(command)?;
+                Self::with_result(result, self.show("stack")?)
+            }
+            ["queue", command @ ..] => {
+                let result = self.queue_command(command)?;
+                Self::with_result(result, self.show("queue")?)
+            }
+            ["tree", command @ ..] => {
+                let result = self.tree_command(command)?;
+                Self::with_result(result, self.show("tree")?)
+            }
+            _ => return Err(format!("Unknown command '{}', try 'help'", line.trim())),
+        };
+        Ok(output)
+    }
+
+    fn with_result(result: Option<String>, shown: String) -> String {
+        match result {
+            Some(result) => format!("{}\n{}", result, shown),
+            None => shown,
+        }
+    }
+
+    /// Every change goes through the list's history, so it can be undone
+    fn list_command(&mut self, command: &[&str]) -> Result<Option<String>, String> {
+        let edit = match command {
+            ["append", item] => ListEdit::Append(item.to_string()),
+            ["prepend", item] => ListEdit::Prepend(item.to_string()),
+            ["remove", item] => ListEdit::Remove(self.position(item)?),
+            ["after", item, given] => ListEdit::Insert(self.position(given)? + 1, item.to_string()),
+            ["before", item, given] => ListEdit::Insert(self.position(given)?, item.to_string()),
+            ["first"] => ListEdit::DeleteFirst,
+            ["last"] => ListEdit::DeleteLast,
+            _ => return Err(usage("list")),
+        };
+        // Deleting from an end is undone by putting the item back, that's
+        // the only inverse that carries an item worth printing
+        match self.list.apply(edit) {
+            Some(ListEdit::Prepend(item) | ListEdit::Append(item)) => Ok(Some(item.clone())),
+            Some(_) => Ok(None),
+            None => Err(error(Error::EmptyCollection)),
+        }
+    }
+
+    /// Index of the first node holding `item`
+    fn position(&self, item: &str) -> Result<usize, String> {
+        if self.list.is_empty() {
+            return Err(error(Error::EmptyCollection));
+        }
+        self.list.iter().position(|data| *data == item).ok_or_else(|| error(Error::NotFound))
+    }
+
+    fn map_command(&mut self, command: &[&str]) -> Result<Option<String>, String> {
+        let previous = match command {
+            ["insert", key, value] => {
+                match self.map.apply(MapEdit::Insert(key.to_string(), value.to_string())) {
+                    Some(MapEdit::Insert(_, old)) => Some(old.clone()),
+                    _ => None,
+                }
+            }
+            ["get", key] => self.map.get(key.to_string()),
+            ["remove", key] => match self.map.apply(MapEdit::Remove(key.to_string())) {
+                Some(MapEdit::Insert(_, value)) => Some(value.clone()),
+                _ => None,
+            },
+            _ => return Err(usage("map")),
+        };
+        Ok(Some(previous.unwrap_or_else(|| "(none)".to_string())))
+    }
+
+    fn stack_command(&mut self, command: &[&str]) -> Result<Option<String>, String> {
+        match command {
+            ["push", item] => {
+                self.stack.apply(StackEdit::Push(number(item)?));
+                Ok(None)
+            }
+            ["pop"] => match self.stack.apply(StackEdit::Pop) {
+                Some(StackEdit::Push(item)) => Ok(Some(item.to_string())),
+                _ => Err(error(Error::EmptyCollection)),
+            },
+            ["peek"] => self.stack.try_peek().map(|item| Some(item.to_string())).map_err(error),
+            _ => Err(usage("stack")),
+        }
+    }
+
+    fn queue_command(&mut self, command: &[&str]) -> Result<Option<String>, String> {
+        match command {
+            ["add", item] => {
+                self.queue.add(number(item)?);
+                Ok(None)
+            }
+            ["remove"] => self.queue.try_remove().map(|item| Some(item.to_string())).map_err(error),
+            ["peek"] => self.queue.try_peek().map(|item| Some(item.to_string())).map_err(error),
+            _ => Err(usage("queue")),
+        }
+    }
+
+    fn tree_command(&mut self, command: &[&str]) -> Result<Option<String>, String> {
+        match command {
+            ["insert", item] => {
+                let added = self.tree.insert(number(item)?);
+                Ok((!added).then(|| "(already there)".to_string()))
+            }
+            ["remove", item] => {
+                let removed = self.tree.remove(&number(item)?);
+                Ok((!removed).then(|| "(not found)".to_string()))
+            }
+            _ => Err(usage("tree")),
+        }
+    }
+
+    fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.list.save_to(&mut file)?;
+        self.map.save_to(&mut file)?;
+        self.stack.save_to(&mut file)?;
+        self.queue.save_to(&mut file)
+    }
+
+    /// Nothing changes unless the whole file loads
+    fn load(&mut self, path: &str) -> io::Result<()> {
+        let mut file = BufReader::new(File::open(path)?);
+        let list = SinglyLinkedList::load_from(&mut file)?;
+        let map = HashMap::load_from(&mut file)?;
+        let stack = Stack::load_from(&mut file)?;
+        self.queue = Queue::load_from(&mut file)?;
+        self.list.reset(list);
+        self.map.reset(map);
+        self.stack.reset(stack);
+        Ok(())
+    }
+
+    fn show(&self, name: &str) -> Result<String, String> {
+        let shown = match name {
+            "list" => {
+                let items: Vec<&str> = self.list.iter().map(String::as_str).collect();
+                format!("list:  [{}]", items.join(" -> "))
+            }
+            "map" => {
+                // Bucket order depends on the hashes, sorting keeps the output readable
+                let mut pairs: Vec<String> = self.map
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                pairs.sort();
+                format!("map:   {{{}}}", pairs.join(", "))
+            }
+            "stack" => format!("stack: {} <- top", items(self.stack.iter())),
+            "queue" => format!("queue: front <- {}", items(self.queue.iter())),
+            "tree" => format!("tree:\n{}", self.tree.render()),
+            _ => return Err(format!("Unknown structure '{}'", name)),
+        };
+        Ok(shown)
+    }
+}
+
+fn step<S, E: Edit<S>>(history: &mut History<S, E>, redo: bool) -> bool {
+    if redo {
+        history.redo()
+    } else {
+        history.undo()
+    }
+}
+
+fn items<'a>(items: impl Iterator<Item = &'a i64>) -> String {
+    let items: Vec<String> = items.map(i64::to_string).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn number(item: &str) -> Result<i64, String> {
+    item.parse().map_err(|_| format!("'{}' is not a number", item))
+}
+
+fn error(error: Error) -> String {
+    error.to_string()
+}
+
+fn usage(name: &str) -> String {
+    let lines: Vec<&str> = HELP.lines()
+        .filter(|line| line.starts_with(name))
+        .collect();
+    format!("Usage:\n{}", lines.join("\n"))
+}
 
 fn main() {
-    singly_linked_list::run();
-    hash_map::run();
+    let mut playground = Playground::default();
+    let stdin = io::stdin();
+    println!("Data structures playground, type 'help' for the commands");
+
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Failed to write the prompt");
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Failed to read the command: {}", err);
+                break;
+            }
+        }
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+
+        match playground.execute(&line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_commands_print_the_list() {
+        let mut playground = Playground::default();
+
+        playground.execute("list append B").unwrap();
+        playground.execute("list prepend A").unwrap();
+        let output = playground.execute("list after C B").unwrap();
+
+        assert_eq!(output, "list:  [A -> B -> C]");
+        assert_eq!(playground.execute("list last").unwrap(), "C\nlist:  [A -> B]");
+    }
+
+    #[test]
+    fn test_map_stack_and_queue_commands() {
+        let mut playground = Playground::default();
+
+        playground.execute("map insert k v").unwrap();
+        playground.execute("stack push 1").unwrap();
+        playground.execute("stack push 2").unwrap();
+        playground.execute("queue add 1").unwrap();
+        playground.execute("queue add 2").unwrap();
+
+        assert_eq!(playground.execute("map get k").unwrap(), "v\nmap:   {k: v}");
+        assert_eq!(playground.execute("stack pop").unwrap(), "2\nstack: [1] <- top");
+        assert_eq!(playground.execute("queue remove").unwrap(), "1\nqueue: front <- [2]");
+    }
+
+    #[test]
+    fn test_trace_narrates_the_steps() {
+        let mut playground = Playground::default();
+        playground.execute("trace on").unwrap();
+        playground.execute("tree insert 1").unwrap();
+        playground.execute("tree insert 2").unwrap();
+
+        let output = playground.execute("tree insert 3").unwrap();
+
+        assert_eq!(
+            output,
+            "  . rotate left, subtree height is now 2\ntree:\n2\n|-- 1\n`-- 3"
+        );
+    }
+
+    #[test]
+    fn test_bad_commands_are_errors() {
+        let mut playground = Playground::default();
+
+        assert_eq!(playground.execute("stack pop"), Err("the collection is empty".to_string()));
+        assert!(playground.execute("stack push x").is_err());
+        assert!(playground.execute("heap push 1").is_err());
+        assert!(playground.execute("list insert").unwrap_err().starts_with("Usage:"));
+        assert_eq!(playground.execute("   "), Ok(String::new()));
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut playground = Playground::default();
+        playground.execute("list append A").unwrap();
+        playground.execute("list append B").unwrap();
+        playground.execute("list before C B").unwrap();
+        playground.execute("map insert k v").unwrap();
+        playground.execute("map insert k w").unwrap();
+
+        assert_eq!(playground.execute("undo list").unwrap(), "list:  [A -> B]");
+        assert_eq!(playground.execute("redo list").unwrap(), "list:  [A -> C -> B]");
+        assert_eq!(playground.execute("undo map").unwrap(), "map:   {k: v}");
+        assert_eq!(playground.execute("redo stack"), Err("Nothing to redo".to_string()));
+        assert!(playground.execute("undo queue").is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("playground-{}.dsr", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut playground = Playground::default();
+        playground.execute("list append A").unwrap();
+        playground.execute("map insert k v").unwrap();
+        playground.execute("queue add 3").unwrap();
+
+        playground.execute(&format!("save {}", path)).unwrap();
+        let output = Playground::default().execute(&format!("load {}", path));
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            output.unwrap(),
+            "list:  [A]\nmap:   {k: v}\nstack: [] <- top\nqueue: front <- [3]"
+        );
+        assert!(playground.execute("load /nonexistent/file").unwrap_err().starts_with("Failed"));
+    }
 }
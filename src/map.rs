@@ -0,0 +1,129 @@
+//! Map trait
+//! The operations every key-value structure has, so code can be written once
+//! and run against any of them: the hash map, the AVL tree, the concurrent
+//! skip list and the std maps. Values and pairs come back cloned rather than
+//! borrowed, because the skip list can't lend out a reference into a node
+//! another thread may be unlinking. The structures keep their own inherent
+//! methods, which win over these when both are in scope.
+
+use std::collections::{ BTreeMap, HashMap as StdHashMap };
+use std::hash::{ BuildHasher, Hash };
+
+pub trait Map<K, V> {
+    /// Returns the old value if the key was present
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+
+    fn get(&self, key: &K) -> Option<V>;
+
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pairs in the order the structure keeps them, which is ascending key
+    /// order for the ordered ones and arbitrary for the hash maps
+    fn iter(&self) -> impl Iterator<Item = (K, V)> + '_;
+}
+
+impl<K: Hash + Eq + Clone, V: Clone, S: BuildHasher> Map<K, V> for StdHashMap<K, V, S> {
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        StdHashMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        StdHashMap::get(self, key).cloned()
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        StdHashMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        StdHashMap::len(self)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        StdHashMap::iter(self).map(|(key, value)| (key.clone(), value.clone()))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Map<K, V> for BTreeMap<K, V> {
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        BTreeMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        BTreeMap::get(self, key).cloned()
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        BTreeMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        BTreeMap::iter(self).map(|(key, value)| (key.clone(), value.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl_tree::AvlTreeMap;
+    use crate::concurrent_skip_list::ConcurrentSkipListMap;
+    use crate::hash_map::HashMap;
+
+    /// Runs the same edits on any map and returns its pairs sorted
+    fn edit<M: Map<u32, char>>(mut map: M) -> Vec<(u32, char)> {
+        assert_eq!(map.insert(1, 'a'), None);
+        assert_eq!(map.insert(2, 'b'), None);
+        assert_eq!(map.insert(1, 'c'), Some('a'));
+        assert_eq!(map.remove(&2), Some('b'));
+        assert_eq!(map.remove(&2), None);
+        map.insert(3, 'd');
+
+        assert_eq!(map.get(&1), Some('c'));
+        assert!(map.contains_key(&3) && !map.contains_key(&2));
+        assert_eq!(map.len(), 2);
+        let mut pairs: Vec<(u32, char)> = map.iter().collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_every_map_behaves_the_same() {
+        let expected = vec![(1, 'c'), (3, 'd')];
+
+        assert_eq!(edit(HashMap::new()), expected);
+        assert_eq!(edit(AvlTreeMap::new()), expected);
+        assert_eq!(edit(ConcurrentSkipListMap::new()), expected);
+        assert_eq!(edit(StdHashMap::new()), expected);
+        assert_eq!(edit(BTreeMap::new()), expected);
+    }
+
+    #[test]
+    fn test_ordered_maps_iterate_in_key_order() {
+        let mut avl = AvlTreeMap::new();
+        let mut skip_list = ConcurrentSkipListMap::new();
+
+        for key in [5, 1, 4, 2, 3] {
+            Map::insert(&mut avl, key, key * 10);
+            Map::insert(&mut skip_list, key, key * 10);
+        }
+
+        let avl_keys: Vec<i32> = Map::iter(&avl).map(|(key, _)| key).collect();
+        let skip_list_keys: Vec<i32> = Map::iter(&skip_list).map(|(key, _)| key).collect();
+        assert_eq!(avl_keys, vec![1, 2, 3, 4, 5]);
+        assert_eq!(skip_list_keys, avl_keys);
+    }
+}
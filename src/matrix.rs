@@ -0,0 +1,278 @@
+//! Dense Matrix implementation
+//! Fixed-size 2D grid stored row by row in a single vector, so a row is a
+//! contiguous slice and cell (row, column) is at `row * columns + column`.
+//! Indexed with a `(row, column)` tuple.
+
+use std::fmt::{ self, Display };
+use std::ops::{ Index, IndexMut };
+
+use crate::heap_size::HeapSize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Matrix<T> {
+    rows: usize,
+    columns: usize,
+    data: Vec<T>,
+}
+
+impl<T> Matrix<T> {
+    /// Creates a matrix with every cell set to `value`
+    pub fn new(rows: usize, columns: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Matrix { rows, columns, data: vec![value; rows * columns] }
+    }
+
+    /// Creates a matrix with cell (row, column) set to `f(row, column)`
+    pub fn from_fn(rows: usize, columns: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let data = (0..rows * columns).map(|index| f(index / columns, index % columns)).collect();
+        Matrix { rows, columns, data }
+    }
+
+    /// Uses the vector as the cells in row-major order
+    pub fn from_vec(rows: usize, columns: usize, data: Vec<T>) -> Self {
+        assert_eq!(
+            data.len(),
+            rows * columns,
+            "A {}x{} matrix needs {} values",
+            rows,
+            columns,
+            rows * columns
+        );
+        Matrix { rows, columns, data }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.columns
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&T> {
+        self.offset(row, column).map(|offset| &self.data[offset])
+    }
+
+    pub fn get_mut(&mut self, row: usize, column: usize) -> Option<&mut T> {
+        self.offset(row, column).map(|offset| &mut self.data[offset])
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        self.assert_row(row);
+        &self.data[row * self.columns..(row + 1) * self.columns]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut [T] {
+        self.assert_row(row);
+        &mut self.data[row * self.columns..(row + 1) * self.columns]
+    }
+
+    /// Cells of one column from top to bottom
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(
+            column < self.columns,
+            "Column {} out of bounds for {} columns",
+            column,
+            self.columns
+        );
+        self.data.iter().skip(column).step_by(self.columns)
+    }
+
+    /// Rows as slices from top to bottom
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.rows).map(|row| self.row(row))
+    }
+
+    /// Columns from left to right, each one an iterator over its cells
+    pub fn iter_columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.columns).map(|column| self.column(column))
+    }
+
+    /// All cells in row-major order
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn transpose(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        Matrix::from_fn(self.columns, self.rows, |row, column| self[(column, row)].clone())
+    }
+
+    /// New matrix of the same shape with `f` applied to every cell
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Matrix<U> {
+        Matrix { rows: self.rows, columns: self.columns, data: self.data.iter().map(f).collect() }
+    }
+
+    /// Combines cells at the same position of two matrices of the same shape
+    pub fn zip_with<U, R>(&self, other: &Matrix<U>, mut f: impl FnMut(&T, &U) -> R) -> Matrix<R> {
+        assert!(
+            self.rows == other.rows && self.columns == other.columns,
+            "Matrices of shape {}x{} and {}x{} can't be combined",
+            self.rows,
+            self.columns,
+            other.rows,
+            other.columns
+        );
+        let data = self.data.iter().zip(&other.data).map(|(a, b)| f(a, b)).collect();
+        Matrix { rows: self.rows, columns: self.columns, data }
+    }
+
+    fn offset(&self, row: usize, column: usize) -> Option<usize> {
+        (row < self.rows && column < self.columns).then_some(row * self.columns + column)
+    }
+
+    fn assert_row(&self, row: usize) {
+        assert!(row < self.rows, "Row {} out of bounds for {} rows", row, self.rows);
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &T {
+        match self.offset(row, column) {
+            Some(offset) => &self.data[offset],
+            None => panic!(
+                "Index ({}, {}) out of bounds for {}x{} matrix",
+                row,
+                column,
+                self.rows,
+                self.columns
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut T {
+        match self.offset(row, column) {
+            Some(offset) => &mut self.data[offset],
+            None => panic!(
+                "Index ({}, {}) out of bounds for {}x{} matrix",
+                row,
+                column,
+                self.rows,
+                self.columns
+            ),
+        }
+    }
+}
+
+/// One line per row with the cells separated by spaces
+impl<T: Display> Display for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.iter_rows() {
+            let cells: Vec<String> = row.iter().map(|cell| cell.to_string()).collect();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: HeapSize> HeapSize for Matrix<T> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting(rows: usize, columns: usize) -> Matrix<usize> {
+        Matrix::from_fn(rows, columns, |row, column| row * columns + column)
+    }
+
+    #[test]
+    fn when_matrix_has_no_cells_it_is_empty() {
+        let matrix = Matrix::new(0, 3, 0);
+
+        assert!(matrix.is_empty());
+        assert_eq!(matrix.iter_rows().count(), 0);
+        assert_eq!(matrix.get(0, 0), None);
+    }
+
+    #[test]
+    fn test_indexing() {
+        let mut matrix = Matrix::new(2, 3, 0);
+
+        matrix[(1, 2)] = 7;
+        *matrix.get_mut(0, 1).unwrap() = 4;
+
+        assert_eq!(matrix[(1, 2)], 7);
+        assert_eq!(matrix.get(0, 1), Some(&4));
+        assert_eq!(matrix.get(2, 0), None);
+        assert_eq!(matrix.as_slice(), &[0, 4, 0, 0, 0, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index (0, 3) out of bounds for 2x3 matrix")]
+    fn test_index_out_of_bounds_panics() {
+        let matrix = Matrix::new(2, 3, 0);
+        let _ = matrix[(0, 3)];
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let mut matrix = counting(3, 2);
+
+        matrix.row_mut(0)[1] = 10;
+
+        assert_eq!(matrix.row(0), &[0, 10]);
+        assert_eq!(matrix.column(1).copied().collect::<Vec<_>>(), vec![10, 3, 5]);
+        let columns: Vec<Vec<usize>> =
+            matrix.iter_columns().map(|column| column.copied().collect()).collect();
+        assert_eq!(columns, vec![vec![0, 2, 4], vec![10, 3, 5]]);
+    }
+
+    #[test]
+    fn test_transpose() {
+        let matrix = counting(2, 3);
+
+        let transposed = matrix.transpose();
+
+        assert_eq!((transposed.rows(), transposed.columns()), (3, 2));
+        assert_eq!(transposed.as_slice(), &[0, 3, 1, 4, 2, 5]);
+        assert_eq!(transposed.transpose(), matrix);
+    }
+
+    #[test]
+    fn test_map_and_zip_with() {
+        let matrix = counting(2, 2);
+
+        let doubled = matrix.map(|cell| cell * 2);
+        let sum = matrix.zip_with(&doubled, |a, b| a + b);
+
+        assert_eq!(sum.into_vec(), vec![0, 3, 6, 9]);
+        assert_eq!(matrix.map(|cell| cell % 2 == 0).row(1), &[true, false]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Matrices of shape 2x2 and 2x3 can't be combined")]
+    fn test_zip_with_different_shapes_panics() {
+        counting(2, 2).zip_with(&counting(2, 3), |a, b| a + b);
+    }
+
+    #[test]
+    fn test_display() {
+        let matrix = Matrix::from_vec(2, 2, vec![1, 2, 3, 4]);
+
+        assert_eq!(matrix.to_string(), "1 2\n3 4\n");
+    }
+}
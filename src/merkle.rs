@@ -0,0 +1,246 @@
+//! Merkle Tree implementation
+//! Binary tree of hashes over a list of data blocks. Every leaf is the hash
+//! of one block and every inner node the hash of its two children, so the
+//! root hash changes if any block changes. An inclusion proof for a block is
+//! the list of sibling hashes on the path from its leaf to the root: with
+//! only those O(log n) hashes anyone can recompute the root.
+//! When a level has an odd number of nodes, the last one moves up unchanged.
+//! The hash function is pluggable through the `MerkleHasher` trait.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::Hasher;
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+pub trait MerkleHasher {
+    type Hash: Clone + PartialEq + Debug;
+
+    fn hash_leaf(&self, data: &[u8]) -> Self::Hash;
+
+    fn hash_nodes(&self, left: &Self::Hash, right: &Self::Hash) -> Self::Hash;
+}
+
+/// SipHash from the standard library. Quick and deterministic, but not a
+/// cryptographic hash, so it doesn't protect against forged blocks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SipMerkleHasher;
+
+impl MerkleHasher for SipMerkleHasher {
+    type Hash = u64;
+
+    fn hash_leaf(&self, data: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        // Different prefixes keep a leaf from ever hashing like an inner node
+        hasher.write_u8(0);
+        hasher.write(data);
+        hasher.finish()
+    }
+
+    fn hash_nodes(&self, left: &u64, right: &u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u8(1);
+        hasher.write_u64(*left);
+        hasher.write_u64(*right);
+        hasher.finish()
+    }
+}
+
+/// One step from a node to its parent
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofStep<H> {
+    pub sibling: H,
+    /// True if the sibling is the left child
+    pub sibling_is_left: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleProof<H> {
+    pub leaf_index: usize,
+    pub steps: Vec<ProofStep<H>>,
+}
+
+impl<H: Clone + PartialEq + Debug> MerkleProof<H> {
+    /// Recomputes the root from the block and the sibling hashes
+    pub fn verify<M: MerkleHasher<Hash = H>>(&self, hasher: &M, root: &H, block: &[u8]) -> bool {
+        let computed = self.steps.iter().fold(hasher.hash_leaf(block), |hash, step| {
+            if step.sibling_is_left {
+                hasher.hash_nodes(&step.sibling, &hash)
+            } else {
+                hasher.hash_nodes(&hash, &step.sibling)
+            }
+        });
+        computed == *root
+    }
+}
+
+pub struct MerkleTree<M: MerkleHasher> {
+    hasher: M,
+    /// Hashes level by level, the leaves first and the root last
+    levels: Vec<Vec<M::Hash>>,
+}
+
+impl<M: MerkleHasher> MerkleTree<M> {
+    pub fn new<B: AsRef<[u8]>>(hasher: M, blocks: &[B]) -> Self {
+        let leaves: Vec<M::Hash> =
+            blocks.iter().map(|block| hasher.hash_leaf(block.as_ref())).collect();
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap();
+            let parents = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hasher.hash_nodes(left, right),
+                    [single] => single.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(parents);
+        }
+        let tree = MerkleTree { hasher, levels };
+        check_invariants!(&tree);
+        tree
+    }
+
+    /// Number of blocks
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Root hash, None when there are no blocks
+    pub fn root(&self) -> Option<&M::Hash> {
+        self.levels.last().unwrap().first()
+    }
+
+    /// Levels above the leaves
+    pub fn height(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn hasher(&self) -> &M {
+        &self.hasher
+    }
+
+    /// Inclusion proof for the block at `leaf_index`
+    pub fn proof(&self, leaf_index: usize) -> Option<MerkleProof<M::Hash>> {
+        if leaf_index >= self.len() {
+            return None;
+        }
+        let mut steps = vec![];
+        let mut index = leaf_index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                steps.push(ProofStep { sibling: hash.clone(), sibling_is_left: sibling < index });
+            }
+            index /= 2;
+        }
+        Some(MerkleProof { leaf_index, steps })
+    }
+
+    /// Checks the proof of a block against this tree's root
+    pub fn verify(&self, block: &[u8], proof: &MerkleProof<M::Hash>) -> bool {
+        self.root().is_some_and(|root| proof.verify(&self.hasher, root, block))
+    }
+}
+
+/// Checks every level halves the one below, with each parent the hash of its
+/// two children or a lone last child moved up, and the top has at most one
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<M: MerkleHasher> Invariants for MerkleTree<M> {
+    fn assert_invariants(&self) {
+        for pair in self.levels.windows(2) {
+            let (children, parents) = (&pair[0], &pair[1]);
+            assert_eq!(parents.len(), children.len().div_ceil(2), "Level has the wrong size");
+            for (parent, pair) in parents.iter().zip(children.chunks(2)) {
+                let expected = match pair {
+                    [left, right] => self.hasher.hash_nodes(left, right),
+                    [single] => single.clone(),
+                    _ => unreachable!(),
+                };
+                assert_eq!(*parent, expected, "Parent isn't the hash of its children");
+            }
+        }
+        assert!(self.levels.last().unwrap().len() <= 1, "Top level isn't the root");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Readable hasher for tests: a leaf is its text, a node is "(left+right)"
+    struct ConcatHasher;
+
+    impl MerkleHasher for ConcatHasher {
+        type Hash = String;
+
+        fn hash_leaf(&self, data: &[u8]) -> String {
+            String::from_utf8_lossy(data).into_owned()
+        }
+
+        fn hash_nodes(&self, left: &String, right: &String) -> String {
+            format!("({}+{})", left, right)
+        }
+    }
+
+    #[test]
+    fn when_tree_is_built_without_blocks_it_is_empty() {
+        let tree = MerkleTree::new(ConcatHasher, &[] as &[&str]);
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), None);
+        assert_eq!(tree.proof(0), None);
+    }
+
+    #[test]
+    fn test_root_combines_blocks_in_order() {
+        let tree = MerkleTree::new(ConcatHasher, &["a", "b", "c", "d", "e"]);
+
+        assert_eq!(tree.root().unwrap(), "(((a+b)+(c+d))+e)");
+        assert_eq!(tree.height(), 3);
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn test_proof_contains_siblings_on_the_path() {
+        let tree = MerkleTree::new(ConcatHasher, &["a", "b", "c", "d", "e"]);
+
+        let proof = tree.proof(2).unwrap();
+
+        let siblings: Vec<(&str, bool)> = proof.steps
+            .iter()
+            .map(|step| (step.sibling.as_str(), step.sibling_is_left))
+            .collect();
+        assert_eq!(siblings, vec![("d", false), ("(a+b)", true), ("e", false)]);
+        assert!(tree.verify(b"c", &proof));
+    }
+
+    #[test]
+    fn test_every_proof_verifies_and_rejects_other_blocks() {
+        let blocks: Vec<Vec<u8>> = (0..13u8).map(|i| vec![i; 3]).collect();
+        let tree = MerkleTree::new(SipMerkleHasher, &blocks);
+        let root = *tree.root().unwrap();
+
+        for (index, block) in blocks.iter().enumerate() {
+            let proof = tree.proof(index).unwrap();
+            assert!(proof.verify(&SipMerkleHasher, &root, block));
+            assert!(!proof.verify(&SipMerkleHasher, &root, b"forged"));
+        }
+    }
+
+    #[test]
+    fn test_changed_block_changes_root() {
+        let original = MerkleTree::new(SipMerkleHasher, &["x", "y", "z"]);
+        let changed = MerkleTree::new(SipMerkleHasher, &["x", "Y", "z"]);
+
+        assert_ne!(original.root(), changed.root());
+        assert!(!changed.verify(b"y", &original.proof(1).unwrap()));
+    }
+}
@@ -0,0 +1,249 @@
+//! MultiSet (Bag) implementation
+//! Set that can hold an element more than once. Built on the crate's
+//! HashMap, mapping every distinct element to its multiplicity, so
+//! elements with a count of zero are never stored.
+
+use std::{ hash::Hash, fmt::Debug };
+
+use crate::collection::Collection;
+use crate::hash_map::HashMap;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+pub struct MultiSet<T> {
+    counts: HashMap<T, usize>,
+    /// Sum of all multiplicities
+    total: usize,
+}
+
+impl<T: Hash + Clone + PartialEq + Debug> MultiSet<T> {
+    pub fn new() -> Self {
+        MultiSet { counts: HashMap::new(), total: 0 }
+    }
+
+    /// Number of elements, counting repeats
+    pub fn len(&self) -> usize {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = MultiSet::new();
+        check_invariants!(self);
+    }
+
+    /// Number of different elements
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Adds one copy and returns the new multiplicity
+    pub fn insert(&mut self, item: T) -> usize {
+        self.insert_many(item, 1)
+    }
+
+    /// Adds `copies` copies and returns the new multiplicity
+    pub fn insert_many(&mut self, item: T, copies: usize) -> usize {
+        if copies == 0 {
+            return self.count(&item);
+        }
+        let count = self.count(&item) + copies;
+        self.counts.insert(item, count);
+        self.total += copies;
+        check_invariants!(self);
+        count
+    }
+
+    /// Removes one copy. Returns false if the element wasn't present.
+    pub fn remove_one(&mut self, item: &T) -> bool {
+        match self.count(item) {
+            0 => {
+                return false;
+            }
+            1 => {
+                self.counts.remove(item.clone());
+            }
+            count => {
+                self.counts.insert(item.clone(), count - 1);
+            }
+        }
+        self.total -= 1;
+        check_invariants!(self);
+        true
+    }
+
+    /// Removes every copy and returns how many there were
+    pub fn remove_all(&mut self, item: &T) -> usize {
+        let count = self.counts.remove(item.clone()).unwrap_or(0);
+        self.total -= count;
+        check_invariants!(self);
+        count
+    }
+
+    pub fn count(&self, item: &T) -> usize {
+        self.counts.get(item.clone()).unwrap_or(0)
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.count(item) > 0
+    }
+
+    /// Distinct elements with their multiplicities
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.counts.iter().map(|(item, &count)| (item, count))
+    }
+
+    /// Every element with the larger of its two multiplicities
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for (item, count) in other.iter() {
+            let missing = count.saturating_sub(result.count(item));
+            result.insert_many(item.clone(), missing);
+        }
+        result
+    }
+
+    /// Every element with the smaller of its two multiplicities
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        for (item, count) in self.iter() {
+            result.insert_many(item.clone(), count.min(other.count(item)));
+        }
+        result
+    }
+
+    /// True if no element occurs more often here than in `other`
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.iter().all(|(item, count)| count <= other.count(item))
+    }
+}
+
+impl<T: Hash + Clone + PartialEq + Debug> Default for MultiSet<T> {
+    fn default() -> Self {
+        MultiSet::new()
+    }
+}
+
+/// `len` counts repeats, like the inherent one
+impl<T: Hash + Clone + PartialEq + Debug> Collection for MultiSet<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        MultiSet::len(self)
+    }
+
+    fn clear(&mut self) {
+        MultiSet::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        MultiSet::contains(self, item)
+    }
+}
+
+impl<T: Hash + Clone + PartialEq + Debug> FromIterator<T> for MultiSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = MultiSet::new();
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<T: HeapSize> HeapSize for MultiSet<T> {
+    fn heap_size(&self) -> usize {
+        self.counts.heap_size()
+    }
+}
+
+/// Checks no stored count is zero and the counts add up to `total`
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Hash + Clone + PartialEq + Debug> Invariants for MultiSet<T> {
+    fn assert_invariants(&self) {
+        assert!(self.counts.iter().all(|(_, &count)| count > 0), "An element has count 0");
+        let counted: usize = self.counts.iter().map(|(_, &count)| count).sum();
+        assert_eq!(counted, self.total, "total doesn't match the counts");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_counts(set: &MultiSet<char>) -> Vec<(char, usize)> {
+        let mut counts: Vec<(char, usize)> = set
+            .iter()
+            .map(|(&item, count)| (item, count))
+            .collect();
+        counts.sort();
+        counts
+    }
+
+    #[test]
+    fn when_multiset_is_created_it_is_empty() {
+        let set = MultiSet::<i32>::new();
+
+        assert!(set.is_empty());
+        assert_eq!(set.count(&1), 0);
+        assert_eq!(set.distinct_len(), 0);
+    }
+
+    #[test]
+    fn test_insert_counts_copies() {
+        let mut set = MultiSet::new();
+
+        assert_eq!(set.insert("a"), 1);
+        assert_eq!(set.insert("a"), 2);
+        assert_eq!(set.insert_many("b", 3), 3);
+
+        assert_eq!(set.len(), 5);
+        assert_eq!(set.distinct_len(), 2);
+        assert_eq!(set.count(&"a"), 2);
+    }
+
+    #[test]
+    fn test_remove_one_and_remove_all() {
+        let mut set: MultiSet<char> = "banana".chars().collect();
+
+        assert!(set.remove_one(&'a'));
+        assert!(set.remove_one(&'b'));
+        assert!(!set.remove_one(&'b'));
+        assert_eq!(set.remove_all(&'n'), 2);
+        assert_eq!(set.remove_all(&'n'), 0);
+
+        assert_eq!(sorted_counts(&set), vec![('a', 2)]);
+        assert_eq!(set.len(), 2);
+        assert!(!set.contains(&'b'));
+    }
+
+    #[test]
+    fn test_union_takes_larger_multiplicity() {
+        let first: MultiSet<char> = "aab".chars().collect();
+        let second: MultiSet<char> = "abbbc".chars().collect();
+
+        let union = first.union(&second);
+
+        assert_eq!(sorted_counts(&union), vec![('a', 2), ('b', 3), ('c', 1)]);
+        assert_eq!(union.len(), 6);
+    }
+
+    #[test]
+    fn test_intersection_takes_smaller_multiplicity() {
+        let first: MultiSet<char> = "aab".chars().collect();
+        let second: MultiSet<char> = "abbbc".chars().collect();
+
+        let intersection = first.intersection(&second);
+
+        assert_eq!(sorted_counts(&intersection), vec![('a', 1), ('b', 1)]);
+        assert!(intersection.is_subset(&first));
+        assert!(intersection.is_subset(&second));
+        assert!(!first.is_subset(&second));
+    }
+}
@@ -0,0 +1,307 @@
+//! Node allocators
+//! Structures whose nodes link to each other by handle instead of by
+//! pointer take the store for their nodes as a type parameter. The store
+//! hands out a handle for every node it is given and gives the node back
+//! when the handle is freed. Two stores come with the crate:
+//! - `Global` makes one allocation per node, like a Box, and its handles are
+//!   pointers. Every `Global` shares its nodes with every other, so a
+//!   structure can take over another one's nodes without copying them.
+//! - `Slab` keeps every node in one vector and reuses freed slots, so adding
+//!   a node only calls the global allocator when the vector grows, and a
+//!   stale handle is caught by its generation instead of reading someone
+//!   else's node.
+//!
+//! The handle type is declared apart from `NodeAlloc`, so a node can hold
+//! handles of the allocator that stores it.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ptr::NonNull;
+
+/// Handle an allocator gives out, the same for every node type it stores
+pub trait NodeHandle {
+    type Handle: Copy + Eq + Hash + Debug;
+}
+
+/// Reading a node is unsafe because not every allocator can tell a live
+/// handle from a freed one. A structure only passes the handles it holds;
+/// where a handle comes from outside, it asks for a `CheckedAlloc`.
+pub trait NodeAlloc<T>: NodeHandle {
+    fn alloc(&mut self, node: T) -> Self::Handle;
+
+    /// None if the handle was freed and the allocator can tell
+    ///
+    /// # Safety
+    /// Unless the allocator is a `CheckedAlloc`, `handle` must come from
+    /// `alloc` on this allocator, or one it shares nodes with, and not be
+    /// freed yet
+    unsafe fn get(&self, handle: Self::Handle) -> Option<&T>;
+
+    /// # Safety
+    /// As for `get`
+    unsafe fn get_mut(&mut self, handle: Self::Handle) -> Option<&mut T>;
+
+    /// Like `get_mut`, but the pointer only borrows the node and not the
+    /// whole allocator, so several nodes can be lent out mutably at once
+    ///
+    /// # Safety
+    /// As for `get`
+    unsafe fn get_ptr(&mut self, handle: Self::Handle) -> Option<NonNull<T>>;
+
+    /// Gives the node back, the handle is not valid afterwards
+    ///
+    /// # Safety
+    /// As for `get`
+    unsafe fn free(&mut self, handle: Self::Handle) -> Option<T>;
+
+    /// Whether handles given out by `other` are valid in `self`, so nodes
+    /// can move between the structures holding the two without a copy
+    fn shares_nodes_with(&self, _other: &Self) -> bool {
+        false
+    }
+}
+
+/// Allocator whose `get`, `get_mut`, `get_ptr` and `free` return None for a
+/// freed handle, so they can be given handles from outside a structure
+///
+/// # Safety
+/// The four methods must be sound for any handle of the right type
+pub unsafe trait CheckedAlloc<T>: NodeAlloc<T> {}
+
+/// The global allocator, one allocation per node. Not `Clone`: a structure
+/// deriving Clone would copy the handles and free every node twice.
+#[derive(Debug, Default)]
+pub struct Global;
+
+/// Address of a node allocated by `Global`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlobalHandle(NonNull<u8>);
+
+// SAFETY: A handle is only an address. Reaching the node behind it takes an
+// unsafe call on behalf of the structure owning the node, and that structure
+// holds the node type itself, so it is only Send or Sync if the node is.
+unsafe impl Send for GlobalHandle {}
+unsafe impl Sync for GlobalHandle {}
+
+impl NodeHandle for Global {
+    type Handle = GlobalHandle;
+}
+
+impl<T> NodeAlloc<T> for Global {
+    fn alloc(&mut self, node: T) -> GlobalHandle {
+        GlobalHandle(NonNull::from(Box::leak(Box::new(node))).cast())
+    }
+
+    unsafe fn get(&self, handle: GlobalHandle) -> Option<&T> {
+        // SAFETY: The caller passes a live handle, which came from a leaked Box<T>
+        Some(unsafe { handle.0.cast::<T>().as_ref() })
+    }
+
+    unsafe fn get_mut(&mut self, handle: GlobalHandle) -> Option<&mut T> {
+        // SAFETY: As above
+        Some(unsafe { handle.0.cast::<T>().as_mut() })
+    }
+
+    unsafe fn get_ptr(&mut self, handle: GlobalHandle) -> Option<NonNull<T>> {
+        Some(handle.0.cast())
+    }
+
+    unsafe fn free(&mut self, handle: GlobalHandle) -> Option<T> {
+        // SAFETY: As above, and the caller doesn't use the handle again
+        Some(*unsafe { Box::from_raw(handle.0.cast::<T>().as_ptr()) })
+    }
+
+    /// Every node comes from the same global heap
+    fn shares_nodes_with(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl_tree::AvlTreeMap;
+    use crate::linked_hash_set::LinkedHashSet;
+    use crate::pairing_heap::PairingHeap;
+    use crate::singly_linked_list::SinglyLinkedList;
+    use crate::slab::{ Key, Slab };
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Slab that counts the nodes it was asked for
+    struct CountingSlab<T> {
+        slab: Slab<T>,
+        allocated: usize,
+    }
+
+    impl<T> CountingSlab<T> {
+        fn new() -> Self {
+            CountingSlab { slab: Slab::new(), allocated: 0 }
+        }
+    }
+
+    impl<T> NodeHandle for CountingSlab<T> {
+        type Handle = Key;
+    }
+
+    impl<T> NodeAlloc<T> for CountingSlab<T> {
+        fn alloc(&mut self, node: T) -> Key {
+            self.allocated += 1;
+            self.slab.alloc(node)
+        }
+
+        unsafe fn get(&self, key: Key) -> Option<&T> {
+            self.slab.get(key)
+        }
+
+        unsafe fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+            self.slab.get_mut(key)
+        }
+
+        unsafe fn get_ptr(&mut self, key: Key) -> Option<NonNull<T>> {
+            unsafe { self.slab.get_ptr(key) }
+        }
+
+        unsafe fn free(&mut self, key: Key) -> Option<T> {
+            self.slab.remove(key)
+        }
+    }
+
+    // SAFETY: The slab checks every key
+    unsafe impl<T> CheckedAlloc<T> for CountingSlab<T> {}
+
+    /// The global allocator, counting the nodes it was asked for
+    struct CountingGlobal {
+        global: Global,
+        allocated: Rc<Cell<usize>>,
+    }
+
+    impl NodeHandle for CountingGlobal {
+        type Handle = GlobalHandle;
+    }
+
+    impl<T> NodeAlloc<T> for CountingGlobal {
+        fn alloc(&mut self, node: T) -> GlobalHandle {
+            self.allocated.set(self.allocated.get() + 1);
+            self.global.alloc(node)
+        }
+
+        unsafe fn get(&self, handle: GlobalHandle) -> Option<&T> {
+            unsafe { self.global.get(handle) }
+        }
+
+        unsafe fn get_mut(&mut self, handle: GlobalHandle) -> Option<&mut T> {
+            unsafe { self.global.get_mut(handle) }
+        }
+
+        unsafe fn get_ptr(&mut self, handle: GlobalHandle) -> Option<NonNull<T>> {
+            unsafe { self.global.get_ptr(handle) }
+        }
+
+        unsafe fn free(&mut self, handle: GlobalHandle) -> Option<T> {
+            unsafe { self.global.free(handle) }
+        }
+
+        fn shares_nodes_with(&self, _other: &Self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_structures_use_the_given_allocator() {
+        let mut set = LinkedHashSet::new_in(CountingSlab::new());
+        let mut heap = PairingHeap::new_in(CountingSlab::new());
+
+        for item in [3, 1, 2, 1] {
+            set.insert(item);
+            heap.push(item);
+        }
+        set.remove(&1);
+
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(set.nodes().allocated, 3);
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.nodes().allocated, 4);
+    }
+
+    #[test]
+    fn test_popped_heap_nodes_are_reused() {
+        let mut heap = PairingHeap::new_in(Slab::with_capacity(4));
+
+        for round in 0..100 {
+            for item in 0..4 {
+                heap.push(round * 4 + item);
+            }
+            while heap.pop().is_some() {}
+        }
+
+        assert_eq!(heap.nodes().capacity(), 4);
+    }
+
+    #[test]
+    fn test_list_nodes_can_live_in_a_slab() {
+        let mut list = SinglyLinkedList::new_in(Slab::new());
+
+        list.extend([1, 2, 3]);
+        list.iter_mut().for_each(|item| *item *= 10);
+        list.delete_first();
+        list.append(40);
+
+        assert_eq!(list.to_string(), "20 -> 30 -> 40 -> ");
+        assert_eq!(list.nodes().len(), 3);
+        assert_eq!(list.nodes().capacity(), 3);
+    }
+
+    #[test]
+    fn test_removed_tree_nodes_go_back_to_the_slab() {
+        let mut tree = AvlTreeMap::new_in(Slab::new());
+
+        for key in 0..100 {
+            tree.insert(key, key * 2);
+        }
+        for key in (0..100).step_by(2) {
+            tree.remove(&key);
+        }
+        for key in 100..150 {
+            tree.insert(key, key * 2);
+        }
+
+        assert_eq!(tree.len(), 100);
+        assert_eq!(tree.nodes().capacity(), 100);
+        assert_eq!(tree.get(&7), Some(&14));
+        assert!(tree.clone() == tree);
+    }
+
+    #[test]
+    fn test_meld_links_heaps_sharing_their_nodes() {
+        let allocated = Rc::new(Cell::new(0));
+        let counting = || CountingGlobal { global: Global, allocated: allocated.clone() };
+        let new_heap = || PairingHeap::new_in(counting());
+        let (mut heap, mut other) = (new_heap(), new_heap());
+        for item in 0..10 {
+            heap.push(item * 2);
+            other.push(item * 2 + 1);
+        }
+
+        heap.meld(other);
+
+        assert_eq!(allocated.get(), 20);
+        assert_eq!(heap.len(), 20);
+        assert!((0..20).eq(std::iter::from_fn(|| heap.pop())));
+    }
+
+    #[test]
+    fn test_meld_copies_the_nodes_of_another_slab() {
+        let mut heap = PairingHeap::new_in(CountingSlab::new());
+        let mut other = PairingHeap::new_in(CountingSlab::new());
+        for item in 0..10 {
+            heap.push(item * 2);
+            other.push(item * 2 + 1);
+        }
+
+        heap.meld(other);
+
+        assert_eq!(heap.nodes().allocated, 20);
+        assert!((0..20).eq(std::iter::from_fn(|| heap.pop())));
+    }
+}
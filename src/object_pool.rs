@@ -0,0 +1,220 @@
+//! Object Pool implementation
+//! Keeps values that are expensive to build so they can be used again.
+//! `get` hands out an idle object, or builds a new one with the factory, in a
+//! guard that derefs to it. Dropping the guard runs the reset hook and puts
+//! the object back. At most `max_size` objects exist at the same time.
+
+use std::cell::{ Cell, RefCell };
+use std::ops::{ Deref, DerefMut };
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+type ResetHook<T> = Box<dyn Fn(&mut T)>;
+
+pub struct ObjectPool<T> {
+    idle: RefCell<Vec<T>>,
+    factory: Box<dyn Fn() -> T>,
+    reset: Option<ResetHook<T>>,
+    max_size: usize,
+    /// Objects that are handed out right now
+    in_use: Cell<usize>,
+    /// Objects built by the factory so far
+    created: Cell<usize>,
+}
+
+impl<T> ObjectPool<T> {
+    /// Creates an empty pool that builds objects with `factory` when needed
+    pub fn new(max_size: usize, factory: impl Fn() -> T + 'static) -> Self {
+        assert!(max_size > 0, "Pool size must be positive");
+        ObjectPool {
+            idle: RefCell::new(vec![]),
+            factory: Box::new(factory),
+            reset: None,
+            max_size,
+            in_use: Cell::new(0),
+            created: Cell::new(0),
+        }
+    }
+
+    /// Sets the hook that cleans an object when it comes back to the pool
+    pub fn with_reset(mut self, reset: impl Fn(&mut T) + 'static) -> Self {
+        self.reset = Some(Box::new(reset));
+        self
+    }
+
+    /// Builds `count` objects up front, as long as the size limit allows
+    pub fn prefill(&self, count: usize) {
+        let room = self.max_size - self.in_use.get() - self.idle_count();
+        for _ in 0..count.min(room) {
+            let object = self.build();
+            self.idle.borrow_mut().push(object);
+        }
+        check_invariants!(self);
+    }
+
+    /// Hands out an idle object or builds a new one.
+    /// Returns None when `max_size` objects are already in use.
+    pub fn get(&self) -> Option<Pooled<'_, T>> {
+        let object = match self.idle.borrow_mut().pop() {
+            Some(object) => object,
+            None if self.in_use.get() < self.max_size => self.build(),
+            None => return None,
+        };
+        self.in_use.set(self.in_use.get() + 1);
+        check_invariants!(self);
+        Some(Pooled { pool: self, object: Some(object) })
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn idle_count(&self) -> usize {
+        self.idle.borrow().len()
+    }
+
+    pub fn in_use_count(&self) -> usize {
+        self.in_use.get()
+    }
+
+    /// Number of objects the factory has built
+    pub fn created_count(&self) -> usize {
+        self.created.get()
+    }
+
+    fn build(&self) -> T {
+        self.created.set(self.created.get() + 1);
+        (self.factory)()
+    }
+
+    fn give_back(&self, mut object: T) {
+        if let Some(reset) = &self.reset {
+            reset(&mut object);
+        }
+        self.in_use.set(self.in_use.get() - 1);
+        self.idle.borrow_mut().push(object);
+        check_invariants!(self);
+    }
+}
+
+/// Object borrowed from a pool, it goes back when the guard is dropped
+pub struct Pooled<'a, T> {
+    pool: &'a ObjectPool<T>,
+    /// Always Some until the guard is dropped or detached
+    object: Option<T>,
+}
+
+impl<T> Pooled<'_, T> {
+    /// Takes the object out of the pool for good, freeing its place
+    pub fn detach(mut self) -> T {
+        self.pool.in_use.set(self.pool.in_use.get() - 1);
+        check_invariants!(self.pool);
+        self.object.take().unwrap()
+    }
+}
+
+impl<T> Deref for Pooled<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.object.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for Pooled<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.object.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for Pooled<'_, T> {
+    fn drop(&mut self) {
+        if let Some(object) = self.object.take() {
+            self.pool.give_back(object);
+        }
+    }
+}
+
+/// Checks the idle and handed out objects fit the size limit and were all
+/// built by the factory, detached ones are built but no longer counted
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for ObjectPool<T> {
+    fn assert_invariants(&self) {
+        let live = self.idle_count() + self.in_use.get();
+        assert!(live <= self.max_size, "More objects than the size limit");
+        assert!(live <= self.created.get(), "More objects than the factory built");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer_pool(max_size: usize) -> ObjectPool<Vec<u8>> {
+        ObjectPool::new(max_size, || Vec::with_capacity(1024)).with_reset(Vec::clear)
+    }
+
+    #[test]
+    fn when_pool_is_created_it_is_empty() {
+        let pool = buffer_pool(2);
+
+        assert_eq!(pool.idle_count(), 0);
+        assert_eq!(pool.in_use_count(), 0);
+        assert_eq!(pool.created_count(), 0);
+    }
+
+    #[test]
+    fn test_objects_are_reused_and_reset() {
+        let pool = buffer_pool(2);
+
+        {
+            let mut buffer = pool.get().unwrap();
+            buffer.extend_from_slice(b"hello");
+            assert_eq!(pool.in_use_count(), 1);
+        }
+        let buffer = pool.get().unwrap();
+
+        assert!(buffer.is_empty(), "The reset hook cleared the buffer");
+        assert!(buffer.capacity() >= 1024);
+        assert_eq!(pool.created_count(), 1);
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let pool = buffer_pool(2);
+
+        let first = pool.get().unwrap();
+        let second = pool.get().unwrap();
+        assert!(pool.get().is_none());
+
+        drop(first);
+        assert!(pool.get().is_some());
+        drop(second);
+        assert_eq!(pool.idle_count(), 2);
+    }
+
+    #[test]
+    fn test_detach_frees_a_place() {
+        let pool = buffer_pool(1);
+
+        let mut buffer = pool.get().unwrap().detach();
+        buffer.push(1);
+
+        assert_eq!(pool.in_use_count(), 0);
+        assert!(pool.get().is_some());
+        assert_eq!(pool.created_count(), 2);
+    }
+
+    #[test]
+    fn test_prefill_respects_the_limit() {
+        let pool = buffer_pool(3);
+        let _held = pool.get().unwrap();
+
+        pool.prefill(10);
+
+        assert_eq!(pool.idle_count(), 2);
+        assert_eq!(pool.created_count(), 3);
+    }
+}
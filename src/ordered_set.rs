@@ -0,0 +1,212 @@
+//! Ordered Set implementation
+//! Set that keeps its elements sorted, stored as the keys of an AVL tree
+//! with `()` values. Besides O(log n) membership it answers order
+//! questions: the smallest and largest element, the elements in a range
+//! and the closest elements above or below a value.
+
+use std::fmt::Debug;
+use std::ops::{ RangeBounds, RangeFull };
+
+use crate::avl_tree::{ self, AvlTreeMap };
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::render::Render;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderedSet<T> {
+    tree: AvlTreeMap<T, ()>,
+}
+
+impl<T: Ord> OrderedSet<T> {
+    pub fn new() -> Self {
+        OrderedSet { tree: AvlTreeMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        check_invariants!(self);
+    }
+
+    /// Adds the element. Returns false if it was already present.
+    pub fn insert(&mut self, item: T) -> bool {
+        let inserted = self.tree.insert(item, ()).is_none();
+        check_invariants!(self);
+        inserted
+    }
+
+    /// Returns false if the element wasn't present
+    pub fn remove(&mut self, item: &T) -> bool {
+        let removed = self.tree.remove(item).is_some();
+        check_invariants!(self);
+        removed
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.tree.contains_key(item)
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.tree.first().map(|(item, _)| item)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.tree.last().map(|(item, _)| item)
+    }
+
+    /// Smallest element strictly greater than `item`
+    pub fn next_above(&self, item: &T) -> Option<&T> {
+        self.tree.next_above(item).map(|(item, _)| item)
+    }
+
+    /// Largest element strictly smaller than `item`
+    pub fn next_below(&self, item: &T) -> Option<&T> {
+        self.tree.next_below(item).map(|(item, _)| item)
+    }
+
+    /// Elements in ascending order
+    pub fn iter(&self) -> Iter<'_, T, RangeFull> {
+        Iter { inner: self.tree.iter() }
+    }
+
+    /// Elements inside the range in ascending order
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Iter<'_, T, R> {
+        Iter { inner: self.tree.range(range) }
+    }
+}
+
+impl<T: Ord> Default for OrderedSet<T> {
+    fn default() -> Self {
+        OrderedSet::new()
+    }
+}
+
+impl<T: Ord> Collection for OrderedSet<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        OrderedSet::len(self)
+    }
+
+    fn clear(&mut self) {
+        OrderedSet::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        OrderedSet::contains(self, item)
+    }
+}
+
+impl<T: Ord> FromIterator<T> for OrderedSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = OrderedSet::new();
+        for item in iter {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+pub struct Iter<'a, T, R> {
+    inner: avl_tree::Range<'a, T, (), R>,
+}
+
+impl<'a, T: Ord, R: RangeBounds<T>> Iterator for Iter<'a, T, R> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(item, _)| item)
+    }
+}
+
+/// The AVL tree underneath, labelled with the elements only
+impl<T: Debug> Render for OrderedSet<T> {
+    fn render(&self) -> String {
+        self.tree.render_with(|item, _| format!("{:?}", item))
+    }
+}
+
+impl<T: HeapSize> HeapSize for OrderedSet<T> {
+    fn heap_size(&self) -> usize {
+        self.tree.heap_size()
+    }
+}
+
+/// The elements are the tree's keys, so its order and size are the set's
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for OrderedSet<T> {
+    fn assert_invariants(&self) {
+        self.tree.assert_invariants();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_set_is_created_it_is_empty() {
+        let set = OrderedSet::<i32>::new();
+
+        assert!(set.is_empty());
+        assert_eq!(set.first(), None);
+        assert_eq!(set.last(), None);
+    }
+
+    #[test]
+    fn test_insert_ignores_duplicates_and_iterates_sorted() {
+        let mut set = OrderedSet::new();
+
+        for item in [5, 1, 9, 1, 3, 5] {
+            set.insert(item);
+        }
+
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![1, 3, 5, 9]);
+        assert!(!set.insert(3));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set: OrderedSet<&str> = ["pear", "apple", "fig"].into_iter().collect();
+
+        assert!(set.remove(&"apple"));
+        assert!(!set.remove(&"apple"));
+
+        assert_eq!(set.first(), Some(&"fig"));
+        assert!(!set.contains(&"apple"));
+    }
+
+    #[test]
+    fn test_range() {
+        let set: OrderedSet<i32> = (0..20).map(|i| i * 5).collect();
+
+        assert_eq!(set.range(12..31).copied().collect::<Vec<_>>(), vec![15, 20, 25, 30]);
+        assert_eq!(set.range(90..).copied().collect::<Vec<_>>(), vec![90, 95]);
+        assert_eq!(set.range(..=5).copied().collect::<Vec<_>>(), vec![0, 5]);
+        assert_eq!(set.range(41..44).count(), 0);
+    }
+
+    #[test]
+    fn test_first_last_and_neighbors() {
+        let set: OrderedSet<i32> = [40, 10, 30, 20].into_iter().collect();
+
+        assert_eq!(set.first(), Some(&10));
+        assert_eq!(set.last(), Some(&40));
+        assert_eq!(set.next_above(&10), Some(&20));
+        assert_eq!(set.next_above(&35), Some(&40));
+        assert_eq!(set.next_above(&40), None);
+        assert_eq!(set.next_below(&10), None);
+        assert_eq!(set.next_below(&100), Some(&40));
+    }
+}
@@ -0,0 +1,511 @@
+//! Pairing Heap implementation (min heap)
+//! Multi-way tree where the root always holds the smallest item. Push and
+//! meld just link two trees, pop merges the children of the root in two
+//! passes. Nodes are kept by a `NodeAlloc`, a `Slab` unless `new_in` is
+//! given another one, so `push` can hand out a stable `Handle` that is
+//! later used to decrease the key of that item, and popped nodes are reused.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::node_alloc::{ CheckedAlloc, NodeAlloc, NodeHandle };
+use crate::slab::{ Key, Slab };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle<H = Key>(H);
+
+/// Tree node, only public so an allocator for it can be named
+#[derive(Debug, Clone)]
+pub struct PairingNode<T, H> {
+    item: T,
+    child: Option<H>,
+    sibling: Option<H>,
+    /// Parent if the node is the leftmost child, left sibling otherwise
+    prev: Option<H>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PairingHeap<T: Ord, A = Slab<PairingNode<T, Key>>>
+where
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>>,
+{
+    nodes: A,
+    root: Option<A::Handle>,
+    size: usize,
+    items: PhantomData<T>,
+}
+
+impl<T: Ord> PairingHeap<T> {
+    pub fn new() -> Self {
+        PairingHeap::new_in(Slab::new())
+    }
+}
+
+impl<T, A> PairingHeap<T, A>
+where
+    T: Ord,
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>>,
+{
+    /// Empty heap that keeps its nodes in `nodes`
+    pub fn new_in(nodes: A) -> Self {
+        PairingHeap { nodes, root: None, size: 0, items: PhantomData }
+    }
+
+    /// The allocator holding the nodes
+    pub fn nodes(&self) -> &A {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Frees every node, the allocator is kept
+    pub fn clear(&mut self) {
+        let mut pending: Vec<A::Handle> = self.root.take().into_iter().collect();
+        while let Some(handle) = pending.pop() {
+            let node = self.free(handle);
+            pending.extend(node.child.into_iter().chain(node.sibling));
+        }
+        self.size = 0;
+        check_invariants!(self);
+    }
+
+    /// Returns the smallest item without removing it
+    pub fn peek(&self) -> Option<&T> {
+        self.root.map(|root| &self.node(root).item)
+    }
+
+    /// Items in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut stack: Vec<A::Handle> = self.root.into_iter().collect();
+        std::iter::from_fn(move || {
+            let node = self.node(stack.pop()?);
+            stack.extend(node.child);
+            stack.extend(node.sibling);
+            Some(&node.item)
+        })
+    }
+
+    /// Adds an item in O(1) and returns a handle to it
+    pub fn push(&mut self, item: T) -> Handle<A::Handle> {
+        let node = self.nodes.alloc(PairingNode { item, child: None, sibling: None, prev: None });
+        self.root = Some(match self.root {
+            Some(root) => self.link(root, node),
+            None => node,
+        });
+        self.size += 1;
+        check_invariants!(self);
+        Handle(node)
+    }
+
+    /// Removes the smallest item. Children of the root are linked in pairs
+    /// from left to right, then the pairs are linked from right to left.
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.free(self.root?);
+
+        let mut children = vec![];
+        let mut current = root.child;
+        while let Some(child) = current {
+            let node = self.node_mut(child);
+            current = node.sibling.take();
+            node.prev = None;
+            children.push(child);
+        }
+
+        let paired: Vec<A::Handle> = children
+            .chunks(2)
+            .map(|pair| if pair.len() == 2 { self.link(pair[0], pair[1]) } else { pair[0] })
+            .collect();
+        self.root = paired.into_iter().rev().reduce(|merged, tree| self.link(tree, merged));
+        self.size -= 1;
+        check_invariants!(self);
+        Some(root.item)
+    }
+
+    /// Moves all items of `other` into this heap. If the two heaps share
+    /// their allocator the roots are just linked in O(1), otherwise the
+    /// nodes of `other` are copied over one by one.
+    /// Handles returned by `other` are not valid for this heap.
+    pub fn meld(&mut self, mut other: PairingHeap<T, A>) {
+        let Some(other_root) = other.root.take() else {
+            return;
+        };
+        let other_root = if self.nodes.shares_nodes_with(&other.nodes) {
+            other_root
+        } else {
+            self.copy_nodes(&mut other, other_root)
+        };
+
+        self.root = Some(match self.root {
+            Some(root) => self.link(root, other_root),
+            None => other_root,
+        });
+        self.size += other.size;
+        check_invariants!(self);
+    }
+
+    /// Moves the tree under `root` out of `other` into this heap's
+    /// allocator and returns its new root
+    fn copy_nodes(&mut self, other: &mut PairingHeap<T, A>, root: A::Handle) -> A::Handle {
+        // Every node is moved over first, then the links are translated, so
+        // deep trees don't need deep recursion
+        let mut moved = HashMap::new();
+        let mut links = vec![];
+        let mut pending = vec![root];
+        while let Some(old) = pending.pop() {
+            let node = other.free(old);
+            pending.extend(node.child.into_iter().chain(node.sibling));
+            let links_of_node = (node.child, node.sibling, node.prev);
+            let unlinked = PairingNode { item: node.item, child: None, sibling: None, prev: None };
+            let new = self.nodes.alloc(unlinked);
+            moved.insert(old, new);
+            links.push((new, links_of_node));
+        }
+        for (new, (child, sibling, prev)) in links {
+            let node = self.node_mut(new);
+            node.child = child.map(|old| moved[&old]);
+            node.sibling = sibling.map(|old| moved[&old]);
+            node.prev = prev.map(|old| moved[&old]);
+        }
+        moved[&root]
+    }
+
+    /// Links two roots, the larger one becomes the leftmost child of the smaller.
+    fn link(&mut self, a: A::Handle, b: A::Handle) -> A::Handle {
+        let (parent, child) = if self.node(b).item < self.node(a).item { (b, a) } else { (a, b) };
+        let old_child = self.node(parent).child;
+        if let Some(old_child) = old_child {
+            self.node_mut(old_child).prev = Some(child);
+        }
+        let child_node = self.node_mut(child);
+        child_node.sibling = old_child;
+        child_node.prev = Some(parent);
+        self.node_mut(parent).child = Some(child);
+        parent
+    }
+
+    /// Cuts the subtree rooted at `node` out of its parent's child list
+    fn detach(&mut self, node: A::Handle) {
+        let prev = self.node_mut(node).prev.take().expect("Non-root node has a prev link");
+        let next = self.node_mut(node).sibling.take();
+        if self.node(prev).child == Some(node) {
+            self.node_mut(prev).child = next;
+        } else {
+            self.node_mut(prev).sibling = next;
+        }
+        if let Some(next) = next {
+            self.node_mut(next).prev = Some(prev);
+        }
+    }
+
+    /// `handle` has to be linked into this heap
+    fn node(&self, handle: A::Handle) -> &PairingNode<T, A::Handle> {
+        // SAFETY: Linked nodes were allocated by this heap, or by one whose
+        // nodes it shares and took over in `meld`, and are not freed yet
+        unsafe { self.nodes.get(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// `handle` has to be linked into this heap
+    fn node_mut(&mut self, handle: A::Handle) -> &mut PairingNode<T, A::Handle> {
+        // SAFETY: As in `node`
+        unsafe { self.nodes.get_mut(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// `handle` has to be linked into this heap, and is unlinked by the caller
+    fn free(&mut self, handle: A::Handle) -> PairingNode<T, A::Handle> {
+        // SAFETY: As in `node`
+        unsafe { self.nodes.free(handle) }.expect("Linked nodes are never freed")
+    }
+}
+
+/// Looking up an item by handle needs an allocator that can tell a handle
+/// of a popped item from a live one
+impl<T, A> PairingHeap<T, A>
+where
+    T: Ord,
+    A: CheckedAlloc<PairingNode<T, <A as NodeHandle>::Handle>>,
+{
+    /// Returns the item behind the handle if it is still in the heap
+    pub fn get(&self, handle: Handle<A::Handle>) -> Option<&T> {
+        // SAFETY: A checked allocator takes any handle
+        unsafe { self.nodes.get(handle.0) }.map(|node| &node.item)
+    }
+
+    pub fn contains(&self, handle: Handle<A::Handle>) -> bool {
+        self.get(handle).is_some()
+    }
+
+    /// Replaces the item behind the handle with a smaller one.
+    /// Returns false if the handle is no longer in the heap or the
+    /// new item is greater than the current one.
+    pub fn decrease_key(&mut self, handle: Handle<A::Handle>, new_item: T) -> bool {
+        let node = handle.0;
+        match self.get(handle) {
+            Some(current) if new_item <= *current => {}
+            _ => {
+                return false;
+            }
+        }
+        self.node_mut(node).item = new_item;
+
+        if self.root != Some(node) {
+            self.detach(node);
+            let root = self.root.expect("Heap with a node has a root");
+            self.root = Some(self.link(root, node));
+        }
+        check_invariants!(self);
+        true
+    }
+}
+
+impl<T, A> Default for PairingHeap<T, A>
+where
+    T: Ord,
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>> + Default,
+{
+    fn default() -> Self {
+        PairingHeap::new_in(A::default())
+    }
+}
+
+/// Frees the nodes through the allocator, which may not own them
+impl<T, A> Drop for PairingHeap<T, A>
+where
+    T: Ord,
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>>,
+{
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// `contains` looks for an item by value, the inherent one by handle
+impl<T, A> Collection for PairingHeap<T, A>
+where
+    T: Ord,
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>>,
+{
+    type Item = T;
+
+    fn len(&self) -> usize {
+        PairingHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        PairingHeap::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.iter().any(|other| other == item)
+    }
+}
+
+/// Checks heap order and that every `prev` link points back at the parent or
+/// left sibling that links the node
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, A> Invariants for PairingHeap<T, A>
+where
+    T: Ord,
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>>,
+{
+    fn assert_invariants(&self) {
+        let mut nodes = 0;
+        let mut pending: Vec<A::Handle> = self.root.into_iter().collect();
+        if let Some(root) = self.root {
+            let root = self.node(root);
+            assert!(root.prev.is_none() && root.sibling.is_none(), "Root has a prev or sibling");
+        }
+        while let Some(parent) = pending.pop() {
+            nodes += 1;
+            let mut prev = parent;
+            let mut child = self.node(parent).child;
+            while let Some(handle) = child {
+                let node = self.node(handle);
+                assert!(self.node(parent).item <= node.item, "Child is smaller than its parent");
+                assert_eq!(node.prev, Some(prev), "prev doesn't point back");
+                pending.push(handle);
+                prev = handle;
+                child = node.sibling;
+            }
+        }
+        assert_eq!(nodes, self.size, "size doesn't match the nodes");
+    }
+}
+
+impl<T: HeapSize, H> HeapSize for PairingNode<T, H> {
+    fn heap_size(&self) -> usize {
+        self.item.heap_size()
+    }
+}
+
+/// The allocator with all its nodes, free ones included
+impl<T, A> HeapSize for PairingHeap<T, A>
+where
+    T: Ord,
+    A: NodeAlloc<PairingNode<T, <A as NodeHandle>::Handle>> + HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.nodes.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain<T: Ord>(heap: &mut PairingHeap<T>) -> Vec<T> {
+        let mut items = vec![];
+        while let Some(item) = heap.pop() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn when_heap_is_created_it_is_empty() {
+        let mut heap = PairingHeap::<i32>::new();
+
+        assert!(heap.is_empty());
+        assert_eq!(heap.peek(), None);
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    fn test_push_keeps_smallest_on_top() {
+        let mut heap = PairingHeap::new();
+
+        for item in [5, 3, 8, 1, 9] {
+            heap.push(item);
+        }
+
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 5);
+    }
+
+    #[test]
+    fn test_pop_returns_items_in_ascending_order() {
+        let mut heap = PairingHeap::new();
+        for item in (0..50).map(|i| (i * 7) % 50) {
+            heap.push(item);
+        }
+
+        assert_eq!(drain(&mut heap), (0..50).collect::<Vec<i32>>());
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_decrease_key_moves_item_to_top() {
+        let mut heap = PairingHeap::new();
+        heap.push(10);
+        heap.push(20);
+        let handle = heap.push(30);
+
+        let result = heap.decrease_key(handle, 5);
+
+        assert!(result);
+        assert_eq!(heap.peek(), Some(&5));
+        assert_eq!(heap.get(handle), Some(&5));
+        assert_eq!(drain(&mut heap), vec![5, 10, 20]);
+    }
+
+    #[test]
+    fn test_decrease_key_of_deep_node() {
+        let mut heap = PairingHeap::new();
+        let handles: Vec<Handle> = (0..20).map(|i| heap.push(i * 10)).collect();
+        heap.pop();
+
+        assert!(heap.decrease_key(handles[15], 55));
+
+        let mut expected: Vec<i32> = (1..20).filter(|&i| i != 15).map(|i| i * 10).collect();
+        expected.insert(5, 55);
+        assert_eq!(drain(&mut heap), expected);
+    }
+
+    #[test]
+    fn test_decrease_key_rejects_larger_item() {
+        let mut heap = PairingHeap::new();
+        let handle = heap.push(3);
+
+        assert!(!heap.decrease_key(handle, 4));
+        assert_eq!(heap.get(handle), Some(&3));
+    }
+
+    #[test]
+    fn test_decrease_key_of_popped_item() {
+        let mut heap = PairingHeap::new();
+        let handle = heap.push(1);
+        heap.push(2);
+        heap.pop();
+
+        assert!(!heap.contains(handle));
+        assert!(!heap.decrease_key(handle, 0));
+        assert_eq!(heap.len(), 1);
+    }
+
+    #[test]
+    fn test_meld_two_heaps() {
+        let mut heap_a = PairingHeap::new();
+        let mut heap_b = PairingHeap::new();
+        for item in [4, 8, 2] {
+            heap_a.push(item);
+        }
+        for item in [7, 1, 5] {
+            heap_b.push(item);
+        }
+
+        heap_a.meld(heap_b);
+
+        assert_eq!(heap_a.len(), 6);
+        assert_eq!(drain(&mut heap_a), vec![1, 2, 4, 5, 7, 8]);
+    }
+
+    #[test]
+    fn test_meld_into_empty_heap() {
+        let mut empty = PairingHeap::new();
+        let mut other = PairingHeap::new();
+        other.push("b");
+        other.push("a");
+
+        empty.meld(other);
+
+        assert_eq!(drain(&mut empty), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_mixed_operations_match_sorted_model() {
+        let mut heap = PairingHeap::new();
+        let mut model: Vec<i64> = vec![];
+        let mut handles = vec![];
+        for step in 0..200i64 {
+            let value = (step * 7919) % 1000;
+            handles.push(heap.push(value));
+            model.push(value);
+            if step % 3 == 0 {
+                let handle = handles[(step as usize * 31) % handles.len()];
+                if let Some(&old) = heap.get(handle) {
+                    let position = model.iter().position(|&v| v == old).unwrap();
+                    model[position] = old - 500;
+                    assert!(heap.decrease_key(handle, old - 500));
+                }
+            }
+            if step % 5 == 0 {
+                model.sort();
+                assert_eq!(heap.pop(), Some(model.remove(0)));
+            }
+        }
+
+        heap.assert_invariants();
+        model.sort();
+        assert_eq!(drain(&mut heap), model);
+    }
+}
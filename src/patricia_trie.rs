@@ -0,0 +1,419 @@
+//! PATRICIA Trie implementation over bit strings
+//! Binary trie keyed by bit prefixes of up to 128 bits, such as IPv4 or IPv6
+//! routes. Like the radix trie, chains of single-child nodes are merged,
+//! so every node stores the whole prefix it stands for and branches on the
+//! first bit after it. The height is bounded by the key length, and
+//! longest-prefix match walks down one path, picking the deepest value.
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+/// Up to 128 bits, stored left-aligned so the first bit is the highest one.
+/// Bits after `len` are always zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BitPrefix {
+    bits: u128,
+    len: u8,
+}
+
+impl BitPrefix {
+    /// Prefix of the first `len` bits of a left-aligned value
+    pub fn new(bits: u128, len: u8) -> Self {
+        assert!(len <= 128, "Prefix length {} is longer than 128 bits", len);
+        BitPrefix { bits: bits & mask(len), len }
+    }
+
+    /// Prefix of the first `len` bits of a 32 bit value, like an IPv4 route
+    pub fn from_u32(value: u32, len: u8) -> Self {
+        assert!(len <= 32, "Prefix length {} is longer than 32 bits", len);
+        BitPrefix::new((value as u128) << 96, len)
+    }
+
+    /// Prefix of the first `len` bits of a 64 bit value
+    pub fn from_u64(value: u64, len: u8) -> Self {
+        assert!(len <= 64, "Prefix length {} is longer than 64 bits", len);
+        BitPrefix::new((value as u128) << 64, len)
+    }
+
+    /// All bits of the bytes, the first byte first
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert!(bytes.len() <= 16, "{} bytes are longer than 128 bits", bytes.len());
+        let bits = bytes
+            .iter()
+            .enumerate()
+            .fold(0u128, |bits, (index, byte)| bits | ((*byte as u128) << (120 - 8 * index)));
+        BitPrefix::new(bits, (bytes.len() * 8) as u8)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bit at `index`, counted from the first bit
+    pub fn bit(&self, index: usize) -> usize {
+        assert!(index < self.len(), "Index {} out of bounds for length {}", index, self.len);
+        ((self.bits >> (127 - index)) & 1) as usize
+    }
+
+    pub fn is_prefix_of(&self, other: &BitPrefix) -> bool {
+        self.len <= other.len && other.truncate(self.len) == *self
+    }
+
+    fn truncate(&self, len: u8) -> BitPrefix {
+        BitPrefix::new(self.bits, len.min(self.len))
+    }
+
+    fn common_len(&self, other: &BitPrefix) -> u8 {
+        ((self.bits ^ other.bits).leading_zeros() as u8).min(self.len).min(other.len)
+    }
+}
+
+fn mask(len: u8) -> u128 {
+    if len == 0 {
+        0
+    } else {
+        u128::MAX << (128 - len as u32)
+    }
+}
+
+type Tree<V> = Option<Box<PatriciaNode<V>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+struct PatriciaNode<V> {
+    prefix: BitPrefix,
+    value: Option<V>,
+    /// Children by the first bit after this node's prefix
+    children: [Tree<V>; 2],
+}
+
+impl<V> PatriciaNode<V> {
+    fn leaf(prefix: BitPrefix, value: V) -> Box<Self> {
+        Box::new(PatriciaNode { prefix, value: Some(value), children: [None, None] })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatriciaTrie<V> {
+    root: Tree<V>,
+    size: usize,
+}
+
+impl<V> PatriciaTrie<V> {
+    pub fn new() -> Self {
+        PatriciaTrie { root: None, size: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = PatriciaTrie::new();
+        check_invariants!(self);
+    }
+
+    /// Inserts the pair. Returns the old value if the prefix was present.
+    pub fn insert(&mut self, prefix: BitPrefix, value: V) -> Option<V> {
+        let old_value = insert(&mut self.root, prefix, value);
+        if old_value.is_none() {
+            self.size += 1;
+        }
+        check_invariants!(self);
+        old_value
+    }
+
+    pub fn get(&self, prefix: &BitPrefix) -> Option<&V> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if !node.prefix.is_prefix_of(prefix) {
+                return None;
+            }
+            if node.prefix.len == prefix.len {
+                return node.value.as_ref();
+            }
+            current = &node.children[prefix.bit(node.prefix.len())];
+        }
+        None
+    }
+
+    pub fn contains_key(&self, prefix: &BitPrefix) -> bool {
+        self.get(prefix).is_some()
+    }
+
+    /// Removes the prefix and merges nodes that are no longer needed
+    pub fn remove(&mut self, prefix: &BitPrefix) -> Option<V> {
+        let removed = remove(&mut self.root, prefix);
+        if removed.is_some() {
+            self.size -= 1;
+        }
+        check_invariants!(self);
+        removed
+    }
+
+    /// Longest stored prefix of `key` and its value
+    pub fn longest_prefix_match(&self, key: &BitPrefix) -> Option<(BitPrefix, &V)> {
+        let mut best = None;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if !node.prefix.is_prefix_of(key) {
+                break;
+            }
+            if let Some(value) = &node.value {
+                best = Some((node.prefix, value));
+            }
+            if node.prefix.len == key.len {
+                break;
+            }
+            current = &node.children[key.bit(node.prefix.len())];
+        }
+        best
+    }
+
+    /// Pairs in prefix order, a prefix before all its extensions
+    pub fn iter(&self) -> impl Iterator<Item = (BitPrefix, &V)> + '_ {
+        let mut stack: Vec<&PatriciaNode<V>> = self.root.as_deref().into_iter().collect();
+        std::iter::from_fn(move || loop {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev().flatten().map(|child| child.as_ref()));
+            if let Some(value) = &node.value {
+                return Some((node.prefix, value));
+            }
+        })
+    }
+}
+
+impl<V> Default for PatriciaTrie<V> {
+    fn default() -> Self {
+        PatriciaTrie::new()
+    }
+}
+
+/// The items of a trie are its keys
+impl<V> Collection for PatriciaTrie<V> {
+    type Item = BitPrefix;
+
+    fn len(&self) -> usize {
+        PatriciaTrie::len(self)
+    }
+
+    fn clear(&mut self) {
+        PatriciaTrie::clear(self);
+    }
+
+    fn contains(&self, key: &BitPrefix) -> bool {
+        self.contains_key(key)
+    }
+}
+
+fn insert<V>(tree: &mut Tree<V>, prefix: BitPrefix, value: V) -> Option<V> {
+    let Some(node) = tree else {
+        *tree = Some(PatriciaNode::leaf(prefix, value));
+        return None;
+    };
+    let common = node.prefix.common_len(&prefix);
+    if common == node.prefix.len {
+        if common == prefix.len {
+            return node.value.replace(value);
+        }
+        return insert(&mut node.children[prefix.bit(common as usize)], prefix, value);
+    }
+
+    // The prefixes part ways inside this node's prefix, split it there
+    let old = tree.take().unwrap();
+    let mut split = Box::new(PatriciaNode {
+        prefix: prefix.truncate(common),
+        value: None,
+        children: [None, None],
+    });
+    let old_side = old.prefix.bit(common as usize);
+    split.children[old_side] = Some(old);
+    if common == prefix.len {
+        split.value = Some(value);
+    } else {
+        split.children[1 - old_side] = Some(PatriciaNode::leaf(prefix, value));
+    }
+    *tree = Some(split);
+    None
+}
+
+fn remove<V>(tree: &mut Tree<V>, prefix: &BitPrefix) -> Option<V> {
+    let node = tree.as_mut()?;
+    if !node.prefix.is_prefix_of(prefix) {
+        return None;
+    }
+    let removed = if node.prefix.len == prefix.len {
+        node.value.take()
+    } else {
+        remove(&mut node.children[prefix.bit(node.prefix.len())], prefix)
+    };
+
+    // A node without a value is only needed while it has two children
+    if node.value.is_none() {
+        match &mut node.children {
+            [None, None] => *tree = None,
+            [Some(_), None] | [None, Some(_)] => {
+                let [left, right] = &mut node.children;
+                let child = left.take().or_else(|| right.take());
+                *tree = child;
+            }
+            _ => {}
+        }
+    }
+    removed
+}
+
+/// Checks every node without a value has two children and every child
+/// extends its parent on the side of its first extra bit. Returns the
+/// number of values in the tree.
+#[cfg(any(test, feature = "debug-invariants"))]
+fn assert_compressed<V>(tree: &Tree<V>) -> usize {
+    let Some(node) = tree else {
+        return 0;
+    };
+    if node.value.is_none() {
+        assert!(node.children.iter().all(Option::is_some), "Node without a value has one child");
+    }
+    let mut values = usize::from(node.value.is_some());
+    for (bit, child) in node.children.iter().enumerate() {
+        if let Some(child) = child {
+            assert!(node.prefix.is_prefix_of(&child.prefix), "Child doesn't extend its parent");
+            assert!(child.prefix.len > node.prefix.len, "Child is no longer than its parent");
+            assert_eq!(child.prefix.bit(node.prefix.len()), bit, "Child is on the wrong side");
+        }
+        values += assert_compressed(child);
+    }
+    values
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<V> Invariants for PatriciaTrie<V> {
+    fn assert_invariants(&self) {
+        assert_eq!(assert_compressed(&self.root), self.size, "size doesn't match the values");
+    }
+}
+
+impl<V: HeapSize> HeapSize for PatriciaNode<V> {
+    fn heap_size(&self) -> usize {
+        self.value.heap_size() + self.children.heap_size()
+    }
+}
+
+impl<V: HeapSize> HeapSize for PatriciaTrie<V> {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipv4(a: u8, b: u8, c: u8, d: u8, len: u8) -> BitPrefix {
+        BitPrefix::from_u32(u32::from_be_bytes([a, b, c, d]), len)
+    }
+
+    #[test]
+    fn when_trie_is_created_it_is_empty() {
+        let trie = PatriciaTrie::<i32>::new();
+
+        assert!(trie.is_empty());
+        assert_eq!(trie.get(&BitPrefix::from_u32(0, 0)), None);
+        assert_eq!(trie.longest_prefix_match(&BitPrefix::from_u32(1, 32)), None);
+    }
+
+    #[test]
+    fn test_bit_prefix() {
+        let prefix = BitPrefix::from_bytes(&[0b1010_0000, 0xff]);
+
+        assert_eq!(prefix.len(), 16);
+        assert_eq!(prefix.bit(0), 1);
+        assert_eq!(prefix.bit(1), 0);
+        assert!(BitPrefix::from_bytes(&[0b1010_0000]).is_prefix_of(&prefix));
+        assert_eq!(BitPrefix::from_u32(0xffff_ffff, 4), BitPrefix::new(0xf << 124, 4));
+    }
+
+    #[test]
+    fn test_insert_get_and_update() {
+        let mut trie = PatriciaTrie::new();
+
+        assert_eq!(trie.insert(ipv4(10, 0, 0, 0, 8), "a"), None);
+        assert_eq!(trie.insert(ipv4(10, 1, 0, 0, 16), "b"), None);
+        assert_eq!(trie.insert(ipv4(10, 0, 0, 0, 8), "c"), Some("a"));
+
+        assert_eq!(trie.get(&ipv4(10, 0, 0, 0, 8)), Some(&"c"));
+        assert_eq!(trie.get(&ipv4(10, 0, 0, 0, 16)), None);
+        assert_eq!(trie.len(), 2);
+        trie.assert_invariants();
+    }
+
+    #[test]
+    fn test_longest_prefix_match_picks_most_specific_route() {
+        let mut trie = PatriciaTrie::new();
+        trie.insert(ipv4(0, 0, 0, 0, 0), "default");
+        trie.insert(ipv4(192, 168, 0, 0, 16), "lan");
+        trie.insert(ipv4(192, 168, 1, 0, 24), "office");
+
+        let route = |address: [u8; 4]| {
+            let key = BitPrefix::from_u32(u32::from_be_bytes(address), 32);
+            trie.longest_prefix_match(&key).map(|(_, value)| *value)
+        };
+
+        assert_eq!(route([192, 168, 1, 7]), Some("office"));
+        assert_eq!(route([192, 168, 2, 7]), Some("lan"));
+        assert_eq!(route([8, 8, 8, 8]), Some("default"));
+        let (prefix, _) = trie.longest_prefix_match(&ipv4(192, 168, 1, 7, 32)).unwrap();
+        assert_eq!(prefix, ipv4(192, 168, 1, 0, 24));
+    }
+
+    #[test]
+    fn test_remove_merges_nodes() {
+        let mut trie = PatriciaTrie::new();
+        let prefixes = [ipv4(10, 0, 0, 0, 8), ipv4(10, 128, 0, 0, 9), ipv4(10, 0, 0, 0, 9)];
+        for (value, prefix) in prefixes.iter().enumerate() {
+            trie.insert(*prefix, value);
+        }
+
+        assert_eq!(trie.remove(&prefixes[0]), Some(0));
+        assert_eq!(trie.remove(&prefixes[0]), None);
+        trie.assert_invariants();
+        assert_eq!(trie.remove(&prefixes[1]), Some(1));
+        trie.assert_invariants();
+
+        assert_eq!(trie.root.as_ref().unwrap().prefix, prefixes[2]);
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn test_random_operations_match_btree_map() {
+        let mut trie = PatriciaTrie::new();
+        let mut expected = std::collections::BTreeMap::new();
+        let mut state: u64 = 7;
+
+        for step in 0..3000u32 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let prefix = BitPrefix::from_u32((state >> 40) as u32 & 0xff00_0000, (state % 9) as u8);
+            if state.is_multiple_of(3) {
+                assert_eq!(trie.remove(&prefix), expected.remove(&prefix));
+            } else {
+                assert_eq!(trie.insert(prefix, step), expected.insert(prefix, step));
+            }
+        }
+
+        trie.assert_invariants();
+        assert_eq!(trie.len(), expected.len());
+        let mut pairs: Vec<(BitPrefix, u32)> = trie.iter().map(|(k, v)| (k, *v)).collect();
+        pairs.sort();
+        assert_eq!(pairs, expected.into_iter().collect::<Vec<_>>());
+    }
+}
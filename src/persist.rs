@@ -0,0 +1,293 @@
+//! Binary save and load
+//! `save_to` writes a structure to any `Write` and `load_from` reads it back
+//! from any `Read`, without serde. The encoding starts with a header: the
+//! bytes `DSR`, a format version and a byte for the kind of structure, so a
+//! stack can't be loaded as a queue by mistake. Then comes the number of
+//! items and the items themselves. Numbers are little-endian and fixed
+//! width, strings are their length followed by their UTF-8 bytes. Sequences
+//! are stored first item first: the front of the list and the queue, the
+//! bottom of the stack. Map pairs are stored key first, in no particular
+//! order. Several structures can be written one after the other to the same
+//! writer and read back in the same order.
+
+use std::fmt::{ Debug, Display };
+use std::hash::Hash;
+use std::io::{ self, Read, Write };
+
+use crate::hash_map::HashMap;
+use crate::queue::Queue;
+use crate::singly_linked_list::SinglyLinkedList;
+use crate::stack::Stack;
+
+const MAGIC: &[u8; 3] = b"DSR";
+const VERSION: u8 = 1;
+
+const LIST: u8 = b'L';
+const STACK: u8 = b'S';
+const QUEUE: u8 = b'Q';
+const MAP: u8 = b'M';
+
+/// Items that can be written and read back
+pub trait Codec: Sized {
+    fn encode(&self, out: &mut dyn Write) -> io::Result<()>;
+
+    fn decode(input: &mut dyn Read) -> io::Result<Self>;
+}
+
+pub trait Persist: Sized {
+    fn save_to(&self, out: impl Write) -> io::Result<()>;
+
+    fn load_from(input: impl Read) -> io::Result<Self>;
+}
+
+macro_rules! impl_codec_for_numbers {
+    ($($number:ty),*) => {
+        $(
+            impl Codec for $number {
+                fn encode(&self, out: &mut dyn Write) -> io::Result<()> {
+                    out.write_all(&self.to_le_bytes())
+                }
+
+                fn decode(input: &mut dyn Read) -> io::Result<Self> {
+                    let mut bytes = [0; std::mem::size_of::<$number>()];
+                    input.read_exact(&mut bytes)?;
+                    Ok(<$number>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_numbers!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+/// Stored as 64 bits, so files move between 32 and 64-bit machines
+impl Codec for usize {
+    fn encode(&self, out: &mut dyn Write) -> io::Result<()> {
+        (*self as u64).encode(out)
+    }
+
+    fn decode(input: &mut dyn Read) -> io::Result<Self> {
+        usize::try_from(u64::decode(input)?).map_err(|_| invalid("length doesn't fit in usize"))
+    }
+}
+
+impl Codec for isize {
+    fn encode(&self, out: &mut dyn Write) -> io::Result<()> {
+        (*self as i64).encode(out)
+    }
+
+    fn decode(input: &mut dyn Read) -> io::Result<Self> {
+        isize::try_from(i64::decode(input)?).map_err(|_| invalid("number doesn't fit in isize"))
+    }
+}
+
+impl Codec for bool {
+    fn encode(&self, out: &mut dyn Write) -> io::Result<()> {
+        (*self as u8).encode(out)
+    }
+
+    fn decode(input: &mut dyn Read) -> io::Result<Self> {
+        match u8::decode(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("bool is neither 0 nor 1")),
+        }
+    }
+}
+
+impl Codec for char {
+    fn encode(&self, out: &mut dyn Write) -> io::Result<()> {
+        (*self as u32).encode(out)
+    }
+
+    fn decode(input: &mut dyn Read) -> io::Result<Self> {
+        char::from_u32(u32::decode(input)?).ok_or_else(|| invalid("not a unicode scalar value"))
+    }
+}
+
+impl Codec for String {
+    fn encode(&self, out: &mut dyn Write) -> io::Result<()> {
+        self.len().encode(out)?;
+        out.write_all(self.as_bytes())
+    }
+
+    fn decode(input: &mut dyn Read) -> io::Result<Self> {
+        let len = usize::decode(input)?;
+        // `take` stops a corrupt length from reserving more than the input holds
+        let mut bytes = Vec::new();
+        input.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        String::from_utf8(bytes).map_err(|_| invalid("string is not UTF-8"))
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_header(out: &mut dyn Write, kind: u8, len: usize) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION, kind])?;
+    len.encode(out)
+}
+
+/// Checks the header and returns the number of items that follow
+fn read_header(input: &mut dyn Read, kind: u8) -> io::Result<usize> {
+    let mut header = [0; 5];
+    input.read_exact(&mut header)?;
+    if &header[..3] != MAGIC {
+        return Err(invalid("not a saved structure"));
+    }
+    if header[3] != VERSION {
+        return Err(invalid("unsupported format version"));
+    }
+    if header[4] != kind {
+        return Err(invalid("saved structure is of another kind"));
+    }
+    usize::decode(input)
+}
+
+fn save_items<'a, T: Codec + 'a>(
+    mut out: impl Write,
+    kind: u8,
+    len: usize,
+    items: impl Iterator<Item = &'a T>
+) -> io::Result<()> {
+    write_header(&mut out, kind, len)?;
+    for item in items {
+        item.encode(&mut out)?;
+    }
+    out.flush()
+}
+
+fn load_items<T: Codec>(mut input: impl Read, kind: u8) -> io::Result<Vec<T>> {
+    let len = read_header(&mut input, kind)?;
+    (0..len).map(|_| T::decode(&mut input)).collect()
+}
+
+impl<T: Codec + PartialEq + Debug + Clone + Display> Persist for SinglyLinkedList<T> {
+    fn save_to(&self, out: impl Write) -> io::Result<()> {
+        save_items(out, LIST, self.len(), self.iter())
+    }
+
+    fn load_from(input: impl Read) -> io::Result<Self> {
+        load_items(input, LIST).map(SinglyLinkedList::from)
+    }
+}
+
+impl<T: Codec> Persist for Stack<T> {
+    fn save_to(&self, out: impl Write) -> io::Result<()> {
+        save_items(out, STACK, self.iter().len(), self.iter())
+    }
+
+    fn load_from(input: impl Read) -> io::Result<Self> {
+        load_items(input, STACK).map(Stack::from)
+    }
+}
+
+impl<T: Codec + Copy> Persist for Queue<T> {
+    fn save_to(&self, out: impl Write) -> io::Result<()> {
+        save_items(out, QUEUE, self.iter().count(), self.iter())
+    }
+
+    fn load_from(input: impl Read) -> io::Result<Self> {
+        load_items(input, QUEUE).map(Queue::from)
+    }
+}
+
+impl<K, V> Persist for HashMap<K, V>
+where
+    K: Codec + Hash + Clone + PartialEq + Debug,
+    V: Codec + Clone + Debug,
+{
+    fn save_to(&self, mut out: impl Write) -> io::Result<()> {
+        write_header(&mut out, MAP, self.len())?;
+        for (key, value) in self.iter() {
+            key.encode(&mut out)?;
+            value.encode(&mut out)?;
+        }
+        out.flush()
+    }
+
+    fn load_from(mut input: impl Read) -> io::Result<Self> {
+        let len = read_header(&mut input, MAP)?;
+        let mut map = HashMap::new();
+        for _ in 0..len {
+            let key = K::decode(&mut input)?;
+            map.insert(key, V::decode(&mut input)?);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `unwrap_err` needs Debug, which the stack and queue don't have
+    fn failure<T>(result: io::Result<T>) -> io::Error {
+        result.err().expect("loading should have failed")
+    }
+
+    #[test]
+    fn test_round_trip_every_structure_through_one_buffer() {
+        let list = SinglyLinkedList::from(vec!["a".to_string(), "ü".to_string()]);
+        let stack = Stack::from(vec![1i64, -2, 3]);
+        let queue = Queue::from(vec![1.5f64, 2.5]);
+        let mut map = HashMap::new();
+        map.insert('x', true);
+        map.insert('y', false);
+
+        let mut buffer = Vec::new();
+        list.save_to(&mut buffer).unwrap();
+        stack.save_to(&mut buffer).unwrap();
+        queue.save_to(&mut buffer).unwrap();
+        map.save_to(&mut buffer).unwrap();
+        let mut input = buffer.as_slice();
+
+        assert_eq!(SinglyLinkedList::<String>::load_from(&mut input).unwrap(), list);
+        assert_eq!(Vec::from(Stack::<i64>::load_from(&mut input).unwrap()), vec![1, -2, 3]);
+        assert_eq!(Vec::from(Queue::<f64>::load_from(&mut input).unwrap()), vec![1.5, 2.5]);
+        let map = HashMap::<char, bool>::load_from(&mut input).unwrap();
+        assert_eq!((map.len(), map.get('x'), map.get('y')), (2, Some(true), Some(false)));
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_encoding_is_compact() {
+        let mut buffer = Vec::new();
+
+        Stack::from(vec![7u8, 8]).save_to(&mut buffer).unwrap();
+
+        assert_eq!(buffer, [b'D', b'S', b'R', 1, b'S', 2, 0, 0, 0, 0, 0, 0, 0, 7, 8]);
+    }
+
+    #[test]
+    fn test_bad_input_is_invalid_data() {
+        let mut stack = Vec::new();
+        Stack::from(vec![1u8]).save_to(&mut stack).unwrap();
+        let mut future = stack.clone();
+        future[3] = 2;
+
+        let kind = failure(Queue::<u8>::load_from(stack.as_slice()));
+        let version = failure(Stack::<u8>::load_from(future.as_slice()));
+        let magic = failure(Stack::<u8>::load_from(&b"XYZ\x01S"[..]));
+
+        for error in [kind, version, magic] {
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn when_input_ends_early_loading_fails() {
+        let mut buffer = Vec::new();
+        Stack::from(vec!["long string".to_string()]).save_to(&mut buffer).unwrap();
+        buffer.truncate(buffer.len() - 3);
+
+        let error = failure(Stack::<String>::load_from(buffer.as_slice()));
+
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,445 @@
+//! Persistent Hash Array Mapped Trie
+//! Map stored in a 32-way trie indexed by 5 bits of the key hash per level.
+//! A branch keeps only its existing children in a dense vector, together
+//! with a 32-bit bitmap of which slots they occupy, and the position of a
+//! child is the popcount of the bits below its slot. An update copies just
+//! the nodes on the path to the key and shares everything else with the old
+//! version. Keys whose full hashes collide share one leaf.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+use std::rc::Rc;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+const BITS_PER_LEVEL: u32 = 5;
+const SLOT_MASK: u64 = (1 << BITS_PER_LEVEL) - 1;
+
+#[derive(Debug)]
+enum HamtNode<K, V> {
+    Branch {
+        bitmap: u32,
+        children: Vec<Rc<HamtNode<K, V>>>,
+    },
+    /// All pairs have the same hash, more than one only on collisions
+    Leaf {
+        hash: u64,
+        pairs: Vec<(K, V)>,
+    },
+}
+
+#[derive(Debug)]
+pub struct HamtMap<K, V> {
+    root: Option<Rc<HamtNode<K, V>>>,
+    len: usize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> HamtMap<K, V> {
+    pub fn new() -> Self {
+        HamtMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let hash = hash_of(key);
+        let mut node = self.root.as_ref()?;
+        let mut shift = 0;
+        loop {
+            match node.as_ref() {
+                HamtNode::Branch { bitmap, children } => {
+                    let bit = slot_bit(hash, shift);
+                    if bitmap & bit == 0 {
+                        return None;
+                    }
+                    node = &children[child_position(*bitmap, bit)];
+                    shift += BITS_PER_LEVEL;
+                }
+                HamtNode::Leaf { hash: leaf_hash, pairs } => {
+                    if *leaf_hash != hash {
+                        return None;
+                    }
+                    return pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+                }
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// New version with the pair inserted or its value replaced.
+    /// Copies O(log32 n) nodes, this version is left unchanged.
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let hash = hash_of(&key);
+        let (root, added) = match &self.root {
+            Some(root) => insert(root, hash, 0, key, value),
+            None => (Rc::new(HamtNode::Leaf { hash, pairs: vec![(key, value)] }), true),
+        };
+        let map = HamtMap { root: Some(root), len: self.len + added as usize };
+        check_invariants!(&map);
+        map
+    }
+
+    /// New version without the key. Shares the whole trie with this
+    /// version if the key wasn't present.
+    pub fn remove(&self, key: &K) -> Self {
+        let Some(root) = &self.root else {
+            return self.clone();
+        };
+        let Some(root) = remove(root, hash_of(key), 0, key) else {
+            return self.clone();
+        };
+        let map = HamtMap { root, len: self.len - 1 };
+        check_invariants!(&map);
+        map
+    }
+
+    /// All pairs, in hash order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        let mut stack: Vec<&HamtNode<K, V>> = self.root.iter().map(|root| root.as_ref()).collect();
+        let mut pairs: std::slice::Iter<'_, (K, V)> = [].iter();
+        std::iter::from_fn(move || {
+            loop {
+                if let Some((key, value)) = pairs.next() {
+                    return Some((key, value));
+                }
+                match stack.pop()? {
+                    HamtNode::Branch { children, .. } => {
+                        stack.extend(children.iter().rev().map(|child| child.as_ref()));
+                    }
+                    HamtNode::Leaf { pairs: leaf_pairs, .. } => pairs = leaf_pairs.iter(),
+                }
+            }
+        })
+    }
+}
+
+/// Cloning only copies the pointer to the root
+impl<K, V> Clone for HamtMap<K, V> {
+    fn clone(&self) -> Self {
+        HamtMap { root: self.root.clone(), len: self.len }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for HamtMap<K, V> {
+    fn default() -> Self {
+        HamtMap::new()
+    }
+}
+
+/// Checks every branch has a child per bit of its bitmap and isn't left
+/// with a lone leaf, every leaf sits under the slots of its hash and holds
+/// distinct keys of that hash, and the length matches the pairs
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Hash + Eq, V> Invariants for HamtMap<K, V> {
+    fn assert_invariants(&self) {
+        let pairs = self.root.as_ref().map_or(0, |root| assert_node(root, 0, 0));
+        assert_eq!(pairs, self.len, "len doesn't match the pairs");
+    }
+}
+
+/// Number of pairs below the node, whose hashes all start with the low
+/// `shift` bits of `prefix`
+#[cfg(any(test, feature = "debug-invariants"))]
+fn assert_node<K: Hash + Eq, V>(node: &HamtNode<K, V>, shift: u32, prefix: u64) -> usize {
+    match node {
+        HamtNode::Branch { bitmap, children } => {
+            assert_eq!(bitmap.count_ones() as usize, children.len(), "Bitmap doesn't match");
+            let lone_leaf =
+                children.len() == 1 && matches!(children[0].as_ref(), HamtNode::Leaf { .. });
+            assert!(!children.is_empty() && !lone_leaf, "Branch should have been collapsed");
+            let slots = (0..u64::from(u32::BITS)).filter(|slot| bitmap & (1 << slot) != 0);
+            slots
+                .zip(children)
+                .map(|(slot, child)| {
+                    assert_node(child, shift + BITS_PER_LEVEL, prefix | (slot << shift))
+                })
+                .sum()
+        }
+        HamtNode::Leaf { hash, pairs } => {
+            let mask = 1u64.checked_shl(shift).map_or(u64::MAX, |bit| bit - 1);
+            assert_eq!(hash & mask, prefix, "Leaf is under the wrong slot");
+            assert!(!pairs.is_empty(), "Leaf is empty");
+            for (index, (key, _)) in pairs.iter().enumerate() {
+                assert_eq!(hash_of(key), *hash, "Key has another hash than its leaf");
+                assert!(pairs[..index].iter().all(|(other, _)| other != key), "Key is repeated");
+            }
+            pairs.len()
+        }
+    }
+}
+
+fn hash_of<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn slot_bit(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & SLOT_MASK)
+}
+
+/// Index in the dense children vector of the child owning `bit`
+fn child_position(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
+
+/// Returns the new node and whether a new key was added
+fn insert<K: Eq + Clone, V: Clone>(
+    node: &Rc<HamtNode<K, V>>,
+    hash: u64,
+    shift: u32,
+    key: K,
+    value: V
+) -> (Rc<HamtNode<K, V>>, bool) {
+    match node.as_ref() {
+        HamtNode::Branch { bitmap, children } => {
+            let bit = slot_bit(hash, shift);
+            let position = child_position(*bitmap, bit);
+            let mut children = children.clone();
+            let added = if bitmap & bit == 0 {
+                let leaf = HamtNode::Leaf { hash, pairs: vec![(key, value)] };
+                children.insert(position, Rc::new(leaf));
+                true
+            } else {
+                let child_shift = shift + BITS_PER_LEVEL;
+                let (child, added) = insert(&children[position], hash, child_shift, key, value);
+                children[position] = child;
+                added
+            };
+            (Rc::new(HamtNode::Branch { bitmap: bitmap | bit, children }), added)
+        }
+        HamtNode::Leaf { hash: leaf_hash, pairs } if *leaf_hash == hash => {
+            let mut pairs = pairs.clone();
+            let added = match pairs.iter_mut().find(|(k, _)| *k == key) {
+                Some(pair) => {
+                    pair.1 = value;
+                    false
+                }
+                None => {
+                    pairs.push((key, value));
+                    true
+                }
+            };
+            (Rc::new(HamtNode::Leaf { hash, pairs }), added)
+        }
+        HamtNode::Leaf { hash: leaf_hash, .. } => {
+            // Different hashes: push the old leaf one level down and retry
+            let branch = Rc::new(HamtNode::Branch {
+                bitmap: slot_bit(*leaf_hash, shift),
+                children: vec![node.clone()],
+            });
+            insert(&branch, hash, shift, key, value)
+        }
+    }
+}
+
+/// None if the key wasn't found, otherwise the new node, which is None
+/// when nothing is left below it
+fn remove<K: Eq + Clone, V: Clone>(
+    node: &Rc<HamtNode<K, V>>,
+    hash: u64,
+    shift: u32,
+    key: &K
+) -> Option<Option<Rc<HamtNode<K, V>>>> {
+    match node.as_ref() {
+        HamtNode::Branch { bitmap, children } => {
+            let bit = slot_bit(hash, shift);
+            if bitmap & bit == 0 {
+                return None;
+            }
+            let position = child_position(*bitmap, bit);
+            let new_child = remove(&children[position], hash, shift + BITS_PER_LEVEL, key)?;
+            let mut children = children.clone();
+            let mut bitmap = *bitmap;
+            match new_child {
+                Some(child) => children[position] = child,
+                None => {
+                    children.remove(position);
+                    bitmap &= !bit;
+                }
+            }
+            // A branch left with a single leaf is replaced by that leaf
+            match children.as_slice() {
+                [] => Some(None),
+                [only] if matches!(only.as_ref(), HamtNode::Leaf { .. }) => {
+                    Some(Some(only.clone()))
+                }
+                _ => Some(Some(Rc::new(HamtNode::Branch { bitmap, children }))),
+            }
+        }
+        HamtNode::Leaf { hash: leaf_hash, pairs } => {
+            if *leaf_hash != hash {
+                return None;
+            }
+            let index = pairs.iter().position(|(k, _)| k == key)?;
+            if pairs.len() == 1 {
+                return Some(None);
+            }
+            let mut pairs = pairs.clone();
+            pairs.remove(index);
+            Some(Some(Rc::new(HamtNode::Leaf { hash, pairs })))
+        }
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for HamtNode<K, V> {
+    fn heap_size(&self) -> usize {
+        match self {
+            HamtNode::Branch { children, .. } => children.heap_size(),
+            HamtNode::Leaf { pairs, .. } => pairs.heap_size(),
+        }
+    }
+}
+
+/// Counts the whole trie, though most of it is usually shared with other
+/// versions
+impl<K: HeapSize, V: HeapSize> HeapSize for HamtMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Key whose hash only depends on `bucket`, to force collisions
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct CollidingKey {
+        bucket: u8,
+        id: u32,
+    }
+
+    impl Hash for CollidingKey {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.bucket.hash(state);
+        }
+    }
+
+    fn sorted_pairs(map: &HamtMap<u32, u32>) -> Vec<(u32, u32)> {
+        let mut pairs: Vec<(u32, u32)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn when_map_is_created_it_is_empty() {
+        let map = HamtMap::<u32, u32>::new();
+
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let map = HamtMap::new().insert("a", 1).insert("b", 2).insert("a", 3);
+
+        assert_eq!(map.get(&"a"), Some(&3));
+        assert_eq!(map.get(&"b"), Some(&2));
+        assert_eq!(map.get(&"c"), None);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_old_versions_are_unchanged() {
+        let mut versions = vec![HamtMap::new()];
+        for i in 0..100u32 {
+            let next = versions.last().unwrap().insert(i, i * 2);
+            versions.push(next);
+        }
+        let removed = versions[100].remove(&50);
+
+        for (size, version) in versions.iter().enumerate() {
+            assert_eq!(version.len(), size);
+            let expected: Vec<(u32, u32)> = (0..size as u32).map(|i| (i, i * 2)).collect();
+            assert_eq!(sorted_pairs(version), expected);
+        }
+        assert!(versions[100].contains_key(&50));
+        assert!(!removed.contains_key(&50));
+        assert_eq!(removed.len(), 99);
+    }
+
+    #[test]
+    fn test_update_shares_untouched_nodes() {
+        let map = (0..1000u32).fold(HamtMap::new(), |map, i| map.insert(i, i));
+
+        let updated = map.insert(7, 70);
+
+        let children = |map: &HamtMap<u32, u32>| match map.root.as_deref() {
+            Some(HamtNode::Branch { children, .. }) => children.clone(),
+            _ => panic!("Root should be a branch"),
+        };
+        let (old, new) = (children(&map), children(&updated));
+        let shared = old.iter().zip(&new).filter(|(a, b)| Rc::ptr_eq(a, b)).count();
+        assert_eq!(shared, old.len() - 1, "Only the child on the path to the key is copied");
+        assert_eq!(map.get(&7), Some(&7));
+        assert_eq!(updated.get(&7), Some(&70));
+    }
+
+    #[test]
+    fn test_hash_collisions() {
+        let keys: Vec<CollidingKey> = (0..5).map(|id| CollidingKey { bucket: 1, id }).collect();
+        let map = keys.iter().fold(HamtMap::new(), |map, key| map.insert(key.clone(), key.id));
+        let other = CollidingKey { bucket: 2, id: 0 };
+        let map = map.insert(other.clone(), 99);
+
+        assert!(keys.iter().all(|key| map.get(key) == Some(&key.id)));
+        assert_eq!(map.get(&other), Some(&99));
+
+        let map = map.remove(&keys[2]);
+        assert_eq!(map.get(&keys[2]), None);
+        assert_eq!(map.get(&keys[3]), Some(&3));
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn test_remove_missing_key_shares_everything() {
+        let map = HamtMap::new().insert(1, 1);
+
+        let same = map.remove(&2);
+
+        assert!(Rc::ptr_eq(map.root.as_ref().unwrap(), same.root.as_ref().unwrap()));
+        assert_eq!(same.len(), 1);
+    }
+
+    #[test]
+    fn test_random_operations_match_hash_map() {
+        let mut map = HamtMap::new();
+        let mut expected = HashMap::new();
+        let mut state = 5u64;
+
+        for step in 0..3000u32 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let key = ((state >> 33) % 300) as u32;
+            if (state >> 20).is_multiple_of(3) {
+                map = map.remove(&key);
+                expected.remove(&key);
+            } else {
+                map = map.insert(key, step);
+                expected.insert(key, step);
+            }
+            assert_eq!(map.len(), expected.len());
+        }
+
+        let mut expected: Vec<(u32, u32)> = expected.into_iter().collect();
+        expected.sort();
+        assert_eq!(sorted_pairs(&map), expected);
+        map = (0..300).fold(map, |map, key| map.remove(&key));
+        assert!(map.is_empty());
+        assert!(map.root.is_none());
+    }
+}
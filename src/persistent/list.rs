@@ -0,0 +1,249 @@
+//! Persistent singly linked list
+//! A list is a pointer to its first node. `push_front` creates one node
+//! pointing at the old list and `tail` returns the list starting at the
+//! second node, both in O(1) and without copying, so many lists can share
+//! the same suffix.
+
+use std::fmt::{ self, Debug };
+use std::rc::Rc;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+struct ListNode<T> {
+    item: T,
+    next: Option<Rc<ListNode<T>>>,
+}
+
+pub struct List<T> {
+    head: Option<Rc<ListNode<T>>>,
+    len: usize,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// New list with the item in front of this one, sharing all its nodes
+    pub fn push_front(&self, item: T) -> List<T> {
+        let list = self.linked(item);
+        check_invariants!(&list);
+        list
+    }
+
+    /// `push_front` without the invariant check
+    fn linked(&self, item: T) -> List<T> {
+        List {
+            head: Some(Rc::new(ListNode { item, next: self.head.clone() })),
+            len: self.len + 1,
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.item)
+    }
+
+    /// The list without its first item, None for an empty list
+    pub fn tail(&self) -> Option<List<T>> {
+        self.head.as_ref().map(|node| List { head: node.next.clone(), len: self.len - 1 })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: self.head.as_deref() }
+    }
+
+    /// True if both lists start with the very same node
+    pub fn ptr_eq(&self, other: &List<T>) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: Clone> List<T> {
+    /// New list with the items in reverse order, O(n)
+    pub fn reverse(&self) -> List<T> {
+        let list = self.iter().fold(List::new(), |list, item| list.linked(item.clone()));
+        check_invariants!(&list);
+        list
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for List<T> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.iter().count(), self.len, "len doesn't match the nodes");
+    }
+}
+
+/// Cloning only copies the pointer to the first node
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List { head: self.head.clone(), len: self.len }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+/// Frees nodes in a loop, recursive drops could overflow the stack on long
+/// lists. Stops at the first node that another list still uses.
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut next = self.head.take();
+        while let Some(node) = next {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => next = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Builds a list with the items in iteration order
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let items: Vec<T> = iter.into_iter().collect();
+        let list = items.into_iter().rev().fold(List::new(), |list, item| list.linked(item));
+        check_invariants!(&list);
+        list
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a ListNode<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next?;
+        self.next = node.next.as_deref();
+        Some(&node.item)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Nodes shared with other versions are counted too
+impl<T: HeapSize> HeapSize for List<T> {
+    fn heap_size(&self) -> usize {
+        let node_size = 2 * std::mem::size_of::<usize>() + std::mem::size_of::<ListNode<T>>();
+        self.iter().map(|item| node_size + item.heap_size()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_list_is_created_it_is_empty() {
+        let list = List::<i32>::new();
+
+        assert!(list.is_empty());
+        assert_eq!(list.head(), None);
+        assert!(list.tail().is_none());
+    }
+
+    #[test]
+    fn test_push_front_keeps_old_version() {
+        let empty = List::new();
+        let one = empty.push_front(1);
+        let two = one.push_front(2);
+
+        assert!(empty.is_empty());
+        assert_eq!(one.iter().copied().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(two.iter().copied().collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(two.len(), 2);
+    }
+
+    #[test]
+    fn test_versions_share_their_suffix() {
+        let base: List<i32> = (1..=3).collect();
+
+        let first = base.push_front(10);
+        let second = base.push_front(20);
+
+        assert!(first.tail().unwrap().ptr_eq(&base));
+        assert!(second.tail().unwrap().ptr_eq(&base));
+        assert_eq!(Rc::strong_count(base.head.as_ref().unwrap()), 3);
+    }
+
+    #[test]
+    fn test_tail() {
+        let list: List<&str> = ["a", "b", "c"].into_iter().collect();
+
+        let tail = list.tail().unwrap();
+
+        assert_eq!(tail.head(), Some(&"b"));
+        assert_eq!(tail.len(), 2);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn test_equality_and_debug() {
+        let built = List::new().push_front(3).push_front(2).push_front(1);
+        let collected: List<i32> = vec![1, 2, 3].into_iter().collect();
+
+        assert_eq!(built, collected);
+        assert_ne!(built, collected.tail().unwrap());
+        assert_eq!(format!("{:?}", built), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn test_reverse() {
+        let list: List<i32> = (1..=4).collect();
+
+        assert_eq!(list.reverse(), (1..=4).rev().collect::<List<i32>>());
+    }
+
+    #[test]
+    fn test_long_list_drops_without_overflow() {
+        let list: List<u32> = (0..200_000).collect();
+        let shared = list.tail().unwrap();
+
+        drop(list);
+
+        assert_eq!(shared.len(), 199_999);
+        assert_eq!(shared.head(), Some(&1));
+    }
+}
@@ -0,0 +1,12 @@
+//! Persistent (immutable) collections
+//! Updates never change a collection in place. They return a new version
+//! that shares most of its memory with the old one through `Rc`, so every
+//! version stays valid and cheap to keep around.
+
+mod hamt;
+mod list;
+mod vector;
+
+pub use hamt::HamtMap;
+pub use list::List;
+pub use vector::PersistentVector;
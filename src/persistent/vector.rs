@@ -0,0 +1,444 @@
+//! Persistent Vector as a 32-way trie with a tail
+//! Items live in leaves of 32 in a trie where every branch has up to 32
+//! children, so an index is split into 5-bit slots from the top level down.
+//! With at most 7 levels for any realistic length, lookups are effectively
+//! O(1). The last (up to) 32 items are kept in a separate tail, so most
+//! pushes only copy the tail, and a full tail is moved into the trie as a
+//! new leaf. Every update copies only the path it touches.
+
+use std::fmt::{ self, Debug };
+use std::rc::Rc;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+#[derive(Debug)]
+enum VectorNode<T> {
+    Branch(Vec<Rc<VectorNode<T>>>),
+    Leaf(Vec<T>),
+}
+
+pub struct PersistentVector<T> {
+    len: usize,
+    /// Bit shift of the slot at the root level, 5 for a single level
+    shift: u32,
+    root: Rc<VectorNode<T>>,
+    tail: Rc<Vec<T>>,
+}
+
+impl<T: Clone> PersistentVector<T> {
+    pub fn new() -> Self {
+        PersistentVector {
+            len: 0,
+            shift: BITS,
+            root: Rc::new(VectorNode::Branch(vec![])),
+            tail: Rc::new(vec![]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        if index >= self.tail_offset() {
+            return Some(&self.tail[index & MASK]);
+        }
+        Some(&self.leaf_for(index)[index & MASK])
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// New version with the item appended
+    pub fn push_back(&self, item: T) -> Self {
+        let vector = self.pushed(item);
+        check_invariants!(&vector);
+        vector
+    }
+
+    /// `push_back` without the invariant check
+    fn pushed(&self, item: T) -> Self {
+        if self.len - self.tail_offset() < WIDTH {
+            let mut tail = (*self.tail).clone();
+            tail.push(item);
+            return PersistentVector { len: self.len + 1, tail: Rc::new(tail), ..self.clone() };
+        }
+
+        let leaf = Rc::new(VectorNode::Leaf((*self.tail).clone()));
+        let (root, shift) = if (self.len >> BITS) > (1 << self.shift) {
+            // The trie is full, grow it by one level
+            let branch = VectorNode::Branch(vec![self.root.clone(), new_path(self.shift, leaf)]);
+            (Rc::new(branch), self.shift + BITS)
+        } else {
+            (self.push_leaf(self.shift, &self.root, leaf), self.shift)
+        };
+        PersistentVector { len: self.len + 1, shift, root, tail: Rc::new(vec![item]) }
+    }
+
+    /// New version without the last item, None for an empty vector
+    pub fn pop_back(&self) -> Option<Self> {
+        match self.len {
+            0 => return None,
+            1 => return Some(Self::new()),
+            _ => {}
+        }
+        let vector = if self.len - self.tail_offset() > 1 {
+            let mut tail = (*self.tail).clone();
+            tail.pop();
+            PersistentVector { len: self.len - 1, tail: Rc::new(tail), ..self.clone() }
+        } else {
+            self.pop_tail()
+        };
+        check_invariants!(&vector);
+        Some(vector)
+    }
+
+    /// `pop_back` for a tail with one item: the tail becomes empty, so the
+    /// last leaf of the trie becomes the tail
+    fn pop_tail(&self) -> Self {
+        let tail = Rc::new(self.leaf_for(self.len - 2).to_vec());
+        let mut root = self
+            .pop_leaf(self.shift, &self.root)
+            .unwrap_or_else(|| Rc::new(VectorNode::Branch(vec![])));
+        let mut shift = self.shift;
+        if shift > BITS {
+            if let VectorNode::Branch(children) = root.as_ref() {
+                if children.len() == 1 {
+                    root = children[0].clone();
+                    shift -= BITS;
+                }
+            }
+        }
+        PersistentVector { len: self.len - 1, shift, root, tail }
+    }
+
+    /// New version with the item at `index` replaced
+    pub fn set(&self, index: usize, item: T) -> Self {
+        assert!(index < self.len, "Index {} out of bounds for length {}", index, self.len);
+        let vector = if index >= self.tail_offset() {
+            let mut tail = (*self.tail).clone();
+            tail[index & MASK] = item;
+            PersistentVector { tail: Rc::new(tail), ..self.clone() }
+        } else {
+            let root = set_in(self.shift, &self.root, index, item);
+            PersistentVector { root, ..self.clone() }
+        };
+        check_invariants!(&vector);
+        vector
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len).map(|index| self.get(index).unwrap())
+    }
+
+    /// Number of items stored in the trie, the rest are in the tail
+    fn tail_offset(&self) -> usize {
+        if self.len < WIDTH {
+            0
+        } else {
+            ((self.len - 1) >> BITS) << BITS
+        }
+    }
+
+    fn leaf_for(&self, index: usize) -> &[T] {
+        let mut node = self.root.as_ref();
+        let mut level = self.shift;
+        loop {
+            match node {
+                VectorNode::Branch(children) => {
+                    node = &children[(index >> level) & MASK];
+                    level -= BITS;
+                }
+                VectorNode::Leaf(items) => return items,
+            }
+        }
+    }
+
+    /// Copies the path to the slot of the next leaf and puts `leaf` there
+    fn push_leaf(
+        &self,
+        level: u32,
+        node: &Rc<VectorNode<T>>,
+        leaf: Rc<VectorNode<T>>
+    ) -> Rc<VectorNode<T>> {
+        let VectorNode::Branch(children) = node.as_ref() else {
+            unreachable!("Leaves are only found at level 0");
+        };
+        let slot = ((self.len - 1) >> level) & MASK;
+        let mut children = children.clone();
+        let child = if level == BITS {
+            leaf
+        } else {
+            match children.get(slot) {
+                Some(child) => self.push_leaf(level - BITS, child, leaf),
+                None => new_path(level - BITS, leaf),
+            }
+        };
+        if slot < children.len() {
+            children[slot] = child;
+        } else {
+            children.push(child);
+        }
+        Rc::new(VectorNode::Branch(children))
+    }
+
+    /// Copies the path to the last leaf without it, None if nothing is left
+    fn pop_leaf(&self, level: u32, node: &Rc<VectorNode<T>>) -> Option<Rc<VectorNode<T>>> {
+        let VectorNode::Branch(children) = node.as_ref() else {
+            unreachable!("Leaves are only found at level 0");
+        };
+        let slot = ((self.len - 2) >> level) & MASK;
+        let mut children = children.clone();
+        if level > BITS {
+            match self.pop_leaf(level - BITS, &children[slot]) {
+                Some(child) => children[slot] = child,
+                None => children.truncate(slot),
+            }
+        } else {
+            children.truncate(slot);
+        }
+        if children.is_empty() {
+            return None;
+        }
+        Some(Rc::new(VectorNode::Branch(children)))
+    }
+}
+
+/// Checks the trie holds exactly the items before the tail in full leaves,
+/// the tail holds the rest, and the root has no level to spare
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Clone> Invariants for PersistentVector<T> {
+    fn assert_invariants(&self) {
+        assert!(self.shift >= BITS, "Root is below the first level");
+        let trie_len = assert_node(&self.root, self.shift);
+        assert_eq!(trie_len, self.tail_offset(), "Trie has the wrong size");
+        assert_eq!(self.tail.len(), self.len - self.tail_offset(), "Tail has the wrong size");
+        assert!(self.len == 0 || !self.tail.is_empty(), "Tail is empty");
+        if let VectorNode::Branch(children) = self.root.as_ref() {
+            assert!(self.shift == BITS || children.len() > 1, "Root has a single child");
+        }
+    }
+}
+
+/// Number of items below the node, which sits at `level`. Every child but
+/// the last has to be full.
+#[cfg(any(test, feature = "debug-invariants"))]
+fn assert_node<T>(node: &VectorNode<T>, level: u32) -> usize {
+    match node {
+        VectorNode::Leaf(items) => {
+            assert_eq!(level, 0, "Leaf above level 0");
+            assert_eq!(items.len(), WIDTH, "Leaf isn't full");
+            WIDTH
+        }
+        VectorNode::Branch(children) => {
+            assert!(level > 0, "Branch at level 0");
+            assert!(children.len() <= WIDTH, "Branch has too many children");
+            let counts: Vec<usize> =
+                children.iter().map(|child| assert_node(child, level - BITS)).collect();
+            if let Some((_, full)) = counts.split_last() {
+                assert!(full.iter().all(|&count| count == 1 << level), "Inner child isn't full");
+            }
+            counts.iter().sum()
+        }
+    }
+}
+
+/// Chain of single-child branches from `level` down to the leaf
+fn new_path<T>(level: u32, leaf: Rc<VectorNode<T>>) -> Rc<VectorNode<T>> {
+    if level == 0 {
+        return leaf;
+    }
+    Rc::new(VectorNode::Branch(vec![new_path(level - BITS, leaf)]))
+}
+
+/// Copies the path to `index` with the item replaced
+fn set_in<T: Clone>(
+    level: u32,
+    node: &Rc<VectorNode<T>>,
+    index: usize,
+    item: T
+) -> Rc<VectorNode<T>> {
+    match node.as_ref() {
+        VectorNode::Branch(children) => {
+            let slot = (index >> level) & MASK;
+            let mut children = children.clone();
+            children[slot] = set_in(level - BITS, &children[slot], index, item);
+            Rc::new(VectorNode::Branch(children))
+        }
+        VectorNode::Leaf(items) => {
+            let mut items = items.clone();
+            items[index & MASK] = item;
+            Rc::new(VectorNode::Leaf(items))
+        }
+    }
+}
+
+/// Cloning only copies the pointers to the root and the tail
+impl<T> Clone for PersistentVector<T> {
+    fn clone(&self) -> Self {
+        PersistentVector {
+            len: self.len,
+            shift: self.shift,
+            root: self.root.clone(),
+            tail: self.tail.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Default for PersistentVector<T> {
+    fn default() -> Self {
+        PersistentVector::new()
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for PersistentVector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + Debug> Debug for PersistentVector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentVector<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let vector =
+            iter.into_iter().fold(PersistentVector::new(), |vector, item| vector.pushed(item));
+        check_invariants!(&vector);
+        vector
+    }
+}
+
+impl<T: HeapSize> HeapSize for VectorNode<T> {
+    fn heap_size(&self) -> usize {
+        match self {
+            VectorNode::Branch(children) => children.heap_size(),
+            VectorNode::Leaf(items) => items.heap_size(),
+        }
+    }
+}
+
+/// Counts the whole trie, though most of it is usually shared with other
+/// versions
+impl<T: HeapSize> HeapSize for PersistentVector<T> {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size() + self.tail.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_vector_is_created_it_is_empty() {
+        let vector = PersistentVector::<i32>::new();
+
+        assert!(vector.is_empty());
+        assert_eq!(vector.get(0), None);
+        assert_eq!(vector.last(), None);
+        assert!(vector.pop_back().is_none());
+    }
+
+    #[test]
+    fn test_push_and_get_across_levels() {
+        let vector: PersistentVector<usize> = (0..40_000).collect();
+
+        assert_eq!(vector.len(), 40_000);
+        assert_eq!(vector.shift, 3 * BITS, "32 * 32 * 32 leaves' worth needs a third level");
+        assert!((0..40_000).all(|i| vector.get(i) == Some(&i)));
+        assert_eq!(vector.get(40_000), None);
+    }
+
+    #[test]
+    fn test_old_versions_are_unchanged() {
+        let versions: Vec<PersistentVector<usize>> = (0..100)
+            .scan(PersistentVector::new(), |vector, i| {
+                *vector = vector.push_back(i);
+                Some(vector.clone())
+            })
+            .collect();
+        let changed = versions[99].set(10, 1000);
+
+        for (index, version) in versions.iter().enumerate() {
+            assert_eq!(version.len(), index + 1);
+            assert!(version.iter().copied().eq(0..=index));
+        }
+        assert_eq!(changed.get(10), Some(&1000));
+    }
+
+    #[test]
+    fn test_push_into_tail_shares_the_trie() {
+        let vector: PersistentVector<u32> = (0..100).collect();
+
+        let pushed = vector.push_back(100);
+
+        assert!(Rc::ptr_eq(&vector.root, &pushed.root));
+        assert_eq!(pushed.last(), Some(&100));
+        assert_eq!(vector.last(), Some(&99));
+    }
+
+    #[test]
+    fn test_set_in_trie_and_tail() {
+        let vector: PersistentVector<u32> = (0..70).collect();
+
+        let changed = vector.set(3, 300).set(69, 6900);
+
+        assert_eq!(changed.get(3), Some(&300));
+        assert_eq!(changed.get(69), Some(&6900));
+        assert_eq!(vector.get(3), Some(&3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 5 out of bounds for length 5")]
+    fn test_set_out_of_bounds_panics() {
+        (0..5).collect::<PersistentVector<i32>>().set(5, 0);
+    }
+
+    #[test]
+    fn test_pop_back_down_to_empty() {
+        let mut vector: PersistentVector<usize> = (0..2000).collect();
+
+        for len in (0..2000).rev() {
+            vector = vector.pop_back().unwrap();
+            assert_eq!(vector.len(), len);
+            assert_eq!(vector.last(), len.checked_sub(1).as_ref());
+            if len % 97 == 0 {
+                assert!(vector.iter().copied().eq(0..len));
+            }
+        }
+
+        assert!(vector.is_empty());
+        assert_eq!(vector.shift, BITS);
+    }
+
+    #[test]
+    fn test_equality_and_debug() {
+        let vector: PersistentVector<i32> = vec![1, 2, 3].into_iter().collect();
+
+        assert_eq!(vector, PersistentVector::new().push_back(1).push_back(2).push_back(3));
+        assert_eq!(format!("{:?}", vector), "[1, 2, 3]");
+    }
+}
@@ -0,0 +1,307 @@
+//! Piece Table implementation for text editing
+//! The original text is never changed and inserted text is only appended to
+//! a second buffer. The document is a list of pieces, each pointing at a
+//! span of one of the two buffers. Inserting splits at most one piece and
+//! deleting only trims or drops pieces, so no text is ever moved. The full
+//! text is put together only when it is asked for.
+//! Positions are byte offsets and have to fall on char boundaries.
+
+use std::ops::Range;
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Original,
+    Added,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Piece {
+    source: Source,
+    start: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceTable {
+    original: String,
+    added: String,
+    pieces: Vec<Piece>,
+    len: usize,
+}
+
+impl PieceTable {
+    pub fn new(original: &str) -> Self {
+        let pieces = if original.is_empty() {
+            vec![]
+        } else {
+            vec![Piece { source: Source::Original, start: 0, len: original.len() }]
+        };
+        PieceTable {
+            original: original.to_string(),
+            added: String::new(),
+            pieces,
+            len: original.len(),
+        }
+    }
+
+    /// Length of the document in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Inserts the text before `position`. Typing at the end of the previous
+    /// insert extends its piece instead of adding a new one.
+    pub fn insert(&mut self, position: usize, text: &str) {
+        self.check_position(position);
+        if text.is_empty() {
+            return;
+        }
+        let added_start = self.added.len();
+        self.added.push_str(text);
+        self.len += text.len();
+        let piece = Piece { source: Source::Added, start: added_start, len: text.len() };
+
+        let (index, offset) = self.locate(position);
+        if offset == 0 {
+            if let Some(previous) = index.checked_sub(1).map(|i| &mut self.pieces[i]) {
+                let ends_at_insert = previous.start + previous.len == added_start;
+                if previous.source == Source::Added && ends_at_insert {
+                    previous.len += text.len();
+                    check_invariants!(self);
+                    return;
+                }
+            }
+            self.pieces.insert(index, piece);
+        } else {
+            let left = Piece { len: offset, ..self.pieces[index] };
+            let right = Piece {
+                start: left.start + offset,
+                len: self.pieces[index].len - offset,
+                ..left
+            };
+            self.pieces.splice(index..index + 1, [left, piece, right]);
+        }
+        check_invariants!(self);
+    }
+
+    /// Deletes the bytes in the range
+    pub fn delete(&mut self, range: Range<usize>) {
+        assert!(
+            range.start <= range.end,
+            "Range start {} is after its end {}",
+            range.start,
+            range.end
+        );
+        self.check_position(range.start);
+        self.check_position(range.end);
+
+        let mut pieces = Vec::with_capacity(self.pieces.len() + 1);
+        let mut piece_start = 0;
+        for &piece in &self.pieces {
+            let piece_end = piece_start + piece.len;
+            if piece_start < range.start {
+                let len = piece_end.min(range.start) - piece_start;
+                pieces.push(Piece { len, ..piece });
+            }
+            if piece_end > range.end {
+                let skipped = range.end.saturating_sub(piece_start);
+                let start = piece.start + skipped;
+                pieces.push(Piece { start, len: piece.len - skipped, ..piece });
+            }
+            piece_start = piece_end;
+        }
+        self.pieces = pieces;
+        self.len -= range.end - range.start;
+        check_invariants!(self);
+    }
+
+    /// The spans of the document in order, without copying
+    pub fn chunks(&self) -> impl Iterator<Item = &str> + '_ {
+        self.pieces
+            .iter()
+            .map(|piece| &self.buffer(piece.source)[piece.start..piece.start + piece.len])
+    }
+
+    /// Puts the document together
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.len);
+        text.extend(self.chunks());
+        text
+    }
+
+    fn buffer(&self, source: Source) -> &str {
+        match source {
+            Source::Original => &self.original,
+            Source::Added => &self.added,
+        }
+    }
+
+    /// Index of the piece containing `position` and the offset inside it.
+    /// A position between two pieces belongs to the second one.
+    fn locate(&self, position: usize) -> (usize, usize) {
+        let mut piece_start = 0;
+        for (index, piece) in self.pieces.iter().enumerate() {
+            if position < piece_start + piece.len {
+                return (index, position - piece_start);
+            }
+            piece_start += piece.len;
+        }
+        (self.pieces.len(), 0)
+    }
+
+    fn check_position(&self, position: usize) {
+        assert!(
+            position <= self.len,
+            "Position {} out of bounds for length {}",
+            position,
+            self.len
+        );
+        let (index, offset) = self.locate(position);
+        if let Some(piece) = self.pieces.get(index) {
+            assert!(
+                self.buffer(piece.source).is_char_boundary(piece.start + offset),
+                "Position {} is not a char boundary",
+                position
+            );
+        }
+    }
+}
+
+/// Checks every piece is a non-empty span of its buffer that starts and ends
+/// on char boundaries, and the pieces add up to the length
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for PieceTable {
+    fn assert_invariants(&self) {
+        for piece in &self.pieces {
+            assert!(piece.len > 0, "Piece is empty");
+            let buffer = self.buffer(piece.source);
+            let end = piece.start + piece.len;
+            assert!(end <= buffer.len(), "Piece goes past its buffer");
+            assert!(
+                buffer.is_char_boundary(piece.start) && buffer.is_char_boundary(end),
+                "Piece cuts a char"
+            );
+        }
+        let total: usize = self.pieces.iter().map(|piece| piece.len).sum();
+        assert_eq!(total, self.len, "len doesn't match the pieces");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_table_is_created_it_holds_the_original() {
+        let table = PieceTable::new("hello");
+
+        assert_eq!(table.text(), "hello");
+        assert_eq!(table.len(), 5);
+        assert_eq!(table.piece_count(), 1);
+        assert!(PieceTable::new("").is_empty());
+    }
+
+    #[test]
+    fn test_insert_in_the_middle_splits_piece() {
+        let mut table = PieceTable::new("helld");
+
+        table.insert(3, "lo wor");
+
+        assert_eq!(table.text(), "hello world");
+        assert_eq!(table.piece_count(), 3);
+        assert_eq!(table.chunks().collect::<Vec<_>>(), vec!["hel", "lo wor", "ld"]);
+    }
+
+    #[test]
+    fn test_typing_extends_last_piece() {
+        let mut table = PieceTable::new("");
+
+        for (position, c) in "typing".chars().enumerate() {
+            table.insert(position, &c.to_string());
+        }
+
+        assert_eq!(table.text(), "typing");
+        assert_eq!(table.piece_count(), 1);
+    }
+
+    #[test]
+    fn test_delete_across_pieces() {
+        let mut table = PieceTable::new("abcdef");
+        table.insert(3, "XYZ");
+
+        table.delete(2..7);
+
+        assert_eq!(table.text(), "abef");
+        assert_eq!(table.chunks().collect::<Vec<_>>(), vec!["ab", "ef"]);
+        assert_eq!(table.len(), 4);
+    }
+
+    #[test]
+    fn test_delete_everything() {
+        let mut table = PieceTable::new("abc");
+        table.insert(1, "123");
+
+        table.delete(0..6);
+
+        assert!(table.is_empty());
+        assert_eq!(table.piece_count(), 0);
+        assert_eq!(table.text(), "");
+    }
+
+    #[test]
+    fn test_unicode_text() {
+        let mut table = PieceTable::new("día");
+
+        table.insert(3, "🦀");
+
+        assert_eq!(table.text(), "dí🦀a");
+    }
+
+    #[test]
+    #[should_panic(expected = "Position 2 is not a char boundary")]
+    fn test_position_inside_char_panics() {
+        PieceTable::new("día").insert(2, "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "Position 4 out of bounds for length 3")]
+    fn test_position_out_of_bounds_panics() {
+        PieceTable::new("abc").delete(1..4);
+    }
+
+    #[test]
+    fn test_random_edits_match_string() {
+        let mut table = PieceTable::new("The quick brown fox");
+        let mut expected = String::from("The quick brown fox");
+        let mut state = 2024u64;
+
+        for step in 0..500 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let position = (state >> 33) as usize % (expected.len() + 1);
+            if (state >> 20).is_multiple_of(3) {
+                let end = (position + (state >> 40) as usize % 5).min(expected.len());
+                table.delete(position..end);
+                expected.replace_range(position..end, "");
+            } else {
+                let text = format!("<{}>", step);
+                table.insert(position, &text);
+                expected.insert_str(position, &text);
+            }
+
+            assert_eq!(table.text(), expected);
+            assert_eq!(table.len(), expected.len());
+        }
+    }
+}
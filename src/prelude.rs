@@ -0,0 +1,68 @@
+//! Main types of every module
+//! Meant to be glob imported. Iterator, handle and error types stay in their
+//! modules. Names that clash with the standard library, like `HashMap` and
+//! `BinaryHeap`, lose to an explicit `use std::...` of the same name.
+
+pub use crate::adaptive_radix_tree::AdaptiveRadixTree;
+pub use crate::aho_corasick::AhoCorasick;
+pub use crate::arc_cache::ArcCache;
+pub use crate::arena::{ Arena, ArenaList, ArenaTreeMap };
+pub use crate::avl_tree::AvlTreeMap;
+pub use crate::bimap::BiMap;
+pub use crate::binary_heap::{ BinaryHeap, HeapOrder };
+pub use crate::binomial_heap::BinomialHeap;
+pub use crate::bitvec::BitVec;
+pub use crate::bk_tree::{ BkTree, Levenshtein, Metric };
+pub use crate::bloom::BloomFilter;
+pub use crate::concurrent_skip_list::ConcurrentSkipListMap;
+pub use crate::cow::{ CowList, CowMap };
+pub use crate::cursor::{ Cursor, CursorMut };
+pub use crate::dancing_links::DancingLinks;
+pub use crate::fenwick::{ FenwickTree, RangeUpdateFenwickTree };
+pub use crate::graph::{ Graph, GraphKind, GraphOps, MatrixGraph, NodeIndex, WeightedGraph };
+pub use crate::growth::GrowthPolicy;
+pub use crate::hash_map::HashMap;
+pub use crate::instrumented::{ Counted, Instrumented, Report };
+pub use crate::heap_size::HeapSize;
+pub use crate::indexed_priority_queue::IndexedPriorityQueue;
+pub use crate::interner::StringInterner;
+pub use crate::interval_heap::IntervalHeap;
+pub use crate::interval_set::IntervalSet;
+pub use crate::kd_tree::KdTree;
+pub use crate::key_adapter::{ CaseInsensitive, KeyAdapter, Normalized };
+pub use crate::leftist_heap::LeftistHeap;
+pub use crate::lfu_cache::LfuCache;
+pub use crate::link_cut_tree::LinkCutTree;
+pub use crate::linked_hash_set::LinkedHashSet;
+pub use crate::map::Map;
+pub use crate::matrix::Matrix;
+pub use crate::merkle::MerkleTree;
+pub use crate::multiset::MultiSet;
+pub use crate::object_pool::ObjectPool;
+pub use crate::ordered_set::OrderedSet;
+pub use crate::pairing_heap::PairingHeap;
+pub use crate::patricia_trie::PatriciaTrie;
+pub use crate::persistent::{ HamtMap, List as PersistentList, PersistentVector };
+pub use crate::piece_table::PieceTable;
+pub use crate::quadtree::Quadtree;
+pub use crate::queue::Queue;
+pub use crate::r_tree::RTree;
+pub use crate::radix_trie::RadixTrie;
+pub use crate::range_map::RangeMap;
+pub use crate::rank_select::RankSelect;
+pub use crate::render::Render;
+pub use crate::singly_linked_list::SinglyLinkedList;
+pub use crate::skew_heap::SkewHeap;
+pub use crate::slab::Slab;
+pub use crate::sliding_window::SlidingWindowAggregator;
+pub use crate::small_vec::SmallVec;
+pub use crate::sorted_vec::SortedVec;
+pub use crate::sparse_matrix::SparseMatrix;
+pub use crate::sparse_table::SparseTable;
+pub use crate::stack::Stack;
+pub use crate::sync::{ SyncHashMap, SyncQueue, SyncStack };
+pub use crate::undo::{ Edit, History };
+pub use crate::veb_tree::VebTree;
+pub use crate::viz::ToDot;
+pub use crate::collection::Collection;
+pub use crate::error::Error;
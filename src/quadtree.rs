@@ -0,0 +1,402 @@
+//! Region Quadtree implementation for 2D points
+//! Every node covers a rectangle. A leaf keeps up to `capacity` points; when
+//! it overflows it is split into four equal quadrants and its points move
+//! down. Nodes at `max_depth` are never split, so many equal points can't
+//! cause endless splitting. Range queries skip quadrants that don't
+//! intersect the searched rectangle.
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::kd_tree::Point;
+
+pub const DEFAULT_NODE_CAPACITY: usize = 4;
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// Axis-aligned rectangle, bounds are inclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub min: Point<2>,
+    pub max: Point<2>,
+}
+
+impl Rect {
+    pub fn new(min: Point<2>, max: Point<2>) -> Self {
+        Rect { min, max }
+    }
+
+    pub fn contains(&self, point: &Point<2>) -> bool {
+        (0..2).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        (0..2).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+
+    pub fn center(&self) -> Point<2> {
+        [(self.min[0] + self.max[0]) / 2.0, (self.min[1] + self.max[1]) / 2.0]
+    }
+
+    pub fn area(&self) -> f64 {
+        (self.max[0] - self.min[0]) * (self.max[1] - self.min[1])
+    }
+
+    /// Smallest rectangle containing both
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::new(
+            [self.min[0].min(other.min[0]), self.min[1].min(other.min[1])],
+            [self.max[0].max(other.max[0]), self.max[1].max(other.max[1])]
+        )
+    }
+
+    /// Quadrants in the order used by `quadrant_index`: SW, SE, NW, NE
+    fn quadrants(&self) -> [Rect; 4] {
+        let [cx, cy] = self.center();
+        [
+            Rect::new(self.min, [cx, cy]),
+            Rect::new([cx, self.min[1]], [self.max[0], cy]),
+            Rect::new([self.min[0], cy], [cx, self.max[1]]),
+            Rect::new([cx, cy], self.max),
+        ]
+    }
+
+    /// Points on a shared border always go to the east or north quadrant
+    fn quadrant_index(&self, point: &Point<2>) -> usize {
+        let [cx, cy] = self.center();
+        (point[0] >= cx) as usize + 2 * ((point[1] >= cy) as usize)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct QuadNode {
+    bounds: Rect,
+    depth: usize,
+    points: Vec<Point<2>>,
+    children: Option<Box<[QuadNode; 4]>>,
+}
+
+impl QuadNode {
+    fn new(bounds: Rect, depth: usize) -> Self {
+        QuadNode { bounds, depth, points: vec![], children: None }
+    }
+
+    fn insert(&mut self, point: Point<2>, capacity: usize, max_depth: usize) {
+        if let Some(children) = &mut self.children {
+            let index = self.bounds.quadrant_index(&point);
+            children[index].insert(point, capacity, max_depth);
+            return;
+        }
+
+        self.points.push(point);
+        if self.points.len() > capacity && self.depth < max_depth {
+            let depth = self.depth + 1;
+            let mut children = Box::new(self.bounds.quadrants().map(|q| QuadNode::new(q, depth)));
+            for point in self.points.drain(..) {
+                let index = self.bounds.quadrant_index(&point);
+                children[index].insert(point, capacity, max_depth);
+            }
+            self.children = Some(children);
+        }
+    }
+
+    fn remove(&mut self, point: &Point<2>, capacity: usize) -> bool {
+        let removed = match &mut self.children {
+            Some(children) => {
+                let index = self.bounds.quadrant_index(point);
+                children[index].remove(point, capacity)
+            }
+            None => {
+                match self.points.iter().position(|p| p == point) {
+                    Some(position) => {
+                        self.points.swap_remove(position);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        };
+        if removed {
+            self.try_merge(capacity);
+        }
+        removed
+    }
+
+    /// Turns the node back into a leaf once its children hold few enough points
+    fn try_merge(&mut self, capacity: usize) {
+        let can_merge = match &self.children {
+            Some(children) => {
+                children.iter().all(|child| child.children.is_none()) &&
+                    children.iter().map(|child| child.points.len()).sum::<usize>() <= capacity
+            }
+            None => false,
+        };
+        if can_merge {
+            let children = self.children.take().unwrap();
+            for mut child in *children {
+                self.points.append(&mut child.points);
+            }
+        }
+    }
+
+    fn query(&self, area: &Rect, found: &mut Vec<Point<2>>) {
+        if !self.bounds.intersects(area) {
+            return;
+        }
+        found.extend(self.points.iter().filter(|point| area.contains(point)));
+        if let Some(children) = &self.children {
+            for child in children.iter() {
+                child.query(area, found);
+            }
+        }
+    }
+
+    fn height(&self) -> usize {
+        match &self.children {
+            Some(children) => 1 + children.iter().map(QuadNode::height).max().unwrap_or(0),
+            None => 1,
+        }
+    }
+
+    /// Checks the node and its quadrants and returns how many points they hold
+    #[cfg(any(test, feature = "debug-invariants"))]
+    fn check(&self, capacity: usize, max_depth: usize) -> usize {
+        let Some(children) = &self.children else {
+            assert!(self.points.iter().all(|point| self.bounds.contains(point)), "Point outside");
+            let full = self.points.len() > capacity;
+            assert!(!full || self.depth == max_depth, "Leaf over capacity wasn't split");
+            return self.points.len();
+        };
+        assert!(self.points.is_empty(), "Inner node holds points");
+        assert!(self.depth < max_depth, "Node at max_depth was split");
+        let mut points = 0;
+        for (child, quadrant) in children.iter().zip(self.bounds.quadrants()) {
+            assert_eq!(child.bounds, quadrant, "Child doesn't cover its quadrant");
+            assert_eq!(child.depth, self.depth + 1, "Child depth is off");
+            points += child.check(capacity, max_depth);
+        }
+        if children.iter().all(|child| child.children.is_none()) {
+            assert!(points > capacity, "Children fit in one leaf but weren't merged");
+        }
+        points
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quadtree {
+    root: QuadNode,
+    capacity: usize,
+    max_depth: usize,
+    size: usize,
+}
+
+impl Quadtree {
+    pub fn new(bounds: Rect) -> Self {
+        Self::with_limits(bounds, DEFAULT_NODE_CAPACITY, DEFAULT_MAX_DEPTH)
+    }
+
+    /// `capacity` is the number of points a leaf holds before it splits,
+    /// `max_depth` is the depth below which nodes are never split.
+    pub fn with_limits(bounds: Rect, capacity: usize, max_depth: usize) -> Self {
+        assert!(capacity > 0, "Node capacity has to be at least 1");
+        Quadtree { root: QuadNode::new(bounds, 0), capacity, max_depth, size: 0 }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.root.bounds
+    }
+
+    /// Points a node holds before it splits
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Depth below which nodes don't split any more
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Inserts the point. Returns false if it lies outside the tree bounds.
+    pub fn insert(&mut self, point: Point<2>) -> bool {
+        if !self.root.bounds.contains(&point) {
+            return false;
+        }
+        self.root.insert(point, self.capacity, self.max_depth);
+        self.size += 1;
+        check_invariants!(self);
+        true
+    }
+
+    /// Removes one copy of the point. Returns false if it wasn't in the tree.
+    pub fn remove(&mut self, point: &Point<2>) -> bool {
+        if !self.root.bounds.contains(point) || !self.root.remove(point, self.capacity) {
+            return false;
+        }
+        self.size -= 1;
+        check_invariants!(self);
+        true
+    }
+
+    pub fn contains(&self, point: &Point<2>) -> bool {
+        !self.query(&Rect::new(*point, *point)).is_empty()
+    }
+
+    /// All points inside the rectangle
+    pub fn query(&self, area: &Rect) -> Vec<Point<2>> {
+        let mut found = vec![];
+        self.root.query(area, &mut found);
+        found
+    }
+
+    /// Number of levels, a single leaf has height 1
+    pub fn height(&self) -> usize {
+        self.root.height()
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for Quadtree {
+    fn assert_invariants(&self) {
+        let points = self.root.check(self.capacity, self.max_depth);
+        assert_eq!(points, self.size, "size doesn't match the points");
+    }
+}
+
+impl HeapSize for QuadNode {
+    fn heap_size(&self) -> usize {
+        self.points.heap_size() + self.children.heap_size()
+    }
+}
+
+impl HeapSize for Quadtree {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_square() -> Rect {
+        Rect::new([0.0, 0.0], [100.0, 100.0])
+    }
+
+    fn random_points(count: usize, seed: u64) -> Vec<Point<2>> {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) % 1000) as f64 / 10.0
+        };
+        (0..count).map(|_| [next(), next()]).collect()
+    }
+
+    fn sorted(mut points: Vec<Point<2>>) -> Vec<Point<2>> {
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        points
+    }
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let tree = Quadtree::new(unit_square());
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 1);
+        assert!(tree.query(&unit_square()).is_empty());
+    }
+
+    #[test]
+    fn test_insert_outside_bounds_is_rejected() {
+        let mut tree = Quadtree::new(unit_square());
+
+        assert!(!tree.insert([150.0, 10.0]));
+        assert!(tree.insert([100.0, 100.0]));
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_leaf_splits_when_capacity_exceeded() {
+        let mut tree = Quadtree::with_limits(unit_square(), 2, 8);
+
+        tree.insert([10.0, 10.0]);
+        tree.insert([90.0, 10.0]);
+        assert_eq!(tree.height(), 1);
+        tree.insert([10.0, 90.0]);
+
+        assert_eq!(tree.height(), 2);
+        assert!(tree.root.points.is_empty());
+        let children = tree.root.children.as_ref().unwrap();
+        assert_eq!(children[0].points, vec![[10.0, 10.0]]);
+        assert_eq!(children[1].points, vec![[90.0, 10.0]]);
+        assert_eq!(children[2].points, vec![[10.0, 90.0]]);
+    }
+
+    #[test]
+    fn test_max_depth_limits_splitting_of_equal_points() {
+        let mut tree = Quadtree::with_limits(unit_square(), 1, 3);
+
+        for _ in 0..10 {
+            tree.insert([42.0, 42.0]);
+        }
+
+        assert_eq!(tree.height(), 4);
+        assert_eq!(tree.len(), 10);
+        assert_eq!(tree.query(&unit_square()).len(), 10);
+    }
+
+    #[test]
+    fn test_query_matches_brute_force() {
+        let points = random_points(500, 5);
+        let mut tree = Quadtree::with_limits(unit_square(), 3, 10);
+        for &point in &points {
+            tree.insert(point);
+        }
+
+        for corners in random_points(40, 17).chunks(2) {
+            let area = Rect::new(
+                [corners[0][0].min(corners[1][0]), corners[0][1].min(corners[1][1])],
+                [corners[0][0].max(corners[1][0]), corners[0][1].max(corners[1][1])]
+            );
+            let expected = points.iter().filter(|p| area.contains(p)).copied().collect();
+            assert_eq!(sorted(tree.query(&area)), sorted(expected));
+        }
+    }
+
+    #[test]
+    fn test_remove_point() {
+        let mut tree = Quadtree::new(unit_square());
+        tree.insert([1.0, 2.0]);
+        tree.insert([3.0, 4.0]);
+
+        assert!(tree.remove(&[1.0, 2.0]));
+        assert!(!tree.remove(&[1.0, 2.0]));
+        assert!(!tree.contains(&[1.0, 2.0]));
+        assert!(tree.contains(&[3.0, 4.0]));
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_merges_children_back_into_leaf() {
+        let points = random_points(50, 9);
+        let mut tree = Quadtree::with_limits(unit_square(), 4, 8);
+        for &point in &points {
+            tree.insert(point);
+        }
+        assert!(tree.height() > 1);
+
+        for point in &points[..47] {
+            assert!(tree.remove(point));
+        }
+
+        assert_eq!(tree.height(), 1);
+        assert_eq!(sorted(tree.query(&unit_square())), sorted(points[47..].to_vec()));
+    }
+}
@@ -1,71 +1,162 @@
-/// Queue implementation
-/// Ordering: FIFI (First-in First-out)
-/// As in line or queue at the ticket stand, items are
-/// removed from the data structure un the same order as
-/// they are added.
+//! Queue implementation
+//! Ordering: FIFI (First-in First-out)
+//! As in line or queue at the ticket stand, items are
+//! removed from the data structure un the same order as
+//! they are added.
+//! Room for more items is made by the growth policy, doubling by default.
+
+use crate::collection::Collection;
+use crate::error::{ Error, Result };
+use crate::growth::{ Doubling, GrowthPolicy };
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
 
 const DEFAULT_CAPACITY_QUEUE: usize = 256;
 
-pub struct Queue<T> {
+pub struct Queue<T, G: GrowthPolicy = Doubling> {
     data: Vec<Option<T>>,
     capacity: usize,
     current_size: usize,
+    growth: G,
 }
 
-#[allow(dead_code)]
 impl<T: Copy> Queue<T> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Queue {
             data: Vec::with_capacity(DEFAULT_CAPACITY_QUEUE),
             capacity: DEFAULT_CAPACITY_QUEUE,
             current_size: 0,
+            growth: Doubling,
         }
     }
+}
+
+impl<T: Copy, G: GrowthPolicy> Queue<T, G> {
+    /// Empty queue that reserves nothing until the first item, then makes
+    /// room as `growth` says whenever it is full
+    pub fn with_growth(growth: G) -> Self {
+        Queue { data: Vec::new(), capacity: 0, current_size: 0, growth }
+    }
 
     /// Add an item to the end of the queue
-    /// Underlying vector increases capacity automatically
-    /// once it becomes full. Increasing capacity variable
-    /// to reflect this change.
-    fn add(&mut self, item: T) {
+    /// Once the queue is full, the growth policy picks the new
+    /// capacity and the underlying vector reserves exactly that.
+    /// Panics if the policy can't make room for the item.
+    pub fn add(&mut self, item: T) {
+        if self.try_add(item).is_err() {
+            panic!("queue is full: the growth policy refused to grow");
+        }
+    }
+
+    /// Like `add`, but a queue that can't grow any more is an error
+    pub fn try_add(&mut self, item: T) -> Result<()> {
         if self.is_full() {
-            self.capacity += DEFAULT_CAPACITY_QUEUE;
+            self.capacity = self.growth.grow(self.capacity).ok_or(Error::CapacityExceeded)?;
+            self.data.reserve_exact(self.capacity - self.current_size);
         }
-        self.data.insert(self.current_size as usize, Some(item));
+        self.data.insert(self.current_size, Some(item));
         self.current_size += 1;
+        check_invariants!(self);
+        Ok(())
+    }
+
+    /// Items the queue holds before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 
     /// Remove the first item in the queue
-    fn remove(&mut self) -> Option<T> {
+    pub fn remove(&mut self) -> Option<T> {
         const FIRST_ITEM_INDEX: usize = 0;
         if self.is_empty() {
             return None;
         }
         let result = self.data.remove(FIRST_ITEM_INDEX);
         self.current_size -= 1;
+        check_invariants!(self);
         result
     }
 
-    /// Return the top of the queue
-    fn peek(&self) -> Option<T> {
-        if let Some(last_element) = self.data.last().cloned() {
-            return last_element;
-        }
-        None
+    /// Return the first item in the queue, the one `remove` takes next
+    pub fn peek(&self) -> Option<T> {
+        self.data.first().copied().flatten()
+    }
+
+    /// Items from the first to the last one added
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter().flatten()
+    }
+
+    /// Like `remove`, but an empty queue is an error
+    pub fn try_remove(&mut self) -> Result<T> {
+        self.remove().ok_or(Error::EmptyCollection)
+    }
+
+    /// Like `peek`, but an empty queue is an error
+    pub fn try_peek(&self) -> Result<T> {
+        self.peek().ok_or(Error::EmptyCollection)
     }
 
     /// Return true if and only if the queue is empty
-    fn is_empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.current_size == 0
     }
 
-    fn is_full(&self) -> bool {
+    pub fn is_full(&self) -> bool {
         self.current_size == self.capacity
     }
 }
 
+impl<T: Copy> Default for Queue<T> {
+    fn default() -> Self {
+        Queue::new()
+    }
+}
+
+impl<T: Copy + PartialEq, G: GrowthPolicy> Collection for Queue<T, G> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        self.current_size
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.current_size = 0;
+        check_invariants!(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.data.iter().any(|slot| slot.as_ref() == Some(item))
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, G: GrowthPolicy> Invariants for Queue<T, G> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.current_size, self.data.len(), "current_size doesn't match the slots");
+        assert!(self.data.iter().all(Option::is_some), "queue has an empty slot");
+        assert!(
+            self.current_size <= self.capacity,
+            "{} items overflow a capacity of {}",
+            self.current_size,
+            self.capacity
+        );
+    }
+}
+
+impl<T: HeapSize, G: GrowthPolicy> HeapSize for Queue<T, G> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::growth::{ Bounded, FixedIncrement };
 
     #[test]
     fn when_queue_is_created_it_is_empty() {
@@ -81,7 +172,7 @@ mod test {
 
         queue.add(13);
 
-        assert_eq!(queue.is_empty(), false);
+        assert!(!queue.is_empty());
         assert_eq!(queue.current_size, 1);
         assert!(queue.data.contains(&Some(13)));
     }
@@ -94,7 +185,7 @@ mod test {
             queue.add(num);
         }
 
-        assert_eq!(queue.is_empty(), false);
+        assert!(!queue.is_empty());
         assert_eq!(queue.current_size, 14);
         for num in 0..14 {
             assert!(queue.data.contains(&Some(num)));
@@ -124,7 +215,7 @@ mod test {
             queue.add(num);
         }
 
-        assert_eq!(queue.is_full(), false, "After reaching full capacity, vector doubles in size.");
+        assert!(!queue.is_full(), "After reaching full capacity, vector doubles in size.");
         assert_eq!(queue.current_size, DEFAULT_CAPACITY_QUEUE + 1);
         assert_eq!(queue.data.len(), DEFAULT_CAPACITY_QUEUE + 1);
         assert_eq!(queue.capacity, DEFAULT_CAPACITY_QUEUE * 2);
@@ -200,8 +291,45 @@ mod test {
         let result = queue.peek();
 
         assert!(result.is_some());
-        assert_eq!(result, Some(6));
-        let actual_last_element = queue.data.last().cloned().unwrap();
-        assert_eq!(actual_last_element, Some(6));
+        assert_eq!(result, Some(0));
+        assert_eq!(queue.remove(), Some(0));
+    }
+
+    #[test]
+    fn test_try_remove_and_try_peek() {
+        let mut queue = Queue::<i32>::new();
+        queue.add(7);
+
+        assert_eq!(queue.try_peek(), Ok(7));
+        assert_eq!(queue.try_remove(), Ok(7));
+        assert_eq!(queue.try_remove(), Err(Error::EmptyCollection));
+        assert_eq!(queue.try_peek(), Err(Error::EmptyCollection));
+    }
+
+    #[test]
+    fn test_capacity_follows_the_growth_policy() {
+        let mut queue = Queue::with_growth(FixedIncrement(4));
+
+        for item in 0..9 {
+            queue.add(item);
+        }
+
+        assert_eq!(queue.capacity(), 12);
+        assert_eq!(queue.data.capacity(), 12);
+        assert_eq!(queue.remove(), Some(0));
+    }
+
+    #[test]
+    fn when_bounded_queue_is_full_add_fails() {
+        let mut queue = Queue::with_growth(Bounded::new(Doubling, 5));
+        for item in 0..5 {
+            queue.add(item);
+        }
+
+        assert!(queue.is_full());
+        assert_eq!(queue.try_add(5), Err(Error::CapacityExceeded));
+        assert_eq!(queue.remove(), Some(0));
+        assert_eq!(queue.try_add(5), Ok(()));
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
     }
 }
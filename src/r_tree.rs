@@ -0,0 +1,508 @@
+//! R-tree implementation for axis-aligned rectangles
+//! Balanced tree where every node stores the bounding box of everything
+//! below it, and all data sits in leaves at the same depth. Bulk loading
+//! uses Sort-Tile-Recursive packing: items are sorted into vertical slices
+//! by x, each slice is sorted by y and cut into full nodes, then the same is
+//! done for the level above. Single inserts go down the child whose box
+//! grows the least and overflowing nodes are split with Guttman's quadratic
+//! split. Intersection queries skip every node whose box misses the area.
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::quadtree::Rect;
+
+pub const DEFAULT_MAX_ENTRIES: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry<T> {
+    rect: Rect,
+    data: T,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Children<T> {
+    Leaf(Vec<Entry<T>>),
+    Inner(Vec<RNode<T>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RNode<T> {
+    bounds: Rect,
+    children: Children<T>,
+}
+
+/// Anything with a bounding box, so splitting and packing work on both levels
+trait Bounded {
+    fn bounds(&self) -> Rect;
+}
+
+impl<T> Bounded for Entry<T> {
+    fn bounds(&self) -> Rect {
+        self.rect
+    }
+}
+
+impl<T> Bounded for RNode<T> {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+impl<T> RNode<T> {
+    fn leaf(entries: Vec<Entry<T>>) -> Self {
+        RNode { bounds: bounds_of(&entries), children: Children::Leaf(entries) }
+    }
+
+    fn inner(nodes: Vec<RNode<T>>) -> Self {
+        RNode { bounds: bounds_of(&nodes), children: Children::Inner(nodes) }
+    }
+
+    /// Inserts the entry below this node. Returns the new sibling if the node was split.
+    fn insert(&mut self, entry: Entry<T>, max_entries: usize) -> Option<RNode<T>> {
+        let min_entries = max_entries / 2;
+        match &mut self.children {
+            Children::Leaf(entries) => {
+                entries.push(entry);
+                if entries.len() > max_entries {
+                    let (kept, moved) = quadratic_split(std::mem::take(entries), min_entries);
+                    *self = RNode::leaf(kept);
+                    return Some(RNode::leaf(moved));
+                }
+                self.bounds = bounds_of(entries);
+            }
+            Children::Inner(nodes) => {
+                let index = choose_subtree(nodes, &entry.rect);
+                if let Some(sibling) = nodes[index].insert(entry, max_entries) {
+                    nodes.push(sibling);
+                }
+                if nodes.len() > max_entries {
+                    let (kept, moved) = quadratic_split(std::mem::take(nodes), min_entries);
+                    *self = RNode::inner(kept);
+                    return Some(RNode::inner(moved));
+                }
+                self.bounds = bounds_of(nodes);
+            }
+        }
+        None
+    }
+
+    fn search<'a>(&'a self, area: &Rect, found: &mut Vec<(&'a Rect, &'a T)>) {
+        match &self.children {
+            Children::Leaf(entries) => {
+                for entry in entries.iter().filter(|entry| entry.rect.intersects(area)) {
+                    found.push((&entry.rect, &entry.data));
+                }
+            }
+            Children::Inner(nodes) => {
+                for node in nodes.iter().filter(|node| node.bounds.intersects(area)) {
+                    node.search(area, found);
+                }
+            }
+        }
+    }
+
+    fn height(&self) -> usize {
+        match &self.children {
+            Children::Leaf(_) => 1,
+            Children::Inner(nodes) => 1 + nodes[0].height(),
+        }
+    }
+
+    /// Checks boxes, the node size limit and that all leaves are at the same
+    /// depth. Returns the height and the number of entries below the node.
+    #[cfg(any(test, feature = "debug-invariants"))]
+    fn check(&self, max_entries: usize, is_root: bool) -> (usize, usize) {
+        let count = match &self.children {
+            Children::Leaf(entries) => entries.len(),
+            Children::Inner(nodes) => nodes.len(),
+        };
+        assert!(count <= max_entries, "Node holds more than max_entries");
+        assert!(is_root || count > 0, "Node other than the root is empty");
+        match &self.children {
+            Children::Leaf(entries) => {
+                assert_eq!(self.bounds, bounds_of(entries), "Box doesn't fit the entries");
+                (1, entries.len())
+            }
+            Children::Inner(nodes) => {
+                assert_eq!(self.bounds, bounds_of(nodes), "Box doesn't fit the children");
+                let checked: Vec<(usize, usize)> =
+                    nodes.iter().map(|child| child.check(max_entries, false)).collect();
+                let height = checked[0].0;
+                assert!(checked.iter().all(|&(h, _)| h == height), "Leaves at different depths");
+                (height + 1, checked.iter().map(|&(_, entries)| entries).sum())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RTree<T> {
+    root: RNode<T>,
+    max_entries: usize,
+    size: usize,
+}
+
+impl<T> RTree<T> {
+    pub fn new() -> Self {
+        Self::with_max_entries(DEFAULT_MAX_ENTRIES)
+    }
+
+    /// `max_entries` is the number of children a node holds before it splits
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        assert!(max_entries >= 2, "Max entries has to be at least 2");
+        RTree { root: RNode::leaf(vec![]), max_entries, size: 0 }
+    }
+
+    /// Builds a packed tree from all items at once using STR
+    pub fn bulk_load(items: Vec<(Rect, T)>) -> Self {
+        let mut tree = Self::new();
+        if items.is_empty() {
+            return tree;
+        }
+        tree.size = items.len();
+        let entries = items.into_iter().map(|(rect, data)| Entry { rect, data }).collect();
+        let mut nodes: Vec<RNode<T>> = str_pack(entries, tree.max_entries)
+            .into_iter()
+            .map(RNode::leaf)
+            .collect();
+        while nodes.len() > 1 {
+            nodes = str_pack(nodes, tree.max_entries).into_iter().map(RNode::inner).collect();
+        }
+        tree.root = nodes.pop().unwrap();
+        check_invariants!(&tree);
+        tree
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Bounding box of all rectangles, None for an empty tree
+    pub fn bounds(&self) -> Option<Rect> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.root.bounds)
+    }
+
+    pub fn insert(&mut self, rect: Rect, data: T) {
+        if let Some(sibling) = self.root.insert(Entry { rect, data }, self.max_entries) {
+            let old_root = std::mem::replace(&mut self.root, RNode::leaf(vec![]));
+            self.root = RNode::inner(vec![old_root, sibling]);
+        }
+        self.size += 1;
+        check_invariants!(self);
+    }
+
+    /// All rectangles that intersect the area, touching borders included
+    pub fn search(&self, area: &Rect) -> Vec<(&Rect, &T)> {
+        let mut found = vec![];
+        if !self.is_empty() {
+            self.root.search(area, &mut found);
+        }
+        found
+    }
+
+    /// Number of levels, every leaf sits at this depth
+    pub fn height(&self) -> usize {
+        self.root.height()
+    }
+}
+
+impl<T> Default for RTree<T> {
+    fn default() -> Self {
+        RTree::new()
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for RTree<T> {
+    fn assert_invariants(&self) {
+        let (_, entries) = self.root.check(self.max_entries, true);
+        assert_eq!(entries, self.size, "size doesn't match the entries");
+    }
+}
+
+/// Bounding box of the items, a zero rectangle at the origin if there are none
+fn bounds_of<E: Bounded>(items: &[E]) -> Rect {
+    items
+        .iter()
+        .map(Bounded::bounds)
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or(Rect::new([0.0, 0.0], [0.0, 0.0]))
+}
+
+/// How much the rectangle has to grow to also cover `other`
+fn enlargement(rect: &Rect, other: &Rect) -> f64 {
+    rect.union(other).area() - rect.area()
+}
+
+/// Child that needs the least enlargement, ties go to the smaller one
+fn choose_subtree<T>(nodes: &[RNode<T>], rect: &Rect) -> usize {
+    (0..nodes.len())
+        .min_by(|&a, &b| {
+            let key = |index: usize| {
+                let bounds = &nodes[index].bounds;
+                (enlargement(bounds, rect), bounds.area())
+            };
+            key(a).partial_cmp(&key(b)).unwrap()
+        })
+        .unwrap()
+}
+
+/// Guttman's quadratic split. The two items that would waste the most
+/// area together start the groups, then the item with the strongest
+/// preference is placed next, until one group needs all that is left.
+fn quadratic_split<E: Bounded>(mut items: Vec<E>, min_entries: usize) -> (Vec<E>, Vec<E>) {
+    let mut seeds = (0, 1);
+    let mut worst_waste = f64::NEG_INFINITY;
+    for i in 0..items.len() {
+        for j in i + 1..items.len() {
+            let (a, b) = (items[i].bounds(), items[j].bounds());
+            let waste = a.union(&b).area() - a.area() - b.area();
+            if waste > worst_waste {
+                worst_waste = waste;
+                seeds = (i, j);
+            }
+        }
+    }
+    // Remove the later index first so the earlier one stays valid
+    let second = items.swap_remove(seeds.1);
+    let first = items.swap_remove(seeds.0);
+    let (mut first_bounds, mut second_bounds) = (first.bounds(), second.bounds());
+    let (mut first_group, mut second_group) = (vec![first], vec![second]);
+
+    while !items.is_empty() {
+        if first_group.len() + items.len() <= min_entries {
+            first_group.append(&mut items);
+            break;
+        }
+        if second_group.len() + items.len() <= min_entries {
+            second_group.append(&mut items);
+            break;
+        }
+        let growth = |item: &E| {
+            let bounds = item.bounds();
+            (enlargement(&first_bounds, &bounds), enlargement(&second_bounds, &bounds))
+        };
+        let next = (0..items.len())
+            .max_by(|&a, &b| {
+                let preference = |index: usize| {
+                    let (first, second) = growth(&items[index]);
+                    (first - second).abs()
+                };
+                preference(a).partial_cmp(&preference(b)).unwrap()
+            })
+            .unwrap();
+        let item = items.swap_remove(next);
+        let (first_growth, second_growth) = growth(&item);
+        let goes_first = match first_growth.partial_cmp(&second_growth).unwrap() {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => {
+                (first_bounds.area(), first_group.len()) <=
+                    (second_bounds.area(), second_group.len())
+            }
+        };
+        if goes_first {
+            first_bounds = first_bounds.union(&item.bounds());
+            first_group.push(item);
+        } else {
+            second_bounds = second_bounds.union(&item.bounds());
+            second_group.push(item);
+        }
+    }
+    (first_group, second_group)
+}
+
+/// Sort-Tile-Recursive packing of one level into groups of at most `max_entries`
+fn str_pack<E: Bounded>(mut items: Vec<E>, max_entries: usize) -> Vec<Vec<E>> {
+    let node_count = items.len().div_ceil(max_entries);
+    let slice_count = (node_count as f64).sqrt().ceil() as usize;
+    let slice_size = slice_count * max_entries;
+    items.sort_by(|a, b| a.bounds().center()[0].total_cmp(&b.bounds().center()[0]));
+
+    let mut groups = vec![];
+    while !items.is_empty() {
+        let rest = items.split_off(slice_size.min(items.len()));
+        let mut slice = std::mem::replace(&mut items, rest);
+        slice.sort_by(|a, b| a.bounds().center()[1].total_cmp(&b.bounds().center()[1]));
+        while !slice.is_empty() {
+            let rest = slice.split_off(max_entries.min(slice.len()));
+            groups.push(std::mem::replace(&mut slice, rest));
+        }
+    }
+    groups
+}
+
+impl<T: HeapSize> HeapSize for RNode<T> {
+    fn heap_size(&self) -> usize {
+        match &self.children {
+            Children::Leaf(entries) => {
+                let slots = entries.capacity() * std::mem::size_of::<Entry<T>>();
+                slots + entries.iter().map(|entry| entry.data.heap_size()).sum::<usize>()
+            }
+            Children::Inner(nodes) => nodes.heap_size(),
+        }
+    }
+}
+
+impl<T: HeapSize> HeapSize for RTree<T> {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_rects(count: usize, seed: u64) -> Vec<Rect> {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) % 1000) as f64 / 10.0
+        };
+        (0..count)
+            .map(|_| {
+                let (x, y) = (next(), next());
+                Rect::new([x, y], [x + next() / 10.0, y + next() / 10.0])
+            })
+            .collect()
+    }
+
+    fn sorted_ids(found: Vec<(&Rect, &usize)>) -> Vec<usize> {
+        let mut ids: Vec<usize> = found.into_iter().map(|(_, &id)| id).collect();
+        ids.sort();
+        ids
+    }
+
+    fn brute_force(rects: &[Rect], area: &Rect) -> Vec<usize> {
+        (0..rects.len()).filter(|&id| rects[id].intersects(area)).collect()
+    }
+
+    /// Checks the tree and that every node but the root is at least half
+    /// full, which splits keep but bulk loading doesn't
+    fn assert_valid<T>(tree: &RTree<T>) {
+        tree.assert_invariants();
+        let mut pending: Vec<&RNode<T>> = vec![];
+        if let Children::Inner(nodes) = &tree.root.children {
+            pending.extend(nodes);
+        }
+        while let Some(node) = pending.pop() {
+            let count = match &node.children {
+                Children::Leaf(entries) => entries.len(),
+                Children::Inner(nodes) => {
+                    pending.extend(nodes);
+                    nodes.len()
+                }
+            };
+            assert!(count >= tree.max_entries / 2);
+        }
+    }
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let tree = RTree::<u32>::new();
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.bounds(), None);
+        assert_eq!(tree.height(), 1);
+        assert!(tree.search(&Rect::new([0.0, 0.0], [0.0, 0.0])).is_empty());
+    }
+
+    #[test]
+    fn test_search_small_tree() {
+        let mut tree = RTree::new();
+        tree.insert(Rect::new([0.0, 0.0], [2.0, 2.0]), "a");
+        tree.insert(Rect::new([5.0, 5.0], [6.0, 8.0]), "b");
+        tree.insert(Rect::new([1.0, 4.0], [3.0, 5.0]), "c");
+
+        let found: Vec<&str> = tree
+            .search(&Rect::new([1.5, 1.5], [5.0, 5.0]))
+            .into_iter()
+            .map(|(_, &name)| name)
+            .collect();
+
+        assert_eq!(found.len(), 3);
+        assert_eq!(tree.bounds(), Some(Rect::new([0.0, 0.0], [6.0, 8.0])));
+        assert!(tree.search(&Rect::new([3.5, 0.0], [4.5, 4.5])).is_empty());
+    }
+
+    #[test]
+    fn test_touching_rectangles_intersect() {
+        let mut tree = RTree::new();
+        tree.insert(Rect::new([0.0, 0.0], [1.0, 1.0]), 1);
+
+        assert_eq!(tree.search(&Rect::new([1.0, 1.0], [2.0, 2.0])).len(), 1);
+    }
+
+    #[test]
+    fn test_overflowing_root_is_split() {
+        let mut tree = RTree::with_max_entries(4);
+
+        for (id, rect) in random_rects(5, 3).into_iter().enumerate() {
+            tree.insert(rect, id);
+        }
+
+        assert_eq!(tree.height(), 2);
+        assert_valid(&tree);
+    }
+
+    #[test]
+    fn test_inserts_keep_tree_valid_and_match_brute_force() {
+        let rects = random_rects(300, 7);
+        let mut tree = RTree::with_max_entries(6);
+        for (id, &rect) in rects.iter().enumerate() {
+            tree.insert(rect, id);
+        }
+
+        assert_eq!(tree.len(), 300);
+        assert_valid(&tree);
+        for area in random_rects(30, 21) {
+            assert_eq!(sorted_ids(tree.search(&area)), brute_force(&rects, &area));
+        }
+    }
+
+    #[test]
+    fn test_bulk_load_packs_full_nodes() {
+        let rects = random_rects(64, 5);
+
+        let tree = RTree::bulk_load(rects.into_iter().zip(0..).collect());
+
+        assert_eq!(tree.len(), 64);
+        assert_eq!(tree.height(), 2);
+        match &tree.root.children {
+            Children::Inner(nodes) => {
+                assert_eq!(nodes.len(), 8);
+                assert!(nodes.iter().all(|node| node.height() == 1));
+            }
+            Children::Leaf(_) => panic!("Root should be an inner node"),
+        }
+    }
+
+    #[test]
+    fn test_bulk_load_matches_brute_force() {
+        let rects = random_rects(500, 13);
+
+        let mut tree = RTree::bulk_load(rects.iter().copied().zip(0..).collect());
+        for area in random_rects(30, 31) {
+            assert_eq!(sorted_ids(tree.search(&area)), brute_force(&rects, &area));
+        }
+
+        let extra = Rect::new([50.0, 50.0], [51.0, 51.0]);
+        tree.insert(extra, 500);
+        assert!(sorted_ids(tree.search(&extra)).contains(&500));
+        assert_eq!(tree.len(), 501);
+    }
+}
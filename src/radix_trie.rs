@@ -0,0 +1,425 @@
+//! Radix Trie (compressed trie) implementation
+//! Chains of nodes that have only a single child are merged into one edge,
+//! so edges are labelled with whole strings instead of single characters.
+//! Supports longest-prefix-match, which is how routing tables pick the most
+//! specific route for an address.
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Edge<V> {
+    label: String,
+    node: RadixNode<V>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RadixNode<V> {
+    value: Option<V>,
+    edges: Vec<Edge<V>>,
+}
+
+impl<V> RadixNode<V> {
+    fn new(value: Option<V>) -> Self {
+        RadixNode { value, edges: vec![] }
+    }
+
+    /// Index of the edge whose label starts with the same character as `key`.
+    /// Edges of one node never share their first character.
+    fn edge_index(&self, key: &str) -> Option<usize> {
+        let first = key.chars().next()?;
+        self.edges.iter().position(|edge| edge.label.starts_with(first))
+    }
+
+    fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        if key.is_empty() {
+            return self.value.replace(value);
+        }
+
+        let index = match self.edge_index(key) {
+            Some(index) => index,
+            None => {
+                self.edges.push(Edge { label: key.to_string(), node: RadixNode::new(Some(value)) });
+                self.edges.sort_by(|a, b| a.label.cmp(&b.label));
+                return None;
+            }
+        };
+
+        let edge = &mut self.edges[index];
+        let common = common_prefix_len(&edge.label, key);
+        if common < edge.label.len() {
+            // Split the edge: the shared prefix becomes a new intermediate node
+            let suffix = edge.label.split_off(common);
+            let old_node = std::mem::replace(&mut edge.node, RadixNode::new(None));
+            edge.node.edges.push(Edge { label: suffix, node: old_node });
+        }
+        edge.node.insert(&key[common..], value)
+    }
+
+    fn get(&self, key: &str) -> Option<&V> {
+        if key.is_empty() {
+            return self.value.as_ref();
+        }
+        let edge = &self.edges[self.edge_index(key)?];
+        key.strip_prefix(edge.label.as_str()).and_then(|rest| edge.node.get(rest))
+    }
+
+    fn remove(&mut self, key: &str) -> Option<V> {
+        if key.is_empty() {
+            return self.value.take();
+        }
+        let index = self.edge_index(key)?;
+        let edge = &mut self.edges[index];
+        let rest = key.strip_prefix(edge.label.as_str())?;
+        let removed = edge.node.remove(rest)?;
+
+        if edge.node.value.is_none() {
+            match edge.node.edges.len() {
+                0 => {
+                    self.edges.remove(index);
+                }
+                1 => {
+                    // Merge the single child back into this edge
+                    let child = edge.node.edges.pop().unwrap();
+                    edge.label.push_str(&child.label);
+                    edge.node = child.node;
+                }
+                _ => {}
+            }
+        }
+        Some(removed)
+    }
+
+    fn collect_keys(&self, prefix: &mut String, keys: &mut Vec<String>) {
+        if self.value.is_some() {
+            keys.push(prefix.clone());
+        }
+        for edge in &self.edges {
+            let length = prefix.len();
+            prefix.push_str(&edge.label);
+            edge.node.collect_keys(prefix, keys);
+            prefix.truncate(length);
+        }
+    }
+
+    /// Checks the edges under the node and returns how many values the
+    /// subtree holds. Only the root may have no value and fewer than two edges.
+    #[cfg(any(test, feature = "debug-invariants"))]
+    fn check(&self, is_root: bool) -> usize {
+        if !is_root && self.value.is_none() {
+            assert!(self.edges.len() >= 2, "Node without a value has fewer than two edges");
+        }
+        let firsts: Vec<char> =
+            self.edges.iter().filter_map(|edge| edge.label.chars().next()).collect();
+        assert_eq!(firsts.len(), self.edges.len(), "Edge has an empty label");
+        let ascending = firsts.windows(2).all(|pair| pair[0] < pair[1]);
+        assert!(ascending, "Edges are out of order or share a first char");
+        let below: usize = self.edges.iter().map(|edge| edge.node.check(false)).sum();
+        below + usize::from(self.value.is_some())
+    }
+}
+
+/// Length in bytes of the longest common prefix that ends on a char boundary.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, char_a), char_b)| char_a != char_b)
+        .map(|((index, _), _)| index)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadixTrie<V> {
+    root: RadixNode<V>,
+    size: usize,
+}
+
+impl<V> RadixTrie<V> {
+    pub fn new() -> Self {
+        RadixTrie { root: RadixNode::new(None), size: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = RadixTrie::new();
+        check_invariants!(self);
+    }
+
+    /// Inserts key and value. Returns the old value if the key was present.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let old_value = self.root.insert(key, value);
+        if old_value.is_none() {
+            self.size += 1;
+        }
+        check_invariants!(self);
+        old_value
+    }
+
+    /// Returns the value stored for exactly this key.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.root.get(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes the key and returns its value. Nodes left with a single
+    /// child are merged with it, so the trie stays compressed.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let removed = self.root.remove(key);
+        if removed.is_some() {
+            self.size -= 1;
+        }
+        check_invariants!(self);
+        removed
+    }
+
+    /// Finds the longest stored key that is a prefix of `key`.
+    /// Returns the matched prefix together with its value.
+    pub fn longest_prefix_match<'a>(&self, key: &'a str) -> Option<(&'a str, &V)> {
+        let mut node = &self.root;
+        let mut consumed = 0;
+        let mut best = node.value.as_ref().map(|value| (0, value));
+
+        while let Some(index) = node.edge_index(&key[consumed..]) {
+            let edge = &node.edges[index];
+            if !key[consumed..].starts_with(edge.label.as_str()) {
+                break;
+            }
+            consumed += edge.label.len();
+            node = &edge.node;
+            if let Some(value) = &node.value {
+                best = Some((consumed, value));
+            }
+        }
+        best.map(|(length, value)| (&key[..length], value))
+    }
+
+    /// Returns all keys in lexicographic order.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = vec![];
+        self.root.collect_keys(&mut String::new(), &mut keys);
+        keys
+    }
+}
+
+impl<V> Default for RadixTrie<V> {
+    fn default() -> Self {
+        RadixTrie::new()
+    }
+}
+
+/// The items of a trie are its keys
+impl<V> Collection for RadixTrie<V> {
+    type Item = String;
+
+    fn len(&self) -> usize {
+        RadixTrie::len(self)
+    }
+
+    fn clear(&mut self) {
+        RadixTrie::clear(self);
+    }
+
+    fn contains(&self, key: &String) -> bool {
+        self.contains_key(key)
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<V> Invariants for RadixTrie<V> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.root.check(true), self.size, "size doesn't match the values");
+    }
+}
+
+impl<V: HeapSize> HeapSize for RadixNode<V> {
+    fn heap_size(&self) -> usize {
+        let edges = self.edges.capacity() * std::mem::size_of::<Edge<V>>();
+        let below: usize =
+            self.edges.iter().map(|edge| edge.label.heap_size() + edge.node.heap_size()).sum();
+        self.value.heap_size() + edges + below
+    }
+}
+
+impl<V: HeapSize> HeapSize for RadixTrie<V> {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trie_with_keys(keys: &[&str]) -> RadixTrie<usize> {
+        let mut trie = RadixTrie::new();
+        for (index, key) in keys.iter().enumerate() {
+            trie.insert(key, index);
+        }
+        trie
+    }
+
+    #[test]
+    fn new_trie_is_empty() {
+        let trie = RadixTrie::<i32>::new();
+
+        assert!(trie.is_empty());
+        assert_eq!(trie.len(), 0);
+        assert!(trie.root.edges.is_empty());
+    }
+
+    #[test]
+    fn test_insert_single_key_creates_one_edge() {
+        let mut trie = RadixTrie::new();
+
+        let result = trie.insert("romane", 1);
+
+        assert!(result.is_none());
+        assert_eq!(trie.len(), 1);
+        assert_eq!(trie.root.edges.len(), 1);
+        assert_eq!(trie.root.edges[0].label, "romane");
+    }
+
+    #[test]
+    fn test_insert_splits_edge_on_common_prefix() {
+        let trie = trie_with_keys(&["romane", "romanus"]);
+
+        assert_eq!(trie.root.edges.len(), 1);
+        let edge = &trie.root.edges[0];
+        assert_eq!(edge.label, "roman");
+        assert!(edge.node.value.is_none());
+        let labels: Vec<&str> = edge.node.edges.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["e", "us"]);
+    }
+
+    #[test]
+    fn test_insert_key_that_is_prefix_of_existing_key() {
+        let mut trie = trie_with_keys(&["romane"]);
+
+        trie.insert("rom", 7);
+
+        assert_eq!(trie.root.edges[0].label, "rom");
+        assert_eq!(trie.root.edges[0].node.value, Some(7));
+        assert_eq!(trie.get("romane"), Some(&0));
+        assert_eq!(trie.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_existing_key_returns_old_value() {
+        let mut trie = trie_with_keys(&["key"]);
+
+        let result = trie.insert("key", 42);
+
+        assert_eq!(result, Some(0));
+        assert_eq!(trie.get("key"), Some(&42));
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn test_get_with_multiple_keys() {
+        let keys = ["romane", "romanus", "romulus", "rubens", "ruber", "rubicon", "rubicundus"];
+        let trie = trie_with_keys(&keys);
+
+        for (index, key) in keys.iter().enumerate() {
+            assert_eq!(trie.get(key), Some(&index));
+        }
+        assert_eq!(trie.get("rom"), None, "Inner nodes without value are not keys");
+        assert_eq!(trie.get("rubiconx"), None);
+        assert_eq!(trie.get(""), None);
+        assert_eq!(trie.len(), keys.len());
+    }
+
+    #[test]
+    fn test_empty_string_is_a_valid_key() {
+        let mut trie = trie_with_keys(&["a"]);
+
+        trie.insert("", 9);
+
+        assert_eq!(trie.get(""), Some(&9));
+        assert!(trie.contains_key("a"));
+        assert_eq!(trie.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_key_not_present() {
+        let mut trie = trie_with_keys(&["romane", "romanus"]);
+
+        assert_eq!(trie.remove("roman"), None);
+        assert_eq!(trie.remove("zebra"), None);
+        assert_eq!(trie.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_merges_single_child_chain() {
+        let mut trie = trie_with_keys(&["romane", "romanus"]);
+
+        let result = trie.remove("romane");
+
+        assert_eq!(result, Some(0));
+        assert_eq!(trie.root.edges.len(), 1);
+        assert_eq!(trie.root.edges[0].label, "romanus");
+        assert!(trie.root.edges[0].node.edges.is_empty());
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_all_keys_leaves_empty_trie() {
+        let keys = ["romane", "romanus", "romulus", "rubens", "ruber", "rubicon"];
+        let mut trie = trie_with_keys(&keys);
+
+        for (index, key) in keys.iter().enumerate() {
+            assert_eq!(trie.remove(key), Some(index));
+        }
+
+        assert!(trie.is_empty());
+        assert_eq!(trie.root, RadixNode::new(None));
+    }
+
+    #[test]
+    fn test_longest_prefix_match_picks_most_specific() {
+        let mut trie = RadixTrie::new();
+        trie.insert("10.", "default");
+        trie.insert("10.1.", "subnet");
+        trie.insert("10.1.2.", "host group");
+
+        assert_eq!(trie.longest_prefix_match("10.1.2.7"), Some(("10.1.2.", &"host group")));
+        assert_eq!(trie.longest_prefix_match("10.1.9.1"), Some(("10.1.", &"subnet")));
+        assert_eq!(trie.longest_prefix_match("10.7.0.1"), Some(("10.", &"default")));
+        assert_eq!(trie.longest_prefix_match("192.168.0.1"), None);
+    }
+
+    #[test]
+    fn test_longest_prefix_match_stops_inside_edge_label() {
+        let trie = trie_with_keys(&["ab", "abcdef"]);
+
+        assert_eq!(trie.longest_prefix_match("abcdx"), Some(("ab", &0)));
+    }
+
+    #[test]
+    fn test_keys_are_returned_in_order() {
+        let trie = trie_with_keys(&["ruber", "romane", "rubens", "a", "romanus"]);
+
+        assert_eq!(trie.keys(), vec!["a", "romane", "romanus", "rubens", "ruber"]);
+    }
+
+    #[test]
+    fn test_multibyte_characters_split_on_char_boundary() {
+        let trie = trie_with_keys(&["żółw", "żółty"]);
+
+        assert_eq!(trie.root.edges[0].label, "żół");
+        assert_eq!(trie.get("żółw"), Some(&0));
+        assert_eq!(trie.get("żółty"), Some(&1));
+    }
+}
@@ -0,0 +1,208 @@
+//! Range Map implementation
+//! Maps disjoint half-open ranges `start..end` to values, stored in an AVL
+//! tree keyed by the range start. Inserting a range overwrites whatever it
+//! overlaps: ranges that stick out on either side are trimmed, and a range
+//! that contains the new one is split in two around it. Looking up a point
+//! finds the range with the largest start not after it in O(log n).
+
+use std::ops::Range;
+
+use crate::avl_tree::AvlTreeMap;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeMap<K, V> {
+    /// Start of every range mapped to its end and value
+    ranges: AvlTreeMap<K, (K, V)>,
+}
+
+impl<K: Ord + Copy, V: Clone> RangeMap<K, V> {
+    pub fn new() -> Self {
+        RangeMap { ranges: AvlTreeMap::new() }
+    }
+
+    /// Number of stored ranges
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Maps every point of the range to the value, overwriting older ranges
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        if range.is_empty() {
+            return;
+        }
+        self.remove(range.clone());
+        self.ranges.insert(range.start, (range.end, value));
+        check_invariants!(self);
+    }
+
+    /// Unmaps every point of the range, trimming and splitting stored ranges
+    pub fn remove(&mut self, range: Range<K>) {
+        if range.is_empty() {
+            return;
+        }
+        let (start, end) = (range.start, range.end);
+        let before = self.ranges
+            .next_below(&start)
+            .map(|(before_start, (before_end, _))| (*before_start, *before_end))
+            .filter(|(_, before_end)| *before_end > start);
+        if let Some((before_start, before_end)) = before {
+            let (_, value) = self.ranges.remove(&before_start).unwrap();
+            if before_end > end {
+                self.ranges.insert(end, (before_end, value.clone()));
+            }
+            self.ranges.insert(before_start, (start, value));
+        }
+        let cut: Vec<K> = self.ranges.range(start..end).map(|(cut_start, _)| *cut_start).collect();
+        for cut_start in cut {
+            let (cut_end, value) = self.ranges.remove(&cut_start).unwrap();
+            if cut_end > end {
+                self.ranges.insert(end, (cut_end, value));
+            }
+        }
+        check_invariants!(self);
+    }
+
+    /// Value of the range that covers the point
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_range_value(point).map(|(_, value)| value)
+    }
+
+    /// Range that covers the point, together with its value
+    pub fn get_range_value(&self, point: &K) -> Option<(Range<K>, &V)> {
+        let (start, (end, value)) = match self.ranges.get(point) {
+            Some(entry) => (point, entry),
+            None => self.ranges.next_below(point)?,
+        };
+        (*end > *point).then_some((*start..*end, value))
+    }
+
+    pub fn contains_key(&self, point: &K) -> bool {
+        self.get(point).is_some()
+    }
+
+    /// Ranges and their values in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = (Range<K>, &V)> + '_ {
+        self.ranges.iter().map(|(start, (end, value))| (*start..*end, value))
+    }
+}
+
+/// Checks no range is empty and no two ranges overlap
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<K: Ord + Copy, V: Clone> Invariants for RangeMap<K, V> {
+    fn assert_invariants(&self) {
+        let ranges: Vec<Range<K>> = self.iter().map(|(range, _)| range).collect();
+        assert!(ranges.iter().all(|range| range.start < range.end), "Stored range is empty");
+        let disjoint = ranges.windows(2).all(|pair| pair[0].end <= pair[1].start);
+        assert!(disjoint, "Ranges overlap");
+    }
+}
+
+impl<K: Ord + Copy, V: Clone> Default for RangeMap<K, V> {
+    fn default() -> Self {
+        RangeMap::new()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for RangeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.ranges.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(map: &RangeMap<u32, char>) -> Vec<(Range<u32>, char)> {
+        map.iter().map(|(range, value)| (range, *value)).collect()
+    }
+
+    #[test]
+    fn when_map_is_created_it_is_empty() {
+        let map = RangeMap::<u32, char>::new();
+
+        assert!(map.is_empty());
+        assert_eq!(map.get(&0), None);
+    }
+
+    #[test]
+    fn test_get_by_point() {
+        let mut map = RangeMap::new();
+
+        map.insert(0..10, 'a');
+        map.insert(20..30, 'b');
+
+        assert_eq!(map.get(&0), Some(&'a'));
+        assert_eq!(map.get(&9), Some(&'a'));
+        assert_eq!(map.get(&10), None);
+        assert_eq!(map.get_range_value(&25), Some((20..30, &'b')));
+        assert!(!map.contains_key(&30));
+    }
+
+    #[test]
+    fn test_insert_inside_splits_existing_range() {
+        let mut map = RangeMap::new();
+
+        map.insert(0..10, 'a');
+        map.insert(3..5, 'b');
+
+        assert_eq!(entries(&map), vec![(0..3, 'a'), (3..5, 'b'), (5..10, 'a')]);
+    }
+
+    #[test]
+    fn test_insert_overwrites_overlapping_ranges() {
+        let mut map = RangeMap::new();
+        map.insert(0..5, 'a');
+        map.insert(5..10, 'b');
+        map.insert(10..15, 'c');
+
+        map.insert(3..12, 'x');
+
+        assert_eq!(entries(&map), vec![(0..3, 'a'), (3..12, 'x'), (12..15, 'c')]);
+    }
+
+    #[test]
+    fn test_remove_leaves_a_gap() {
+        let mut map = RangeMap::new();
+        map.insert(0..10, 'a');
+
+        map.remove(2..4);
+        map.remove(8..20);
+
+        assert_eq!(entries(&map), vec![(0..2, 'a'), (4..8, 'a')]);
+        assert_eq!(map.get(&3), None);
+    }
+
+    #[test]
+    fn test_random_operations_match_array() {
+        let mut map = RangeMap::new();
+        let mut expected: [Option<u32>; 50] = [None; 50];
+        let mut state: u64 = 5;
+
+        for step in 0..2000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let start = ((state >> 33) % 50) as u32;
+            let end = (start + ((state >> 45) % 12) as u32).min(50);
+            let range = start as usize..end as usize;
+            if (state >> 60).is_multiple_of(4) {
+                map.remove(start..end);
+                expected[range].iter_mut().for_each(|cell| *cell = None);
+            } else {
+                map.insert(start..end, step);
+                expected[range].iter_mut().for_each(|cell| *cell = Some(step));
+            }
+        }
+
+        for (point, value) in expected.iter().enumerate() {
+            assert_eq!(map.get(&(point as u32)).copied(), *value, "Point {}", point);
+        }
+    }
+}
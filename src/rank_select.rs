@@ -0,0 +1,235 @@
+//! Rank/Select bit vector
+//! Read-only bit vector with small precomputed counts. Every superblock of
+//! 512 bits stores the number of ones before it, and every 64-bit word stores
+//! the ones before it inside its superblock. `rank1` adds the two counts to
+//! one popcount, so it is O(1). `select1` binary searches the superblocks,
+//! then scans at most 8 words and selects the bit within the last one.
+
+use crate::bitvec::BitVec;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+const WORD_BITS: usize = 64;
+const WORDS_PER_SUPERBLOCK: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankSelect {
+    bits: BitVec,
+    /// Ones before each superblock
+    superblocks: Vec<usize>,
+    /// Ones before each word, counted from the start of its superblock
+    blocks: Vec<u16>,
+    ones: usize,
+}
+
+impl RankSelect {
+    /// Builds the counts in O(n / 64)
+    pub fn new(bits: BitVec) -> Self {
+        let mut superblocks = vec![];
+        let mut blocks = vec![];
+        let mut total = 0;
+        let mut in_superblock = 0;
+        for (index, word) in bits.as_words().iter().enumerate() {
+            if index.is_multiple_of(WORDS_PER_SUPERBLOCK) {
+                superblocks.push(total);
+                in_superblock = 0;
+            }
+            blocks.push(in_superblock as u16);
+            let ones = word.count_ones() as usize;
+            in_superblock += ones;
+            total += ones;
+        }
+        let rank_select = RankSelect { bits, superblocks, blocks, ones: total };
+        check_invariants!(&rank_select);
+        rank_select
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub fn bits(&self) -> &BitVec {
+        &self.bits
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        self.bits.get(index)
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Number of ones in `0..end`
+    pub fn rank1(&self, end: usize) -> usize {
+        assert!(end <= self.len(), "End {} out of bounds for length {}", end, self.len());
+        let word = end / WORD_BITS;
+        if word == self.blocks.len() {
+            // Only possible when `end` is the length and it is a multiple of 64
+            return self.ones;
+        }
+        let mask = (1u64 << (end % WORD_BITS)) - 1;
+        self.rank_before_word(word) + (self.word(word) & mask).count_ones() as usize
+    }
+
+    /// Number of zeros in `0..end`
+    pub fn rank0(&self, end: usize) -> usize {
+        end - self.rank1(end)
+    }
+
+    /// Position of the one with rank `k`, counting from 0
+    pub fn select1(&self, k: usize) -> Option<usize> {
+        self.select(k, false)
+    }
+
+    /// Position of the zero with rank `k`, counting from 0
+    pub fn select0(&self, k: usize) -> Option<usize> {
+        self.select(k, true)
+    }
+
+    /// Shared by both selects. With `zeros` the words are inverted and
+    /// counts become `bits before - ones before`.
+    fn select(&self, k: usize, zeros: bool) -> Option<usize> {
+        let count_before_word = |word: usize| {
+            let ones = self.rank_before_word(word);
+            if zeros { word * WORD_BITS - ones } else { ones }
+        };
+        let total = if zeros { self.len() - self.count_ones() } else { self.count_ones() };
+        if k >= total {
+            return None;
+        }
+
+        // Last superblock that starts with at most k matching bits
+        let (mut lo, mut hi) = (0, self.superblocks.len());
+        while hi - lo > 1 {
+            let middle = (lo + hi) / 2;
+            if count_before_word(middle * WORDS_PER_SUPERBLOCK) <= k {
+                lo = middle;
+            } else {
+                hi = middle;
+            }
+        }
+        let mut word = lo * WORDS_PER_SUPERBLOCK;
+        let last_word = (word + WORDS_PER_SUPERBLOCK).min(self.blocks.len());
+        while word + 1 < last_word && count_before_word(word + 1) <= k {
+            word += 1;
+        }
+
+        let mut bits = if zeros { !self.word(word) } else { self.word(word) };
+        for _ in 0..k - count_before_word(word) {
+            bits &= bits - 1;
+        }
+        Some(word * WORD_BITS + bits.trailing_zeros() as usize)
+    }
+
+    fn rank_before_word(&self, word: usize) -> usize {
+        self.superblocks[word / WORDS_PER_SUPERBLOCK] + self.blocks[word] as usize
+    }
+
+    fn word(&self, word: usize) -> u64 {
+        self.bits.as_words()[word]
+    }
+}
+
+/// Checks the counts before every superblock and word against the bits
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for RankSelect {
+    fn assert_invariants(&self) {
+        let words = self.bits.as_words();
+        assert_eq!(self.blocks.len(), words.len(), "Not one block per word");
+        assert_eq!(
+            self.superblocks.len(),
+            words.len().div_ceil(WORDS_PER_SUPERBLOCK),
+            "Wrong number of superblocks"
+        );
+        let mut total = 0;
+        for (index, word) in words.iter().enumerate() {
+            assert_eq!(self.rank_before_word(index), total, "Count before a word is wrong");
+            total += word.count_ones() as usize;
+        }
+        assert_eq!(self.ones, total, "ones doesn't match the bits");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_bits(len: usize, seed: u64, density: u64) -> BitVec {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 33) % 100 < density
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty() {
+        let rank_select = RankSelect::new(BitVec::new());
+
+        assert!(rank_select.is_empty());
+        assert_eq!(rank_select.rank1(0), 0);
+        assert_eq!(rank_select.select1(0), None);
+        assert_eq!(rank_select.select0(0), None);
+    }
+
+    #[test]
+    fn test_small_example() {
+        let bits: BitVec = [true, false, true, true, false, false, true].into_iter().collect();
+        let rank_select = RankSelect::new(bits);
+
+        assert_eq!(rank_select.rank1(3), 2);
+        assert_eq!(rank_select.rank0(5), 2);
+        assert_eq!(rank_select.select1(0), Some(0));
+        assert_eq!(rank_select.select1(3), Some(6));
+        assert_eq!(rank_select.select1(4), None);
+        assert_eq!(rank_select.select0(2), Some(5));
+    }
+
+    #[test]
+    fn test_length_multiple_of_word() {
+        let rank_select = RankSelect::new(BitVec::repeat(true, 1024));
+
+        assert_eq!(rank_select.rank1(1024), 1024);
+        assert_eq!(rank_select.rank1(513), 513);
+        assert_eq!(rank_select.select1(1023), Some(1023));
+        assert_eq!(rank_select.select0(0), None);
+    }
+
+    #[test]
+    fn test_rank_matches_bitvec_rank() {
+        for density in [3, 50, 97] {
+            let bits = random_bits(3000, density, density);
+            let rank_select = RankSelect::new(bits.clone());
+
+            for end in 0..=bits.len() {
+                assert_eq!(rank_select.rank1(end), bits.rank(end));
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_is_inverse_of_rank() {
+        for density in [3, 50, 97] {
+            let bits = random_bits(3000, density + 1, density);
+            let rank_select = RankSelect::new(bits.clone());
+
+            let ones: Vec<usize> = bits.iter_ones().collect();
+            for (k, &position) in ones.iter().enumerate() {
+                assert_eq!(rank_select.select1(k), Some(position));
+            }
+            let zeros: Vec<usize> = (0..bits.len()).filter(|&i| !bits.get(i).unwrap()).collect();
+            for (k, &position) in zeros.iter().enumerate() {
+                assert_eq!(rank_select.select0(k), Some(position));
+            }
+            assert_eq!(rank_select.select1(ones.len()), None);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Rayon parallel iterators, behind the `rayon` feature
+//! `par_iter` on the hash map, bit vector and matrix splits the work over
+//! rayon's thread pool. The hash map hands out whole buckets, so every
+//! thread walks its own buckets and no key is looked at twice. The bit
+//! vector splits by index and counts ones a word at a time. The matrix
+//! splits by cell, or by row with `par_rows`.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use rayon::prelude::*;
+
+use crate::bitvec::{ BitVec, WORD_BITS };
+use crate::hash_map::HashMap;
+use crate::matrix::Matrix;
+
+impl<K, V> HashMap<K, V>
+where
+    K: Hash + Clone + PartialEq + Debug + Sync,
+    V: Clone + Debug + Sync,
+{
+    /// Key-value pairs in no particular order, bucket by bucket
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&K, &V)> {
+        self.buckets()
+            .par_iter()
+            .flat_map_iter(|bucket| bucket.iter().map(|(key, value)| (key, value)))
+    }
+}
+
+impl<K, V> HashMap<K, V>
+where
+    K: Hash + Clone + PartialEq + Debug + Send + Sync,
+    V: Clone + Debug + Send,
+{
+    /// Pairs with mutable values, to update all of them in place
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (&K, &mut V)> {
+        self.buckets_mut()
+            .par_iter_mut()
+            .flat_map_iter(|bucket| bucket.iter_mut().map(|(key, value)| (&*key, value)))
+    }
+}
+
+impl BitVec {
+    /// Bits in index order
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = bool> + '_ {
+        let words = self.as_words();
+        (0..self.len())
+            .into_par_iter()
+            .map(move |index| (words[index / WORD_BITS] >> (index % WORD_BITS)) & 1 == 1)
+    }
+
+    /// `count_ones` with the words split between threads
+    pub fn par_count_ones(&self) -> usize {
+        self.as_words()
+            .par_iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+impl<T: Sync> Matrix<T> {
+    /// Cells in row-major order
+    pub fn par_iter(&self) -> rayon::slice::Iter<'_, T> {
+        self.as_slice().par_iter()
+    }
+
+    /// Rows as slices, from the first one
+    pub fn par_rows(&self) -> rayon::slice::Chunks<'_, T> {
+        self.as_slice().par_chunks(self.columns().max(1))
+    }
+
+    /// `map` with the cells split between threads
+    pub fn par_map<U: Send>(&self, f: impl Fn(&T) -> U + Sync + Send) -> Matrix<U> {
+        let data = self.as_slice().par_iter().map(f).collect();
+        Matrix::from_vec(self.rows(), self.columns(), data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_map_par_iter_sees_every_pair_once() {
+        let mut map = HashMap::new();
+        for key in 0..1000u64 {
+            map.insert(key, key * 2);
+        }
+
+        let count = map.par_iter().count();
+        let sum: u64 = map.par_iter().map(|(_, value)| value).sum();
+
+        assert_eq!(count, 1000);
+        assert_eq!(sum, 999 * 1000);
+    }
+
+    #[test]
+    fn test_hash_map_par_iter_mut_updates_values() {
+        let mut map = HashMap::new();
+        for key in 0..600 {
+            map.insert(key, key);
+        }
+
+        map.par_iter_mut().for_each(|(key, value)| *value += key);
+
+        assert!((0..600).all(|key| map.get(key) == Some(key * 2)));
+    }
+
+    #[test]
+    fn test_bit_vec_matches_sequential() {
+        let mut bits = BitVec::new();
+        for index in 0..1000 {
+            bits.push(index % 3 == 0);
+        }
+
+        let parallel: Vec<bool> = bits.par_iter().collect();
+
+        assert_eq!(parallel, bits.iter().collect::<Vec<_>>());
+        assert_eq!(bits.par_count_ones(), bits.count_ones());
+    }
+
+    #[test]
+    fn test_matrix_rows_and_map() {
+        let matrix = Matrix::from_fn(3, 4, |row, column| row * 4 + column);
+
+        let row_sums: Vec<usize> = matrix.par_rows().map(|row| row.iter().sum()).collect();
+        let doubled = matrix.par_map(|cell| cell * 2);
+
+        assert_eq!(row_sums, vec![6, 22, 38]);
+        assert_eq!(doubled, matrix.map(|cell| cell * 2));
+        assert_eq!(matrix.par_iter().max(), Some(&11));
+    }
+}
@@ -0,0 +1,141 @@
+//! ASCII art rendering
+//! `render` draws a structure as plain text, for a test failure message or
+//! the terminal, without Graphviz or any other tool. Lists are drawn left to
+//! right as a row of boxes ending in `None`. Trees are drawn top down as an
+//! indented outline with branches, the left child first, and a `.` for a
+//! missing child when the other one is there, so left and right never get
+//! mixed up.
+
+use std::fmt::Debug;
+
+use crate::persistent::List;
+use crate::singly_linked_list::SinglyLinkedList;
+
+pub trait Render {
+    fn render(&self) -> String;
+}
+
+/// Row of boxes, one per item, linked by arrows
+pub(crate) fn render_list(labels: impl Iterator<Item = String>) -> String {
+    let (mut top, mut middle, mut bottom) = (String::new(), String::new(), String::new());
+    for label in labels {
+        let border = format!("+{}+    ", "-".repeat(label.chars().count() + 2));
+        top.push_str(&border);
+        middle.push_str(&format!("| {} | -> ", label));
+        bottom.push_str(&border);
+    }
+    middle.push_str("None");
+    [top, middle, bottom]
+        .iter()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Outline of a binary tree, `children` returns the left and the right child
+pub(crate) fn render_tree<'a, N>(
+    root: Option<&'a N>,
+    label: &impl Fn(&N) -> String,
+    children: &impl Fn(&'a N) -> [Option<&'a N>; 2]
+) -> String {
+    let Some(root) = root else {
+        return "(empty)".to_string();
+    };
+    let mut out = label(root);
+    render_children(root, "", label, children, &mut out);
+    out
+}
+
+fn render_children<'a, N>(
+    node: &'a N,
+    prefix: &str,
+    label: &impl Fn(&N) -> String,
+    children: &impl Fn(&'a N) -> [Option<&'a N>; 2],
+    out: &mut String
+) {
+    let [left, right] = children(node);
+    if left.is_none() && right.is_none() {
+        return;
+    }
+    for (child, last) in [(left, false), (right, true)] {
+        let (branch, indent) = if last { ("`-- ", "    ") } else { ("|-- ", "|   ") };
+        out.push('\n');
+        out.push_str(prefix);
+        out.push_str(branch);
+        match child {
+            Some(child) => {
+                out.push_str(&label(child));
+                render_children(child, &format!("{}{}", prefix, indent), label, children, out);
+            }
+            None => out.push('.'),
+        }
+    }
+}
+
+impl<T: Clone + Debug> Render for SinglyLinkedList<T> {
+    fn render(&self) -> String {
+        render_list(self.iter().map(|data| format!("{:?}", data)))
+    }
+}
+
+impl<T: Debug> Render for List<T> {
+    fn render(&self) -> String {
+        render_list(self.iter().map(|item| format!("{:?}", item)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl_tree::AvlTreeMap;
+
+    #[test]
+    fn when_list_is_empty_only_none_is_drawn() {
+        assert_eq!(SinglyLinkedList::<i32>::new().render(), "None");
+        assert_eq!(List::<i32>::new().render(), "None");
+    }
+
+    #[test]
+    fn test_singly_linked_list() {
+        let mut list = SinglyLinkedList::new();
+        list.append(1);
+        list.append(22);
+
+        let drawing = list.render();
+
+        assert_eq!(
+            drawing,
+            "+---+    +----+\n\
+             | 1 | -> | 22 | -> None\n\
+             +---+    +----+"
+        );
+    }
+
+    #[test]
+    fn test_persistent_list_shows_debug_labels() {
+        let list = List::new().push_front("b").push_front("a");
+
+        assert_eq!(list.render().lines().nth(1), Some("| \"a\" | -> | \"b\" | -> None"));
+    }
+
+    #[test]
+    fn test_tree_branches_and_missing_children() {
+        let mut tree = AvlTreeMap::new();
+        for key in [2, 1, 3, 4] {
+            tree.insert(key, 'x');
+        }
+
+        let drawing = tree.render();
+
+        assert_eq!(
+            drawing,
+            "2: 'x'\n\
+             |-- 1: 'x'\n\
+             `-- 3: 'x'\n    \
+                 |-- .\n    \
+                 `-- 4: 'x'"
+        );
+        assert_eq!(AvlTreeMap::<i32, i32>::new().render(), "(empty)");
+    }
+}
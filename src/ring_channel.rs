@@ -0,0 +1,311 @@
+//! Bounded ring buffer channels
+//! A fixed number of slots is used as a circular queue by exactly one
+//! producer and one consumer thread. Each side owns one counter: the producer
+//! only moves `tail` and the consumer only moves `head`, so neither ever
+//! waits for the other and both `try_send` and `try_recv` are wait-free.
+//! The counters only grow, a slot index is the counter modulo the capacity.
+//! The MPSC channel puts the producer behind a mutex so it can be shared by
+//! many senders, while receiving stays wait-free.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{ fence, AtomicUsize, Ordering };
+use std::sync::{ Arc, Mutex };
+
+struct RingBuffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// Number of items ever received, written only by the consumer
+    head: AtomicUsize,
+    /// Number of items ever sent, written only by the producer
+    tail: AtomicUsize,
+}
+
+// SAFETY: A slot is only written by the producer while it is outside
+// head..tail and only read by the consumer while it is inside, and the
+// release/acquire pairs on the counters hand it over between the threads.
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+unsafe impl<T: Send> Send for RingBuffer<T> {}
+
+impl<T> RingBuffer<T> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn len(&self) -> usize {
+        self.tail.load(Ordering::Acquire) - self.head.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        let (head, tail) = (*self.head.get_mut(), *self.tail.get_mut());
+        for count in head..tail {
+            let slot = &mut self.slots[count % self.slots.len()];
+            // SAFETY: Slots in head..tail hold items that were never received
+            unsafe { slot.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// Every slot is taken, the item is handed back
+    Full(T),
+    /// The receiving side is gone, the item is handed back
+    Disconnected(T),
+}
+
+impl<T> TrySendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(item) | TrySendError::Disconnected(item) => item,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    /// Nothing is left and the sending side is gone
+    Disconnected,
+}
+
+/// Creates a channel for one producer and one consumer thread
+pub fn spsc_channel<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "Channel capacity must be positive");
+    let buffer = Arc::new(RingBuffer {
+        slots: (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (Producer { buffer: buffer.clone() }, Consumer { buffer })
+}
+
+/// Creates a channel for many producer threads and one consumer thread
+pub fn mpsc_channel<T>(capacity: usize) -> (Sender<T>, Consumer<T>) {
+    let (producer, consumer) = spsc_channel(capacity);
+    (Sender { producer: Arc::new(Mutex::new(producer)) }, consumer)
+}
+
+/// Sending half of a SPSC channel, can be moved to another thread but not shared
+pub struct Producer<T> {
+    buffer: Arc<RingBuffer<T>>,
+}
+
+impl<T> Producer<T> {
+    pub fn try_send(&mut self, item: T) -> Result<(), TrySendError<T>> {
+        if Arc::strong_count(&self.buffer) == 1 {
+            return Err(TrySendError::Disconnected(item));
+        }
+        let tail = self.buffer.tail.load(Ordering::Relaxed);
+        if tail - self.buffer.head.load(Ordering::Acquire) == self.buffer.capacity() {
+            return Err(TrySendError::Full(item));
+        }
+        let slot = &self.buffer.slots[tail % self.buffer.capacity()];
+        // SAFETY: The slot is outside head..tail, so the consumer doesn't touch it,
+        // and `&mut self` makes this the only producer
+        unsafe { (*slot.get()).write(item) };
+        self.buffer.tail.store(tail + 1, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Items waiting to be received, may be outdated as soon as it returns
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Receiving half of a channel, can be moved to another thread but not shared
+pub struct Consumer<T> {
+    buffer: Arc<RingBuffer<T>>,
+}
+
+impl<T> Consumer<T> {
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let head = self.buffer.head.load(Ordering::Relaxed);
+        if head == self.buffer.tail.load(Ordering::Acquire) {
+            if Arc::strong_count(&self.buffer) > 1 {
+                return Err(TryRecvError::Empty);
+            }
+            // The producer is gone, but it may have sent an item right before.
+            // The fence pairs with the release in its drop, so the last tail is seen.
+            fence(Ordering::Acquire);
+            if head == self.buffer.tail.load(Ordering::Acquire) {
+                return Err(TryRecvError::Disconnected);
+            }
+        }
+        let slot = &self.buffer.slots[head % self.buffer.capacity()];
+        // SAFETY: The slot is inside head..tail, so the producer wrote it and
+        // won't touch it again until `head` moves past it
+        let item = unsafe { (*slot.get()).assume_init_read() };
+        self.buffer.head.store(head + 1, Ordering::Release);
+        Ok(item)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Items waiting to be received, may be outdated as soon as it returns
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Sending half of a MPSC channel, clone it to get more senders
+pub struct Sender<T> {
+    producer: Arc<Mutex<Producer<T>>>,
+}
+
+impl<T> Sender<T> {
+    pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
+        let mut producer = self.producer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        producer.try_send(item)
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender { producer: self.producer.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn when_channel_is_created_it_is_empty() {
+        let (producer, mut consumer) = spsc_channel::<i32>(4);
+
+        assert!(producer.is_empty());
+        assert_eq!(consumer.capacity(), 4);
+        assert_eq!(consumer.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_items_come_out_in_order_and_wrap_around() {
+        let (mut producer, mut consumer) = spsc_channel(3);
+
+        for round in 0..5 {
+            producer.try_send(round * 2).unwrap();
+            producer.try_send(round * 2 + 1).unwrap();
+            assert_eq!(consumer.try_recv(), Ok(round * 2));
+            assert_eq!(consumer.try_recv(), Ok(round * 2 + 1));
+        }
+
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_full_channel_hands_item_back() {
+        let (mut producer, mut consumer) = spsc_channel(2);
+
+        producer.try_send("a").unwrap();
+        producer.try_send("b").unwrap();
+
+        assert_eq!(producer.try_send("c"), Err(TrySendError::Full("c")));
+        assert_eq!(consumer.try_recv(), Ok("a"));
+        assert_eq!(producer.try_send("c"), Ok(()));
+        assert_eq!(producer.len(), 2);
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let (mut producer, consumer) = spsc_channel(2);
+        drop(consumer);
+        assert_eq!(producer.try_send(1).map_err(TrySendError::into_inner), Err(1));
+
+        let (mut producer, mut consumer) = spsc_channel(2);
+        producer.try_send(1).unwrap();
+        drop(producer);
+        assert_eq!(consumer.try_recv(), Ok(1));
+        assert_eq!(consumer.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_unreceived_items_are_dropped() {
+        let item = Arc::new(());
+        let (mut producer, consumer) = spsc_channel(4);
+
+        producer.try_send(item.clone()).unwrap();
+        producer.try_send(item.clone()).unwrap();
+        assert_eq!(Arc::strong_count(&item), 3);
+        drop(producer);
+        drop(consumer);
+
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_spsc_across_threads() {
+        let (mut producer, mut consumer) = spsc_channel(16);
+
+        let sender = thread::spawn(move || {
+            for item in 0..10_000u32 {
+                let mut item = item;
+                while let Err(TrySendError::Full(back)) = producer.try_send(item) {
+                    item = back;
+                    thread::yield_now();
+                }
+            }
+        });
+        let mut received = vec![];
+        loop {
+            match consumer.try_recv() {
+                Ok(item) => received.push(item),
+                Err(TryRecvError::Empty) => thread::yield_now(),
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        sender.join().unwrap();
+
+        assert_eq!(received, (0..10_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_mpsc_keeps_order_per_sender() {
+        let (sender, mut consumer) = mpsc_channel(8);
+
+        let senders: Vec<_> = (0..4u32)
+            .map(|thread| {
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        while sender.try_send((thread, i)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+        let mut next = [0; 4];
+        loop {
+            match consumer.try_recv() {
+                Ok((thread, i)) => {
+                    assert_eq!(next[thread as usize], i);
+                    next[thread as usize] += 1;
+                }
+                Err(TryRecvError::Empty) => thread::yield_now(),
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        senders.into_iter().for_each(|handle| handle.join().unwrap());
+
+        assert_eq!(next, [1000; 4]);
+    }
+}
@@ -0,0 +1,1007 @@
+//! Serde support, behind the `serde` feature
+//! Collections are written the way their contents look from outside, not
+//! the way they are laid out in memory. Lists, stacks, queues, vectors and
+//! sets become sequences in iteration order, maps become maps. Reading one
+//! back builds the collection through its normal insert methods, so hash
+//! maps are re-hashed, sorted collections re-sorted and sets deduplicated.
+//! Stacks are written from the bottom, queues from the front. Multisets are
+//! sequences of (item, count) pairs, range maps of (range, value) pairs.
+//! Heaps are sequences of their items in no particular order, and are heaped
+//! again when read. Structures with settings, like the order of a binary
+//! heap, the limits of a quadtree or the capacity of a cache, are tuples of
+//! the settings followed by the contents. Graphs are (kind, nodes, edges)
+//! with the nodes in index order and every edge as (from, to, data), where
+//! `from` and `to` are positions in the node list. Removed nodes aren't
+//! written, so the nodes after them get lower indices when read back.
+
+use std::fmt::{ self, Debug };
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use serde::de::{ Error, MapAccess, Visitor };
+use serde::{ Deserialize, Deserializer, Serialize, Serializer };
+
+use crate::arc_cache::ArcCache;
+use crate::avl_tree::AvlTreeMap;
+use crate::bimap::BiMap;
+use crate::binary_heap::{ BinaryHeap, HeapOrder };
+use crate::binomial_heap::BinomialHeap;
+use crate::graph::{ Graph, GraphKind, GraphOps, MatrixGraph, NodeIndex };
+use crate::hash_map::HashMap;
+use crate::indexed_priority_queue::IndexedPriorityQueue;
+use crate::interval_heap::IntervalHeap;
+use crate::interval_set::IntervalSet;
+use crate::kd_tree::{ KdTree, Point };
+use crate::leftist_heap::LeftistHeap;
+use crate::lfu_cache::LfuCache;
+use crate::linked_hash_set::LinkedHashSet;
+use crate::multiset::MultiSet;
+use crate::ordered_set::OrderedSet;
+use crate::pairing_heap::PairingHeap;
+use crate::persistent::{ HamtMap, List, PersistentVector };
+use crate::quadtree::{ Quadtree, Rect };
+use crate::queue::Queue;
+use crate::r_tree::RTree;
+use crate::radix_trie::RadixTrie;
+use crate::range_map::RangeMap;
+use crate::singly_linked_list::SinglyLinkedList;
+use crate::skew_heap::SkewHeap;
+use crate::small_vec::SmallVec;
+use crate::sorted_vec::SortedVec;
+use crate::stack::Stack;
+
+/// Key-value pairs of a serialized map, in the order they were read
+struct MapEntries<K, V>(Vec<(K, V)>);
+
+struct MapEntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for MapEntriesVisitor<K, V> {
+    type Value = MapEntries<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some(entry) = access.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(MapEntries(entries))
+    }
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for MapEntries<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapEntriesVisitor(PhantomData))
+    }
+}
+
+impl<T: Serialize + PartialEq + Debug + Clone + fmt::Display> Serialize for SinglyLinkedList<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T> Deserialize<'de> for SinglyLinkedList<T>
+where
+    T: Deserialize<'de> + PartialEq + Debug + Clone + fmt::Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<T: Serialize> Serialize for Stack<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Stack<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut stack = Stack::new();
+        for item in Vec::<T>::deserialize(deserializer)? {
+            stack.push(item);
+        }
+        Ok(stack)
+    }
+}
+
+impl<T: Serialize + Copy> Serialize for Queue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Copy> Deserialize<'de> for Queue<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut queue = Queue::new();
+        for item in Vec::<T>::deserialize(deserializer)? {
+            queue.add(item);
+        }
+        Ok(queue)
+    }
+}
+
+impl<T: Serialize> Serialize for List<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for List<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<T: Serialize + Clone> Serialize for PersistentVector<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for PersistentVector<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<T: Serialize, const N: usize> Serialize for SmallVec<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for SmallVec<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+/// The deduping mode isn't written, a read vector keeps duplicates
+impl<T: Serialize + Ord> Serialize for SortedVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for SortedVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(SortedVec::from_vec(Vec::deserialize(deserializer)?, false))
+    }
+}
+
+impl<T: Serialize + Ord> Serialize for OrderedSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for OrderedSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<T: Serialize + Hash + Eq + Clone> Serialize for LinkedHashSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Hash + Eq + Clone> Deserialize<'de> for LinkedHashSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<T: Serialize + Hash + Clone + PartialEq + Debug> Serialize for MultiSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Hash + Clone + PartialEq + Debug> Deserialize<'de> for MultiSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut set = MultiSet::new();
+        for (item, copies) in Vec::<(T, usize)>::deserialize(deserializer)? {
+            set.insert_many(item, copies);
+        }
+        Ok(set)
+    }
+}
+
+impl<T: Serialize + Ord + Copy> Serialize for IntervalSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord + Copy> Deserialize<'de> for IntervalSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<Range<T>>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<K, V> Serialize for HashMap<K, V>
+where
+    K: Serialize + Hash + Clone + PartialEq + Debug,
+    V: Serialize + Clone + Debug,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for HashMap<K, V>
+where
+    K: Deserialize<'de> + Hash + Clone + PartialEq + Debug,
+    V: Deserialize<'de> + Clone + Debug,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = HashMap::new();
+        for (key, value) in MapEntries::deserialize(deserializer)?.0 {
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+impl<K: Serialize + Ord, V: Serialize> Serialize for AvlTreeMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, K: Deserialize<'de> + Ord, V: Deserialize<'de>> Deserialize<'de> for AvlTreeMap<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = AvlTreeMap::new();
+        for (key, value) in MapEntries::deserialize(deserializer)?.0 {
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+impl<K, V> Serialize for HamtMap<K, V>
+where
+    K: Serialize + Hash + Eq + Clone,
+    V: Serialize + Clone,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for HamtMap<K, V>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone,
+    V: Deserialize<'de> + Clone,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = MapEntries::deserialize(deserializer)?.0;
+        Ok(entries.into_iter().fold(HamtMap::new(), |map, (key, value)| map.insert(key, value)))
+    }
+}
+
+/// Written as a map from left to right values
+impl<L, R> Serialize for BiMap<L, R>
+where
+    L: Serialize + Hash + Clone + PartialEq + Debug,
+    R: Serialize + Hash + Clone + PartialEq + Debug,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, L, R> Deserialize<'de> for BiMap<L, R>
+where
+    L: Deserialize<'de> + Hash + Clone + PartialEq + Debug,
+    R: Deserialize<'de> + Hash + Clone + PartialEq + Debug,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = BiMap::new();
+        for (left, right) in MapEntries::deserialize(deserializer)?.0 {
+            map.insert(left, right);
+        }
+        Ok(map)
+    }
+}
+
+impl<V: Serialize> Serialize for RadixTrie<V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let keys = self.keys();
+        serializer.collect_map(keys.iter().map(|key| (key, self.get(key).unwrap())))
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for RadixTrie<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut trie = RadixTrie::new();
+        for (key, value) in MapEntries::<String, V>::deserialize(deserializer)?.0 {
+            trie.insert(&key, value);
+        }
+        Ok(trie)
+    }
+}
+
+impl<K: Serialize + Ord + Copy, V: Serialize + Clone> Serialize for RangeMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for RangeMap<K, V>
+where
+    K: Deserialize<'de> + Ord + Copy,
+    V: Deserialize<'de> + Clone,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = RangeMap::new();
+        for (range, value) in Vec::<(Range<K>, V)>::deserialize(deserializer)? {
+            map.insert(range, value);
+        }
+        Ok(map)
+    }
+}
+
+impl Serialize for HeapOrder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            HeapOrder::Min => serializer.serialize_unit_variant("HeapOrder", 0, "Min"),
+            HeapOrder::Max => serializer.serialize_unit_variant("HeapOrder", 1, "Max"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HeapOrder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "Min" => Ok(HeapOrder::Min),
+            "Max" => Ok(HeapOrder::Max),
+            other => Err(D::Error::unknown_variant(other, &["Min", "Max"])),
+        }
+    }
+}
+
+impl<T: Serialize + Ord> Serialize for BinaryHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.order(), self.iter().collect::<Vec<_>>()).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for BinaryHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (order, items) = <(HeapOrder, Vec<T>)>::deserialize(deserializer)?;
+        Ok(BinaryHeap::from_vec(items, order))
+    }
+}
+
+impl<T: Serialize + Ord> Serialize for BinomialHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for BinomialHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut heap = BinomialHeap::new();
+        for item in Vec::<T>::deserialize(deserializer)? {
+            heap.push(item);
+        }
+        Ok(heap)
+    }
+}
+
+impl<T: Serialize + Ord> Serialize for LeftistHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for LeftistHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<T: Serialize + Ord> Serialize for SkewHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for SkewHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+/// Handles aren't written, the items get new ones when read
+impl<T: Serialize + Ord> Serialize for PairingHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for PairingHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut heap = PairingHeap::new();
+        for item in Vec::<T>::deserialize(deserializer)? {
+            heap.push(item);
+        }
+        Ok(heap)
+    }
+}
+
+impl<T: Serialize + Ord> Serialize for IntervalHeap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Ord> Deserialize<'de> for IntervalHeap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut heap = IntervalHeap::new();
+        for item in Vec::<T>::deserialize(deserializer)? {
+            heap.push(item);
+        }
+        Ok(heap)
+    }
+}
+
+/// Written as a map from keys to their priorities
+impl<K, P> Serialize for IndexedPriorityQueue<K, P>
+where
+    K: Serialize + Hash + Eq + Clone,
+    P: Serialize + Ord,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, K, P> Deserialize<'de> for IndexedPriorityQueue<K, P>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone,
+    P: Deserialize<'de> + Ord,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut queue = IndexedPriorityQueue::new();
+        for (key, priority) in MapEntries::deserialize(deserializer)?.0 {
+            queue.push(key, priority);
+        }
+        Ok(queue)
+    }
+}
+
+impl Serialize for GraphKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            GraphKind::Directed => serializer.serialize_unit_variant("GraphKind", 0, "Directed"),
+            GraphKind::Undirected => {
+                serializer.serialize_unit_variant("GraphKind", 1, "Undirected")
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GraphKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "Directed" => Ok(GraphKind::Directed),
+            "Undirected" => Ok(GraphKind::Undirected),
+            other => Err(D::Error::unknown_variant(other, &["Directed", "Undirected"])),
+        }
+    }
+}
+
+/// Nodes in index order and the edges between them by position in that
+/// order, an undirected edge only once
+type GraphParts<N, E> = (GraphKind, Vec<N>, Vec<(usize, usize, E)>);
+
+fn graph_parts<'a, G: GraphOps, N>(
+    graph: &'a G,
+    kind: GraphKind,
+    data: impl Fn(NodeIndex) -> &'a N
+) -> GraphParts<&'a N, &'a G::EdgeData> {
+    let mut positions = vec![0; graph.node_bound()];
+    let nodes = graph
+        .node_indices()
+        .enumerate()
+        .map(|(position, node)| {
+            positions[node.index()] = position;
+            data(node)
+        })
+        .collect();
+    let edges = graph
+        .node_indices()
+        .flat_map(|from| graph.edges(from).map(move |(to, data)| (from, to, data)))
+        .filter(|(from, to, _)| graph.is_directed() || from <= to)
+        .map(|(from, to, data)| (positions[from.index()], positions[to.index()], data))
+        .collect();
+    (kind, nodes, edges)
+}
+
+/// Checks the edges only join nodes that were read
+fn check_edges<E, D: Error>(nodes: &[NodeIndex], edges: &[(usize, usize, E)]) -> Result<(), D> {
+    match edges.iter().find(|(from, to, _)| *from.max(to) >= nodes.len()) {
+        Some((from, to, _)) => Err(D::custom(format!(
+            "edge {} -> {} joins a node past the {} nodes",
+            from,
+            to,
+            nodes.len()
+        ))),
+        None => Ok(()),
+    }
+}
+
+impl<N: Serialize, E: Serialize + Clone> Serialize for Graph<N, E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        graph_parts(self, self.kind(), |node| self.node(node).unwrap()).serialize(serializer)
+    }
+}
+
+impl<'de, N: Deserialize<'de>, E: Deserialize<'de> + Clone> Deserialize<'de> for Graph<N, E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (kind, nodes, edges) = GraphParts::<N, E>::deserialize(deserializer)?;
+        let mut graph = Graph::new(kind);
+        let nodes: Vec<NodeIndex> = nodes.into_iter().map(|data| graph.add_node(data)).collect();
+        check_edges(&nodes, &edges)?;
+        for (from, to, data) in edges {
+            graph.add_edge(nodes[from], nodes[to], data);
+        }
+        Ok(graph)
+    }
+}
+
+impl<N: Serialize, E: Serialize + Clone> Serialize for MatrixGraph<N, E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        graph_parts(self, self.kind(), |node| self.node(node).unwrap()).serialize(serializer)
+    }
+}
+
+impl<'de, N, E> Deserialize<'de> for MatrixGraph<N, E>
+where
+    N: Deserialize<'de>,
+    E: Deserialize<'de> + Clone,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (kind, nodes, edges) = GraphParts::<N, E>::deserialize(deserializer)?;
+        let mut graph = MatrixGraph::new(kind);
+        let nodes: Vec<NodeIndex> = nodes.into_iter().map(|data| graph.add_node(data)).collect();
+        check_edges(&nodes, &edges)?;
+        for (from, to, data) in edges {
+            graph.add_edge(nodes[from], nodes[to], data);
+        }
+        Ok(graph)
+    }
+}
+
+/// The tree is balanced again when read, so the points may come back in
+/// another order
+impl<const K: usize> Serialize for KdTree<K>
+where
+    Point<K>: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, const K: usize> Deserialize<'de> for KdTree<K>
+where
+    Point<K>: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(KdTree::new(Vec::deserialize(deserializer)?))
+    }
+}
+
+/// Written as its (min, max) corners
+impl Serialize for Rect {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.min, self.max).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rect {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (min, max) = Deserialize::deserialize(deserializer)?;
+        Ok(Rect::new(min, max))
+    }
+}
+
+/// Written as (bounds, capacity, max depth, points)
+impl Serialize for Quadtree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let points = self.query(&self.bounds());
+        (self.bounds(), self.capacity(), self.max_depth(), points).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Quadtree {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (bounds, capacity, max_depth, points) =
+            <(Rect, usize, usize, Vec<Point<2>>)>::deserialize(deserializer)?;
+        if capacity == 0 {
+            return Err(D::Error::custom("node capacity has to be at least 1"));
+        }
+        let mut tree = Quadtree::with_limits(bounds, capacity, max_depth);
+        for point in points {
+            if !tree.insert(point) {
+                return Err(D::Error::custom(format!("point {:?} is outside the bounds", point)));
+            }
+        }
+        Ok(tree)
+    }
+}
+
+/// Written as (max entries, entries) with every entry as (rect, data)
+impl<T: Serialize> Serialize for RTree<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries = self.bounds().map_or(vec![], |bounds| self.search(&bounds));
+        (self.max_entries(), entries).serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RTree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (max_entries, entries) = <(usize, Vec<(Rect, T)>)>::deserialize(deserializer)?;
+        if max_entries < 2 {
+            return Err(D::Error::custom("max entries has to be at least 2"));
+        }
+        let mut tree = RTree::with_max_entries(max_entries);
+        for (rect, data) in entries {
+            tree.insert(rect, data);
+        }
+        Ok(tree)
+    }
+}
+
+/// Written as (capacity, entries) with every entry as (key, value, frequency),
+/// in eviction order. Reading puts the entries back and uses each one until
+/// it has its frequency again, so the cache evicts in the same order.
+impl<K, V> Serialize for LfuCache<K, V>
+where
+    K: Serialize + Hash + Eq + Clone,
+    V: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(&K, &V, usize)> = self
+            .iter()
+            .map(|(key, value)| (key, value, self.frequency(key).unwrap()))
+            .collect();
+        (self.capacity(), entries).serialize(serializer)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for LfuCache<K, V>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (capacity, entries) = <(usize, Vec<(K, V, usize)>)>::deserialize(deserializer)?;
+        if entries.len() > capacity {
+            return Err(D::Error::custom("more entries than the capacity"));
+        }
+        let mut cache = LfuCache::new(capacity);
+        for (key, value, frequency) in entries {
+            cache.put(key.clone(), value);
+            for _ in 1..frequency {
+                cache.get(&key);
+            }
+        }
+        Ok(cache)
+    }
+}
+
+/// Written as (capacity, recent, frequent) with the entries used once and
+/// more than once as (key, value) pairs from the oldest. The ghost lists,
+/// the adapted target and the stats aren't written and start over.
+impl<K, V> Serialize for ArcCache<K, V>
+where
+    K: Serialize + Hash + Eq + Clone,
+    V: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let recent: Vec<(&K, &V)> = self.recent_entries().collect();
+        let frequent: Vec<(&K, &V)> = self.frequent_entries().collect();
+        (self.capacity(), recent, frequent).serialize(serializer)
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for ArcCache<K, V>
+where
+    K: Deserialize<'de> + Hash + Eq + Clone,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (capacity, recent, frequent) =
+            <(usize, Vec<(K, V)>, Vec<(K, V)>)>::deserialize(deserializer)?;
+        if recent.len() + frequent.len() > capacity {
+            return Err(D::Error::custom("more entries than the capacity"));
+        }
+        let mut cache = ArcCache::new(capacity);
+        // A second use moves an entry to the frequent list
+        for (key, value) in frequent {
+            cache.put(key.clone(), value);
+            cache.get(&key);
+        }
+        for (key, value) in recent {
+            cache.put(key, value);
+        }
+        cache.reset_stats();
+        Ok(cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_lists_stack_and_queue_keep_their_order() {
+        let mut list = SinglyLinkedList::new();
+        let mut stack = Stack::new();
+        let mut queue = Queue::new();
+        for item in [3, 1, 2] {
+            list.append(item);
+            stack.push(item);
+            queue.add(item);
+        }
+
+        assert_eq!(serde_json::to_string(&list).unwrap(), "[3,1,2]");
+        assert_eq!(serde_json::to_string(&stack).unwrap(), "[3,1,2]");
+        assert_eq!(round_trip(&list), list);
+        let mut stack = round_trip(&stack);
+        assert_eq!(stack.pop(), Some(2));
+        let mut queue = round_trip(&queue);
+        assert_eq!(queue.remove(), Some(3));
+        let persistent: List<i32> = [3, 1, 2].into_iter().collect();
+        assert_eq!(round_trip(&persistent), persistent);
+    }
+
+    #[test]
+    fn test_hash_map_is_rehashed_on_deserialize() {
+        let mut map = HashMap::new();
+        for key in 0..100 {
+            map.insert(key, key * key);
+        }
+
+        let json = serde_json::to_string(&map).unwrap();
+        let read: HashMap<i32, i32> = serde_json::from_str(&json).unwrap();
+
+        assert!(json.starts_with('{'));
+        assert_eq!(read.len(), 100);
+        for key in 0..100 {
+            assert_eq!(read.get(key), Some(key * key));
+        }
+    }
+
+    #[test]
+    fn test_duplicate_map_keys_keep_the_last_value() {
+        let json = r#"{"a": 1, "b": 2, "a": 3}"#;
+
+        let map: HashMap<String, i32> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a".to_string()), Some(3));
+    }
+
+    #[test]
+    fn test_sorted_collections_are_resorted() {
+        let sorted: SortedVec<i32> = serde_json::from_str("[5, 1, 4, 1]").unwrap();
+        let set: OrderedSet<i32> = serde_json::from_str("[5, 1, 4, 1]").unwrap();
+        let tree: AvlTreeMap<u8, char> = serde_json::from_str(r#"{"3": "c", "1": "a"}"#).unwrap();
+
+        assert_eq!(sorted.iter().copied().collect::<Vec<_>>(), vec![1, 1, 4, 5]);
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![1, 4, 5]);
+        assert_eq!(serde_json::to_string(&tree).unwrap(), r#"{"1":"a","3":"c"}"#);
+    }
+
+    #[test]
+    fn test_sets_round_trip() {
+        let linked: LinkedHashSet<&str> = ["b", "a", "c"].into_iter().collect();
+        let multiset: MultiSet<char> = "banana".chars().collect();
+        let intervals: IntervalSet<u32> = [1..3, 2..5, 8..9].into_iter().collect();
+
+        let linked: LinkedHashSet<String> = serde_json::from_str(
+            &serde_json::to_string(&linked).unwrap()
+        ).unwrap();
+        let multiset = round_trip(&multiset);
+        let intervals = round_trip(&intervals);
+
+        assert_eq!(linked.iter().cloned().collect::<Vec<_>>(), vec!["b", "a", "c"]);
+        assert_eq!((multiset.count(&'a'), multiset.count(&'n'), multiset.len()), (3, 2, 6));
+        assert_eq!(intervals.iter().collect::<Vec<_>>(), vec![1..5, 8..9]);
+    }
+
+    #[test]
+    fn test_other_maps_round_trip() {
+        let hamt = HamtMap::new().insert("x".to_string(), 1).insert("y".to_string(), 2);
+        let mut bimap = BiMap::new();
+        bimap.insert(1, 'a');
+        bimap.insert(2, 'b');
+        let mut trie = RadixTrie::new();
+        trie.insert("romane", 1);
+        trie.insert("romanus", 2);
+        let mut ranges = RangeMap::new();
+        ranges.insert(0..10, "low".to_string());
+        ranges.insert(5..20, "high".to_string());
+
+        let hamt = round_trip(&hamt);
+        let bimap = round_trip(&bimap);
+        let trie = round_trip(&trie);
+        let ranges = round_trip(&ranges);
+
+        assert_eq!((hamt.get(&"x".to_string()), hamt.len()), (Some(&1), 2));
+        assert_eq!(bimap.get_by_right(&'b'), Some(2));
+        assert_eq!(trie.get("romanus"), Some(&2));
+        assert_eq!(ranges.iter().map(|(range, _)| range).collect::<Vec<_>>(), vec![0..5, 5..20]);
+    }
+
+    #[test]
+    fn test_vectors_round_trip() {
+        let small: SmallVec<i32, 2> = (0..5).collect();
+        let persistent: PersistentVector<i32> = (0..100).collect();
+
+        assert_eq!(round_trip(&small), small);
+        assert_eq!(round_trip(&persistent), persistent);
+    }
+
+    #[test]
+    fn test_heaps_round_trip() {
+        let items = [5, 1, 4, 1, 3];
+        let binary = BinaryHeap::from_vec(items.to_vec(), HeapOrder::Max);
+        let mut binomial = BinomialHeap::new();
+        let leftist: LeftistHeap<i32> = items.into_iter().collect();
+        let skew: SkewHeap<i32> = items.into_iter().collect();
+        let mut interval = IntervalHeap::new();
+        let mut indexed = IndexedPriorityQueue::new();
+        for (key, priority) in ["e", "a", "d", "b", "c"].into_iter().zip(items) {
+            binomial.push(priority);
+            interval.push(priority);
+            indexed.push(key.to_string(), priority);
+        }
+
+        assert!(serde_json::to_string(&binary).unwrap().starts_with(r#"["Max","#));
+        let mut binary = round_trip(&binary);
+        let binomial = round_trip(&binomial);
+        let mut leftist = round_trip(&leftist);
+        let mut skew = round_trip(&skew);
+        let mut interval = round_trip(&interval);
+        let mut indexed = round_trip(&indexed);
+        assert_eq!((binary.pop(), binary.len()), (Some(5), 4));
+        assert_eq!((binomial.peek(), binomial.len()), (Some(&1), 5));
+        assert_eq!((leftist.pop(), skew.pop()), (Some(1), Some(1)));
+        assert_eq!((interval.pop_min(), interval.pop_max()), (Some(1), Some(5)));
+        assert_eq!(indexed.pop(), Some(("a".to_string(), 1)));
+        assert_eq!(indexed.len(), 4);
+    }
+
+    #[test]
+    fn test_pairing_heap_round_trip() {
+        let mut heap = PairingHeap::new();
+        for item in [7, 2, 9, 4] {
+            heap.push(item);
+        }
+        heap.pop();
+
+        let mut heap = round_trip(&heap);
+
+        assert_eq!(heap.len(), 3);
+        assert_eq!((heap.pop(), heap.pop(), heap.pop()), (Some(4), Some(7), Some(9)));
+    }
+
+    #[test]
+    fn test_graphs_renumber_nodes_after_removed_ones() {
+        let mut graph = Graph::new(GraphKind::Undirected);
+        let a = graph.add_node('a');
+        let removed = graph.add_node('x');
+        let b = graph.add_node('b');
+        let c = graph.add_node('c');
+        graph.add_edge(a, b, 1);
+        graph.add_edge(b, c, 2);
+        graph.add_edge(a, removed, 3);
+        graph.remove_node(removed);
+        let mut matrix = MatrixGraph::new(GraphKind::Directed);
+        let from = matrix.add_node("from".to_string());
+        let to = matrix.add_node("to".to_string());
+        matrix.add_edge(from, to, 1.5);
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let graph: Graph<char, i32> = serde_json::from_str(&json).unwrap();
+        let matrix = round_trip(&matrix);
+
+        assert_eq!(json, r#"["Undirected",["a","b","c"],[[0,1,1],[1,2,2]]]"#);
+        assert_eq!((graph.node_count(), graph.edge_count()), (3, 2));
+        assert_eq!(graph.node(NodeIndex::new(1)), Some(&'b'));
+        assert!(graph.contains_edge(NodeIndex::new(2), NodeIndex::new(1)));
+        assert_eq!(matrix.kind(), GraphKind::Directed);
+        assert!(matrix.contains_edge(from, to));
+        assert!(!matrix.contains_edge(to, from));
+    }
+
+    #[test]
+    fn test_edges_to_missing_nodes_are_rejected() {
+        let json = r#"["Directed",["a"],[[0,1,null]]]"#;
+
+        let read = serde_json::from_str::<Graph<String, ()>>(json);
+
+        assert!(read.unwrap_err().to_string().contains("past the 1 nodes"));
+    }
+
+    #[test]
+    fn test_spatial_trees_round_trip() {
+        let points = vec![[1.0, 2.0], [5.0, 5.0], [8.0, 1.0]];
+        let kd = KdTree::new(points.clone());
+        let bounds = Rect::new([0.0, 0.0], [10.0, 10.0]);
+        let mut quad = Quadtree::with_limits(bounds, 1, 4);
+        let mut r_tree = RTree::with_max_entries(3);
+        for (id, point) in points.into_iter().enumerate() {
+            quad.insert(point);
+            r_tree.insert(Rect::new(point, point), id);
+        }
+
+        let kd = round_trip(&kd);
+        let quad = round_trip(&quad);
+        let r_tree = round_trip(&r_tree);
+
+        assert_eq!(kd.nearest(&[7.0, 2.0]).map(|(point, _)| *point), Some([8.0, 1.0]));
+        assert_eq!((quad.len(), quad.capacity(), quad.max_depth()), (3, 1, 4));
+        assert_eq!(quad.query(&Rect::new([4.0, 4.0], [9.0, 9.0])), vec![[5.0, 5.0]]);
+        assert_eq!((r_tree.len(), r_tree.max_entries()), (3, 3));
+        let found = r_tree.search(&Rect::new([0.0, 0.0], [2.0, 2.0]));
+        assert_eq!(found.into_iter().map(|(_, id)| *id).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn test_points_outside_the_bounds_are_rejected() {
+        let json = r#"[[[0.0,0.0],[1.0,1.0]],4,8,[[2.0,2.0]]]"#;
+
+        assert!(serde_json::from_str::<Quadtree>(json).is_err());
+    }
+
+    #[test]
+    fn test_caches_keep_their_eviction_order() {
+        let mut lfu = LfuCache::new(3);
+        let mut arc = ArcCache::new(3);
+        for key in ["a", "b", "c"] {
+            lfu.put(key.to_string(), key.len());
+            arc.put(key.to_string(), key.len());
+        }
+        lfu.get(&"a".to_string());
+        lfu.get(&"a".to_string());
+        lfu.get(&"c".to_string());
+        arc.get(&"b".to_string());
+
+        let mut lfu = round_trip(&lfu);
+        let mut arc = round_trip(&arc);
+
+        assert_eq!(lfu.frequency(&"a".to_string()), Some(3));
+        lfu.put("d".to_string(), 1);
+        assert!(lfu.get(&"b".to_string()).is_none());
+        lfu.put("e".to_string(), 1);
+        assert!(lfu.get(&"d".to_string()).is_none());
+        assert!(lfu.get(&"c".to_string()).is_some());
+        assert_eq!(arc.stats().hits, 0);
+        let recent: Vec<&str> = arc.recent_entries().map(|(key, _)| key.as_str()).collect();
+        let frequent: Vec<&str> = arc.frequent_entries().map(|(key, _)| key.as_str()).collect();
+        assert_eq!((recent, frequent), (vec!["a", "c"], vec!["b"]));
+        arc.put("d".to_string(), 1);
+        assert!(arc.get(&"a".to_string()).is_none());
+        assert!(arc.get(&"b".to_string()).is_some());
+    }
+}
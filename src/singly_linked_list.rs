@@ -1,226 +1,661 @@
 // Implement Singly Linked List that accepts Generic data from scratch
 
 use std::fmt::{ self, Debug };
-
-#[derive(Debug, PartialEq)]
-pub struct Node<T> where T: Clone {
-    pub data: T,
-    pub next: Option<Box<Node<T>>>,
-}
-impl<T: Clone> Clone for Node<T> {
-    fn clone(&self) -> Self {
-        Node {
-            data: self.data.clone(),
-            next: self.next.clone(),
-        }
-    }
+use std::marker::PhantomData;
+
+use crate::collection::Collection;
+use crate::cursor::{ Cursor, CursorMut };
+use crate::error::{ Error, Result };
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::node_alloc::{ Global, NodeAlloc, NodeHandle };
+use crate::trace::{ self, Event };
+
+/// List node, only public so an allocator for it can be named
+pub struct ListNode<T, H> {
+    data: T,
+    next: Option<H>,
 }
 
-#[allow(dead_code)]
-impl<T: Clone> Node<T> {
-    fn new(data: T) -> Self {
-        Node {
-            data,
-            next: None,
-        }
-    }
+/// Node of a list keeping its nodes in `A`
+type Node<T, A> = ListNode<T, <A as NodeHandle>::Handle>;
+
+/// A link to a node the list owns. Nodes are kept by a `NodeAlloc`,
+/// `Global` unless `new_in` is given another one, so by default every node
+/// is its own allocation and a link is its address, the way std's
+/// `LinkedList` does it. `tail` can then point at the last node without
+/// anything claiming to be the only way there.
+type Link<A> = Option<<A as NodeHandle>::Handle>;
+
+pub struct SinglyLinkedList<T: Clone, A = Global>
+where
+    A: NodeAlloc<Node<T, A>>,
+{
+    nodes: A,
+    first: Link<A>,
+    /// The last node, None only when the list is empty
+    tail: Link<A>,
+    len: usize,
+    /// The list owns the nodes and their data, for the drop check
+    marker: PhantomData<Box<Node<T, A>>>,
+}
 
-    fn new_with_next(data: T, next: Option<Box<Node<T>>>) -> Self {
-        Node { data, next }
+impl<T: PartialEq<T> + Debug + Clone + std::fmt::Display> SinglyLinkedList<T> {
+    pub fn new() -> Self {
+        SinglyLinkedList::new_in(Global)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct SinglyLinkedList<T: Clone> {
-    pub first: Option<Box<Node<T>>>,
-    pub node_count: i32,
-}
+impl<T, A> SinglyLinkedList<T, A>
+where
+    T: PartialEq<T> + Debug + Clone + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>>,
+{
+    /// Empty list that keeps its nodes in `nodes`
+    pub fn new_in(nodes: A) -> Self {
+        SinglyLinkedList { nodes, first: None, tail: None, len: 0, marker: PhantomData }
+    }
 
-#[allow(dead_code)]
-impl<T: PartialEq<T> + Debug + Clone + std::fmt::Display> SinglyLinkedList<T> {
-    pub fn new() -> Self {
-        SinglyLinkedList { first: None, node_count: 0 }
+    /// The allocator holding the nodes
+    pub fn nodes(&self) -> &A {
+        &self.nodes
     }
 
     pub fn is_empty(&self) -> bool {
         self.first.is_none()
     }
 
-    pub fn clear(&mut self) {
-        self.first = None;
-        self.node_count = 0;
+    /// Number of nodes in O(1), kept up to date by every method that links
+    /// or unlinks a node
+    pub fn len(&self) -> usize {
+        self.len
     }
 
-    pub fn find_last_node(&mut self) -> Option<&mut Box<Node<T>>> {
-        let mut current = &mut self.first;
-
-        while let Some(node) = current {
-            if node.next.is_none() {
-                return Some(node);
-            }
+    pub fn contains(&self, data: &T) -> bool {
+        self.iter().any(|item| item == data)
+    }
 
-            current = &mut node.next;
-        }
-        None
+    /// How many nodes hold `data`
+    pub fn count_occurrences(&self, data: &T) -> usize {
+        self.iter().filter(|&item| item == data).count()
     }
 
-    pub fn find_before_last(&mut self) -> Option<&mut Box<Node<T>>> {
-        let mut current_node = &mut self.first;
+    /// Frees the nodes one at a time, like dropping the list
+    pub fn clear(&mut self) {
+        while self.unlink_after(None).is_some() {}
+        check_invariants!(self);
+    }
 
-        while let Some(node) = current_node {
-            if let Some(next_node) = &mut node.next {
-                if next_node.next.is_none() {
-                    return Some(node);
-                }
-            }
-            current_node = &mut node.next;
-        }
-        None
+    fn find_node_at(&self, index: usize) -> Link<A> {
+        self.handles().nth(index)
     }
 
-    pub fn find_node(&mut self, given_data: &T) -> Option<&mut Box<Node<T>>> {
-        let mut current_node = &mut self.first;
+    /// The last node, found through `tail` without walking the list
+    fn find_last_node(&self) -> Link<A> {
+        self.tail
+    }
 
-        while let Some(node) = current_node {
-            if &node.data == given_data {
-                return Some(node); // Return early after inserting the new node
-            }
-            current_node = &mut node.next;
-        }
-        None
+    fn find_before_last(&self) -> Link<A> {
+        self.handles().find(|&node| self.next(Some(node)) == self.tail)
     }
 
-    pub fn find_previous_node(&mut self, given_data: &T) -> Option<&mut Box<Node<T>>> {
-        let mut current_node = &mut self.first;
+    fn find_node(&self, given_data: &T) -> Link<A> {
+        self.handles().find(|&node| self.data(node) == given_data)
+    }
 
-        while let Some(node) = current_node {
-            if let Some(next_node) = &mut node.next {
-                if &next_node.data == given_data {
-                    return Some(node);
-                }
-            }
-            current_node = &mut node.next;
-        }
-        None
+    fn find_previous_node(&self, given_data: &T) -> Link<A> {
+        self.handles().find(|&node| {
+            self.next(Some(node)).is_some_and(|next| self.data(next) == given_data)
+        })
     }
 
+    /// Links the item after the last node in O(1)
     pub fn append(&mut self, data: T) {
-        let new_node = Box::new(Node::new(data));
-        let last_node = self.find_last_node();
-        match last_node {
-            Some(node) => {
-                node.next = Some(new_node);
-            }
-            None => {
-                self.first = Some(new_node);
-            }
-        }
-        self.node_count += 1;
+        trace_linked(&data, self.find_last_node().map(|node| self.data(node)));
+        self.link_last(data);
+        check_invariants!(self);
     }
 
     pub fn prepend(&mut self, data: T) {
-        let new_node = Box::new(Node::new_with_next(data, self.first.take()));
-        self.first = Some(new_node);
-        self.node_count += 1;
+        trace_linked(&data, None);
+        self.link_after(None, data);
+        check_invariants!(self);
     }
 
+    /// Panics where `try_insert_after_given` returns an error
     pub fn insert_after_given(&mut self, data: T, given_data: T) {
-        if self.is_empty() {
-            panic!("List is empty, this action is not possible.");
+        match self.try_insert_after_given(data, given_data.clone()) {
+            Ok(()) => {}
+            Err(Error::EmptyCollection) => panic!("List is empty, this action is not possible."),
+            Err(_) => panic!("Given node '{}' not found in the list!", given_data),
         }
+    }
 
-        let node_with_data = &mut self.find_node(&given_data);
-        match node_with_data {
-            Some(node) => {
-                let new_node = Box::new(Node::new_with_next(data, node.next.take()));
-                node.next = Some(new_node);
-            }
-            None => {
-                panic!("Given node '{}' not found in the list!", given_data);
-            }
+    /// Inserts `data` after the first node holding `given_data`
+    pub fn try_insert_after_given(&mut self, data: T, given_data: T) -> Result<()> {
+        if self.is_empty() {
+            return Err(Error::EmptyCollection);
         }
-        self.node_count += 1;
+
+        let node = self.find_node(&given_data).ok_or(Error::NotFound)?;
+        trace_linked(&data, Some(self.data(node)));
+        self.link_after(Some(node), data);
+        check_invariants!(self);
+        Ok(())
     }
 
+    /// Panics where `try_insert_before_given` returns an error
     pub fn insert_before_given(&mut self, data: T, given_data: T) {
-        if self.is_empty() {
-            panic!("List is empty, this action is not possible.");
+        match self.try_insert_before_given(data, given_data.clone()) {
+            Ok(()) => {}
+            Err(Error::EmptyCollection) => panic!("List is empty, this action is not possible."),
+            Err(_) => panic!("Given node '{}' not found in the list!", given_data),
         }
+    }
 
-        let node_before = self.find_previous_node(&given_data);
-        match node_before {
-            Some(node) => {
-                let new_node = Box::new(Node::new_with_next(data, node.next.take()));
-                node.next = Some(new_node);
-            }
-            None => {
-                panic!("Given node '{}' not found in the list!", given_data);
-            }
+    /// Inserts `data` before the first node holding `given_data`, which can't be the head
+    pub fn try_insert_before_given(&mut self, data: T, given_data: T) -> Result<()> {
+        if self.is_empty() {
+            return Err(Error::EmptyCollection);
         }
-        self.node_count += 1;
+
+        let node = self.find_previous_node(&given_data).ok_or(Error::NotFound)?;
+        trace_linked(&data, Some(self.data(node)));
+        self.link_after(Some(node), data);
+        check_invariants!(self);
+        Ok(())
     }
 
+    /// Panics where `try_delete_first` returns an error
     pub fn delete_first(&mut self) {
-        if self.is_empty() {
+        if self.try_delete_first().is_err() {
             panic!("Cannot delete the first element from an empty list!");
         }
-        let new_first = self.first.take().unwrap().next;
-        self.first = new_first;
-        self.node_count -= 1;
     }
 
+    /// Removes the first node and returns its data
+    pub fn try_delete_first(&mut self) -> Result<T> {
+        let first = self.unlink_after(None).ok_or(Error::EmptyCollection)?;
+        trace_unlinked(&first, None);
+        check_invariants!(self);
+        Ok(first)
+    }
+
+    /// Panics where `try_delete_last` returns an error
     pub fn delete_last(&mut self) {
-        let last_node = self.find_before_last();
-        match last_node {
-            Some(node) => {
-                node.next = None;
-            }
-            None => {
-                panic!("Cannot delete the last element from an empty list!");
-            }
+        if self.try_delete_last().is_err() {
+            panic!("Cannot delete the last element from an empty list!");
         }
-        self.node_count -= 1;
     }
 
+    /// Removes the last node and returns its data
+    pub fn try_delete_last(&mut self) -> Result<T> {
+        // None with zero or one node, the last one is then the first one
+        let before_last = self.find_before_last();
+        let last = self.unlink_after(before_last).ok_or(Error::EmptyCollection)?;
+        trace_unlinked(&last, before_last.map(|node| self.data(node)));
+        check_invariants!(self);
+        Ok(last)
+    }
+
+    /// Panics where `try_delete_node_with_data` returns an error
     pub fn delete_node_with_data(&mut self, data: T) {
-        let data_node = self.find_node(&data);
-        match data_node {
-            Some(node) => {
-                let reference = node.next.take();
-                let previous_node = self.find_previous_node(&data);
-                match previous_node {
-                    Some(previous) => {
-                        previous.next = reference;
-                    }
-                    None => {
-                        self.first = None;
-                    }
-                }
+        if self.try_delete_node_with_data(data).is_err() {
+            panic!("Node with given data not found!");
+        }
+    }
+
+    /// Removes the first node holding `data`
+    pub fn try_delete_node_with_data(&mut self, data: T) -> Result<()> {
+        let first = self.first.ok_or(Error::NotFound)?;
+        if self.data(first) == &data {
+            return self.try_delete_first().map(|_| ());
+        }
+
+        let previous = self.find_previous_node(&data).ok_or(Error::NotFound)?;
+        let removed = self.unlink_after(Some(previous)).unwrap();
+        trace_unlinked(&removed, Some(self.data(previous)));
+        check_invariants!(self);
+        Ok(())
+    }
+
+    /// Inserts `data` so it ends up at `index`, which can be the length to
+    /// append it
+    pub fn insert_at(&mut self, index: usize, data: T) -> Result<()> {
+        let len = self.len();
+        match index {
+            0 => self.prepend(data),
+            _ if index == len => self.append(data),
+            _ if index > len => return Err(Error::IndexOutOfBounds { index, len }),
+            _ => {
+                let out_of_bounds = Error::IndexOutOfBounds { index, len };
+                let node = self.find_node_at(index - 1).ok_or(out_of_bounds)?;
+                trace_linked(&data, Some(self.data(node)));
+                self.link_after(Some(node), data);
+                check_invariants!(self);
             }
+        }
+        Ok(())
+    }
+
+    /// Removes the node at `index` and returns its data, None if there is
+    /// no such node
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        if index == 0 {
+            return self.try_delete_first().ok();
+        }
+
+        let previous = self.find_node_at(index - 1)?;
+        let removed = self.unlink_after(Some(previous))?;
+        trace_unlinked(&removed, Some(self.data(previous)));
+        check_invariants!(self);
+        Some(removed)
+    }
+
+    /// Reverses the list in place by turning every link around, O(n) time
+    /// and no allocation
+    pub fn reverse(&mut self) {
+        // The first node ends up last
+        self.tail = self.first;
+        let mut reversed = None;
+        let mut current = self.first.take();
+
+        while let Some(node) = current {
+            let next = &mut self.node_mut(node).next;
+            current = std::mem::replace(next, reversed);
+            reversed = Some(node);
+        }
+        self.first = reversed;
+        check_invariants!(self);
+    }
+}
+
+impl<T, A> Default for SinglyLinkedList<T, A>
+where
+    T: PartialEq<T> + Debug + Clone + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>> + Default,
+{
+    fn default() -> Self {
+        SinglyLinkedList::new_in(A::default())
+    }
+}
+
+/// Builds a list with the items in iteration order
+impl<T, A> FromIterator<T> for SinglyLinkedList<T, A>
+where
+    T: PartialEq<T> + Debug + Clone + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = SinglyLinkedList::default();
+        list.extend(iter);
+        list
+    }
+}
+
+/// Appends the items in order, each in O(1)
+impl<T, A> Extend<T> for SinglyLinkedList<T, A>
+where
+    T: PartialEq<T> + Debug + Clone + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.append(item);
+        }
+    }
+}
+
+impl<'a, T, A> Extend<&'a T> for SinglyLinkedList<T, A>
+where
+    T: PartialEq<T> + Debug + Copy + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>>,
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, A> Collection for SinglyLinkedList<T, A>
+where
+    T: PartialEq<T> + Debug + Clone + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>>,
+{
+    type Item = T;
+
+    fn len(&self) -> usize {
+        SinglyLinkedList::len(self)
+    }
+
+    fn clear(&mut self) {
+        SinglyLinkedList::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        SinglyLinkedList::contains(self, item)
+    }
+}
+
+fn trace_linked<T: fmt::Display>(item: &T, after: Option<&T>) {
+    trace::emit(|| Event::Linked { item: item.to_string(), after: after.map(T::to_string) });
+}
+
+fn trace_unlinked<T: fmt::Display>(item: &T, previous: Option<&T>) {
+    trace::emit(|| Event::Unlinked {
+        item: item.to_string(),
+        previous: previous.map(T::to_string),
+    });
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> SinglyLinkedList<T, A> {
+    /// The nodes from the first, as the handles that link them
+    fn handles(&self) -> impl Iterator<Item = A::Handle> + '_ {
+        std::iter::successors(self.first, |&node| self.next(Some(node)))
+    }
+
+    /// The node after `previous`, or the first node when it is None
+    fn next(&self, previous: Link<A>) -> Link<A> {
+        match previous {
+            Some(previous) => self.node(previous).next,
+            None => self.first,
+        }
+    }
+
+    /// `node` has to be one of the list's nodes
+    fn data(&self, node: A::Handle) -> &T {
+        &self.node(node).data
+    }
+
+    /// `handle` has to be one of the list's nodes
+    fn node(&self, handle: A::Handle) -> &Node<T, A> {
+        // SAFETY: The list only links nodes it allocated and hasn't freed
+        unsafe { self.nodes.get(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// `handle` has to be one of the list's nodes
+    fn node_mut(&mut self, handle: A::Handle) -> &mut Node<T, A> {
+        // SAFETY: As in `node`
+        unsafe { self.nodes.get_mut(handle) }.expect("Linked nodes are never freed")
+    }
+
+    /// The link that points at the node after `previous`
+    fn link_mut(&mut self, previous: Link<A>) -> &mut Link<A> {
+        match previous {
+            Some(previous) => &mut self.node_mut(previous).next,
+            None => &mut self.first,
+        }
+    }
+
+    /// Links a new node holding `data` after `previous`, or first when it
+    /// is None, and moves the tail if it went at the end
+    fn link_after(&mut self, previous: Link<A>, data: T) {
+        let next = self.next(previous);
+        let node = self.nodes.alloc(ListNode { data, next });
+        *self.link_mut(previous) = Some(node);
+        if next.is_none() {
+            self.tail = Some(node);
+        }
+        self.len += 1;
+    }
+
+    /// Links a new node holding `data` after the last one in O(1)
+    fn link_last(&mut self, data: T) {
+        self.link_after(self.tail, data);
+    }
+
+    /// Unlinks the node after `previous`, or the first node when it is
+    /// None, frees it and returns its data
+    fn unlink_after(&mut self, previous: Link<A>) -> Option<T> {
+        let handle = self.next(previous)?;
+        // SAFETY: The node is linked, so it is live, and only the link
+        // replaced below points at it
+        let node = unsafe { self.nodes.free(handle) }.expect("Linked nodes are never freed");
+        *self.link_mut(previous) = node.next;
+        if node.next.is_none() {
+            self.tail = previous;
+        }
+        self.len -= 1;
+        Some(node.data)
+    }
+
+    /// Items from the first node
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        Iter { nodes: &self.nodes, next: self.first, marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, A> {
+        IterMut { nodes: &mut self.nodes, next: self.first, marker: PhantomData }
+    }
+
+    /// Cursor at the first node
+    pub fn cursor(&self) -> ListCursor<'_, T, A> {
+        ListCursor { nodes: &self.nodes, node: self.first, marker: PhantomData }
+    }
+
+    /// Cursor at the first node that can change the list
+    pub fn cursor_mut(&mut self) -> ListCursorMut<'_, T, A> {
+        ListCursorMut { list: self, previous: None }
+    }
+}
+
+/// Frees the nodes in a loop, dropping them recursively could overflow the
+/// stack on long lists
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> Drop for SinglyLinkedList<T, A> {
+    fn drop(&mut self) {
+        while self.unlink_after(None).is_some() {}
+    }
+}
+
+pub struct Iter<'a, T: Clone, A: NodeAlloc<Node<T, A>> = Global> {
+    nodes: &'a A,
+    next: Link<A>,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T: Clone, A: NodeAlloc<Node<T, A>>> Iterator for Iter<'a, T, A> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nodes: &'a A = self.nodes;
+        // SAFETY: The list is borrowed for 'a, so its links stay live and
+        // nothing changes them while the iterator or its items are around
+        let node = unsafe { nodes.get(self.next?) }.expect("Linked nodes are never freed");
+        self.next = node.next;
+        Some(&node.data)
+    }
+}
+
+pub struct IterMut<'a, T: Clone, A: NodeAlloc<Node<T, A>> = Global> {
+    nodes: &'a mut A,
+    next: Link<A>,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: Clone, A: NodeAlloc<Node<T, A>>> Iterator for IterMut<'a, T, A> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // SAFETY: The list is borrowed mutably for 'a and every node is
+        // visited once, so the items handed out never alias. `get_ptr` only
+        // borrows the one node, and of it only the data is lent out, `next`
+        // is read before and never again.
+        unsafe {
+            let node = self.nodes.get_ptr(self.next?).expect("Linked nodes are never freed");
+            self.next = (*node.as_ptr()).next;
+            Some(&mut (*node.as_ptr()).data)
+        }
+    }
+}
+
+/// Unlinks the nodes one at a time from the first
+pub struct IntoIter<T: Clone, A: NodeAlloc<Node<T, A>> = Global> {
+    list: SinglyLinkedList<T, A>,
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.list.unlink_after(None)
+    }
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> IntoIterator for SinglyLinkedList<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, T: Clone, A: NodeAlloc<Node<T, A>>> IntoIterator for &'a SinglyLinkedList<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Clone, A: NodeAlloc<Node<T, A>>> IntoIterator for &'a mut SinglyLinkedList<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct ListCursor<'a, T: Clone, A: NodeAlloc<Node<T, A>> = Global> {
+    nodes: &'a A,
+    node: Link<A>,
+    marker: PhantomData<&'a T>,
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> ListCursor<'_, T, A> {
+    fn node(&self, handle: A::Handle) -> &Node<T, A> {
+        // SAFETY: The list is borrowed for as long as the cursor, so its
+        // links stay live
+        unsafe { self.nodes.get(handle) }.expect("Linked nodes are never freed")
+    }
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> Cursor for ListCursor<'_, T, A> {
+    type Item = T;
+
+    fn current(&self) -> Option<&T> {
+        self.node.map(|node| &self.node(node).data)
+    }
+
+    fn move_next(&mut self) {
+        self.node = self.node.and_then(|node| self.node(node).next);
+    }
+}
+
+/// Holds the node before the current one, so the current node can be
+/// unlinked without going back to find it
+pub struct ListCursorMut<'a, T: Clone, A: NodeAlloc<Node<T, A>> = Global> {
+    list: &'a mut SinglyLinkedList<T, A>,
+    /// None at the first node. Becomes the tail if the last node is removed.
+    previous: Link<A>,
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> Cursor for ListCursorMut<'_, T, A> {
+    type Item = T;
+
+    fn current(&self) -> Option<&T> {
+        self.list.next(self.previous).map(|node| self.list.data(node))
+    }
+
+    fn move_next(&mut self) {
+        if let Some(node) = self.list.next(self.previous) {
+            self.previous = Some(node);
+        }
+    }
+}
+
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> CursorMut for ListCursorMut<'_, T, A> {
+    fn current_mut(&mut self) -> Option<&mut T> {
+        let node = self.list.next(self.previous)?;
+        Some(&mut self.list.node_mut(node).data)
+    }
+
+    fn insert_after(&mut self, item: T) {
+        match self.list.next(self.previous) {
+            Some(node) => self.list.link_after(Some(node), item),
+            // At the end: link the item here, then step over it to stay at the end
             None => {
-                panic!("Node with given data not found!");
+                self.list.link_after(self.previous, item);
+                self.move_next();
             }
         }
-        self.node_count -= 1;
+    }
+
+    fn remove_current(&mut self) -> Option<T> {
+        self.list.unlink_after(self.previous)
     }
 }
 
-impl<T: fmt::Display + Clone + std::fmt::Display> fmt::Display for SinglyLinkedList<T> {
+impl<T, A> fmt::Display for SinglyLinkedList<T, A>
+where
+    T: fmt::Display + Clone + std::fmt::Display,
+    A: NodeAlloc<Node<T, A>>,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut current = &self.first;
-
         // Iterate over the nodes and format their data
-        while let Some(node) = current {
-            write!(f, "{} -> ", node.data)?;
-            current = &node.next;
+        for data in self.iter() {
+            write!(f, "{} -> ", data)?;
         }
 
         Ok(())
     }
 }
 
-pub fn run() {
-    println!("In Singly Linked Lists");
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Clone, A: NodeAlloc<Node<T, A>>> Invariants for SinglyLinkedList<T, A> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.len, self.iter().count(), "len doesn't match the nodes");
+        assert_eq!(self.tail, self.handles().last(), "tail isn't the last node");
+    }
+}
+
+/// Copies node by node into a new allocator, so the copy gets a tail of its own
+impl<T: Clone, A: NodeAlloc<Node<T, A>> + Default> Clone for SinglyLinkedList<T, A> {
+    fn clone(&self) -> Self {
+        let mut list = SinglyLinkedList {
+            nodes: A::default(),
+            first: None,
+            tail: None,
+            len: 0,
+            marker: PhantomData,
+        };
+        self.iter().for_each(|data| list.link_last(data.clone()));
+        list
+    }
+}
+
+/// Compares the items in order, walking both lists in a loop
+impl<T: PartialEq + Clone, A: NodeAlloc<Node<T, A>>> PartialEq for SinglyLinkedList<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+/// Formats the items like a slice
+impl<T: Debug + Clone, A: NodeAlloc<Node<T, A>>> Debug for SinglyLinkedList<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: HeapSize + Clone> HeapSize for SinglyLinkedList<T> {
+    /// Walks the nodes in a loop, a recursive sum could overflow the stack on long lists
+    fn heap_size(&self) -> usize {
+        self.iter().map(|data| std::mem::size_of::<Node<T, Global>>() + data.heap_size()).sum()
+    }
 }
 
 #[cfg(test)]
@@ -230,12 +665,11 @@ mod tests {
     // Custom assertion macro to check if the list contains specific data
     macro_rules! assert_list_contains_data {
         ($list:expr, $expected_data:expr) => {
-            let mut current = $list.first.as_ref();
+            let mut items = $list.iter();
             for expected in $expected_data {
-                assert_eq!(current.map(|node| &node.data), Some(expected));
-                current = current.unwrap().next.as_ref();
+                assert_eq!(items.next(), Some(expected));
             }
-            assert!(current.is_none());
+            assert!(items.next().is_none());
         };
     }
 
@@ -245,7 +679,7 @@ mod tests {
 
         assert_eq!(list.first, None);
         assert!(list.is_empty());
-        assert_eq!(list.node_count, 0);
+        assert_eq!(list.len(), 0);
     }
 
     #[test]
@@ -255,13 +689,13 @@ mod tests {
         let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
         list.append(data);
 
-        assert_eq!(list.first, Some(Box::new(Node::new(data))));
+        assert_eq!(list.first, list.tail);
         assert_eq!(
-            list.first.as_ref().map(|node| &node.data),
+            list.iter().next(),
             Some(&data)
         );
-        assert_eq!(list.first.as_ref().unwrap().next, None);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.iter().nth(1), None);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
@@ -273,20 +707,19 @@ mod tests {
             list.append(value);
         }
 
-        let mut current = list.first.as_ref();
+        let mut items = list.iter();
 
         for value in values {
             assert_eq!(
-                current.map(|node| &node.data),
+                items.next(),
                 Some(&value)
             );
-            current = current.and_then(|node| node.next.as_ref());
         }
         assert_eq!(
-            current.map(|node| &node.data),
+            items.next(),
             None
         );
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
@@ -296,11 +729,11 @@ mod tests {
         list.prepend(a);
 
         assert_eq!(
-            list.first.as_ref().map(|node| &node.data),
+            list.iter().next(),
             Some(&a)
         );
-        assert_eq!(list.first.as_ref().unwrap().next, None);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.iter().nth(1), None);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
@@ -309,38 +742,38 @@ mod tests {
         list.prepend("A");
 
         assert_eq!(
-            list.first.as_ref().map(|node| &node.data),
+            list.iter().next(),
             Some(&"A")
         );
-        assert_eq!(list.first.as_ref().unwrap().next, None);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.iter().nth(1), None);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
     fn test_prepend_to_non_empty_list() {
         let values = vec!["A", "B"];
         let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        list.append(&values[0]);
-        list.append(&values[1]);
+        list.append(values[0]);
+        list.append(values[1]);
 
         assert_list_contains_data!(list, &values);
-        assert_eq!(list.node_count, 2);
+        assert_eq!(list.len(), 2);
     }
 
     #[test]
     fn test_prepend_adding_multiple_nodes() {
-        let values = vec!["A", "B", "C"];
+        let values = ["A", "B", "C"];
         let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
         for value in values.iter().take(2) {
             list.append(value);
         }
 
-        list.prepend(&values[2]);
+        list.prepend(values[2]);
 
         let expected_data = vec!["C", "A", "B"];
 
         assert_list_contains_data!(&list, &expected_data);
-        assert_eq!(list.node_count, 3);
+        assert_eq!(list.len(), 3);
     }
 
     #[test]
@@ -369,7 +802,7 @@ mod tests {
         let expected_data = vec!["A", "C", "B"];
 
         assert_list_contains_data!(&list, &expected_data);
-        assert_eq!(list.node_count, 3);
+        assert_eq!(list.len(), 3);
     }
 
     #[test]
@@ -398,16 +831,16 @@ mod tests {
         let expected_data = vec!["A", "C", "B"];
 
         assert_list_contains_data!(&list, &expected_data);
-        assert_eq!(list.node_count, 3);
+        assert_eq!(list.len(), 3);
     }
 
     #[test]
     fn find_last_node_in_empty_list() {
-        let mut empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        let empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
         let result = empty_list.find_last_node();
         assert_eq!(result, None);
         assert_list_contains_data!(&empty_list, &[]);
-        assert_eq!(empty_list.node_count, 0);
+        assert_eq!(empty_list.len(), 0);
     }
 
     #[test]
@@ -418,40 +851,36 @@ mod tests {
         let result = list.find_last_node();
 
         assert_eq!(
-            result.map(|node| &node.data),
+            result.map(|node| list.data(node)),
             Some(&"A")
         );
         assert_list_contains_data!(&list, &["A"]);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
     fn find_last_node_when_multiple_nodes() {
         let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-
-        for value in &values {
-            list.append(&value);
-        }
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         let result = list.find_last_node();
         assert_eq!(
-            result.map(|node| &node.data),
+            result.map(|node| list.data(node)),
             Some(&"D")
         );
         assert_list_contains_data!(&list, &values);
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
     fn find_before_last_when_empty_list() {
-        let mut empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        let empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
 
         let result = empty_list.find_before_last();
 
         assert_eq!(result, None);
         assert_list_contains_data!(&empty_list, &[]);
-        assert_eq!(empty_list.node_count, 0);
+        assert_eq!(empty_list.len(), 0);
     }
 
     #[test]
@@ -463,36 +892,33 @@ mod tests {
 
         assert_eq!(result, None);
         assert_list_contains_data!(&list, &["A"]);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
     fn find_before_last_when_multiple_nodes() {
         let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         let result = list.find_before_last();
 
         assert_eq!(
-            result.map(|node| &node.data),
+            result.map(|node| list.data(node)),
             Some(&"C")
         );
         assert_list_contains_data!(&list, &values);
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
     fn find_node_when_empty_list() {
-        let mut empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        let empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
 
         let result = empty_list.find_node(&"A");
 
         assert_eq!(result, None);
         assert_list_contains_data!(&empty_list, &[]);
-        assert_eq!(empty_list.node_count, 0);
+        assert_eq!(empty_list.len(), 0);
     }
 
     #[test]
@@ -503,54 +929,48 @@ mod tests {
         let result = list.find_node(&"A");
 
         assert_eq!(
-            result.map(|node| &node.data),
+            result.map(|node| list.data(node)),
             Some(&"A")
         );
         assert_list_contains_data!(&list, &["A"]);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
     fn find_node_when_single_node_but_given_node_not_found() {
         let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         let result = list.find_node(&"Z");
 
         assert_eq!(result, None);
         assert_list_contains_data!(&list, &values);
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
     fn find_node_when_multiple_nodes_and_given_data_found() {
         let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
         let result = list.find_node(&"C");
 
         assert_eq!(
-            result.map(|node| &node.data),
+            result.map(|node| list.data(node)),
             Some(&"C")
         );
         assert_list_contains_data!(&list, &values);
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
     fn find_previous_node_when_empty_list() {
-        let mut empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        let empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
 
         let result = empty_list.find_previous_node(&"A");
 
         assert_eq!(result, None);
         assert_list_contains_data!(&empty_list, &[]);
-        assert_eq!(empty_list.node_count, 0);
+        assert_eq!(empty_list.len(), 0);
     }
 
     #[test]
@@ -562,40 +982,34 @@ mod tests {
 
         assert_eq!(result, None);
         assert_list_contains_data!(&list, &["A"]);
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
     fn find_precious_node_when_multiple_nodes_in_list() {
         let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         let result = list.find_previous_node(&"C");
 
         assert_eq!(
-            result.map(|node| &node.data),
+            result.map(|node| list.data(node)),
             Some(&"B")
         );
         assert_list_contains_data!(&list, &values);
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
     fn find_previous_node_when_multiple_nodes_data_not_found() {
         let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         let result = list.find_previous_node(&"Z");
 
         assert_eq!(result, None);
         assert_list_contains_data!(&list, &values);
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
@@ -607,17 +1021,14 @@ mod tests {
 
     #[test]
     fn delete_first_when_list_has_elements() {
-        let values = vec!["A", "B", "C"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let values = ["A", "B", "C"];
+        let mut list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         list.delete_first();
 
         let expected_data = vec!["B", "C"];
         assert_list_contains_data!(&list, &expected_data);
-        assert_eq!(list.node_count, 2);
+        assert_eq!(list.len(), 2);
     }
 
     #[test]
@@ -629,17 +1040,14 @@ mod tests {
 
     #[test]
     fn delete_last_when_list_has_elements() {
-        let values = vec!["A", "B", "C"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let values = ["A", "B", "C"];
+        let mut list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         list.delete_last();
 
         let expected_data = vec!["A", "B"];
         assert_list_contains_data!(&list, &expected_data);
-        assert_eq!(list.node_count, 2);
+        assert_eq!(list.len(), 2);
     }
 
     #[test]
@@ -653,11 +1061,8 @@ mod tests {
     #[test]
     #[should_panic(expected = "Node with given data not found!")]
     fn delete_node_with_data_when_nodes_present_but_data_not_found() {
-        let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let values = ["A", "B", "C", "D"];
+        let mut list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         list.delete_node_with_data("Z");
     }
@@ -670,23 +1075,33 @@ mod tests {
 
         assert!(list.is_empty());
         assert_list_contains_data!(&list, &[]);
-        assert_eq!(list.node_count, 0);
+        assert_eq!(list.len(), 0);
     }
 
     #[test]
-    fn delete_node_with_data_when_multiple_nodes_and_node_present() {
-        let values = vec!["A", "B", "C", "D"];
+    fn delete_node_with_data_when_data_is_in_first_node_keeps_the_rest() {
         let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
+        for value in ["A", "B", "A"] {
+            list.append(value);
         }
 
+        list.delete_node_with_data("A");
+
+        assert_list_contains_data!(&list, &["B", "A"]);
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn delete_node_with_data_when_multiple_nodes_and_node_present() {
+        let values = ["A", "B", "C", "D"];
+        let mut list: SinglyLinkedList<&str> = values.iter().copied().collect();
+
         list.delete_node_with_data("C");
 
         let expected_data = vec!["A", "B", "D"];
 
         assert_list_contains_data!(&list, &expected_data);
-        assert_eq!(list.node_count, 3);
+        assert_eq!(list.len(), 3);
     }
 
     #[test]
@@ -694,7 +1109,7 @@ mod tests {
         let empty_list: SinglyLinkedList<&str> = SinglyLinkedList::new();
 
         assert_eq!(format!("{}", empty_list), "");
-        assert_eq!(empty_list.node_count, 0);
+        assert_eq!(empty_list.len(), 0);
     }
 
     #[test]
@@ -703,32 +1118,385 @@ mod tests {
         list.append("A");
 
         assert_eq!(format!("{}", list), "A -> ");
-        assert_eq!(list.node_count, 1);
+        assert_eq!(list.len(), 1);
     }
 
     #[test]
     fn display_linked_list_multiple_nodes() {
-        let values = vec!["A", "B", "C", "D"];
-        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
-        }
+        let values = ["A", "B", "C", "D"];
+        let list: SinglyLinkedList<&str> = values.iter().copied().collect();
 
         assert_eq!(format!("{}", list), "A -> B -> C -> D -> ");
-        assert_eq!(list.node_count, 4);
+        assert_eq!(list.len(), 4);
     }
 
     #[test]
     fn clear_when_multiple_nodes_exist_in_the_list() {
-        let values = vec!["A", "B", "C", "D"];
+        let values = ["A", "B", "C", "D"];
+        let mut list: SinglyLinkedList<&str> = values.iter().copied().collect();
+
+        list.clear();
+
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn try_variants_on_empty_list_return_empty_collection() {
         let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
-        for value in &values {
-            list.append(&value);
+
+        assert_eq!(list.try_insert_after_given("A", "B"), Err(Error::EmptyCollection));
+        assert_eq!(list.try_insert_before_given("A", "B"), Err(Error::EmptyCollection));
+        assert_eq!(list.try_delete_first(), Err(Error::EmptyCollection));
+        assert_eq!(list.try_delete_last(), Err(Error::EmptyCollection));
+        assert_eq!(list.try_delete_node_with_data("A"), Err(Error::NotFound));
+    }
+
+    #[test]
+    fn try_insert_returns_not_found_for_missing_node() {
+        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        list.append("A");
+
+        let after = list.try_insert_after_given("X", "Z");
+        let before = list.try_insert_before_given("X", "Z");
+
+        assert_eq!(after, Err(Error::NotFound));
+        assert_eq!(before, Err(Error::NotFound));
+        assert_list_contains_data!(&list, &["A"]);
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn try_delete_returns_removed_data() {
+        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        for value in ["A", "B", "C"] {
+            list.append(value);
         }
 
-        list.clear();
+        assert_eq!(list.try_delete_first(), Ok("A"));
+        assert_eq!(list.try_delete_last(), Ok("C"));
+        assert_eq!(list.try_delete_last(), Ok("B"));
 
         assert!(list.is_empty());
-        assert_eq!(list.node_count, 0);
+        assert_eq!(list.len(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_len_follows_every_edit() {
+        let mut list = SinglyLinkedList::new();
+        assert_eq!(list.len(), 0);
+
+        list.append("B");
+        list.prepend("A");
+        list.insert_after_given("D", "B");
+        list.insert_before_given("C", "D");
+        assert_eq!(list.len(), 4);
+
+        list.delete_first();
+        list.delete_last();
+        list.delete_node_with_data("B");
+        assert_eq!(list.len(), 1);
+
+        list.cursor_mut().insert_after("E");
+        assert_eq!(list.len(), 2);
+        list.clear();
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_len_matches_the_nodes_after_every_edit() {
+        let edits: &[fn(&mut SinglyLinkedList<i32>)] = &[
+            |list| list.append(3),
+            |list| list.prepend(1),
+            |list| list.insert_after_given(2, 1),
+            |list| list.insert_before_given(5, 2),
+            |list| list.insert_at(1, 4).unwrap(),
+            |list| assert_eq!(list.remove_at(2), Some(5)),
+            |list| list.delete_node_with_data(3),
+            |list| list.cursor_mut().insert_after(6),
+            |list| assert_eq!(list.cursor_mut().remove_current(), Some(1)),
+            |list| list.reverse(),
+            |list| list.delete_last(),
+            |list| list.extend([7, 8]),
+            |list| list.delete_first(),
+            |list| list.clear(),
+        ];
+        let mut list = SinglyLinkedList::new();
+
+        for edit in edits {
+            edit(&mut list);
+            assert_eq!(list.len(), list.iter().count());
+        }
+    }
+
+    #[test]
+    fn test_append_follows_every_change_at_the_end() {
+        let mut list = SinglyLinkedList::from(vec!["A", "B", "C"]);
+
+        list.delete_last();
+        list.append("D");
+        list.delete_node_with_data("D");
+        list.append("E");
+        list.insert_after_given("F", "E");
+        list.append("G");
+        list.reverse();
+        list.append("H");
+
+        list.assert_invariants();
+        assert_list_contains_data!(&list, &["G", "F", "E", "B", "A", "H"]);
+    }
+
+    #[test]
+    fn test_append_after_emptying_the_list() {
+        let mut list = SinglyLinkedList::from(vec!["A"]);
+
+        list.delete_first();
+        list.append("B");
+        list.delete_last();
+        list.prepend("C");
+        list.append("D");
+
+        list.assert_invariants();
+        assert_list_contains_data!(&list, &["C", "D"]);
+    }
+
+    #[test]
+    fn test_cursor_edits_at_the_end_move_the_tail() {
+        let mut list = SinglyLinkedList::from(vec!["A", "B"]);
+
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some("B"));
+        list.append("C");
+        let mut cursor = list.cursor_mut();
+        (0..2).for_each(|_| cursor.move_next());
+        cursor.insert_after("D");
+        list.append("E");
+
+        list.assert_invariants();
+        assert_list_contains_data!(&list, &["A", "C", "D", "E"]);
+    }
+
+    #[test]
+    fn test_clone_appends_to_its_own_nodes() {
+        let mut list = SinglyLinkedList::from(vec![1, 2]);
+
+        let mut copy = list.clone();
+        copy.append(3);
+        list.append(4);
+
+        copy.assert_invariants();
+        assert_eq!(copy.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &4]);
+        assert_ne!(list, copy);
+    }
+
+    #[test]
+    fn test_contains_and_count_occurrences() {
+        let list = SinglyLinkedList::from(vec!["A", "B", "A", "C", "A"]);
+
+        assert!(list.contains(&"C"));
+        assert!(!list.contains(&"D"));
+        assert_eq!(list.count_occurrences(&"A"), 3);
+        assert_eq!(list.count_occurrences(&"B"), 1);
+        assert_eq!(list.count_occurrences(&"D"), 0);
+        assert_eq!(SinglyLinkedList::<&str>::new().count_occurrences(&"A"), 0);
+    }
+
+    #[test]
+    fn test_collect_and_extend_append_in_order() {
+        let mut list: SinglyLinkedList<i32> = (1..=3).collect();
+
+        list.extend(vec![4, 5]);
+        list.extend(&[6, 7]);
+        list.extend(std::iter::empty::<i32>());
+
+        assert!(list.iter().eq((1..=7).collect::<Vec<_>>().iter()));
+        assert_eq!(list.len(), 7);
+        list.assert_invariants();
+        assert!(std::iter::empty::<i32>().collect::<SinglyLinkedList<_>>().is_empty());
+    }
+
+    #[test]
+    fn test_insert_at_every_position() {
+        let mut list = SinglyLinkedList::new();
+
+        list.insert_at(0, "B").unwrap();
+        list.insert_at(0, "A").unwrap();
+        list.insert_at(2, "D").unwrap();
+        list.insert_at(2, "C").unwrap();
+        list.append("E");
+
+        assert_list_contains_data!(&list, &["A", "B", "C", "D", "E"]);
+        assert_eq!(list.len(), 5);
+        list.assert_invariants();
+    }
+
+    #[test]
+    fn test_insert_at_past_the_end_is_an_error() {
+        let mut list = SinglyLinkedList::from(vec!["A"]);
+
+        let result = list.insert_at(2, "B");
+
+        assert_eq!(result, Err(Error::IndexOutOfBounds { index: 2, len: 1 }));
+        assert_list_contains_data!(&list, &["A"]);
+    }
+
+    #[test]
+    fn test_remove_at_every_position() {
+        let mut list = SinglyLinkedList::from(vec!["A", "B", "C", "D"]);
+
+        assert_eq!(list.remove_at(1), Some("B"));
+        assert_eq!(list.remove_at(2), Some("D"));
+        list.append("E");
+        assert_eq!(list.remove_at(0), Some("A"));
+
+        assert_list_contains_data!(&list, &["C", "E"]);
+        assert_eq!(list.len(), 2);
+        list.assert_invariants();
+    }
+
+    #[test]
+    fn test_remove_at_out_of_range_is_none() {
+        let mut list = SinglyLinkedList::from(vec!["A", "B"]);
+
+        assert_eq!(list.remove_at(2), None);
+        assert_eq!(list.remove_at(usize::MAX), None);
+        assert_eq!(SinglyLinkedList::<i32>::new().remove_at(0), None);
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn test_reverse_empty_and_single_node_lists() {
+        let mut empty: SinglyLinkedList<i32> = SinglyLinkedList::new();
+        let mut single = SinglyLinkedList::from(vec![1]);
+
+        empty.reverse();
+        single.reverse();
+
+        assert!(empty.is_empty());
+        assert_eq!(single.iter().collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(single.len(), 1);
+    }
+
+    #[test]
+    fn test_reverse_relinks_every_node() {
+        let mut list = SinglyLinkedList::from(vec!["A", "B", "C"]);
+
+        list.reverse();
+
+        assert_list_contains_data!(&list, &["C", "B", "A"]);
+        list.append("D");
+        assert_eq!(list.try_delete_last(), Ok("D"));
+        assert_eq!(list.try_delete_last(), Ok("A"));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_reverse_long_list_twice_gives_it_back() {
+        let items: Vec<u32> = (0..10_000).collect();
+        let mut list = SinglyLinkedList::from(items.clone());
+
+        list.reverse();
+        assert!(list.iter().eq(items.iter().rev()));
+        list.reverse();
+
+        assert!(list.iter().eq(items.iter()));
+        assert_eq!(list.len(), items.len());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_long_lists_drop_compare_and_format_in_a_loop() {
+        // Linked directly, `append` would check the invariants after every item
+        let mut list = SinglyLinkedList::new();
+        (0..1_000_000u32).for_each(|item| list.link_last(item));
+        let copy = list.clone();
+
+        assert_eq!(list, copy);
+        assert!(format!("{:?}", list).ends_with(", 999999]"));
+        drop(list);
+        drop(copy);
+    }
+
+    #[test]
+    fn test_iter_goes_from_the_first_node() {
+        let list = SinglyLinkedList::from(vec![1, 2, 3]);
+
+        let items: Vec<&i32> = list.iter().collect();
+
+        assert_eq!(items, vec![&1, &2, &3]);
+        assert_eq!((&list).into_iter().count(), 3);
+        assert_eq!(SinglyLinkedList::<i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_iter_mut_and_for_loops() {
+        let mut list = SinglyLinkedList::from(vec![1, 2, 3]);
+
+        for data in &mut list {
+            *data *= 10;
+        }
+        list.iter_mut().filter(|data| **data > 10).for_each(|data| *data += 1);
+
+        let mut sum = 0;
+        for data in &list {
+            sum += data;
+        }
+        assert_eq!(sum, 10 + 21 + 31);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![10, 21, 31]);
+    }
+
+    #[test]
+    fn test_into_iter_keeps_the_rest_of_the_list_counted() {
+        let list = SinglyLinkedList::from(vec!["A", "B", "C"]);
+
+        let mut items = list.into_iter();
+        assert_eq!(items.next(), Some("A"));
+
+        assert_eq!(items.list.len(), 2);
+        assert_eq!(items.collect::<Vec<_>>(), vec!["B", "C"]);
+    }
+
+    #[test]
+    fn test_cursor_edits_in_the_middle() {
+        let mut list: SinglyLinkedList<&str> = SinglyLinkedList::new();
+        for value in ["A", "B", "C"] {
+            list.append(value);
+        }
+
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some("B"));
+        *cursor.current_mut().unwrap() = "D";
+        cursor.insert_after("E");
+
+        assert_list_contains_data!(&list, &["A", "D", "E"]);
+        assert_eq!(list.len(), 3);
+        let mut cursor = list.cursor();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&"D"));
+    }
+
+    fn miscounted_list() -> SinglyLinkedList<i32> {
+        let mut list = SinglyLinkedList::from(vec![1, 2]);
+        list.len = 3;
+        list
+    }
+
+    #[test]
+    #[should_panic(expected = "len doesn't match the nodes")]
+    fn test_broken_invariant_panics() {
+        let list = miscounted_list();
+
+        list.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "len doesn't match the nodes")]
+    fn test_mutations_check_the_invariants() {
+        let mut list = miscounted_list();
+
+        list.prepend(0);
+    }
+}
@@ -0,0 +1,304 @@
+//! Skew Heap implementation (min heap)
+//! Self-adjusting version of the leftist heap that keeps no rank at all.
+//! Meld walks down the right spines of both heaps like a merge of two
+//! sorted lists and swaps the children of every node it passes, so right
+//! spines that grow long are turned into left spines. A single meld can be
+//! O(n), but any sequence of operations is O(log n) amortized per operation.
+//! Push and pop are both just a meld. Meld is iterative because the right
+//! spine has no height bound.
+
+use std::mem;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+struct SkewNode<T> {
+    item: T,
+    left: Option<Box<SkewNode<T>>>,
+    right: Option<Box<SkewNode<T>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkewHeap<T: Ord> {
+    root: Option<Box<SkewNode<T>>>,
+    size: usize,
+}
+
+/// Takes nodes off the right spines in ascending order, then links them back
+/// bottom up, each one with the melded rest as its left child and its old
+/// left child moved to the right
+fn meld_nodes<T: Ord>(
+    mut a: Option<Box<SkewNode<T>>>,
+    mut b: Option<Box<SkewNode<T>>>
+) -> Option<Box<SkewNode<T>>> {
+    let mut spine = vec![];
+    let mut rest = loop {
+        match (a, b) {
+            (None, node) | (node, None) => {
+                break node;
+            }
+            (Some(mut x), Some(y)) => {
+                if y.item < x.item {
+                    (x, b) = (y, Some(x));
+                } else {
+                    b = Some(y);
+                }
+                a = x.right.take();
+                spine.push(x);
+            }
+        }
+    };
+    while let Some(mut node) = spine.pop() {
+        node.right = node.left.take();
+        node.left = rest;
+        rest = Some(node);
+    }
+    rest
+}
+
+impl<T: Ord> SkewHeap<T> {
+    pub fn new() -> Self {
+        SkewHeap { root: None, size: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = SkewHeap::new();
+        check_invariants!(self);
+    }
+
+    /// Returns the smallest item without removing it
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.item)
+    }
+
+    /// Items in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut stack: Vec<&SkewNode<T>> = self.root.as_deref().into_iter().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+            Some(&node.item)
+        })
+    }
+
+    /// Melds a single node heap into this one. O(log n) amortized
+    pub fn push(&mut self, item: T) {
+        self.link(item);
+        check_invariants!(self);
+    }
+
+    /// `push` without the invariant check
+    fn link(&mut self, item: T) {
+        let node = Box::new(SkewNode { item, left: None, right: None });
+        self.root = meld_nodes(self.root.take(), Some(node));
+        self.size += 1;
+    }
+
+    /// Removes the root and melds its two subtrees. O(log n) amortized
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let SkewNode { item, left, right } = *root;
+        self.root = meld_nodes(left, right);
+        self.size -= 1;
+        check_invariants!(self);
+        Some(item)
+    }
+
+    /// Moves all items of `other` into this heap. O(log n) amortized
+    pub fn meld(&mut self, mut other: SkewHeap<T>) {
+        self.root = meld_nodes(self.root.take(), other.root.take());
+        self.size += mem::take(&mut other.size);
+        check_invariants!(self);
+    }
+}
+
+impl<T: Ord> Default for SkewHeap<T> {
+    fn default() -> Self {
+        SkewHeap::new()
+    }
+}
+
+/// `contains` goes through every item, a heap is only ordered for its top
+impl<T: Ord> Collection for SkewHeap<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        SkewHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        SkewHeap::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.iter().any(|other| other == item)
+    }
+}
+
+/// Links the items one by one and checks the invariants once at the end
+impl<T: Ord> FromIterator<T> for SkewHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = SkewHeap::new();
+        for item in iter {
+            heap.link(item);
+        }
+        check_invariants!(&heap);
+        heap
+    }
+}
+
+/// Drops the nodes one by one, the tree can be O(n) deep
+impl<T: Ord> Drop for SkewHeap<T> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<SkewNode<T>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+/// Checks heap order node by node, in a loop since the tree can be O(n) deep
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for SkewHeap<T> {
+    fn assert_invariants(&self) {
+        let mut nodes = 0;
+        let mut stack: Vec<&SkewNode<T>> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            nodes += 1;
+            for child in [&node.left, &node.right].into_iter().flatten() {
+                assert!(node.item <= child.item, "Parent is not greater than its children");
+                stack.push(child);
+            }
+        }
+        assert_eq!(nodes, self.size, "size doesn't match the nodes");
+    }
+}
+
+/// Every node is a box of its own
+impl<T: Ord + HeapSize> HeapSize for SkewHeap<T> {
+    fn heap_size(&self) -> usize {
+        self.iter().map(|item| mem::size_of::<SkewNode<T>>() + item.heap_size()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain<T: Ord>(heap: &mut SkewHeap<T>) -> Vec<T> {
+        let mut items = vec![];
+        while let Some(item) = heap.pop() {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn when_heap_is_created_it_is_empty() {
+        let mut heap = SkewHeap::<i32>::new();
+
+        assert!(heap.is_empty());
+        assert_eq!(heap.peek(), None);
+        assert_eq!(heap.pop(), None);
+    }
+
+    #[test]
+    fn test_push_keeps_smallest_on_top() {
+        let mut heap = SkewHeap::new();
+
+        for item in [5, 3, 8, 1, 9] {
+            heap.push(item);
+        }
+
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 5);
+        heap.assert_invariants();
+    }
+
+    #[test]
+    fn test_meld_swaps_children_on_the_merge_path() {
+        let mut heap: SkewHeap<i32> = [1, 2].into_iter().collect();
+
+        heap.push(3);
+
+        // 2 was the left child of 1, the meld path moved it to the right
+        let root = heap.root.as_ref().unwrap();
+        assert_eq!(root.left.as_ref().map(|node| node.item), Some(3));
+        assert_eq!(root.right.as_ref().map(|node| node.item), Some(2));
+    }
+
+    #[test]
+    fn test_pop_returns_items_in_ascending_order() {
+        let mut heap: SkewHeap<i32> = (0..100).map(|i| (i * 37) % 100).collect();
+
+        assert_eq!(drain(&mut heap), (0..100).collect::<Vec<i32>>());
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_meld_two_heaps() {
+        let mut heap_a: SkewHeap<i32> = [4, 8, 2, 6].into_iter().collect();
+        let heap_b: SkewHeap<i32> = [7, 1, 5].into_iter().collect();
+
+        heap_a.meld(heap_b);
+
+        assert_eq!(heap_a.len(), 7);
+        heap_a.assert_invariants();
+        assert_eq!(drain(&mut heap_a), vec![1, 2, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_random_operations_match_sorted_model() {
+        let mut heap = SkewHeap::new();
+        let mut model: Vec<u64> = vec![];
+        let mut state: u64 = 23;
+
+        for _ in 0..1000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let value = (state >> 33) % 500;
+            if (state >> 60).is_multiple_of(3) {
+                model.sort_unstable_by(|a, b| b.cmp(a));
+                assert_eq!(heap.pop(), model.pop());
+            } else {
+                heap.push(value);
+                model.push(value);
+            }
+        }
+
+        assert_eq!(heap.len(), model.len());
+        heap.assert_invariants();
+    }
+
+    #[test]
+    fn test_deep_tree_does_not_overflow() {
+        // Descending pushes build a left spine n nodes deep, which neither
+        // meld nor drop may follow recursively. Linked directly, `push`
+        // would check the invariants after every item.
+        let mut heap = SkewHeap::new();
+        for item in (0..100_000).rev() {
+            heap.link(item * 2);
+        }
+        for item in 0..1000 {
+            heap.link(item * 2 + 1);
+        }
+
+        assert_eq!(heap.pop(), Some(0));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.len(), 100_998);
+        drop(heap);
+    }
+}
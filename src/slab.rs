@@ -0,0 +1,304 @@
+//! Slab with generational keys
+//! Values are stored in a vector and identified by their index. Removed
+//! slots form a free list threaded through the vacant entries and are reused
+//! by later inserts, so `insert`, `get` and `remove` are all O(1).
+//! Every slot counts how often it was freed. A key remembers that generation,
+//! so a key to a removed value never finds the value reusing its slot.
+
+use std::mem;
+use std::ptr::NonNull;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::node_alloc::{ CheckedAlloc, NodeAlloc, NodeHandle };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key {
+    index: usize,
+    generation: u32,
+}
+
+impl Key {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Entry<T> {
+    Occupied { generation: u32, value: T },
+    Vacant { generation: u32, next_free: Option<usize> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Slab<T> {
+    entries: Vec<Entry<T>>,
+    free_head: Option<usize>,
+    len: usize,
+}
+
+impl<T> Slab<T> {
+    pub fn new() -> Self {
+        Slab { entries: vec![], free_head: None, len: 0 }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Slab { entries: Vec::with_capacity(capacity), free_head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of slots, used or free
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Stores the value in a free slot, or a new one if there is none
+    pub fn insert(&mut self, value: T) -> Key {
+        self.len += 1;
+        let key = match self.free_head {
+            Some(index) => {
+                let Entry::Vacant { generation, next_free } = self.entries[index] else {
+                    unreachable!("The free list only links vacant entries");
+                };
+                self.free_head = next_free;
+                self.entries[index] = Entry::Occupied { generation, value };
+                Key { index, generation }
+            }
+            None => {
+                self.entries.push(Entry::Occupied { generation: 0, value });
+                Key { index: self.entries.len() - 1, generation: 0 }
+            }
+        };
+        check_invariants!(self);
+        key
+    }
+
+    pub fn get(&self, key: Key) -> Option<&T> {
+        match self.entries.get(key.index)? {
+            Entry::Occupied { generation, value } if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        match self.entries.get_mut(key.index)? {
+            Entry::Occupied { generation, value } if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, key: Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes the value and frees its slot. A stale key returns None.
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        self.get(key)?;
+        let vacant = Entry::Vacant {
+            generation: key.generation.wrapping_add(1),
+            next_free: self.free_head,
+        };
+        let entry = mem::replace(&mut self.entries[key.index], vacant);
+        let Entry::Occupied { value, .. } = entry else {
+            unreachable!("The key was checked above");
+        };
+        self.free_head = Some(key.index);
+        self.len -= 1;
+        check_invariants!(self);
+        Some(value)
+    }
+
+    /// Removes every value. Old keys stay invalid.
+    pub fn clear(&mut self) {
+        let keys: Vec<Key> = self.keys().collect();
+        for key in keys {
+            self.remove(key);
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Pairs in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T)> + '_ {
+        self.entries.iter().enumerate().filter_map(|(index, entry)| match entry {
+            Entry::Occupied { generation, value } => {
+                Some((Key { index, generation: *generation }, value))
+            }
+            Entry::Vacant { .. } => None,
+        })
+    }
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Slab::new()
+    }
+}
+
+/// Follows the free list, which has to reach every vacant entry exactly once
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for Slab<T> {
+    fn assert_invariants(&self) {
+        let occupied = self.iter().count();
+        assert_eq!(occupied, self.len, "len doesn't match the occupied entries");
+        let vacant = self.entries.len() - self.len;
+        let mut free = 0;
+        let mut next = self.free_head;
+        while let Some(index) = next {
+            free += 1;
+            assert!(free <= vacant, "Free list is longer than the vacancies");
+            match self.entries.get(index) {
+                Some(Entry::Vacant { next_free, .. }) => next = *next_free,
+                _ => panic!("Free list links an entry that isn't vacant"),
+            }
+        }
+        assert_eq!(free, vacant, "Free list misses a vacant entry");
+    }
+}
+
+impl<T> NodeHandle for Slab<T> {
+    type Handle = Key;
+}
+
+impl<T> NodeAlloc<T> for Slab<T> {
+    fn alloc(&mut self, node: T) -> Key {
+        self.insert(node)
+    }
+
+    unsafe fn get(&self, key: Key) -> Option<&T> {
+        Slab::get(self, key)
+    }
+
+    unsafe fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        Slab::get_mut(self, key)
+    }
+
+    /// Borrows the one entry, `get_mut` would borrow every entry of the vector
+    unsafe fn get_ptr(&mut self, key: Key) -> Option<NonNull<T>> {
+        if key.index >= self.entries.len() {
+            return None;
+        }
+        // SAFETY: The index is in bounds and only this entry is borrowed, so
+        // nodes lent out from other entries stay valid
+        match unsafe { &mut *self.entries.as_mut_ptr().add(key.index) } {
+            Entry::Occupied { generation, value } if *generation == key.generation => {
+                Some(NonNull::from(value))
+            }
+            _ => None,
+        }
+    }
+
+    unsafe fn free(&mut self, key: Key) -> Option<T> {
+        self.remove(key)
+    }
+}
+
+// SAFETY: Every method checks the index and generation of the key
+unsafe impl<T> CheckedAlloc<T> for Slab<T> {}
+
+impl HeapSize for Key {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<T: HeapSize> HeapSize for Entry<T> {
+    fn heap_size(&self) -> usize {
+        match self {
+            Entry::Occupied { value, .. } => value.heap_size(),
+            Entry::Vacant { .. } => 0,
+        }
+    }
+}
+
+impl<T: HeapSize> HeapSize for Slab<T> {
+    fn heap_size(&self) -> usize {
+        self.entries.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_slab_is_created_it_is_empty() {
+        let slab = Slab::<i32>::new();
+
+        assert!(slab.is_empty());
+        assert_eq!(slab.iter().next(), None);
+    }
+
+    #[test]
+    fn test_insert_get_and_remove() {
+        let mut slab = Slab::new();
+
+        let a = slab.insert("a");
+        let b = slab.insert("b");
+        *slab.get_mut(a).unwrap() = "A";
+
+        assert_eq!(slab.get(a), Some(&"A"));
+        assert_eq!(slab.remove(b), Some("b"));
+        assert_eq!(slab.get(b), None);
+        assert_eq!(slab.len(), 1);
+    }
+
+    #[test]
+    fn test_freed_slots_are_reused_with_new_generation() {
+        let mut slab = Slab::new();
+        let old = slab.insert(1);
+        slab.insert(2);
+
+        slab.remove(old);
+        let new = slab.insert(3);
+
+        assert_eq!(new.index(), old.index());
+        assert_ne!(new.generation(), old.generation());
+        assert_eq!(slab.get(old), None, "Stale key must not see the new value");
+        assert_eq!(slab.remove(old), None);
+        assert_eq!(slab.get(new), Some(&3));
+        assert_eq!(slab.capacity(), 2);
+    }
+
+    #[test]
+    fn test_free_list_is_last_in_first_out() {
+        let mut slab = Slab::new();
+        let keys: Vec<Key> = (0..5).map(|i| slab.insert(i)).collect();
+
+        slab.remove(keys[1]);
+        slab.remove(keys[3]);
+
+        assert_eq!(slab.insert(10).index(), 3);
+        assert_eq!(slab.insert(11).index(), 1);
+        assert_eq!(slab.insert(12).index(), 5);
+    }
+
+    #[test]
+    fn test_iter_and_clear() {
+        let mut slab = Slab::new();
+        let keys: Vec<Key> = (0..4).map(|i| slab.insert(i * 10)).collect();
+        slab.remove(keys[2]);
+
+        let values: Vec<i32> = slab.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, vec![0, 10, 30]);
+
+        slab.clear();
+        assert!(slab.is_empty());
+        assert!(keys.iter().all(|key| !slab.contains(*key)));
+    }
+}
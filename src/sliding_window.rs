@@ -0,0 +1,224 @@
+//! Sliding window aggregation
+//! FIFO window of items that can report the combined value of all of them,
+//! such as their sum, minimum or maximum, for any associative operation.
+//! The window is two stacks. New items go on the back stack, which keeps the
+//! aggregate of everything on it. Items leave from the front stack, where
+//! every item is stored with the aggregate of itself and all items pushed
+//! after it. When the front stack runs empty the back stack is moved over in
+//! one go, computing those aggregates on the way, so push and pop are O(1)
+//! amortized and the aggregate of the window is one operation on the two
+//! stack aggregates. The operation doesn't have to be commutative.
+
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::stack::Stack;
+
+pub struct SlidingWindowAggregator<T, Op = fn(&T, &T) -> T> {
+    /// Oldest items, each with the aggregate from it up to the newest item
+    /// on this stack. The oldest item is on top.
+    front: Stack<(T, T)>,
+    /// Newest items, newest on top
+    back: Stack<T>,
+    /// Aggregate of the back stack, oldest item first
+    back_aggregate: Option<T>,
+    len: usize,
+    operation: Op,
+}
+
+impl<T: Clone, Op: Fn(&T, &T) -> T> SlidingWindowAggregator<T, Op> {
+    /// Creates an empty window for an associative operation, which means
+    /// `op(op(a, b), c) == op(a, op(b, c))`
+    pub fn new(operation: Op) -> Self {
+        SlidingWindowAggregator {
+            front: Stack::new(),
+            back: Stack::new(),
+            back_aggregate: None,
+            len: 0,
+            operation,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds the newest item
+    pub fn push(&mut self, item: T) {
+        self.back_aggregate = Some(match &self.back_aggregate {
+            Some(aggregate) => (self.operation)(aggregate, &item),
+            None => item.clone(),
+        });
+        self.back.push(item);
+        self.len += 1;
+        check_invariants!(self);
+    }
+
+    /// Removes the oldest item
+    pub fn pop(&mut self) -> Option<T> {
+        if self.front.is_empty() {
+            self.move_back_to_front();
+        }
+        let (item, _) = self.front.pop()?;
+        self.len -= 1;
+        check_invariants!(self);
+        Some(item)
+    }
+
+    /// All items combined from the oldest to the newest, None for an empty window
+    pub fn aggregate(&self) -> Option<T> {
+        let front = self.front.peek().map(|(_, aggregate)| aggregate);
+        match (front, &self.back_aggregate) {
+            (Some(front), Some(back)) => Some((self.operation)(front, back)),
+            (Some(only), None) | (None, Some(only)) => Some(only.clone()),
+            (None, None) => None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.front = Stack::new();
+        self.back = Stack::new();
+        self.back_aggregate = None;
+        self.len = 0;
+        check_invariants!(self);
+    }
+
+    /// Reverses the back stack onto the front one, newest item first
+    fn move_back_to_front(&mut self) {
+        while let Some(item) = self.back.pop() {
+            let aggregate = match self.front.peek() {
+                Some((_, newer)) => (self.operation)(&item, newer),
+                None => item.clone(),
+            };
+            self.front.push((item, aggregate));
+        }
+        self.back_aggregate = None;
+    }
+}
+
+/// Checks the length covers both stacks and the back stack has an aggregate
+/// exactly when it has items
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, Op> Invariants for SlidingWindowAggregator<T, Op> {
+    fn assert_invariants(&self) {
+        let count = self.front.iter().count() + self.back.iter().count();
+        assert_eq!(count, self.len, "len doesn't match the stacks");
+        assert_eq!(
+            self.back_aggregate.is_some(),
+            !self.back.is_empty(),
+            "Back aggregate doesn't match the back stack"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn min(a: &i64, b: &i64) -> i64 {
+        *a.min(b)
+    }
+
+    #[test]
+    fn when_window_is_created_it_is_empty() {
+        let mut window = SlidingWindowAggregator::new(min as fn(&i64, &i64) -> i64);
+
+        assert!(window.is_empty());
+        assert_eq!(window.aggregate(), None);
+        assert_eq!(window.pop(), None);
+    }
+
+    #[test]
+    fn test_sum_over_window() {
+        let mut window = SlidingWindowAggregator::new(|a: &i32, b: &i32| a + b);
+
+        for item in [3, 1, 4, 1, 5] {
+            window.push(item);
+        }
+        window.pop();
+        window.push(9);
+
+        assert_eq!(window.aggregate(), Some(20));
+        assert_eq!(window.len(), 5);
+    }
+
+    #[test]
+    fn test_sliding_minimum() {
+        let items = [5, 3, 8, 6, 2, 7, 9, 4, 1];
+        let mut window = SlidingWindowAggregator::new(min as fn(&i64, &i64) -> i64);
+        let mut minimums = vec![];
+
+        for (i, &item) in items.iter().enumerate() {
+            window.push(item);
+            if i >= 3 {
+                window.pop();
+            }
+            if i >= 2 {
+                minimums.push(window.aggregate().unwrap());
+            }
+        }
+
+        assert_eq!(minimums, vec![3, 3, 2, 2, 2, 4, 1]);
+    }
+
+    #[test]
+    fn test_operation_does_not_have_to_commute() {
+        let mut window =
+            SlidingWindowAggregator::new(|a: &String, b: &String| format!("{}{}", a, b));
+        for word in ["a", "b", "c"] {
+            window.push(word.to_string());
+        }
+
+        assert_eq!(window.pop().as_deref(), Some("a"));
+        window.push("d".to_string());
+
+        assert_eq!(window.aggregate().as_deref(), Some("bcd"));
+    }
+
+    /// Applies the linear function `x -> a * x + b` and then the other one,
+    /// associative but not commutative
+    fn compose(first: &(u64, u64), second: &(u64, u64)) -> (u64, u64) {
+        let (a, b) = *first;
+        let (c, d) = *second;
+        (a.wrapping_mul(c), b.wrapping_mul(c).wrapping_add(d))
+    }
+
+    #[test]
+    fn test_random_operations_match_naive_fold() {
+        let mut window = SlidingWindowAggregator::new(compose);
+        let mut expected: VecDeque<(u64, u64)> = VecDeque::new();
+        let mut state: u64 = 17;
+
+        for _ in 0..2000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            if (state >> 62) == 0 {
+                assert_eq!(window.pop(), expected.pop_front());
+            } else {
+                let item = (state >> 40, state >> 20);
+                window.push(item);
+                expected.push_back(item);
+            }
+            let naive = expected.iter().copied().reduce(|a, b| compose(&a, &b));
+            assert_eq!(window.aggregate(), naive);
+            assert_eq!(window.len(), expected.len());
+        }
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut window = SlidingWindowAggregator::new(|a: &i32, b: &i32| *a.max(b));
+        window.push(1);
+        window.push(2);
+        window.pop();
+
+        window.clear();
+
+        assert!(window.is_empty());
+        assert_eq!(window.aggregate(), None);
+    }
+}
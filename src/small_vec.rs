@@ -0,0 +1,341 @@
+//! Small Vector implementation
+//! Vector that keeps its first `N` elements inline, inside the struct itself,
+//! and only moves them to a heap allocated `Vec` once a push would go past
+//! `N`. Collections that are usually short, like hash map buckets, then
+//! never allocate. Once spilled it stays on the heap until it is dropped.
+//! Derefs to a slice, so slice methods like `iter`, `get` and `contains`
+//! work on it directly.
+
+use std::fmt::{ self, Debug };
+use std::mem::MaybeUninit;
+use std::ops::{ Deref, DerefMut };
+use std::ptr;
+
+use crate::trace::{ self, Event };
+use std::slice;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+enum Storage<T, const N: usize> {
+    /// Elements 0..len of the array are initialized
+    Inline {
+        items: [MaybeUninit<T>; N],
+        len: usize,
+    },
+    Heap(Vec<T>),
+}
+
+pub struct SmallVec<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+impl<T, const N: usize> SmallVec<T, N> {
+    pub const fn new() -> Self {
+        SmallVec {
+            storage: Storage::Inline { items: [const { MaybeUninit::uninit() }; N], len: 0 },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { len, .. } => *len,
+            Storage::Heap(vec) => vec.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of elements it can hold without allocating
+    pub fn capacity(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { .. } => N,
+            Storage::Heap(vec) => vec.capacity(),
+        }
+    }
+
+    /// True once the elements have moved to the heap
+    pub fn spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    pub fn push(&mut self, item: T) {
+        match &mut self.storage {
+            Storage::Inline { items, len } if *len < N => {
+                items[*len].write(item);
+                *len += 1;
+            }
+            _ => {
+                self.spill();
+                if let Storage::Heap(vec) = &mut self.storage {
+                    vec.push(item);
+                }
+            }
+        }
+        check_invariants!(self);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let item = match &mut self.storage {
+            Storage::Inline { items, len } => {
+                if *len == 0 {
+                    return None;
+                }
+                *len -= 1;
+                // SAFETY: The element was initialized and is now outside 0..len
+                Some(unsafe { items[*len].assume_init_read() })
+            }
+            Storage::Heap(vec) => vec.pop(),
+        };
+        check_invariants!(self);
+        item
+    }
+
+    /// Inserts the element at the index, shifting the ones after it right
+    pub fn insert(&mut self, index: usize, item: T) {
+        let len = self.len();
+        assert!(index <= len, "Index {} out of bounds for length {}", index, len);
+        self.push(item);
+        self[index..].rotate_right(1);
+    }
+
+    /// Removes the element at the index, shifting the ones after it left
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(index < len, "Index {} out of bounds for length {}", index, len);
+        self[index..].rotate_left(1);
+        self.pop().unwrap()
+    }
+
+    /// Removes the element at the index by moving the last element into its place
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(index < len, "Index {} out of bounds for length {}", index, len);
+        self.swap(index, len - 1);
+        self.pop().unwrap()
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.storage {
+            // SAFETY: Elements 0..len are initialized
+            Storage::Inline { items, len } => unsafe {
+                slice::from_raw_parts(items.as_ptr() as *const T, *len)
+            },
+            Storage::Heap(vec) => vec,
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.storage {
+            // SAFETY: Elements 0..len are initialized
+            Storage::Inline { items, len } => unsafe {
+                slice::from_raw_parts_mut(items.as_mut_ptr() as *mut T, *len)
+            },
+            Storage::Heap(vec) => vec,
+        }
+    }
+
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.spill();
+        match std::mem::replace(&mut self.storage, Storage::Heap(Vec::new())) {
+            Storage::Heap(vec) => vec,
+            Storage::Inline { .. } => unreachable!(),
+        }
+    }
+
+    /// Moves the inline elements into a vector with room for twice as many
+    fn spill(&mut self) {
+        if let Storage::Inline { items, len } = &mut self.storage {
+            let mut vec = Vec::with_capacity((N * 2).max(4));
+            for item in &items[..*len] {
+                // SAFETY: Elements 0..len are initialized, and setting len to 0
+                // below makes sure each one is only read once
+                vec.push(unsafe { item.assume_init_read() });
+            }
+            *len = 0;
+            trace::emit(|| Event::Spilled { len: vec.len() });
+            self.storage = Storage::Heap(vec);
+        }
+    }
+}
+
+/// Inline storage never counts more elements than the array holds
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, const N: usize> Invariants for SmallVec<T, N> {
+    fn assert_invariants(&self) {
+        if let Storage::Inline { len, .. } = &self.storage {
+            assert!(*len <= N, "Inline length {} is past the {} slots", len, N);
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for SmallVec<T, N> {
+    fn drop(&mut self) {
+        if let Storage::Inline { .. } = self.storage {
+            // SAFETY: The slice covers exactly the initialized elements
+            unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for SmallVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for SmallVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> Self {
+        SmallVec::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SmallVec<T, N> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for SmallVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for SmallVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for SmallVec<T, N> {}
+
+impl<T, const N: usize> FromIterator<T> for SmallVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = SmallVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T, const N: usize> Extend<T> for SmallVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: HeapSize, const N: usize> HeapSize for SmallVec<T, N> {
+    fn heap_size(&self) -> usize {
+        match &self.storage {
+            Storage::Inline { .. } => self.iter().map(HeapSize::heap_size).sum(),
+            Storage::Heap(vec) => vec.heap_size(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn when_vector_is_created_it_is_empty() {
+        let vec = SmallVec::<i32, 4>::new();
+
+        assert!(vec.is_empty());
+        assert!(!vec.spilled());
+        assert_eq!(vec.capacity(), 4);
+    }
+
+    #[test]
+    fn test_push_and_pop_inline() {
+        let mut vec = SmallVec::<i32, 4>::new();
+
+        vec.push(1);
+        vec.push(2);
+        vec.push(3);
+
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        assert!(!vec.spilled());
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.len(), 2);
+    }
+
+    #[test]
+    fn test_push_past_capacity_spills_to_heap() {
+        let mut vec = SmallVec::<i32, 2>::new();
+
+        vec.extend(0..5);
+
+        assert!(vec.spilled());
+        assert_eq!(vec.as_slice(), &[0, 1, 2, 3, 4]);
+        assert_eq!(vec.iter().sum::<i32>(), 10);
+        assert_eq!(vec.into_vec(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_insert_and_remove_shift_elements() {
+        let mut vec: SmallVec<char, 3> = "ac".chars().collect();
+
+        vec.insert(1, 'b');
+        vec.insert(3, 'd');
+        assert_eq!(vec.as_slice(), &['a', 'b', 'c', 'd']);
+
+        assert_eq!(vec.remove(0), 'a');
+        assert_eq!(vec.swap_remove(0), 'b');
+        assert_eq!(vec.as_slice(), &['d', 'c']);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 1 out of bounds for length 1")]
+    fn test_remove_out_of_bounds_panics() {
+        let mut vec: SmallVec<i32, 2> = std::iter::once(1).collect();
+        vec.remove(1);
+    }
+
+    #[test]
+    fn test_every_element_is_dropped_once() {
+        let counter = Rc::new(());
+        let mut inline: SmallVec<Rc<()>, 4> = (0..3).map(|_| counter.clone()).collect();
+        let spilled: SmallVec<Rc<()>, 2> = (0..3).map(|_| counter.clone()).collect();
+        assert_eq!(Rc::strong_count(&counter), 7);
+
+        drop(inline.pop());
+        assert_eq!(Rc::strong_count(&counter), 6);
+        let copy = spilled.clone();
+        drop(inline);
+        drop(spilled);
+        assert_eq!(Rc::strong_count(&counter), 4);
+        drop(copy);
+
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+}
@@ -0,0 +1,282 @@
+//! Sorted Vector implementation
+//! Vector that is always kept in ascending order. The position of a new
+//! item is found by binary search and the items after it are shifted, so
+//! inserting is O(n) but lookups are O(log n) and a range is a plain slice.
+//! For small collections the contiguous memory usually beats a tree.
+//! In deduping mode every value is stored at most once.
+
+use std::ops::{ Bound, RangeBounds };
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedVec<T> {
+    items: Vec<T>,
+    dedup: bool,
+}
+
+impl<T: Ord> SortedVec<T> {
+    /// Creates a vector that keeps duplicates
+    pub fn new() -> Self {
+        SortedVec { items: vec![], dedup: false }
+    }
+
+    /// Creates a vector that ignores inserts of values it already holds
+    pub fn new_dedup() -> Self {
+        SortedVec { items: vec![], dedup: true }
+    }
+
+    /// Sorts the items in O(n log n), removing duplicates in deduping mode
+    pub fn from_vec(mut items: Vec<T>, dedup: bool) -> Self {
+        items.sort();
+        if dedup {
+            items.dedup();
+        }
+        let sorted = SortedVec { items, dedup };
+        check_invariants!(&sorted);
+        sorted
+    }
+
+    pub fn is_dedup(&self) -> bool {
+        self.dedup
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        check_invariants!(self);
+    }
+
+    /// Inserts the item after all equal ones and returns its index.
+    /// In deduping mode an existing value is not inserted again and None is returned.
+    pub fn insert(&mut self, item: T) -> Option<usize> {
+        let index = self.items.partition_point(|existing| *existing <= item);
+        if self.dedup && index > 0 && self.items[index - 1] == item {
+            return None;
+        }
+        self.items.insert(index, item);
+        check_invariants!(self);
+        Some(index)
+    }
+
+    /// Removes one item equal to `item`. Returns false if there was none.
+    pub fn remove(&mut self, item: &T) -> bool {
+        match self.items.binary_search(item) {
+            Ok(index) => {
+                self.items.remove(index);
+                check_invariants!(self);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> T {
+        let item = self.items.remove(index);
+        check_invariants!(self);
+        item
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.binary_search(item).is_ok()
+    }
+
+    /// Index of the first item equal to `item`
+    pub fn position(&self, item: &T) -> Option<usize> {
+        let index = self.items.partition_point(|existing| existing < item);
+        (self.items.get(index) == Some(item)).then_some(index)
+    }
+
+    /// Number of items equal to `item`
+    pub fn count(&self, item: &T) -> usize {
+        self.range(item..=item).len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.items.last()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Items inside the value range, found with two binary searches
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> &[T] {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.items.partition_point(|item| item < start),
+            Bound::Excluded(start) => self.items.partition_point(|item| item <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.items.partition_point(|item| item <= end),
+            Bound::Excluded(end) => self.items.partition_point(|item| item < end),
+            Bound::Unbounded => self.items.len(),
+        };
+        &self.items[start..end.max(start)]
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T: Ord> Default for SortedVec<T> {
+    fn default() -> Self {
+        SortedVec::new()
+    }
+}
+
+impl<T: Ord> Collection for SortedVec<T> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        SortedVec::len(self)
+    }
+
+    fn clear(&mut self) {
+        SortedVec::clear(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        SortedVec::contains(self, item)
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        SortedVec::from_vec(iter.into_iter().collect(), false)
+    }
+}
+
+impl<T: HeapSize> HeapSize for SortedVec<T> {
+    fn heap_size(&self) -> usize {
+        self.items.heap_size()
+    }
+}
+
+/// Checks the items are ascending, strictly in deduping mode
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T: Ord> Invariants for SortedVec<T> {
+    fn assert_invariants(&self) {
+        for pair in self.items.windows(2) {
+            assert!(pair[0] <= pair[1], "Items are out of order");
+            assert!(!self.dedup || pair[0] != pair[1], "Deduping vector holds a duplicate");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_vec_is_created_it_is_empty() {
+        let items = SortedVec::<i32>::new();
+
+        assert!(items.is_empty());
+        assert!(!items.is_dedup());
+        assert_eq!(items.first(), None);
+    }
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut items = SortedVec::new();
+
+        for item in [5, 1, 4, 1, 3] {
+            items.insert(item);
+        }
+
+        assert_eq!(items.as_slice(), &[1, 1, 3, 4, 5]);
+        assert_eq!(items.insert(2), Some(2));
+        assert_eq!(items.insert(9), Some(6));
+    }
+
+    #[test]
+    fn test_equal_items_keep_insertion_order() {
+        let mut items = SortedVec::new();
+
+        items.insert((1, "first"));
+        items.insert((0, "zero"));
+        items.insert((1, "second"));
+
+        let order: Vec<&str> = items.iter().map(|item| item.1).collect();
+        assert_eq!(order, vec!["zero", "first", "second"]);
+    }
+
+    #[test]
+    fn test_dedup_mode_ignores_duplicates() {
+        let mut items = SortedVec::new_dedup();
+
+        assert_eq!(items.insert("b"), Some(0));
+        assert_eq!(items.insert("a"), Some(0));
+        assert_eq!(items.insert("b"), None);
+
+        assert_eq!(items.as_slice(), &["a", "b"]);
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let items = SortedVec::from_vec(vec![3, 1, 3, 2], true);
+        let collected: SortedVec<i32> = vec![3, 1, 3, 2].into_iter().collect();
+
+        assert_eq!(items.into_vec(), vec![1, 2, 3]);
+        assert_eq!(collected.as_slice(), &[1, 2, 3, 3]);
+    }
+
+    #[test]
+    fn test_contains_position_and_count() {
+        let items: SortedVec<i32> = vec![7, 2, 7, 7, 9].into_iter().collect();
+
+        assert!(items.contains(&9));
+        assert!(!items.contains(&8));
+        assert_eq!(items.position(&7), Some(1));
+        assert_eq!(items.position(&3), None);
+        assert_eq!(items.count(&7), 3);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut items: SortedVec<i32> = vec![1, 2, 2, 3].into_iter().collect();
+
+        assert!(items.remove(&2));
+        assert!(!items.remove(&5));
+        assert_eq!(items.remove_at(0), 1);
+
+        assert_eq!(items.as_slice(), &[2, 3]);
+    }
+
+    #[test]
+    fn test_range_slices() {
+        let items: SortedVec<i32> = (0..10).map(|i| i * 10).collect();
+
+        assert_eq!(items.range(15..45), &[20, 30, 40]);
+        assert_eq!(items.range(20..=40), &[20, 30, 40]);
+        assert_eq!(items.range(..20), &[0, 10]);
+        assert_eq!(items.range(85..), &[90]);
+        assert!(items.range(41..45).is_empty());
+        assert!(items.range((Bound::Excluded(50), Bound::Excluded(50))).is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! Sorting algorithms
+//! Insertion, merge, quick and heap sort over slices, all ascending. Insertion
+//! and quick sort work in place by swapping; merge and heap sort need
+//! `Clone`, merge sort for its buffer and heap sort because the crate's
+//! `BinaryHeap` owns the items it sorts. Only insertion and merge sort are
+//! stable. The list and the queue aren't slices, so `sort_list` and
+//! `sort_queue` move their items into a vector, run the given sort on it and
+//! put them back in the new order.
+
+use std::fmt::{ Debug, Display };
+
+use crate::binary_heap::{ BinaryHeap, HeapOrder };
+use crate::queue::Queue;
+use crate::singly_linked_list::SinglyLinkedList;
+
+/// Slices this short are left to insertion sort by quick sort
+const INSERTION_SORT_THRESHOLD: usize = 16;
+
+/// O(n^2), but the fastest on short or nearly sorted slices
+pub fn insertion_sort<T: Ord>(items: &mut [T]) {
+    for sorted in 1..items.len() {
+        let mut index = sorted;
+        while index > 0 && items[index - 1] > items[index] {
+            items.swap(index - 1, index);
+            index -= 1;
+        }
+    }
+}
+
+/// Top-down merge sort. O(n log n) always, with a buffer of n items
+pub fn merge_sort<T: Ord + Clone>(items: &mut [T]) {
+    let mut buffer = items.to_vec();
+    merge_sort_into(&mut buffer, items);
+}
+
+/// Sorts `source` into `target`, the two hold the same items on entry
+fn merge_sort_into<T: Ord + Clone>(source: &mut [T], target: &mut [T]) {
+    if target.len() <= 1 {
+        return;
+    }
+    let middle = target.len() / 2;
+    // The halves of `target` are sorted using `source` as the buffer,
+    // then merged back into `target`
+    merge_sort_into(&mut target[..middle], &mut source[..middle]);
+    merge_sort_into(&mut target[middle..], &mut source[middle..]);
+    let (left, right) = source.split_at(middle);
+    let (mut i, mut j) = (0, 0);
+    for slot in target.iter_mut() {
+        // `<=` takes from the left on ties, which keeps the sort stable
+        if j == right.len() || (i < left.len() && left[i] <= right[j]) {
+            *slot = left[i].clone();
+            i += 1;
+        } else {
+            *slot = right[j].clone();
+            j += 1;
+        }
+    }
+}
+
+/// O(n log n) on average. The pivot is the median of the first, middle and
+/// last item, so sorted input isn't the worst case. Recursing on the smaller
+/// side only keeps the stack at O(log n).
+pub fn quick_sort<T: Ord>(mut items: &mut [T]) {
+    while items.len() > INSERTION_SORT_THRESHOLD {
+        let pivot = partition(items);
+        let (left, right) = items.split_at_mut(pivot);
+        let right = &mut right[1..];
+        if left.len() < right.len() {
+            quick_sort(left);
+            items = right;
+        } else {
+            quick_sort(right);
+            items = left;
+        }
+    }
+    insertion_sort(items);
+}
+
+/// Lomuto partition, returns where the pivot ends up
+fn partition<T: Ord>(items: &mut [T]) -> usize {
+    let last = items.len() - 1;
+    let middle = last / 2;
+    if items[middle] < items[0] {
+        items.swap(middle, 0);
+    }
+    if items[last] < items[0] {
+        items.swap(last, 0);
+    }
+    if items[middle] < items[last] {
+        items.swap(middle, last);
+    }
+    let mut store = 0;
+    for index in 0..last {
+        if items[index] < items[last] {
+            items.swap(index, store);
+            store += 1;
+        }
+    }
+    items.swap(store, last);
+    store
+}
+
+/// O(n log n) always, by building a max `BinaryHeap` and draining it
+pub fn heap_sort<T: Ord + Clone>(items: &mut [T]) {
+    let heap = BinaryHeap::from_vec(items.to_vec(), HeapOrder::Max);
+    items.clone_from_slice(&heap.into_sorted_vec());
+}
+
+/// Sorts the list with `sort`, e.g. `sort_list(&mut list, merge_sort)`
+pub fn sort_list<T>(list: &mut SinglyLinkedList<T>, sort: impl FnOnce(&mut [T]))
+where
+    T: PartialEq + Debug + Clone + Display,
+{
+    let mut items = Vec::from(std::mem::take(list));
+    sort(&mut items);
+    *list = SinglyLinkedList::from(items);
+}
+
+/// Sorts the queue with `sort`, the smallest item ends up at the front
+pub fn sort_queue<T: Copy>(queue: &mut Queue<T>, sort: impl FnOnce(&mut [T])) {
+    let mut items = Vec::from(std::mem::take(queue));
+    sort(&mut items);
+    *queue = Queue::from(items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> Vec<Vec<i32>> {
+        vec![
+            vec![],
+            vec![1],
+            vec![2, 1],
+            (0..100).collect(),
+            (0..100).rev().collect(),
+            (0..200).map(|item| (item * 37) % 11).collect(),
+            (0..300).map(|item| (item * 7919) % 1009 - 500).collect()
+        ]
+    }
+
+    fn check(sort: fn(&mut [i32])) {
+        for mut items in inputs() {
+            let mut expected = items.clone();
+            expected.sort();
+
+            sort(&mut items);
+
+            assert_eq!(items, expected);
+        }
+    }
+
+    #[test]
+    fn test_insertion_sort() {
+        check(insertion_sort);
+    }
+
+    #[test]
+    fn test_merge_sort() {
+        check(merge_sort);
+    }
+
+    #[test]
+    fn test_quick_sort() {
+        check(quick_sort);
+    }
+
+    #[test]
+    fn test_heap_sort() {
+        check(heap_sort);
+    }
+
+    #[test]
+    fn test_stable_sorts_keep_equal_items_in_order() {
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        struct Entry(i32, char);
+        impl PartialOrd for Entry {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Entry {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+        let items = vec![Entry(2, 'a'), Entry(1, 'b'), Entry(2, 'c'), Entry(1, 'd')];
+        let expected = vec![Entry(1, 'b'), Entry(1, 'd'), Entry(2, 'a'), Entry(2, 'c')];
+
+        let mut merged = items.clone();
+        merge_sort(&mut merged);
+        let mut inserted = items;
+        insertion_sort(&mut inserted);
+
+        assert_eq!(merged, expected);
+        assert_eq!(inserted, expected);
+    }
+
+    #[test]
+    fn test_list_and_queue_adapters() {
+        let mut list = SinglyLinkedList::from(vec![3, 1, 2]);
+        let mut queue = Queue::from(vec![3, 1, 2]);
+
+        sort_list(&mut list, quick_sort);
+        sort_queue(&mut queue, heap_sort);
+
+        assert_eq!(Vec::from(list), vec![1, 2, 3]);
+        assert_eq!(queue.remove(), Some(1));
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Sparse Matrix in compressed sparse row (CSR) format
+//! Only non-zero cells are stored, row after row. `column_indices` and
+//! `values` hold the column and value of every stored cell, and row `r`
+//! owns the cells in `row_offsets[r]..row_offsets[r + 1]`, sorted by column.
+//! Memory is O(rows + non-zeros), and multiplying by a vector touches every
+//! stored cell once, so it is far cheaper than the dense `Matrix` when most
+//! cells are zero.
+
+use std::ops::{ Add, Mul };
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+use crate::matrix::Matrix;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<T> {
+    rows: usize,
+    columns: usize,
+    row_offsets: Vec<usize>,
+    column_indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: Copy + Default + Add<Output = T>> SparseMatrix<T> {
+    /// Builds the matrix from (row, column, value) triplets in any order.
+    /// Values of repeated positions are added up.
+    pub fn from_triplets(
+        rows: usize,
+        columns: usize,
+        mut triplets: Vec<(usize, usize, T)>
+    ) -> Self {
+        for &(row, column, _) in &triplets {
+            assert!(
+                row < rows && column < columns,
+                "Index ({}, {}) out of bounds for {}x{} matrix",
+                row,
+                column,
+                rows,
+                columns
+            );
+        }
+        triplets.sort_by_key(|&(row, column, _)| (row, column));
+
+        let mut row_offsets = vec![0; rows + 1];
+        let mut column_indices: Vec<usize> = vec![];
+        let mut values: Vec<T> = vec![];
+        let mut last = None;
+        for (row, column, value) in triplets {
+            if last == Some((row, column)) {
+                let sum = *values.last().unwrap() + value;
+                *values.last_mut().unwrap() = sum;
+                continue;
+            }
+            last = Some((row, column));
+            row_offsets[row + 1] += 1;
+            column_indices.push(column);
+            values.push(value);
+        }
+        // Counts per row become the start of every row
+        for row in 0..rows {
+            row_offsets[row + 1] += row_offsets[row];
+        }
+        let matrix = SparseMatrix { rows, columns, row_offsets, column_indices, values };
+        check_invariants!(&matrix);
+        matrix
+    }
+
+    /// Stores every cell of the dense matrix that isn't the default value
+    pub fn from_dense(matrix: &Matrix<T>) -> Self
+    where
+        T: PartialEq,
+    {
+        let triplets = (0..matrix.rows())
+            .flat_map(|row| (0..matrix.columns()).map(move |column| (row, column)))
+            .map(|(row, column)| (row, column, matrix[(row, column)]))
+            .filter(|&(_, _, value)| value != T::default())
+            .collect();
+        SparseMatrix::from_triplets(matrix.rows(), matrix.columns(), triplets)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of stored cells
+    pub fn non_zero_count(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Value of a cell, found by binary search in its row.
+    /// Cells that are not stored are the default value.
+    pub fn get(&self, row: usize, column: usize) -> T {
+        assert!(
+            row < self.rows && column < self.columns,
+            "Index ({}, {}) out of bounds for {}x{} matrix",
+            row,
+            column,
+            self.rows,
+            self.columns
+        );
+        let range = self.row_offsets[row]..self.row_offsets[row + 1];
+        match self.column_indices[range.clone()].binary_search(&column) {
+            Ok(position) => self.values[range.start + position],
+            Err(_) => T::default(),
+        }
+    }
+
+    /// Stored cells of one row as (column, value) pairs, by column
+    pub fn row(&self, row: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        assert!(row < self.rows, "Row {} out of bounds for {} rows", row, self.rows);
+        let range = self.row_offsets[row]..self.row_offsets[row + 1];
+        let columns = self.column_indices[range.clone()].iter().copied();
+        columns.zip(self.values[range].iter().copied())
+    }
+
+    /// All stored cells as (row, column, value) triplets in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        (0..self.rows)
+            .flat_map(move |row| self.row(row).map(move |(column, value)| (row, column, value)))
+    }
+
+    /// Sparse matrix-vector product in O(rows + non-zeros)
+    pub fn mul_vector(&self, vector: &[T]) -> Vec<T>
+    where
+        T: Mul<Output = T>,
+    {
+        assert_eq!(
+            vector.len(),
+            self.columns,
+            "A {}x{} matrix needs a vector of length {}",
+            self.rows,
+            self.columns,
+            self.columns
+        );
+        (0..self.rows)
+            .map(|row| {
+                self.row(row)
+                    .fold(T::default(), |sum, (column, value)| sum + value * vector[column])
+            })
+            .collect()
+    }
+
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut matrix = Matrix::new(self.rows, self.columns, T::default());
+        for (row, column, value) in self.iter() {
+            matrix[(row, column)] = value;
+        }
+        matrix
+    }
+}
+
+/// Checks the row offsets cover the stored cells in order, and every row
+/// holds distinct columns inside the matrix in ascending order
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for SparseMatrix<T> {
+    fn assert_invariants(&self) {
+        assert_eq!(self.row_offsets.len(), self.rows + 1, "Not one offset per row plus one");
+        assert_eq!(self.row_offsets[0], 0, "First row doesn't start at 0");
+        let stored = self.column_indices.len();
+        assert_eq!(self.values.len(), stored, "Columns and values have different lengths");
+        assert_eq!(self.row_offsets[self.rows], stored, "Last row doesn't end at the last cell");
+        for bounds in self.row_offsets.windows(2) {
+            assert!(bounds[0] <= bounds[1], "Row ends before it starts");
+            let row = &self.column_indices[bounds[0]..bounds[1]];
+            assert!(row.windows(2).all(|pair| pair[0] < pair[1]), "Columns out of order");
+            assert!(row.iter().all(|&column| column < self.columns), "Column out of bounds");
+        }
+    }
+}
+
+impl<T: HeapSize> HeapSize for SparseMatrix<T> {
+    fn heap_size(&self) -> usize {
+        self.row_offsets.heap_size() + self.column_indices.heap_size() + self.values.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 0 2
+    /// 0 0 3
+    /// 4 5 0
+    fn example() -> SparseMatrix<i32> {
+        let triplets = vec![(2, 1, 5), (0, 2, 2), (1, 2, 3), (0, 0, 1), (2, 0, 4)];
+        SparseMatrix::from_triplets(3, 3, triplets)
+    }
+
+    #[test]
+    fn when_matrix_has_no_triplets_every_cell_is_zero() {
+        let matrix = SparseMatrix::<i32>::from_triplets(2, 3, vec![]);
+
+        assert_eq!(matrix.non_zero_count(), 0);
+        assert_eq!(matrix.get(1, 2), 0);
+        assert_eq!(matrix.mul_vector(&[1, 2, 3]), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_layout_is_compressed_by_row() {
+        let matrix = example();
+
+        assert_eq!(matrix.row_offsets, vec![0, 2, 3, 5]);
+        assert_eq!(matrix.column_indices, vec![0, 2, 2, 0, 1]);
+        assert_eq!(matrix.values, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_get_and_row() {
+        let matrix = example();
+
+        assert_eq!(matrix.get(2, 1), 5);
+        assert_eq!(matrix.get(1, 0), 0);
+        assert_eq!(matrix.row(0).collect::<Vec<_>>(), vec![(0, 1), (2, 2)]);
+        assert_eq!(matrix.row(1).count(), 1);
+    }
+
+    #[test]
+    fn test_repeated_positions_are_added() {
+        let matrix = SparseMatrix::from_triplets(1, 2, vec![(0, 1, 2.5), (0, 1, 1.5)]);
+
+        assert_eq!(matrix.non_zero_count(), 1);
+        assert_eq!(matrix.get(0, 1), 4.0);
+    }
+
+    #[test]
+    fn test_mul_vector_matches_dense() {
+        let matrix = example();
+        let vector = [1, -2, 3];
+
+        let dense = matrix.to_dense();
+        let expected: Vec<i32> = dense
+            .iter_rows()
+            .map(|row| row.iter().zip(&vector).map(|(a, b)| a * b).sum())
+            .collect();
+
+        assert_eq!(matrix.mul_vector(&vector), expected);
+        assert_eq!(expected, vec![7, 9, -6]);
+    }
+
+    #[test]
+    fn test_dense_round_trip() {
+        let dense = Matrix::from_fn(4, 5, |row, column| (row + column).is_multiple_of(3) as i32);
+
+        let sparse = SparseMatrix::from_dense(&dense);
+
+        assert_eq!(sparse.non_zero_count(), 7);
+        assert_eq!(sparse.to_dense(), dense);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index (3, 0) out of bounds for 3x3 matrix")]
+    fn test_triplet_out_of_bounds_panics() {
+        SparseMatrix::from_triplets(3, 3, vec![(3, 0, 1)]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Sparse Table for static range queries
+//! Row k stores the answer for every range of length 2^k, built in
+//! O(n log n) from two ranges of row k - 1. Any range is covered by two
+//! (possibly overlapping) ranges of the same power of two length, so for
+//! idempotent operations like min, max and gcd a query is O(1).
+//! Items can't change after construction.
+
+use std::ops::Range;
+
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+pub struct SparseTable<T> {
+    /// `table[k][i]` is the answer for `i..i + 2^k`
+    table: Vec<Vec<T>>,
+    operation: fn(&T, &T) -> T,
+}
+
+impl<T: Clone> SparseTable<T> {
+    /// Builds the table for an idempotent operation, which means
+    /// `operation(x, x) == x`. Otherwise overlapping ranges give wrong answers.
+    pub fn new(items: &[T], operation: fn(&T, &T) -> T) -> Self {
+        let mut table = vec![items.to_vec()];
+        let mut length = 1;
+        while 2 * length <= items.len() {
+            let previous = table.last().unwrap();
+            let row = (0..=items.len() - 2 * length)
+                .map(|start| operation(&previous[start], &previous[start + length]))
+                .collect();
+            table.push(row);
+            length *= 2;
+        }
+        let table = SparseTable { table, operation };
+        check_invariants!(&table);
+        table
+    }
+
+    pub fn len(&self) -> usize {
+        self.table[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Answer for the items in the range in O(1). None for an empty range.
+    pub fn query(&self, range: Range<usize>) -> Option<T> {
+        assert!(
+            range.end <= self.len(),
+            "Range end {} out of bounds for length {}",
+            range.end,
+            self.len()
+        );
+        if range.start >= range.end {
+            return None;
+        }
+        let level = (range.end - range.start).ilog2() as usize;
+        let row = &self.table[level];
+        Some((self.operation)(&row[range.start], &row[range.end - (1 << level)]))
+    }
+}
+
+impl<T: Ord + Clone> SparseTable<T> {
+    pub fn min(items: &[T]) -> Self {
+        Self::new(items, |a, b| a.min(b).clone())
+    }
+
+    pub fn max(items: &[T]) -> Self {
+        Self::new(items, |a, b| a.max(b).clone())
+    }
+}
+
+impl SparseTable<u64> {
+    pub fn gcd(items: &[u64]) -> Self {
+        Self::new(items, |&a, &b| gcd(a, b))
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Checks row k has an answer for every start of a range of length 2^k,
+/// and there is a row for every power of two up to the length
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T> Invariants for SparseTable<T> {
+    fn assert_invariants(&self) {
+        let len = self.table[0].len();
+        let rows = if len == 0 { 1 } else { len.ilog2() as usize + 1 };
+        assert_eq!(self.table.len(), rows, "Wrong number of rows");
+        for (level, row) in self.table.iter().enumerate() {
+            assert_eq!(row.len(), len + 1 - (1 << level), "Row has the wrong length");
+        }
+    }
+}
+
+impl<T: HeapSize> HeapSize for SparseTable<T> {
+    fn heap_size(&self) -> usize {
+        self.table.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<i32> {
+        vec![5, 2, 8, 6, 3, 7, 1, 9, 4]
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let table = SparseTable::<i32>::min(&[]);
+
+        assert!(table.is_empty());
+        assert_eq!(table.query(0..0), None);
+    }
+
+    #[test]
+    fn test_table_has_log_levels() {
+        let table = SparseTable::min(&sample());
+
+        assert_eq!(table.table.len(), 4, "Lengths 1, 2, 4 and 8 fit in 9 items");
+        assert_eq!(table.table[3], vec![1, 1]);
+    }
+
+    #[test]
+    fn test_min_queries() {
+        let table = SparseTable::min(&sample());
+
+        assert_eq!(table.query(0..2), Some(2));
+        assert_eq!(table.query(2..6), Some(3));
+        assert_eq!(table.query(0..9), Some(1));
+        assert_eq!(table.query(7..8), Some(9));
+        assert_eq!(table.query(4..4), None);
+    }
+
+    #[test]
+    fn test_max_queries() {
+        let table = SparseTable::max(&sample());
+
+        assert_eq!(table.query(0..3), Some(8));
+        assert_eq!(table.query(3..7), Some(7));
+        assert_eq!(table.query(0..9), Some(9));
+    }
+
+    #[test]
+    fn test_gcd_queries() {
+        let table = SparseTable::gcd(&[12, 18, 24, 36, 7, 14]);
+
+        assert_eq!(table.query(0..4), Some(6));
+        assert_eq!(table.query(1..3), Some(6));
+        assert_eq!(table.query(3..6), Some(1));
+        assert_eq!(table.query(4..6), Some(7));
+    }
+
+    #[test]
+    fn test_every_range_matches_brute_force() {
+        let items: Vec<u32> = (0..40).map(|i| (i * 29 + 7) % 23).collect();
+        let min_table = SparseTable::min(&items);
+        let max_table = SparseTable::max(&items);
+
+        for start in 0..items.len() {
+            for end in start + 1..=items.len() {
+                assert_eq!(min_table.query(start..end), items[start..end].iter().min().copied());
+                assert_eq!(max_table.query(start..end), items[start..end].iter().max().copied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_operation_on_strings() {
+        let words = ["pear", "apple", "fig", "kiwi"];
+        let table = SparseTable::new(&words, |a, b| if a.len() >= b.len() { *a } else { *b });
+
+        assert_eq!(table.query(0..4), Some("apple"));
+        assert_eq!(table.query(2..4), Some("kiwi"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Range end 10 out of bounds for length 9")]
+    fn test_query_out_of_bounds_panics() {
+        SparseTable::min(&sample()).query(0..10);
+    }
+}
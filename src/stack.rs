@@ -1,43 +1,83 @@
-/// Stack implementation
-/// Uses LIFO (last-in first-out) ordering.
-/// The most recently added is the first item to be removed.
-
-pub struct Stack<T> {
+//! Stack implementation
+//! Uses LIFO (last-in first-out) ordering.
+//! The most recently added is the first item to be removed.
+//! Room for more items is made by the growth policy, doubling by default.
+
+use crate::collection::Collection;
+use crate::error::{ Error, Result };
+use crate::growth::{ Doubling, GrowthPolicy };
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+pub struct Stack<T, G: GrowthPolicy = Doubling> {
     data: Vec<T>,
     pointer_to_top: i32,
     size: u32,
+    capacity: usize,
+    growth: G,
 }
 
-#[allow(dead_code)]
 impl<T> Stack<T> {
-    fn new() -> Self {
-        Stack { data: vec![], pointer_to_top: -1, size: 0 }
+    pub fn new() -> Self {
+        Stack::with_growth(Doubling)
+    }
+}
+
+impl<T, G: GrowthPolicy> Stack<T, G> {
+    /// Empty stack that makes room as `growth` says once it is full
+    pub fn with_growth(growth: G) -> Self {
+        Stack { data: vec![], pointer_to_top: -1, size: 0, capacity: 0, growth }
     }
 
     /// Returns true if and only if the stack is empty
-    fn is_empty(&self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
 
     /// Add an item on top of the stack
     /// When stack is full, then it is said to be
-    /// an Overflow condition.
-    fn push(&mut self, item: T) {
+    /// an Overflow condition. Panics if the growth policy
+    /// can't make room for it.
+    pub fn push(&mut self, item: T) {
+        if self.try_push(item).is_err() {
+            panic!("stack overflow: the growth policy refused to grow");
+        }
+    }
+
+    /// Like `push`, but a stack that can't grow any more is an error
+    pub fn try_push(&mut self, item: T) -> Result<()> {
+        if self.is_full() {
+            self.capacity = self.growth.grow(self.capacity).ok_or(Error::CapacityExceeded)?;
+            self.data.reserve_exact(self.capacity - self.data.len());
+        }
         self.pointer_to_top += 1;
         self.data.insert(self.pointer_to_top as usize, item);
         self.size += 1;
+        check_invariants!(self);
+        Ok(())
+    }
+
+    /// Items the stack holds before it has to grow
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.data.len() == self.capacity
     }
 
     /// Return the top of the stack, but doesn't remove it
     /// from the stack
-    fn peek(&self) -> Option<&T> {
+    pub fn peek(&self) -> Option<&T> {
         self.data.last()
     }
 
     /// Remove the top item from the stack
     /// Removed in reverse order as pushed.
     /// If the stack is empty, it is an Underflow condition.
-    fn pop(&mut self) -> Option<T> {
+    pub fn pop(&mut self) -> Option<T> {
         if self.pointer_to_top.is_negative() {
             return None;
         }
@@ -45,13 +85,71 @@ impl<T> Stack<T> {
 
         let result = Some(self.data.remove(self.pointer_to_top as usize));
         self.pointer_to_top -= 1;
+        check_invariants!(self);
         result
     }
+
+    /// Items from the bottom to the top of the stack
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Like `peek`, but an empty stack is an error
+    pub fn try_peek(&self) -> Result<&T> {
+        self.peek().ok_or(Error::EmptyCollection)
+    }
+
+    /// Like `pop`, but an empty stack is an error
+    pub fn try_pop(&mut self) -> Result<T> {
+        self.pop().ok_or(Error::EmptyCollection)
+    }
+}
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Stack::new()
+    }
+}
+
+impl<T: PartialEq, G: GrowthPolicy> Collection for Stack<T, G> {
+    type Item = T;
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.pointer_to_top = -1;
+        self.size = 0;
+        check_invariants!(self);
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.data.contains(item)
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl<T, G: GrowthPolicy> Invariants for Stack<T, G> {
+    fn assert_invariants(&self) {
+        let len = self.data.len();
+        assert_eq!(self.size as usize, len, "size doesn't match the items");
+        assert_eq!(self.pointer_to_top, len as i32 - 1, "pointer_to_top isn't the last item");
+        assert!(len <= self.capacity, "{} items overflow a capacity of {}", len, self.capacity);
+    }
+}
+
+impl<T: HeapSize, G: GrowthPolicy> HeapSize for Stack<T, G> {
+    fn heap_size(&self) -> usize {
+        self.data.heap_size()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::growth::{ Bounded, FixedIncrement };
 
     #[test]
     fn when_stack_is_created_it_is_empty() {
@@ -165,4 +263,43 @@ mod test {
         assert!(stack.is_empty());
         assert_eq!(stack.size, 0);
     }
+
+    #[test]
+    fn test_try_pop_and_try_peek() {
+        let mut stack = Stack::new();
+        stack.push("A");
+
+        assert_eq!(stack.try_peek(), Ok(&"A"));
+        assert_eq!(stack.try_pop(), Ok("A"));
+        assert_eq!(stack.try_pop(), Err(Error::EmptyCollection));
+        assert_eq!(stack.try_peek(), Err(Error::EmptyCollection));
+    }
+
+    #[test]
+    fn test_capacity_follows_the_growth_policy() {
+        let mut doubling = Stack::new();
+        let mut fixed = Stack::with_growth(FixedIncrement(3));
+
+        for item in 0..5 {
+            doubling.push(item);
+            fixed.push(item);
+        }
+
+        assert_eq!((doubling.capacity(), doubling.data.capacity()), (8, 8));
+        assert_eq!((fixed.capacity(), fixed.data.capacity()), (6, 6));
+    }
+
+    #[test]
+    fn when_bounded_stack_is_full_push_fails() {
+        let mut stack = Stack::with_growth(Bounded::new(Doubling, 3));
+        for item in 0..3 {
+            stack.push(item);
+        }
+
+        assert!(stack.is_full());
+        assert_eq!(stack.try_push(3), Err(Error::CapacityExceeded));
+        assert_eq!(stack.pop(), Some(2));
+        assert_eq!(stack.try_push(3), Ok(()));
+        assert_eq!(stack.size, 3);
+    }
 }
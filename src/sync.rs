@@ -0,0 +1,271 @@
+//! Thread-safe wrappers
+//! `SyncStack` and `SyncQueue` put the crate's stack and queue behind a
+//! `Mutex`, and `SyncHashMap` puts the hash map behind an `RwLock` so lookups
+//! can run side by side. Every method takes `&self`, so a wrapper can be
+//! shared with an `Arc`. Each call takes the lock once; `with`, `read` and
+//! `write` hold it for a whole closure when several steps must not be
+//! interleaved with other threads. A thread that panics while holding the
+//! lock poisons it, the wrappers ignore that and keep using the data, since
+//! every operation leaves the structure valid between its own steps.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{ Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard };
+
+use crate::collection::Collection;
+use crate::hash_map::HashMap;
+use crate::queue::Queue;
+use crate::stack::Stack;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub struct SyncStack<T> {
+    stack: Mutex<Stack<T>>,
+}
+
+impl<T> SyncStack<T> {
+    pub fn new() -> Self {
+        SyncStack { stack: Mutex::new(Stack::new()) }
+    }
+
+    pub fn push(&self, item: T) {
+        lock(&self.stack).push(item);
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        lock(&self.stack).pop()
+    }
+
+    /// A copy of the top item, the stack may change as soon as it is returned
+    pub fn peek(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        lock(&self.stack).peek().cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        lock(&self.stack).iter().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        lock(&self.stack).is_empty()
+    }
+
+    /// Runs `f` on the stack with the lock held throughout
+    pub fn with<R>(&self, f: impl FnOnce(&mut Stack<T>) -> R) -> R {
+        f(&mut lock(&self.stack))
+    }
+
+    pub fn into_inner(self) -> Stack<T> {
+        self.stack.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T> Default for SyncStack<T> {
+    fn default() -> Self {
+        SyncStack::new()
+    }
+}
+
+pub struct SyncQueue<T> {
+    queue: Mutex<Queue<T>>,
+}
+
+impl<T: Copy> SyncQueue<T> {
+    pub fn new() -> Self {
+        SyncQueue { queue: Mutex::new(Queue::new()) }
+    }
+
+    pub fn add(&self, item: T) {
+        lock(&self.queue).add(item);
+    }
+
+    pub fn remove(&self) -> Option<T> {
+        lock(&self.queue).remove()
+    }
+
+    pub fn peek(&self) -> Option<T> {
+        lock(&self.queue).peek()
+    }
+
+    pub fn len(&self) -> usize {
+        lock(&self.queue).iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        lock(&self.queue).is_empty()
+    }
+
+    /// Runs `f` on the queue with the lock held throughout
+    pub fn with<R>(&self, f: impl FnOnce(&mut Queue<T>) -> R) -> R {
+        f(&mut lock(&self.queue))
+    }
+
+    pub fn into_inner(self) -> Queue<T> {
+        self.queue.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T: Copy> Default for SyncQueue<T> {
+    fn default() -> Self {
+        SyncQueue::new()
+    }
+}
+
+pub struct SyncHashMap<K, V> {
+    map: RwLock<HashMap<K, V>>,
+}
+
+impl<K: Hash + Clone + PartialEq + Debug, V: Clone + Debug> SyncHashMap<K, V> {
+    pub fn new() -> Self {
+        SyncHashMap { map: RwLock::new(HashMap::new()) }
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        write(&self.map).insert(key, value)
+    }
+
+    /// A copy of the value, other threads only wait while it is cloned
+    pub fn get(&self, key: K) -> Option<V> {
+        read(&self.map).get(key)
+    }
+
+    pub fn remove(&self, key: K) -> Option<V> {
+        write(&self.map).remove(key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        read(&self.map).contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        read(&self.map).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        read(&self.map).is_empty()
+    }
+
+    pub fn clear(&self) {
+        write(&self.map).clear();
+    }
+
+    /// Runs `f` on the map with a shared lock, other readers aren't blocked
+    pub fn read<R>(&self, f: impl FnOnce(&HashMap<K, V>) -> R) -> R {
+        f(&read(&self.map))
+    }
+
+    /// Runs `f` on the map with the exclusive lock held throughout
+    pub fn write<R>(&self, f: impl FnOnce(&mut HashMap<K, V>) -> R) -> R {
+        f(&mut write(&self.map))
+    }
+
+    pub fn into_inner(self) -> HashMap<K, V> {
+        self.map.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<K: Hash + Clone + PartialEq + Debug, V: Clone + Debug> Default for SyncHashMap<K, V> {
+    fn default() -> Self {
+        SyncHashMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{ catch_unwind, AssertUnwindSafe };
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_stack_shared_between_threads() {
+        let stack = Arc::new(SyncStack::new());
+
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                let stack = Arc::clone(&stack);
+                thread::spawn(move || (0..250).for_each(|item| stack.push(thread * 1000 + item)))
+            })
+            .collect();
+        handles.into_iter().for_each(|handle| handle.join().unwrap());
+
+        assert_eq!(stack.len(), 1000);
+        let stack = Arc::try_unwrap(stack).ok().unwrap().into_inner();
+        let mut items: Vec<i32> = stack.iter().copied().collect();
+        items.sort();
+        items.dedup();
+        assert_eq!(items.len(), 1000);
+    }
+
+    #[test]
+    fn test_queue_hands_every_item_to_exactly_one_consumer() {
+        let queue = Arc::new(SyncQueue::new());
+        (0..1000).for_each(|item| queue.add(item));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || std::iter::from_fn(|| queue.remove()).collect::<Vec<_>>())
+            })
+            .collect();
+        let mut taken: Vec<i32> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+
+        taken.sort();
+        assert_eq!(taken, (0..1000).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_map_readers_and_writers() {
+        let map = Arc::new(SyncHashMap::new());
+
+        let handles: Vec<_> = (0..4u64)
+            .map(|thread| {
+                let map = Arc::clone(&map);
+                thread::spawn(move || {
+                    for key in thread * 100..(thread + 1) * 100 {
+                        map.insert(key, key * 2);
+                        assert_eq!(map.get(key), Some(key * 2));
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|handle| handle.join().unwrap());
+
+        assert_eq!(map.len(), 400);
+        assert_eq!(map.read(|map| map.iter().map(|(_, value)| value).sum::<u64>()), 399 * 400);
+    }
+
+    #[test]
+    fn test_panic_while_locked_does_not_break_the_wrapper() {
+        let map = SyncHashMap::new();
+        map.insert("a", 1);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            map.write(|map| {
+                map.insert("b", 2);
+                panic!("failed halfway");
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(map.get("b"), Some(2));
+        assert_eq!(map.remove("a"), Some(1));
+    }
+}
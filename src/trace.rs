@@ -0,0 +1,193 @@
+//! Step-by-step trace of what the structures do
+//! Key steps of some operations are reported as `Event`s: the bucket a hash
+//! map key lands in, a small vector moving to the heap, an AVL rotation, a
+//! list node linked in or out. Nothing is reported unless an observer is set
+//! with `observe`, or the events are collected with `record`, so the events
+//! cost nothing in normal use. Like the counters of `instrumented`, the
+//! observer is per thread.
+
+use std::cell::RefCell;
+use std::fmt::{ self, Display };
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A hash map key went to a bucket that already held `occupied` pairs
+    Hashed { key: String, bucket: usize, occupied: usize },
+    /// A small vector ran out of inline slots and moved its items to the heap
+    Spilled { len: usize },
+    /// A hash map holding `len` pairs moved them to a table of `buckets` buckets
+    Resized { len: usize, buckets: usize },
+    /// An AVL subtree was rotated to restore balance, `height` is its new height
+    Rotated { rotation: Rotation, height: usize },
+    /// A list node was linked in after `after`, or as the new first node
+    Linked { item: String, after: Option<String> },
+    /// A list node was unlinked, `previous` now points past it
+    Unlinked { item: String, previous: Option<String> },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Hashed { key, bucket, occupied } => {
+                write!(f, "key {} hashes to bucket {} holding {} pair(s)", key, bucket, occupied)
+            }
+            Event::Spilled { len } => {
+                write!(f, "inline storage full, {} item(s) moved to the heap", len)
+            }
+            Event::Resized { len, buckets } => {
+                write!(f, "{} pair(s) rehashed into {} buckets", len, buckets)
+            }
+            Event::Rotated { rotation, height } => {
+                let rotation = match rotation {
+                    Rotation::Left => "left",
+                    Rotation::Right => "right",
+                };
+                write!(f, "rotate {}, subtree height is now {}", rotation, height)
+            }
+            Event::Linked { item, after: Some(after) } => {
+                write!(f, "link {} after {}", item, after)
+            }
+            Event::Linked { item, after: None } => write!(f, "link {} as the first node", item),
+            Event::Unlinked { item, previous: Some(previous) } => {
+                write!(f, "unlink {}, {} now points past it", item, previous)
+            }
+            Event::Unlinked { item, previous: None } => {
+                write!(f, "unlink {}, the next node becomes the first", item)
+            }
+        }
+    }
+}
+
+type Observer = Box<dyn FnMut(&Event)>;
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Observer>> = const { RefCell::new(None) };
+}
+
+/// Puts the previous observer back, also when the operation panics
+struct Restore(Option<Observer>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        let _ = OBSERVER.try_with(|observer| *observer.borrow_mut() = previous);
+    }
+}
+
+/// Runs the operation with `observer` called for every event it emits
+pub fn observe<R>(observer: impl FnMut(&Event) + 'static, operation: impl FnOnce() -> R) -> R {
+    let previous = OBSERVER.with(|current| current.borrow_mut().replace(Box::new(observer)));
+    let _restore = Restore(previous);
+    operation()
+}
+
+/// Runs the operation and returns the events it emitted, in order
+pub fn record<R>(operation: impl FnOnce() -> R) -> (R, Vec<Event>) {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&events);
+    let result = observe(move |event| sink.borrow_mut().push(event.clone()), operation);
+    let events = events.take();
+    (result, events)
+}
+
+/// Passes the event to the observer, the event is only built when there is one
+pub(crate) fn emit(event: impl FnOnce() -> Event) {
+    // The observer is taken out while it runs, so it can use traced structures itself
+    let Some(mut observer) = OBSERVER.with(|current| current.borrow_mut().take()) else {
+        return;
+    };
+    observer(&event());
+    OBSERVER.with(|current| {
+        let mut current = current.borrow_mut();
+        if current.is_none() {
+            *current = Some(observer);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl_tree::AvlTreeMap;
+    use crate::hash_map::HashMap;
+    use crate::singly_linked_list::SinglyLinkedList;
+    use crate::small_vec::SmallVec;
+
+    #[test]
+    fn test_only_events_inside_record_are_kept() {
+        let mut list = SinglyLinkedList::new();
+        list.append(1);
+
+        let ((), events) = record(|| ());
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_list_relinks() {
+        let mut list = SinglyLinkedList::new();
+        list.append(1);
+        list.append(3);
+
+        let (_, events) = record(|| {
+            list.insert_after_given(2, 1);
+            list.delete_first();
+        });
+
+        assert_eq!(events, vec![
+            Event::Linked { item: "2".to_string(), after: Some("1".to_string()) },
+            Event::Unlinked { item: "1".to_string(), previous: None },
+        ]);
+    }
+
+    #[test]
+    fn test_sorted_inserts_rotate_the_tree() {
+        let mut tree = AvlTreeMap::new();
+
+        let (_, events) = record(|| {
+            for key in 1..=3 {
+                tree.insert(key, ());
+            }
+        });
+
+        assert_eq!(events, vec![Event::Rotated { rotation: Rotation::Left, height: 2 }]);
+    }
+
+    #[test]
+    fn test_hashing_and_spilling() {
+        let mut map = HashMap::new();
+        let mut small: SmallVec<u8, 2> = SmallVec::new();
+
+        let (_, events) = record(|| {
+            map.insert("key", 1);
+            small.extend([1, 2, 3]);
+        });
+
+        assert!(matches!(&events[0], Event::Hashed { key, occupied: 0, .. } if key == "\"key\""));
+        assert_eq!(events[1], Event::Spilled { len: 2 });
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_nested_observers_are_restored() {
+        let mut list = SinglyLinkedList::new();
+
+        let (inner, outer) = record(|| {
+            list.prepend(1);
+            let (_, inner) = record(|| list.prepend(2));
+            list.prepend(3);
+            inner
+        });
+
+        assert_eq!(inner.len(), 1);
+        assert_eq!(outer.len(), 2);
+        assert_eq!(outer[1].to_string(), "link 3 as the first node");
+    }
+}
@@ -0,0 +1,245 @@
+//! Undo and redo
+//! A `History` owns a structure and changes it only through edits. Applying
+//! an edit returns the edit that takes it back, which goes on the undo log;
+//! `undo` applies the newest one and moves its own inverse to the redo log,
+//! and `redo` does the same the other way. A new edit clears the redo log,
+//! like in any editor. Edits that change nothing, like popping an empty
+//! stack, leave both logs alone. `ListEdit`, `MapEdit` and `StackEdit` cover
+//! the mutations of the linked list, the hash map and the stack.
+
+use std::fmt::{ Debug, Display };
+use std::hash::Hash;
+use std::ops::Deref;
+
+use crate::hash_map::HashMap;
+use crate::singly_linked_list::SinglyLinkedList;
+use crate::stack::Stack;
+
+pub trait Edit<S>: Sized {
+    /// Makes the change and returns the edit that reverts it, None if
+    /// nothing changed
+    fn apply(self, target: &mut S) -> Option<Self>;
+}
+
+pub struct History<S, E> {
+    target: S,
+    undo: Vec<E>,
+    redo: Vec<E>,
+}
+
+impl<S, E: Edit<S>> History<S, E> {
+    pub fn new(target: S) -> Self {
+        History { target, undo: Vec::new(), redo: Vec::new() }
+    }
+
+    /// Returns the inverse that was logged, None if the edit changed nothing
+    pub fn apply(&mut self, edit: E) -> Option<&E> {
+        let inverse = edit.apply(&mut self.target)?;
+        self.redo.clear();
+        self.undo.push(inverse);
+        self.undo.last()
+    }
+
+    /// False if there was nothing to undo
+    pub fn undo(&mut self) -> bool {
+        Self::replay(&mut self.target, &mut self.undo, &mut self.redo)
+    }
+
+    /// False if there was nothing to redo
+    pub fn redo(&mut self) -> bool {
+        Self::replay(&mut self.target, &mut self.redo, &mut self.undo)
+    }
+
+    fn replay(target: &mut S, from: &mut Vec<E>, to: &mut Vec<E>) -> bool {
+        let Some(edit) = from.pop() else {
+            return false;
+        };
+        if let Some(inverse) = edit.apply(target) {
+            to.push(inverse);
+        }
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Swaps in a new structure and forgets both logs
+    pub fn reset(&mut self, target: S) {
+        self.target = target;
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn into_inner(self) -> S {
+        self.target
+    }
+}
+
+impl<S: Default, E: Edit<S>> Default for History<S, E> {
+    fn default() -> Self {
+        History::new(S::default())
+    }
+}
+
+impl<S, E> Deref for History<S, E> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.target
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListEdit<T> {
+    Append(T),
+    Prepend(T),
+    DeleteFirst,
+    DeleteLast,
+    /// Inserts so the item ends up at the index, which can be the length
+    Insert(usize, T),
+    Remove(usize),
+}
+
+impl<T: PartialEq + Debug + Clone + Display> Edit<SinglyLinkedList<T>> for ListEdit<T> {
+    fn apply(self, list: &mut SinglyLinkedList<T>) -> Option<Self> {
+        match self {
+            ListEdit::Append(item) => {
+                list.append(item);
+                Some(ListEdit::DeleteLast)
+            }
+            ListEdit::Prepend(item) => {
+                list.prepend(item);
+                Some(ListEdit::DeleteFirst)
+            }
+            ListEdit::DeleteFirst => list.try_delete_first().ok().map(ListEdit::Prepend),
+            ListEdit::DeleteLast => list.try_delete_last().ok().map(ListEdit::Append),
+            ListEdit::Insert(index, item) => {
+                list.insert_at(index, item).ok()?;
+                Some(ListEdit::Remove(index))
+            }
+            ListEdit::Remove(index) => {
+                list.remove_at(index).map(|item| ListEdit::Insert(index, item))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapEdit<K, V> {
+    Insert(K, V),
+    Remove(K),
+}
+
+impl<K, V> Edit<HashMap<K, V>> for MapEdit<K, V>
+where
+    K: Hash + Clone + PartialEq + Debug,
+    V: Clone + Debug,
+{
+    fn apply(self, map: &mut HashMap<K, V>) -> Option<Self> {
+        match self {
+            MapEdit::Insert(key, value) => match map.insert(key.clone(), value) {
+                Some(old) => Some(MapEdit::Insert(key, old)),
+                None => Some(MapEdit::Remove(key)),
+            },
+            MapEdit::Remove(key) => {
+                map.remove(key.clone()).map(|value| MapEdit::Insert(key, value))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackEdit<T> {
+    Push(T),
+    Pop,
+}
+
+impl<T> Edit<Stack<T>> for StackEdit<T> {
+    fn apply(self, stack: &mut Stack<T>) -> Option<Self> {
+        match self {
+            StackEdit::Push(item) => {
+                stack.push(item);
+                Some(StackEdit::Pop)
+            }
+            StackEdit::Pop => stack.pop().map(StackEdit::Push),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(list: &SinglyLinkedList<i32>) -> Vec<i32> {
+        Vec::from(list.clone())
+    }
+
+    #[test]
+    fn test_undo_and_redo_a_list() {
+        let mut history = History::new(SinglyLinkedList::from(vec![1, 2, 3]));
+
+        history.apply(ListEdit::Remove(1));
+        history.apply(ListEdit::Append(4));
+        history.apply(ListEdit::Insert(1, 5));
+        assert_eq!(items(&history), vec![1, 5, 3, 4]);
+
+        assert!(history.undo());
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(items(&history), vec![1, 2, 3]);
+        assert!(!history.undo());
+        assert!(history.redo());
+        assert_eq!(items(&history), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_list_edits_at_either_end() {
+        let mut history = History::new(SinglyLinkedList::from(vec![2]));
+
+        history.apply(ListEdit::Prepend(1));
+        history.apply(ListEdit::Insert(2, 3));
+        let popped = history.apply(ListEdit::DeleteLast).cloned();
+
+        assert_eq!(popped, Some(ListEdit::Append(3)));
+        assert_eq!(history.apply(ListEdit::Insert(5, 0)), None);
+        while history.undo() {}
+        assert_eq!(Vec::from(history.into_inner()), vec![2]);
+    }
+
+    #[test]
+    fn test_map_undo_restores_old_values() {
+        let mut history = History::new(HashMap::new());
+        history.apply(MapEdit::Insert("a", 1));
+        history.apply(MapEdit::Insert("a", 2));
+        history.apply(MapEdit::Remove("a"));
+
+        history.undo();
+        assert_eq!(history.get("a"), Some(2));
+        history.undo();
+        assert_eq!(history.get("a"), Some(1));
+        history.undo();
+
+        assert!(history.is_empty());
+        assert!(history.can_redo());
+    }
+
+    #[test]
+    fn when_nothing_changes_nothing_is_logged() {
+        let mut history: History<Stack<i32>, StackEdit<i32>> = History::default();
+
+        assert_eq!(history.apply(StackEdit::Pop), None);
+        assert_eq!(history.apply(StackEdit::Push(1)), Some(&StackEdit::Pop));
+        history.undo();
+        history.apply(StackEdit::Push(2));
+
+        assert!(!history.can_redo());
+        assert!(history.undo());
+        assert!(!history.can_undo());
+        assert!(history.is_empty());
+    }
+}
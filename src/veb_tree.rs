@@ -0,0 +1,463 @@
+//! van Emde Boas Tree implementation
+//! Set of integers from a universe of `2^bits` values. A node over `bits`
+//! bits splits a value into its high half, which picks a cluster, and its low
+//! half, which is stored in that cluster: a node over half as many bits.
+//! A summary node holds which clusters are non-empty. The minimum of a node
+//! is kept only in the node itself, not in a cluster, so inserting into an
+//! empty cluster is O(1) and every operation recurses into just one node of
+//! half the bits: insert, remove, contains, successor and predecessor are
+//! all O(log log u). Clusters are created when they get their first value
+//! and dropped with their last, so memory grows with the number of values
+//! rather than with the universe.
+
+use std::collections::HashMap;
+
+use crate::collection::Collection;
+use crate::heap_size::HeapSize;
+#[cfg(any(test, feature = "debug-invariants"))]
+use crate::invariants::Invariants;
+use crate::invariants::check_invariants;
+
+#[derive(Debug, Clone)]
+struct VebNode {
+    bits: u32,
+    min: Option<u64>,
+    max: Option<u64>,
+    /// Non-empty clusters by their high half, over `low_bits` bits
+    clusters: HashMap<u64, VebNode>,
+    /// Indexes of the non-empty clusters, over `high_bits` bits
+    summary: Option<Box<VebNode>>,
+}
+
+impl VebNode {
+    fn new(bits: u32) -> Self {
+        VebNode { bits, min: None, max: None, clusters: HashMap::new(), summary: None }
+    }
+
+    fn low_bits(&self) -> u32 {
+        self.bits / 2
+    }
+
+    fn high_bits(&self) -> u32 {
+        self.bits - self.low_bits()
+    }
+
+    fn high(&self, value: u64) -> u64 {
+        value >> self.low_bits()
+    }
+
+    fn low(&self, value: u64) -> u64 {
+        value & ((1 << self.low_bits()) - 1)
+    }
+
+    fn index(&self, high: u64, low: u64) -> u64 {
+        (high << self.low_bits()) | low
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        if self.min == Some(value) || self.max == Some(value) {
+            return true;
+        }
+        if self.bits == 1 {
+            return false;
+        }
+        self.clusters
+            .get(&self.high(value))
+            .is_some_and(|cluster| cluster.contains(self.low(value)))
+    }
+
+    /// Inserts a value that is not in the node yet
+    fn insert(&mut self, mut value: u64) {
+        let Some(min) = self.min else {
+            self.min = Some(value);
+            self.max = Some(value);
+            return;
+        };
+        if value < min {
+            // The new value becomes the minimum and the old one moves down
+            value = self.min.replace(value).unwrap();
+        }
+        if self.bits > 1 {
+            let (high, low) = (self.high(value), self.low(value));
+            let (low_bits, high_bits) = (self.low_bits(), self.high_bits());
+            let cluster = self.clusters.entry(high).or_insert_with(|| VebNode::new(low_bits));
+            if cluster.min.is_none() {
+                self.summary.get_or_insert_with(|| Box::new(VebNode::new(high_bits))).insert(high);
+            }
+            cluster.insert(low);
+        }
+        if self.max.is_some_and(|max| value > max) {
+            self.max = Some(value);
+        }
+    }
+
+    /// Removes a value that is in the node
+    fn remove(&mut self, mut value: u64) {
+        if self.min == self.max {
+            self.min = None;
+            self.max = None;
+            return;
+        }
+        if self.bits == 1 {
+            // Both 0 and 1 are present, the other one stays
+            self.min = Some(1 - value);
+            self.max = self.min;
+            return;
+        }
+        if self.min == Some(value) {
+            // The smallest value of the first cluster becomes the new minimum
+            let first = self.summary.as_ref().and_then(|summary| summary.min).unwrap();
+            value = self.index(first, self.clusters[&first].min.unwrap());
+            self.min = Some(value);
+        }
+        let (high, low) = (self.high(value), self.low(value));
+        let cluster = self.clusters.get_mut(&high).unwrap();
+        cluster.remove(low);
+        match cluster.max {
+            Some(cluster_max) if self.max == Some(value) => {
+                self.max = Some(self.index(high, cluster_max));
+            }
+            Some(_) => {}
+            None => {
+                self.clusters.remove(&high);
+                let summary = self.summary.as_mut().unwrap();
+                summary.remove(high);
+                if self.max == Some(value) {
+                    self.max = match summary.max {
+                        Some(last) => Some(self.index(last, self.clusters[&last].max.unwrap())),
+                        None => self.min,
+                    };
+                }
+            }
+        }
+    }
+
+    /// Smallest value greater than `value`
+    fn successor(&self, value: u64) -> Option<u64> {
+        if self.bits == 1 {
+            return (value == 0 && self.max == Some(1)).then_some(1);
+        }
+        if let Some(min) = self.min.filter(|&min| value < min) {
+            return Some(min);
+        }
+        let (high, low) = (self.high(value), self.low(value));
+        let cluster = self.clusters.get(&high);
+        if let Some(cluster) = cluster.filter(|cluster| cluster.max.is_some_and(|max| low < max)) {
+            return Some(self.index(high, cluster.successor(low).unwrap()));
+        }
+        let next = self.summary.as_ref()?.successor(high)?;
+        Some(self.index(next, self.clusters[&next].min.unwrap()))
+    }
+
+    /// Largest value smaller than `value`
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        if self.bits == 1 {
+            return (value == 1 && self.min == Some(0)).then_some(0);
+        }
+        if let Some(max) = self.max.filter(|&max| value > max) {
+            return Some(max);
+        }
+        let (high, low) = (self.high(value), self.low(value));
+        let cluster = self.clusters.get(&high);
+        if let Some(cluster) = cluster.filter(|cluster| cluster.min.is_some_and(|min| low > min)) {
+            return Some(self.index(high, cluster.predecessor(low).unwrap()));
+        }
+        match self.summary.as_ref().and_then(|summary| summary.predecessor(high)) {
+            Some(previous) => Some(self.index(previous, self.clusters[&previous].max.unwrap())),
+            // Only the minimum, which is in no cluster, can still be smaller
+            None => self.min.filter(|&min| value > min),
+        }
+    }
+
+    /// Checks the node and its clusters and returns how many values they hold
+    #[cfg(any(test, feature = "debug-invariants"))]
+    fn check(&self) -> usize {
+        let (Some(min), Some(max)) = (self.min, self.max) else {
+            assert!(self.min.is_none() && self.max.is_none(), "Node has only a min or a max");
+            assert!(self.clusters.is_empty(), "Empty node has clusters");
+            return 0;
+        };
+        assert!(min <= max, "min is larger than max");
+        assert!(self.bits == 64 || max >> self.bits == 0, "Value outside the node's universe");
+        if self.bits == 1 {
+            assert!(self.clusters.is_empty(), "One bit node has clusters");
+            return if min == max { 1 } else { 2 };
+        }
+
+        let mut values = 1;
+        let summary_len = self.summary.as_ref().map_or(0, |summary| summary.check());
+        assert_eq!(summary_len, self.clusters.len(), "summary doesn't match the clusters");
+        for (&high, cluster) in &self.clusters {
+            assert_eq!(cluster.bits, self.low_bits(), "Cluster has the wrong universe");
+            let summarized = self.summary.as_ref().is_some_and(|summary| summary.contains(high));
+            assert!(summarized, "Cluster is missing from the summary");
+            let cluster_min = cluster.min.expect("Empty clusters are dropped");
+            assert!(self.index(high, cluster_min) > min, "Cluster holds a value below min");
+            values += cluster.check();
+        }
+        let last = self.summary.as_ref().and_then(|summary| summary.max);
+        let expected_max = match last {
+            Some(last) => self.index(last, self.clusters[&last].max.unwrap()),
+            None => min,
+        };
+        assert_eq!(max, expected_max, "max isn't the largest value");
+        values
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VebTree {
+    root: VebNode,
+    len: usize,
+}
+
+impl VebTree {
+    /// Creates an empty set for the values `0..2^bits`, `bits` is 1 to 64
+    pub fn new(bits: u32) -> Self {
+        assert!((1..=64).contains(&bits), "Universe must have 1 to 64 bits, got {}", bits);
+        VebTree { root: VebNode::new(bits), len: 0 }
+    }
+
+    /// Number of bits of the values in the universe
+    pub fn universe_bits(&self) -> u32 {
+        self.root.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = VebTree::new(self.universe_bits());
+        check_invariants!(self);
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        self.root.min
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        self.root.max
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        self.in_universe(value) && self.root.contains(value)
+    }
+
+    /// Returns false if the value was already present
+    pub fn insert(&mut self, value: u64) -> bool {
+        self.assert_in_universe(value);
+        if self.root.contains(value) {
+            return false;
+        }
+        self.root.insert(value);
+        self.len += 1;
+        check_invariants!(self);
+        true
+    }
+
+    /// Returns false if the value wasn't present
+    pub fn remove(&mut self, value: u64) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        self.root.remove(value);
+        self.len -= 1;
+        check_invariants!(self);
+        true
+    }
+
+    /// Smallest value greater than `value`
+    pub fn successor(&self, value: u64) -> Option<u64> {
+        if !self.in_universe(value) {
+            return None;
+        }
+        self.root.successor(value)
+    }
+
+    /// Largest value smaller than `value`
+    pub fn predecessor(&self, value: u64) -> Option<u64> {
+        if !self.in_universe(value) {
+            return self.max();
+        }
+        self.root.predecessor(value)
+    }
+
+    /// Values in ascending order, one successor query per step
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        std::iter::successors(self.min(), |&value| self.successor(value))
+    }
+
+    fn in_universe(&self, value: u64) -> bool {
+        self.root.bits == 64 || value >> self.root.bits == 0
+    }
+
+    fn assert_in_universe(&self, value: u64) {
+        assert!(
+            self.in_universe(value),
+            "Value {} out of range for a universe of {} bits",
+            value,
+            self.root.bits
+        );
+    }
+}
+
+impl Collection for VebTree {
+    type Item = u64;
+
+    fn len(&self) -> usize {
+        VebTree::len(self)
+    }
+
+    fn clear(&mut self) {
+        VebTree::clear(self);
+    }
+
+    fn contains(&self, value: &u64) -> bool {
+        VebTree::contains(self, *value)
+    }
+}
+
+#[cfg(any(test, feature = "debug-invariants"))]
+impl Invariants for VebTree {
+    fn assert_invariants(&self) {
+        assert_eq!(self.root.check(), self.len, "len doesn't match the values");
+    }
+}
+
+/// Clusters and summaries exist only where there are items
+impl HeapSize for VebNode {
+    fn heap_size(&self) -> usize {
+        self.clusters.heap_size() + self.summary.heap_size()
+    }
+}
+
+impl HeapSize for VebTree {
+    fn heap_size(&self) -> usize {
+        self.root.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn when_tree_is_created_it_is_empty() {
+        let tree = VebTree::new(16);
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.successor(0), None);
+        assert!(!tree.contains(0));
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut tree = VebTree::new(8);
+
+        assert!(tree.insert(42));
+        assert!(tree.insert(7));
+        assert!(!tree.insert(42));
+
+        assert!(tree.contains(7));
+        assert!(!tree.contains(8));
+        assert_eq!((tree.min(), tree.max()), (Some(7), Some(42)));
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn test_successor_and_predecessor() {
+        let mut tree = VebTree::new(16);
+        for value in [3, 100, 256, 257, 40_000] {
+            tree.insert(value);
+        }
+
+        assert_eq!(tree.successor(0), Some(3));
+        assert_eq!(tree.successor(3), Some(100));
+        assert_eq!(tree.successor(256), Some(257));
+        assert_eq!(tree.successor(40_000), None);
+        assert_eq!(tree.predecessor(65_535), Some(40_000));
+        assert_eq!(tree.predecessor(256), Some(100));
+        assert_eq!(tree.predecessor(3), None);
+        assert_eq!(tree.iter().collect::<Vec<u64>>(), vec![3, 100, 256, 257, 40_000]);
+    }
+
+    #[test]
+    fn test_remove_min_and_max() {
+        let mut tree = VebTree::new(8);
+        for value in [10, 20, 30] {
+            tree.insert(value);
+        }
+
+        assert!(tree.remove(10));
+        assert!(tree.remove(30));
+        assert!(!tree.remove(30));
+
+        assert_eq!((tree.min(), tree.max()), (Some(20), Some(20)));
+        assert!(tree.remove(20));
+        assert!(tree.is_empty());
+        assert_eq!(tree.root.clusters.len(), 0, "Empty clusters are dropped");
+    }
+
+    #[test]
+    fn test_one_bit_universe() {
+        let mut tree = VebTree::new(1);
+
+        tree.insert(1);
+        tree.insert(0);
+        tree.remove(0);
+
+        assert_eq!(tree.iter().collect::<Vec<u64>>(), vec![1]);
+        assert_eq!(tree.predecessor(1), None);
+    }
+
+    #[test]
+    fn test_full_64_bit_universe() {
+        let mut tree = VebTree::new(64);
+        let values = [0, 1, u32::MAX as u64, 1 << 40, u64::MAX - 1, u64::MAX];
+        for value in values {
+            tree.insert(value);
+        }
+
+        assert_eq!(tree.iter().collect::<Vec<u64>>(), values);
+        assert_eq!(tree.successor(1 << 40), Some(u64::MAX - 1));
+        assert_eq!(tree.predecessor(u64::MAX), Some(u64::MAX - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Value 256 out of range for a universe of 8 bits")]
+    fn test_insert_outside_universe_panics() {
+        VebTree::new(8).insert(256);
+    }
+
+    #[test]
+    fn test_random_operations_match_btree_set() {
+        let mut tree = VebTree::new(10);
+        let mut expected = BTreeSet::new();
+        let mut state: u64 = 29;
+
+        for _ in 0..5000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let value = (state >> 33) % 1024;
+            match (state >> 60) % 4 {
+                0 => assert_eq!(tree.remove(value), expected.remove(&value)),
+                1 => {
+                    assert_eq!(tree.successor(value), expected.range(value + 1..).next().copied());
+                    assert_eq!(tree.predecessor(value), expected.range(..value).last().copied());
+                }
+                _ => assert_eq!(tree.insert(value), expected.insert(value)),
+            }
+            assert_eq!(tree.len(), expected.len());
+        }
+
+        assert!(tree.iter().eq(expected.iter().copied()));
+        assert_eq!(tree.min(), expected.first().copied());
+    }
+}
@@ -0,0 +1,185 @@
+//! Graphviz DOT export
+//! `to_dot` turns a structure into the text of a Graphviz graph, with one DOT
+//! node per node of the structure labelled with its data, so the layout can
+//! be looked at with `dot -Tsvg`. Lists are drawn left to right, trees top
+//! down with an invisible point for a missing child so left and right
+//! children stay on their sides, and graphs as they are, with weights on the
+//! edges unless the edge data is `()`.
+
+use std::fmt::{ Debug, Write };
+
+use crate::graph::{ Graph, GraphOps, MatrixGraph, NodeIndex };
+use crate::persistent::List;
+use crate::singly_linked_list::SinglyLinkedList;
+
+pub trait ToDot {
+    fn to_dot(&self) -> String;
+}
+
+/// Collects the statements of a DOT graph
+pub(crate) struct DotWriter {
+    out: String,
+    edge_operator: &'static str,
+}
+
+impl DotWriter {
+    pub(crate) fn new(directed: bool, name: &str, attributes: &[&str]) -> Self {
+        let (keyword, edge_operator) = if directed { ("digraph", "->") } else { ("graph", "--") };
+        let mut out = format!("{} {} {{\n", keyword, name);
+        for attribute in attributes {
+            writeln!(out, "    {};", attribute).unwrap();
+        }
+        DotWriter { out, edge_operator }
+    }
+
+    pub(crate) fn node(&mut self, id: usize, label: &str) {
+        writeln!(self.out, "    n{} [label=\"{}\"];", id, escape(label)).unwrap();
+    }
+
+    /// Invisible node that only takes up space
+    pub(crate) fn point(&mut self, id: usize) {
+        writeln!(self.out, "    n{} [shape=point, style=invis];", id).unwrap();
+    }
+
+    pub(crate) fn edge(&mut self, from: usize, to: usize, label: Option<&str>) {
+        write!(self.out, "    n{} {} n{}", from, self.edge_operator, to).unwrap();
+        if let Some(label) = label {
+            write!(self.out, " [label=\"{}\"]", escape(label)).unwrap();
+        }
+        self.out.push_str(";\n");
+    }
+
+    pub(crate) fn invisible_edge(&mut self, from: usize, to: usize) {
+        writeln!(self.out, "    n{} {} n{} [style=invis];", from, self.edge_operator, to).unwrap();
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn list_to_dot<'a, T: Debug + 'a>(items: impl Iterator<Item = &'a T>) -> String {
+    let mut dot = DotWriter::new(true, "list", &["rankdir=LR", "node [shape=box]"]);
+    for (id, item) in items.enumerate() {
+        dot.node(id, &format!("{:?}", item));
+        if id > 0 {
+            dot.edge(id - 1, id, None);
+        }
+    }
+    dot.finish()
+}
+
+impl<T: Clone + Debug> ToDot for SinglyLinkedList<T> {
+    fn to_dot(&self) -> String {
+        list_to_dot(self.iter())
+    }
+}
+
+impl<T: Debug> ToDot for List<T> {
+    fn to_dot(&self) -> String {
+        list_to_dot(self.iter())
+    }
+}
+
+/// Draws every node with its data, and every edge once
+fn graph_to_dot<G>(graph: &G, node_label: impl Fn(NodeIndex) -> String) -> String
+where
+    G: GraphOps,
+    G::EdgeData: Debug,
+{
+    let mut dot = DotWriter::new(graph.is_directed(), "G", &[]);
+    for node in graph.node_indices() {
+        dot.node(node.index(), &node_label(node));
+    }
+    let weighted = std::mem::size_of::<G::EdgeData>() > 0;
+    for from in graph.node_indices() {
+        for (to, data) in graph.edges(from) {
+            // An undirected edge is stored at both ends
+            if !graph.is_directed() && to < from {
+                continue;
+            }
+            let label = weighted.then(|| format!("{:?}", data));
+            dot.edge(from.index(), to.index(), label.as_deref());
+        }
+    }
+    dot.finish()
+}
+
+impl<N: Debug, E: Clone + Debug> ToDot for Graph<N, E> {
+    fn to_dot(&self) -> String {
+        graph_to_dot(self, |node| format!("{:?}", self.node(node).unwrap()))
+    }
+}
+
+impl<N: Debug, E: Clone + Debug> ToDot for MatrixGraph<N, E> {
+    fn to_dot(&self) -> String {
+        graph_to_dot(self, |node| format!("{:?}", self.node(node).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn when_list_is_empty_graph_has_no_nodes() {
+        let list = SinglyLinkedList::<i32>::new();
+
+        assert_eq!(list.to_dot(), "digraph list {\n    rankdir=LR;\n    node [shape=box];\n}\n");
+    }
+
+    #[test]
+    fn test_singly_linked_list() {
+        let mut list = SinglyLinkedList::new();
+        list.append(1);
+        list.append(2);
+
+        let dot = list.to_dot();
+
+        assert!(dot.contains("    n0 [label=\"1\"];\n    n1 [label=\"2\"];\n    n0 -> n1;\n"));
+    }
+
+    #[test]
+    fn test_persistent_list_and_escaping() {
+        let list = List::new().push_front("b\\c").push_front("say \"a\"");
+
+        let dot = list.to_dot();
+
+        assert!(dot.contains(r#"n0 [label="\"say \\\"a\\\"\""];"#));
+        assert!(dot.contains(r#"n1 [label="\"b\\\\c\""];"#));
+    }
+
+    #[test]
+    fn test_undirected_graph_draws_each_edge_once() {
+        let mut graph = Graph::new_undirected();
+        let a = graph.add_node('a');
+        let b = graph.add_node('b');
+        graph.add_edge(a, b, 2.5);
+
+        let dot = graph.to_dot();
+
+        assert!(dot.starts_with("graph G {\n"));
+        assert_eq!(dot.matches("--").count(), 1);
+        assert!(dot.contains("    n0 -- n1 [label=\"2.5\"];\n"));
+    }
+
+    #[test]
+    fn test_unit_edges_have_no_labels() {
+        let mut graph = MatrixGraph::new_directed();
+        let a = graph.add_node(1);
+        let b = graph.add_node(2);
+        graph.add_edge(b, a, ());
+
+        let dot = graph.to_dot();
+
+        assert_eq!(
+            dot,
+            "digraph G {\n    n0 [label=\"1\"];\n    n1 [label=\"2\"];\n    n1 -> n0;\n}\n"
+        );
+    }
+}
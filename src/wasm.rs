@@ -0,0 +1,229 @@
+//! JavaScript bindings, behind the `wasm` feature
+//! `wasm-bindgen` classes for the linked list, stack, queue and hash map, so
+//! a web page can drive them. JavaScript has one number type, so the stack
+//! and queue hold `f64`; the list and map hold strings. Methods that can come
+//! up empty return `undefined` instead of throwing, and every class can hand
+//! its items over as an array for the page to draw. `www/index.html` is a
+//! small playground built on them.
+
+use wasm_bindgen::prelude::*;
+
+use crate::hash_map::HashMap;
+use crate::queue::Queue;
+use crate::render::Render;
+use crate::singly_linked_list::SinglyLinkedList;
+use crate::stack::Stack;
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct JsList {
+    list: SinglyLinkedList<String>,
+}
+
+#[wasm_bindgen]
+impl JsList {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsList {
+        JsList::default()
+    }
+
+    pub fn append(&mut self, item: String) {
+        self.list.append(item);
+    }
+
+    pub fn prepend(&mut self, item: String) {
+        self.list.prepend(item);
+    }
+
+    /// Removes the first node holding `item`, false if there is none
+    pub fn remove(&mut self, item: String) -> bool {
+        self.list.try_delete_node_with_data(item).is_ok()
+    }
+
+    #[wasm_bindgen(js_name = deleteFirst)]
+    pub fn delete_first(&mut self) -> Option<String> {
+        self.list.try_delete_first().ok()
+    }
+
+    #[wasm_bindgen(js_name = deleteLast)]
+    pub fn delete_last(&mut self) -> Option<String> {
+        self.list.try_delete_last().ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Items from the first node
+    pub fn items(&self) -> Vec<String> {
+        self.list.iter().cloned().collect()
+    }
+
+    /// The boxes-and-arrows drawing, for a `<pre>` element
+    pub fn render(&self) -> String {
+        self.list.render()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct JsStack {
+    stack: Stack<f64>,
+}
+
+#[wasm_bindgen]
+impl JsStack {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsStack {
+        JsStack::default()
+    }
+
+    pub fn push(&mut self, item: f64) {
+        self.stack.push(item);
+    }
+
+    pub fn pop(&mut self) -> Option<f64> {
+        self.stack.pop()
+    }
+
+    pub fn peek(&self) -> Option<f64> {
+        self.stack.peek().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stack.iter().len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Items from the bottom to the top
+    pub fn items(&self) -> Vec<f64> {
+        self.stack.iter().copied().collect()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct JsQueue {
+    queue: Queue<f64>,
+}
+
+#[wasm_bindgen]
+impl JsQueue {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsQueue {
+        JsQueue::default()
+    }
+
+    pub fn add(&mut self, item: f64) {
+        self.queue.add(item);
+    }
+
+    pub fn remove(&mut self) -> Option<f64> {
+        self.queue.remove()
+    }
+
+    pub fn peek(&self) -> Option<f64> {
+        self.queue.peek()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.iter().count()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Items from the front to the back
+    pub fn items(&self) -> Vec<f64> {
+        self.queue.iter().copied().collect()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct JsMap {
+    map: HashMap<String, String>,
+}
+
+#[wasm_bindgen]
+impl JsMap {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsMap {
+        JsMap::default()
+    }
+
+    /// Returns the old value if the key was there
+    pub fn insert(&mut self, key: String, value: String) -> Option<String> {
+        self.map.insert(key, value)
+    }
+
+    pub fn get(&self, key: String) -> Option<String> {
+        self.map.get(key)
+    }
+
+    pub fn remove(&mut self, key: String) -> Option<String> {
+        self.map.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Keys in bucket order, which changes from one map to the next
+    pub fn keys(&self) -> Vec<String> {
+        self.map.iter().map(|(key, _)| key.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_and_map_wrappers() {
+        let mut list = JsList::new();
+        let mut map = JsMap::new();
+
+        list.append("b".to_string());
+        list.prepend("a".to_string());
+        map.insert("k".to_string(), "v".to_string());
+
+        assert_eq!(list.items(), vec!["a", "b"]);
+        assert_eq!(list.delete_last(), Some("b".to_string()));
+        assert!(list.render().contains("| \"a\" | -> None"));
+        assert_eq!(map.insert("k".to_string(), "w".to_string()), Some("v".to_string()));
+        assert_eq!(map.keys(), vec!["k"]);
+    }
+
+    #[test]
+    fn test_stack_and_queue_wrappers() {
+        let mut stack = JsStack::new();
+        let mut queue = JsQueue::new();
+
+        for item in [1.0, 2.5] {
+            stack.push(item);
+            queue.add(item);
+        }
+
+        assert_eq!(stack.pop(), Some(2.5));
+        assert_eq!(queue.remove(), Some(1.0));
+        assert_eq!((stack.items(), queue.items()), (vec![1.0], vec![2.5]));
+        assert_eq!(JsQueue::new().peek(), None);
+    }
+}
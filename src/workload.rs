@@ -0,0 +1,293 @@
+//! Workload generator
+//! Reproducible random operation sequences for the property tests and the
+//! benchmarks. A `Workload` is a seed, a length, how often each kind of
+//! operation comes up and how the keys are spread: uniformly over a range, or
+//! zipfian, where a few hot keys take most of the traffic the way they do in
+//! real caches and indexes. The same workload always gives the same ops, so a
+//! failing seed can be replayed and two structures can be timed on exactly
+//! the same sequence. `replay` runs the ops on anything implementing `Map`,
+//! `replay_collection` on a `Collection` given how to add and take an item.
+
+use crate::collection::Collection;
+use crate::map::Map;
+
+/// One step of a workload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Insert(u64, u64),
+    Lookup(u64),
+    Remove(u64),
+}
+
+/// How keys are drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyDistribution {
+    /// Every key in `0..keys` equally often
+    Uniform { keys: u64 },
+    /// Key `k` in `0..keys` with weight `1 / (k + 1)^exponent`, so key 0 is
+    /// the hottest. Exponents near 1 are the usual choice. Keeps a table of
+    /// `keys` floats while the ops are generated.
+    Zipfian { keys: u64, exponent: f64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workload {
+    seed: u64,
+    len: usize,
+    ratios: [u32; 3],
+    keys: KeyDistribution,
+}
+
+impl Workload {
+    /// `len` ops, half inserts, 40% lookups and 10% removes, over 1000
+    /// uniform keys
+    pub fn new(seed: u64, len: usize) -> Self {
+        Workload {
+            seed,
+            len,
+            ratios: [50, 40, 10],
+            keys: KeyDistribution::Uniform { keys: 1_000 },
+        }
+    }
+
+    /// Relative weights of inserts, lookups and removes, `(1, 1, 0)` is half
+    /// inserts and half lookups. Panics if they are all zero.
+    pub fn with_ratios(mut self, insert: u32, lookup: u32, remove: u32) -> Self {
+        assert!(insert as u64 + lookup as u64 + remove as u64 > 0, "every ratio is zero");
+        self.ratios = [insert, lookup, remove];
+        self
+    }
+
+    /// Panics if the distribution has no keys or a negative exponent
+    pub fn with_keys(mut self, keys: KeyDistribution) -> Self {
+        match keys {
+            KeyDistribution::Uniform { keys } => assert!(keys > 0, "no keys to draw from"),
+            KeyDistribution::Zipfian { keys, exponent } => {
+                assert!(keys > 0, "no keys to draw from");
+                assert!(exponent >= 0.0, "zipfian exponent must not be negative");
+            }
+        }
+        self.keys = keys;
+        self
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The ops, the same ones every time for the same workload
+    pub fn ops(&self) -> Vec<Op> {
+        let mut random = SplitMix(self.seed);
+        let keys = KeySampler::new(self.keys);
+        let ratios = self.ratios.map(u64::from);
+        let [insert, lookup, _] = ratios;
+        let total: u64 = ratios.iter().sum();
+
+        (0..self.len)
+            .map(|_| {
+                let kind = random.below(total);
+                let key = keys.sample(&mut random);
+                if kind < insert {
+                    Op::Insert(key, random.next())
+                } else if kind < insert + lookup {
+                    Op::Lookup(key)
+                } else {
+                    Op::Remove(key)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Runs `ops` on `map` one at a time as the answers are pulled: the old value
+/// for an insert, the value found for a lookup and the value taken for a
+/// remove. Nothing happens until the iterator is consumed.
+pub fn replay<'a, M: Map<u64, u64>>(
+    map: &'a mut M,
+    ops: &'a [Op]
+) -> impl Iterator<Item = Option<u64>> + 'a {
+    ops.iter().map(move |&op| match op {
+        Op::Insert(key, value) => map.insert(key, value),
+        Op::Lookup(key) => map.get(&key),
+        Op::Remove(key) => map.remove(&key),
+    })
+}
+
+/// Like `replay` for a collection of keys: inserts go through `insert` and
+/// answer None, a lookup answers the key if `contains` finds it and a remove
+/// answers whatever `remove` took. Structures that can't remove a given key,
+/// like the stack, can take their next item instead.
+pub fn replay_collection<'a, C: Collection<Item = u64>>(
+    collection: &'a mut C,
+    ops: &'a [Op],
+    insert: fn(&mut C, u64),
+    remove: fn(&mut C, u64) -> Option<u64>
+) -> impl Iterator<Item = Option<u64>> + 'a {
+    ops.iter().map(move |&op| match op {
+        Op::Insert(key, _) => {
+            insert(collection, key);
+            None
+        }
+        Op::Lookup(key) => collection.contains(&key).then_some(key),
+        Op::Remove(key) => remove(collection, key),
+    })
+}
+
+/// splitmix64, small and good enough for picking ops
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`, by taking the high half of a 128-bit product
+    fn below(&mut self, bound: u64) -> u64 {
+        ((self.next() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// Uniform in `[0, 1)`
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+enum KeySampler {
+    Uniform(u64),
+    /// Cumulative weights, normalized so the last one is 1
+    Zipfian(Vec<f64>),
+}
+
+impl KeySampler {
+    fn new(keys: KeyDistribution) -> Self {
+        match keys {
+            KeyDistribution::Uniform { keys } => KeySampler::Uniform(keys),
+            KeyDistribution::Zipfian { keys, exponent } => {
+                let mut total = 0.0;
+                let mut cumulative: Vec<f64> = (1..=keys)
+                    .map(|rank| {
+                        total += (rank as f64).powf(-exponent);
+                        total
+                    })
+                    .collect();
+                cumulative.iter_mut().for_each(|weight| *weight /= total);
+                KeySampler::Zipfian(cumulative)
+            }
+        }
+    }
+
+    fn sample(&self, random: &mut SplitMix) -> u64 {
+        match self {
+            KeySampler::Uniform(keys) => random.below(*keys),
+            KeySampler::Zipfian(cumulative) => {
+                let target = random.unit();
+                let rank = cumulative.partition_point(|&weight| weight <= target);
+                rank.min(cumulative.len() - 1) as u64
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::hash_map::HashMap;
+    use crate::stack::Stack;
+
+    fn count(ops: &[Op], matches: fn(&Op) -> bool) -> usize {
+        ops.iter().filter(|op| matches(op)).count()
+    }
+
+    fn key(op: &Op) -> u64 {
+        match *op {
+            Op::Insert(key, _) | Op::Lookup(key) | Op::Remove(key) => key,
+        }
+    }
+
+    #[test]
+    fn test_same_seed_gives_the_same_ops() {
+        let workload = Workload::new(7, 500);
+
+        let ops = workload.ops();
+
+        assert_eq!(ops.len(), 500);
+        assert_eq!(ops, workload.ops());
+        assert_ne!(ops, Workload::new(8, 500).ops());
+    }
+
+    #[test]
+    fn test_ratios_are_followed() {
+        let ops = Workload::new(1, 10_000).with_ratios(3, 1, 0).ops();
+
+        let inserts = count(&ops, |op| matches!(op, Op::Insert(..)));
+        let removes = count(&ops, |op| matches!(op, Op::Remove(_)));
+
+        assert!((7_000..8_000).contains(&inserts), "{} inserts", inserts);
+        assert_eq!(removes, 0);
+    }
+
+    #[test]
+    fn test_zipfian_keys_favour_the_first_ones() {
+        let keys = KeyDistribution::Zipfian { keys: 100, exponent: 1.0 };
+
+        let ops = Workload::new(3, 10_000).with_keys(keys).ops();
+
+        let hottest = count(&ops, |op| key(op) == 0);
+        let coldest = count(&ops, |op| key(op) == 99);
+        // Key 0 has weight 1 / H(100), about 19%, key 99 a hundredth of that
+        assert!((1_700..2_200).contains(&hottest), "key 0 came up {} times", hottest);
+        assert!(coldest < 60, "key 99 came up {} times", coldest);
+        assert!(ops.iter().all(|op| key(op) < 100));
+    }
+
+    #[test]
+    fn test_uniform_keys_stay_in_range() {
+        let ops = Workload::new(5, 2_000).with_keys(KeyDistribution::Uniform { keys: 10 }).ops();
+
+        assert!(ops.iter().all(|op| key(op) < 10));
+        assert!((0..10).all(|wanted| ops.iter().any(|op| key(op) == wanted)));
+    }
+
+    #[test]
+    fn test_replay_gives_the_same_answers_on_every_map() {
+        let ops = Workload::new(11, 2_000).with_keys(KeyDistribution::Uniform { keys: 50 }).ops();
+        let mut map = HashMap::new();
+        let mut model = BTreeMap::new();
+
+        let answers: Vec<Option<u64>> = replay(&mut map, &ops).collect();
+
+        assert!(replay(&mut model, &ops).eq(answers));
+        assert_eq!(map.len(), model.len());
+    }
+
+    #[test]
+    fn test_replay_collection() {
+        let ops = [Op::Insert(4, 0), Op::Lookup(4), Op::Lookup(5), Op::Remove(9), Op::Remove(9)];
+        let mut stack = Stack::new();
+
+        let answers: Vec<Option<u64>> =
+            replay_collection(&mut stack, &ops, Stack::push, |stack, _| stack.pop()).collect();
+
+        assert_eq!(answers, vec![None, Some(4), None, Some(4), None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "every ratio is zero")]
+    fn test_all_zero_ratios_panic() {
+        Workload::new(0, 10).with_ratios(0, 0, 0);
+    }
+}
@@ -0,0 +1,318 @@
+//! Property tests against std models
+//! Every test runs a random sequence of operations on a crate collection and
+//! on the std collection that behaves the way it should, side by side, and
+//! checks that both give the same answers and hold the same items after
+//! every step. When a sequence fails, proptest shrinks it to a short one
+//! that still fails, which is usually enough to see the bug.
+
+use std::collections::{ BTreeMap, HashMap as StdHashMap, VecDeque };
+
+use data_structures_in_rust::error::Error;
+use data_structures_in_rust::prelude::*;
+use data_structures_in_rust::workload::{ self, KeyDistribution, Workload };
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Items of the list from the first one
+fn list_items(list: &SinglyLinkedList<u8>) -> Vec<u8> {
+    list.iter().copied().collect()
+}
+
+#[derive(Debug, Clone)]
+enum MapOp {
+    Insert(u16, u32),
+    Remove(u16),
+    Get(u16),
+    Clear,
+}
+
+fn map_op() -> impl Strategy<Value = MapOp> {
+    // Keys from a range larger than the bucket count, so buckets collide
+    let key = 0..600u16;
+    prop_oneof![
+        4 => (key.clone(), any::<u32>()).prop_map(|(key, value)| MapOp::Insert(key, value)),
+        3 => key.clone().prop_map(MapOp::Remove),
+        3 => key.prop_map(MapOp::Get),
+        1 => Just(MapOp::Clear),
+    ]
+}
+
+/// Runs the ops through `Map` on `map` and on a `BTreeMap`, so any two
+/// implementations that pass agree with each other as well
+fn map_matches_btree_map<M: Map<u16, u32> + Default>(ops: Vec<MapOp>) -> Result<(), TestCaseError> {
+    let mut map = M::default();
+    let mut model = BTreeMap::new();
+
+    for op in ops {
+        match op {
+            MapOp::Insert(key, value) => {
+                prop_assert_eq!(map.insert(key, value), model.insert(key, value));
+            }
+            MapOp::Remove(key) => prop_assert_eq!(map.remove(&key), model.remove(&key)),
+            MapOp::Get(key) => {
+                prop_assert_eq!(map.get(&key), model.get(&key).copied());
+                prop_assert_eq!(map.contains_key(&key), model.contains_key(&key));
+            }
+            MapOp::Clear => {
+                map = M::default();
+                model.clear();
+            }
+        }
+        prop_assert_eq!(Map::len(&map), model.len());
+    }
+
+    let mut pairs: Vec<(u16, u32)> = Map::iter(&map).collect();
+    pairs.sort();
+    prop_assert_eq!(pairs, model.into_iter().collect::<Vec<_>>());
+    Ok(())
+}
+
+type Replayed = (Vec<Option<u64>>, Vec<(u64, u64)>);
+
+/// The answers a map gives to a generated workload, and its pairs after it
+fn replayed<M: Map<u64, u64> + Default>(workload: &Workload) -> Replayed {
+    let ops = workload.ops();
+    let mut map = M::default();
+    let answers = workload::replay(&mut map, &ops).collect();
+    let mut pairs: Vec<(u64, u64)> = Map::iter(&map).collect();
+    pairs.sort();
+    (answers, pairs)
+}
+
+fn key_distribution() -> impl Strategy<Value = KeyDistribution> {
+    prop_oneof![
+        (1..2_000u64).prop_map(|keys| KeyDistribution::Uniform { keys }),
+        (1..2_000u64, 0.5..1.5f64)
+            .prop_map(|(keys, exponent)| KeyDistribution::Zipfian { keys, exponent }),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum StackOp {
+    Push(i32),
+    Pop,
+    Peek,
+}
+
+fn stack_op() -> impl Strategy<Value = StackOp> {
+    prop_oneof![
+        any::<i32>().prop_map(StackOp::Push),
+        Just(StackOp::Pop),
+        Just(StackOp::Peek),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum QueueOp {
+    Add(i32),
+    Remove,
+    Peek,
+}
+
+fn queue_op() -> impl Strategy<Value = QueueOp> {
+    prop_oneof![
+        any::<i32>().prop_map(QueueOp::Add),
+        Just(QueueOp::Remove),
+        Just(QueueOp::Peek),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum ListOp {
+    Append(u8),
+    Prepend(u8),
+    InsertAfter(u8, u8),
+    InsertBefore(u8, u8),
+    DeleteFirst,
+    DeleteLast,
+    DeleteWithData(u8),
+    Contains(u8),
+}
+
+fn list_op() -> impl Strategy<Value = ListOp> {
+    // Few distinct values, so lists hold duplicates and lookups hit
+    let item = 0..5u8;
+    prop_oneof![
+        item.clone().prop_map(ListOp::Append),
+        item.clone().prop_map(ListOp::Prepend),
+        (item.clone(), item.clone()).prop_map(|(data, given)| ListOp::InsertAfter(data, given)),
+        (item.clone(), item.clone()).prop_map(|(data, given)| ListOp::InsertBefore(data, given)),
+        Just(ListOp::DeleteFirst),
+        Just(ListOp::DeleteLast),
+        item.clone().prop_map(ListOp::DeleteWithData),
+        item.prop_map(ListOp::Contains),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn hash_map_matches_std(ops in vec(map_op(), 0..200)) {
+        let mut map = HashMap::new();
+        let mut model = StdHashMap::new();
+
+        for op in ops {
+            match op {
+                MapOp::Insert(key, value) => {
+                    prop_assert_eq!(map.insert(key, value), model.insert(key, value));
+                }
+                MapOp::Remove(key) => prop_assert_eq!(map.remove(key), model.remove(&key)),
+                MapOp::Get(key) => prop_assert_eq!(map.get(key), model.get(&key).copied()),
+                MapOp::Clear => {
+                    map.clear();
+                    model.clear();
+                }
+            }
+            prop_assert_eq!(map.len(), model.len());
+            prop_assert_eq!(map.is_empty(), model.is_empty());
+        }
+
+        let mut pairs: Vec<(u16, u32)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        pairs.sort();
+        let mut expected: Vec<(u16, u32)> = model.into_iter().collect();
+        expected.sort();
+        prop_assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn every_map_matches_btree_map(ops in vec(map_op(), 0..200)) {
+        map_matches_btree_map::<HashMap<u16, u32>>(ops.clone())?;
+        map_matches_btree_map::<AvlTreeMap<u16, u32>>(ops.clone())?;
+        map_matches_btree_map::<ConcurrentSkipListMap<u16, u32>>(ops.clone())?;
+        map_matches_btree_map::<StdHashMap<u16, u32>>(ops)?;
+    }
+
+    #[test]
+    fn every_map_agrees_on_generated_workloads(
+        seed in any::<u64>(),
+        ratios in (0..10u32, 0..10u32, 1..10u32),
+        keys in key_distribution()
+    ) {
+        let workload = Workload::new(seed, 1_000)
+            .with_ratios(ratios.0, ratios.1, ratios.2)
+            .with_keys(keys);
+
+        let expected = replayed::<BTreeMap<u64, u64>>(&workload);
+
+        prop_assert_eq!(replayed::<HashMap<u64, u64>>(&workload), expected.clone());
+        prop_assert_eq!(replayed::<AvlTreeMap<u64, u64>>(&workload), expected.clone());
+        prop_assert_eq!(replayed::<ConcurrentSkipListMap<u64, u64>>(&workload), expected);
+    }
+
+    #[test]
+    fn stack_matches_vec(ops in vec(stack_op(), 0..200)) {
+        let mut stack = Stack::new();
+        let mut model = Vec::new();
+
+        for op in ops {
+            match op {
+                StackOp::Push(item) => {
+                    stack.push(item);
+                    model.push(item);
+                }
+                StackOp::Pop => prop_assert_eq!(stack.pop(), model.pop()),
+                StackOp::Peek => prop_assert_eq!(stack.peek(), model.last()),
+            }
+            prop_assert_eq!(Collection::len(&stack), model.len());
+            prop_assert_eq!(stack.is_empty(), model.is_empty());
+        }
+
+        prop_assert!(stack.iter().eq(model.iter()));
+    }
+
+    #[test]
+    fn queue_matches_vec_deque(ops in vec(queue_op(), 0..300)) {
+        let mut queue = Queue::new();
+        let mut model = VecDeque::new();
+
+        for op in ops {
+            match op {
+                QueueOp::Add(item) => {
+                    queue.add(item);
+                    model.push_back(item);
+                }
+                QueueOp::Remove => prop_assert_eq!(queue.remove(), model.pop_front()),
+                QueueOp::Peek => prop_assert_eq!(queue.peek(), model.front().copied()),
+            }
+            prop_assert_eq!(Collection::len(&queue), model.len());
+            prop_assert_eq!(queue.is_empty(), model.is_empty());
+        }
+
+        prop_assert!(queue.iter().eq(model.iter()));
+    }
+
+    #[test]
+    fn singly_linked_list_matches_vec(ops in vec(list_op(), 0..100)) {
+        let mut list = SinglyLinkedList::new();
+        let mut model: Vec<u8> = Vec::new();
+
+        for op in ops {
+            match op {
+                ListOp::Append(data) => {
+                    list.append(data);
+                    model.push(data);
+                }
+                ListOp::Prepend(data) => {
+                    list.prepend(data);
+                    model.insert(0, data);
+                }
+                ListOp::InsertAfter(data, given) => {
+                    let expected = if model.is_empty() {
+                        Err(Error::EmptyCollection)
+                    } else {
+                        match model.iter().position(|&item| item == given) {
+                            Some(index) => {
+                                model.insert(index + 1, data);
+                                Ok(())
+                            }
+                            None => Err(Error::NotFound),
+                        }
+                    };
+                    prop_assert_eq!(list.try_insert_after_given(data, given), expected);
+                }
+                ListOp::InsertBefore(data, given) => {
+                    // The first node is never matched, there is nothing before it to link from
+                    let expected = if model.is_empty() {
+                        Err(Error::EmptyCollection)
+                    } else {
+                        match model.iter().skip(1).position(|&item| item == given) {
+                            Some(index) => {
+                                model.insert(index + 1, data);
+                                Ok(())
+                            }
+                            None => Err(Error::NotFound),
+                        }
+                    };
+                    prop_assert_eq!(list.try_insert_before_given(data, given), expected);
+                }
+                ListOp::DeleteFirst => {
+                    let expected = if model.is_empty() {
+                        Err(Error::EmptyCollection)
+                    } else {
+                        Ok(model.remove(0))
+                    };
+                    prop_assert_eq!(list.try_delete_first(), expected);
+                }
+                ListOp::DeleteLast => {
+                    let expected = model.pop().ok_or(Error::EmptyCollection);
+                    prop_assert_eq!(list.try_delete_last(), expected);
+                }
+                ListOp::DeleteWithData(data) => {
+                    let expected = match model.iter().position(|&item| item == data) {
+                        Some(index) => {
+                            model.remove(index);
+                            Ok(())
+                        }
+                        None => Err(Error::NotFound),
+                    };
+                    prop_assert_eq!(list.try_delete_node_with_data(data), expected);
+                }
+                ListOp::Contains(data) => {
+                    prop_assert_eq!(Collection::contains(&list, &data), model.contains(&data));
+                }
+            }
+            prop_assert_eq!(list_items(&list), model.clone());
+            prop_assert_eq!(list.len(), model.len());
+            prop_assert_eq!(list.is_empty(), model.is_empty());
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Panic safety tests
+//! `Item` is an element type whose clone, drop or comparison panics on
+//! demand. Every test arms one of those faults, runs an operation that panics
+//! halfway through, catches the panic and then checks the structure: its
+//! length agrees with what iterating it finds, the items that weren't being
+//! touched are all still there, and once it is dropped every `Item` that was
+//! ever created has been dropped exactly once.
+
+use std::cell::Cell;
+use std::fmt;
+use std::hash::{ Hash, Hasher };
+use std::panic::{ catch_unwind, AssertUnwindSafe };
+
+use data_structures_in_rust::prelude::*;
+use data_structures_in_rust::sorting::{ merge_sort, quick_sort };
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Fault {
+    Clone,
+    Drop,
+    Compare,
+}
+
+thread_local! {
+    /// The armed fault and how many calls still succeed before it fires
+    static ARMED: Cell<Option<(Fault, usize)>> = const { Cell::new(None) };
+    /// Items created minus items dropped
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Makes the fault fire on the call after the next `after` ones
+fn arm(fault: Fault, after: usize) {
+    ARMED.set(Some((fault, after)));
+}
+
+fn trip(fault: Fault) {
+    let Some((armed, after)) = ARMED.get() else {
+        return;
+    };
+    if armed != fault {
+        return;
+    }
+    if after == 0 {
+        ARMED.set(None);
+        panic!("injected {:?} fault", fault);
+    }
+    ARMED.set(Some((armed, after - 1)));
+}
+
+fn live() -> isize {
+    LIVE.get()
+}
+
+/// Runs `f` and tells whether it panicked, the fault is disarmed either way
+fn panics(f: impl FnOnce()) -> bool {
+    let panicked = catch_unwind(AssertUnwindSafe(f)).is_err();
+    ARMED.set(None);
+    panicked
+}
+
+#[derive(Debug)]
+struct Item(u32);
+
+fn item(value: u32) -> Item {
+    LIVE.set(LIVE.get() + 1);
+    Item(value)
+}
+
+impl Clone for Item {
+    fn clone(&self) -> Self {
+        trip(Fault::Clone);
+        item(self.0)
+    }
+}
+
+impl Drop for Item {
+    fn drop(&mut self) {
+        LIVE.set(LIVE.get() - 1);
+        trip(Fault::Drop);
+    }
+}
+
+impl PartialEq for Item {
+    fn eq(&self, other: &Self) -> bool {
+        trip(Fault::Compare);
+        self.0 == other.0
+    }
+}
+
+impl Eq for Item {}
+
+impl Hash for Item {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Item {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        trip(Fault::Compare);
+        self.0.cmp(&other.0)
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Values of the items in ascending order
+fn sorted_values<'a>(items: impl Iterator<Item = &'a Item>) -> Vec<u32> {
+    let mut values: Vec<u32> = items.map(|item| item.0).collect();
+    values.sort();
+    values
+}
+
+fn filled_map(keys: std::ops::Range<u32>) -> HashMap<Item, u32> {
+    let mut map = HashMap::new();
+    for key in keys {
+        map.insert(item(key), key);
+    }
+    map
+}
+
+fn check_map(map: &HashMap<Item, u32>, keys: &[u32]) {
+    assert_eq!(map.len(), map.iter().count());
+    assert_eq!(sorted_values(map.iter().map(|(key, _)| key)), keys);
+    assert!(map.iter().all(|(key, value)| key.0 == *value));
+}
+
+#[test]
+fn test_hash_map_survives_a_panicking_compare() {
+    let mut map = filled_map(0..100);
+
+    arm(Fault::Compare, 0);
+    assert!(panics(|| {
+        map.insert(item(5), 50);
+    }));
+    arm(Fault::Compare, 0);
+    assert!(panics(|| {
+        map.remove(item(7));
+    }));
+
+    check_map(&map, &(0..100).collect::<Vec<_>>());
+    drop(map);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn test_hash_map_survives_a_panicking_drop() {
+    let mut map = filled_map(0..100);
+
+    // The first drop is the copy of the key made for hashing, the second
+    // one is the key taken out of the map
+    arm(Fault::Drop, 1);
+    assert!(panics(|| {
+        map.remove(item(7));
+    }));
+    check_map(&map, &(0..100).filter(|&key| key != 7).collect::<Vec<_>>());
+    arm(Fault::Drop, 40);
+    assert!(panics(|| map.clear()));
+
+    check_map(&map, &[]);
+    assert!(map.is_empty());
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn when_clone_panics_the_original_map_is_untouched() {
+    let map = filled_map(0..100);
+
+    arm(Fault::Clone, 60);
+    assert!(panics(|| {
+        let _ = map.clone();
+    }));
+
+    check_map(&map, &(0..100).collect::<Vec<_>>());
+    assert_eq!(live(), 100);
+    drop(map);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn test_list_survives_panics() {
+    let mut list = SinglyLinkedList::new();
+    for value in 0..20 {
+        list.append(item(value));
+    }
+
+    arm(Fault::Compare, 10);
+    assert!(panics(|| {
+        let _ = list.try_delete_node_with_data(item(15));
+    }));
+    arm(Fault::Drop, 0);
+    assert!(panics(|| {
+        let _ = list.try_delete_first();
+    }));
+    arm(Fault::Clone, 5);
+    assert!(panics(|| {
+        let _ = list.clone();
+    }));
+
+    assert_eq!(sorted_values(list.iter()), (1..20).collect::<Vec<_>>());
+    assert_eq!(list.len(), 19);
+    drop(list);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn test_binary_heap_keeps_its_items_when_sifting_panics() {
+    let mut heap = BinaryHeap::new_min();
+    for value in (0..50).rev() {
+        heap.push(item(value));
+    }
+
+    // Sifts up to the root, the third comparison panics on the way
+    arm(Fault::Compare, 2);
+    assert!(panics(|| heap.push(item(0))));
+    let len = heap.len();
+    arm(Fault::Compare, 3);
+    assert!(panics(|| {
+        heap.pop();
+    }));
+
+    // The popped item is gone, the rest are all there even if out of order
+    assert_eq!(heap.len(), len - 1);
+    assert_eq!(heap.iter().count(), heap.len());
+    assert_eq!(live(), heap.len() as isize);
+    drop(heap);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn test_avl_tree_stays_consistent() {
+    let mut tree = AvlTreeMap::new();
+    for value in 0..64 {
+        tree.insert(item(value), value);
+    }
+
+    arm(Fault::Compare, 3);
+    assert!(panics(|| {
+        tree.insert(item(100), 100);
+    }));
+    // The root has two children, so its key is replaced by the successor's
+    let root = tree.pre_order().next().unwrap().0 .0;
+    arm(Fault::Drop, 0);
+    assert!(panics(|| {
+        tree.remove(&item(root));
+    }));
+
+    assert_eq!(tree.len(), tree.iter().count());
+    let expected: Vec<u32> = (0..64).filter(|&key| key != root).collect();
+    assert_eq!(tree.iter().map(|(key, _)| key.0).collect::<Vec<_>>(), expected);
+    drop(tree);
+    assert_eq!(live(), 0);
+}
+
+#[test]
+fn test_sorts_never_leak_or_drop_twice() {
+    let mut items: Vec<Item> = (0..100).map(|value| item((value * 37) % 100)).collect();
+
+    arm(Fault::Compare, 300);
+    assert!(panics(|| quick_sort(&mut items)));
+    assert_eq!(sorted_values(items.iter()), (0..100).collect::<Vec<_>>());
+    arm(Fault::Clone, 150);
+    assert!(panics(|| merge_sort(&mut items)));
+
+    // Merge sort writes clones over the slice, so items can repeat, but
+    // nothing leaks and nothing is dropped twice
+    assert_eq!(items.len(), 100);
+    assert_eq!(live(), 100);
+    drop(items);
+    assert_eq!(live(), 0);
+}